@@ -0,0 +1,3 @@
+ALTER TABLE users ADD COLUMN suspended_until TEXT;
+
+ALTER TABLE users ADD COLUMN suspension_reason TEXT;
@@ -0,0 +1,9 @@
+DROP TRIGGER IF EXISTS users_capture_delete;
+
+DROP TRIGGER IF EXISTS users_capture_update;
+
+DROP TRIGGER IF EXISTS users_capture_insert;
+
+DROP TABLE IF EXISTS cdc_offsets;
+
+DROP TABLE IF EXISTS user_changes;
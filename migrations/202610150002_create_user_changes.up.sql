@@ -0,0 +1,39 @@
+CREATE TABLE
+    IF NOT EXISTS user_changes (
+        seq INTEGER PRIMARY KEY AUTOINCREMENT,
+        user_id BLOB NOT NULL,
+        operation TEXT NOT NULL,
+        changed_at TEXT NOT NULL DEFAULT (strftime ('%Y-%m-%dT%H:%M:%fZ', 'now'))
+    );
+
+CREATE TABLE
+    IF NOT EXISTS cdc_offsets (
+        consumer TEXT PRIMARY KEY,
+        last_seq INTEGER NOT NULL,
+        updated_at TEXT NOT NULL
+    );
+
+CREATE TRIGGER IF NOT EXISTS users_capture_insert AFTER INSERT ON users BEGIN
+INSERT INTO
+    user_changes (user_id, operation)
+VALUES
+    (NEW.id, 'insert');
+
+END;
+
+CREATE TRIGGER IF NOT EXISTS users_capture_update AFTER
+UPDATE ON users BEGIN
+INSERT INTO
+    user_changes (user_id, operation)
+VALUES
+    (NEW.id, 'update');
+
+END;
+
+CREATE TRIGGER IF NOT EXISTS users_capture_delete AFTER DELETE ON users BEGIN
+INSERT INTO
+    user_changes (user_id, operation)
+VALUES
+    (OLD.id, 'delete');
+
+END;
@@ -0,0 +1,24 @@
+CREATE TABLE
+    IF NOT EXISTS webhooks (
+        id BLOB PRIMARY KEY,
+        url TEXT NOT NULL,
+        secret TEXT NOT NULL,
+        events TEXT NOT NULL DEFAULT '[]',
+        active INTEGER NOT NULL DEFAULT 1,
+        created_at TEXT NOT NULL
+    );
+
+CREATE TABLE
+    IF NOT EXISTS webhook_deliveries (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        webhook_id BLOB NOT NULL REFERENCES webhooks (id) ON DELETE CASCADE,
+        event_id BLOB NOT NULL,
+        event_type TEXT NOT NULL,
+        attempt INTEGER NOT NULL,
+        status_code INTEGER,
+        error TEXT,
+        success INTEGER NOT NULL,
+        attempted_at TEXT NOT NULL
+    );
+
+CREATE INDEX IF NOT EXISTS idx_webhook_deliveries_webhook ON webhook_deliveries (webhook_id, id);
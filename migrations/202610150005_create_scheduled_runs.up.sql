@@ -0,0 +1,5 @@
+CREATE TABLE
+    IF NOT EXISTS scheduled_runs (
+        task TEXT PRIMARY KEY,
+        last_run_at TEXT NOT NULL
+    );
@@ -0,0 +1,11 @@
+CREATE TABLE
+    IF NOT EXISTS outbox (
+        seq INTEGER PRIMARY KEY AUTOINCREMENT,
+        event_id BLOB NOT NULL UNIQUE,
+        event_type TEXT NOT NULL,
+        payload TEXT NOT NULL,
+        occurred_at TEXT NOT NULL,
+        sent_at TEXT
+    );
+
+CREATE INDEX IF NOT EXISTS idx_outbox_pending ON outbox (sent_at, seq);
@@ -0,0 +1,4 @@
+-- Los archivos de los avatares siguen en el almacén; solo se pierde la referencia.
+ALTER TABLE users DROP COLUMN avatar_url;
+
+ALTER TABLE users DROP COLUMN avatar_key;
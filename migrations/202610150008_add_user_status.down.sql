@@ -0,0 +1,4 @@
+-- Las cuentas suspendidas se siguen reconociendo por `suspended_until`; las bajas se pierden.
+DROP INDEX IF EXISTS users_status_idx;
+
+ALTER TABLE users DROP COLUMN status;
@@ -0,0 +1,5 @@
+ALTER TABLE users ADD COLUMN status TEXT NOT NULL DEFAULT 'active';
+
+UPDATE users SET status = 'suspended' WHERE suspended_until IS NOT NULL;
+
+CREATE INDEX IF NOT EXISTS users_status_idx ON users (status);
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS idx_posts_author;
+
+DROP TABLE IF EXISTS posts;
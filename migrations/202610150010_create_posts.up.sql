@@ -0,0 +1,11 @@
+CREATE TABLE
+    IF NOT EXISTS posts (
+        id BLOB PRIMARY KEY,
+        author_id BLOB NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+        title TEXT NOT NULL,
+        body TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        updated_at TEXT NOT NULL
+    );
+
+CREATE INDEX IF NOT EXISTS idx_posts_author ON posts (author_id, created_at);
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS idx_comments_post;
+
+DROP TABLE IF EXISTS comments;
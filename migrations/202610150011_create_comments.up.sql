@@ -0,0 +1,14 @@
+-- Los comentarios desaparecen con su publicación, pero sobreviven a su autor (quedan
+-- anónimos). El borrado desde la API es lógico: solo se rellena `deleted_at`.
+CREATE TABLE
+    IF NOT EXISTS comments (
+        id BLOB PRIMARY KEY,
+        post_id BLOB NOT NULL REFERENCES posts (id) ON DELETE CASCADE,
+        author_id BLOB REFERENCES users (id) ON DELETE SET NULL,
+        body TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        updated_at TEXT NOT NULL,
+        deleted_at TEXT
+    );
+
+CREATE INDEX IF NOT EXISTS idx_comments_post ON comments (post_id, created_at);
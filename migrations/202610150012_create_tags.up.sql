@@ -0,0 +1,16 @@
+CREATE TABLE
+    IF NOT EXISTS tags (
+        id BLOB PRIMARY KEY,
+        name TEXT NOT NULL UNIQUE,
+        created_at TEXT NOT NULL
+    );
+
+CREATE TABLE
+    IF NOT EXISTS user_tags (
+        user_id BLOB NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+        tag_id BLOB NOT NULL REFERENCES tags (id) ON DELETE CASCADE,
+        created_at TEXT NOT NULL,
+        PRIMARY KEY (user_id, tag_id)
+    );
+
+CREATE INDEX IF NOT EXISTS idx_user_tags_tag ON user_tags (tag_id, user_id);
@@ -0,0 +1,20 @@
+-- Equipos de usuarios. Cada miembro tiene un rol dentro del equipo: los `owner` gestionan
+-- el equipo y sus miembros; los `member` solo pueden consultarlo o abandonarlo.
+CREATE TABLE
+    IF NOT EXISTS teams (
+        id BLOB PRIMARY KEY,
+        name TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        updated_at TEXT NOT NULL
+    );
+
+CREATE TABLE
+    IF NOT EXISTS team_members (
+        team_id BLOB NOT NULL REFERENCES teams (id) ON DELETE CASCADE,
+        user_id BLOB NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+        role TEXT NOT NULL CHECK (role IN ('owner', 'member')),
+        created_at TEXT NOT NULL,
+        PRIMARY KEY (team_id, user_id)
+    );
+
+CREATE INDEX IF NOT EXISTS idx_team_members_user ON team_members (user_id);
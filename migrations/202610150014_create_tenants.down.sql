@@ -0,0 +1,6 @@
+-- Los usuarios de todos los inquilinos vuelven a compartir un único espacio.
+DROP INDEX IF EXISTS idx_users_tenant;
+
+ALTER TABLE users DROP COLUMN tenant_id;
+
+DROP TABLE IF EXISTS tenants;
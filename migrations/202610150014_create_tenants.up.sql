@@ -0,0 +1,17 @@
+-- Inquilinos de la aplicación, identificados por un slug legible (`acme`) que coincide con
+-- el valor de la cabecera `X-Tenant-Id` o con el subdominio. Los usuarios existentes pasan
+-- al inquilino `default`, que es también el que se usa cuando la petición no indica ninguno.
+CREATE TABLE
+    IF NOT EXISTS tenants (
+        id TEXT PRIMARY KEY,
+        name TEXT NOT NULL,
+        created_at TEXT NOT NULL
+    );
+
+INSERT INTO tenants (id, name, created_at) VALUES ('default', 'Default', strftime('%Y-%m-%dT%H:%M:%SZ', 'now'));
+
+-- SQLite no admite añadir una columna con REFERENCES y un valor por defecto no nulo, así
+-- que la pertenencia a un inquilino existente la garantiza el middleware de resolución.
+ALTER TABLE users ADD COLUMN tenant_id TEXT NOT NULL DEFAULT 'default';
+
+CREATE INDEX IF NOT EXISTS idx_users_tenant ON users (tenant_id, status);
@@ -0,0 +1,5 @@
+DROP TABLE IF EXISTS tenant_usage;
+
+ALTER TABLE tenants DROP COLUMN max_requests_per_day;
+
+ALTER TABLE tenants DROP COLUMN max_users;
@@ -0,0 +1,13 @@
+-- Cuotas por inquilino. Un valor nulo significa que el inquilino no tiene límite.
+ALTER TABLE tenants ADD COLUMN max_users INTEGER;
+
+ALTER TABLE tenants ADD COLUMN max_requests_per_day INTEGER;
+
+-- Peticiones atendidas por inquilino y día (UTC), para aplicar y consultar la cuota diaria.
+CREATE TABLE
+    IF NOT EXISTS tenant_usage (
+        tenant_id TEXT NOT NULL REFERENCES tenants (id) ON DELETE CASCADE,
+        day TEXT NOT NULL,
+        requests INTEGER NOT NULL DEFAULT 0,
+        PRIMARY KEY (tenant_id, day)
+    );
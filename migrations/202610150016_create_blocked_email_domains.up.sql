@@ -0,0 +1,9 @@
+-- Dominios de correo con los que no se admiten altas (por ejemplo, proveedores de correos
+-- desechables). Bloquear un dominio bloquea también sus subdominios. El dominio se guarda en
+-- minúsculas y, si es internacionalizado, en Punycode, igual que los correos de `users`.
+CREATE TABLE
+    IF NOT EXISTS blocked_email_domains (
+        domain TEXT PRIMARY KEY,
+        reason TEXT,
+        created_at TEXT NOT NULL
+    );
@@ -0,0 +1,4 @@
+-- Los usuarios ya anonimizados no recuperan sus datos; solo se pierde la marca.
+DROP TABLE IF EXISTS user_erasure_requests;
+
+ALTER TABLE users DROP COLUMN erased_at;
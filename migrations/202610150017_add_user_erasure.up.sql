@@ -0,0 +1,11 @@
+-- Borrado de datos personales (RGPD). El usuario borrado conserva su fila, anonimizada, para
+-- que publicaciones, comentarios e historial sigan apuntando a ella; `erased_at` lo marca.
+ALTER TABLE users ADD COLUMN erased_at TEXT;
+
+-- Solicitudes de borrado pendientes de confirmar: solo se guarda el hash del token.
+CREATE TABLE
+    IF NOT EXISTS user_erasure_requests (
+        user_id BLOB PRIMARY KEY REFERENCES users (id) ON DELETE CASCADE,
+        token_hash TEXT NOT NULL,
+        expires_at TEXT NOT NULL
+    );
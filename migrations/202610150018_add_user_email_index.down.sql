@@ -0,0 +1,4 @@
+-- Revertir no descifra los correos que ya estén cifrados.
+DROP INDEX IF EXISTS idx_users_email_index;
+
+ALTER TABLE users DROP COLUMN email_index;
@@ -0,0 +1,6 @@
+-- Índice ciego del correo: con el cifrado activo, `email` guarda un texto distinto en cada
+-- escritura, así que las búsquedas y la unicidad se resuelven con este HMAC del correo. Sin
+-- cifrado queda a `NULL`, y SQLite no trata los `NULL` como duplicados.
+ALTER TABLE users ADD COLUMN email_index TEXT;
+
+CREATE UNIQUE INDEX IF NOT EXISTS idx_users_email_index ON users (email_index);
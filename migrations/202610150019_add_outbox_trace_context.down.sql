@@ -0,0 +1,3 @@
+ALTER TABLE outbox DROP COLUMN tracestate;
+
+ALTER TABLE outbox DROP COLUMN traceparent;
@@ -0,0 +1,5 @@
+-- Traza W3C de la petición que originó cada evento, para que las entregas posteriores
+-- sigan en la misma traza. Queda a `NULL` en los eventos registrados fuera de una petición.
+ALTER TABLE outbox ADD COLUMN traceparent TEXT;
+
+ALTER TABLE outbox ADD COLUMN tracestate TEXT;
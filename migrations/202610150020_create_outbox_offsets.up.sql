@@ -0,0 +1,8 @@
+-- Última posición del outbox publicada por cada consumidor externo (el broker de eventos),
+-- independiente de `sent_at`, que solo refleja la publicación en el bus del proceso.
+CREATE TABLE
+    IF NOT EXISTS outbox_offsets (
+        consumer TEXT PRIMARY KEY,
+        last_seq INTEGER NOT NULL,
+        updated_at TEXT NOT NULL
+    );
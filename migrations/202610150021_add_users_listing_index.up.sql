@@ -0,0 +1,3 @@
+-- Índice del listado de usuarios, que se filtra por inquilino y se ordena por fecha de alta
+-- y, a igualdad, por identificador.
+CREATE INDEX IF NOT EXISTS idx_users_tenant_created ON users (tenant_id, created_at, id);
@@ -0,0 +1,3 @@
+DROP INDEX IF EXISTS idx_users_tenant_email_nocase;
+
+DROP INDEX IF EXISTS idx_users_tenant_name_nocase;
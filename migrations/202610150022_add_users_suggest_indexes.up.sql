@@ -0,0 +1,5 @@
+-- Índices de `GET /users/suggest`: con `COLLATE NOCASE`, SQLite resuelve
+-- `name LIKE 'prefijo%'` (que no distingue mayúsculas) como un rango del índice.
+CREATE INDEX IF NOT EXISTS idx_users_tenant_name_nocase ON users (tenant_id, name COLLATE NOCASE);
+
+CREATE INDEX IF NOT EXISTS idx_users_tenant_email_nocase ON users (tenant_id, email COLLATE NOCASE);
@@ -0,0 +1,12 @@
+-- Índice de trigramas de los nombres de usuario para la búsqueda aproximada
+-- (`GET /users/search`). Lo mantienen los handlers que escriben el nombre y sus filas se
+-- borran con el usuario; los usuarios que ya existían se indexan al arrancar.
+CREATE TABLE
+    IF NOT EXISTS user_trigrams (
+        tenant_id TEXT NOT NULL,
+        trigram TEXT NOT NULL,
+        user_id BLOB NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+        PRIMARY KEY (tenant_id, trigram, user_id)
+    ) WITHOUT ROWID;
+
+CREATE INDEX IF NOT EXISTS idx_user_trigrams_user ON user_trigrams (user_id);
@@ -0,0 +1,15 @@
+-- Vistas guardadas: combinaciones de filtros y orden del listado de usuarios con un nombre,
+-- que pertenecen al usuario que las crea y se borran con él.
+CREATE TABLE
+    IF NOT EXISTS saved_views (
+        id BLOB PRIMARY KEY,
+        tenant_id TEXT NOT NULL,
+        owner_id BLOB NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+        name TEXT NOT NULL,
+        status TEXT NOT NULL,
+        tag TEXT,
+        filter TEXT,
+        sort TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        UNIQUE (owner_id, name)
+    );
@@ -0,0 +1,17 @@
+-- Exportaciones asíncronas del listado de usuarios: cada fila sigue el trabajo que genera el
+-- archivo en el almacenamiento y guarda su clave una vez completado.
+CREATE TABLE
+    IF NOT EXISTS exports (
+        id BLOB PRIMARY KEY,
+        tenant_id TEXT NOT NULL,
+        format TEXT NOT NULL,
+        status TEXT NOT NULL CHECK (status IN ('pending', 'running', 'completed', 'failed')),
+        storage_key TEXT,
+        row_count INTEGER,
+        error TEXT,
+        attempts INTEGER NOT NULL DEFAULT 0,
+        created_at TEXT NOT NULL,
+        completed_at TEXT
+    );
+
+CREATE INDEX IF NOT EXISTS idx_exports_tenant_id ON exports (tenant_id, created_at);
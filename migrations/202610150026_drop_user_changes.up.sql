@@ -0,0 +1,12 @@
+-- El CDC lee ahora el outbox, como el broker, y guarda su posición en `outbox_offsets`: el
+-- registro de cambios por triggers solo lo purgaba su propio consumidor y, sin CDC activo,
+-- crecía sin límite.
+DROP TRIGGER IF EXISTS users_capture_delete;
+
+DROP TRIGGER IF EXISTS users_capture_update;
+
+DROP TRIGGER IF EXISTS users_capture_insert;
+
+DROP TABLE IF EXISTS cdc_offsets;
+
+DROP TABLE IF EXISTS user_changes;
@@ -0,0 +1,4 @@
+-- Los usuarios fusionados vuelven a quedar dados de baja, como antes de existir el estado.
+UPDATE users SET status = 'deactivated' WHERE status = 'merged';
+
+ALTER TABLE users DROP COLUMN merged_into;
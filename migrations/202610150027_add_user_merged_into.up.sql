@@ -0,0 +1,4 @@
+-- Fusión de usuarios. El origen de una fusión conserva su fila en el estado terminal `merged`
+-- y `merged_into` apunta al usuario que lo absorbió. Sin clave foránea: si el destino se
+-- borra después, el origen sigue marcado como fusionado.
+ALTER TABLE users ADD COLUMN merged_into BLOB;
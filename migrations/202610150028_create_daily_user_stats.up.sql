@@ -0,0 +1,9 @@
+-- Resumen diario (UTC) por inquilino que rellena la tarea `rollup_daily_stats`: peticiones
+-- atendidas, para que `GET /stats/users` no recorra `tenant_usage`.
+CREATE TABLE
+    IF NOT EXISTS daily_user_stats (
+        tenant_id TEXT NOT NULL,
+        day TEXT NOT NULL,
+        requests INTEGER NOT NULL DEFAULT 0,
+        PRIMARY KEY (tenant_id, day)
+    );
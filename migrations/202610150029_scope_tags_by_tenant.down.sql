@@ -0,0 +1,62 @@
+-- Las etiquetas vuelven a ser globales: las homónimas de distintos inquilinos se funden en
+-- la más antigua.
+CREATE TABLE
+    IF NOT EXISTS global_tags (
+        id BLOB PRIMARY KEY,
+        name TEXT NOT NULL UNIQUE,
+        created_at TEXT NOT NULL
+    );
+
+INSERT OR IGNORE INTO
+    global_tags (id, name, created_at)
+SELECT
+    id,
+    name,
+    created_at
+FROM
+    tags
+ORDER BY
+    created_at,
+    id;
+
+CREATE TEMP TABLE global_user_tags AS
+SELECT
+    user_tags.user_id,
+    global_tags.id AS tag_id,
+    MIN(user_tags.created_at) AS created_at
+FROM
+    user_tags
+    JOIN tags ON tags.id = user_tags.tag_id
+    JOIN global_tags ON global_tags.name = tags.name
+GROUP BY
+    user_tags.user_id,
+    global_tags.id;
+
+DROP INDEX IF EXISTS idx_user_tags_tag;
+
+DROP TABLE user_tags;
+
+DROP TABLE tags;
+
+ALTER TABLE global_tags RENAME TO tags;
+
+CREATE TABLE
+    IF NOT EXISTS user_tags (
+        user_id BLOB NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+        tag_id BLOB NOT NULL REFERENCES tags (id) ON DELETE CASCADE,
+        created_at TEXT NOT NULL,
+        PRIMARY KEY (user_id, tag_id)
+    );
+
+INSERT INTO
+    user_tags (user_id, tag_id, created_at)
+SELECT
+    user_id,
+    tag_id,
+    created_at
+FROM
+    global_user_tags;
+
+DROP TABLE global_user_tags;
+
+CREATE INDEX IF NOT EXISTS idx_user_tags_tag ON user_tags (tag_id, user_id);
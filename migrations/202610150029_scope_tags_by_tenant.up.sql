@@ -0,0 +1,86 @@
+-- Las etiquetas pasan a pertenecer a un inquilino: el nombre solo es único dentro de él.
+-- SQLite no permite cambiar la restricción `UNIQUE (name)`, así que se reconstruyen `tags`
+-- y `user_tags`. Cada etiqueta existente se reparte entre los inquilinos de los usuarios
+-- que la tienen asignada; las que no tienen asignaciones quedan en `default`.
+CREATE TABLE
+    IF NOT EXISTS tenant_tags (
+        id BLOB PRIMARY KEY,
+        tenant_id TEXT NOT NULL,
+        name TEXT NOT NULL,
+        created_at TEXT NOT NULL,
+        UNIQUE (tenant_id, name)
+    );
+
+-- El primero, por orden alfabético, de los inquilinos que la usan conserva su identificador.
+INSERT INTO
+    tenant_tags (id, tenant_id, name, created_at)
+SELECT
+    tags.id,
+    COALESCE(
+        (
+            SELECT MIN(users.tenant_id) FROM user_tags
+            JOIN users ON users.id = user_tags.user_id
+            WHERE user_tags.tag_id = tags.id
+        ),
+        'default'
+    ),
+    tags.name,
+    tags.created_at
+FROM
+    tags;
+
+INSERT OR IGNORE INTO
+    tenant_tags (id, tenant_id, name, created_at)
+SELECT
+    randomblob (16),
+    users.tenant_id,
+    tags.name,
+    tags.created_at
+FROM
+    user_tags
+    JOIN users ON users.id = user_tags.user_id
+    JOIN tags ON tags.id = user_tags.tag_id
+GROUP BY
+    users.tenant_id,
+    tags.id;
+
+CREATE TEMP TABLE tenant_user_tags AS
+SELECT
+    user_tags.user_id,
+    tenant_tags.id AS tag_id,
+    user_tags.created_at
+FROM
+    user_tags
+    JOIN users ON users.id = user_tags.user_id
+    JOIN tags ON tags.id = user_tags.tag_id
+    JOIN tenant_tags ON tenant_tags.tenant_id = users.tenant_id
+    AND tenant_tags.name = tags.name;
+
+DROP INDEX IF EXISTS idx_user_tags_tag;
+
+DROP TABLE user_tags;
+
+DROP TABLE tags;
+
+ALTER TABLE tenant_tags RENAME TO tags;
+
+CREATE TABLE
+    IF NOT EXISTS user_tags (
+        user_id BLOB NOT NULL REFERENCES users (id) ON DELETE CASCADE,
+        tag_id BLOB NOT NULL REFERENCES tags (id) ON DELETE CASCADE,
+        created_at TEXT NOT NULL,
+        PRIMARY KEY (user_id, tag_id)
+    );
+
+INSERT INTO
+    user_tags (user_id, tag_id, created_at)
+SELECT
+    user_id,
+    tag_id,
+    created_at
+FROM
+    tenant_user_tags;
+
+DROP TABLE tenant_user_tags;
+
+CREATE INDEX IF NOT EXISTS idx_user_tags_tag ON user_tags (tag_id, user_id);
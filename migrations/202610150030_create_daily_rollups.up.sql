@@ -0,0 +1,7 @@
+-- Días ya resumidos en `daily_user_stats`, en la base principal: la tarea `rollup_daily_stats`
+-- continúa desde el último aunque ese día no hubiera altas ni peticiones.
+CREATE TABLE
+    IF NOT EXISTS daily_rollups (
+        day TEXT PRIMARY KEY NOT NULL,
+        rolled_up_at TEXT NOT NULL
+    );
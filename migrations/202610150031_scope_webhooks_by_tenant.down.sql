@@ -0,0 +1,4 @@
+-- Las suscripciones vuelven a recibir los eventos de todos los inquilinos.
+DROP INDEX IF EXISTS idx_webhooks_tenant;
+
+ALTER TABLE webhooks DROP COLUMN tenant_id;
@@ -0,0 +1,6 @@
+-- Cada suscripción recibe solo los eventos de los usuarios de su inquilino. Las existentes
+-- pasan al inquilino `default`; como en `users`, SQLite no admite REFERENCES con un valor
+-- por defecto no nulo, así que al borrar un inquilino se borran también sus suscripciones.
+ALTER TABLE webhooks ADD COLUMN tenant_id TEXT NOT NULL DEFAULT 'default';
+
+CREATE INDEX IF NOT EXISTS idx_webhooks_tenant ON webhooks (tenant_id, active);
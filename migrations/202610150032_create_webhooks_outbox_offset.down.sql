@@ -0,0 +1 @@
+DELETE FROM outbox_offsets WHERE consumer = 'webhooks';
@@ -0,0 +1,7 @@
+-- Los webhooks pasan a leer el outbox con su propia posición, como el broker y el CDC. Lo
+-- que el relay ya publicó en el bus se dio por entregado, así que se empieza tras ello.
+INSERT INTO outbox_offsets (consumer, last_seq, updated_at)
+SELECT 'webhooks', COALESCE(MAX(seq), 0), strftime('%Y-%m-%dT%H:%M:%SZ', 'now')
+FROM outbox
+WHERE sent_at IS NOT NULL
+ON CONFLICT (consumer) DO NOTHING;
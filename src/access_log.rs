@@ -0,0 +1,206 @@
+//! Log de accesos HTTP.
+//!
+//! [`log_requests`] escribe una línea por petición con el método, la ruta, el estado, la
+//! latencia, el tamaño de la respuesta, el `x-request-id` y la IP del cliente (la de
+//! [`ClientIp`], o `-` si no se resolvió), con el target `access_log` para que se pueda filtrar
+//! con `RUST_LOG` (por ejemplo, `info,access_log=off`). La ruta se registra sin la query
+//! string, que puede llevar datos personales.
+//!
+//! El formato lo decide el suscriptor de trazas según `LOG_FORMAT`: `human` (por defecto) da
+//! líneas compactas con pares `clave=valor` y `json` un objeto JSON por línea con los campos en
+//! la raíz, listo para un agregador de logs. Las respuestas correctas de las rutas de
+//! `ACCESS_LOG_SKIP_PATHS` (`/health` y `/metrics` por defecto) no se registran, para que las
+//! sondas y el scraping no llenen el log; sus errores sí.
+
+use std::{
+    str::FromStr,
+    sync::{Arc, RwLock},
+    time::Instant,
+};
+
+use anyhow::bail;
+use axum::{
+    body::HttpBody,
+    extract::{Request, State},
+    http::header,
+    middleware::Next,
+    response::Response,
+};
+use tracing::info;
+
+use crate::client_ip::ClientIp;
+use crate::config::{env_or, parse_flag, Environment};
+
+/// Formato de las líneas de log.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Texto compacto para leer en consola.
+    #[default]
+    Human,
+    /// Un objeto JSON por línea.
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> anyhow::Result<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "human" | "text" => Ok(Self::Human),
+            "json" => Ok(Self::Json),
+            other => bail!("Formato de log desconocido: {other}"),
+        }
+    }
+}
+
+impl LogFormat {
+    /// Lee `LOG_FORMAT`; sin ella o con un valor desconocido, JSON en el perfil `production` y
+    /// texto en el resto.
+    pub fn from_env() -> Self {
+        let default = match Environment::from_env().unwrap_or_default() {
+            Environment::Production => Self::Json,
+            Environment::Development | Environment::Test => Self::Human,
+        };
+        env_or("LOG_FORMAT", default)
+    }
+}
+
+/// Configuración del log de accesos.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessLogConfig {
+    /// Si se registran las peticiones (`ACCESS_LOG`).
+    pub enabled: bool,
+    /// Rutas cuyas respuestas correctas no se registran (`ACCESS_LOG_SKIP_PATHS`).
+    pub skip_paths: Vec<String>,
+}
+
+impl Default for AccessLogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            skip_paths: vec!["/health".to_string(), "/metrics".to_string()],
+        }
+    }
+}
+
+impl AccessLogConfig {
+    /// Lee `ACCESS_LOG` y `ACCESS_LOG_SKIP_PATHS` (rutas separadas por comas).
+    pub fn from_env() -> Self {
+        Self::from_lookup(|name| std::env::var(name).ok())
+    }
+
+    /// Igual que [`Self::from_env`], pero obteniendo cada variable de `lookup`.
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let defaults = Self::default();
+
+        Self {
+            enabled: lookup("ACCESS_LOG")
+                .map(|value| parse_flag(&value))
+                .unwrap_or(defaults.enabled),
+            skip_paths: lookup("ACCESS_LOG_SKIP_PATHS")
+                .map(|paths| {
+                    paths
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|path| !path.is_empty())
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or(defaults.skip_paths),
+        }
+    }
+
+    /// Indica si la respuesta a `path` con `status` debe registrarse.
+    fn should_log(&self, path: &str, status: u16) -> bool {
+        self.enabled && (status >= 400 || !self.skip_paths.iter().any(|skip| skip == path))
+    }
+}
+
+/// Configuración del log de accesos compartida con el middleware, que se puede sustituir en
+/// caliente (ver [`crate::reload`]).
+#[derive(Debug, Clone)]
+pub struct AccessLog {
+    config: Arc<RwLock<Arc<AccessLogConfig>>>,
+}
+
+impl AccessLog {
+    /// Crea la configuración compartida.
+    pub fn new(config: AccessLogConfig) -> Self {
+        Self {
+            config: Arc::new(RwLock::new(Arc::new(config))),
+        }
+    }
+
+    /// Configuración vigente.
+    pub fn current(&self) -> Arc<AccessLogConfig> {
+        self.config
+            .read()
+            .expect("mutex del log de accesos envenenado")
+            .clone()
+    }
+
+    /// Sustituye la configuración; se aplica a las peticiones que lleguen a partir de ahora.
+    pub fn replace(&self, config: AccessLogConfig) {
+        *self
+            .config
+            .write()
+            .expect("mutex del log de accesos envenenado") = Arc::new(config);
+    }
+}
+
+/// Middleware que registra cada petición al terminar de generar su respuesta.
+///
+/// La latencia llega hasta que están listas las cabeceras, así que en las respuestas en
+/// streaming no incluye el envío del cuerpo. El tamaño sale del cuerpo o de `Content-Length`;
+/// si no se conoce de antemano se registra como `-`.
+pub async fn log_requests(
+    State(access_log): State<AccessLog>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let started_at = Instant::now();
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let request_id = request
+        .headers()
+        .get("x-request-id")
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("-")
+        .to_string();
+    let client_ip = request
+        .extensions()
+        .get::<ClientIp>()
+        .map_or_else(|| "-".to_string(), |ClientIp(ip)| ip.to_string());
+
+    let response = next.run(request).await;
+
+    let config = access_log.current();
+    let status = response.status().as_u16();
+    if !config.should_log(&path, status) {
+        return response;
+    }
+
+    let latency_ms = started_at.elapsed().as_secs_f64() * 1000.0;
+    let bytes = response.body().size_hint().exact().or_else(|| {
+        response
+            .headers()
+            .get(header::CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse().ok())
+    });
+
+    match bytes {
+        Some(bytes) => info!(
+            target: "access_log",
+            %method, path, status, latency_ms, bytes, request_id, client_ip,
+            "Petición atendida"
+        ),
+        None => info!(
+            target: "access_log",
+            %method, path, status, latency_ms, bytes = "-", request_id, client_ip,
+            "Petición atendida"
+        ),
+    }
+
+    response
+}
@@ -0,0 +1,64 @@
+//! Firma de peticiones a AWS con Signature Version 4.
+//!
+//! La comparten el almacén S3 y el proveedor de secretos de AWS Secrets Manager. Se
+//! implementa con `hmac` y `sha2`, sin depender del SDK de AWS.
+
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+pub(crate) const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+
+/// Clave secreta, región y servicio con los que se firman las peticiones.
+pub(crate) struct Signer<'a> {
+    pub secret_access_key: &'a str,
+    pub region: &'a str,
+    pub service: &'a str,
+}
+
+impl Signer<'_> {
+    /// Ámbito de la credencial: `<fecha>/<región>/<servicio>/aws4_request`.
+    pub(crate) fn scope(&self, date: &str) -> String {
+        format!("{date}/{}/{}/aws4_request", self.region, self.service)
+    }
+
+    /// Firma una petición canónica según SigV4.
+    pub(crate) fn signature(&self, canonical_request: &str, amz_date: &str, date: &str) -> String {
+        let string_to_sign = format!(
+            "{ALGORITHM}\n{amz_date}\n{}\n{}",
+            self.scope(date),
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let secret = format!("AWS4{}", self.secret_access_key);
+        let signing_key = [date, self.region, self.service, "aws4_request"]
+            .iter()
+            .fold(secret.into_bytes(), |key, part| {
+                hmac_sha256(&key, part.as_bytes())
+            });
+
+        hex::encode(hmac_sha256(&signing_key, string_to_sign.as_bytes()))
+    }
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(key).expect("HMAC acepta claves de cualquier longitud");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Codificación de URI de SigV4: solo quedan sin escapar los caracteres no reservados y,
+/// si `encode_slash` es falso, la barra.
+pub(crate) fn uri_encode(value: &str, encode_slash: bool) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b'/' if !encode_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    encoded
+}
@@ -0,0 +1,386 @@
+//! Copias de seguridad en caliente de la base principal.
+//!
+//! La copia usa la API de backup en línea de SQLite (`sqlite3_backup_*`) sobre una conexión
+//! del pool: lee la base dentro de una transacción de lectura, así que el resultado es una
+//! instantánea consistente y, con WAL, las escrituras siguen atendiéndose mientras se copia.
+//! El archivo se escribe primero como `.partial` y se renombra al terminar, de modo que en
+//! `BACKUP_DIR` nunca queda una copia a medias con nombre definitivo.
+//!
+//! Con `BACKUP_TO_STORAGE=true` la copia se sube después al almacenamiento configurado (S3 o
+//! MinIO con `STORAGE_BACKEND=s3`) bajo `backups/` y se borra del disco local.
+//!
+//! [`restore`] hace el camino inverso desde un archivo de copia: lo valida, guarda la base
+//! actual por si hay que volver atrás, la sustituye y la migra a la versión de este binario.
+
+use std::{
+    ffi::CStr,
+    ops::DerefMut,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use libsqlite3_sys as ffi;
+use serde::Serialize;
+use sqlx::{sqlite::SqliteConnectOptions, Connection, SqliteConnection, SqlitePool};
+use tracing::info;
+
+use crate::config::AppConfig;
+use crate::fuzzy_search;
+use crate::migrations::{self, MIGRATOR};
+use crate::storage::Storage;
+
+/// Prefijo de las claves de las copias subidas al almacenamiento.
+const STORAGE_PREFIX: &str = "backups/";
+/// Espera antes de reintentar un paso de la copia que encontró la base bloqueada.
+const BUSY_BACKOFF: Duration = Duration::from_millis(50);
+
+/// Dónde quedó guardada una copia.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BackupDestination {
+    /// Archivo en `BACKUP_DIR`.
+    File,
+    /// Objeto en el almacenamiento configurado.
+    Storage,
+}
+
+/// Copia generada, tal como la devuelve `POST /admin/backup`.
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupArtifact {
+    pub destination: BackupDestination,
+    /// Ruta del archivo o clave del objeto.
+    pub location: String,
+    pub size_bytes: u64,
+    pub created_at: DateTime<Utc>,
+    /// URL temporal de descarga, si el almacenamiento la admite.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub download_url: Option<String>,
+}
+
+/// Genera una copia de la base principal según `BACKUP_DIR` y `BACKUP_TO_STORAGE`.
+pub async fn create(
+    pool: &SqlitePool,
+    config: &AppConfig,
+    storage: &dyn Storage,
+) -> Result<BackupArtifact> {
+    let created_at = Utc::now();
+    let file_name = format!("backup-{}.db", created_at.format("%Y%m%dT%H%M%S%.3fZ"));
+    let path = config.backup_dir.join(&file_name);
+    let size_bytes = snapshot(pool, &path).await?;
+
+    let artifact = if config.backup_to_storage {
+        let key = format!("{STORAGE_PREFIX}{file_name}");
+        let bytes = tokio::fs::read(&path)
+            .await
+            .with_context(|| format!("No se pudo leer la copia {}", path.display()))?;
+        storage
+            .put(&key, bytes, "application/vnd.sqlite3")
+            .await
+            .with_context(|| format!("No se pudo subir la copia a {key}"))?;
+        tokio::fs::remove_file(&path).await.ok();
+
+        BackupArtifact {
+            destination: BackupDestination::Storage,
+            download_url: storage.presigned_url(&key),
+            location: key,
+            size_bytes,
+            created_at,
+        }
+    } else {
+        BackupArtifact {
+            destination: BackupDestination::File,
+            location: path.display().to_string(),
+            size_bytes,
+            created_at,
+            download_url: None,
+        }
+    };
+
+    info!(
+        location = %artifact.location,
+        size_bytes,
+        "Copia de seguridad generada"
+    );
+    Ok(artifact)
+}
+
+/// Copia la base de `pool` en `path` con la API de backup en línea y devuelve el tamaño del
+/// archivo resultante. Falla si `path` ya existe.
+pub async fn snapshot(pool: &SqlitePool, path: &Path) -> Result<u64> {
+    let (_, size) = snapshot_from(pool.acquire().await?, path).await?;
+    Ok(size)
+}
+
+/// Como [`snapshot`], pero copiando desde `source` y devolviéndola después. Si `source` tiene
+/// una transacción de lectura abierta, la copia refleja exactamente lo que esa transacción ve.
+pub(crate) async fn snapshot_from<S>(source: S, path: &Path) -> Result<(S, u64)>
+where
+    S: DerefMut<Target = SqliteConnection> + Send + 'static,
+{
+    if path.exists() {
+        bail!("Ya existe un archivo en {}", path.display());
+    }
+    if let Some(directory) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        tokio::fs::create_dir_all(directory)
+            .await
+            .with_context(|| format!("No se pudo crear el directorio {}", directory.display()))?;
+    }
+
+    let partial = partial_path(path);
+    let _ = tokio::fs::remove_file(&partial).await;
+    let copied = async {
+        let destination = SqliteConnection::connect_with(
+            &SqliteConnectOptions::new()
+                .filename(&partial)
+                .create_if_missing(true),
+        )
+        .await?;
+        let (source, mut destination) = copy_between(source, Box::new(destination)).await?;
+        // La copia hereda el modo WAL de la base; se deja como un único archivo autónomo.
+        sqlx::query("PRAGMA journal_mode = DELETE")
+            .execute(&mut *destination)
+            .await?;
+        destination.close().await?;
+        Ok::<_, anyhow::Error>(source)
+    }
+    .await;
+
+    let source = match copied {
+        Ok(source) => source,
+        Err(error) => {
+            let _ = tokio::fs::remove_file(&partial).await;
+            return Err(error);
+        }
+    };
+    tokio::fs::rename(&partial, path)
+        .await
+        .with_context(|| format!("No se pudo mover la copia a {}", path.display()))?;
+
+    Ok((source, tokio::fs::metadata(path).await?.len()))
+}
+
+/// Restaura la base de `pool` a partir de la copia `snapshot`.
+///
+/// Antes de tocar nada valida la copia con [`validate`] y guarda la base actual en
+/// `BACKUP_DIR`, para poder volver atrás. El contenido se sustituye con la misma API de backup
+/// en línea, que lo hace de forma atómica bajo un bloqueo exclusivo; aun así, el servidor debe
+/// estar parado o en modo de mantenimiento para que ninguna escritura se pierda. Después se
+/// aplican las migraciones posteriores a la copia y se comprueba la integridad del resultado.
+pub async fn restore(
+    pool: &SqlitePool,
+    config: &AppConfig,
+    snapshot: &Path,
+) -> Result<RestoreReport> {
+    let snapshot_version = validate(snapshot).await?;
+
+    let safety_copy = config.backup_dir.join(format!(
+        "pre-restore-{}.db",
+        Utc::now().format("%Y%m%dT%H%M%S%.3fZ")
+    ));
+    self::snapshot(pool, &safety_copy)
+        .await
+        .context("No se pudo guardar la base actual antes de restaurar; no se ha cambiado nada")?;
+    info!(safety_copy = %safety_copy.display(), "Base actual guardada antes de restaurar");
+
+    let source = open_read_only(snapshot).await?;
+    let (source, _) = copy_between(Box::new(source), pool.acquire().await?)
+        .await
+        .with_context(|| {
+            format!(
+                "No se pudo restaurar {}; la base anterior está en {}",
+                snapshot.display(),
+                safety_copy.display()
+            )
+        })?;
+    source.close().await?;
+
+    let restored = migrations::applied_versions(pool).await?;
+    MIGRATOR
+        .run(pool)
+        .await
+        .context("Fallo al aplicar las migraciones sobre la base restaurada")?;
+    fuzzy_search::index_missing(pool)
+        .await
+        .context("No se pudo completar el índice de búsqueda de la base restaurada")?;
+    let applied_migrations = migrations::applied_versions(pool)
+        .await?
+        .into_iter()
+        .filter(|version| !restored.contains(version))
+        .collect();
+
+    integrity_check(&mut *pool.acquire().await?)
+        .await
+        .with_context(|| {
+            format!(
+                "La base restaurada está dañada; la anterior está en {}",
+                safety_copy.display()
+            )
+        })?;
+
+    info!(
+        snapshot = %snapshot.display(),
+        ?snapshot_version,
+        "Copia de seguridad restaurada"
+    );
+    Ok(RestoreReport {
+        snapshot_version,
+        applied_migrations,
+        safety_copy,
+    })
+}
+
+/// Resultado de [`restore`].
+#[derive(Debug, Clone)]
+pub struct RestoreReport {
+    /// Última migración que tenía la copia.
+    pub snapshot_version: Option<i64>,
+    /// Migraciones aplicadas después de restaurar.
+    pub applied_migrations: Vec<i64>,
+    /// Copia de la base tal como estaba antes de restaurar.
+    pub safety_copy: PathBuf,
+}
+
+/// Comprueba que `path` sea una copia íntegra de esta aplicación que este binario sepa
+/// migrar, y devuelve su última migración.
+pub async fn validate(path: &Path) -> Result<Option<i64>> {
+    if !path.is_file() {
+        bail!("No existe la copia {}", path.display());
+    }
+
+    let mut connection = open_read_only(path).await?;
+    integrity_check(&mut connection)
+        .await
+        .with_context(|| format!("La copia {} está dañada", path.display()))?;
+    let versions = sqlx::query_scalar::<_, i64>(
+        "SELECT version FROM _sqlx_migrations WHERE success ORDER BY version",
+    )
+    .fetch_all(&mut connection)
+    .await
+    .with_context(|| {
+        format!(
+            "{} no es una copia de esta aplicación: no tiene tabla de migraciones",
+            path.display()
+        )
+    })?;
+    connection.close().await?;
+
+    let unknown = versions
+        .iter()
+        .filter(|version| {
+            !MIGRATOR
+                .iter()
+                .any(|migration| migration.version == **version)
+        })
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    if !unknown.is_empty() {
+        bail!(
+            "La copia tiene migraciones que este binario no conoce ({}); restáurala con una \
+             versión más reciente",
+            unknown.join(", ")
+        );
+    }
+
+    Ok(versions.last().copied())
+}
+
+/// Falla si `PRAGMA integrity_check` encuentra problemas, y los incluye en el error.
+async fn integrity_check(connection: &mut SqliteConnection) -> Result<()> {
+    let problems = sqlx::query_scalar::<_, String>("PRAGMA integrity_check")
+        .fetch_all(connection)
+        .await?;
+    if problems != ["ok"] {
+        bail!("integrity_check: {}", problems.join("; "));
+    }
+
+    Ok(())
+}
+
+/// Abre `path` en solo lectura.
+async fn open_read_only(path: &Path) -> Result<SqliteConnection> {
+    SqliteConnection::connect_with(&SqliteConnectOptions::new().filename(path).read_only(true))
+        .await
+        .with_context(|| format!("No se pudo abrir {}", path.display()))
+}
+
+/// Copia la base de `source` sobre la de `destination` y devuelve las dos conexiones. Admite
+/// conexiones del pool y conexiones sueltas en un `Box`.
+async fn copy_between<S, D>(mut source: S, mut destination: D) -> Result<(S, D)>
+where
+    S: DerefMut<Target = SqliteConnection> + Send + 'static,
+    D: DerefMut<Target = SqliteConnection> + Send + 'static,
+{
+    let runtime = tokio::runtime::Handle::current();
+    // La copia bloquea el hilo mientras avanza; se hace fuera de los hilos del runtime.
+    tokio::task::spawn_blocking(move || {
+        runtime.block_on(async {
+            let mut source = source.lock_handle().await?;
+            let mut destination = destination.lock_handle().await?;
+            // SAFETY: ambos punteros provienen de conexiones abiertas cuyo acceso queda
+            // bloqueado en exclusiva por los guards mientras dura la copia.
+            unsafe {
+                copy_database(
+                    source.as_raw_handle().as_ptr(),
+                    destination.as_raw_handle().as_ptr(),
+                )
+            }
+        })?;
+        Ok((source, destination))
+    })
+    .await
+    .context("La tarea de copia terminó de forma inesperada")?
+}
+
+/// Ejecuta `sqlite3_backup_*` de la base `main` de `source` a la de `destination`.
+///
+/// Se copia en un único paso para que toda la lectura ocurra en la misma transacción: si se
+/// hiciera por tramos, cada escritura concurrente obligaría a SQLite a reiniciar la copia.
+///
+/// # Safety
+///
+/// Ambos punteros deben ser conexiones abiertas que nadie más use durante la llamada.
+unsafe fn copy_database(source: *mut ffi::sqlite3, destination: *mut ffi::sqlite3) -> Result<()> {
+    let backup = ffi::sqlite3_backup_init(destination, c"main".as_ptr(), source, c"main".as_ptr());
+    if backup.is_null() {
+        bail!(
+            "No se pudo iniciar la copia: {}",
+            error_message(destination)
+        );
+    }
+
+    let step = loop {
+        match ffi::sqlite3_backup_step(backup, -1) {
+            ffi::SQLITE_BUSY | ffi::SQLITE_LOCKED => std::thread::sleep(BUSY_BACKOFF),
+            code => break code,
+        }
+    };
+    let finish = ffi::sqlite3_backup_finish(backup);
+    if step != ffi::SQLITE_DONE || finish != ffi::SQLITE_OK {
+        bail!("La copia falló: {}", error_message(destination));
+    }
+
+    Ok(())
+}
+
+/// Mensaje del último error de `connection`.
+///
+/// # Safety
+///
+/// `connection` debe ser una conexión abierta.
+unsafe fn error_message(connection: *mut ffi::sqlite3) -> String {
+    CStr::from_ptr(ffi::sqlite3_errmsg(connection))
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Ruta temporal en la que se escribe la copia antes de renombrarla.
+fn partial_path(path: &Path) -> PathBuf {
+    let mut partial = path.as_os_str().to_owned();
+    partial.push(".partial");
+    PathBuf::from(partial)
+}
@@ -0,0 +1,444 @@
+//! Generador de carga para medir la API (feature `bench`).
+//!
+//! `bench <url>` lanza varios trabajadores concurrentes que ejecutan operaciones CRUD sobre
+//! `/users` con el [`ApiClient`] hasta agotar el tiempo o el número de peticiones, y resume la
+//! latencia (percentiles 50, 90 y 99) y la tasa de errores de cada operación. Sirve para
+//! detectar regresiones de rendimiento en la capa de SQLite comparando ejecuciones:
+//!
+//! ```text
+//! bench http://localhost:3000 --concurrency 16 --duration 30 --mix create=1,get=5,list=1
+//! ```
+//!
+//! Cada trabajador solo lee, modifica y borra los usuarios que él mismo creó; si aún no tiene
+//! ninguno, la operación sorteada se sustituye por un alta. Los usuarios creados y no
+//! borrados se quedan en la base de destino, así que conviene apuntar a una base desechable.
+
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Context, Result};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use uuid::Uuid;
+
+use crate::client::ApiClient;
+use crate::models::user::{CreateUser, ListUsersQuery, StatusFilter, UpdateUser};
+
+const USAGE: &str = "Uso: bench <url> [--concurrency <n>] [--duration <segundos>] \
+    [--requests <n>] [--mix create=<peso>,get=<peso>,...] [--tenant <id>]";
+
+/// Operación de la carga de trabajo.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Create,
+    Get,
+    List,
+    Update,
+    Delete,
+}
+
+impl Operation {
+    pub const ALL: [Self; 5] = [
+        Self::Create,
+        Self::Get,
+        Self::List,
+        Self::Update,
+        Self::Delete,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Create => "create",
+            Self::Get => "get",
+            Self::List => "list",
+            Self::Update => "update",
+            Self::Delete => "delete",
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// Peso relativo de cada operación en la carga de trabajo.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mix {
+    weights: [u32; Operation::ALL.len()],
+}
+
+impl Default for Mix {
+    fn default() -> Self {
+        Self {
+            weights: [1, 5, 1, 2, 1],
+        }
+    }
+}
+
+impl Mix {
+    /// Interpreta `create=1,get=5,...`; las operaciones omitidas tienen peso `0`.
+    pub fn parse(value: &str) -> Result<Self> {
+        let mut weights = [0; Operation::ALL.len()];
+        for entry in value
+            .split(',')
+            .map(str::trim)
+            .filter(|entry| !entry.is_empty())
+        {
+            let (name, weight) = entry
+                .split_once('=')
+                .with_context(|| format!("Peso inválido (se espera operación=peso): {entry}"))?;
+            let operation = Operation::ALL
+                .into_iter()
+                .find(|operation| operation.as_str() == name.trim())
+                .with_context(|| format!("Operación desconocida: {name}"))?;
+            weights[operation.index()] = weight
+                .trim()
+                .parse()
+                .with_context(|| format!("Peso inválido para {name}: {weight}"))?;
+        }
+
+        if weights.iter().all(|weight| *weight == 0) {
+            bail!("La mezcla de operaciones no puede estar vacía");
+        }
+        Ok(Self { weights })
+    }
+
+    /// Peso de `operation`.
+    pub fn weight(&self, operation: Operation) -> u32 {
+        self.weights[operation.index()]
+    }
+
+    fn draw(&self, rng: &mut impl Rng) -> Operation {
+        let total: u32 = self.weights.iter().sum();
+        let mut ticket = rng.gen_range(0..total);
+        for operation in Operation::ALL {
+            let weight = self.weight(operation);
+            if ticket < weight {
+                return operation;
+            }
+            ticket -= weight;
+        }
+        unreachable!("el sorteo siempre cae dentro de la suma de pesos")
+    }
+}
+
+/// Parámetros de `bench`.
+#[derive(Debug, Clone)]
+pub struct BenchConfig {
+    /// URL base de la API que se va a medir.
+    pub target: String,
+    /// Trabajadores que envían peticiones a la vez.
+    pub concurrency: usize,
+    /// Tiempo máximo de la prueba.
+    pub duration: Duration,
+    /// Número máximo de peticiones entre todos los trabajadores.
+    pub requests: Option<u64>,
+    pub mix: Mix,
+    /// Inquilino en cuyo nombre se envían las peticiones.
+    pub tenant: Option<String>,
+}
+
+impl BenchConfig {
+    /// Configuración por defecto contra `target`: 8 trabajadores durante 10 segundos.
+    pub fn new(target: impl Into<String>) -> Self {
+        Self {
+            target: target.into(),
+            concurrency: 8,
+            duration: Duration::from_secs(10),
+            requests: None,
+            mix: Mix::default(),
+            tenant: None,
+        }
+    }
+
+    /// Interpreta los argumentos que siguen a `bench`.
+    pub fn parse(args: &[String]) -> Result<Self> {
+        let Some((target, mut rest)) = args.split_first() else {
+            bail!(USAGE);
+        };
+        if target.starts_with("--") {
+            bail!(USAGE);
+        }
+
+        let mut config = Self::new(target.clone());
+        while let [flag, value, tail @ ..] = rest {
+            match flag.as_str() {
+                "--concurrency" => {
+                    config.concurrency = parse_number(flag, value)?;
+                    if config.concurrency == 0 {
+                        bail!("--concurrency debe ser mayor que 0");
+                    }
+                }
+                "--duration" => config.duration = Duration::from_secs(parse_number(flag, value)?),
+                "--requests" => config.requests = Some(parse_number(flag, value)?),
+                "--mix" => config.mix = Mix::parse(value)?,
+                "--tenant" => config.tenant = Some(value.clone()),
+                _ => bail!("Opción desconocida: {flag}\n{USAGE}"),
+            }
+            rest = tail;
+        }
+        if !rest.is_empty() {
+            bail!(USAGE);
+        }
+
+        Ok(config)
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T> {
+    value
+        .parse()
+        .ok()
+        .with_context(|| format!("Valor inválido para {flag}: {value}"))
+}
+
+/// Latencias y errores acumulados de una operación.
+#[derive(Debug, Default, Clone)]
+struct Samples {
+    latencies: Vec<Duration>,
+    errors: u64,
+}
+
+/// Estadísticas de una operación.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OperationStats {
+    pub operation: Operation,
+    pub requests: u64,
+    pub errors: u64,
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+    pub max: Duration,
+}
+
+impl OperationStats {
+    fn from_samples(operation: Operation, mut samples: Samples) -> Self {
+        samples.latencies.sort_unstable();
+        let latencies = &samples.latencies;
+        Self {
+            operation,
+            requests: latencies.len() as u64,
+            errors: samples.errors,
+            p50: percentile(latencies, 50.0),
+            p90: percentile(latencies, 90.0),
+            p99: percentile(latencies, 99.0),
+            max: latencies.last().copied().unwrap_or_default(),
+        }
+    }
+
+    /// Fracción de peticiones fallidas, entre `0` y `1`.
+    pub fn error_rate(&self) -> f64 {
+        rate(self.errors, self.requests)
+    }
+}
+
+/// Percentil por rango más cercano sobre latencias ya ordenadas.
+fn percentile(sorted: &[Duration], percent: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let rank = (percent / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+fn rate(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 / total as f64
+    }
+}
+
+/// Resumen de una ejecución de `bench`.
+#[derive(Debug, Clone)]
+pub struct BenchReport {
+    pub elapsed: Duration,
+    /// Estadísticas de las operaciones que llegaron a ejecutarse.
+    pub operations: Vec<OperationStats>,
+}
+
+impl BenchReport {
+    pub fn total_requests(&self) -> u64 {
+        self.operations.iter().map(|stats| stats.requests).sum()
+    }
+
+    pub fn total_errors(&self) -> u64 {
+        self.operations.iter().map(|stats| stats.errors).sum()
+    }
+
+    /// Fracción de peticiones fallidas, entre `0` y `1`.
+    pub fn error_rate(&self) -> f64 {
+        rate(self.total_errors(), self.total_requests())
+    }
+
+    /// Peticiones completadas por segundo.
+    pub fn throughput(&self) -> f64 {
+        self.total_requests() as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+impl fmt::Display for BenchReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{:<8} {:>10} {:>8} {:>10} {:>10} {:>10} {:>10}",
+            "op", "peticiones", "errores", "p50 ms", "p90 ms", "p99 ms", "máx ms"
+        )?;
+        for stats in &self.operations {
+            writeln!(
+                f,
+                "{:<8} {:>10} {:>7.2}% {:>10.2} {:>10.2} {:>10.2} {:>10.2}",
+                stats.operation.as_str(),
+                stats.requests,
+                stats.error_rate() * 100.0,
+                millis(stats.p50),
+                millis(stats.p90),
+                millis(stats.p99),
+                millis(stats.max),
+            )?;
+        }
+        write!(
+            f,
+            "Total: {} peticiones en {:.1} s ({:.1} req/s), {:.2}% de errores",
+            self.total_requests(),
+            self.elapsed.as_secs_f64(),
+            self.throughput(),
+            self.error_rate() * 100.0
+        )
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Ejecuta la carga de trabajo descrita por `config` y devuelve el resumen.
+pub async fn run(config: &BenchConfig) -> Result<BenchReport> {
+    let mut client = ApiClient::new(&config.target)
+        .with_context(|| format!("No se pudo crear el cliente para {}", config.target))?;
+    if let Some(tenant) = &config.tenant {
+        client = client.with_tenant(tenant);
+    }
+
+    let run_id = Uuid::new_v4().simple().to_string();
+    let issued = Arc::new(AtomicU64::new(0));
+    let started_at = Instant::now();
+    let deadline = started_at + config.duration;
+
+    let workers: Vec<_> = (0..config.concurrency)
+        .map(|worker| {
+            let worker = Worker {
+                client: client.clone(),
+                mix: config.mix,
+                prefix: format!("bench-{}-{worker}", &run_id[..8]),
+                issued: issued.clone(),
+                limit: config.requests,
+                deadline,
+            };
+            tokio::spawn(worker.run())
+        })
+        .collect();
+
+    let mut samples = vec![Samples::default(); Operation::ALL.len()];
+    for worker in workers {
+        let worker_samples = worker.await.context("Un trabajador de la prueba falló")?;
+        for (total, partial) in samples.iter_mut().zip(worker_samples) {
+            total.latencies.extend(partial.latencies);
+            total.errors += partial.errors;
+        }
+    }
+
+    Ok(BenchReport {
+        elapsed: started_at.elapsed(),
+        operations: Operation::ALL
+            .into_iter()
+            .zip(samples)
+            .filter(|(_, samples)| !samples.latencies.is_empty())
+            .map(|(operation, samples)| OperationStats::from_samples(operation, samples))
+            .collect(),
+    })
+}
+
+/// Trabajador que envía peticiones en serie hasta agotar el tiempo o el cupo compartido.
+struct Worker {
+    client: ApiClient,
+    mix: Mix,
+    /// Prefijo de los correos de los usuarios que crea, único por ejecución y trabajador.
+    prefix: String,
+    issued: Arc<AtomicU64>,
+    limit: Option<u64>,
+    deadline: Instant,
+}
+
+impl Worker {
+    async fn run(self) -> Vec<Samples> {
+        let mut samples = vec![Samples::default(); Operation::ALL.len()];
+        let mut rng = StdRng::from_entropy();
+        let mut owned: Vec<Uuid> = Vec::new();
+        let mut created = 0_u64;
+        let users = self.client.users();
+
+        while Instant::now() < self.deadline {
+            if let Some(limit) = self.limit {
+                if self.issued.fetch_add(1, Ordering::Relaxed) >= limit {
+                    break;
+                }
+            }
+
+            let mut operation = self.mix.draw(&mut rng);
+            if owned.is_empty() && operation != Operation::List {
+                operation = Operation::Create;
+            }
+            let target = (!owned.is_empty()).then(|| rng.gen_range(0..owned.len()));
+
+            let started_at = Instant::now();
+            let succeeded = match operation {
+                Operation::Create => {
+                    created += 1;
+                    let user = CreateUser {
+                        name: format!("Bench {created}"),
+                        email: format!("{}-{created}@example.com", self.prefix),
+                    };
+                    users
+                        .create(&user)
+                        .await
+                        .map(|user| owned.push(user.id))
+                        .is_ok()
+                }
+                Operation::Get => users.get(owned[target.unwrap()]).await.is_ok(),
+                Operation::List => users
+                    .list(&ListUsersQuery {
+                        status: StatusFilter::All,
+                        tag: None,
+                        filter: None,
+                    })
+                    .await
+                    .is_ok(),
+                Operation::Update => {
+                    let changes = UpdateUser {
+                        name: Some(format!("Bench {}", rng.gen::<u32>())),
+                        ..UpdateUser::default()
+                    };
+                    users.update(owned[target.unwrap()], &changes).await.is_ok()
+                }
+                Operation::Delete => {
+                    let id = owned.swap_remove(target.unwrap());
+                    users.delete(id).await.is_ok()
+                }
+            };
+
+            let operation_samples = &mut samples[operation.index()];
+            operation_samples.latencies.push(started_at.elapsed());
+            if !succeeded {
+                operation_samples.errors += 1;
+            }
+        }
+
+        samples
+    }
+}
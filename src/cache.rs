@@ -0,0 +1,472 @@
+//! Caché opcional para las lecturas de usuarios.
+//!
+//! [`Cache`] envuelve un [`CacheStore`] intercambiable (en memoria o Redis) y guarda los
+//! valores serializados en JSON con un TTL por tipo de lectura. Los handlers de escritura
+//! invalidan las claves afectadas tras confirmar cada cambio y [`CacheInvalidator`] repite
+//! la invalidación al recibir el evento de dominio, cubriendo lecturas concurrentes que
+//! pudieran haber guardado un valor anterior. Los fallos del almacén nunca rompen una
+//! petición: se registran y la lectura cae a la base de datos.
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result};
+use moka::Expiry;
+use redis::{aio::ConnectionManager, AsyncCommands};
+use serde::{de::DeserializeOwned, Serialize};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::{
+    config::env_or,
+    events::{EventEnvelope, EventSubscriber},
+    ids,
+    metrics::Metrics,
+};
+
+/// Futuro devuelto por las operaciones de un [`CacheStore`].
+pub type CacheFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + Send + 'a>>;
+
+/// Almacén clave-valor con expiración en el que se apoya la caché.
+pub trait CacheStore: Send + Sync + 'static {
+    /// Recupera un valor si existe y no ha expirado.
+    fn get<'a>(&'a self, key: &'a str) -> CacheFuture<'a, Option<String>>;
+
+    /// Guarda un valor durante `ttl`.
+    fn set<'a>(&'a self, key: &'a str, value: String, ttl: Duration) -> CacheFuture<'a, ()>;
+
+    /// Elimina las claves indicadas.
+    fn delete<'a>(&'a self, keys: &'a [String]) -> CacheFuture<'a, ()>;
+
+    /// Guarda un valor durante `ttl` solo si la clave no existe, de forma atómica; devuelve si
+    /// lo guardó.
+    fn insert_if_absent<'a>(
+        &'a self,
+        key: &'a str,
+        value: String,
+        ttl: Duration,
+    ) -> CacheFuture<'a, bool>;
+
+    /// Comprueba que el almacén responde; los que viven en el proceso siempre lo hacen.
+    fn ping(&self) -> CacheFuture<'_, ()> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+/// Almacén LRU en memoria del proceso, pensado para una única instancia sin Redis.
+///
+/// Limita el número de entradas y respeta el TTL indicado en cada escritura.
+#[derive(Clone)]
+pub struct MemoryStore {
+    entries: moka::future::Cache<String, MemoryEntry>,
+}
+
+#[derive(Clone)]
+struct MemoryEntry {
+    value: String,
+    ttl: Duration,
+}
+
+/// Política de expiración que aplica el TTL propio de cada entrada.
+struct PerEntryTtl;
+
+impl Expiry<String, MemoryEntry> for PerEntryTtl {
+    fn expire_after_create(
+        &self,
+        _key: &String,
+        entry: &MemoryEntry,
+        _created_at: Instant,
+    ) -> Option<Duration> {
+        Some(entry.ttl)
+    }
+
+    fn expire_after_update(
+        &self,
+        _key: &String,
+        entry: &MemoryEntry,
+        _updated_at: Instant,
+        _duration_until_expiry: Option<Duration>,
+    ) -> Option<Duration> {
+        Some(entry.ttl)
+    }
+}
+
+impl MemoryStore {
+    /// Crea un almacén que conserva como máximo `max_entries` valores.
+    pub fn new(max_entries: u64) -> Self {
+        let entries = moka::future::Cache::builder()
+            .max_capacity(max_entries)
+            .expire_after(PerEntryTtl)
+            .build();
+
+        Self { entries }
+    }
+}
+
+impl std::fmt::Debug for MemoryStore {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter
+            .debug_struct("MemoryStore")
+            .field("entries", &self.entries.entry_count())
+            .finish()
+    }
+}
+
+impl CacheStore for MemoryStore {
+    fn get<'a>(&'a self, key: &'a str) -> CacheFuture<'a, Option<String>> {
+        Box::pin(async move { Ok(self.entries.get(key).await.map(|entry| entry.value)) })
+    }
+
+    fn set<'a>(&'a self, key: &'a str, value: String, ttl: Duration) -> CacheFuture<'a, ()> {
+        Box::pin(async move {
+            self.entries
+                .insert(key.to_string(), MemoryEntry { value, ttl })
+                .await;
+            Ok(())
+        })
+    }
+
+    fn delete<'a>(&'a self, keys: &'a [String]) -> CacheFuture<'a, ()> {
+        Box::pin(async move {
+            for key in keys {
+                self.entries.invalidate(key).await;
+            }
+            Ok(())
+        })
+    }
+
+    fn insert_if_absent<'a>(
+        &'a self,
+        key: &'a str,
+        value: String,
+        ttl: Duration,
+    ) -> CacheFuture<'a, bool> {
+        Box::pin(async move {
+            let entry = self
+                .entries
+                .entry(key.to_string())
+                .or_insert(MemoryEntry { value, ttl })
+                .await;
+            Ok(entry.is_fresh())
+        })
+    }
+}
+
+/// Almacén respaldado por Redis, compartido entre instancias.
+#[derive(Clone)]
+pub struct RedisStore {
+    connection: ConnectionManager,
+}
+
+impl RedisStore {
+    /// Abre una conexión gestionada (con reconexión automática) a `url`.
+    pub async fn connect(url: &str) -> Result<Self> {
+        let client =
+            redis::Client::open(url).with_context(|| format!("URL de Redis inválida: {url}"))?;
+        let connection = ConnectionManager::new(client)
+            .await
+            .with_context(|| format!("No se pudo conectar a Redis en {url}"))?;
+
+        Ok(Self { connection })
+    }
+}
+
+impl CacheStore for RedisStore {
+    fn get<'a>(&'a self, key: &'a str) -> CacheFuture<'a, Option<String>> {
+        Box::pin(async move {
+            let mut connection = self.connection.clone();
+            Ok(connection.get(key).await?)
+        })
+    }
+
+    fn set<'a>(&'a self, key: &'a str, value: String, ttl: Duration) -> CacheFuture<'a, ()> {
+        Box::pin(async move {
+            let mut connection = self.connection.clone();
+            connection
+                .set_ex::<_, _, ()>(key, value, ttl.as_secs().max(1))
+                .await?;
+            Ok(())
+        })
+    }
+
+    fn delete<'a>(&'a self, keys: &'a [String]) -> CacheFuture<'a, ()> {
+        Box::pin(async move {
+            if keys.is_empty() {
+                return Ok(());
+            }
+            let mut connection = self.connection.clone();
+            connection.del::<_, ()>(keys).await?;
+            Ok(())
+        })
+    }
+
+    fn insert_if_absent<'a>(
+        &'a self,
+        key: &'a str,
+        value: String,
+        ttl: Duration,
+    ) -> CacheFuture<'a, bool> {
+        Box::pin(async move {
+            let mut connection = self.connection.clone();
+            let stored: Option<String> = redis::cmd("SET")
+                .arg(key)
+                .arg(value)
+                .arg("NX")
+                .arg("PX")
+                .arg(ttl.as_millis().max(1) as u64)
+                .query_async(&mut connection)
+                .await?;
+            Ok(stored.is_some())
+        })
+    }
+
+    fn ping(&self) -> CacheFuture<'_, ()> {
+        Box::pin(async move {
+            let mut connection = self.connection.clone();
+            redis::cmd("PING")
+                .query_async::<_, String>(&mut connection)
+                .await?;
+            Ok(())
+        })
+    }
+}
+
+/// Almacén elegido mediante `CACHE_BACKEND`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CacheBackend {
+    Disabled,
+    Memory,
+    Redis { url: String },
+}
+
+/// Configuración de la caché leída desde variables de entorno.
+#[derive(Debug, Clone)]
+pub struct CacheConfig {
+    pub backend: CacheBackend,
+    /// Tiempo de vida de cada usuario individual.
+    pub user_ttl: Duration,
+    /// Tiempo de vida del listado completo.
+    pub list_ttl: Duration,
+    /// Número máximo de entradas del almacén en memoria.
+    pub max_entries: u64,
+}
+
+impl CacheConfig {
+    /// Lee `CACHE_BACKEND` (`off`, `memory` o `redis`), `REDIS_URL`,
+    /// `CACHE_USER_TTL_SECS`, `CACHE_LIST_TTL_SECS` y `CACHE_MAX_ENTRIES`.
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let backend = match std::env::var("CACHE_BACKEND")
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "memory" => CacheBackend::Memory,
+            "redis" => CacheBackend::Redis {
+                url: std::env::var("REDIS_URL")
+                    .unwrap_or_else(|_| "redis://127.0.0.1:6379".to_string()),
+            },
+            _ => CacheBackend::Disabled,
+        };
+
+        Self {
+            backend,
+            user_ttl: Duration::from_secs(env_or(
+                "CACHE_USER_TTL_SECS",
+                defaults.user_ttl.as_secs(),
+            )),
+            list_ttl: Duration::from_secs(env_or(
+                "CACHE_LIST_TTL_SECS",
+                defaults.list_ttl.as_secs(),
+            )),
+            max_entries: env_or("CACHE_MAX_ENTRIES", defaults.max_entries),
+        }
+    }
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            backend: CacheBackend::Disabled,
+            user_ttl: Duration::from_secs(60),
+            list_ttl: Duration::from_secs(10),
+            max_entries: 10_000,
+        }
+    }
+}
+
+/// Caché compartida por los handlers; sin almacén se comporta como una caché vacía.
+#[derive(Clone)]
+pub struct Cache {
+    store: Option<Arc<dyn CacheStore>>,
+    config: CacheConfig,
+    metrics: Metrics,
+}
+
+impl Cache {
+    /// Caché desactivada: todas las lecturas van a la base de datos.
+    pub fn disabled(metrics: Metrics) -> Self {
+        Self {
+            store: None,
+            config: CacheConfig::default(),
+            metrics,
+        }
+    }
+
+    /// Crea una caché sobre el almacén indicado.
+    pub fn new(store: impl CacheStore, config: CacheConfig, metrics: Metrics) -> Self {
+        Self {
+            store: Some(Arc::new(store)),
+            config,
+            metrics,
+        }
+    }
+
+    /// Construye la caché según la configuración, conectando a Redis si procede.
+    pub async fn from_config(config: CacheConfig, metrics: Metrics) -> Result<Self> {
+        let cache = match &config.backend {
+            CacheBackend::Disabled => Self::disabled(metrics),
+            CacheBackend::Memory => Self::new(
+                MemoryStore::new(config.max_entries),
+                config.clone(),
+                metrics,
+            ),
+            CacheBackend::Redis { url } => {
+                let store = RedisStore::connect(url).await?;
+                Self::new(store, config.clone(), metrics)
+            }
+        };
+
+        info!(backend = ?config.backend, "Caché configurada");
+
+        Ok(cache)
+    }
+
+    /// Comprueba que el almacén responde; sin almacén no hay nada que comprobar.
+    pub async fn ping(&self) -> Result<()> {
+        match &self.store {
+            Some(store) => store.ping().await,
+            None => Ok(()),
+        }
+    }
+
+    /// Indica si hay un almacén configurado.
+    pub fn is_enabled(&self) -> bool {
+        self.store.is_some()
+    }
+
+    /// Tiempo de vida de los usuarios individuales.
+    pub fn user_ttl(&self) -> Duration {
+        self.config.user_ttl
+    }
+
+    /// Tiempo de vida del listado de usuarios.
+    pub fn list_ttl(&self) -> Duration {
+        self.config.list_ttl
+    }
+
+    /// Recupera y deserializa un valor, contabilizando el acierto o fallo.
+    pub async fn get_json<T: DeserializeOwned>(&self, key: &str) -> Option<T> {
+        let store = self.store.as_ref()?;
+
+        let value = match store.get(key).await {
+            Ok(value) => value.and_then(|raw| serde_json::from_str(&raw).ok()),
+            Err(error) => {
+                warn!(key, ?error, "Fallo al leer de la caché");
+                None
+            }
+        };
+
+        self.metrics.record_cache_lookup(value.is_some());
+        value
+    }
+
+    /// Serializa y guarda un valor durante `ttl`.
+    pub async fn set_json<T: Serialize>(&self, key: &str, value: &T, ttl: Duration) {
+        let Some(store) = &self.store else {
+            return;
+        };
+
+        let result = match ids::canonical(|| serde_json::to_string(value)) {
+            Ok(raw) => store.set(key, raw, ttl).await,
+            Err(error) => Err(error.into()),
+        };
+        if let Err(error) = result {
+            warn!(key, ?error, "Fallo al escribir en la caché");
+        }
+    }
+
+    /// Elimina las claves indicadas.
+    pub async fn invalidate(&self, keys: &[String]) {
+        let Some(store) = &self.store else {
+            return;
+        };
+
+        if let Err(error) = store.delete(keys).await {
+            warn!(?keys, ?error, "Fallo al invalidar la caché");
+        }
+    }
+
+    /// Invalida el listado de usuarios de un inquilino, por ejemplo tras crear nuevos.
+    pub async fn invalidate_user_list(&self, tenant_id: &str) {
+        self.invalidate(&[user_list_key(tenant_id)]).await;
+    }
+
+    /// Invalida un usuario y el listado en el que aparece.
+    pub async fn invalidate_user(&self, tenant_id: &str, user_id: Uuid) {
+        self.invalidate(&[user_key(user_id), user_list_key(tenant_id)])
+            .await;
+    }
+}
+
+impl std::fmt::Debug for Cache {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter
+            .debug_struct("Cache")
+            .field("enabled", &self.is_enabled())
+            .field("config", &self.config)
+            .finish()
+    }
+}
+
+/// Clave bajo la que se guarda el listado por defecto de los usuarios de un inquilino.
+pub fn user_list_key(tenant_id: &str) -> String {
+    format!("users:list:{tenant_id}")
+}
+
+/// Clave bajo la que se guarda un usuario individual.
+pub fn user_key(user_id: Uuid) -> String {
+    format!("users:{user_id}")
+}
+
+/// Suscriptor que invalida las entradas afectadas por cada evento de usuario.
+#[derive(Debug, Clone)]
+pub struct CacheInvalidator {
+    cache: Cache,
+}
+
+impl CacheInvalidator {
+    /// Crea un invalidador sobre la caché indicada.
+    pub fn new(cache: Cache) -> Self {
+        Self { cache }
+    }
+}
+
+impl EventSubscriber for CacheInvalidator {
+    fn name(&self) -> &'static str {
+        "cache"
+    }
+
+    async fn handle(&self, envelope: EventEnvelope) {
+        let keys = [
+            user_key(envelope.event.user_id()),
+            user_list_key(envelope.event.tenant_id()),
+        ];
+        self.cache.invalidate(&keys).await;
+    }
+}
@@ -0,0 +1,367 @@
+//! Replicación de cambios (CDC) hacia un destino analítico.
+//!
+//! El conector lee los eventos de dominio del outbox, igual que el relay del broker, y vuelca
+//! cada lote al destino configurado: archivos NDJSON rotados por tamaño en un directorio
+//! local (`CDC_SINK=ndjson`) o un objeto NDJSON por lote en el almacenamiento de la
+//! aplicación (`CDC_SINK=storage`), que con `STORAGE_BACKEND=s3` es un bucket S3. DuckDB lee
+//! esos archivos directamente con `read_json_auto`, así que no hace falta un destino propio.
+//!
+//! La última posición confirmada se guarda en `outbox_offsets` bajo [`CONSUMER`], en cada base
+//! (la principal y las de los inquilinos), de modo que un reinicio continúa donde se quedó sin
+//! tocar las tablas transaccionales. La purga del outbox respeta esa posición.
+
+use std::{future::Future, path::PathBuf, sync::Arc, time::Duration};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::{FromRow, SqlitePool};
+use tokio::{fs, io::AsyncWriteExt};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use crate::config::env_or;
+use crate::encryption::EmailEncryption;
+use crate::events::{DomainEvent, EventEnvelope};
+use crate::ids;
+use crate::models::user::User;
+use crate::outbox;
+use crate::state::AppState;
+use crate::storage::Storage;
+
+/// Nombre con el que el conector guarda su posición en `outbox_offsets`.
+pub const CONSUMER: &str = "cdc";
+
+/// Destino configurado del conector.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CdcSinkConfig {
+    /// Archivos NDJSON locales, rotados al superar `max_file_bytes`.
+    Ndjson {
+        output_dir: PathBuf,
+        max_file_bytes: u64,
+    },
+    /// Un objeto NDJSON por lote en el almacenamiento de la aplicación, bajo `prefix`.
+    Storage { prefix: String },
+}
+
+/// Configuración del conector CDC leída desde variables de entorno.
+#[derive(Debug, Clone)]
+pub struct CdcConfig {
+    pub sink: CdcSinkConfig,
+    /// Número máximo de cambios procesados por iteración.
+    pub batch_size: i64,
+    /// Espera entre iteraciones cuando no hay cambios pendientes.
+    pub poll_interval: Duration,
+}
+
+impl CdcConfig {
+    /// Lee `CDC_SINK` (`ndjson` por defecto, o `storage`), `CDC_NDJSON_DIR` y
+    /// `CDC_MAX_FILE_BYTES` para el destino local, `CDC_STORAGE_PREFIX` para el
+    /// almacenamiento, `CDC_BATCH_SIZE` y `CDC_POLL_INTERVAL_SECS`. Devuelve `None` si el
+    /// conector no está activo: con el destino local hace falta `CDC_NDJSON_DIR`.
+    pub fn from_env() -> Option<Self> {
+        let sink = std::env::var("CDC_SINK").unwrap_or_default();
+        let sink = if sink.trim().eq_ignore_ascii_case("storage") {
+            CdcSinkConfig::Storage {
+                prefix: std::env::var("CDC_STORAGE_PREFIX")
+                    .ok()
+                    .filter(|prefix| !prefix.trim().is_empty())
+                    .unwrap_or_else(|| "cdc/".to_string()),
+            }
+        } else {
+            CdcSinkConfig::Ndjson {
+                output_dir: PathBuf::from(std::env::var("CDC_NDJSON_DIR").ok()?),
+                max_file_bytes: env_or("CDC_MAX_FILE_BYTES", 64 * 1024 * 1024),
+            }
+        };
+
+        Some(Self {
+            sink,
+            batch_size: env_or("CDC_BATCH_SIZE", 500),
+            poll_interval: Duration::from_secs(env_or("CDC_POLL_INTERVAL_SECS", 5)),
+        })
+    }
+}
+
+#[derive(Debug, FromRow)]
+struct OutboxRow {
+    seq: i64,
+    payload: String,
+}
+
+/// Registro que se entrega al destino analítico, uno por evento del outbox.
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangeRecord {
+    /// Posición del evento en el outbox de su base.
+    pub seq: i64,
+    pub event_id: Uuid,
+    /// `insert`, `update`, `merge` o `delete`.
+    pub operation: &'static str,
+    pub tenant_id: String,
+    pub user_id: Uuid,
+    pub changed_at: DateTime<Utc>,
+    /// Estado del usuario tras el cambio, o `null` en las bajas.
+    pub user: Option<User>,
+    /// Usuario que absorbió a `user_id` en una fusión.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub merged_into: Option<Uuid>,
+}
+
+impl ChangeRecord {
+    fn new(seq: i64, envelope: EventEnvelope) -> Self {
+        let (operation, user, merged_into) = match &envelope.event {
+            DomainEvent::UserCreated { user } => ("insert", Some(user.clone()), None),
+            DomainEvent::UserUpdated { user } => ("update", Some(user.clone()), None),
+            DomainEvent::UserMerged { user, merged_into } => {
+                ("merge", Some(user.clone()), Some(*merged_into))
+            }
+            DomainEvent::UserDeleted { .. } => ("delete", None, None),
+        };
+
+        Self {
+            seq,
+            event_id: envelope.id,
+            operation,
+            tenant_id: envelope.event.tenant_id().to_string(),
+            user_id: envelope.event.user_id(),
+            changed_at: envelope.occurred_at,
+            user,
+            merged_into,
+        }
+    }
+}
+
+/// Destino al que se vuelcan los lotes de cambios.
+pub trait ChangeSink: Send {
+    /// Persiste un lote completo; solo si tiene éxito se avanza la posición.
+    fn write_batch(&mut self, changes: &[ChangeRecord]) -> impl Future<Output = Result<()>> + Send;
+}
+
+/// Serializa un lote como NDJSON, con los identificadores en forma canónica.
+fn to_ndjson(changes: &[ChangeRecord]) -> Result<Vec<u8>> {
+    ids::canonical(|| {
+        let mut contents = Vec::new();
+        for change in changes {
+            serde_json::to_writer(&mut contents, change)?;
+            contents.push(b'\n');
+        }
+        Ok(contents)
+    })
+}
+
+/// Destino que escribe un cambio por línea en archivos NDJSON rotados por tamaño.
+#[derive(Debug)]
+pub struct NdjsonFileSink {
+    output_dir: PathBuf,
+    max_file_bytes: u64,
+    current_file: Option<(PathBuf, u64)>,
+    rotation: u64,
+}
+
+impl NdjsonFileSink {
+    /// Crea un destino que escribe en `output_dir`, rotando al superar `max_file_bytes`.
+    pub fn new(output_dir: impl Into<PathBuf>, max_file_bytes: u64) -> Self {
+        Self {
+            output_dir: output_dir.into(),
+            max_file_bytes,
+            current_file: None,
+            rotation: 0,
+        }
+    }
+
+    /// Devuelve el archivo activo, abriendo uno nuevo si el actual alcanzó el límite.
+    async fn active_file(&mut self) -> Result<(PathBuf, u64)> {
+        if let Some((path, size)) = &self.current_file {
+            if *size < self.max_file_bytes {
+                return Ok((path.clone(), *size));
+            }
+        }
+
+        fs::create_dir_all(&self.output_dir)
+            .await
+            .with_context(|| format!("No se pudo crear {}", self.output_dir.display()))?;
+
+        self.rotation += 1;
+        let file_name = format!(
+            "user-changes-{}-{:04}.ndjson",
+            Utc::now().format("%Y%m%dT%H%M%S"),
+            self.rotation
+        );
+        let path = self.output_dir.join(file_name);
+        self.current_file = Some((path.clone(), 0));
+
+        Ok((path, 0))
+    }
+}
+
+impl ChangeSink for NdjsonFileSink {
+    async fn write_batch(&mut self, changes: &[ChangeRecord]) -> Result<()> {
+        let (path, size) = self.active_file().await?;
+        let contents = to_ndjson(changes)?;
+
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .await
+            .with_context(|| format!("No se pudo abrir {}", path.display()))?;
+        file.write_all(&contents).await?;
+        file.flush().await?;
+
+        self.current_file = Some((path, size + contents.len() as u64));
+
+        Ok(())
+    }
+}
+
+/// Destino que guarda cada lote como un objeto NDJSON en el almacenamiento.
+///
+/// La clave se deriva del primer evento del lote, así que reintentar un lote cuya posición no
+/// llegó a guardarse sobrescribe el mismo objeto en lugar de duplicarlo.
+#[derive(Debug)]
+pub struct StorageSink {
+    storage: Arc<dyn Storage>,
+    prefix: String,
+}
+
+impl StorageSink {
+    /// Crea un destino que escribe bajo `prefix` en `storage`.
+    pub fn new(storage: Arc<dyn Storage>, prefix: impl Into<String>) -> Self {
+        Self {
+            storage,
+            prefix: prefix.into(),
+        }
+    }
+}
+
+impl ChangeSink for StorageSink {
+    async fn write_batch(&mut self, changes: &[ChangeRecord]) -> Result<()> {
+        let Some(first) = changes.first() else {
+            return Ok(());
+        };
+        let key = format!(
+            "{}user-changes-{}-{}.ndjson",
+            self.prefix,
+            first.changed_at.format("%Y%m%dT%H%M%S%.3fZ"),
+            first.event_id
+        );
+
+        self.storage
+            .put(&key, to_ndjson(changes)?, "application/x-ndjson")
+            .await
+            .with_context(|| format!("No se pudo guardar el lote de cambios en {key}"))
+    }
+}
+
+/// Replica un lote de eventos del outbox posteriores a la posición de `consumer` y devuelve
+/// cuántos se leyeron.
+pub async fn replicate_once<S: ChangeSink>(
+    database_pool: &SqlitePool,
+    encryption: &EmailEncryption,
+    sink: &mut S,
+    consumer: &str,
+    batch_size: i64,
+) -> Result<usize> {
+    let last_seq: i64 =
+        sqlx::query_scalar("SELECT last_seq FROM outbox_offsets WHERE consumer = ?")
+            .bind(consumer)
+            .fetch_optional(database_pool)
+            .await?
+            .unwrap_or(0);
+
+    let rows = sqlx::query_as::<_, OutboxRow>(
+        "SELECT seq, payload FROM outbox WHERE seq > ? ORDER BY seq LIMIT ?",
+    )
+    .bind(last_seq)
+    .bind(batch_size)
+    .fetch_all(database_pool)
+    .await?;
+
+    let Some(new_last_seq) = rows.last().map(|row| row.seq) else {
+        return Ok(0);
+    };
+
+    let changes = rows
+        .iter()
+        .filter_map(|row| match outbox::decode(&row.payload, encryption) {
+            Ok(envelope) => Some(ChangeRecord::new(row.seq, envelope)),
+            // Como en los demás lectores del outbox, un evento ilegible no bloquea la cola.
+            Err(error) => {
+                warn!(seq = row.seq, ?error, "Evento del outbox ilegible");
+                None
+            }
+        })
+        .collect::<Vec<_>>();
+
+    if !changes.is_empty() {
+        sink.write_batch(&changes).await?;
+    }
+
+    sqlx::query(
+        "INSERT INTO outbox_offsets (consumer, last_seq, updated_at) VALUES (?, ?, ?) \
+         ON CONFLICT (consumer) DO UPDATE SET last_seq = excluded.last_seq, \
+         updated_at = excluded.updated_at",
+    )
+    .bind(consumer)
+    .bind(new_last_seq)
+    .bind(Utc::now())
+    .execute(database_pool)
+    .await?;
+
+    Ok(rows.len())
+}
+
+/// Bucle del conector: replica continuamente los eventos de todas las bases y reintenta con
+/// backoff exponencial ante fallos.
+pub async fn run(state: AppState, config: CdcConfig) {
+    info!(sink = ?config.sink, "Conector CDC iniciado");
+    match &config.sink {
+        CdcSinkConfig::Ndjson {
+            output_dir,
+            max_file_bytes,
+        } => {
+            let sink = NdjsonFileSink::new(output_dir, *max_file_bytes);
+            run_with(state, sink, &config).await
+        }
+        CdcSinkConfig::Storage { prefix } => {
+            let sink = StorageSink::new(state.storage.clone(), prefix.clone());
+            run_with(state, sink, &config).await
+        }
+    }
+}
+
+async fn run_with(state: AppState, mut sink: impl ChangeSink, config: &CdcConfig) {
+    let mut backoff = config.poll_interval;
+
+    loop {
+        let mut has_more = false;
+        let mut failed = false;
+        for database_pool in state.database_pools().await {
+            match replicate_once(
+                &database_pool,
+                &state.email_encryption,
+                &mut sink,
+                CONSUMER,
+                config.batch_size,
+            )
+            .await
+            {
+                Ok(replicated) => has_more |= replicated as i64 == config.batch_size,
+                Err(error) => {
+                    warn!(?error, retry_in = ?backoff, "Fallo al replicar cambios");
+                    failed = true;
+                }
+            }
+        }
+
+        if failed {
+            tokio::time::sleep(backoff).await;
+            backoff = (backoff * 2).min(Duration::from_secs(300));
+            continue;
+        }
+        backoff = config.poll_interval;
+        if !has_more {
+            tokio::time::sleep(config.poll_interval).await;
+        }
+    }
+}
@@ -0,0 +1,180 @@
+//! Modo caos: inyección de fallos para probar la resiliencia de los clientes.
+//!
+//! Con `CHAOS_ENABLED=true`, [`inject_faults`] altera un porcentaje de las peticiones para
+//! ejercitar los reintentos, timeouts y circuit breakers de quien consume la API:
+//!
+//! - `CHAOS_LATENCY_PERCENT` / `CHAOS_LATENCY_MS`: retrasa la petición antes de atenderla.
+//! - `CHAOS_ERROR_PERCENT`: responde `500` sin llegar al handler.
+//! - `CHAOS_DROP_PERCENT`: atiende la petición pero corta la conexión al enviar la respuesta,
+//!   como una caída de red después de que el servidor haya aplicado el cambio.
+//!
+//! Los porcentajes van de `0` a `100` y se sortean por separado en cada petición. Las rutas de
+//! `CHAOS_SKIP_PATHS` (`/health` y `/metrics` por defecto) no se alteran, para que las sondas
+//! no saquen la instancia de servicio. Las respuestas alteradas llevan la cabecera
+//! `x-chaos-fault` con el fallo aplicado.
+//!
+//! Solo está disponible en compilaciones de desarrollo: en `--release` la variable se ignora.
+
+use std::{sync::Arc, time::Duration};
+
+use axum::{
+    body::Body,
+    extract::{Request, State},
+    http::{HeaderValue, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Json,
+};
+use futures_util::stream;
+use rand::Rng;
+use tracing::info;
+
+use crate::config::{env_or, parse_flag};
+
+/// Cabecera que identifica el fallo inyectado en una respuesta.
+pub const CHAOS_FAULT_HEADER: &str = "x-chaos-fault";
+
+/// Configuración del modo caos.
+#[derive(Debug, Clone)]
+pub struct ChaosConfig {
+    /// Porcentaje de peticiones que se retrasan.
+    pub latency_percent: f64,
+    /// Retraso aplicado.
+    pub latency: Duration,
+    /// Porcentaje de peticiones que responden `500`.
+    pub error_percent: f64,
+    /// Porcentaje de respuestas cuya conexión se corta.
+    pub drop_percent: f64,
+    /// Rutas que nunca se alteran.
+    pub skip_paths: Vec<String>,
+}
+
+impl Default for ChaosConfig {
+    fn default() -> Self {
+        Self {
+            latency_percent: 0.0,
+            latency: Duration::from_millis(500),
+            error_percent: 0.0,
+            drop_percent: 0.0,
+            skip_paths: vec!["/health".to_string(), "/metrics".to_string()],
+        }
+    }
+}
+
+impl ChaosConfig {
+    /// Devuelve `None` salvo que `CHAOS_ENABLED` esté activo en una compilación de desarrollo.
+    pub fn from_env() -> Option<Self> {
+        if !cfg!(debug_assertions)
+            || !std::env::var("CHAOS_ENABLED").is_ok_and(|value| parse_flag(&value))
+        {
+            return None;
+        }
+
+        let defaults = Self::default();
+        Some(Self {
+            latency_percent: percent_from_env("CHAOS_LATENCY_PERCENT"),
+            latency: Duration::from_millis(env_or(
+                "CHAOS_LATENCY_MS",
+                defaults.latency.as_millis() as u64,
+            )),
+            error_percent: percent_from_env("CHAOS_ERROR_PERCENT"),
+            drop_percent: percent_from_env("CHAOS_DROP_PERCENT"),
+            skip_paths: std::env::var("CHAOS_SKIP_PATHS")
+                .map(|paths| {
+                    paths
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|path| !path.is_empty())
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or(defaults.skip_paths),
+        })
+    }
+}
+
+/// Fallos sorteados para una petición.
+#[derive(Debug, Default)]
+struct Faults {
+    delay: bool,
+    error: bool,
+    drop: bool,
+}
+
+impl Faults {
+    fn draw(config: &ChaosConfig) -> Self {
+        let mut rng = rand::thread_rng();
+        let mut hit = |percent: f64| rng.gen::<f64>() * 100.0 < percent;
+
+        Self {
+            delay: hit(config.latency_percent),
+            error: hit(config.error_percent),
+            drop: hit(config.drop_percent),
+        }
+    }
+}
+
+/// Middleware que inyecta latencia, errores y cortes de conexión según [`ChaosConfig`].
+pub async fn inject_faults(
+    State(config): State<Arc<ChaosConfig>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let path = request.uri().path();
+    if config.skip_paths.iter().any(|skip| skip == path) {
+        return next.run(request).await;
+    }
+
+    let faults = Faults::draw(&config);
+    let method = request.method().clone();
+    let path = path.to_string();
+
+    if faults.delay {
+        let delay_ms = config.latency.as_millis() as u64;
+        info!(%method, %path, delay_ms, "Caos: latencia inyectada");
+        tokio::time::sleep(config.latency).await;
+    }
+
+    if faults.error {
+        info!(%method, %path, "Caos: error inyectado");
+        let mut response = (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(serde_json::json!({ "message": "Fallo inyectado por el modo caos" })),
+        )
+            .into_response();
+        mark(&mut response, "error");
+        return response;
+    }
+
+    let mut response = next.run(request).await;
+
+    if faults.drop {
+        info!(%method, %path, "Caos: conexión cortada");
+        // Un cuerpo que falla hace que el servidor aborte la conexión sin completar la respuesta.
+        let failing = stream::once(async {
+            Err::<Vec<u8>, _>(std::io::Error::new(
+                std::io::ErrorKind::ConnectionAborted,
+                "conexión cortada por el modo caos",
+            ))
+        });
+        let (mut parts, _) = response.into_parts();
+        parts.headers.remove(axum::http::header::CONTENT_LENGTH);
+        response = Response::from_parts(parts, Body::from_stream(failing));
+        mark(&mut response, "drop");
+    } else if faults.delay {
+        mark(&mut response, "latency");
+    }
+
+    response
+}
+
+fn mark(response: &mut Response, fault: &'static str) {
+    response
+        .headers_mut()
+        .insert(CHAOS_FAULT_HEADER, HeaderValue::from_static(fault));
+}
+
+/// Lee un porcentaje entre `0` y `100`; los valores fuera de rango se recortan.
+fn percent_from_env(name: &str) -> f64 {
+    env_or(name, 0.0_f64).clamp(0.0, 100.0)
+}
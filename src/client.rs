@@ -0,0 +1,248 @@
+//! Cliente tipado de la API (feature `client`).
+//!
+//! Otros servicios en Rust pueden consumir la API con los mismos DTOs que usa el servidor en
+//! lugar de montar las peticiones a mano:
+//!
+//! ```no_run
+//! # async fn demo() -> Result<(), rust_web_demo::client::ClientError> {
+//! use rust_web_demo::client::ApiClient;
+//! use rust_web_demo::models::user::{CreateUser, ListUsersQuery};
+//!
+//! let client = ApiClient::new("http://localhost:3000")?.with_tenant("acme");
+//! let users = client.users();
+//! let ada = users
+//!     .create(&CreateUser {
+//!         name: "Ada".to_string(),
+//!         email: "ada@example.com".to_string(),
+//!     })
+//!     .await?;
+//! let active = users.list(&ListUsersQuery::default()).await?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Las respuestas de error se devuelven como [`ClientError::Api`] con el estado HTTP y el
+//! `message` (y los errores por campo, si los hay) que envía el servidor. El listado de
+//! usuarios no está paginado en el servidor, así que [`UsersClient::list`] recibe los mismos
+//! filtros que `GET /users` y devuelve la lista completa.
+
+use std::fmt;
+
+use reqwest::{header::HeaderMap, Method, RequestBuilder, Response, StatusCode, Url};
+use serde::{de::DeserializeOwned, Deserialize};
+use uuid::Uuid;
+
+use crate::handlers::actor::ACTOR_HEADER;
+use crate::models::user::{CreateUser, ListUsersQuery, UpdateUser, User, UserCount};
+use crate::tenant::TENANT_HEADER;
+
+/// Error de una llamada a la API.
+#[derive(Debug)]
+pub enum ClientError {
+    /// La petición no llegó a completarse (conexión, timeout, cuerpo ilegible...).
+    Transport(reqwest::Error),
+    /// El servidor respondió con un estado de error.
+    Api {
+        status: StatusCode,
+        message: String,
+        errors: Vec<FieldError>,
+    },
+}
+
+impl ClientError {
+    /// Estado HTTP de la respuesta, si llegó a recibirse.
+    pub fn status(&self) -> Option<StatusCode> {
+        match self {
+            Self::Transport(error) => error.status(),
+            Self::Api { status, .. } => Some(*status),
+        }
+    }
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Transport(error) => write!(f, "No se pudo completar la petición: {error}"),
+            Self::Api {
+                status, message, ..
+            } => write!(f, "La API respondió {status}: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Transport(error) => Some(error),
+            Self::Api { .. } => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for ClientError {
+    fn from(error: reqwest::Error) -> Self {
+        Self::Transport(error)
+    }
+}
+
+/// Error de validación de un campo, tal como lo describe el servidor.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+    pub code: Option<String>,
+}
+
+/// Cuerpo de las respuestas de error de la API.
+#[derive(Debug, Deserialize)]
+struct ErrorBody {
+    message: String,
+    #[serde(default)]
+    errors: Vec<FieldError>,
+}
+
+/// Cliente HTTP de la API; es barato de clonar y sus copias comparten las conexiones.
+#[derive(Debug, Clone)]
+pub struct ApiClient {
+    http: reqwest::Client,
+    base_url: Url,
+    headers: HeaderMap,
+}
+
+impl ApiClient {
+    /// Crea un cliente contra `base_url` (por ejemplo, `http://localhost:3000`).
+    pub fn new(base_url: &str) -> Result<Self, ClientError> {
+        Ok(Self::with_http_client(
+            reqwest::Client::builder().build()?,
+            base_url,
+        ))
+    }
+
+    /// Crea un cliente que reutiliza `http`, con sus timeouts y ajustes de TLS.
+    ///
+    /// # Panics
+    ///
+    /// Si `base_url` no es una URL válida.
+    pub fn with_http_client(http: reqwest::Client, base_url: &str) -> Self {
+        let mut base_url = Url::parse(base_url).expect("URL base del cliente inválida");
+        // Sin la barra final, `join` sustituiría el último segmento de la ruta base.
+        if !base_url.path().ends_with('/') {
+            base_url.set_path(&format!("{}/", base_url.path()));
+        }
+
+        Self {
+            http,
+            base_url,
+            headers: HeaderMap::new(),
+        }
+    }
+
+    /// Envía las peticiones en nombre del inquilino `tenant` (cabecera `X-Tenant-Id`).
+    pub fn with_tenant(self, tenant: &str) -> Self {
+        self.with_header(TENANT_HEADER, tenant)
+    }
+
+    /// Identifica al usuario que actúa en las peticiones (cabecera `X-User-Id`).
+    pub fn acting_as(self, user_id: Uuid) -> Self {
+        self.with_header(ACTOR_HEADER, &user_id.to_string())
+    }
+
+    /// Operaciones sobre `/users`.
+    ///
+    /// Los identificadores se envían en su forma canónica de UUID, que el servidor acepta sea
+    /// cual sea el formato público configurado.
+    pub fn users(&self) -> UsersClient<'_> {
+        UsersClient { client: self }
+    }
+
+    fn with_header(mut self, name: &'static str, value: &str) -> Self {
+        let value = value.parse().expect("valor de cabecera inválido");
+        self.headers.insert(name, value);
+        self
+    }
+
+    fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let url = self
+            .base_url
+            .join(path.trim_start_matches('/'))
+            .expect("ruta de la API inválida");
+        self.http.request(method, url).headers(self.headers.clone())
+    }
+}
+
+/// Cliente de las operaciones sobre usuarios de la API v1.
+#[derive(Debug, Clone, Copy)]
+pub struct UsersClient<'a> {
+    client: &'a ApiClient,
+}
+
+impl UsersClient<'_> {
+    /// `POST /users`.
+    pub async fn create(&self, user: &CreateUser) -> Result<User, ClientError> {
+        let request = self.client.request(Method::POST, "users").json(user);
+        json(request).await
+    }
+
+    /// `GET /users` con los filtros de `query`.
+    pub async fn list(&self, query: &ListUsersQuery) -> Result<Vec<User>, ClientError> {
+        let request = self.client.request(Method::GET, "users").query(query);
+        json(request).await
+    }
+
+    /// `GET /users/:id`.
+    pub async fn get(&self, id: Uuid) -> Result<User, ClientError> {
+        let request = self.client.request(Method::GET, &user_path(id));
+        json(request).await
+    }
+
+    /// `PUT /users/:id`; solo se envían los campos presentes en `changes`.
+    pub async fn update(&self, id: Uuid, changes: &UpdateUser) -> Result<User, ClientError> {
+        let request = self
+            .client
+            .request(Method::PUT, &user_path(id))
+            .json(changes);
+        json(request).await
+    }
+
+    /// `DELETE /users/:id`.
+    pub async fn delete(&self, id: Uuid) -> Result<(), ClientError> {
+        send(self.client.request(Method::DELETE, &user_path(id))).await?;
+        Ok(())
+    }
+
+    /// `GET /users/count`.
+    pub async fn count(&self) -> Result<i64, ClientError> {
+        let request = self.client.request(Method::GET, "users/count");
+        let UserCount { count } = json(request).await?;
+        Ok(count)
+    }
+}
+
+fn user_path(id: Uuid) -> String {
+    format!("users/{id}")
+}
+
+/// Envía la petición y convierte las respuestas de error en [`ClientError::Api`].
+async fn send(request: RequestBuilder) -> Result<Response, ClientError> {
+    let response = request.send().await?;
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let body = response.bytes().await?;
+    let ErrorBody { message, errors } =
+        serde_json::from_slice(&body).unwrap_or_else(|_| ErrorBody {
+            message: String::from_utf8_lossy(&body).into_owned(),
+            errors: Vec::new(),
+        });
+    Err(ClientError::Api {
+        status,
+        message,
+        errors,
+    })
+}
+
+async fn json<T: DeserializeOwned>(request: RequestBuilder) -> Result<T, ClientError> {
+    Ok(send(request).await?.json().await?)
+}
@@ -0,0 +1,121 @@
+//! Dirección IP real del cliente.
+//!
+//! Detrás de un balanceador o un CDN la conexión llega desde el proxy, y la IP del cliente
+//! viaja en `X-Forwarded-For`, donde cada proxy añade por la derecha la dirección de quien le
+//! habló. Esa cabecera la puede escribir cualquiera, así que solo se confía en los
+//! `TRUSTED_PROXY_HOPS` saltos más próximos al servidor: con `0` (por defecto) se ignora y la
+//! IP es la del par de la conexión; con `1`, la última entrada de la cabecera, y así
+//! sucesivamente.
+//!
+//! [`resolve_client_ip`] la calcula una vez por petición y la deja en las extensiones como
+//! [`ClientIp`], de donde la leen el log de accesos y cualquier handler que la necesite, en
+//! lugar de interpretar la cabecera cada uno por su cuenta.
+
+use std::{
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+};
+
+use anyhow::anyhow;
+use axum::{
+    async_trait,
+    extract::{ConnectInfo, FromRequestParts, Request, State},
+    http::{request::Parts, HeaderMap},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::config::env_or;
+use crate::handlers::error::AppError;
+
+/// Cabecera con la cadena de direcciones por las que pasó la petición.
+pub const FORWARDED_FOR_HEADER: &str = "x-forwarded-for";
+
+/// Proxies de confianza entre el cliente y el servidor.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TrustedProxies {
+    /// Saltos de `X-Forwarded-For`, contados desde el servidor, que añadieron proxies propios.
+    pub hops: usize,
+}
+
+impl TrustedProxies {
+    /// Lee `TRUSTED_PROXY_HOPS`.
+    pub fn from_env() -> Self {
+        Self {
+            hops: env_or("TRUSTED_PROXY_HOPS", Self::default().hops),
+        }
+    }
+
+    /// IP del cliente a partir de la dirección del par y de las cabeceras.
+    ///
+    /// Si la cabecera trae menos saltos de los configurados se toma la entrada más lejana,
+    /// que es la que escribió el primer proxy propio. Si la entrada elegida no es una IP se
+    /// devuelve `None` en lugar de atribuir la petición a otra dirección.
+    pub fn resolve(&self, peer: Option<IpAddr>, headers: &HeaderMap) -> Option<IpAddr> {
+        if self.hops == 0 {
+            return peer;
+        }
+
+        let mut chain: Vec<Option<IpAddr>> = headers
+            .get_all(FORWARDED_FOR_HEADER)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .map(|entry| parse_entry(entry.trim()))
+            .collect();
+        chain.push(peer);
+
+        let index = chain.len().saturating_sub(self.hops + 1);
+        chain[index]
+    }
+}
+
+/// Interpreta una entrada de `X-Forwarded-For`, con o sin puerto.
+fn parse_entry(entry: &str) -> Option<IpAddr> {
+    entry
+        .parse()
+        .ok()
+        .or_else(|| entry.parse::<SocketAddr>().ok().map(|address| address.ip()))
+}
+
+/// IP del cliente que hizo la petición.
+///
+/// Responde `500` si no se pudo determinar; los handlers a los que les baste con saberla
+/// cuando exista pueden pedir `Option<ClientIp>`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClientIp(pub IpAddr);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for ClientIp
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<ClientIp>()
+            .copied()
+            .ok_or_else(|| AppError::internal(anyhow!("No se pudo determinar la IP del cliente")))
+    }
+}
+
+/// Middleware que resuelve la IP del cliente y la guarda en las extensiones de la petición.
+///
+/// La dirección del par sale de `ConnectInfo<SocketAddr>`, así que el servidor debe
+/// arrancarse con `into_make_service_with_connect_info`.
+pub async fn resolve_client_ip(
+    State(proxies): State<Arc<TrustedProxies>>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let peer = connect_info.map(|ConnectInfo(address)| address.ip());
+
+    if let Some(ip) = proxies.resolve(peer, request.headers()) {
+        request.extensions_mut().insert(ClientIp(ip));
+    }
+
+    next.run(request).await
+}
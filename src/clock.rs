@@ -0,0 +1,60 @@
+//! Fuente de la hora actual.
+//!
+//! Los handlers toman la hora del [`Clock`] del estado de la aplicación en lugar de llamar
+//! directamente a `Utc::now()`. En producción es [`SystemClock`]; las pruebas pueden montar un
+//! [`MockClock`] para fijar la hora o avanzarla a voluntad y comprobar marcas como
+//! `created_at` sin depender del reloj de la máquina.
+
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use chrono::{DateTime, Duration, Utc};
+
+/// Origen de la hora actual.
+pub trait Clock: fmt::Debug + Send + Sync + 'static {
+    /// Hora actual en UTC.
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// Reloj del sistema.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// Reloj que solo avanza cuando se le indica; sus copias comparten la misma hora.
+#[derive(Debug, Clone)]
+pub struct MockClock {
+    now: Arc<Mutex<DateTime<Utc>>>,
+}
+
+impl MockClock {
+    /// Crea un reloj detenido en `now`.
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            now: Arc::new(Mutex::new(now)),
+        }
+    }
+
+    /// Fija la hora actual.
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.now.lock().expect("mutex del reloj envenenado") = now;
+    }
+
+    /// Adelanta la hora actual en `duration`.
+    pub fn advance(&self, duration: Duration) {
+        *self.now.lock().expect("mutex del reloj envenenado") += duration;
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().expect("mutex del reloj envenenado")
+    }
+}
@@ -0,0 +1,390 @@
+//! Configuración de la aplicación.
+//!
+//! Centraliza la lectura de variables de entorno en una estructura tipada que se comparte
+//! con los handlers a través del estado de Axum.
+//!
+//! `APP_ENV` elige el perfil (`development` por defecto, `test` o `production`), que decide
+//! los valores por defecto de las variables no definidas: en `test` la base es SQLite en
+//! memoria con una única conexión, y en `production` los logs son JSON y el modo caos no se
+//! puede activar. Al arrancar se carga `.env.<perfil>` antes que `.env`, de modo que sus
+//! valores tienen prioridad sobre los comunes y los del entorno del proceso sobre ambos.
+
+use std::{env, fmt, path::PathBuf, str::FromStr, time::Duration};
+
+use serde::Serialize;
+use sqlx::sqlite::{SqliteJournalMode, SqliteSynchronous};
+
+use crate::ids::{self, IdFormat};
+
+/// Configuración global cargada al arrancar.
+#[derive(Debug, Clone)]
+pub struct AppConfig {
+    /// Perfil de ejecución (`APP_ENV`).
+    pub environment: Environment,
+    /// Cadena de conexión a la base de datos (`DATABASE_URL`).
+    pub database_url: String,
+    /// Aplica las migraciones pendientes al arrancar el servidor (`RUN_MIGRATIONS`). Con
+    /// `false` se espera que un paso previo del despliegue las aplique con `--migrate-only`.
+    pub run_migrations: bool,
+    /// Intentos de conexión a la base de datos al arrancar (`DATABASE_CONNECT_ATTEMPTS`).
+    pub database_connect_attempts: u32,
+    /// Espera antes del primer reintento de conexión; se duplica en cada intento
+    /// (`DATABASE_CONNECT_BACKOFF_MS`).
+    pub database_connect_backoff: Duration,
+    /// Conexiones máximas de cada pool (`DATABASE_MAX_CONNECTIONS`).
+    pub database_max_connections: u32,
+    /// Conexiones que cada pool mantiene abiertas aunque estén ociosas
+    /// (`DATABASE_MIN_CONNECTIONS`).
+    pub database_min_connections: u32,
+    /// Espera máxima para obtener una conexión del pool (`DATABASE_ACQUIRE_TIMEOUT_SECS`).
+    pub database_acquire_timeout: Duration,
+    /// Tiempo tras el que se cierra una conexión ociosa (`DATABASE_IDLE_TIMEOUT_SECS`; `0`
+    /// la mantiene abierta).
+    pub database_idle_timeout: Option<Duration>,
+    /// Vida máxima de una conexión (`DATABASE_MAX_LIFETIME_SECS`; `0` sin límite).
+    pub database_max_lifetime: Option<Duration>,
+    /// Modo de diario de SQLite (`SQLITE_JOURNAL_MODE`).
+    pub sqlite_journal_mode: SqliteJournalMode,
+    /// Nivel de sincronización con el disco (`SQLITE_SYNCHRONOUS`).
+    pub sqlite_synchronous: SqliteSynchronous,
+    /// Tiempo que una conexión espera a que se libere un bloqueo (`SQLITE_BUSY_TIMEOUT_MS`).
+    pub sqlite_busy_timeout: Duration,
+    /// Comprueba las claves foráneas (`SQLITE_FOREIGN_KEYS`).
+    pub sqlite_foreign_keys: bool,
+    /// Veces que se repite una petición que encontró la base ocupada
+    /// (`SQLITE_BUSY_RETRIES`); agotadas, se responde `503`.
+    pub sqlite_busy_retries: u32,
+    /// Duración a partir de la que una sentencia se registra como lenta, con un aviso
+    /// (`SLOW_QUERY_THRESHOLD_MS`).
+    pub slow_query_threshold: Duration,
+    /// Espera a partir de la que un trabajo vencido sin recoger deja la cola como `degraded`
+    /// en `/health/ready` (`JOB_QUEUE_MAX_AGE_SECS`).
+    pub job_queue_max_age: Duration,
+    /// Dirección en la que escucha el servidor (`HOST`).
+    pub host: String,
+    /// Puerto en el que escucha el servidor (`PORT`).
+    pub port: u16,
+    /// Dirección del puerto de administración (`ADMIN_HOST`).
+    pub admin_host: String,
+    /// Puerto propio para la administración, las métricas y el perfilado (`ADMIN_PORT`); sin
+    /// él se sirven en el puerto público.
+    pub admin_port: Option<u16>,
+    /// Permite que `PUT /users/:id` cree el usuario si no existe (`ALLOW_PUT_UPSERT`).
+    pub allow_put_upsert: bool,
+    /// Arranca en modo de mantenimiento, con la API en solo lectura (`MAINTENANCE_MODE`).
+    pub maintenance_mode: bool,
+    /// Sirve `index.html` en las rutas de `/public` sin archivo, para alojar una SPA con
+    /// enrutado en el cliente (`PUBLIC_SPA_FALLBACK`).
+    pub public_spa_fallback: bool,
+    /// Dominio base para resolver el inquilino desde el subdominio (`TENANT_BASE_DOMAIN`).
+    pub tenant_base_domain: Option<String>,
+    /// Directorio con una base SQLite por inquilino (`TENANT_DATABASE_DIR`); sin él, todos
+    /// los inquilinos comparten la base principal.
+    pub tenant_database_dir: Option<PathBuf>,
+    /// Directorio en el que se escriben las copias de seguridad (`BACKUP_DIR`).
+    pub backup_dir: PathBuf,
+    /// Sube cada copia al almacenamiento configurado en lugar de dejarla en `backup_dir`
+    /// (`BACKUP_TO_STORAGE`).
+    pub backup_to_storage: bool,
+    /// Clave con la que se firman los enlaces de descarga de las exportaciones
+    /// (`EXPORT_URL_KEY`); sin ella se genera una al arrancar, válida solo para esa instancia.
+    pub export_url_key: Option<String>,
+    /// Validez de los enlaces de descarga de las exportaciones (`EXPORT_URL_TTL_SECS`).
+    pub export_url_ttl: Duration,
+    /// Archivo con dominios de correo que se bloquean al arrancar, uno por línea
+    /// (`BLOCKED_EMAIL_DOMAINS_FILE`).
+    pub blocked_email_domains_file: Option<PathBuf>,
+    /// Clave con la que se cifran los correos en reposo, 32 bytes en hexadecimal
+    /// (`EMAIL_ENCRYPTION_KEY`); sin ella se guardan en claro.
+    pub email_encryption_key: Option<String>,
+    /// Claves anteriores, separadas por comas, con las que aún se descifran los correos que no
+    /// se han vuelto a cifrar (`EMAIL_ENCRYPTION_PREVIOUS_KEYS`).
+    pub email_encryption_previous_keys: Vec<String>,
+    /// Clave del índice ciego con el que se buscan los correos cifrados
+    /// (`EMAIL_BLIND_INDEX_KEY`).
+    pub email_blind_index_key: Option<String>,
+    /// Formato de los identificadores de usuario nuevos (`ID_FORMAT`).
+    pub id_format: IdFormat,
+    /// Prefijo de los identificadores de usuario, como `usr` (`ID_PREFIX`).
+    pub id_prefix: Option<String>,
+}
+
+impl AppConfig {
+    /// Construye la configuración a partir de las variables de entorno, aplicando valores
+    /// por defecto cuando no están definidas.
+    pub fn from_env() -> Self {
+        let environment = Environment::from_env().unwrap_or_default();
+        let defaults = Self::defaults_for(environment);
+
+        Self {
+            environment,
+            database_url: env::var("DATABASE_URL").unwrap_or(defaults.database_url),
+            run_migrations: env::var("RUN_MIGRATIONS")
+                .ok()
+                .map(|value| parse_flag(&value))
+                .unwrap_or(defaults.run_migrations),
+            database_connect_attempts: env_or(
+                "DATABASE_CONNECT_ATTEMPTS",
+                defaults.database_connect_attempts,
+            )
+            .max(1),
+            database_connect_backoff: Duration::from_millis(env_or(
+                "DATABASE_CONNECT_BACKOFF_MS",
+                defaults.database_connect_backoff.as_millis() as u64,
+            )),
+            database_max_connections: env_or(
+                "DATABASE_MAX_CONNECTIONS",
+                defaults.database_max_connections,
+            )
+            .max(1),
+            database_min_connections: env_or(
+                "DATABASE_MIN_CONNECTIONS",
+                defaults.database_min_connections,
+            ),
+            database_acquire_timeout: Duration::from_secs(env_or(
+                "DATABASE_ACQUIRE_TIMEOUT_SECS",
+                defaults.database_acquire_timeout.as_secs(),
+            )),
+            database_idle_timeout: optional_secs(
+                "DATABASE_IDLE_TIMEOUT_SECS",
+                defaults.database_idle_timeout,
+            ),
+            database_max_lifetime: optional_secs(
+                "DATABASE_MAX_LIFETIME_SECS",
+                defaults.database_max_lifetime,
+            ),
+            sqlite_journal_mode: env_or("SQLITE_JOURNAL_MODE", defaults.sqlite_journal_mode),
+            sqlite_synchronous: env_or("SQLITE_SYNCHRONOUS", defaults.sqlite_synchronous),
+            sqlite_busy_timeout: Duration::from_millis(env_or(
+                "SQLITE_BUSY_TIMEOUT_MS",
+                defaults.sqlite_busy_timeout.as_millis() as u64,
+            )),
+            sqlite_foreign_keys: env::var("SQLITE_FOREIGN_KEYS")
+                .ok()
+                .map(|value| parse_flag(&value))
+                .unwrap_or(defaults.sqlite_foreign_keys),
+            sqlite_busy_retries: env_or("SQLITE_BUSY_RETRIES", defaults.sqlite_busy_retries),
+            slow_query_threshold: Duration::from_millis(env_or(
+                "SLOW_QUERY_THRESHOLD_MS",
+                defaults.slow_query_threshold.as_millis() as u64,
+            )),
+            job_queue_max_age: Duration::from_secs(env_or(
+                "JOB_QUEUE_MAX_AGE_SECS",
+                defaults.job_queue_max_age.as_secs(),
+            )),
+            host: env::var("HOST").unwrap_or(defaults.host),
+            port: env::var("PORT")
+                .ok()
+                .and_then(|value| value.parse::<u16>().ok())
+                .unwrap_or(defaults.port),
+            admin_host: env::var("ADMIN_HOST").unwrap_or(defaults.admin_host),
+            admin_port: env::var("ADMIN_PORT")
+                .ok()
+                .and_then(|value| value.parse::<u16>().ok()),
+            allow_put_upsert: env::var("ALLOW_PUT_UPSERT")
+                .ok()
+                .map(|value| parse_flag(&value))
+                .unwrap_or(defaults.allow_put_upsert),
+            maintenance_mode: env::var("MAINTENANCE_MODE")
+                .ok()
+                .map(|value| parse_flag(&value))
+                .unwrap_or(defaults.maintenance_mode),
+            public_spa_fallback: env::var("PUBLIC_SPA_FALLBACK")
+                .ok()
+                .map(|value| parse_flag(&value))
+                .unwrap_or(defaults.public_spa_fallback),
+            tenant_base_domain: env::var("TENANT_BASE_DOMAIN")
+                .ok()
+                .map(|value| value.trim().trim_start_matches('.').to_ascii_lowercase())
+                .filter(|value| !value.is_empty()),
+            tenant_database_dir: env::var("TENANT_DATABASE_DIR")
+                .ok()
+                .filter(|value| !value.trim().is_empty())
+                .map(PathBuf::from),
+            backup_dir: env::var("BACKUP_DIR")
+                .ok()
+                .filter(|value| !value.trim().is_empty())
+                .map(PathBuf::from)
+                .unwrap_or(defaults.backup_dir),
+            backup_to_storage: env::var("BACKUP_TO_STORAGE")
+                .ok()
+                .map(|value| parse_flag(&value))
+                .unwrap_or(defaults.backup_to_storage),
+            export_url_key: env::var("EXPORT_URL_KEY")
+                .ok()
+                .filter(|value| !value.trim().is_empty()),
+            export_url_ttl: Duration::from_secs(env_or(
+                "EXPORT_URL_TTL_SECS",
+                defaults.export_url_ttl.as_secs(),
+            )),
+            blocked_email_domains_file: env::var("BLOCKED_EMAIL_DOMAINS_FILE")
+                .ok()
+                .filter(|value| !value.trim().is_empty())
+                .map(PathBuf::from),
+            email_encryption_key: env::var("EMAIL_ENCRYPTION_KEY")
+                .ok()
+                .filter(|value| !value.trim().is_empty()),
+            email_encryption_previous_keys: env::var("EMAIL_ENCRYPTION_PREVIOUS_KEYS")
+                .map(|value| {
+                    value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|key| !key.is_empty())
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default(),
+            email_blind_index_key: env::var("EMAIL_BLIND_INDEX_KEY")
+                .ok()
+                .filter(|value| !value.trim().is_empty()),
+            id_format: env_or("ID_FORMAT", defaults.id_format),
+            id_prefix: env::var("ID_PREFIX")
+                .ok()
+                .map(|value| value.trim().to_ascii_lowercase())
+                .filter(|value| ids::is_valid_prefix(value)),
+        }
+    }
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self::defaults_for(Environment::default())
+    }
+}
+
+impl AppConfig {
+    /// Valores por defecto del perfil `environment`.
+    pub fn defaults_for(environment: Environment) -> Self {
+        let common = Self {
+            environment,
+            database_url: "sqlite://db.sqlite".to_string(),
+            run_migrations: true,
+            database_connect_attempts: 5,
+            database_connect_backoff: Duration::from_millis(500),
+            database_max_connections: 10,
+            database_min_connections: 0,
+            database_acquire_timeout: Duration::from_secs(30),
+            database_idle_timeout: Some(Duration::from_secs(600)),
+            database_max_lifetime: Some(Duration::from_secs(1800)),
+            sqlite_journal_mode: SqliteJournalMode::Wal,
+            // Con WAL, `NORMAL` solo puede perder las últimas transacciones ante un corte de
+            // luz, nunca corromper la base.
+            sqlite_synchronous: SqliteSynchronous::Normal,
+            sqlite_busy_timeout: Duration::from_secs(5),
+            sqlite_foreign_keys: true,
+            sqlite_busy_retries: 3,
+            slow_query_threshold: Duration::from_millis(500),
+            job_queue_max_age: Duration::from_secs(300),
+            host: "0.0.0.0".to_string(),
+            port: 3000,
+            admin_host: "127.0.0.1".to_string(),
+            admin_port: None,
+            allow_put_upsert: false,
+            maintenance_mode: false,
+            public_spa_fallback: false,
+            tenant_base_domain: None,
+            tenant_database_dir: None,
+            backup_dir: PathBuf::from("backups"),
+            backup_to_storage: false,
+            export_url_key: None,
+            export_url_ttl: Duration::from_secs(900),
+            blocked_email_domains_file: None,
+            email_encryption_key: None,
+            email_encryption_previous_keys: Vec::new(),
+            email_blind_index_key: None,
+            id_format: IdFormat::default(),
+            id_prefix: None,
+        };
+
+        match environment {
+            Environment::Development | Environment::Production => common,
+            // Cada conexión a `sqlite::memory:` abre una base distinta, así que el pool se
+            // limita a una que nunca se cierra.
+            Environment::Test => Self {
+                database_url: "sqlite::memory:".to_string(),
+                database_connect_attempts: 1,
+                database_max_connections: 1,
+                database_min_connections: 1,
+                database_idle_timeout: None,
+                database_max_lifetime: None,
+                ..common
+            },
+        }
+    }
+}
+
+/// Perfil de ejecución elegido con `APP_ENV`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Environment {
+    #[default]
+    Development,
+    Test,
+    Production,
+}
+
+impl Environment {
+    /// Lee `APP_ENV`; sin ella, `development`. Falla si el valor no es un perfil conocido.
+    pub fn from_env() -> Result<Self, String> {
+        match env::var("APP_ENV") {
+            Ok(value) if !value.trim().is_empty() => value.parse(),
+            _ => Ok(Self::default()),
+        }
+    }
+
+    /// Nombre del perfil, tal como aparece en `.env.<perfil>` y en `/version`.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Development => "development",
+            Self::Test => "test",
+            Self::Production => "production",
+        }
+    }
+}
+
+impl FromStr for Environment {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "development" | "dev" => Ok(Self::Development),
+            "test" => Ok(Self::Test),
+            "production" | "prod" => Ok(Self::Production),
+            other => Err(format!("Perfil de APP_ENV desconocido: {other}")),
+        }
+    }
+}
+
+impl fmt::Display for Environment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Lee una variable de entorno numérica o devuelve el valor por defecto.
+pub(crate) fn env_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    env::var(name)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
+
+/// Lee una duración en segundos en la que `0` significa "sin límite".
+fn optional_secs(name: &str, default: Option<Duration>) -> Option<Duration> {
+    match env::var(name)
+        .ok()
+        .and_then(|value| value.parse::<u64>().ok())
+    {
+        Some(0) => None,
+        Some(seconds) => Some(Duration::from_secs(seconds)),
+        None => default,
+    }
+}
+
+/// Interpreta valores booleanos habituales en variables de entorno.
+pub(crate) fn parse_flag(value: &str) -> bool {
+    matches!(
+        value.trim().to_ascii_lowercase().as_str(),
+        "1" | "true" | "yes" | "on"
+    )
+}
@@ -0,0 +1,206 @@
+//! Opciones de conexión a SQLite.
+//!
+//! Todas las conexiones, las de la base principal y las de las bases de inquilinos, se abren
+//! con los pragmas de [`AppConfig`]. Los valores por defecto de SQLite (diario `DELETE`, sin
+//! espera ante un bloqueo) provocan errores `database is locked` en cuanto dos peticiones
+//! escriben a la vez; con WAL los lectores no bloquean al escritor y `busy_timeout` hace que
+//! una escritura espere su turno en lugar de fallar.
+//!
+//! Si aun así la base sigue ocupada, el error se traduce en `503` con `Retry-After` y el
+//! middleware [`retry_when_busy`] repite la petición completa con backoff exponencial: la
+//! transacción fallida ya se deshizo, así que repetirla no duplica cambios.
+//!
+//! Las sentencias que tardan más de `SLOW_QUERY_THRESHOLD_MS` se registran con un aviso de sqlx
+//! (target `sqlx::query`) que incluye el SQL, sin los valores vinculados. Las consultas de los
+//! handlers se ejecutan con [`TracedQuery::traced`] dentro de un span `db.query` con el nombre
+//! de la sentencia y el identificador que reciben, así que el aviso indica también de qué
+//! operación y de qué registro se trataba.
+
+use std::{
+    future::Future,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result};
+use axum::{
+    body::{self, Body},
+    extract::{Request, State},
+    http::header,
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use sqlx::{
+    sqlite::{SqliteConnectOptions, SqlitePoolOptions},
+    ConnectOptions, SqlitePool,
+};
+use tracing::{field, info_span, warn, Instrument};
+use uuid::Uuid;
+
+use crate::config::AppConfig;
+use crate::handlers::error::AppError;
+use crate::metrics::Metrics;
+
+/// Código primario de SQLite para una base ocupada por otra conexión.
+const SQLITE_BUSY: i32 = 5;
+/// Código primario de SQLite para una tabla bloqueada dentro de la misma conexión.
+const SQLITE_LOCKED: i32 = 6;
+/// Espera antes del primer reintento de una petición que encontró la base ocupada.
+const BUSY_RETRY_BASE_DELAY: Duration = Duration::from_millis(50);
+/// Tamaño máximo de cuerpo que se guarda para poder repetir la petición.
+const MAX_RETRY_BODY_BYTES: usize = 2 * 1024 * 1024;
+/// Intervalo entre dos mediciones de la latencia de obtención de conexiones.
+const POOL_PROBE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Opciones de conexión de la base principal (`DATABASE_URL`) con los pragmas configurados.
+pub fn connect_options(config: &AppConfig) -> Result<SqliteConnectOptions> {
+    let options = config
+        .database_url
+        .parse::<SqliteConnectOptions>()
+        .with_context(|| format!("DATABASE_URL inválida: {}", config.database_url))?;
+    Ok(with_pragmas(options, config))
+}
+
+/// Aplica a `options` los pragmas configurados; SQLite los ejecuta en cada conexión nueva
+/// del pool.
+pub fn with_pragmas(options: SqliteConnectOptions, config: &AppConfig) -> SqliteConnectOptions {
+    options
+        .journal_mode(config.sqlite_journal_mode)
+        .synchronous(config.sqlite_synchronous)
+        .busy_timeout(config.sqlite_busy_timeout)
+        .foreign_keys(config.sqlite_foreign_keys)
+        .log_slow_statements(log::LevelFilter::Warn, config.slow_query_threshold)
+}
+
+/// Opciones de pool configuradas, comunes a la base principal y a las de los inquilinos.
+pub fn pool_options(config: &AppConfig) -> SqlitePoolOptions {
+    SqlitePoolOptions::new()
+        .max_connections(config.database_max_connections)
+        .min_connections(config.database_min_connections)
+        .acquire_timeout(config.database_acquire_timeout)
+        .idle_timeout(config.database_idle_timeout)
+        .max_lifetime(config.database_max_lifetime)
+}
+
+/// Ejecución de consultas dentro de un span `db.query`.
+pub trait TracedQuery: Future + Send + Sized {
+    /// Ejecuta la consulta en un span con el nombre de la sentencia (`statement`, como
+    /// `users.get`) y el identificador vinculado, si lo hay, y anota en él su duración en
+    /// `duration_ms`. Nunca se registran otros valores, que pueden ser datos personales.
+    fn traced(
+        self,
+        statement: &'static str,
+        id: Option<Uuid>,
+    ) -> impl Future<Output = Self::Output> + Send;
+}
+
+impl<F: Future + Send> TracedQuery for F {
+    fn traced(
+        self,
+        statement: &'static str,
+        id: Option<Uuid>,
+    ) -> impl Future<Output = Self::Output> + Send {
+        let span = info_span!(
+            "db.query",
+            statement,
+            id = field::Empty,
+            duration_ms = field::Empty
+        );
+        if let Some(id) = id {
+            span.record("id", field::display(id));
+        }
+
+        async move {
+            let started_at = Instant::now();
+            let output = self.instrument(span.clone()).await;
+            span.record("duration_ms", started_at.elapsed().as_secs_f64() * 1000.0);
+            output
+        }
+    }
+}
+
+/// Mide periódicamente cuánto tarda en obtenerse una conexión de `pool`, como lo haría una
+/// petición en ese momento, y lo registra en las métricas.
+pub async fn probe_pool(pool: SqlitePool, metrics: Metrics) {
+    let mut interval = tokio::time::interval(POOL_PROBE_INTERVAL);
+    loop {
+        interval.tick().await;
+
+        let started_at = Instant::now();
+        match pool.acquire().await {
+            Ok(connection) => {
+                metrics.record_pool_acquire(started_at.elapsed(), false);
+                drop(connection);
+            }
+            Err(sqlx::Error::PoolTimedOut) => {
+                warn!("Se agotó la espera de una conexión del pool");
+                metrics.record_pool_acquire(started_at.elapsed(), true);
+            }
+            Err(sqlx::Error::PoolClosed) => return,
+            Err(error) => warn!(?error, "No se pudo medir el pool de conexiones"),
+        }
+    }
+}
+
+/// Indica si `error` es un `SQLITE_BUSY` o `SQLITE_LOCKED`, incluidos sus códigos extendidos.
+pub fn is_busy(error: &sqlx::Error) -> bool {
+    error
+        .as_database_error()
+        .and_then(|database_error| database_error.code())
+        .and_then(|code| code.parse::<i32>().ok())
+        .is_some_and(|code| matches!(code & 0xff, SQLITE_BUSY | SQLITE_LOCKED))
+}
+
+/// Marca que llevan las respuestas `503` causadas por una base ocupada.
+#[derive(Debug, Clone, Copy)]
+pub struct DatabaseBusy;
+
+/// Middleware que repite hasta `SQLITE_BUSY_RETRIES` veces las peticiones cuya respuesta
+/// indica que la base estaba ocupada. Las peticiones con cuerpo en streaming o mayor de
+/// 2 MiB se ejecutan una sola vez.
+pub async fn retry_when_busy(
+    State(config): State<Arc<AppConfig>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let retries = config.sqlite_busy_retries;
+    let headers = request.headers();
+    let body_is_replayable = match headers.get(header::CONTENT_LENGTH) {
+        Some(length) => length
+            .to_str()
+            .ok()
+            .and_then(|length| length.parse::<usize>().ok())
+            .is_some_and(|length| length <= MAX_RETRY_BODY_BYTES),
+        // Sin `Content-Length` ni `Transfer-Encoding` la petición no tiene cuerpo.
+        None => !headers.contains_key(header::TRANSFER_ENCODING),
+    };
+    if retries == 0 || !body_is_replayable {
+        return next.run(request).await;
+    }
+
+    let (parts, request_body) = request.into_parts();
+    let Ok(bytes) = body::to_bytes(request_body, MAX_RETRY_BODY_BYTES).await else {
+        return AppError::bad_request("No se pudo leer el cuerpo de la petición").into_response();
+    };
+
+    let mut delay = BUSY_RETRY_BASE_DELAY;
+    let mut attempt = 0;
+    loop {
+        let request = Request::from_parts(parts.clone(), Body::from(bytes.clone()));
+        let response = next.clone().run(request).await;
+        if response.extensions().get::<DatabaseBusy>().is_none() || attempt == retries {
+            return response;
+        }
+
+        attempt += 1;
+        warn!(
+            method = %parts.method,
+            uri = %parts.uri,
+            attempt,
+            retry_in = ?delay,
+            "Base de datos ocupada, se repite la petición"
+        );
+        tokio::time::sleep(delay).await;
+        delay *= 2;
+    }
+}
@@ -0,0 +1,55 @@
+//! Diagnóstico del runtime de Tokio.
+//!
+//! Las tareas de larga duración (servidor, worker de trabajos, programador, relay del outbox,
+//! replicación...) se lanzan con [`spawn`], que les pone nombre para distinguirlas en
+//! `tokio-console`. Los nombres solo existen en compilaciones con `--cfg tokio_unstable`; en
+//! las demás [`spawn`] equivale a `tokio::spawn`.
+//!
+//! La feature `tokio-console` añade el suscriptor de `console-subscriber`, que publica el
+//! estado de cada tarea (tiempo ocupada, en espera, despertares) para ver tareas que acaparan
+//! el runtime o que nunca vuelven a despertar:
+//!
+//! ```text
+//! RUSTFLAGS="--cfg tokio_unstable" cargo run --features tokio-console
+//! tokio-console http://127.0.0.1:6669
+//! ```
+//!
+//! La dirección y la retención se configuran con las variables de `console-subscriber`
+//! (`TOKIO_CONSOLE_BIND`, `TOKIO_CONSOLE_RETENTION`...).
+
+use std::future::Future;
+
+use tokio::task::JoinHandle;
+
+#[cfg(all(feature = "tokio-console", not(tokio_unstable)))]
+compile_error!("La feature tokio-console requiere compilar con RUSTFLAGS=\"--cfg tokio_unstable\"");
+
+/// Lanza `future` en una tarea llamada `name`.
+pub fn spawn<F>(name: &str, future: F) -> JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    #[cfg(tokio_unstable)]
+    {
+        tokio::task::Builder::new()
+            .name(name)
+            .spawn(future)
+            .expect("No se pudo lanzar la tarea")
+    }
+
+    #[cfg(not(tokio_unstable))]
+    {
+        let _ = name;
+        tokio::spawn(future)
+    }
+}
+
+/// Capa de trazas que alimenta `tokio-console`.
+#[cfg(feature = "tokio-console")]
+pub fn console_layer<S>() -> impl tracing_subscriber::Layer<S>
+where
+    S: tracing::Subscriber + for<'a> tracing_subscriber::registry::LookupSpan<'a>,
+{
+    console_subscriber::spawn()
+}
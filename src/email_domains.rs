@@ -0,0 +1,136 @@
+//! Política de dominios de correo bloqueados.
+//!
+//! La lista vive en la tabla `blocked_email_domains` de la base principal, compartida por
+//! todos los inquilinos, y se administra en `/admin/blocked-email-domains`. Al arrancar se puede
+//! completar con un archivo (`BLOCKED_EMAIL_DOMAINS_FILE`) con un dominio por línea, útil
+//! para cargar listas públicas de proveedores de correos desechables.
+//!
+//! Los handlers que dan de alta o cambian correos reciben [`EmailDomainPolicy`] y rechazan
+//! los dominios bloqueados (y sus subdominios) con `422` y el código
+//! [`BLOCKED_EMAIL_DOMAIN_CODE`].
+
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use axum::{
+    async_trait,
+    extract::{FromRef, FromRequestParts},
+    http::request::Parts,
+};
+use chrono::Utc;
+use sqlx::SqlitePool;
+use tracing::{info, warn};
+
+use crate::handlers::error::AppError;
+use crate::models::email_domain::parse_domain;
+use crate::models::validation::ValidationErrors;
+
+/// Código del error de validación de un correo con dominio bloqueado.
+pub const BLOCKED_EMAIL_DOMAIN_CODE: &str = "blocked_email_domain";
+
+/// Acceso a la lista de dominios bloqueados desde los handlers.
+#[derive(Debug, Clone)]
+pub struct EmailDomainPolicy {
+    database_pool: SqlitePool,
+}
+
+impl EmailDomainPolicy {
+    /// Crea la política sobre la base principal.
+    pub fn new(database_pool: SqlitePool) -> Self {
+        Self { database_pool }
+    }
+
+    /// Indica si el dominio de `email`, o alguno de sus dominios padre, está bloqueado. Un
+    /// correo con formato inválido no se considera bloqueado: lo rechaza la validación.
+    ///
+    /// La lista vive en la base principal, así que debe consultarse antes de abrir una
+    /// transacción sobre la base del inquilino.
+    pub async fn is_blocked(&self, email: &str) -> Result<bool, AppError> {
+        let Some(domain) = email
+            .rsplit_once('@')
+            .and_then(|(_, domain)| parse_domain(domain))
+        else {
+            return Ok(false);
+        };
+
+        let (blocked,): (bool,) = sqlx::query_as(
+            "SELECT EXISTS(SELECT 1 FROM blocked_email_domains \
+             WHERE domain = ?1 OR ?1 LIKE '%.' || domain)",
+        )
+        .bind(domain)
+        .fetch_one(&self.database_pool)
+        .await
+        .map_err(AppError::from)?;
+
+        Ok(blocked)
+    }
+
+    /// Falla con `422` si el dominio de `email` está bloqueado.
+    pub async fn ensure_allowed(&self, email: &str) -> Result<(), AppError> {
+        if self.is_blocked(email).await? {
+            return Err(AppError::validation(blocked_email_errors()));
+        }
+        Ok(())
+    }
+}
+
+/// Errores de validación de un correo cuyo dominio está bloqueado.
+pub fn blocked_email_errors() -> ValidationErrors {
+    let mut errors = ValidationErrors::new();
+    errors.push_with_code(
+        "email",
+        BLOCKED_EMAIL_DOMAIN_CODE,
+        "No se admiten correos de este dominio",
+    );
+    errors
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for EmailDomainPolicy
+where
+    SqlitePool: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(_parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        Ok(Self::new(SqlitePool::from_ref(state)))
+    }
+}
+
+/// Añade a la lista los dominios de `path` que aún no estén bloqueados. Se ignoran las
+/// líneas vacías, los comentarios (`#`) y, con un aviso, los dominios inválidos.
+pub async fn import_file(database_pool: &SqlitePool, path: &Path) -> Result<u64> {
+    let contents = tokio::fs::read_to_string(path)
+        .await
+        .with_context(|| format!("No se pudo leer {}", path.display()))?;
+
+    let now = Utc::now();
+    let mut transaction = database_pool.begin().await?;
+    let mut imported = 0;
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let Some(domain) = parse_domain(line) else {
+            warn!(line = index + 1, value = line, "Dominio bloqueado inválido");
+            continue;
+        };
+
+        imported += sqlx::query(
+            "INSERT INTO blocked_email_domains (domain, reason, created_at) VALUES (?, ?, ?) \
+             ON CONFLICT (domain) DO NOTHING",
+        )
+        .bind(domain)
+        .bind(format!("Importado de {}", path.display()))
+        .bind(now)
+        .execute(&mut *transaction)
+        .await?
+        .rows_affected();
+    }
+    transaction.commit().await?;
+
+    info!(imported, path = %path.display(), "Dominios bloqueados importados");
+    Ok(imported)
+}
@@ -0,0 +1,269 @@
+//! Cifrado en reposo de los correos de los usuarios.
+//!
+//! Con `EMAIL_ENCRYPTION_KEY` definida, la columna `users.email` guarda el correo cifrado con
+//! AES-256-GCM y un nonce aleatorio, con el formato `enc:v1:<clave>:<hex>`, donde `<clave>`
+//! identifica la clave con la que se cifró. Como el mismo correo produce cada vez un texto
+//! distinto, las búsquedas y la unicidad pasan por `users.email_index`, un HMAC-SHA256 del
+//! correo normalizado con `EMAIL_BLIND_INDEX_KEY` (el "índice ciego").
+//!
+//! Para rotar la clave se pasa la actual a `EMAIL_ENCRYPTION_PREVIOUS_KEYS`, se define la
+//! nueva y se ejecuta `rekey-emails`, que vuelve a cifrar todos los correos con ella. El mismo
+//! comando cifra los correos guardados antes de activar el cifrado: mientras no se ejecute,
+//! los valores sin prefijo se siguen leyendo tal cual.
+//!
+//! El cifrado protege la base de datos y sus copias: los eventos del outbox también guardan el
+//! correo cifrado, y solo se descifra al entregarlos. Los handlers trabajan con el correo en
+//! claro, así que la caché en memoria lo sigue conteniendo.
+
+use std::{fmt, sync::Arc};
+
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    Aes256Gcm, Nonce,
+};
+use anyhow::{anyhow, bail, Context, Result};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::config::AppConfig;
+use crate::models::user::User;
+
+/// Prefijo de los valores cifrados, con la versión del formato.
+const SEALED_PREFIX: &str = "enc:v1:";
+
+/// Longitud en bytes del nonce de AES-GCM.
+const NONCE_LENGTH: usize = 12;
+
+/// Clave de cifrado junto con su identificador público.
+struct DataKey {
+    id: String,
+    cipher: Aes256Gcm,
+}
+
+impl DataKey {
+    fn new(key: &[u8; 32]) -> Self {
+        Self {
+            id: hex::encode(&Sha256::digest(key)[..4]),
+            cipher: Aes256Gcm::new(key.into()),
+        }
+    }
+}
+
+struct Keys {
+    current: DataKey,
+    previous: Vec<DataKey>,
+    blind_index: [u8; 32],
+}
+
+/// Cifrado de correos; desactivado, deja pasar los valores sin cambios.
+#[derive(Clone, Default)]
+pub struct EmailEncryption {
+    keys: Option<Arc<Keys>>,
+}
+
+impl fmt::Debug for EmailEncryption {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("EmailEncryption")
+            .field("key_id", &self.current_key_id())
+            .finish()
+    }
+}
+
+impl EmailEncryption {
+    /// Cifrado desactivado: los correos se guardan en claro.
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// Cifra con `current`, descifra también con `previous` y calcula el índice ciego con
+    /// `blind_index`.
+    pub fn new(current: [u8; 32], previous: &[[u8; 32]], blind_index: [u8; 32]) -> Self {
+        Self {
+            keys: Some(Arc::new(Keys {
+                current: DataKey::new(&current),
+                previous: previous.iter().map(DataKey::new).collect(),
+                blind_index,
+            })),
+        }
+    }
+
+    /// Construye el cifrado a partir de las claves de la configuración, en hexadecimal.
+    pub fn from_config(config: &AppConfig) -> Result<Self> {
+        let Some(current) = &config.email_encryption_key else {
+            if !config.email_encryption_previous_keys.is_empty() {
+                bail!("EMAIL_ENCRYPTION_PREVIOUS_KEYS requiere EMAIL_ENCRYPTION_KEY");
+            }
+            return Ok(Self::disabled());
+        };
+
+        let current = parse_key("EMAIL_ENCRYPTION_KEY", current)?;
+        let previous = config
+            .email_encryption_previous_keys
+            .iter()
+            .map(|key| parse_key("EMAIL_ENCRYPTION_PREVIOUS_KEYS", key))
+            .collect::<Result<Vec<_>>>()?;
+        let blind_index = config
+            .email_blind_index_key
+            .as_deref()
+            .context("EMAIL_ENCRYPTION_KEY requiere EMAIL_BLIND_INDEX_KEY")
+            .and_then(|key| parse_key("EMAIL_BLIND_INDEX_KEY", key))?;
+
+        Ok(Self::new(current, &previous, blind_index))
+    }
+
+    /// Indica si los correos se guardan cifrados.
+    pub fn is_enabled(&self) -> bool {
+        self.keys.is_some()
+    }
+
+    /// Identificador de la clave con la que se cifran los correos nuevos.
+    pub fn current_key_id(&self) -> Option<&str> {
+        self.keys.as_ref().map(|keys| keys.current.id.as_str())
+    }
+
+    /// Valor que se guarda en `users.email`.
+    pub fn seal(&self, email: &str) -> String {
+        let Some(keys) = &self.keys else {
+            return email.to_string();
+        };
+
+        let nonce = rand::random::<[u8; NONCE_LENGTH]>();
+        let ciphertext = keys
+            .current
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce), email.as_bytes())
+            .expect("AES-GCM solo falla con mensajes de más de 64 GiB");
+
+        format!(
+            "{SEALED_PREFIX}{}:{}{}",
+            keys.current.id,
+            hex::encode(nonce),
+            hex::encode(ciphertext)
+        )
+    }
+
+    /// Recupera el correo guardado en `users.email`. Los valores sin prefijo, anteriores al
+    /// cifrado, se devuelven tal cual.
+    pub fn open(&self, stored: &str) -> Result<String> {
+        let Some(sealed) = stored.strip_prefix(SEALED_PREFIX) else {
+            return Ok(stored.to_string());
+        };
+        let keys = self
+            .keys
+            .as_ref()
+            .context("Hay correos cifrados, pero EMAIL_ENCRYPTION_KEY no está definida")?;
+
+        let (key_id, payload) = sealed
+            .split_once(':')
+            .context("Correo cifrado con un formato inválido")?;
+        let key = std::iter::once(&keys.current)
+            .chain(&keys.previous)
+            .find(|key| key.id == key_id)
+            .ok_or_else(|| {
+                anyhow!("No se conoce la clave {key_id} con la que se cifró un correo")
+            })?;
+
+        let payload = hex::decode(payload).context("Correo cifrado con un formato inválido")?;
+        if payload.len() < NONCE_LENGTH {
+            bail!("Correo cifrado con un formato inválido");
+        }
+        let (nonce, ciphertext) = payload.split_at(NONCE_LENGTH);
+        let plaintext = key
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| anyhow!("No se pudo descifrar un correo con la clave {key_id}"))?;
+
+        String::from_utf8(plaintext).context("El correo descifrado no es UTF-8")
+    }
+
+    /// Descifra el correo de un usuario leído de la base de datos.
+    pub fn open_user(&self, mut user: User) -> Result<User> {
+        user.email = self.open(&user.email)?;
+        Ok(user)
+    }
+
+    /// Descifra el correo de cada usuario leído de la base de datos.
+    pub fn open_users(&self, users: Vec<User>) -> Result<Vec<User>> {
+        users.into_iter().map(|user| self.open_user(user)).collect()
+    }
+
+    /// Valor de `users.email_index` para un correo ya normalizado; `None` sin cifrado.
+    pub fn blind_index(&self, email: &str) -> Option<String> {
+        let keys = self.keys.as_ref()?;
+        let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&keys.blind_index)
+            .expect("HMAC admite claves de cualquier longitud");
+        mac.update(email.as_bytes());
+
+        Some(hex::encode(mac.finalize().into_bytes()))
+    }
+
+    /// Indica si el valor guardado ya está cifrado con la clave actual.
+    fn is_current(&self, stored: &str) -> bool {
+        self.current_key_id().is_some_and(|key_id| {
+            stored
+                .strip_prefix(SEALED_PREFIX)
+                .and_then(|sealed| sealed.split_once(':'))
+                .is_some_and(|(stored_key_id, _)| stored_key_id == key_id)
+        })
+    }
+}
+
+/// Resultado de [`rekey`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RekeyReport {
+    /// Usuarios revisados.
+    pub users: u64,
+    /// Usuarios cuyo correo o índice ciego se reescribió.
+    pub rewritten: u64,
+}
+
+/// Cifra con la clave actual todos los correos que estaban en claro o cifrados con una clave
+/// anterior, y recalcula su índice ciego. Todo ocurre en una transacción, así que un fallo
+/// (por ejemplo, un correo cifrado con una clave que ya no está configurada) no deja la base a
+/// medias.
+pub async fn rekey(pool: &SqlitePool, encryption: &EmailEncryption) -> Result<RekeyReport> {
+    if !encryption.is_enabled() {
+        bail!("Para volver a cifrar los correos hay que definir EMAIL_ENCRYPTION_KEY");
+    }
+
+    let mut transaction = pool.begin().await?;
+    let rows = sqlx::query_as::<_, (Uuid, String, Option<String>)>(
+        "SELECT id, email, email_index FROM users",
+    )
+    .fetch_all(&mut *transaction)
+    .await?;
+
+    let mut report = RekeyReport::default();
+    for (user_id, stored, stored_index) in rows {
+        report.users += 1;
+        let email = encryption
+            .open(&stored)
+            .with_context(|| format!("No se pudo descifrar el correo del usuario {user_id}"))?;
+        let index = encryption.blind_index(&email);
+        if encryption.is_current(&stored) && stored_index == index {
+            continue;
+        }
+
+        sqlx::query("UPDATE users SET email = ?, email_index = ? WHERE id = ?")
+            .bind(encryption.seal(&email))
+            .bind(index)
+            .bind(user_id)
+            .execute(&mut *transaction)
+            .await?;
+        report.rewritten += 1;
+    }
+    transaction.commit().await?;
+
+    Ok(report)
+}
+
+/// Interpreta una clave de 32 bytes en hexadecimal.
+fn parse_key(variable: &str, value: &str) -> Result<[u8; 32]> {
+    hex::decode(value.trim())
+        .ok()
+        .and_then(|bytes| <[u8; 32]>::try_from(bytes).ok())
+        .ok_or_else(|| anyhow!("{variable} debe ser una clave de 32 bytes en hexadecimal"))
+}
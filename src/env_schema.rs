@@ -0,0 +1,425 @@
+//! Esquema de las variables de entorno.
+//!
+//! [`SCHEMA`] enumera todas las variables que lee la aplicación con su tipo, si son
+//! obligatorias y su valor por defecto. Cada módulo sigue leyendo las suyas y cae en el valor
+//! por defecto si no son válidas; por eso, al arrancar, [`check_env`] recorre el esquema y
+//! detiene el servidor con la lista completa de problemas, en lugar de que, por ejemplo,
+//! `PORT=80a` acabe escuchando en silencio en el 3000.
+//!
+//! Las variables vacías cuentan como no definidas. Al añadir una variable nueva hay que
+//! declararla aquí.
+
+use std::{fmt, net::IpAddr};
+
+use anyhow::{bail, Result};
+use tracing_subscriber::EnvFilter;
+
+use crate::ids;
+
+/// Tipo de valor que admite una variable.
+#[derive(Debug, Clone, Copy)]
+pub enum VarType {
+    /// Cualquier texto.
+    Text,
+    /// Booleano: `1`, `true`, `yes` u `on`, o `0`, `false`, `no` u `off`.
+    Flag,
+    /// Entero dentro del rango indicado.
+    Integer { min: u64, max: u64 },
+    /// Número decimal dentro del rango indicado.
+    Decimal { min: f64, max: f64 },
+    /// Uno de los valores indicados, sin distinguir mayúsculas.
+    OneOf(&'static [&'static str]),
+    /// Lista separada por comas de valores de entre los indicados.
+    ListOf(&'static [&'static str]),
+    /// Dirección IP.
+    IpAddress,
+    /// Validación propia; devuelve el motivo si el valor no sirve.
+    Custom(fn(&str) -> Result<(), String>),
+}
+
+/// Cuándo es obligatoria una variable.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Requirement {
+    Optional,
+    Always,
+    /// Cuando `variable` vale `value` o, si es una lista, lo incluye.
+    When {
+        variable: &'static str,
+        value: &'static str,
+    },
+}
+
+/// Descripción de una variable de entorno.
+#[derive(Debug, Clone, Copy)]
+pub struct EnvVar {
+    pub name: &'static str,
+    pub kind: VarType,
+    pub requirement: Requirement,
+    /// Valor que se usa si no está definida, tal como se escribiría en el entorno.
+    pub default: Option<&'static str>,
+}
+
+/// Problema encontrado en una variable.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnvProblem {
+    pub name: &'static str,
+    pub message: String,
+}
+
+impl fmt::Display for EnvProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.name, self.message)
+    }
+}
+
+const fn var(
+    name: &'static str,
+    kind: VarType,
+    requirement: Requirement,
+    default: Option<&'static str>,
+) -> EnvVar {
+    EnvVar {
+        name,
+        kind,
+        requirement,
+        default,
+    }
+}
+
+const fn optional(name: &'static str, kind: VarType, default: &'static str) -> EnvVar {
+    var(name, kind, Requirement::Optional, Some(default))
+}
+
+const fn optional_without_default(name: &'static str, kind: VarType) -> EnvVar {
+    var(name, kind, Requirement::Optional, None)
+}
+
+const fn required_when(name: &'static str, variable: &'static str, value: &'static str) -> EnvVar {
+    var(
+        name,
+        VarType::Text,
+        Requirement::When { variable, value },
+        None,
+    )
+}
+
+const U16: VarType = VarType::Integer {
+    min: 0,
+    max: u16::MAX as u64,
+};
+const U32: VarType = VarType::Integer {
+    min: 0,
+    max: u32::MAX as u64,
+};
+const U64: VarType = VarType::Integer {
+    min: 0,
+    max: u64::MAX,
+};
+const POSITIVE_U32: VarType = VarType::Integer {
+    min: 1,
+    max: u32::MAX as u64,
+};
+const PERCENT: VarType = VarType::Decimal {
+    min: 0.0,
+    max: 100.0,
+};
+
+/// Todas las variables de entorno que lee la aplicación.
+pub const SCHEMA: &[EnvVar] = &[
+    // Perfil y servidor.
+    optional(
+        "APP_ENV",
+        VarType::OneOf(&["development", "dev", "test", "production", "prod"]),
+        "development",
+    ),
+    optional("HOST", VarType::IpAddress, "0.0.0.0"),
+    optional("PORT", U16, "3000"),
+    optional("ADMIN_HOST", VarType::IpAddress, "127.0.0.1"),
+    optional_without_default("ADMIN_PORT", U16),
+    // Activación por socket: las define systemd o el envoltorio que pasa los sockets.
+    optional_without_default("LISTEN_PID", POSITIVE_U32),
+    optional_without_default("LISTEN_FDS", U32),
+    optional_without_default("LISTEN_FDNAMES", VarType::Text),
+    optional("CONFIG_FILE", VarType::Text, ".env"),
+    optional("RUST_LOG", VarType::Custom(log_filter), "info"),
+    optional(
+        "LOG_FORMAT",
+        VarType::OneOf(&["human", "text", "json"]),
+        "human",
+    ),
+    optional("ACCESS_LOG", VarType::Flag, "true"),
+    optional("ACCESS_LOG_SKIP_PATHS", VarType::Text, "/health,/metrics"),
+    optional("TRUSTED_PROXY_HOPS", U64, "0"),
+    optional("ALLOW_PUT_UPSERT", VarType::Flag, "false"),
+    optional("MAINTENANCE_MODE", VarType::Flag, "false"),
+    optional("PUBLIC_SPA_FALLBACK", VarType::Flag, "false"),
+    optional("CONCURRENCY_LIMIT_API", U64, "256"),
+    optional("CONCURRENCY_LIMIT_ADMIN", U64, "4"),
+    optional("LOAD_SHED_RETRY_AFTER_SECS", U64, "1"),
+    optional_without_default("REQUEST_SIGNING_CLIENTS", VarType::Custom(signing_clients)),
+    optional("REQUEST_SIGNING_MAX_SKEW_SECS", U64, "300"),
+    optional_without_default("PPROF_TOKEN", VarType::Text),
+    optional(
+        "METRICS_SINK",
+        VarType::OneOf(&["prometheus", "statsd", "dogstatsd"]),
+        "prometheus",
+    ),
+    optional("STATSD_ADDR", VarType::Text, "127.0.0.1:8125"),
+    optional("STATSD_PREFIX", VarType::Text, "proyecto_rust"),
+    optional("STATSD_FLUSH_INTERVAL_MS", POSITIVE_U32, "1000"),
+    // Base de datos.
+    optional("DATABASE_URL", VarType::Text, "sqlite://db.sqlite"),
+    optional("RUN_MIGRATIONS", VarType::Flag, "true"),
+    optional("DATABASE_CONNECT_ATTEMPTS", POSITIVE_U32, "5"),
+    optional("DATABASE_CONNECT_BACKOFF_MS", U64, "500"),
+    optional("DATABASE_MAX_CONNECTIONS", POSITIVE_U32, "10"),
+    optional("DATABASE_MIN_CONNECTIONS", U32, "0"),
+    optional("DATABASE_ACQUIRE_TIMEOUT_SECS", U64, "30"),
+    optional("DATABASE_IDLE_TIMEOUT_SECS", U64, "600"),
+    optional("DATABASE_MAX_LIFETIME_SECS", U64, "1800"),
+    optional(
+        "SQLITE_JOURNAL_MODE",
+        VarType::OneOf(&["delete", "truncate", "persist", "memory", "wal", "off"]),
+        "wal",
+    ),
+    optional(
+        "SQLITE_SYNCHRONOUS",
+        VarType::OneOf(&["off", "normal", "full", "extra"]),
+        "normal",
+    ),
+    optional("SQLITE_BUSY_TIMEOUT_MS", U64, "5000"),
+    optional("SQLITE_FOREIGN_KEYS", VarType::Flag, "true"),
+    optional("SQLITE_BUSY_RETRIES", U32, "3"),
+    optional("SLOW_QUERY_THRESHOLD_MS", U64, "500"),
+    optional("JOB_QUEUE_MAX_AGE_SECS", U64, "300"),
+    // Inquilinos.
+    optional_without_default("TENANT_BASE_DOMAIN", VarType::Text),
+    optional_without_default("TENANT_DATABASE_DIR", VarType::Text),
+    // Copias, réplica y CDC.
+    optional("BACKUP_DIR", VarType::Text, "backups"),
+    optional("BACKUP_TO_STORAGE", VarType::Flag, "false"),
+    optional_without_default("EXPORT_URL_KEY", VarType::Text),
+    optional("EXPORT_URL_TTL_SECS", U64, "900"),
+    optional("REPLICATE_WAL", VarType::Flag, "false"),
+    optional("REPLICATION_INTERVAL_MS", U64, "1000"),
+    optional("REPLICATION_CHECKPOINT_BYTES", U64, "4194304"),
+    optional("REPLICATION_RETAIN_GENERATIONS", U64, "2"),
+    optional("CDC_SINK", VarType::OneOf(&["ndjson", "storage"]), "ndjson"),
+    optional_without_default("CDC_NDJSON_DIR", VarType::Text),
+    optional("CDC_STORAGE_PREFIX", VarType::Text, "cdc/"),
+    optional("CDC_MAX_FILE_BYTES", U64, "67108864"),
+    optional("CDC_BATCH_SIZE", U64, "500"),
+    optional("CDC_POLL_INTERVAL_SECS", U64, "5"),
+    // Usuarios.
+    optional_without_default("BLOCKED_EMAIL_DOMAINS_FILE", VarType::Text),
+    optional_without_default("EMAIL_ENCRYPTION_KEY", VarType::Text),
+    optional_without_default("EMAIL_ENCRYPTION_PREVIOUS_KEYS", VarType::Text),
+    optional_without_default("EMAIL_BLIND_INDEX_KEY", VarType::Text),
+    optional(
+        "ID_FORMAT",
+        VarType::OneOf(&["uuid-v4", "uuidv4", "uuid-v7", "uuidv7", "uuid", "ulid"]),
+        "uuid-v7",
+    ),
+    optional_without_default("ID_PREFIX", VarType::Custom(id_prefix)),
+    // Caché.
+    optional(
+        "CACHE_BACKEND",
+        VarType::OneOf(&["disabled", "none", "memory", "redis"]),
+        "disabled",
+    ),
+    optional("REDIS_URL", VarType::Text, "redis://127.0.0.1:6379"),
+    optional("CACHE_USER_TTL_SECS", U64, "60"),
+    optional("CACHE_LIST_TTL_SECS", U64, "10"),
+    optional("CACHE_MAX_ENTRIES", U64, "10000"),
+    // Broker de eventos.
+    optional_without_default("EVENT_BROKER", VarType::OneOf(&["nats", "kafka"])),
+    optional_without_default("EVENT_BROKER_URL", VarType::Text),
+    optional("EVENT_BROKER_TOPIC", VarType::Text, "proyecto_rust.users"),
+    optional("EVENT_BROKER_POLL_INTERVAL_MS", U64, "1000"),
+    // Estado compartido entre réplicas.
+    optional(
+        "SHARED_STATE_BACKEND",
+        VarType::OneOf(&["memory", "redis"]),
+        "memory",
+    ),
+    // Almacenamiento.
+    optional("STORAGE_BACKEND", VarType::OneOf(&["local", "s3"]), "local"),
+    optional("STORAGE_DIR", VarType::Text, "storage"),
+    optional("S3_REGION", VarType::Text, "us-east-1"),
+    optional_without_default("S3_ENDPOINT", VarType::Text),
+    optional_without_default("S3_PATH_STYLE", VarType::Flag),
+    required_when("S3_BUCKET", "STORAGE_BACKEND", "s3"),
+    required_when("S3_ACCESS_KEY_ID", "STORAGE_BACKEND", "s3"),
+    required_when("S3_SECRET_ACCESS_KEY", "STORAGE_BACKEND", "s3"),
+    optional("STORAGE_PRESIGN_TTL_SECS", U64, "900"),
+    // Secretos.
+    optional(
+        "SECRETS_PROVIDERS",
+        VarType::ListOf(&["env", "file", "vault", "aws-secrets-manager"]),
+        "env,file",
+    ),
+    optional_without_default("SECRETS_DIR", VarType::Text),
+    optional("SECRETS_CACHE_TTL_SECS", U64, "300"),
+    optional("SECRETS_REFRESH_SECS", U64, "0"),
+    required_when("VAULT_ADDR", "SECRETS_PROVIDERS", "vault"),
+    required_when("VAULT_TOKEN", "SECRETS_PROVIDERS", "vault"),
+    required_when("VAULT_SECRET_PATH", "SECRETS_PROVIDERS", "vault"),
+    optional("VAULT_KV_MOUNT", VarType::Text, "secret"),
+    optional_without_default("VAULT_NAMESPACE", VarType::Text),
+    required_when(
+        "AWS_SECRETS_MANAGER_SECRET_ID",
+        "SECRETS_PROVIDERS",
+        "aws-secrets-manager",
+    ),
+    required_when(
+        "AWS_ACCESS_KEY_ID",
+        "SECRETS_PROVIDERS",
+        "aws-secrets-manager",
+    ),
+    required_when(
+        "AWS_SECRET_ACCESS_KEY",
+        "SECRETS_PROVIDERS",
+        "aws-secrets-manager",
+    ),
+    optional_without_default("AWS_SESSION_TOKEN", VarType::Text),
+    optional_without_default("AWS_REGION", VarType::Text),
+    optional_without_default("AWS_DEFAULT_REGION", VarType::Text),
+    optional_without_default("AWS_SECRETS_MANAGER_ENDPOINT", VarType::Text),
+    // Modo caos (solo desarrollo).
+    optional("CHAOS_ENABLED", VarType::Flag, "false"),
+    optional("CHAOS_LATENCY_PERCENT", PERCENT, "0"),
+    optional("CHAOS_LATENCY_MS", U64, "500"),
+    optional("CHAOS_ERROR_PERCENT", PERCENT, "0"),
+    optional("CHAOS_DROP_PERCENT", PERCENT, "0"),
+    optional("CHAOS_SKIP_PATHS", VarType::Text, "/health,/metrics"),
+];
+
+impl VarType {
+    /// Comprueba `value`; devuelve el motivo si no es válido.
+    pub fn check(&self, value: &str) -> Result<(), String> {
+        let value = value.trim();
+        match *self {
+            Self::Text => Ok(()),
+            Self::Flag => match value.to_ascii_lowercase().as_str() {
+                "1" | "true" | "yes" | "on" | "0" | "false" | "no" | "off" => Ok(()),
+                _ => Err(format!("«{value}» no es un booleano (true o false)")),
+            },
+            Self::Integer { min, max } => match value.parse::<u64>() {
+                Ok(number) if (min..=max).contains(&number) => Ok(()),
+                _ => Err(format!("«{value}» no es un entero entre {min} y {max}")),
+            },
+            Self::Decimal { min, max } => match value.parse::<f64>() {
+                Ok(number) if (min..=max).contains(&number) => Ok(()),
+                _ => Err(format!("«{value}» no es un número entre {min} y {max}")),
+            },
+            Self::OneOf(allowed) => {
+                if allowed
+                    .iter()
+                    .any(|option| option.eq_ignore_ascii_case(value))
+                {
+                    Ok(())
+                } else {
+                    Err(format!("«{value}» no es uno de: {}", allowed.join(", ")))
+                }
+            }
+            Self::ListOf(allowed) => value
+                .split(',')
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .try_for_each(|item| Self::OneOf(allowed).check(item)),
+            Self::IpAddress => value
+                .parse::<IpAddr>()
+                .map(|_| ())
+                .map_err(|_| format!("«{value}» no es una dirección IP")),
+            Self::Custom(check) => check(value),
+        }
+    }
+}
+
+/// Comprueba las variables de `SCHEMA` obteniendo cada una de `lookup`, y devuelve todos los
+/// problemas encontrados.
+pub fn validate(lookup: impl Fn(&str) -> Option<String>) -> Vec<EnvProblem> {
+    let lookup = |name: &str| lookup(name).filter(|value| !value.trim().is_empty());
+    let mut problems = Vec::new();
+
+    for variable in SCHEMA {
+        match lookup(variable.name) {
+            Some(value) => {
+                if let Err(message) = variable.kind.check(&value) {
+                    problems.push(EnvProblem {
+                        name: variable.name,
+                        message,
+                    });
+                }
+            }
+            None => {
+                let missing = match variable.requirement {
+                    Requirement::Optional => None,
+                    Requirement::Always => Some("es obligatoria".to_string()),
+                    Requirement::When { variable, value } => lookup(variable)
+                        .filter(|current| {
+                            current
+                                .split(',')
+                                .any(|item| item.trim().eq_ignore_ascii_case(value))
+                        })
+                        .map(|_| format!("es obligatoria con {variable}={value}")),
+                };
+                if let Some(message) = missing {
+                    problems.push(EnvProblem {
+                        name: variable.name,
+                        message,
+                    });
+                }
+            }
+        }
+    }
+
+    problems
+}
+
+/// Valida el entorno del proceso y falla con la lista completa de problemas.
+pub fn check_env() -> Result<()> {
+    let problems = validate(|name| std::env::var(name).ok())
+        .iter()
+        .map(|problem| format!("\n  - {problem}"))
+        .collect::<String>();
+    if !problems.is_empty() {
+        bail!("Variables de entorno no válidas:{problems}");
+    }
+
+    Ok(())
+}
+
+/// Pares `id:secreto` separados por comas.
+fn signing_clients(value: &str) -> Result<(), String> {
+    let malformed = value
+        .split(',')
+        .map(str::trim)
+        .filter(|pair| !pair.is_empty())
+        .any(|pair| {
+            pair.split_once(':')
+                .is_none_or(|(id, secret)| id.trim().is_empty() || secret.trim().is_empty())
+        });
+    if malformed {
+        return Err("cada cliente debe tener la forma id:secreto".to_string());
+    }
+
+    Ok(())
+}
+
+/// Filtro de trazas con la sintaxis de `EnvFilter`.
+fn log_filter(value: &str) -> Result<(), String> {
+    EnvFilter::try_new(value)
+        .map(|_| ())
+        .map_err(|error| format!("«{value}» no es un filtro de trazas válido: {error}"))
+}
+
+fn id_prefix(value: &str) -> Result<(), String> {
+    if ids::is_valid_prefix(&value.to_ascii_lowercase()) {
+        Ok(())
+    } else {
+        Err(format!(
+            "«{value}» no es un prefijo válido (hasta 8 letras ASCII)"
+        ))
+    }
+}
@@ -0,0 +1,189 @@
+//! Bus de eventos de dominio en proceso.
+//!
+//! Los handlers registran eventos tipados (`UserCreated`, `UserUpdated`, `UserMerged`,
+//! `UserDeleted`) en el outbox junto con cada cambio y el relay de [`crate::outbox`] los
+//! publica en este bus. Funcionalidades transversales como la invalidación de caché se
+//! suscriben a él sin modificar los handlers. La entrega no está garantizada, así que
+//! lo que no puede perder eventos (webhooks, broker, CDC) lee el outbox con su propio
+//! cursor.
+
+use std::future::Future;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::{debug, warn};
+use uuid::Uuid;
+
+use crate::diagnostics;
+use crate::models::user::User;
+use crate::tenant::DEFAULT_TENANT;
+use crate::trace_context::TraceContext;
+
+/// Número de eventos que se retienen para suscriptores lentos antes de descartarlos.
+const DEFAULT_CAPACITY: usize = 1024;
+
+/// Evento de dominio relacionado con el ciclo de vida de los usuarios.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+#[allow(clippy::enum_variant_names)]
+pub enum DomainEvent {
+    UserCreated {
+        user: User,
+    },
+    UserUpdated {
+        user: User,
+    },
+    /// `user` se fusionó en `merged_into` y queda en el estado terminal `merged`.
+    UserMerged {
+        user: User,
+        merged_into: Uuid,
+    },
+    UserDeleted {
+        user_id: Uuid,
+        /// Los eventos registrados antes de la multi-tenencia pertenecen al inquilino `default`.
+        #[serde(default = "default_tenant_id")]
+        tenant_id: String,
+    },
+}
+
+impl DomainEvent {
+    /// Nombre estable del evento, útil para filtros y registros.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::UserCreated { .. } => "user.created",
+            Self::UserUpdated { .. } => "user.updated",
+            Self::UserMerged { .. } => "user.merged",
+            Self::UserDeleted { .. } => "user.deleted",
+        }
+    }
+
+    /// Identificador del usuario afectado por el evento.
+    pub fn user_id(&self) -> Uuid {
+        match self {
+            Self::UserCreated { user }
+            | Self::UserUpdated { user }
+            | Self::UserMerged { user, .. } => user.id,
+            Self::UserDeleted { user_id, .. } => *user_id,
+        }
+    }
+
+    /// Usuario que acompaña al evento, si lo lleva.
+    pub fn user_mut(&mut self) -> Option<&mut User> {
+        match self {
+            Self::UserCreated { user }
+            | Self::UserUpdated { user }
+            | Self::UserMerged { user, .. } => Some(user),
+            Self::UserDeleted { .. } => None,
+        }
+    }
+
+    /// Inquilino del usuario afectado por el evento.
+    pub fn tenant_id(&self) -> &str {
+        match self {
+            Self::UserCreated { user }
+            | Self::UserUpdated { user }
+            | Self::UserMerged { user, .. } => &user.tenant_id,
+            Self::UserDeleted { tenant_id, .. } => tenant_id,
+        }
+    }
+}
+
+fn default_tenant_id() -> String {
+    DEFAULT_TENANT.to_string()
+}
+
+/// Envoltorio con los metadatos comunes de cada evento publicado.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventEnvelope {
+    pub id: Uuid,
+    pub occurred_at: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: DomainEvent,
+    /// Traza de la operación que originó el evento. No forma parte del cuerpo: el outbox la
+    /// guarda aparte y los webhooks la envían en sus cabeceras.
+    #[serde(skip)]
+    pub trace_context: Option<TraceContext>,
+}
+
+impl EventEnvelope {
+    /// Envuelve un evento asignándole identificador y marca de tiempo, dentro de la traza en
+    /// curso.
+    pub fn new(event: DomainEvent) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            occurred_at: Utc::now(),
+            event,
+            trace_context: TraceContext::current(),
+        }
+    }
+}
+
+/// Suscriptor en proceso que reacciona a los eventos publicados en el bus.
+pub trait EventSubscriber: Send + Sync + 'static {
+    /// Nombre del suscriptor, usado en las trazas.
+    fn name(&self) -> &'static str;
+
+    /// Procesa un evento; los errores deben gestionarse internamente.
+    fn handle(&self, envelope: EventEnvelope) -> impl Future<Output = ()> + Send;
+}
+
+/// Canal de difusión compartido por toda la aplicación.
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<EventEnvelope>,
+}
+
+impl EventBus {
+    /// Crea un bus con la capacidad por defecto.
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(DEFAULT_CAPACITY);
+        Self { sender }
+    }
+
+    /// Publica un evento asignándole identificador y marca de tiempo.
+    ///
+    /// La publicación directa no sobrevive a una caída del proceso; los cambios persistidos
+    /// deben pasar por [`crate::outbox::record`].
+    pub fn publish(&self, event: DomainEvent) {
+        self.publish_envelope(EventEnvelope::new(event));
+    }
+
+    /// Publica un evento ya envuelto, conservando su identificador original.
+    pub fn publish_envelope(&self, envelope: EventEnvelope) {
+        debug!(event = envelope.event.name(), id = %envelope.id, "Evento publicado");
+        // Sin suscriptores activos el envío falla, lo cual no es un error para el emisor.
+        let _ = self.sender.send(envelope);
+    }
+
+    /// Devuelve un receptor que verá los eventos publicados a partir de ahora.
+    pub fn subscribe(&self) -> broadcast::Receiver<EventEnvelope> {
+        self.sender.subscribe()
+    }
+
+    /// Registra un suscriptor ejecutándolo en su propia tarea mientras viva el bus.
+    pub fn register<S: EventSubscriber>(&self, subscriber: S) {
+        let mut receiver = self.subscribe();
+
+        diagnostics::spawn(subscriber.name(), async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(envelope) => subscriber.handle(envelope).await,
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!(
+                            subscriber = subscriber.name(),
+                            skipped, "Suscriptor rezagado"
+                        );
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+        });
+    }
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,132 @@
+//! Trabajo en segundo plano que genera una exportación de usuarios.
+//!
+//! [`ExportJob`] consulta los usuarios con los parámetros del listado guardados al crear la
+//! exportación, los codifica en el formato pedido y guarda el archivo en el almacenamiento
+//! bajo `exports/<id>.<extensión>`. La fila de `exports` refleja cada paso para que
+//! `GET /exports/:id` pueda informar del estado; si el trabajo agota sus reintentos la
+//! exportación queda como `failed` con el último error.
+
+use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sqlx::{QueryBuilder, Sqlite};
+use tracing::info;
+use uuid::Uuid;
+
+use crate::handlers::user::push_user_filters;
+use crate::jobs::Job;
+use crate::models::user::{ListUsersQuery, User, USER_COLUMNS};
+use crate::state::AppState;
+use crate::tenant::Tenant;
+
+use super::{encode_users, ExportFormat};
+
+/// Prefijo de las claves de almacenamiento de las exportaciones.
+pub const STORAGE_PREFIX: &str = "exports/";
+
+/// Genera el archivo de una exportación registrada en `exports`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportJob {
+    pub export_id: Uuid,
+    pub tenant_id: String,
+    pub format: ExportFormat,
+    pub query: ListUsersQuery,
+}
+
+impl Job for ExportJob {
+    const KIND: &'static str = "users.export";
+
+    async fn run(self, state: AppState) -> Result<()> {
+        let attempts = sqlx::query_scalar::<_, i64>(
+            "UPDATE exports SET status = 'running', attempts = attempts + 1 WHERE id = ? \
+             RETURNING attempts",
+        )
+        .bind(self.export_id)
+        .fetch_optional(&state.database_pool)
+        .await?;
+        // La exportación pudo borrarse mientras el trabajo esperaba en la cola.
+        let Some(attempts) = attempts else {
+            return Ok(());
+        };
+
+        match self.generate(&state).await {
+            Ok((storage_key, row_count)) => {
+                sqlx::query(
+                    "UPDATE exports SET status = 'completed', storage_key = ?, row_count = ?, \
+                     error = NULL, completed_at = ? WHERE id = ?",
+                )
+                .bind(&storage_key)
+                .bind(row_count)
+                .bind(Utc::now())
+                .bind(self.export_id)
+                .execute(&state.database_pool)
+                .await?;
+
+                info!(
+                    export_id = %self.export_id,
+                    %storage_key,
+                    row_count,
+                    "Exportación completada"
+                );
+                Ok(())
+            }
+            Err(error) => {
+                let status = if attempts >= i64::from(Self::MAX_ATTEMPTS) {
+                    "failed"
+                } else {
+                    "pending"
+                };
+                sqlx::query("UPDATE exports SET status = ?, error = ? WHERE id = ?")
+                    .bind(status)
+                    .bind(format!("{error:#}"))
+                    .bind(self.export_id)
+                    .execute(&state.database_pool)
+                    .await?;
+
+                Err(error)
+            }
+        }
+    }
+}
+
+impl ExportJob {
+    /// Genera y guarda el archivo; devuelve su clave y el número de usuarios incluidos.
+    async fn generate(&self, state: &AppState) -> Result<(String, i64)> {
+        let database_pool = state
+            .tenant_databases
+            .pool(&self.tenant_id)
+            .await?
+            .unwrap_or_else(|| state.database_pool.clone());
+        let encryption = &state.email_encryption;
+
+        let mut builder = QueryBuilder::<Sqlite>::new(format!("SELECT {USER_COLUMNS} FROM users"));
+        push_user_filters(
+            &mut builder,
+            &Tenant(self.tenant_id.clone()),
+            encryption,
+            self.query.clone(),
+        )
+        .map_err(|_| anyhow!("Los parámetros de la exportación no son válidos"))?;
+        builder.push(" ORDER BY created_at, id");
+
+        let users = builder
+            .build_query_as::<User>()
+            .fetch_all(&database_pool)
+            .await
+            .context("No se pudieron leer los usuarios de la exportación")?;
+        let users = encryption.open_users(users)?;
+        let row_count = users.len() as i64;
+
+        let format = self.format;
+        let contents = tokio::task::spawn_blocking(move || encode_users(&users, format)).await??;
+
+        let storage_key = format!("{STORAGE_PREFIX}{}.{}", self.export_id, format.extension());
+        state
+            .storage
+            .put(&storage_key, contents, format.content_type())
+            .await
+            .with_context(|| format!("No se pudo guardar la exportación en {storage_key}"))?;
+
+        Ok((storage_key, row_count))
+    }
+}
@@ -0,0 +1,73 @@
+//! Enlaces de descarga firmados de las exportaciones.
+//!
+//! Un enlace `/exports/<id>/download?expires=<segundos Unix>&signature=<hex>` lleva el
+//! HMAC-SHA256 del identificador y de la caducidad, calculado con `EXPORT_URL_KEY`. Quien lo
+//! tenga puede descargar el archivo sin indicar inquilino hasta que caduque, y no es posible
+//! alargar su validez ni reutilizar la firma para otra exportación.
+//!
+//! Sin `EXPORT_URL_KEY` se usa una clave aleatoria generada al arrancar: los enlaces dejan de
+//! valer al reiniciar y no sirven en otras réplicas.
+
+use std::sync::OnceLock;
+
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde::Deserialize;
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::config::AppConfig;
+
+/// Parámetros de consulta de un enlace de descarga.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DownloadLink {
+    /// Instante de caducidad, en segundos Unix.
+    pub expires: i64,
+    /// HMAC-SHA256 en hexadecimal.
+    pub signature: String,
+}
+
+/// Enlace de descarga de `export_id` válido durante `EXPORT_URL_TTL_SECS` desde `now`.
+pub fn download_url(config: &AppConfig, export_id: Uuid, now: DateTime<Utc>) -> String {
+    let ttl = i64::try_from(config.export_url_ttl.as_secs()).unwrap_or(i64::MAX);
+    let expires = now.timestamp().saturating_add(ttl);
+    let signature = hex::encode(mac(config, export_id, expires).finalize().into_bytes());
+    format!("/exports/{export_id}/download?expires={expires}&signature={signature}")
+}
+
+impl DownloadLink {
+    /// Comprueba que el enlace corresponda a `export_id` y no haya caducado en `now`.
+    pub fn verify(&self, config: &AppConfig, export_id: Uuid, now: DateTime<Utc>) -> bool {
+        if now.timestamp() >= self.expires {
+            return false;
+        }
+        let Ok(signature) = hex::decode(&self.signature) else {
+            return false;
+        };
+        mac(config, export_id, self.expires)
+            .verify_slice(&signature)
+            .is_ok()
+    }
+}
+
+fn mac(config: &AppConfig, export_id: Uuid, expires: i64) -> Hmac<Sha256> {
+    let key = match &config.export_url_key {
+        Some(key) => key.as_bytes(),
+        None => process_key(),
+    };
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(key).expect("HMAC acepta claves de cualquier longitud");
+    mac.update(format!("{export_id}\n{expires}").as_bytes());
+    mac
+}
+
+/// Clave aleatoria del proceso, para cuando no se configura `EXPORT_URL_KEY`.
+fn process_key() -> &'static [u8] {
+    static KEY: OnceLock<[u8; 32]> = OnceLock::new();
+    KEY.get_or_init(|| {
+        let mut key = [0; 32];
+        rand::thread_rng().fill_bytes(&mut key);
+        key
+    })
+}
@@ -0,0 +1,191 @@
+//! Serialización de usuarios para exportaciones.
+//!
+//! Convierte listas de [`User`] a los formatos de descarga soportados (JSON, CSV y
+//! Parquet). Parquet se genera con el escritor de bajo nivel del crate `parquet`, sin
+//! depender de Arrow, para que el equipo de datos pueda cargarlo directamente en su
+//! data lake. Las exportaciones grandes se generan en segundo plano con [`job`] y se
+//! descargan mediante los enlaces firmados de [`link`].
+
+pub mod job;
+pub mod link;
+
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use parquet::{
+    data_type::{ByteArray, ByteArrayType, Int64Type},
+    file::{properties::WriterProperties, writer::SerializedFileWriter},
+    schema::parser::parse_message_type,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::models::user::User;
+
+/// Esquema Parquet de la exportación de usuarios; las marcas de tiempo van en microsegundos UTC.
+const USER_PARQUET_SCHEMA: &str = "
+    message user {
+        REQUIRED BYTE_ARRAY id (UTF8);
+        REQUIRED BYTE_ARRAY name (UTF8);
+        REQUIRED BYTE_ARRAY email (UTF8);
+        REQUIRED INT64 created_at (TIMESTAMP(MICROS, true));
+        OPTIONAL INT64 suspended_until (TIMESTAMP(MICROS, true));
+        OPTIONAL BYTE_ARRAY suspension_reason (UTF8);
+        OPTIONAL BYTE_ARRAY avatar_url (UTF8);
+        REQUIRED BYTE_ARRAY status (UTF8);
+    }
+";
+
+/// Formatos de exportación disponibles.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "TEXT", rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Json,
+    Csv,
+    Parquet,
+}
+
+impl ExportFormat {
+    /// Tipo MIME con el que se sirve el archivo generado.
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Json => "application/json",
+            Self::Csv => "text/csv; charset=utf-8",
+            Self::Parquet => "application/vnd.apache.parquet",
+        }
+    }
+
+    /// Extensión de archivo sugerida para la descarga.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Csv => "csv",
+            Self::Parquet => "parquet",
+        }
+    }
+}
+
+/// Parámetros de consulta del endpoint de exportación.
+#[derive(Debug, Default, Deserialize)]
+pub struct ExportOptions {
+    #[serde(default)]
+    pub format: ExportFormat,
+}
+
+/// Serializa los usuarios en el formato indicado.
+pub fn encode_users(users: &[User], format: ExportFormat) -> Result<Vec<u8>> {
+    match format {
+        ExportFormat::Json => serde_json::to_vec(users).context("Fallo al serializar JSON"),
+        ExportFormat::Csv => users_to_csv(users),
+        ExportFormat::Parquet => users_to_parquet(users),
+    }
+}
+
+/// Genera un CSV con cabecera a partir de los usuarios.
+fn users_to_csv(users: &[User]) -> Result<Vec<u8>> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+
+    for user in users {
+        writer
+            .serialize(user)
+            .context("Fallo al serializar fila CSV")?;
+    }
+
+    writer.into_inner().context("Fallo al finalizar el CSV")
+}
+
+/// Genera un archivo Parquet con un único row group a partir de los usuarios.
+fn users_to_parquet(users: &[User]) -> Result<Vec<u8>> {
+    let schema = Arc::new(parse_message_type(USER_PARQUET_SCHEMA)?);
+    let properties = Arc::new(WriterProperties::builder().build());
+    let mut writer = SerializedFileWriter::new(Vec::new(), schema, properties)?;
+
+    let text = |values: Vec<String>| -> Vec<ByteArray> {
+        values
+            .into_iter()
+            .map(|value| ByteArray::from(value.into_bytes()))
+            .collect()
+    };
+
+    let ids = text(users.iter().map(|user| user.id.to_string()).collect());
+    let names = text(users.iter().map(|user| user.name.clone()).collect());
+    let emails = text(users.iter().map(|user| user.email.clone()).collect());
+    let created_at: Vec<i64> = users
+        .iter()
+        .map(|user| user.created_at.timestamp_micros())
+        .collect();
+    let (suspended_until, suspended_levels) = optional_column(users, |user| {
+        user.suspended_until.map(|until| until.timestamp_micros())
+    });
+    let (reasons, reason_levels) = optional_column(users, |user| user.suspension_reason.clone());
+    let reasons = text(reasons);
+    let (avatar_urls, avatar_levels) = optional_column(users, |user| user.avatar_url.clone());
+    let avatar_urls = text(avatar_urls);
+    let statuses = text(
+        users
+            .iter()
+            .map(|user| user.status.as_str().to_string())
+            .collect(),
+    );
+
+    let mut row_group = writer.next_row_group()?;
+    let mut column_index = 0;
+    while let Some(mut column) = row_group.next_column()? {
+        match column_index {
+            0 => column
+                .typed::<ByteArrayType>()
+                .write_batch(&ids, None, None)?,
+            1 => column
+                .typed::<ByteArrayType>()
+                .write_batch(&names, None, None)?,
+            2 => column
+                .typed::<ByteArrayType>()
+                .write_batch(&emails, None, None)?,
+            3 => column
+                .typed::<Int64Type>()
+                .write_batch(&created_at, None, None)?,
+            4 => column.typed::<Int64Type>().write_batch(
+                &suspended_until,
+                Some(&suspended_levels),
+                None,
+            )?,
+            5 => {
+                column
+                    .typed::<ByteArrayType>()
+                    .write_batch(&reasons, Some(&reason_levels), None)?
+            }
+            6 => column.typed::<ByteArrayType>().write_batch(
+                &avatar_urls,
+                Some(&avatar_levels),
+                None,
+            )?,
+            _ => column
+                .typed::<ByteArrayType>()
+                .write_batch(&statuses, None, None)?,
+        };
+        column.close()?;
+        column_index += 1;
+    }
+    row_group.close()?;
+
+    writer.into_inner().context("Fallo al finalizar el Parquet")
+}
+
+/// Separa una columna opcional en valores presentes y niveles de definición.
+fn optional_column<T>(users: &[User], value: impl Fn(&User) -> Option<T>) -> (Vec<T>, Vec<i16>) {
+    let mut values = Vec::new();
+    let mut levels = Vec::with_capacity(users.len());
+
+    for user in users {
+        match value(user) {
+            Some(present) => {
+                values.push(present);
+                levels.push(1);
+            }
+            None => levels.push(0),
+        }
+    }
+
+    (values, levels)
+}
@@ -0,0 +1,421 @@
+//! Lenguaje de filtros de los listados (`?filter=`).
+//!
+//! Una expresión combina comparaciones `campo operador valor` con `and`, `or`, `not` y
+//! paréntesis, al estilo de los filtros de SCIM:
+//!
+//! ```text
+//! name co "ada" and (created_at gt 2024-01-01 or not status eq "active")
+//! ```
+//!
+//! Operadores: `eq`, `ne`, `co` (contiene), `sw` (empieza por), `ew` (termina en), `gt`, `ge`,
+//! `lt`, `le` y `pr` (tiene valor, sin operando). Los textos van entre comillas dobles, con
+//! `\"` y `\\` como escapes; las fechas admiten `2024-01-01` o RFC 3339. Los campos, las palabras
+//! clave y los operadores no distinguen mayúsculas, y las comparaciones de texto tampoco.
+//!
+//! Cada listado declara los campos que admite con [`FilterField`]; solo esos nombres llegan al
+//! SQL, y los valores siempre van como parámetros.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use sqlx::{QueryBuilder, Sqlite};
+
+use crate::models::validation::ValidationErrors;
+
+/// Longitud máxima de una expresión.
+pub const MAX_FILTER_LENGTH: usize = 1000;
+/// Número máximo de comparaciones de una expresión.
+pub const MAX_FILTER_TERMS: usize = 20;
+
+/// Tipo de un campo filtrable, que decide los operadores y valores que admite.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    /// Texto: `eq`, `ne`, `co`, `sw`, `ew`.
+    Text,
+    /// Fecha guardada en RFC 3339: `eq`, `ne`, `gt`, `ge`, `lt`, `le`.
+    Timestamp,
+    /// Texto con un conjunto cerrado de valores: `eq`, `ne`.
+    Keyword(&'static [&'static str]),
+}
+
+/// Campo que un listado permite filtrar; el nombre es también la columna.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FilterField {
+    pub name: &'static str,
+    pub kind: FieldKind,
+}
+
+/// Operador de comparación.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Eq,
+    Ne,
+    Co,
+    Sw,
+    Ew,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Pr,
+}
+
+impl Operator {
+    fn parse(word: &str) -> Option<Self> {
+        Some(match word.to_ascii_lowercase().as_str() {
+            "eq" => Self::Eq,
+            "ne" => Self::Ne,
+            "co" => Self::Co,
+            "sw" => Self::Sw,
+            "ew" => Self::Ew,
+            "gt" => Self::Gt,
+            "ge" => Self::Ge,
+            "lt" => Self::Lt,
+            "le" => Self::Le,
+            "pr" => Self::Pr,
+            _ => return None,
+        })
+    }
+
+    fn applies_to(self, kind: FieldKind) -> bool {
+        match self {
+            Self::Eq | Self::Ne | Self::Pr => true,
+            Self::Co | Self::Sw | Self::Ew => kind == FieldKind::Text,
+            Self::Gt | Self::Ge | Self::Lt | Self::Le => kind == FieldKind::Timestamp,
+        }
+    }
+}
+
+/// Valor de una comparación, ya convertido al tipo del campo.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterValue {
+    Text(String),
+    Timestamp(DateTime<Utc>),
+}
+
+/// Expresión de filtro validada contra los campos de un listado.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Filter {
+    Compare {
+        column: &'static str,
+        operator: Operator,
+        value: FilterValue,
+    },
+    Present {
+        column: &'static str,
+    },
+    Not(Box<Filter>),
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+}
+
+/// Motivo por el que se rechaza una expresión.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FilterError {
+    TooLong,
+    TooComplex,
+    Syntax,
+    UnknownField,
+    Operator,
+    Value,
+}
+
+impl From<FilterError> for ValidationErrors {
+    fn from(error: FilterError) -> Self {
+        let (code, message) = match error {
+            FilterError::TooLong => (
+                "filter_too_long",
+                "El filtro admite como mucho 1000 caracteres",
+            ),
+            FilterError::TooComplex => (
+                "filter_too_complex",
+                "El filtro admite como mucho 20 comparaciones",
+            ),
+            FilterError::Syntax => ("filter_syntax", "El filtro no está bien formado"),
+            FilterError::UnknownField => ("filter_field", "El filtro usa un campo no filtrable"),
+            FilterError::Operator => (
+                "filter_operator",
+                "El filtro usa un operador que el campo no admite",
+            ),
+            FilterError::Value => (
+                "filter_value",
+                "El filtro compara un campo con un valor no válido",
+            ),
+        };
+        let mut errors = ValidationErrors::new();
+        errors.push_with_code("filter", code, message);
+        errors
+    }
+}
+
+impl Filter {
+    /// Analiza `input` admitiendo solo los campos de `fields`.
+    pub fn parse(input: &str, fields: &[FilterField]) -> Result<Self, ValidationErrors> {
+        if input.chars().count() > MAX_FILTER_LENGTH {
+            return Err(FilterError::TooLong.into());
+        }
+        let mut parser = Parser {
+            tokens: tokenize(input)?,
+            position: 0,
+            fields,
+            terms: 0,
+        };
+        let filter = parser.parse_or()?;
+        if parser.position != parser.tokens.len() {
+            return Err(FilterError::Syntax.into());
+        }
+        Ok(filter)
+    }
+
+    /// Añade la expresión a `builder` como condición SQL entre paréntesis.
+    pub fn push_sql(&self, builder: &mut QueryBuilder<'_, Sqlite>) {
+        builder.push("(");
+        match self {
+            Self::Compare {
+                column,
+                operator,
+                value,
+            } => push_comparison(builder, column, *operator, value),
+            Self::Present { column } => {
+                builder.push(*column).push(" IS NOT NULL");
+            }
+            Self::Not(inner) => {
+                builder.push("NOT ");
+                inner.push_sql(builder);
+            }
+            Self::And(left, right) => {
+                left.push_sql(builder);
+                builder.push(" AND ");
+                right.push_sql(builder);
+            }
+            Self::Or(left, right) => {
+                left.push_sql(builder);
+                builder.push(" OR ");
+                right.push_sql(builder);
+            }
+        }
+        builder.push(")");
+    }
+}
+
+fn push_comparison(
+    builder: &mut QueryBuilder<'_, Sqlite>,
+    column: &'static str,
+    operator: Operator,
+    value: &FilterValue,
+) {
+    builder.push(column);
+    match value {
+        FilterValue::Text(text) => {
+            let (sql, bound) = match operator {
+                Operator::Co => (" LIKE ", format!("%{}%", escape_like(text))),
+                Operator::Sw => (" LIKE ", format!("{}%", escape_like(text))),
+                Operator::Ew => (" LIKE ", format!("%{}", escape_like(text))),
+                Operator::Ne => (" IS NOT ", text.clone()),
+                _ => (" = ", text.clone()),
+            };
+            builder.push(sql).push_bind(bound);
+            if matches!(operator, Operator::Co | Operator::Sw | Operator::Ew) {
+                builder.push(" ESCAPE '\\'");
+            } else {
+                builder.push(" COLLATE NOCASE");
+            }
+        }
+        // Las fechas se guardan en RFC 3339 y UTC, así que se comparan como texto igual que
+        // las del resto de consultas.
+        FilterValue::Timestamp(timestamp) => {
+            let sql = match operator {
+                Operator::Ne => " IS NOT ",
+                Operator::Gt => " > ",
+                Operator::Ge => " >= ",
+                Operator::Lt => " < ",
+                Operator::Le => " <= ",
+                _ => " = ",
+            };
+            builder.push(sql).push_bind(*timestamp);
+        }
+    }
+}
+
+/// Escapa los comodines de `LIKE` (`%`, `_`) y la barra para usar `text` de forma literal
+/// con `ESCAPE '\'`.
+pub fn escape_like(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        if matches!(character, '%' | '_' | '\\') {
+            escaped.push('\\');
+        }
+        escaped.push(character);
+    }
+    escaped
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Open,
+    Close,
+    Quoted(String),
+    Word(String),
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, FilterError> {
+    let mut tokens = Vec::new();
+    let mut characters = input.chars().peekable();
+    while let Some(&character) = characters.peek() {
+        match character {
+            _ if character.is_whitespace() => {
+                characters.next();
+            }
+            '(' => {
+                characters.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                characters.next();
+                tokens.push(Token::Close);
+            }
+            '"' => {
+                characters.next();
+                let mut text = String::new();
+                loop {
+                    match characters.next() {
+                        Some('"') => break,
+                        Some('\\') => match characters.next() {
+                            Some(escaped @ ('"' | '\\')) => text.push(escaped),
+                            _ => return Err(FilterError::Syntax),
+                        },
+                        Some(other) => text.push(other),
+                        None => return Err(FilterError::Syntax),
+                    }
+                }
+                tokens.push(Token::Quoted(text));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&next) = characters.peek() {
+                    if next.is_whitespace() || matches!(next, '(' | ')' | '"') {
+                        break;
+                    }
+                    word.push(next);
+                    characters.next();
+                }
+                tokens.push(Token::Word(word));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// Analizador descendente: `or` agrupa menos que `and`, y este menos que `not`.
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    position: usize,
+    fields: &'a [FilterField],
+    terms: usize,
+}
+
+impl Parser<'_> {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        match self.tokens.get(self.position) {
+            Some(Token::Word(word)) if word.eq_ignore_ascii_case(keyword) => {
+                self.position += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Filter, FilterError> {
+        let mut filter = self.parse_and()?;
+        while self.eat_keyword("or") {
+            filter = Filter::Or(Box::new(filter), Box::new(self.parse_and()?));
+        }
+        Ok(filter)
+    }
+
+    fn parse_and(&mut self) -> Result<Filter, FilterError> {
+        let mut filter = self.parse_unary()?;
+        while self.eat_keyword("and") {
+            filter = Filter::And(Box::new(filter), Box::new(self.parse_unary()?));
+        }
+        Ok(filter)
+    }
+
+    fn parse_unary(&mut self) -> Result<Filter, FilterError> {
+        if self.eat_keyword("not") {
+            return Ok(Filter::Not(Box::new(self.parse_unary()?)));
+        }
+        match self.next() {
+            Some(Token::Open) => {
+                let filter = self.parse_or()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(filter),
+                    _ => Err(FilterError::Syntax),
+                }
+            }
+            Some(Token::Word(name)) => self.parse_comparison(&name),
+            _ => Err(FilterError::Syntax),
+        }
+    }
+
+    fn parse_comparison(&mut self, name: &str) -> Result<Filter, FilterError> {
+        self.terms += 1;
+        if self.terms > MAX_FILTER_TERMS {
+            return Err(FilterError::TooComplex);
+        }
+
+        let field = *self
+            .fields
+            .iter()
+            .find(|field| field.name.eq_ignore_ascii_case(name))
+            .ok_or(FilterError::UnknownField)?;
+        let operator = match self.next() {
+            Some(Token::Word(word)) => Operator::parse(&word).ok_or(FilterError::Syntax)?,
+            _ => return Err(FilterError::Syntax),
+        };
+        if !operator.applies_to(field.kind) {
+            return Err(FilterError::Operator);
+        }
+        if operator == Operator::Pr {
+            return Ok(Filter::Present { column: field.name });
+        }
+
+        let raw = match self.next() {
+            Some(Token::Quoted(text) | Token::Word(text)) => text,
+            _ => return Err(FilterError::Syntax),
+        };
+        Ok(Filter::Compare {
+            column: field.name,
+            operator,
+            value: parse_value(field.kind, raw)?,
+        })
+    }
+}
+
+fn parse_value(kind: FieldKind, raw: String) -> Result<FilterValue, FilterError> {
+    match kind {
+        FieldKind::Text => Ok(FilterValue::Text(raw)),
+        FieldKind::Keyword(allowed) => {
+            let value = raw.to_ascii_lowercase();
+            if allowed.contains(&value.as_str()) {
+                Ok(FilterValue::Text(value))
+            } else {
+                Err(FilterError::Value)
+            }
+        }
+        FieldKind::Timestamp => {
+            if let Ok(timestamp) = DateTime::parse_from_rfc3339(&raw) {
+                return Ok(FilterValue::Timestamp(timestamp.with_timezone(&Utc)));
+            }
+            NaiveDate::parse_from_str(&raw, "%Y-%m-%d")
+                .ok()
+                .and_then(|date| date.and_hms_opt(0, 0, 0))
+                .map(|midnight| FilterValue::Timestamp(midnight.and_utc()))
+                .ok_or(FilterError::Value)
+        }
+    }
+}
@@ -0,0 +1,142 @@
+//! Búsqueda aproximada de usuarios por nombre con un índice de trigramas.
+//!
+//! Cada nombre se descompone en los mismos trigramas que usa la detección de duplicados
+//! ([`crate::models::duplicate::trigrams`]), que se guardan en `user_trigrams`. Los handlers
+//! que escriben el nombre actualizan el índice en la misma transacción, y al borrar un usuario
+//! sus filas se borran en cascada. Una búsqueda solo lee las filas de los trigramas de la
+//! consulta, sin recorrer todos los usuarios.
+//!
+//! La puntuación es la fracción de trigramas de la consulta presentes en el nombre, como la
+//! `word_similarity` de `pg_trgm`: `grce` encuentra a «Grace Hopper» aunque el apellido no se
+//! parezca a nada de la consulta. A igual puntuación va primero el nombre más parecido en
+//! conjunto, así que «Grace» precede a «Grace Hopper».
+
+use sqlx::{Executor, FromRow, QueryBuilder, Sqlite, SqliteConnection, SqlitePool};
+use uuid::Uuid;
+
+use crate::models::duplicate::trigrams;
+use crate::models::user::{User, UserMatch, USER_COLUMNS};
+
+/// Sustituye los trigramas del nombre de `user_id` por los de `name`.
+pub async fn index_user(
+    connection: &mut SqliteConnection,
+    tenant_id: &str,
+    user_id: Uuid,
+    name: &str,
+) -> sqlx::Result<()> {
+    remove_user(&mut *connection, user_id).await?;
+    for trigram in trigrams(name) {
+        sqlx::query("INSERT INTO user_trigrams (tenant_id, trigram, user_id) VALUES (?, ?, ?)")
+            .bind(tenant_id)
+            .bind(String::from_iter(trigram))
+            .bind(user_id)
+            .execute(&mut *connection)
+            .await?;
+    }
+    Ok(())
+}
+
+/// Quita a `user_id` del índice, por ejemplo al borrar sus datos personales.
+pub async fn remove_user<'e, E>(executor: E, user_id: Uuid) -> sqlx::Result<()>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    sqlx::query("DELETE FROM user_trigrams WHERE user_id = ?")
+        .bind(user_id)
+        .execute(executor)
+        .await?;
+    Ok(())
+}
+
+/// Indexa los usuarios que aún no están en el índice, como los creados antes de que
+/// existiera; devuelve cuántos se han revisado.
+pub async fn index_missing(pool: &SqlitePool) -> sqlx::Result<usize> {
+    let users = sqlx::query_as::<_, (Uuid, String, String)>(
+        "SELECT id, tenant_id, name FROM users WHERE erased_at IS NULL \
+         AND NOT EXISTS (SELECT 1 FROM user_trigrams WHERE user_trigrams.user_id = users.id)",
+    )
+    .fetch_all(pool)
+    .await?;
+
+    let mut transaction = pool.begin().await?;
+    for (user_id, tenant_id, name) in &users {
+        index_user(&mut transaction, tenant_id, *user_id, name).await?;
+    }
+    transaction.commit().await?;
+
+    Ok(users.len())
+}
+
+/// Fila de la búsqueda: el usuario y los trigramas que comparte con la consulta.
+#[derive(FromRow)]
+struct Candidate {
+    #[sqlx(flatten)]
+    user: User,
+    shared: i64,
+    total: i64,
+}
+
+/// Usuarios del inquilino cuyo nombre alcanza `threshold` para `query`, de mayor a menor
+/// puntuación, como mucho `limit`. Los usuarios con los datos borrados no aparecen.
+pub async fn search(
+    pool: &SqlitePool,
+    tenant_id: &str,
+    query: &str,
+    threshold: f64,
+    limit: usize,
+) -> sqlx::Result<Vec<UserMatch>> {
+    let query_trigrams = trigrams(query);
+    if query_trigrams.is_empty() {
+        return Ok(Vec::new());
+    }
+    let wanted = query_trigrams.len() as f64;
+    // Trigramas compartidos necesarios para llegar al umbral; el margen evita que el redondeo
+    // deje fuera una puntuación exacta.
+    let min_shared = (threshold * wanted - 1e-9).ceil().max(1.0) as i64;
+
+    let mut builder = QueryBuilder::<Sqlite>::new(format!(
+        "SELECT {USER_COLUMNS}, matches.shared, \
+         (SELECT COUNT(*) FROM user_trigrams AS own WHERE own.user_id = users.id) AS total \
+         FROM (SELECT user_id, COUNT(*) AS shared FROM user_trigrams WHERE tenant_id = "
+    ));
+    builder.push_bind(tenant_id).push(" AND trigram IN (");
+    let mut separated = builder.separated(", ");
+    for trigram in &query_trigrams {
+        separated.push_bind(String::from_iter(trigram));
+    }
+    builder
+        .push(") GROUP BY user_id HAVING COUNT(*) >= ")
+        .push_bind(min_shared)
+        .push(
+            ") AS matches JOIN users ON users.id = matches.user_id \
+             WHERE users.erased_at IS NULL AND users.status != 'merged'",
+        );
+
+    let candidates = builder
+        .build_query_as::<Candidate>()
+        .fetch_all(pool)
+        .await?;
+
+    let mut ranked = candidates
+        .into_iter()
+        .map(|candidate| {
+            let shared = candidate.shared as f64;
+            let union = candidate.total as f64 + wanted - shared;
+            (candidate.user, shared / wanted, shared / union)
+        })
+        .collect::<Vec<_>>();
+    ranked.sort_by(|left, right| {
+        right
+            .1
+            .total_cmp(&left.1)
+            .then(right.2.total_cmp(&left.2))
+            .then_with(|| left.0.name.cmp(&right.0.name))
+            .then(left.0.id.cmp(&right.0.id))
+    });
+    ranked.truncate(limit);
+
+    Ok(ranked
+        .into_iter()
+        .map(|(user, similarity, _)| UserMatch { user, similarity })
+        .collect())
+}
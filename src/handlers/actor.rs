@@ -0,0 +1,46 @@
+//! Identificación del usuario que realiza cada petición.
+//!
+//! Todavía no hay autenticación: el cliente declara quién es mediante la cabecera
+//! `X-User-Id`, y los handlers que aplican reglas de autorización la leen a través del
+//! extractor [`Actor`]. Cuando exista un mecanismo de sesión bastará con cambiar aquí la
+//! forma de obtener el identificador.
+
+use std::sync::Arc;
+
+use axum::{
+    async_trait,
+    extract::{FromRef, FromRequestParts},
+    http::request::Parts,
+};
+use uuid::Uuid;
+
+use crate::handlers::error::AppError;
+use crate::ids::{parse_user_id, IdGenerator};
+
+/// Cabecera con el identificador del usuario que realiza la petición.
+pub const ACTOR_HEADER: &str = "x-user-id";
+
+/// Usuario que realiza la petición. Responde `401` si falta la cabecera o no es un
+/// identificador válido.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Actor(pub Uuid);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Actor
+where
+    Arc<dyn IdGenerator>: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let generator = Arc::<dyn IdGenerator>::from_ref(state);
+        parts
+            .headers
+            .get(ACTOR_HEADER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| parse_user_id(generator.as_ref(), value))
+            .map(Actor)
+            .ok_or_else(AppError::unauthorized)
+    }
+}
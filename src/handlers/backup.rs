@@ -0,0 +1,24 @@
+//! Handler HTTP para generar copias de seguridad de la base principal.
+
+use std::sync::Arc;
+
+use axum::{extract::State, http::StatusCode, Json};
+use sqlx::SqlitePool;
+
+use crate::backup::{self, BackupArtifact};
+use crate::config::AppConfig;
+use crate::handlers::error::AppError;
+use crate::storage::Storage;
+
+/// Genera una copia consistente de la base sin detener el servidor y devuelve dónde quedó.
+pub async fn create_backup(
+    State(pool): State<SqlitePool>,
+    State(config): State<Arc<AppConfig>>,
+    State(storage): State<Arc<dyn Storage>>,
+) -> Result<(StatusCode, Json<BackupArtifact>), AppError> {
+    let artifact = backup::create(&pool, &config, storage.as_ref())
+        .await
+        .map_err(AppError::internal)?;
+
+    Ok((StatusCode::CREATED, Json(artifact)))
+}
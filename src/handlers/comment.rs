@@ -0,0 +1,185 @@
+//! Handlers HTTP para los comentarios anidados bajo `/posts/:id/comments`.
+//!
+//! Todas las operaciones comprueban primero que la publicación exista en el inquilino, de
+//! modo que una publicación inexistente o ajena responde `404` aunque no tenga comentarios. El borrado es
+//! lógico: el comentario deja de listarse pero la fila se conserva.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    Json,
+};
+use sqlx::{Pool, Sqlite};
+use uuid::Uuid;
+
+use crate::clock::Clock;
+use crate::handlers::error::AppError;
+use crate::handlers::post::{ensure_author_exists, is_foreign_key_violation, TENANT_POSTS};
+use crate::handlers::validated::ValidatedJson;
+use crate::models::comment::{Comment, CommentBody, NewComment, COMMENT_COLUMNS};
+use crate::models::pagination::{Page, PageParams};
+use crate::models::user::ValidationErrors;
+use crate::tenant::{Database, Tenant};
+
+/// Devuelve una página de comentarios de la publicación, del más antiguo al más reciente.
+pub async fn list_comments(
+    Path(post_id): Path<Uuid>,
+    tenant: Tenant,
+    Database(database_pool): Database,
+    Query(params): Query<PageParams>,
+) -> Result<Json<Page<Comment>>, AppError> {
+    let params = params.validate().map_err(AppError::validation)?;
+    ensure_post_exists(&database_pool, &tenant, post_id).await?;
+
+    let total = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM comments WHERE post_id = ? AND deleted_at IS NULL",
+    )
+    .bind(post_id)
+    .fetch_one(&database_pool)
+    .await
+    .map_err(AppError::from)?;
+
+    let comments = sqlx::query_as::<_, Comment>(&format!(
+        "SELECT {COMMENT_COLUMNS} FROM comments WHERE post_id = ? AND deleted_at IS NULL \
+         ORDER BY created_at, id LIMIT ? OFFSET ?"
+    ))
+    .bind(post_id)
+    .bind(params.limit())
+    .bind(params.offset())
+    .fetch_all(&database_pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(Json(Page::new(comments, params, total)))
+}
+
+/// Recupera un comentario concreto de la publicación.
+pub async fn get_comment(
+    Path((post_id, comment_id)): Path<(Uuid, Uuid)>,
+    tenant: Tenant,
+    Database(database_pool): Database,
+) -> Result<Json<Comment>, AppError> {
+    ensure_post_exists(&database_pool, &tenant, post_id).await?;
+    let comment = sqlx::query_as::<_, Comment>(&format!(
+        "SELECT {COMMENT_COLUMNS} FROM comments \
+         WHERE id = ? AND post_id = ? AND deleted_at IS NULL"
+    ))
+    .bind(comment_id)
+    .bind(post_id)
+    .fetch_optional(&database_pool)
+    .await
+    .map_err(AppError::from)?
+    .ok_or_else(AppError::not_found)?;
+
+    Ok(Json(comment))
+}
+
+/// Publica un comentario en la publicación indicada.
+pub async fn create_comment(
+    Path(post_id): Path<Uuid>,
+    tenant: Tenant,
+    Database(database_pool): Database,
+    State(clock): State<Arc<dyn Clock>>,
+    ValidatedJson(new_comment): ValidatedJson<NewComment>,
+) -> Result<(StatusCode, Json<Comment>), AppError> {
+    ensure_post_exists(&database_pool, &tenant, post_id).await?;
+    ensure_author_exists(&database_pool, &tenant, new_comment.author_id).await?;
+    let now = clock.now();
+
+    let comment = sqlx::query_as::<_, Comment>(&format!(
+        "INSERT INTO comments (id, post_id, author_id, body, created_at, updated_at) \
+         VALUES (?, ?, ?, ?, ?, ?) RETURNING {COMMENT_COLUMNS}"
+    ))
+    .bind(Uuid::new_v4())
+    .bind(post_id)
+    .bind(new_comment.author_id)
+    .bind(&new_comment.body)
+    .bind(now)
+    .bind(now)
+    .fetch_one(&database_pool)
+    .await
+    .map_err(|error| {
+        if is_foreign_key_violation(&error) {
+            let mut errors = ValidationErrors::new();
+            errors.push(
+                "author_id",
+                "No existe ningún usuario con ese identificador",
+            );
+            AppError::validation(errors)
+        } else {
+            AppError::from(error)
+        }
+    })?;
+
+    Ok((StatusCode::CREATED, Json(comment)))
+}
+
+/// Edita el texto de un comentario.
+pub async fn update_comment(
+    Path((post_id, comment_id)): Path<(Uuid, Uuid)>,
+    tenant: Tenant,
+    Database(database_pool): Database,
+    State(clock): State<Arc<dyn Clock>>,
+    ValidatedJson(CommentBody { body }): ValidatedJson<CommentBody>,
+) -> Result<Json<Comment>, AppError> {
+    ensure_post_exists(&database_pool, &tenant, post_id).await?;
+    let comment = sqlx::query_as::<_, Comment>(&format!(
+        "UPDATE comments SET body = ?, updated_at = ? \
+         WHERE id = ? AND post_id = ? AND deleted_at IS NULL RETURNING {COMMENT_COLUMNS}"
+    ))
+    .bind(&body)
+    .bind(clock.now())
+    .bind(comment_id)
+    .bind(post_id)
+    .fetch_optional(&database_pool)
+    .await
+    .map_err(AppError::from)?
+    .ok_or_else(AppError::not_found)?;
+
+    Ok(Json(comment))
+}
+
+/// Marca un comentario como eliminado.
+pub async fn delete_comment(
+    Path((post_id, comment_id)): Path<(Uuid, Uuid)>,
+    tenant: Tenant,
+    Database(database_pool): Database,
+    State(clock): State<Arc<dyn Clock>>,
+) -> Result<StatusCode, AppError> {
+    ensure_post_exists(&database_pool, &tenant, post_id).await?;
+    let result = sqlx::query(
+        "UPDATE comments SET deleted_at = ? WHERE id = ? AND post_id = ? AND deleted_at IS NULL",
+    )
+    .bind(clock.now())
+    .bind(comment_id)
+    .bind(post_id)
+    .execute(&database_pool)
+    .await
+    .map_err(AppError::from)?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found());
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Devuelve `404` si la publicación no existe o es de otro inquilino.
+async fn ensure_post_exists(
+    database_pool: &Pool<Sqlite>,
+    tenant: &Tenant,
+    post_id: Uuid,
+) -> Result<(), AppError> {
+    sqlx::query_scalar::<_, i64>(&format!(
+        "SELECT 1 FROM posts WHERE id = ? AND {TENANT_POSTS}"
+    ))
+    .bind(post_id)
+    .bind(tenant.id())
+    .fetch_optional(database_pool)
+    .await
+    .map_err(AppError::from)?
+    .map(|_| ())
+    .ok_or_else(AppError::not_found)
+}
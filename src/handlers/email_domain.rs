@@ -0,0 +1,86 @@
+//! Handlers HTTP para administrar los dominios de correo bloqueados.
+//!
+//! Como el registro de inquilinos, la lista vive en la base principal y es común a todos
+//! los inquilinos.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use sqlx::{Pool, Sqlite};
+
+use crate::clock::Clock;
+use crate::handlers::error::AppError;
+use crate::handlers::user::is_unique_violation;
+use crate::handlers::validated::ValidatedJson;
+use crate::models::email_domain::{
+    parse_domain, BlockedEmailDomain, NewBlockedEmailDomain, BLOCKED_EMAIL_DOMAIN_COLUMNS,
+};
+use crate::models::user::ValidationErrors;
+
+/// Devuelve los dominios bloqueados ordenados alfabéticamente.
+pub async fn list_blocked_email_domains(
+    State(database_pool): State<Pool<Sqlite>>,
+) -> Result<Json<Vec<BlockedEmailDomain>>, AppError> {
+    let domains = sqlx::query_as::<_, BlockedEmailDomain>(&format!(
+        "SELECT {BLOCKED_EMAIL_DOMAIN_COLUMNS} FROM blocked_email_domains ORDER BY domain"
+    ))
+    .fetch_all(&database_pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(Json(domains))
+}
+
+/// Bloquea un dominio; los usuarios que ya lo usan no se ven afectados.
+pub async fn block_email_domain(
+    State(database_pool): State<Pool<Sqlite>>,
+    State(clock): State<Arc<dyn Clock>>,
+    ValidatedJson(new_domain): ValidatedJson<NewBlockedEmailDomain>,
+) -> Result<(StatusCode, Json<BlockedEmailDomain>), AppError> {
+    let domain = sqlx::query_as::<_, BlockedEmailDomain>(&format!(
+        "INSERT INTO blocked_email_domains (domain, reason, created_at) VALUES (?, ?, ?) \
+         RETURNING {BLOCKED_EMAIL_DOMAIN_COLUMNS}"
+    ))
+    .bind(&new_domain.domain)
+    .bind(&new_domain.reason)
+    .bind(clock.now())
+    .fetch_one(&database_pool)
+    .await
+    .map_err(|error| {
+        if is_unique_violation(&error) {
+            let mut errors = ValidationErrors::new();
+            errors.push("domain", "El dominio ya está bloqueado");
+            AppError::validation(errors)
+        } else {
+            AppError::from(error)
+        }
+    })?;
+
+    Ok((StatusCode::CREATED, Json(domain)))
+}
+
+/// Vuelve a admitir altas con el dominio indicado.
+pub async fn unblock_email_domain(
+    Path(domain): Path<String>,
+    State(database_pool): State<Pool<Sqlite>>,
+) -> Result<StatusCode, AppError> {
+    let Some(domain) = parse_domain(&domain) else {
+        return Err(AppError::not_found());
+    };
+
+    let result = sqlx::query("DELETE FROM blocked_email_domains WHERE domain = ?")
+        .bind(&domain)
+        .execute(&database_pool)
+        .await
+        .map_err(AppError::from)?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found());
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
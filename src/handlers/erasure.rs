@@ -0,0 +1,205 @@
+//! Handler HTTP para el borrado de datos personales (RGPD).
+//!
+//! `POST /users/:id/erase` anonimiza de forma irreversible a un usuario: el nombre y el correo
+//! se sustituyen por marcadores y se borran el avatar, el motivo de suspensión y las
+//! preferencias. A diferencia de `DELETE /users/:id`, la fila se conserva, así que sus
+//! publicaciones, comentarios y equipos siguen apuntando a ella y los recuentos no varían.
+//!
+//! Los eventos del outbox que ya se habían registrado sobre el usuario se reescriben con los
+//! datos anonimizados, de modo que los pendientes no vuelven a sacar sus datos del sistema.
+//!
+//! Al no poder deshacerse, se hace en dos pasos: una petición sin token responde `202` con un
+//! token de confirmación de un solo uso, y solo reenviándolo antes de que caduque se borra.
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use chrono::{DateTime, Duration, Utc};
+use sha2::{Digest, Sha256};
+use sqlx::{Executor, Pool, Sqlite};
+use std::sync::Arc;
+use tracing::info;
+use uuid::Uuid;
+
+use crate::cache::Cache;
+use crate::clock::Clock;
+use crate::encryption::EmailEncryption;
+use crate::events::DomainEvent;
+use crate::fuzzy_search;
+use crate::handlers::error::AppError;
+use crate::handlers::user::remove_stored_avatar;
+use crate::ids::UserId;
+use crate::models::erasure::{erased_email, EraseUser, ErasureConfirmation, ERASED_NAME};
+use crate::models::user::{User, UserStatus, ValidationErrors, USER_COLUMNS};
+use crate::outbox::{self, Outbox};
+use crate::storage::Storage;
+use crate::tenant::{Database, Tenant};
+use crate::webhooks::generate_secret;
+
+/// Tiempo durante el que es válido un token de confirmación.
+const CONFIRMATION_TTL_MINUTES: i64 = 15;
+
+/// Solicita o confirma el borrado de los datos personales de un usuario.
+#[allow(clippy::too_many_arguments)]
+pub async fn erase_user(
+    UserId(user_id): UserId,
+    tenant: Tenant,
+    Database(database_pool): Database,
+    State(storage): State<Arc<dyn Storage>>,
+    State(encryption): State<EmailEncryption>,
+    State(outbox): State<Outbox>,
+    State(cache): State<Cache>,
+    State(clock): State<Arc<dyn Clock>>,
+    payload: Option<Json<EraseUser>>,
+) -> Result<Response, AppError> {
+    let now = clock.now();
+    let Some(token) = payload.and_then(|Json(payload)| payload.confirmation_token) else {
+        let confirmation = request_erasure(&database_pool, &tenant, user_id, now).await?;
+        return Ok((StatusCode::ACCEPTED, Json(confirmation)).into_response());
+    };
+
+    let (user, avatar_key) =
+        erase(&database_pool, &encryption, &tenant, user_id, &token, now).await?;
+    if let Some(avatar_key) = avatar_key {
+        remove_stored_avatar(storage.as_ref(), &avatar_key).await;
+    }
+
+    outbox.wake();
+    cache.invalidate_user(tenant.id(), user_id).await;
+    info!(%user_id, tenant = tenant.id(), "Datos personales del usuario borrados");
+
+    Ok(Json(user).into_response())
+}
+
+/// Emite un token de confirmación nuevo; reemplaza al anterior si había uno pendiente.
+async fn request_erasure(
+    database_pool: &Pool<Sqlite>,
+    tenant: &Tenant,
+    user_id: Uuid,
+    now: DateTime<Utc>,
+) -> Result<ErasureConfirmation, AppError> {
+    ensure_erasable(database_pool, tenant, user_id).await?;
+
+    let confirmation = ErasureConfirmation {
+        confirmation_token: generate_secret(),
+        expires_at: now + Duration::minutes(CONFIRMATION_TTL_MINUTES),
+    };
+    sqlx::query(
+        "INSERT INTO user_erasure_requests (user_id, token_hash, expires_at) VALUES (?, ?, ?) \
+         ON CONFLICT (user_id) DO UPDATE \
+         SET token_hash = excluded.token_hash, expires_at = excluded.expires_at",
+    )
+    .bind(user_id)
+    .bind(hash_token(&confirmation.confirmation_token))
+    .bind(confirmation.expires_at)
+    .execute(database_pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(confirmation)
+}
+
+/// Consume el token y anonimiza al usuario en una única transacción. Devuelve el usuario
+/// resultante y la clave del avatar que hay que borrar del almacén.
+async fn erase(
+    database_pool: &Pool<Sqlite>,
+    encryption: &EmailEncryption,
+    tenant: &Tenant,
+    user_id: Uuid,
+    token: &str,
+    now: DateTime<Utc>,
+) -> Result<(User, Option<String>), AppError> {
+    let mut transaction = database_pool.begin().await.map_err(AppError::from)?;
+    let avatar_key = ensure_erasable(&mut *transaction, tenant, user_id).await?;
+
+    let confirmed = sqlx::query(
+        "DELETE FROM user_erasure_requests \
+         WHERE user_id = ? AND token_hash = ? AND expires_at > ?",
+    )
+    .bind(user_id)
+    .bind(hash_token(token))
+    .bind(now)
+    .execute(&mut *transaction)
+    .await
+    .map_err(AppError::from)?
+    .rows_affected();
+    if confirmed == 0 {
+        let mut errors = ValidationErrors::new();
+        errors.push(
+            "confirmation_token",
+            "El token de confirmación no es válido o ha caducado",
+        );
+        return Err(AppError::validation(errors));
+    }
+
+    let user = sqlx::query_as::<_, User>(&format!(
+        "UPDATE users SET name = ?, email = ?, email_index = NULL, avatar_url = NULL, \
+         avatar_key = NULL, preferences = '{{}}', \
+         status = CASE status WHEN 'merged' THEN status ELSE ? END, suspended_until = NULL, \
+         suspension_reason = NULL, erased_at = ? WHERE id = ? RETURNING {USER_COLUMNS}"
+    ))
+    .bind(ERASED_NAME)
+    .bind(erased_email(user_id))
+    // Una cuenta fusionada conserva su estado terminal.
+    .bind(UserStatus::Deactivated)
+    .bind(now)
+    .bind(user_id)
+    .fetch_one(&mut *transaction)
+    .await
+    .map_err(AppError::from)?;
+    fuzzy_search::remove_user(&mut *transaction, user_id)
+        .await
+        .map_err(AppError::from)?;
+    // Webhooks, broker y CDC leen del outbox: basta con anonimizar sus eventos.
+    outbox::redact_user(&mut *transaction, encryption, &user)
+        .await
+        .map_err(AppError::from)?;
+
+    outbox::record(
+        &mut *transaction,
+        encryption,
+        DomainEvent::UserUpdated { user: user.clone() },
+    )
+    .await
+    .map_err(AppError::from)?;
+    transaction.commit().await.map_err(AppError::from)?;
+
+    Ok((user, avatar_key))
+}
+
+/// Comprueba que el usuario existe en el inquilino y no se ha borrado ya; devuelve la clave
+/// de su avatar.
+async fn ensure_erasable<'e, E>(
+    executor: E,
+    tenant: &Tenant,
+    user_id: Uuid,
+) -> Result<Option<String>, AppError>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    let (avatar_key, erased) = sqlx::query_as::<_, (Option<String>, bool)>(
+        "SELECT avatar_key, erased_at IS NOT NULL FROM users WHERE id = ? AND tenant_id = ?",
+    )
+    .bind(user_id)
+    .bind(tenant.id())
+    .fetch_optional(executor)
+    .await
+    .map_err(AppError::from)?
+    .ok_or_else(AppError::not_found)?;
+
+    if erased {
+        return Err(AppError::bad_request(
+            "Los datos personales de este usuario ya se borraron",
+        ));
+    }
+
+    Ok(avatar_key)
+}
+
+/// Hash con el que se guarda el token, para que una copia de la base no permita confirmar.
+fn hash_token(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
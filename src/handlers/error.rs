@@ -0,0 +1,388 @@
+//! Error común de la capa HTTP.
+//!
+//! Define `AppError`, que todos los handlers devuelven, y su conversión a respuestas JSON
+//! con un formato homogéneo (`message` y, opcionalmente, `errors` por campo).
+
+use std::time::Duration;
+
+use axum::{
+    extract::Request,
+    http::{header, HeaderValue, Method, StatusCode, Uri},
+    middleware::Next,
+    response::{IntoResponse, Response},
+    Extension, Json,
+};
+use serde::Serialize;
+use tower_http::request_id::RequestId;
+use tracing::{debug, error, warn};
+
+use crate::database::{self, DatabaseBusy};
+use crate::maintenance::MaintenanceStatus;
+use crate::models::user::{ValidationError, ValidationErrors};
+use crate::signing::SignatureError;
+use crate::tenant::{QuotaExceeded, QuotaKind};
+
+/// Segundos que se sugiere esperar (`Retry-After`) cuando la base está ocupada.
+const BUSY_RETRY_AFTER_SECS: u64 = 1;
+/// Segundos que se sugiere esperar antes de repetir una escritura durante el mantenimiento.
+const MAINTENANCE_RETRY_AFTER_SECS: u64 = 60;
+
+/// Forma serializada del error que se devolverá en las respuestas HTTP.
+#[derive(Debug, Serialize)]
+struct ErrorResponse {
+    message: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    errors: Option<Vec<FieldError>>,
+}
+
+/// Forma serializada de una cuota superada.
+#[derive(Debug, Serialize)]
+struct QuotaErrorResponse {
+    message: &'static str,
+    quota: QuotaExceeded,
+}
+
+/// Forma serializada de una escritura rechazada por el modo de mantenimiento.
+#[derive(Debug, Serialize)]
+struct MaintenanceErrorResponse {
+    message: &'static str,
+    maintenance: MaintenanceStatus,
+}
+
+/// Forma serializada de un método no admitido, con los que sí lo están.
+#[derive(Debug, Serialize)]
+struct MethodNotAllowedResponse {
+    message: &'static str,
+    allowed_methods: Vec<String>,
+}
+
+/// Error por campo utilizado para describir el detalle de validaciones fallidas.
+#[derive(Debug, Serialize)]
+struct FieldError {
+    field: &'static str,
+    message: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code: Option<&'static str>,
+}
+
+/// Error personalizado que agrupa distintas situaciones a nivel aplicación.
+#[derive(Debug)]
+pub struct AppError {
+    kind: AppErrorKind,
+}
+
+/// Enumeración interna para clasificar los errores posibles.
+#[derive(Debug)]
+enum AppErrorKind {
+    Validation(ValidationErrors),
+    BadRequest(&'static str),
+    Unauthorized,
+    Forbidden,
+    /// Petición firmada rechazada por [`crate::signing::require_signature`].
+    Signature(SignatureError),
+    QuotaExceeded(QuotaExceeded),
+    Maintenance(MaintenanceStatus),
+    /// Petición descartada por exceso de carga, con la espera sugerida.
+    Overloaded(Duration),
+    NotFound,
+    /// Conserva el valor de la cabecera `Allow` calculada por el router.
+    MethodNotAllowed(Option<HeaderValue>),
+    Sqlx(sqlx::Error),
+    Internal(anyhow::Error),
+}
+
+impl AppError {
+    /// Construye un error de validación.
+    pub(crate) fn validation(errors: ValidationErrors) -> Self {
+        Self {
+            kind: AppErrorKind::Validation(errors),
+        }
+    }
+
+    /// Construye un error por una solicitud que no se puede interpretar.
+    pub(crate) fn bad_request(message: &'static str) -> Self {
+        Self {
+            kind: AppErrorKind::BadRequest(message),
+        }
+    }
+
+    /// Construye un error por una solicitud que no identifica a quien la realiza.
+    pub(crate) fn unauthorized() -> Self {
+        Self {
+            kind: AppErrorKind::Unauthorized,
+        }
+    }
+
+    /// Construye un error por una operación que el usuario no tiene permitida.
+    pub(crate) fn forbidden() -> Self {
+        Self {
+            kind: AppErrorKind::Forbidden,
+        }
+    }
+
+    /// Construye un error por una cuota del inquilino agotada.
+    pub(crate) fn quota_exceeded(quota: QuotaExceeded) -> Self {
+        Self {
+            kind: AppErrorKind::QuotaExceeded(quota),
+        }
+    }
+
+    /// Construye un error por una escritura durante el modo de mantenimiento.
+    pub(crate) fn maintenance(status: MaintenanceStatus) -> Self {
+        Self {
+            kind: AppErrorKind::Maintenance(status),
+        }
+    }
+
+    /// Construye un error por una petición descartada al estar el servicio saturado.
+    pub(crate) fn overloaded(retry_after: Duration) -> Self {
+        Self {
+            kind: AppErrorKind::Overloaded(retry_after),
+        }
+    }
+
+    /// Construye un error interno inesperado que no proviene de la base de datos.
+    pub(crate) fn internal(error: anyhow::Error) -> Self {
+        Self {
+            kind: AppErrorKind::Internal(error),
+        }
+    }
+
+    /// Construye un error por un método no admitido en una ruta existente.
+    pub(crate) fn method_not_allowed(allow: Option<HeaderValue>) -> Self {
+        Self {
+            kind: AppErrorKind::MethodNotAllowed(allow),
+        }
+    }
+
+    /// Construye un error de tipo "recurso no encontrado".
+    pub(crate) fn not_found() -> Self {
+        Self {
+            kind: AppErrorKind::NotFound,
+        }
+    }
+
+    /// Indica si es un error de tipo "recurso no encontrado".
+    pub(crate) fn is_not_found(&self) -> bool {
+        matches!(self.kind, AppErrorKind::NotFound)
+    }
+}
+
+impl From<SignatureError> for AppError {
+    fn from(error: SignatureError) -> Self {
+        Self {
+            kind: AppErrorKind::Signature(error),
+        }
+    }
+}
+
+impl From<sqlx::Error> for AppError {
+    fn from(error: sqlx::Error) -> Self {
+        Self {
+            kind: AppErrorKind::Sqlx(error),
+        }
+    }
+}
+
+impl IntoResponse for AppError {
+    fn into_response(self) -> Response {
+        match self.kind {
+            AppErrorKind::Validation(errors) => {
+                let details = errors
+                    .errors
+                    .into_iter()
+                    .map(|error: ValidationError| FieldError {
+                        field: error.field,
+                        message: error.message,
+                        code: error.code,
+                    })
+                    .collect::<Vec<_>>();
+
+                let body = Json(ErrorResponse {
+                    message: "Datos de entrada inválidos",
+                    errors: Some(details),
+                });
+
+                (StatusCode::UNPROCESSABLE_ENTITY, body).into_response()
+            }
+            AppErrorKind::BadRequest(message) => (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse {
+                    message,
+                    errors: None,
+                }),
+            )
+                .into_response(),
+            AppErrorKind::Unauthorized => (
+                StatusCode::UNAUTHORIZED,
+                Json(ErrorResponse {
+                    message: "Debe identificarse mediante la cabecera X-User-Id",
+                    errors: None,
+                }),
+            )
+                .into_response(),
+            AppErrorKind::Forbidden => (
+                StatusCode::FORBIDDEN,
+                Json(ErrorResponse {
+                    message: "No tiene permiso para realizar esta operación",
+                    errors: None,
+                }),
+            )
+                .into_response(),
+            AppErrorKind::Signature(error) => (
+                error.status_code(),
+                Json(ErrorResponse {
+                    message: error.message(),
+                    errors: None,
+                }),
+            )
+                .into_response(),
+            AppErrorKind::QuotaExceeded(quota) => match quota.quota {
+                QuotaKind::MaxUsers => (
+                    StatusCode::FORBIDDEN,
+                    Json(QuotaErrorResponse {
+                        message: "El inquilino alcanzó su máximo de usuarios",
+                        quota,
+                    }),
+                )
+                    .into_response(),
+                QuotaKind::MaxRequestsPerDay => {
+                    let retry_after = quota
+                        .resets_at
+                        .map(|resets_at| (resets_at - chrono::Utc::now()).num_seconds().max(1))
+                        .unwrap_or(1);
+                    (
+                        StatusCode::TOO_MANY_REQUESTS,
+                        [(header::RETRY_AFTER, retry_after.to_string())],
+                        Json(QuotaErrorResponse {
+                            message: "El inquilino agotó su cuota diaria de peticiones",
+                            quota,
+                        }),
+                    )
+                        .into_response()
+                }
+            },
+            AppErrorKind::Maintenance(maintenance) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                [(
+                    header::RETRY_AFTER,
+                    MAINTENANCE_RETRY_AFTER_SECS.to_string(),
+                )],
+                Json(MaintenanceErrorResponse {
+                    message: "El servicio está en mantenimiento; solo se admiten lecturas",
+                    maintenance,
+                }),
+            )
+                .into_response(),
+            AppErrorKind::Overloaded(retry_after) => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                [(
+                    header::RETRY_AFTER,
+                    retry_after.as_secs().max(1).to_string(),
+                )],
+                Json(ErrorResponse {
+                    message: "El servicio está saturado, inténtelo de nuevo en unos segundos",
+                    errors: None,
+                }),
+            )
+                .into_response(),
+            AppErrorKind::NotFound => (
+                StatusCode::NOT_FOUND,
+                Json(ErrorResponse {
+                    message: "Recurso no encontrado",
+                    errors: None,
+                }),
+            )
+                .into_response(),
+            AppErrorKind::MethodNotAllowed(allow) => {
+                let allowed_methods = allow
+                    .as_ref()
+                    .and_then(|allow| allow.to_str().ok())
+                    .map(|allow| {
+                        allow
+                            .split(',')
+                            .map(str::trim)
+                            .filter(|method| !method.is_empty())
+                            .map(str::to_string)
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                let mut response = (
+                    StatusCode::METHOD_NOT_ALLOWED,
+                    Json(MethodNotAllowedResponse {
+                        message: "Método no permitido para este recurso",
+                        allowed_methods,
+                    }),
+                )
+                    .into_response();
+                if let Some(allow) = allow {
+                    response.headers_mut().insert(header::ALLOW, allow);
+                }
+                response
+            }
+            AppErrorKind::Sqlx(error) if database::is_busy(&error) => {
+                warn!(?error, "Base de datos ocupada");
+                let mut response = (
+                    StatusCode::SERVICE_UNAVAILABLE,
+                    [(header::RETRY_AFTER, BUSY_RETRY_AFTER_SECS.to_string())],
+                    Json(ErrorResponse {
+                        message: "La base de datos está ocupada, inténtelo de nuevo",
+                        errors: None,
+                    }),
+                )
+                    .into_response();
+                response.extensions_mut().insert(DatabaseBusy);
+                response
+            }
+            AppErrorKind::Sqlx(error) => {
+                error!(?error, "Error en la base de datos");
+                unexpected_error_response()
+            }
+            AppErrorKind::Internal(error) => {
+                error!(?error, "Error interno");
+                unexpected_error_response()
+            }
+        }
+    }
+}
+
+/// Middleware que sustituye la respuesta vacía que genera Axum ante un método no admitido
+/// por el formato de error común, conservando la cabecera `Allow`.
+///
+/// Debe envolver al router completo: dentro de él la cabecera aún no se ha calculado.
+pub async fn json_method_not_allowed(request: Request, next: Next) -> Response {
+    let response = next.run(request).await;
+    if response.status() != StatusCode::METHOD_NOT_ALLOWED {
+        return response;
+    }
+
+    let allow = response.headers().get(header::ALLOW).cloned();
+    AppError::method_not_allowed(allow).into_response()
+}
+
+/// Fallback global: las rutas que no existen responden con el error común en lugar de un
+/// `404` sin cuerpo.
+pub async fn not_found_fallback(
+    method: Method,
+    uri: Uri,
+    request_id: Option<Extension<RequestId>>,
+) -> AppError {
+    let request_id = request_id
+        .as_ref()
+        .and_then(|Extension(id)| id.header_value().to_str().ok())
+        .unwrap_or("-");
+    debug!(%method, %uri, request_id, "Ruta no encontrada");
+    AppError::not_found()
+}
+
+/// Respuesta genérica para errores inesperados, sin filtrar detalles internos.
+fn unexpected_error_response() -> Response {
+    (
+        StatusCode::INTERNAL_SERVER_ERROR,
+        Json(ErrorResponse {
+            message: "Ocurrió un error inesperado",
+            errors: None,
+        }),
+    )
+        .into_response()
+}
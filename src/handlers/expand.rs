@@ -0,0 +1,156 @@
+//! Carga de las relaciones pedidas con `?expand=`.
+
+use std::collections::HashMap;
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Query},
+    http::request::Parts,
+};
+use sqlx::{QueryBuilder, Sqlite, SqlitePool};
+use uuid::Uuid;
+
+use crate::handlers::error::AppError;
+use crate::models::comment::{Comment, COMMENT_COLUMNS};
+use crate::models::expand::{ExpandedPost, ExpandedUser, UserExpansions, MAX_EXPANDED_ITEMS};
+use crate::models::post::{Post, POST_COLUMNS};
+use crate::models::projection::Projected;
+use crate::models::tag::Tag;
+use crate::models::team::Team;
+use crate::models::user::User;
+
+/// Parámetro de consulta con las relaciones separadas por comas.
+const EXPAND_PARAMETER: &str = "expand";
+
+/// Incrusta en `user` las relaciones pedidas, con un máximo de [`MAX_EXPANDED_ITEMS`]
+/// elementos por relación.
+pub(crate) async fn expand_user(
+    database_pool: &SqlitePool,
+    user_id: Uuid,
+    user: Projected<User>,
+    expansions: UserExpansions,
+) -> Result<ExpandedUser, AppError> {
+    let mut expanded = ExpandedUser {
+        user,
+        posts: None,
+        teams: None,
+        tags: None,
+    };
+
+    if expansions.posts {
+        let posts = sqlx::query_as::<_, Post>(&format!(
+            "SELECT {POST_COLUMNS} FROM posts WHERE author_id = ? \
+             ORDER BY created_at DESC LIMIT ?"
+        ))
+        .bind(user_id)
+        .bind(MAX_EXPANDED_ITEMS)
+        .fetch_all(database_pool)
+        .await
+        .map_err(AppError::from)?;
+
+        let mut comments = if expansions.post_comments {
+            Some(fetch_comments(database_pool, &posts).await?)
+        } else {
+            None
+        };
+        expanded.posts = Some(
+            posts
+                .into_iter()
+                .map(|post| ExpandedPost {
+                    comments: comments
+                        .as_mut()
+                        .map(|comments| comments.remove(&post.id).unwrap_or_default()),
+                    post,
+                })
+                .collect(),
+        );
+    }
+
+    if expansions.teams {
+        let teams = sqlx::query_as::<_, Team>(
+            "SELECT teams.id, teams.name, teams.created_at, teams.updated_at FROM teams \
+             JOIN team_members ON team_members.team_id = teams.id \
+             WHERE team_members.user_id = ? ORDER BY teams.name LIMIT ?",
+        )
+        .bind(user_id)
+        .bind(MAX_EXPANDED_ITEMS)
+        .fetch_all(database_pool)
+        .await
+        .map_err(AppError::from)?;
+        expanded.teams = Some(teams);
+    }
+
+    if expansions.tags {
+        let tags = sqlx::query_as::<_, Tag>(
+            "SELECT tags.id, tags.name, tags.created_at FROM tags \
+             JOIN user_tags ON user_tags.tag_id = tags.id \
+             WHERE user_tags.user_id = ? ORDER BY tags.name LIMIT ?",
+        )
+        .bind(user_id)
+        .bind(MAX_EXPANDED_ITEMS)
+        .fetch_all(database_pool)
+        .await
+        .map_err(AppError::from)?;
+        expanded.tags = Some(tags);
+    }
+
+    Ok(expanded)
+}
+
+/// Primeros comentarios visibles de cada publicación, en una única consulta.
+async fn fetch_comments(
+    database_pool: &SqlitePool,
+    posts: &[Post],
+) -> Result<HashMap<Uuid, Vec<Comment>>, AppError> {
+    let mut comments_by_post = HashMap::<Uuid, Vec<Comment>>::new();
+    if posts.is_empty() {
+        return Ok(comments_by_post);
+    }
+
+    let mut builder = QueryBuilder::<Sqlite>::new(format!(
+        "SELECT {COMMENT_COLUMNS} FROM (SELECT {COMMENT_COLUMNS}, ROW_NUMBER() OVER \
+         (PARTITION BY post_id ORDER BY created_at, id) AS position FROM comments \
+         WHERE deleted_at IS NULL AND post_id IN ("
+    ));
+    let mut separated = builder.separated(", ");
+    for post in posts {
+        separated.push_bind(post.id);
+    }
+    builder
+        .push(")) WHERE position <= ")
+        .push_bind(MAX_EXPANDED_ITEMS)
+        .push(" ORDER BY created_at, id");
+
+    let comments = builder
+        .build_query_as::<Comment>()
+        .fetch_all(database_pool)
+        .await
+        .map_err(AppError::from)?;
+    for comment in comments {
+        comments_by_post
+            .entry(comment.post_id)
+            .or_default()
+            .push(comment);
+    }
+
+    Ok(comments_by_post)
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for UserExpansions
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(parameters) = Query::<HashMap<String, String>>::from_request_parts(parts, state)
+            .await
+            .map_err(|_| AppError::bad_request("Parámetros de consulta inválidos"))?;
+
+        match parameters.get(EXPAND_PARAMETER) {
+            Some(value) => UserExpansions::try_from(value.as_str()).map_err(AppError::validation),
+            None => Ok(Self::default()),
+        }
+    }
+}
@@ -0,0 +1,158 @@
+//! Handlers HTTP de las exportaciones asíncronas de usuarios.
+//!
+//! La fila de `exports` y su trabajo viven en la base principal, donde está la cola, aunque
+//! el inquilino tenga base propia; cada exportación guarda su inquilino y solo se muestra
+//! dentro de él. La descarga no pide inquilino: la autoriza la firma del enlace.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
+    Json,
+};
+use sqlx::{QueryBuilder, Sqlite, SqlitePool};
+use uuid::Uuid;
+
+use crate::clock::Clock;
+use crate::config::AppConfig;
+use crate::encryption::EmailEncryption;
+use crate::export::job::ExportJob;
+use crate::export::link::{self, DownloadLink};
+use crate::handlers::error::AppError;
+use crate::handlers::user::push_user_filters;
+use crate::jobs;
+use crate::models::export::{CreateExport, Export, ExportStatus, EXPORT_COLUMNS};
+use crate::storage::Storage;
+use crate::tenant::Tenant;
+
+/// Registra una exportación de los usuarios del inquilino y encola el trabajo que la genera.
+/// Responde `202` con la exportación en estado `pending`.
+pub async fn create_export(
+    tenant: Tenant,
+    State(database_pool): State<SqlitePool>,
+    State(encryption): State<EmailEncryption>,
+    State(clock): State<Arc<dyn Clock>>,
+    Json(payload): Json<CreateExport>,
+) -> Result<(StatusCode, [(header::HeaderName, String); 1], Json<Export>), AppError> {
+    // Los parámetros se validan ahora para no encolar un trabajo que nunca podría terminar.
+    let mut scratch = QueryBuilder::<Sqlite>::new("");
+    push_user_filters(&mut scratch, &tenant, &encryption, payload.query.clone())?;
+
+    let export_id = Uuid::new_v4();
+    let mut transaction = database_pool.begin().await.map_err(AppError::from)?;
+    let export = sqlx::query_as::<_, Export>(&format!(
+        "INSERT INTO exports (id, tenant_id, format, status, created_at) \
+         VALUES (?, ?, ?, 'pending', ?) RETURNING {EXPORT_COLUMNS}"
+    ))
+    .bind(export_id)
+    .bind(tenant.id())
+    .bind(payload.format)
+    .bind(clock.now())
+    .fetch_one(&mut *transaction)
+    .await
+    .map_err(AppError::from)?;
+    let job = ExportJob {
+        export_id,
+        tenant_id: tenant.0,
+        format: payload.format,
+        query: payload.query,
+    };
+    jobs::enqueue(&mut *transaction, &job)
+        .await
+        .map_err(AppError::internal)?;
+    transaction.commit().await.map_err(AppError::from)?;
+
+    Ok((
+        StatusCode::ACCEPTED,
+        [(header::LOCATION, format!("/exports/{export_id}"))],
+        Json(export),
+    ))
+}
+
+/// Devuelve el estado de una exportación del inquilino y, si está completada, un enlace de
+/// descarga válido durante `EXPORT_URL_TTL_SECS`.
+pub async fn get_export(
+    tenant: Tenant,
+    State(database_pool): State<SqlitePool>,
+    State(config): State<Arc<AppConfig>>,
+    State(clock): State<Arc<dyn Clock>>,
+    Path(export_id): Path<Uuid>,
+) -> Result<Json<Export>, AppError> {
+    let mut export = sqlx::query_as::<_, Export>(&format!(
+        "SELECT {EXPORT_COLUMNS} FROM exports WHERE id = ? AND tenant_id = ?"
+    ))
+    .bind(export_id)
+    .bind(tenant.id())
+    .fetch_optional(&database_pool)
+    .await
+    .map_err(AppError::from)?
+    .ok_or_else(AppError::not_found)?;
+
+    if export.status == ExportStatus::Completed {
+        export.download_url = Some(link::download_url(&config, export.id, clock.now()));
+    }
+
+    Ok(Json(export))
+}
+
+/// Descarga el archivo de una exportación completada con un enlace firmado. Responde `403`
+/// si la firma no corresponde o el enlace caducó.
+pub async fn download_export(
+    State(database_pool): State<SqlitePool>,
+    State(config): State<Arc<AppConfig>>,
+    State(clock): State<Arc<dyn Clock>>,
+    State(storage): State<Arc<dyn Storage>>,
+    Path(export_id): Path<Uuid>,
+    Query(download): Query<DownloadLink>,
+) -> Result<Response, AppError> {
+    if !download.verify(&config, export_id, clock.now()) {
+        return Err(AppError::forbidden());
+    }
+
+    let export = sqlx::query_as::<_, Export>(&format!(
+        "SELECT {EXPORT_COLUMNS} FROM exports WHERE id = ? AND status = 'completed'"
+    ))
+    .bind(export_id)
+    .fetch_optional(&database_pool)
+    .await
+    .map_err(AppError::from)?
+    .ok_or_else(AppError::not_found)?;
+    let key = export.storage_key.ok_or_else(AppError::not_found)?;
+
+    // Con almacenes remotos el cliente descarga directamente del bucket.
+    if let Some(url) = storage.presigned_url(&key) {
+        return Ok((
+            StatusCode::TEMPORARY_REDIRECT,
+            [
+                (header::LOCATION, url),
+                (header::CACHE_CONTROL, "no-store".to_string()),
+            ],
+        )
+            .into_response());
+    }
+
+    let contents = storage
+        .get(&key)
+        .await
+        .map_err(AppError::internal)?
+        .ok_or_else(AppError::not_found)?;
+    let disposition = format!(
+        "attachment; filename=\"users-{export_id}.{}\"",
+        export.format.extension()
+    );
+
+    Ok((
+        [
+            (
+                header::CONTENT_TYPE,
+                export.format.content_type().to_string(),
+            ),
+            (header::CONTENT_DISPOSITION, disposition),
+            (header::CACHE_CONTROL, "no-store".to_string()),
+        ],
+        contents,
+    )
+        .into_response())
+}
@@ -0,0 +1,99 @@
+//! Selección de campos mediante `?fields=`.
+//!
+//! El extractor [`SparseFields`] valida la lista contra los campos del modelo y los
+//! handlers la aplican a la respuesta con [`SparseFields::project`].
+
+use std::{collections::HashMap, marker::PhantomData, sync::Arc};
+
+use axum::{
+    async_trait,
+    extract::{FromRequestParts, Query},
+    http::request::Parts,
+};
+
+use crate::handlers::error::AppError;
+use crate::models::projection::{Projectable, Projected};
+use crate::models::user::ValidationErrors;
+
+/// Parámetro de consulta con la lista de campos separados por comas.
+const FIELDS_PARAMETER: &str = "fields";
+
+/// Campos de `T` pedidos por el cliente; sin `?fields=` se devuelven todos.
+#[derive(Debug, Clone)]
+pub struct SparseFields<T> {
+    fields: Option<Arc<[&'static str]>>,
+    model: PhantomData<fn() -> T>,
+}
+
+impl<T: Projectable> SparseFields<T> {
+    /// Selección que devuelve todos los campos.
+    pub fn all() -> Self {
+        Self {
+            fields: None,
+            model: PhantomData,
+        }
+    }
+
+    /// Interpreta una lista como `id,name`, conservando el orden de [`Projectable::FIELDS`].
+    pub fn parse(value: &str) -> Result<Self, ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+        let requested = value
+            .split(',')
+            .map(str::trim)
+            .filter(|field| !field.is_empty())
+            .collect::<Vec<_>>();
+
+        if requested.is_empty() {
+            errors.push(FIELDS_PARAMETER, "Debe indicar al menos un campo");
+        } else if requested.iter().any(|field| !T::FIELDS.contains(field)) {
+            errors.push(FIELDS_PARAMETER, "Contiene un campo desconocido");
+        }
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        let fields = T::FIELDS
+            .iter()
+            .copied()
+            .filter(|field| requested.contains(field))
+            .collect();
+        Ok(Self {
+            fields: Some(fields),
+            model: PhantomData,
+        })
+    }
+
+    /// Aplica la selección a un valor.
+    pub fn project(&self, value: T) -> Projected<T> {
+        Projected::new(value, self.fields.clone())
+    }
+
+    /// Aplica la selección a cada elemento de un listado.
+    pub fn project_all(&self, values: Vec<T>) -> Vec<Projected<T>> {
+        values
+            .into_iter()
+            .map(|value| self.project(value))
+            .collect()
+    }
+}
+
+#[async_trait]
+impl<S, T> FromRequestParts<S> for SparseFields<T>
+where
+    S: Send + Sync,
+    T: Projectable,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Query(parameters) = Query::<HashMap<String, String>>::from_request_parts(parts, state)
+            .await
+            .map_err(|_| AppError::bad_request("Parámetros de consulta inválidos"))?;
+
+        match parameters.get(FIELDS_PARAMETER) {
+            Some(value) => Self::parse(value).map_err(AppError::validation),
+            None => Ok(Self::all()),
+        }
+    }
+}
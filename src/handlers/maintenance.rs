@@ -0,0 +1,40 @@
+//! Handlers HTTP para consultar y cambiar el modo de mantenimiento.
+
+use axum::{extract::State, Json};
+use serde::Deserialize;
+
+use crate::handlers::error::AppError;
+use crate::maintenance::{Maintenance, MaintenanceStatus};
+
+/// Cuerpo de `PUT /admin/maintenance`.
+#[derive(Debug, Deserialize)]
+pub struct SetMaintenance {
+    pub enabled: bool,
+    /// Motivo que se muestra a los clientes mientras dure.
+    pub reason: Option<String>,
+}
+
+/// Devuelve el estado del modo de mantenimiento.
+pub async fn get_maintenance(State(maintenance): State<Maintenance>) -> Json<MaintenanceStatus> {
+    Json(maintenance.status())
+}
+
+/// Activa o desactiva el modo de mantenimiento, lo publica para el resto de réplicas y
+/// devuelve el estado resultante.
+pub async fn set_maintenance(
+    State(maintenance): State<Maintenance>,
+    Json(payload): Json<SetMaintenance>,
+) -> Result<Json<MaintenanceStatus>, AppError> {
+    if payload.enabled {
+        let reason = payload
+            .reason
+            .map(|reason| reason.trim().to_string())
+            .filter(|reason| !reason.is_empty());
+        maintenance.enable(reason);
+    } else {
+        maintenance.disable();
+    }
+    maintenance.publish().await.map_err(AppError::internal)?;
+
+    Ok(Json(maintenance.status()))
+}
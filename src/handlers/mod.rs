@@ -1 +1,23 @@
+pub mod actor;
+pub mod backup;
+pub mod comment;
+pub mod email_domain;
+pub mod erasure;
+pub mod error;
+pub mod expand;
+pub mod export;
+pub mod fields;
+pub mod maintenance;
+pub mod policy;
+pub mod post;
+pub mod preferences;
+pub mod stats;
+pub mod tag;
+pub mod team;
+pub mod tenant;
+pub mod total_count;
 pub mod user;
+pub mod v2;
+pub mod validated;
+pub mod view;
+pub mod webhook;
@@ -0,0 +1,170 @@
+//! Política de autorización.
+//!
+//! Qué puede hacer cada cual se declara aquí, una acción por tipo, en lugar de repartir
+//! comprobaciones de rol por los handlers. Cada acción implementa [`TeamAction`] indicando la
+//! [`Rule`] que exige, y los handlers la hacen cumplir pidiendo el extractor
+//! [`Authorized<Acción>`](Authorized): si la petición llega al cuerpo del handler es que está
+//! permitida.
+//!
+//! Por ahora solo hay reglas sobre equipos, porque son el único recurso con roles. Reglas
+//! como «cada usuario solo se modifica a sí mismo» o «un administrador puede borrar a
+//! cualquiera» necesitan que `/users` identifique a quien llama y un rol global de
+//! administrador; cuando existan se declararán aquí de la misma forma.
+
+use std::{collections::HashMap, marker::PhantomData, sync::Arc};
+
+use axum::{
+    async_trait,
+    extract::{FromRef, FromRequestParts, Path},
+    http::request::Parts,
+};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::handlers::actor::Actor;
+use crate::handlers::error::AppError;
+use crate::ids::{IdGenerator, UserId};
+use crate::models::team::TeamRole;
+use crate::tenant::{Database, Tenant};
+
+/// Condición que debe cumplir quien realiza la petición respecto al equipo.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    /// Pertenecer al equipo, con cualquier rol.
+    Member,
+    /// Ser `owner` del equipo.
+    Owner,
+    /// Ser `owner` o ser el propio usuario de la ruta (`:user_id`).
+    OwnerOrSelf,
+}
+
+impl Rule {
+    /// Decide si alguien con `role` en el equipo cumple la regla; `is_self` indica si el
+    /// usuario de la ruta es el propio actor.
+    pub fn allows(self, role: Option<TeamRole>, is_self: bool) -> bool {
+        match (self, role) {
+            (_, None) => false,
+            (_, Some(TeamRole::Owner)) => true,
+            (Self::Member, Some(TeamRole::Member)) => true,
+            (Self::OwnerOrSelf, Some(TeamRole::Member)) => is_self,
+            (Self::Owner, Some(TeamRole::Member)) => false,
+        }
+    }
+}
+
+/// Acción sobre un equipo sujeta a autorización.
+pub trait TeamAction: Send + Sync + 'static {
+    /// Regla que debe cumplirse para realizarla.
+    const RULE: Rule;
+}
+
+/// Consultar un equipo.
+pub struct ViewTeam;
+/// Renombrar un equipo.
+pub struct RenameTeam;
+/// Eliminar un equipo.
+pub struct DeleteTeam;
+/// Listar los miembros de un equipo.
+pub struct ListMembers;
+/// Añadir un miembro.
+pub struct AddMember;
+/// Cambiar el rol de un miembro.
+pub struct ChangeMemberRole;
+/// Retirar a un miembro; cada miembro puede retirarse a sí mismo.
+pub struct RemoveMember;
+
+impl TeamAction for ViewTeam {
+    const RULE: Rule = Rule::Member;
+}
+impl TeamAction for RenameTeam {
+    const RULE: Rule = Rule::Owner;
+}
+impl TeamAction for DeleteTeam {
+    const RULE: Rule = Rule::Owner;
+}
+impl TeamAction for ListMembers {
+    const RULE: Rule = Rule::Member;
+}
+impl TeamAction for AddMember {
+    const RULE: Rule = Rule::Owner;
+}
+impl TeamAction for ChangeMemberRole {
+    const RULE: Rule = Rule::Owner;
+}
+impl TeamAction for RemoveMember {
+    const RULE: Rule = Rule::OwnerOrSelf;
+}
+
+/// Permiso comprobado para realizar la acción `A` sobre el equipo de la ruta (`:id`).
+///
+/// Responde `401` sin `X-User-Id`, `404` si el equipo no existe en el inquilino o su
+/// identificador no es válido y `403` si la política no lo permite.
+#[derive(Debug)]
+pub struct Authorized<A> {
+    /// Quien realiza la petición.
+    pub actor: Actor,
+    /// Equipo sobre el que actúa.
+    pub team_id: Uuid,
+    /// Rol de quien realiza la petición en el equipo.
+    pub role: TeamRole,
+    action: PhantomData<A>,
+}
+
+#[async_trait]
+impl<S, A> FromRequestParts<S> for Authorized<A>
+where
+    A: TeamAction,
+    Arc<dyn IdGenerator>: FromRef<S>,
+    SqlitePool: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let actor = Actor::from_request_parts(parts, state).await?;
+        let Path(params) = Path::<HashMap<String, String>>::from_request_parts(parts, state)
+            .await
+            .map_err(|_| AppError::not_found())?;
+        let team_id = params
+            .get("id")
+            .and_then(|value| Uuid::parse_str(value).ok())
+            .ok_or_else(AppError::not_found)?;
+        let is_self = match A::RULE {
+            Rule::OwnerOrSelf => UserId::from_request_parts(parts, state).await?.0 == actor.0,
+            Rule::Member | Rule::Owner => false,
+        };
+        let tenant = Tenant::from_request_parts(parts, state).await?;
+        let Database(database_pool) = Database::from_request_parts(parts, state).await?;
+
+        // Una sola consulta distingue "el equipo no existe" de "no perteneces a él". Un
+        // equipo existe en el inquilino si alguno de sus miembros es usuario de él.
+        let (team_exists, role) = sqlx::query_as::<_, (bool, Option<TeamRole>)>(
+            "SELECT EXISTS (SELECT 1 FROM team_members \
+             JOIN users ON users.id = team_members.user_id \
+             WHERE team_members.team_id = ?1 AND users.tenant_id = ?3), \
+             (SELECT team_members.role FROM team_members \
+             JOIN users ON users.id = team_members.user_id \
+             WHERE team_members.team_id = ?1 AND team_members.user_id = ?2 \
+             AND users.tenant_id = ?3)",
+        )
+        .bind(team_id)
+        .bind(actor.0)
+        .bind(tenant.id())
+        .fetch_one(&database_pool)
+        .await
+        .map_err(AppError::from)?;
+
+        if !team_exists {
+            return Err(AppError::not_found());
+        }
+        match role {
+            Some(role) if A::RULE.allows(Some(role), is_self) => Ok(Self {
+                actor,
+                team_id,
+                role,
+                action: PhantomData,
+            }),
+            _ => Err(AppError::forbidden()),
+        }
+    }
+}
@@ -0,0 +1,195 @@
+//! Handlers HTTP para gestionar publicaciones.
+//!
+//! Siguen el mismo esquema que los de usuarios: validación mediante `TryFrom` hacia los
+//! modelos de dominio y errores homogéneos a través de [`AppError`]. Las publicaciones no
+//! guardan el inquilino: pertenecen al de su autor, y cada consulta se limita a los autores
+//! del [`Tenant`] resuelto.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use sqlx::{Pool, Sqlite};
+use uuid::Uuid;
+
+use crate::clock::Clock;
+use crate::handlers::error::AppError;
+use crate::handlers::user::ensure_user_exists;
+use crate::handlers::validated::ValidatedJson;
+use crate::ids::UserId;
+use crate::models::post::{NewPost, Post, PostChanges, POST_COLUMNS};
+use crate::models::user::ValidationErrors;
+use crate::tenant::{Database, Tenant};
+
+/// Condición que limita `posts` a las publicaciones de autores del inquilino indicado.
+pub(crate) const TENANT_POSTS: &str = "author_id IN (SELECT id FROM users WHERE tenant_id = ?)";
+
+/// Devuelve las publicaciones del inquilino, de la más reciente a la más antigua.
+pub async fn list_posts(
+    tenant: Tenant,
+    Database(database_pool): Database,
+) -> Result<Json<Vec<Post>>, AppError> {
+    let posts = sqlx::query_as::<_, Post>(&format!(
+        "SELECT {POST_COLUMNS} FROM posts WHERE {TENANT_POSTS} ORDER BY created_at DESC"
+    ))
+    .bind(tenant.id())
+    .fetch_all(&database_pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(Json(posts))
+}
+
+/// Devuelve las publicaciones de un usuario del inquilino, de la más reciente a la más antigua.
+pub async fn list_user_posts(
+    UserId(user_id): UserId,
+    tenant: Tenant,
+    Database(database_pool): Database,
+) -> Result<Json<Vec<Post>>, AppError> {
+    ensure_user_exists(&database_pool, &tenant, user_id).await?;
+
+    let posts = sqlx::query_as::<_, Post>(&format!(
+        "SELECT {POST_COLUMNS} FROM posts WHERE author_id = ? ORDER BY created_at DESC"
+    ))
+    .bind(user_id)
+    .fetch_all(&database_pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(Json(posts))
+}
+
+/// Recupera una publicación del inquilino por su identificador.
+pub async fn get_post(
+    Path(post_id): Path<Uuid>,
+    tenant: Tenant,
+    Database(database_pool): Database,
+) -> Result<Json<Post>, AppError> {
+    let post = sqlx::query_as::<_, Post>(&format!(
+        "SELECT {POST_COLUMNS} FROM posts WHERE id = ? AND {TENANT_POSTS}"
+    ))
+    .bind(post_id)
+    .bind(tenant.id())
+    .fetch_optional(&database_pool)
+    .await
+    .map_err(AppError::from)?
+    .ok_or_else(AppError::not_found)?;
+
+    Ok(Json(post))
+}
+
+/// Crea una publicación para un autor existente del inquilino.
+pub async fn create_post(
+    tenant: Tenant,
+    Database(database_pool): Database,
+    State(clock): State<Arc<dyn Clock>>,
+    ValidatedJson(validated_post): ValidatedJson<NewPost>,
+) -> Result<(StatusCode, Json<Post>), AppError> {
+    ensure_author_exists(&database_pool, &tenant, validated_post.author_id).await?;
+    let now = clock.now();
+
+    let post = sqlx::query_as::<_, Post>(&format!(
+        "INSERT INTO posts (id, author_id, title, body, created_at, updated_at) \
+         VALUES (?, ?, ?, ?, ?, ?) RETURNING {POST_COLUMNS}"
+    ))
+    .bind(Uuid::new_v4())
+    .bind(validated_post.author_id)
+    .bind(&validated_post.title)
+    .bind(&validated_post.body)
+    .bind(now)
+    .bind(now)
+    .fetch_one(&database_pool)
+    .await
+    .map_err(|error| {
+        if is_foreign_key_violation(&error) {
+            unknown_author_error()
+        } else {
+            AppError::from(error)
+        }
+    })?;
+
+    Ok((StatusCode::CREATED, Json(post)))
+}
+
+/// Actualiza el título y/o el cuerpo de una publicación del inquilino.
+pub async fn update_post(
+    Path(post_id): Path<Uuid>,
+    tenant: Tenant,
+    Database(database_pool): Database,
+    State(clock): State<Arc<dyn Clock>>,
+    ValidatedJson(changes): ValidatedJson<PostChanges>,
+) -> Result<Json<Post>, AppError> {
+    let post = sqlx::query_as::<_, Post>(&format!(
+        "UPDATE posts SET title = COALESCE(?, title), body = COALESCE(?, body), \
+         updated_at = ? WHERE id = ? AND {TENANT_POSTS} RETURNING {POST_COLUMNS}"
+    ))
+    .bind(changes.title)
+    .bind(changes.body)
+    .bind(clock.now())
+    .bind(post_id)
+    .bind(tenant.id())
+    .fetch_optional(&database_pool)
+    .await
+    .map_err(AppError::from)?
+    .ok_or_else(AppError::not_found)?;
+
+    Ok(Json(post))
+}
+
+/// Elimina una publicación del inquilino si existe.
+pub async fn delete_post(
+    Path(post_id): Path<Uuid>,
+    tenant: Tenant,
+    Database(database_pool): Database,
+) -> Result<StatusCode, AppError> {
+    let result = sqlx::query(&format!(
+        "DELETE FROM posts WHERE id = ? AND {TENANT_POSTS}"
+    ))
+    .bind(post_id)
+    .bind(tenant.id())
+    .execute(&database_pool)
+    .await
+    .map_err(AppError::from)?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found());
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Comprueba que el autor pertenezca al inquilino; si no, el error señala `author_id`.
+pub(crate) async fn ensure_author_exists(
+    database_pool: &Pool<Sqlite>,
+    tenant: &Tenant,
+    author_id: Uuid,
+) -> Result<(), AppError> {
+    ensure_user_exists(database_pool, tenant, author_id)
+        .await
+        .map_err(|error| {
+            if error.is_not_found() {
+                unknown_author_error()
+            } else {
+                error
+            }
+        })
+}
+
+fn unknown_author_error() -> AppError {
+    let mut errors = ValidationErrors::new();
+    errors.push(
+        "author_id",
+        "No existe ningún usuario con ese identificador",
+    );
+    AppError::validation(errors)
+}
+
+/// Indica si el error proviene de una clave foránea que no se cumple.
+pub(crate) fn is_foreign_key_violation(error: &sqlx::Error) -> bool {
+    error
+        .as_database_error()
+        .is_some_and(|database_error| database_error.is_foreign_key_violation())
+}
@@ -0,0 +1,75 @@
+//! Handlers HTTP para las preferencias de los usuarios.
+//!
+//! `PUT /users/:id/preferences` fusiona los campos enviados con las preferencias guardadas
+//! en lugar de reemplazarlas, así que los clientes pueden cambiar un único ajuste.
+
+use axum::Json;
+use sqlx::{types::Json as SqlJson, Executor, Sqlite};
+use uuid::Uuid;
+
+use crate::handlers::error::AppError;
+use crate::ids::UserId;
+use crate::models::preferences::{UpdatePreferences, UserPreferences};
+use crate::tenant::{Database, Tenant};
+
+/// Devuelve las preferencias de un usuario, con los valores por defecto aplicados.
+pub async fn get_preferences(
+    UserId(user_id): UserId,
+    tenant: Tenant,
+    Database(database_pool): Database,
+) -> Result<Json<UserPreferences>, AppError> {
+    let preferences = fetch_preferences(&database_pool, &tenant, user_id)
+        .await
+        .map_err(AppError::from)?
+        .ok_or_else(AppError::not_found)?;
+
+    Ok(Json(preferences))
+}
+
+/// Valida los cambios y los fusiona con las preferencias actuales del usuario.
+pub async fn update_preferences(
+    UserId(user_id): UserId,
+    tenant: Tenant,
+    Database(database_pool): Database,
+    Json(payload): Json<UpdatePreferences>,
+) -> Result<Json<UserPreferences>, AppError> {
+    let mut transaction = database_pool.begin().await.map_err(AppError::from)?;
+    let current = fetch_preferences(&mut *transaction, &tenant, user_id)
+        .await
+        .map_err(AppError::from)?
+        .ok_or_else(AppError::not_found)?;
+
+    let merged = current.merge(payload).map_err(AppError::validation)?;
+
+    sqlx::query("UPDATE users SET preferences = ? WHERE id = ? AND tenant_id = ?")
+        .bind(SqlJson(&merged))
+        .bind(user_id)
+        .bind(tenant.id())
+        .execute(&mut *transaction)
+        .await
+        .map_err(AppError::from)?;
+    transaction.commit().await.map_err(AppError::from)?;
+
+    Ok(Json(merged))
+}
+
+/// Lee y decodifica el documento de preferencias, o `None` si el usuario no existe en el
+/// inquilino.
+async fn fetch_preferences<'e, E>(
+    executor: E,
+    tenant: &Tenant,
+    user_id: Uuid,
+) -> Result<Option<UserPreferences>, sqlx::Error>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    let preferences = sqlx::query_scalar::<_, SqlJson<UserPreferences>>(
+        "SELECT preferences FROM users WHERE id = ? AND tenant_id = ?",
+    )
+    .bind(user_id)
+    .bind(tenant.id())
+    .fetch_optional(executor)
+    .await?;
+
+    Ok(preferences.map(|SqlJson(preferences)| preferences))
+}
@@ -0,0 +1,265 @@
+//! Handlers HTTP de las estadísticas agregadas, pensadas para paneles sencillos.
+//!
+//! Las agregaciones se resuelven en SQL sobre el índice `(tenant_id, created_at)`. La única
+//! excepción son los dominios de correo con el cifrado activo: el correo guardado no deja ver
+//! el dominio, así que se descifran los de la ventana y se cuentan aquí. Las peticiones se
+//! leen del resumen diario `daily_user_stats`, que rellena [`rollup_daily_stats`] día a día y
+//! [`rollup_pending_days`] con los que falten.
+
+use std::{collections::HashMap, sync::Arc};
+
+use axum::{
+    extract::{Query, State},
+    Json,
+};
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use sqlx::types::Json as SqlJson;
+
+use crate::clock::Clock;
+use crate::database::TracedQuery;
+use crate::encryption::EmailEncryption;
+use crate::handlers::error::AppError;
+use crate::models::stats::{
+    DomainCount, PeriodCount, StatsWindow, UserStats, UserStatsQuery, UserTotals, MAX_STATS_DAYS,
+};
+use crate::state::AppState;
+use crate::tenant::{Database, Tenant};
+
+/// Devuelve los totales de usuarios del inquilino, sus altas por día o por semana y los
+/// dominios de correo más frecuentes entre las altas de la ventana.
+pub async fn user_stats(
+    tenant: Tenant,
+    Database(database_pool): Database,
+    State(encryption): State<EmailEncryption>,
+    State(clock): State<Arc<dyn Clock>>,
+    Query(query): Query<UserStatsQuery>,
+) -> Result<Json<UserStats>, AppError> {
+    let window = query
+        .validate(clock.now().date_naive())
+        .map_err(AppError::validation)?;
+    let start = midnight(window.from);
+    let end = midnight(window.to + Days::new(1));
+
+    let totals = sqlx::query_as::<_, UserTotals>(
+        "SELECT COUNT(*) AS users, \
+         COALESCE(SUM(status = 'active'), 0) AS active, \
+         COALESCE(SUM(status = 'suspended'), 0) AS suspended, \
+         COALESCE(SUM(status = 'deactivated'), 0) AS deactivated, \
+         COALESCE(SUM(status = 'merged'), 0) AS merged, \
+         COALESCE(SUM(created_at >= ?1 AND created_at < ?2), 0) AS signups \
+         FROM users WHERE tenant_id = ?3",
+    )
+    .bind(start)
+    .bind(end)
+    .bind(tenant.id())
+    .fetch_one(&database_pool)
+    .traced("stats.users.totals", None)
+    .await
+    .map_err(AppError::from)?;
+
+    let counted = sqlx::query_as::<_, PeriodCount>(&format!(
+        "SELECT {} AS period, COUNT(*) AS count FROM users \
+         WHERE tenant_id = ? AND created_at >= ? AND created_at < ? \
+         GROUP BY period ORDER BY period",
+        window.interval.period_sql("created_at")
+    ))
+    .bind(tenant.id())
+    .bind(start)
+    .bind(end)
+    .fetch_all(&database_pool)
+    .traced("stats.users.signups", None)
+    .await
+    .map_err(AppError::from)?;
+
+    let requests = sqlx::query_as::<_, PeriodCount>(&format!(
+        "SELECT {} AS period, SUM(requests) AS count FROM daily_user_stats \
+         WHERE tenant_id = ? AND day >= ? AND day <= ? \
+         GROUP BY period ORDER BY period",
+        window.interval.period_sql("day")
+    ))
+    .bind(tenant.id())
+    .bind(window.from.to_string())
+    .bind(window.to.to_string())
+    .fetch_all(&database_pool)
+    .traced("stats.users.requests", None)
+    .await
+    .map_err(AppError::from)?;
+
+    let top_email_domains = if encryption.is_enabled() {
+        let emails = sqlx::query_scalar::<_, String>(
+            "SELECT email FROM users WHERE tenant_id = ? AND erased_at IS NULL \
+             AND created_at >= ? AND created_at < ?",
+        )
+        .bind(tenant.id())
+        .bind(start)
+        .bind(end)
+        .fetch_all(&database_pool)
+        .traced("stats.users.emails", None)
+        .await
+        .map_err(AppError::from)?;
+        let mut domains = HashMap::<String, i64>::new();
+        for email in emails {
+            let email = encryption.open(&email).map_err(AppError::internal)?;
+            *domains.entry(email_domain(&email).to_string()).or_default() += 1;
+        }
+        top_domains(domains, window.top)
+    } else {
+        sqlx::query_as::<_, DomainCount>(
+            "SELECT substr(email, instr(email, '@') + 1) AS domain, COUNT(*) AS count \
+             FROM users WHERE tenant_id = ? AND erased_at IS NULL \
+             AND created_at >= ? AND created_at < ? \
+             GROUP BY domain ORDER BY count DESC, domain LIMIT ?",
+        )
+        .bind(tenant.id())
+        .bind(start)
+        .bind(end)
+        .bind(window.top)
+        .fetch_all(&database_pool)
+        .traced("stats.users.domains", None)
+        .await
+        .map_err(AppError::from)?
+    };
+
+    Ok(Json(UserStats {
+        from: window.from,
+        to: window.to,
+        interval: window.interval,
+        totals,
+        signups: fill_periods(&window, counted),
+        requests: fill_periods(&window, requests),
+        top_email_domains,
+    }))
+}
+
+/// Resume en `daily_user_stats` las peticiones de `day` de cada inquilino y devuelve cuántas
+/// filas escribió o borró. Reescribir un día ya resumido lo deja igual que `tenant_usage`:
+/// los inquilinos que ya no tienen uso ese día pierden su fila.
+///
+/// Las peticiones salen de `tenant_usage`, que vive en la principal, y se guardan en la base
+/// del inquilino para que `GET /stats/users` las lea junto a sus usuarios. Las altas no se
+/// resumen: se cuentan sobre `users`, que ya tiene su índice, e incluyen el día en curso.
+pub async fn rollup_daily_stats(state: &AppState, day: NaiveDate) -> anyhow::Result<u64> {
+    let mut written = 0;
+    let usage = sqlx::query_as::<_, (String, i64)>(
+        "SELECT tenant_id, requests FROM tenant_usage WHERE day = ?",
+    )
+    .bind(day.to_string())
+    .fetch_all(&state.database_pool)
+    .await?;
+    for (tenant_id, requests) in &usage {
+        let database_pool = state
+            .tenant_databases
+            .pool(tenant_id)
+            .await?
+            .unwrap_or_else(|| state.database_pool.clone());
+        written += sqlx::query(
+            "INSERT INTO daily_user_stats (tenant_id, day, requests) VALUES (?, ?, ?) \
+             ON CONFLICT (tenant_id, day) DO UPDATE SET requests = excluded.requests",
+        )
+        .bind(tenant_id)
+        .bind(day.to_string())
+        .bind(requests)
+        .execute(&database_pool)
+        .await?
+        .rows_affected();
+    }
+
+    // Se borra después de escribir para que ningún inquilino con uso se lea a cero entretanto.
+    let tenant_ids = usage
+        .iter()
+        .map(|(tenant_id, _)| tenant_id)
+        .collect::<Vec<_>>();
+    for database_pool in state.database_pools().await {
+        written += sqlx::query(
+            "DELETE FROM daily_user_stats WHERE day = ? \
+             AND tenant_id NOT IN (SELECT value FROM json_each(?))",
+        )
+        .bind(day.to_string())
+        .bind(SqlJson(&tenant_ids))
+        .execute(&database_pool)
+        .await?
+        .rows_affected();
+    }
+
+    Ok(written)
+}
+
+/// Resume cada día cerrado que falte hasta `yesterday`, incluido, y devuelve los días
+/// resumidos. Se sigue desde el último anotado en `daily_rollups`; sin ninguno, desde el
+/// comienzo de la ventana más larga que admite `GET /stats/users`.
+pub async fn rollup_pending_days(
+    state: &AppState,
+    yesterday: NaiveDate,
+) -> anyhow::Result<Vec<NaiveDate>> {
+    let last = sqlx::query_scalar::<_, Option<NaiveDate>>("SELECT MAX(day) FROM daily_rollups")
+        .fetch_one(&state.database_pool)
+        .await?;
+    let mut day = match last {
+        Some(last) => last + Days::new(1),
+        None => yesterday - Days::new(MAX_STATS_DAYS - 1),
+    };
+
+    let mut rolled_up = Vec::new();
+    while day <= yesterday {
+        rollup_daily_stats(state, day).await?;
+        // Se anota tras resumirlo: si algo falla, la próxima ejecución repite el día.
+        sqlx::query(
+            "INSERT INTO daily_rollups (day, rolled_up_at) VALUES (?, ?) \
+             ON CONFLICT (day) DO UPDATE SET rolled_up_at = excluded.rolled_up_at",
+        )
+        .bind(day.to_string())
+        .bind(state.clock.now())
+        .execute(&state.database_pool)
+        .await?;
+        rolled_up.push(day);
+        day = day + Days::new(1);
+    }
+    Ok(rolled_up)
+}
+
+/// Comienzo de `date` en UTC.
+fn midnight(date: NaiveDate) -> DateTime<Utc> {
+    date.and_hms_opt(0, 0, 0)
+        .expect("la medianoche siempre es válida")
+        .and_utc()
+}
+
+/// Parte de `email` tras la arroba.
+fn email_domain(email: &str) -> &str {
+    email.rsplit_once('@').map_or(email, |(_, domain)| domain)
+}
+
+/// Los `top` dominios con más altas; a igualdad, por orden alfabético como en SQL.
+fn top_domains(domains: HashMap<String, i64>, top: u32) -> Vec<DomainCount> {
+    let mut domains = domains
+        .into_iter()
+        .map(|(domain, count)| DomainCount { domain, count })
+        .collect::<Vec<_>>();
+    domains.sort_by(|left, right| {
+        right
+            .count
+            .cmp(&left.count)
+            .then_with(|| left.domain.cmp(&right.domain))
+    });
+    domains.truncate(top as usize);
+    domains
+}
+
+/// Completa con ceros los periodos de la ventana sin recuento, para que la serie sea continua.
+fn fill_periods(window: &StatsWindow, counted: Vec<PeriodCount>) -> Vec<PeriodCount> {
+    let counted = counted
+        .into_iter()
+        .map(|bucket| (bucket.period, bucket.count))
+        .collect::<HashMap<_, _>>();
+
+    let mut periods = Vec::new();
+    let mut period = window.interval.period_start(window.from);
+    while period <= window.to {
+        periods.push(PeriodCount {
+            period,
+            count: counted.get(&period).copied().unwrap_or(0),
+        });
+        period = period + window.interval.days();
+    }
+    periods
+}
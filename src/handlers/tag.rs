@@ -0,0 +1,165 @@
+//! Handlers HTTP para etiquetar usuarios.
+//!
+//! Las etiquetas se crean al asignarlas por primera vez, así que basta con
+//! `PUT /users/:id/tags/:tag` para empezar a segmentar; `GET /users?tag=...` filtra por ellas.
+//! Cada inquilino tiene su propio catálogo: el mismo nombre en dos inquilinos son dos
+//! etiquetas distintas.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use sqlx::{Executor, Sqlite};
+use uuid::Uuid;
+
+use crate::clock::Clock;
+use crate::handlers::error::AppError;
+use crate::handlers::user::ensure_user_exists;
+use crate::ids::UserId;
+use crate::models::tag::{Tag, TagName, TAG_COLUMNS};
+use crate::tenant::{Database, Tenant};
+
+/// Devuelve las etiquetas del inquilino ordenadas por nombre.
+pub async fn list_tags(
+    tenant: Tenant,
+    Database(database_pool): Database,
+) -> Result<Json<Vec<Tag>>, AppError> {
+    let tags = sqlx::query_as::<_, Tag>(&format!(
+        "SELECT {TAG_COLUMNS} FROM tags WHERE tenant_id = ? ORDER BY name"
+    ))
+    .bind(tenant.id())
+    .fetch_all(&database_pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(Json(tags))
+}
+
+/// Elimina una etiqueta del inquilino y todas sus asignaciones.
+pub async fn delete_tag(
+    Path(tag): Path<String>,
+    tenant: Tenant,
+    Database(database_pool): Database,
+) -> Result<StatusCode, AppError> {
+    let TagName(name) = TagName::try_from(tag).map_err(AppError::validation)?;
+
+    let result = sqlx::query("DELETE FROM tags WHERE tenant_id = ? AND name = ?")
+        .bind(tenant.id())
+        .bind(&name)
+        .execute(&database_pool)
+        .await
+        .map_err(AppError::from)?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found());
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Devuelve las etiquetas asignadas a un usuario.
+pub async fn list_user_tags(
+    UserId(user_id): UserId,
+    tenant: Tenant,
+    Database(database_pool): Database,
+) -> Result<Json<Vec<Tag>>, AppError> {
+    ensure_user_exists(&database_pool, &tenant, user_id).await?;
+
+    let tags = fetch_user_tags(&database_pool, user_id)
+        .await
+        .map_err(AppError::from)?;
+
+    Ok(Json(tags))
+}
+
+/// Asigna una etiqueta a un usuario, creándola si aún no existe. Es idempotente.
+pub async fn attach_tag(
+    UserId(user_id): UserId,
+    Path((_, tag)): Path<(String, String)>,
+    tenant: Tenant,
+    Database(database_pool): Database,
+    State(clock): State<Arc<dyn Clock>>,
+) -> Result<Json<Vec<Tag>>, AppError> {
+    let TagName(name) = TagName::try_from(tag).map_err(AppError::validation)?;
+    let now = clock.now();
+
+    let mut transaction = database_pool.begin().await.map_err(AppError::from)?;
+    ensure_user_exists(&mut *transaction, &tenant, user_id).await?;
+    sqlx::query(
+        "INSERT INTO tags (id, tenant_id, name, created_at) VALUES (?, ?, ?, ?) \
+         ON CONFLICT (tenant_id, name) DO NOTHING",
+    )
+    .bind(Uuid::new_v4())
+    .bind(tenant.id())
+    .bind(&name)
+    .bind(now)
+    .execute(&mut *transaction)
+    .await
+    .map_err(AppError::from)?;
+
+    sqlx::query(
+        "INSERT INTO user_tags (user_id, tag_id, created_at) \
+         SELECT ?, id, ? FROM tags WHERE tenant_id = ? AND name = ? \
+         ON CONFLICT (user_id, tag_id) DO NOTHING",
+    )
+    .bind(user_id)
+    .bind(now)
+    .bind(tenant.id())
+    .bind(&name)
+    .execute(&mut *transaction)
+    .await
+    .map_err(AppError::from)?;
+
+    let tags = fetch_user_tags(&mut *transaction, user_id)
+        .await
+        .map_err(AppError::from)?;
+    transaction.commit().await.map_err(AppError::from)?;
+
+    Ok(Json(tags))
+}
+
+/// Retira una etiqueta de un usuario.
+pub async fn detach_tag(
+    UserId(user_id): UserId,
+    Path((_, tag)): Path<(String, String)>,
+    tenant: Tenant,
+    Database(database_pool): Database,
+) -> Result<StatusCode, AppError> {
+    let TagName(name) = TagName::try_from(tag).map_err(AppError::validation)?;
+    ensure_user_exists(&database_pool, &tenant, user_id).await?;
+
+    let result = sqlx::query(
+        "DELETE FROM user_tags WHERE user_id = ? \
+         AND tag_id = (SELECT id FROM tags WHERE tenant_id = ? AND name = ?)",
+    )
+    .bind(user_id)
+    .bind(tenant.id())
+    .bind(&name)
+    .execute(&database_pool)
+    .await
+    .map_err(AppError::from)?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found());
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Lee las etiquetas de un usuario ordenadas por nombre.
+async fn fetch_user_tags<'e, E>(executor: E, user_id: Uuid) -> Result<Vec<Tag>, sqlx::Error>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    sqlx::query_as::<_, Tag>(
+        "SELECT tags.id, tags.name, tags.created_at FROM tags \
+         JOIN user_tags ON user_tags.tag_id = tags.id \
+         WHERE user_tags.user_id = ? ORDER BY tags.name",
+    )
+    .bind(user_id)
+    .fetch_all(executor)
+    .await
+}
@@ -0,0 +1,295 @@
+//! Handlers HTTP para gestionar equipos y sus miembros.
+//!
+//! Son las primeras operaciones con reglas de autorización: quien realiza la petición se
+//! identifica mediante [`Actor`] y su rol en el equipo decide qué puede hacer, según la
+//! política declarada en [`crate::handlers::policy`]. Un equipo inexistente responde `404`;
+//! uno al que no se tiene acceso, `403`. Los equipos no guardan el inquilino: pertenecen al
+//! de sus miembros, que deben ser todos usuarios del [`Tenant`] resuelto.
+
+use std::sync::Arc;
+
+use axum::{extract::State, http::StatusCode, Json};
+use sqlx::{Sqlite, Transaction};
+use uuid::Uuid;
+
+use crate::clock::Clock;
+use crate::handlers::actor::Actor;
+use crate::handlers::error::AppError;
+use crate::handlers::policy::{
+    AddMember, Authorized, ChangeMemberRole, DeleteTeam, ListMembers, RemoveMember, RenameTeam,
+    ViewTeam,
+};
+use crate::handlers::post::is_foreign_key_violation;
+use crate::handlers::user::ensure_user_exists;
+use crate::handlers::validated::ValidatedJson;
+use crate::ids::UserId;
+use crate::models::team::{
+    AddTeamMember, Team, TeamMember, TeamName, TeamRole, UpdateTeamMember, TEAM_COLUMNS,
+    TEAM_MEMBER_COLUMNS,
+};
+use crate::models::user::ValidationErrors;
+use crate::tenant::{Database, Tenant};
+
+/// Devuelve los equipos de los que forma parte quien realiza la petición.
+pub async fn list_teams(
+    actor: Actor,
+    tenant: Tenant,
+    Database(database_pool): Database,
+) -> Result<Json<Vec<Team>>, AppError> {
+    let teams = sqlx::query_as::<_, Team>(
+        "SELECT teams.id, teams.name, teams.created_at, teams.updated_at FROM teams \
+         JOIN team_members ON team_members.team_id = teams.id \
+         JOIN users ON users.id = team_members.user_id \
+         WHERE team_members.user_id = ? AND users.tenant_id = ? ORDER BY teams.name",
+    )
+    .bind(actor.0)
+    .bind(tenant.id())
+    .fetch_all(&database_pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(Json(teams))
+}
+
+/// Crea un equipo cuyo primer `owner` es quien realiza la petición.
+pub async fn create_team(
+    actor: Actor,
+    tenant: Tenant,
+    Database(database_pool): Database,
+    State(clock): State<Arc<dyn Clock>>,
+    ValidatedJson(TeamName { name }): ValidatedJson<TeamName>,
+) -> Result<(StatusCode, Json<Team>), AppError> {
+    let now = clock.now();
+
+    let mut transaction = database_pool.begin().await.map_err(AppError::from)?;
+    // La cabecera no corresponde a ningún usuario del inquilino.
+    ensure_user_exists(&mut *transaction, &tenant, actor.0)
+        .await
+        .map_err(|error| {
+            if error.is_not_found() {
+                AppError::unauthorized()
+            } else {
+                error
+            }
+        })?;
+    let team = sqlx::query_as::<_, Team>(&format!(
+        "INSERT INTO teams (id, name, created_at, updated_at) VALUES (?, ?, ?, ?) \
+         RETURNING {TEAM_COLUMNS}"
+    ))
+    .bind(Uuid::new_v4())
+    .bind(&name)
+    .bind(now)
+    .bind(now)
+    .fetch_one(&mut *transaction)
+    .await
+    .map_err(AppError::from)?;
+
+    sqlx::query(
+        "INSERT INTO team_members (team_id, user_id, role, created_at) VALUES (?, ?, ?, ?)",
+    )
+    .bind(team.id)
+    .bind(actor.0)
+    .bind(TeamRole::Owner)
+    .bind(now)
+    .execute(&mut *transaction)
+    .await
+    .map_err(|error| {
+        // La cabecera no corresponde a ningún usuario existente.
+        if is_foreign_key_violation(&error) {
+            AppError::unauthorized()
+        } else {
+            AppError::from(error)
+        }
+    })?;
+    transaction.commit().await.map_err(AppError::from)?;
+
+    Ok((StatusCode::CREATED, Json(team)))
+}
+
+/// Recupera un equipo; solo para sus miembros.
+pub async fn get_team(
+    Authorized { team_id, .. }: Authorized<ViewTeam>,
+    Database(database_pool): Database,
+) -> Result<Json<Team>, AppError> {
+    let team = sqlx::query_as::<_, Team>(&format!("SELECT {TEAM_COLUMNS} FROM teams WHERE id = ?"))
+        .bind(team_id)
+        .fetch_one(&database_pool)
+        .await
+        .map_err(AppError::from)?;
+
+    Ok(Json(team))
+}
+
+/// Renombra un equipo; solo para sus `owner`.
+pub async fn update_team(
+    Authorized { team_id, .. }: Authorized<RenameTeam>,
+    Database(database_pool): Database,
+    State(clock): State<Arc<dyn Clock>>,
+    ValidatedJson(TeamName { name }): ValidatedJson<TeamName>,
+) -> Result<Json<Team>, AppError> {
+    let team = sqlx::query_as::<_, Team>(&format!(
+        "UPDATE teams SET name = ?, updated_at = ? WHERE id = ? RETURNING {TEAM_COLUMNS}"
+    ))
+    .bind(&name)
+    .bind(clock.now())
+    .bind(team_id)
+    .fetch_optional(&database_pool)
+    .await
+    .map_err(AppError::from)?
+    .ok_or_else(AppError::not_found)?;
+
+    Ok(Json(team))
+}
+
+/// Elimina un equipo junto con sus pertenencias; solo para sus `owner`.
+pub async fn delete_team(
+    Authorized { team_id, .. }: Authorized<DeleteTeam>,
+    Database(database_pool): Database,
+) -> Result<StatusCode, AppError> {
+    sqlx::query("DELETE FROM teams WHERE id = ?")
+        .bind(team_id)
+        .execute(&database_pool)
+        .await
+        .map_err(AppError::from)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Lista los miembros de un equipo; solo para sus miembros.
+pub async fn list_members(
+    Authorized { team_id, .. }: Authorized<ListMembers>,
+    tenant: Tenant,
+    Database(database_pool): Database,
+) -> Result<Json<Vec<TeamMember>>, AppError> {
+    let members = sqlx::query_as::<_, TeamMember>(&format!(
+        "SELECT {TEAM_MEMBER_COLUMNS} FROM team_members WHERE team_id = ? \
+         AND user_id IN (SELECT id FROM users WHERE tenant_id = ?) ORDER BY created_at"
+    ))
+    .bind(team_id)
+    .bind(tenant.id())
+    .fetch_all(&database_pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(Json(members))
+}
+
+/// Añade un usuario del inquilino al equipo con el rol indicado (`member` por defecto);
+/// solo para `owner`.
+pub async fn add_member(
+    Authorized { team_id, .. }: Authorized<AddMember>,
+    tenant: Tenant,
+    Database(database_pool): Database,
+    State(clock): State<Arc<dyn Clock>>,
+    Json(payload): Json<AddTeamMember>,
+) -> Result<(StatusCode, Json<TeamMember>), AppError> {
+    ensure_user_exists(&database_pool, &tenant, payload.user_id)
+        .await
+        .map_err(|error| {
+            if error.is_not_found() {
+                user_id_error("No existe ningún usuario con ese identificador")
+            } else {
+                error
+            }
+        })?;
+
+    let member = sqlx::query_as::<_, TeamMember>(&format!(
+        "INSERT INTO team_members (team_id, user_id, role, created_at) VALUES (?, ?, ?, ?) \
+         ON CONFLICT (team_id, user_id) DO NOTHING RETURNING {TEAM_MEMBER_COLUMNS}"
+    ))
+    .bind(team_id)
+    .bind(payload.user_id)
+    .bind(payload.role)
+    .bind(clock.now())
+    .fetch_optional(&database_pool)
+    .await
+    .map_err(|error| {
+        if is_foreign_key_violation(&error) {
+            user_id_error("No existe ningún usuario con ese identificador")
+        } else {
+            AppError::from(error)
+        }
+    })?
+    .ok_or_else(|| user_id_error("El usuario ya forma parte del equipo"))?;
+
+    Ok((StatusCode::CREATED, Json(member)))
+}
+
+/// Cambia el rol de un miembro; solo para `owner`. El equipo debe conservar un `owner`.
+pub async fn update_member(
+    Authorized { team_id, .. }: Authorized<ChangeMemberRole>,
+    UserId(user_id): UserId,
+    Database(database_pool): Database,
+    Json(payload): Json<UpdateTeamMember>,
+) -> Result<Json<TeamMember>, AppError> {
+    let mut transaction = database_pool.begin().await.map_err(AppError::from)?;
+    let member = sqlx::query_as::<_, TeamMember>(&format!(
+        "UPDATE team_members SET role = ? WHERE team_id = ? AND user_id = ? \
+         RETURNING {TEAM_MEMBER_COLUMNS}"
+    ))
+    .bind(payload.role)
+    .bind(team_id)
+    .bind(user_id)
+    .fetch_optional(&mut *transaction)
+    .await
+    .map_err(AppError::from)?
+    .ok_or_else(AppError::not_found)?;
+
+    ensure_team_keeps_an_owner(&mut transaction, team_id).await?;
+    transaction.commit().await.map_err(AppError::from)?;
+
+    Ok(Json(member))
+}
+
+/// Retira a un miembro del equipo. Los `owner` pueden retirar a cualquiera y cada miembro
+/// puede abandonar el equipo por sí mismo, siempre que quede algún `owner`.
+pub async fn remove_member(
+    Authorized { team_id, .. }: Authorized<RemoveMember>,
+    UserId(user_id): UserId,
+    Database(database_pool): Database,
+) -> Result<StatusCode, AppError> {
+    let mut transaction = database_pool.begin().await.map_err(AppError::from)?;
+    let result = sqlx::query("DELETE FROM team_members WHERE team_id = ? AND user_id = ?")
+        .bind(team_id)
+        .bind(user_id)
+        .execute(&mut *transaction)
+        .await
+        .map_err(AppError::from)?;
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found());
+    }
+
+    ensure_team_keeps_an_owner(&mut transaction, team_id).await?;
+    transaction.commit().await.map_err(AppError::from)?;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Falla si, tras los cambios de la transacción, el equipo se queda sin ningún `owner`.
+async fn ensure_team_keeps_an_owner(
+    transaction: &mut Transaction<'_, Sqlite>,
+    team_id: Uuid,
+) -> Result<(), AppError> {
+    let owners = sqlx::query_scalar::<_, i64>(
+        "SELECT COUNT(*) FROM team_members WHERE team_id = ? AND role = ?",
+    )
+    .bind(team_id)
+    .bind(TeamRole::Owner)
+    .fetch_one(&mut **transaction)
+    .await
+    .map_err(AppError::from)?;
+
+    if owners == 0 {
+        let mut errors = ValidationErrors::new();
+        errors.push("role", "El equipo debe conservar al menos un owner");
+        return Err(AppError::validation(errors));
+    }
+
+    Ok(())
+}
+
+fn user_id_error(message: &'static str) -> AppError {
+    let mut errors = ValidationErrors::new();
+    errors.push("user_id", message);
+    AppError::validation(errors)
+}
@@ -0,0 +1,190 @@
+//! Handlers HTTP para administrar el registro de inquilinos.
+//!
+//! Estas rutas no pasan por el middleware de resolución: operan siempre sobre la base
+//! principal, que es donde vive el registro.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use sqlx::{Pool, Sqlite};
+
+use crate::cache::Cache;
+use crate::clock::Clock;
+use crate::handlers::error::AppError;
+use crate::handlers::user::{is_unique_violation, remove_stored_avatar};
+use crate::handlers::validated::ValidatedJson;
+use crate::models::tenant::{
+    NewTenant, QuotaUsage, Tenant, TenantQuotas, TenantUsage, TENANT_COLUMNS,
+};
+use crate::models::user::ValidationErrors;
+use crate::storage::Storage;
+use crate::tenant::{quota, TenantDatabases, DEFAULT_TENANT};
+
+/// Devuelve todos los inquilinos ordenados por identificador.
+pub async fn list_tenants(
+    State(database_pool): State<Pool<Sqlite>>,
+) -> Result<Json<Vec<Tenant>>, AppError> {
+    let tenants =
+        sqlx::query_as::<_, Tenant>(&format!("SELECT {TENANT_COLUMNS} FROM tenants ORDER BY id"))
+            .fetch_all(&database_pool)
+            .await
+            .map_err(AppError::from)?;
+
+    Ok(Json(tenants))
+}
+
+/// Registra un inquilino; su base propia, si la hay, se crea con la primera petición.
+pub async fn create_tenant(
+    State(database_pool): State<Pool<Sqlite>>,
+    State(clock): State<Arc<dyn Clock>>,
+    ValidatedJson(new_tenant): ValidatedJson<NewTenant>,
+) -> Result<(StatusCode, Json<Tenant>), AppError> {
+    let tenant = sqlx::query_as::<_, Tenant>(&format!(
+        "INSERT INTO tenants (id, name, created_at, max_users, max_requests_per_day) \
+         VALUES (?, ?, ?, ?, ?) RETURNING {TENANT_COLUMNS}"
+    ))
+    .bind(&new_tenant.id)
+    .bind(&new_tenant.name)
+    .bind(clock.now())
+    .bind(new_tenant.max_users)
+    .bind(new_tenant.max_requests_per_day)
+    .fetch_one(&database_pool)
+    .await
+    .map_err(|error| {
+        if is_unique_violation(&error) {
+            let mut errors = ValidationErrors::new();
+            errors.push("id", "Ya existe un inquilino con ese identificador");
+            AppError::validation(errors)
+        } else {
+            AppError::from(error)
+        }
+    })?;
+
+    Ok((StatusCode::CREATED, Json(tenant)))
+}
+
+/// Sustituye las cuotas del inquilino; los límites omitidos quedan sin límite.
+pub async fn update_tenant_quotas(
+    Path(tenant_id): Path<String>,
+    State(database_pool): State<Pool<Sqlite>>,
+    ValidatedJson(quotas): ValidatedJson<TenantQuotas>,
+) -> Result<Json<Tenant>, AppError> {
+    let tenant = sqlx::query_as::<_, Tenant>(&format!(
+        "UPDATE tenants SET max_users = ?, max_requests_per_day = ? WHERE id = ? \
+         RETURNING {TENANT_COLUMNS}"
+    ))
+    .bind(quotas.max_users)
+    .bind(quotas.max_requests_per_day)
+    .bind(&tenant_id)
+    .fetch_optional(&database_pool)
+    .await
+    .map_err(AppError::from)?
+    .ok_or_else(AppError::not_found)?;
+
+    Ok(Json(tenant))
+}
+
+/// Devuelve el consumo del inquilino frente a sus cuotas en el día UTC en curso.
+pub async fn get_tenant_usage(
+    Path(tenant_id): Path<String>,
+    State(database_pool): State<Pool<Sqlite>>,
+    State(tenant_databases): State<TenantDatabases>,
+    State(clock): State<Arc<dyn Clock>>,
+) -> Result<Json<TenantUsage>, AppError> {
+    let tenant = sqlx::query_as::<_, Tenant>(&format!(
+        "SELECT {TENANT_COLUMNS} FROM tenants WHERE id = ?"
+    ))
+    .bind(&tenant_id)
+    .fetch_optional(&database_pool)
+    .await
+    .map_err(AppError::from)?
+    .ok_or_else(AppError::not_found)?;
+
+    let tenant_pool = tenant_databases
+        .pool(&tenant.id)
+        .await
+        .map_err(AppError::internal)?
+        .unwrap_or_else(|| database_pool.clone());
+    let users = quota::count_users(&tenant_pool, &tenant.id).await?;
+
+    let today = clock.now().date_naive();
+    let requests = quota::requests_on(&database_pool, &tenant.id, today)
+        .await
+        .map_err(AppError::from)?;
+
+    Ok(Json(TenantUsage {
+        users: QuotaUsage {
+            used: users,
+            limit: tenant.max_users,
+        },
+        requests_today: QuotaUsage {
+            used: requests,
+            limit: tenant.max_requests_per_day,
+        },
+        resets_at: quota::next_reset(today),
+        tenant_id: tenant.id,
+    }))
+}
+
+/// Elimina un inquilino con todos sus usuarios, sus avatares, sus webhooks y, si tiene base
+/// propia, el archivo de esta.
+pub async fn delete_tenant(
+    Path(tenant_id): Path<String>,
+    State(database_pool): State<Pool<Sqlite>>,
+    State(tenant_databases): State<TenantDatabases>,
+    State(storage): State<Arc<dyn Storage>>,
+    State(cache): State<Cache>,
+) -> Result<StatusCode, AppError> {
+    if tenant_id == DEFAULT_TENANT {
+        let mut errors = ValidationErrors::new();
+        errors.push("id", "El inquilino por defecto no se puede eliminar");
+        return Err(AppError::validation(errors));
+    }
+
+    // Se comprueba antes de abrir su base para no crear el archivo de un inquilino inexistente.
+    sqlx::query_scalar::<_, String>("SELECT id FROM tenants WHERE id = ?")
+        .bind(&tenant_id)
+        .fetch_optional(&database_pool)
+        .await
+        .map_err(AppError::from)?
+        .ok_or_else(AppError::not_found)?;
+
+    let tenant_pool = tenant_databases
+        .pool(&tenant_id)
+        .await
+        .map_err(AppError::internal)?
+        .unwrap_or_else(|| database_pool.clone());
+    let avatar_keys = sqlx::query_scalar::<_, Option<String>>(
+        "DELETE FROM users WHERE tenant_id = ? RETURNING avatar_key",
+    )
+    .bind(&tenant_id)
+    .fetch_all(&tenant_pool)
+    .await
+    .map_err(AppError::from)?;
+
+    sqlx::query("DELETE FROM webhooks WHERE tenant_id = ?")
+        .bind(&tenant_id)
+        .execute(&database_pool)
+        .await
+        .map_err(AppError::from)?;
+    sqlx::query("DELETE FROM tenants WHERE id = ?")
+        .bind(&tenant_id)
+        .execute(&database_pool)
+        .await
+        .map_err(AppError::from)?;
+    tenant_databases
+        .remove(&tenant_id)
+        .await
+        .map_err(AppError::internal)?;
+
+    for avatar_key in avatar_keys.iter().flatten() {
+        remove_stored_avatar(storage.as_ref(), avatar_key).await;
+    }
+    cache.invalidate_user_list(&tenant_id).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
@@ -0,0 +1,36 @@
+//! Total de elementos de un listado, enviado en la cabecera `X-Total-Count`.
+//!
+//! Permite a las interfaces dibujar la paginación sin descargar el cuerpo, por ejemplo
+//! con una petición `HEAD`.
+
+use std::convert::Infallible;
+
+use axum::{
+    http::{HeaderName, HeaderValue},
+    response::{IntoResponse, IntoResponseParts, Response, ResponseParts},
+};
+
+/// Cabecera con el total de elementos del listado.
+pub const TOTAL_COUNT_HEADER: HeaderName = HeaderName::from_static("x-total-count");
+
+/// Total de elementos que cumplen los filtros del listado.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TotalCount(pub i64);
+
+impl IntoResponseParts for TotalCount {
+    type Error = Infallible;
+
+    fn into_response_parts(self, mut parts: ResponseParts) -> Result<ResponseParts, Self::Error> {
+        parts
+            .headers_mut()
+            .insert(TOTAL_COUNT_HEADER, HeaderValue::from(self.0));
+        Ok(parts)
+    }
+}
+
+/// Respuesta sin cuerpo, solo con la cabecera; la usan los handlers de `HEAD`.
+impl IntoResponse for TotalCount {
+    fn into_response(self) -> Response {
+        (self, ()).into_response()
+    }
+}
@@ -2,236 +2,1708 @@
 //!
 //! Cada función expone la lógica necesaria para responder a solicitudes relacionadas con
 //! el recurso `users`, incluído listado, consulta, creación, actualización y eliminación.
+//!
+//! Las consultas fijas usan `query!`/`query_as!`: un cambio de columna o de tipo en el esquema
+//! rompe la compilación en lugar de producir un `500`. Los metadatos de cada consulta viven en
+//! `.sqlx/` (se regeneran con `cargo sqlx prepare`), así que compilar no exige una base de datos.
+//! Solo el listado y el recuento, cuyos filtros son opcionales, usan `QueryBuilder`.
 
 use axum::{
-    extract::{Path, State},
-    http::StatusCode,
+    body::Bytes,
+    extract::{Multipart, Query, State},
+    http::{header, HeaderMap, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
-use serde::Serialize;
-use sqlx::{Pool, Sqlite};
-use tracing::error;
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use sqlx::{Executor, Pool, QueryBuilder, Sqlite, SqliteConnection};
+use std::sync::Arc;
+use tracing::warn;
 use uuid::Uuid;
 
+use crate::cache::{self, Cache};
+use crate::clock::Clock;
+use crate::config::AppConfig;
+use crate::database::TracedQuery;
+use crate::email_domains::{blocked_email_errors, EmailDomainPolicy};
+use crate::encryption::EmailEncryption;
+use crate::events::DomainEvent;
+use crate::export::{self, ExportOptions};
+use crate::filter::{escape_like, Filter};
+use crate::fuzzy_search;
+use crate::handlers::error::AppError;
+use crate::handlers::expand::expand_user;
+use crate::handlers::fields::SparseFields;
+use crate::handlers::total_count::TotalCount;
+use crate::handlers::validated::ValidatedJson;
+use crate::ids::{IdGenerator, UserId};
+use crate::models::avatar::{avatar_url, Avatar, AvatarFormat, AVATAR_FIELD, MAX_AVATAR_BYTES};
+use crate::models::duplicate::{
+    CreateUserOptions, CreatedUser, DuplicateCandidate, DuplicateKey, DuplicatePair,
+};
+use crate::models::expand::{ExpandedUser, UserExpansions};
+use crate::models::projection::Projected;
+use crate::models::tag::TagName;
 use crate::models::user::{
+    BatchCreateResponse,
+    BatchDeleteResponse,
+    BatchDeleteUsers,
+    BatchItemResult,
     CreateUser,
+    ImportOptions,
+    ImportReport,
+    ImportRowReport,
+    ImportRowStatus,
+    ListUsersQuery,
+    MergeUsers,
     NewUser,
+    SearchUsersQuery,
+    StatusFilter,
+    SuggestUsersQuery,
+    Suspension,
     UpdateUser,
     User,
     UserChanges,
+    UserStatus,
+    UserCount,
+    UserMatch,
+    UserSuggestion,
     ValidationError,
-    ValidationErrors,
+    USER_COLUMNS,
+    USER_FILTER_FIELDS,
 };
+use crate::models::validation::{Validate, ValidationErrors};
+use crate::outbox::{self, Outbox};
+use crate::routes::REVALIDATE_CACHE_CONTROL;
+use crate::storage::Storage;
+use crate::tenant::{Database, Tenant, TenantQuota};
+
+/// Devuelve los usuarios del inquilino; por defecto solo las cuentas activas. Con
+/// `?fields=id,name` cada usuario incluye solo esos campos. El total se envía además en
+/// `X-Total-Count`.
+///
+/// El orden forma parte del contrato: por fecha de alta y, a igualdad, por identificador.
+pub async fn list_users(
+    tenant: Tenant,
+    Database(database_pool): Database,
+    State(cache): State<Cache>,
+    State(encryption): State<EmailEncryption>,
+    Query(query): Query<ListUsersQuery>,
+    fields: SparseFields<User>,
+) -> Result<(TotalCount, Json<Vec<Projected<User>>>), AppError> {
+    // Solo se cachea el listado por defecto, que es el que invalidan las escrituras.
+    let is_default_listing = query.status == StatusFilter::default()
+        && query.tag.is_none()
+        && query.filter.is_none();
+    let list_key = cache::user_list_key(tenant.id());
+    if is_default_listing {
+        if let Some(users) = cache.get_json::<Vec<User>>(&list_key).await {
+            return Ok((
+                TotalCount(users.len() as i64),
+                Json(fields.project_all(users)),
+            ));
+        }
+    }
+
+    let mut builder = QueryBuilder::<Sqlite>::new(format!("SELECT {USER_COLUMNS} FROM users"));
+    push_user_filters(&mut builder, &tenant, &encryption, query)?;
+    builder.push(" ORDER BY created_at, id");
 
-/// Devuelve la lista completa de usuarios registrados.
-pub async fn list_users(State(database_pool): State<Pool<Sqlite>>) -> Result<Json<Vec<User>>, AppError> {
-    let users = sqlx::query_as::<_, User>("SELECT id, name, email, created_at FROM users")
+    let users = builder
+        .build_query_as::<User>()
         .fetch_all(&database_pool)
+        .traced("users.list", None)
         .await
         .map_err(AppError::from)?;
+    let users = encryption.open_users(users).map_err(AppError::internal)?;
+
+    if is_default_listing {
+        cache.set_json(&list_key, &users, cache.list_ttl()).await;
+    }
+
+    Ok((
+        TotalCount(users.len() as i64),
+        Json(fields.project_all(users)),
+    ))
+}
+
+/// Cuenta los usuarios que devolvería el listado con los mismos filtros.
+pub async fn count_users(
+    tenant: Tenant,
+    Database(database_pool): Database,
+    State(encryption): State<EmailEncryption>,
+    Query(query): Query<ListUsersQuery>,
+) -> Result<(TotalCount, Json<UserCount>), AppError> {
+    let count = count_matching_users(&database_pool, &tenant, &encryption, query).await?;
 
-    Ok(Json(users))
+    Ok((TotalCount(count), Json(UserCount { count })))
 }
 
-/// Recupera un usuario concreto identificado por su UUID.
+/// Responde a `HEAD /users` con el total en `X-Total-Count`, sin leer las filas.
+pub async fn head_users(
+    tenant: Tenant,
+    Database(database_pool): Database,
+    State(encryption): State<EmailEncryption>,
+    Query(query): Query<ListUsersQuery>,
+) -> Result<TotalCount, AppError> {
+    let count = count_matching_users(&database_pool, &tenant, &encryption, query).await?;
+
+    Ok(TotalCount(count))
+}
+
+/// Recupera un usuario concreto identificado por su UUID, admitiendo `?fields=` y
+/// `?expand=posts,teams,tags` (o `posts.comments`) para incrustar sus relaciones.
 pub async fn get_user(
-    Path(user_id): Path<Uuid>,
-    State(database_pool): State<Pool<Sqlite>>,
-) -> Result<Json<User>, AppError> {
-    let user = sqlx::query_as::<_, User>(
-        "SELECT id, name, email, created_at FROM users WHERE id = ?",
+    UserId(user_id): UserId,
+    tenant: Tenant,
+    Database(database_pool): Database,
+    State(cache): State<Cache>,
+    State(encryption): State<EmailEncryption>,
+    fields: SparseFields<User>,
+    expansions: UserExpansions,
+) -> Result<Json<ExpandedUser>, AppError> {
+    let cache_key = cache::user_key(user_id);
+    // La clave no incluye el inquilino: un acierto de otro inquilino se trata como fallo.
+    let cached_user = cache
+        .get_json::<User>(&cache_key)
+        .await
+        .filter(|user| user.tenant_id == tenant.id());
+    if let Some(user) = cached_user {
+        let expanded =
+            expand_user(&database_pool, user_id, fields.project(user), expansions).await?;
+        return Ok(Json(expanded));
+    }
+
+    let user = sqlx::query_as!(
+        User,
+        r#"SELECT id AS "id!: Uuid", name, email, created_at AS "created_at: DateTime<Utc>",
+                  status AS "status: UserStatus",
+                  suspended_until AS "suspended_until: DateTime<Utc>", suspension_reason,
+                  avatar_url, tenant_id
+           FROM users WHERE id = ? AND tenant_id = ?"#,
+        user_id,
+        tenant.0
     )
-    .bind(user_id)
     .fetch_one(&database_pool)
+    .traced("users.get", Some(user_id))
     .await
     .map_err(|error| match error {
         sqlx::Error::RowNotFound => AppError::not_found(),
         other => AppError::from(other),
     })?;
+    let user = encryption.open_user(user).map_err(AppError::internal)?;
 
-    Ok(Json(user))
+    cache.set_json(&cache_key, &user, cache.user_ttl()).await;
+
+    let expanded = expand_user(&database_pool, user_id, fields.project(user), expansions).await?;
+    Ok(Json(expanded))
 }
 
-/// Crea un nuevo usuario validando los datos de entrada antes de persistirlos.
+/// Crea un nuevo usuario validando los datos de entrada antes de persistirlos. Con
+/// `?check_duplicates=true` la respuesta señala además los usuarios existentes que se le
+/// parecen, sin impedir el alta.
+#[allow(clippy::too_many_arguments)]
 pub async fn create_user(
-    State(database_pool): State<Pool<Sqlite>>,
-    Json(payload): Json<CreateUser>,
-) -> Result<(StatusCode, Json<User>), AppError> {
-    let validated_user = NewUser::try_from(payload).map_err(AppError::validation)?;
+    tenant: Tenant,
+    quota: TenantQuota,
+    Database(database_pool): Database,
+    State(outbox): State<Outbox>,
+    State(cache): State<Cache>,
+    State(ids): State<Arc<dyn IdGenerator>>,
+    State(clock): State<Arc<dyn Clock>>,
+    State(encryption): State<EmailEncryption>,
+    policy: EmailDomainPolicy,
+    Query(options): Query<CreateUserOptions>,
+    ValidatedJson(validated_user): ValidatedJson<NewUser>,
+) -> Result<(StatusCode, Json<CreatedUser>), AppError> {
+    policy.ensure_allowed(&validated_user.email).await?;
 
-    let user_id = Uuid::new_v4();
-    let created_timestamp = chrono::Utc::now();
+    let mut transaction = database_pool.begin().await.map_err(AppError::from)?;
+    let user = insert_user(
+        &mut transaction,
+        &encryption,
+        &tenant,
+        ids.generate(),
+        clock.now(),
+        validated_user,
+    )
+    .await
+    .map_err(AppError::from)?
+    .ok_or_else(|| AppError::validation(duplicate_email_errors()))?;
+    quota
+        .ensure_user_capacity(&mut *transaction, &tenant, 1)
+        .await?;
+    let possible_duplicates = if options.check_duplicates {
+        Some(find_duplicate_candidates(&mut *transaction, &encryption, &tenant, &user).await?)
+    } else {
+        None
+    };
+    outbox::record(
+        &mut *transaction,
+        &encryption,
+        DomainEvent::UserCreated { user: user.clone() }
+    )
+    .await
+    .map_err(AppError::from)?;
+    transaction.commit().await.map_err(AppError::from)?;
 
-    sqlx::query("INSERT INTO users (id, name, email, created_at) VALUES (?, ?, ?, ?)")
-        .bind(user_id)
-        .bind(&validated_user.name)
-        .bind(&validated_user.email)
-        .bind(created_timestamp)
-        .execute(&database_pool)
+    outbox.wake();
+    cache.invalidate_user_list(tenant.id()).await;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreatedUser {
+            user,
+            possible_duplicates,
+        }),
+    ))
+}
+
+/// Sugerencias para los selectores de usuarios: los del inquilino cuyo nombre o correo
+/// empieza por `q`, sin distinguir mayúsculas, ordenados por nombre. Con el cifrado de correos
+/// activo el correo guardado no se puede comparar, así que solo se busca por nombre.
+pub async fn suggest_users(
+    tenant: Tenant,
+    Database(database_pool): Database,
+    State(encryption): State<EmailEncryption>,
+    Query(query): Query<SuggestUsersQuery>,
+) -> Result<Json<Vec<UserSuggestion>>, AppError> {
+    let query = query.validate().map_err(AppError::validation)?;
+    let pattern = like_prefix(&query.q);
+
+    // Los índices `COLLATE NOCASE` sobre `(tenant_id, name)` y `(tenant_id, email)` permiten
+    // a SQLite resolver cada `LIKE 'prefijo%'` como un rango. El `+` del `ORDER BY` evita que
+    // prefiera recorrer entero el índice de nombres para no ordenar: se ordenan solo las
+    // coincidencias.
+    let mut builder = QueryBuilder::<Sqlite>::new("SELECT id, name, email FROM users");
+    builder
+        .push(" WHERE tenant_id = ")
+        .push_bind(tenant.0.clone())
+        .push(" AND erased_at IS NULL AND status != 'merged' AND (name LIKE ")
+        .push_bind(pattern.clone())
+        .push(" ESCAPE '\\'");
+    if !encryption.is_enabled() {
+        builder
+            .push(" OR email LIKE ")
+            .push_bind(pattern)
+            .push(" ESCAPE '\\'");
+    }
+    builder
+        .push(") ORDER BY +name COLLATE NOCASE, id LIMIT ")
+        .push_bind(i64::from(query.limit));
+
+    let suggestions = builder
+        .build_query_as::<UserSuggestion>()
+        .fetch_all(&database_pool)
+        .traced("users.suggest", None)
         .await
         .map_err(AppError::from)?;
+    let suggestions = suggestions
+        .into_iter()
+        .map(|suggestion| {
+            Ok(UserSuggestion {
+                email: encryption.open(&suggestion.email)?,
+                ..suggestion
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()
+        .map_err(AppError::internal)?;
 
-    let user = User {
-        id: user_id,
-        name: validated_user.name,
-        email: validated_user.email,
-        created_at: created_timestamp,
+    Ok(Json(suggestions))
+}
+
+/// Búsqueda aproximada por nombre para los buscadores de administración, tolerante a
+/// erratas (`?q=grce` encuentra a «Grace»). Devuelve los usuarios del inquilino que alcanzan
+/// `threshold`, de mayor a menor similitud; ver [`fuzzy_search`].
+pub async fn search_users(
+    tenant: Tenant,
+    Database(database_pool): Database,
+    State(encryption): State<EmailEncryption>,
+    Query(query): Query<SearchUsersQuery>,
+) -> Result<Json<Vec<UserMatch>>, AppError> {
+    let query = query.validate().map_err(AppError::validation)?;
+    let matches = fuzzy_search::search(
+        &database_pool,
+        tenant.id(),
+        &query.q,
+        query.threshold,
+        query.limit as usize,
+    )
+    .traced("users.search", None)
+    .await
+    .map_err(AppError::from)?;
+    let matches = matches
+        .into_iter()
+        .map(|found| {
+            Ok(UserMatch {
+                user: encryption.open_user(found.user)?,
+                ..found
+            })
+        })
+        .collect::<anyhow::Result<Vec<_>>>()
+        .map_err(AppError::internal)?;
+
+    Ok(Json(matches))
+}
+
+/// Patrón `LIKE` que encuentra los textos que empiezan por `prefix`, con los comodines de
+/// este escapados.
+fn like_prefix(prefix: &str) -> String {
+    format!("{}%", escape_like(prefix))
+}
+
+/// Número máximo de parejas devueltas por [`list_duplicate_users`].
+const MAX_DUPLICATE_PAIRS: usize = 100;
+
+/// Devuelve las parejas de usuarios del inquilino que podrían ser la misma persona,
+/// empezando por las de nombres más parecidos, para que un operador decida si fusionarlas.
+/// Los usuarios con los datos personales borrados no se comparan.
+pub async fn list_duplicate_users(
+    tenant: Tenant,
+    Database(database_pool): Database,
+    State(encryption): State<EmailEncryption>,
+) -> Result<Json<Vec<DuplicatePair>>, AppError> {
+    let users = sqlx::query_as!(
+        User,
+        r#"SELECT id AS "id!: Uuid", name, email, created_at AS "created_at: DateTime<Utc>",
+                  status AS "status: UserStatus",
+                  suspended_until AS "suspended_until: DateTime<Utc>", suspension_reason,
+                  avatar_url, tenant_id
+           FROM users WHERE tenant_id = ? AND erased_at IS NULL AND status != 'merged'
+           ORDER BY created_at, id"#,
+        tenant.0
+    )
+    .fetch_all(&database_pool)
+    .traced("users.list_comparable", None)
+    .await
+    .map_err(AppError::from)?;
+    let users = encryption.open_users(users).map_err(AppError::internal)?;
+
+    let keys = users.iter().map(DuplicateKey::new).collect::<Vec<_>>();
+    let mut pairs = Vec::new();
+    for (index, key) in keys.iter().enumerate() {
+        for (other_index, other_key) in keys.iter().enumerate().skip(index + 1) {
+            if let Some((reasons, name_similarity)) = key.compare(other_key) {
+                pairs.push(DuplicatePair {
+                    users: [users[index].clone(), users[other_index].clone()],
+                    reasons,
+                    name_similarity,
+                });
+            }
+        }
+    }
+
+    pairs.sort_by(|left, right| right.name_similarity.total_cmp(&left.name_similarity));
+    pairs.truncate(MAX_DUPLICATE_PAIRS);
+
+    Ok(Json(pairs))
+}
+
+/// Exporta todos los usuarios del inquilino como archivo descargable en JSON, CSV o Parquet.
+///
+/// La codificación se realiza en un hilo bloqueante para no detener el runtime mientras
+/// se genera el archivo.
+pub async fn export_users(
+    tenant: Tenant,
+    Database(database_pool): Database,
+    State(encryption): State<EmailEncryption>,
+    Query(options): Query<ExportOptions>,
+) -> Result<Response, AppError> {
+    let users = sqlx::query_as!(
+        User,
+        r#"SELECT id AS "id!: Uuid", name, email, created_at AS "created_at: DateTime<Utc>",
+                  status AS "status: UserStatus",
+                  suspended_until AS "suspended_until: DateTime<Utc>", suspension_reason,
+                  avatar_url, tenant_id
+           FROM users WHERE tenant_id = ?"#,
+        tenant.0
+    )
+    .fetch_all(&database_pool)
+    .traced("users.export", None)
+    .await
+    .map_err(AppError::from)?;
+    let users = encryption.open_users(users).map_err(AppError::internal)?;
+
+    let format = options.format;
+    let contents = tokio::task::spawn_blocking(move || export::encode_users(&users, format))
+        .await
+        .map_err(|error| AppError::internal(error.into()))?
+        .map_err(AppError::internal)?;
+
+    let disposition = format!("attachment; filename=\"users.{}\"", format.extension());
+
+    Ok((
+        [
+            (header::CONTENT_TYPE, format.content_type().to_string()),
+            (header::CONTENT_DISPOSITION, disposition),
+        ],
+        contents
+    )
+        .into_response())
+}
+
+/// Número máximo de elementos aceptados en una creación en lote.
+const MAX_BATCH_SIZE: usize = 500;
+
+/// Crea varios usuarios en una única transacción devolviendo un resultado por elemento.
+///
+/// Los elementos inválidos (`422`) o con correo ya registrado (`409`) no impiden que el
+/// resto se persista; la respuesta usa `207 Multi-Status` con el detalle de cada uno. Si
+/// los creados superan el máximo de usuarios del inquilino se rechaza el lote completo.
+#[allow(clippy::too_many_arguments)]
+pub async fn batch_create_users(
+    tenant: Tenant,
+    quota: TenantQuota,
+    Database(database_pool): Database,
+    State(outbox): State<Outbox>,
+    State(cache): State<Cache>,
+    State(ids): State<Arc<dyn IdGenerator>>,
+    State(clock): State<Arc<dyn Clock>>,
+    State(encryption): State<EmailEncryption>,
+    policy: EmailDomainPolicy,
+    Json(payload): Json<Vec<CreateUser>>,
+) -> Result<(StatusCode, Json<BatchCreateResponse>), AppError> {
+    if payload.len() > MAX_BATCH_SIZE {
+        return Err(AppError::bad_request(
+            "El lote supera el máximo de 500 elementos",
+        ));
+    }
+
+    let mut validated_users = Vec::with_capacity(payload.len());
+    for item in payload {
+        validated_users.push(validate_new_user(&policy, item).await?);
+    }
+
+    let mut results = Vec::with_capacity(validated_users.len());
+    let mut transaction = database_pool.begin().await.map_err(AppError::from)?;
+
+    for (index, validated_user) in validated_users.into_iter().enumerate() {
+        let validated_user = match validated_user {
+            Ok(validated_user) => validated_user,
+            Err(errors) => {
+                results.push(BatchItemResult {
+                    index,
+                    status: StatusCode::UNPROCESSABLE_ENTITY.as_u16(),
+                    user: None,
+                    errors: errors.errors,
+                });
+                continue;
+            }
+        };
+
+        let inserted = insert_user(
+            &mut transaction,
+            &encryption,
+            &tenant,
+            ids.generate(),
+            clock.now(),
+            validated_user,
+        )
+        .await;
+        match inserted {
+            Ok(Some(user)) => {
+                outbox::record(
+                    &mut *transaction,
+                    &encryption,
+                    DomainEvent::UserCreated { user: user.clone() }
+                )
+                .await
+                .map_err(AppError::from)?;
+                results.push(BatchItemResult {
+                    index,
+                    status: StatusCode::CREATED.as_u16(),
+                    user: Some(user),
+                    errors: Vec::new(),
+                });
+            }
+            Ok(None) => results.push(BatchItemResult {
+                index,
+                status: StatusCode::CONFLICT.as_u16(),
+                user: None,
+                errors: vec![ValidationError {
+                    field: "email",
+                    message: DUPLICATE_EMAIL_MESSAGE,
+                    code: None,
+                }],
+            }),
+            Err(error) => return Err(AppError::from(error)),
+        }
+    }
+
+    let created = results
+        .iter()
+        .filter(|result| result.user.is_some())
+        .count();
+    quota
+        .ensure_user_capacity(&mut *transaction, &tenant, created as i64)
+        .await?;
+    transaction.commit().await.map_err(AppError::from)?;
+
+    outbox.wake();
+    cache.invalidate_user_list(tenant.id()).await;
+
+    Ok((StatusCode::MULTI_STATUS, Json(BatchCreateResponse { results })))
+}
+
+/// Importa usuarios en bloque a partir de un CSV (`text/csv`) o de un arreglo JSON.
+///
+/// Cada fila se valida de forma independiente y se informa su resultado (creada, omitida
+/// por duplicada o inválida). Toda la importación ocurre en una única transacción; con
+/// `?dry_run=true` la transacción se revierte al final, de modo que el informe refleja lo
+/// que ocurriría sin persistir nada. Si las filas creadas superan el máximo de usuarios del
+/// inquilino se rechaza la importación completa.
+#[allow(clippy::too_many_arguments)]
+pub async fn import_users(
+    tenant: Tenant,
+    quota: TenantQuota,
+    Database(database_pool): Database,
+    State(outbox): State<Outbox>,
+    State(cache): State<Cache>,
+    State(ids): State<Arc<dyn IdGenerator>>,
+    State(clock): State<Arc<dyn Clock>>,
+    State(encryption): State<EmailEncryption>,
+    policy: EmailDomainPolicy,
+    Query(options): Query<ImportOptions>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> Result<Json<ImportReport>, AppError> {
+    let is_csv = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/csv"));
+
+    let rows = if is_csv {
+        parse_csv_rows(&body)?
+    } else {
+        parse_json_rows(&body)?
     };
 
-    Ok((StatusCode::CREATED, Json(user)))
+    let mut report = ImportReport {
+        dry_run: options.dry_run,
+        ..ImportReport::default()
+    };
+
+    let mut validated_rows = Vec::with_capacity(rows.len());
+    for parsed_row in rows {
+        validated_rows.push(match parsed_row {
+            Ok(item) => Ok(validate_new_user(&policy, item).await?),
+            Err(error) => Err(error),
+        });
+    }
+
+    let mut transaction = database_pool.begin().await.map_err(AppError::from)?;
+
+    for (index, validated_row) in validated_rows.into_iter().enumerate() {
+        let row = index + 1;
+
+        let validated_user = match validated_row {
+            Ok(Ok(validated_user)) => validated_user,
+            Ok(Err(errors)) => {
+                report.record(ImportRowReport {
+                    row,
+                    status: ImportRowStatus::Invalid,
+                    id: None,
+                    errors: errors.errors,
+                });
+                continue;
+            }
+            Err(error) => {
+                report.record(ImportRowReport {
+                    row,
+                    status: ImportRowStatus::Invalid,
+                    id: None,
+                    errors: vec![error],
+                });
+                continue;
+            }
+        };
+
+        let inserted = insert_user(
+            &mut transaction,
+            &encryption,
+            &tenant,
+            ids.generate(),
+            clock.now(),
+            validated_user,
+        )
+        .await
+        .map_err(AppError::from)?;
+        let Some(user) = inserted else {
+            report.record(ImportRowReport {
+                row,
+                status: ImportRowStatus::SkippedDuplicate,
+                id: None,
+                errors: Vec::new(),
+            });
+            continue;
+        };
+        outbox::record(
+            &mut *transaction,
+            &encryption,
+            DomainEvent::UserCreated { user: user.clone() }
+        )
+        .await
+        .map_err(AppError::from)?;
+
+        report.record(ImportRowReport {
+            row,
+            status: ImportRowStatus::Created,
+            id: Some(user.id),
+            errors: Vec::new(),
+        });
+    }
+
+    quota
+        .ensure_user_capacity(&mut *transaction, &tenant, report.created as i64)
+        .await?;
+
+    if options.dry_run {
+        transaction.rollback().await.map_err(AppError::from)?;
+    } else {
+        transaction.commit().await.map_err(AppError::from)?;
+        outbox.wake();
+        cache.invalidate_user_list(tenant.id()).await;
+    }
+
+    Ok(Json(report))
 }
 
 /// Actualiza un usuario existente aplicando solo los campos proporcionados en la solicitud.
+///
+/// Si `ALLOW_PUT_UPSERT` está activo y el usuario no existe, se crea con el UUID indicado
+/// por el cliente (exigiendo entonces `name` y `email`) y se responde `201 Created`.
+#[allow(clippy::too_many_arguments)]
 pub async fn update_user(
-    Path(user_id): Path<Uuid>,
-    State(database_pool): State<Pool<Sqlite>>,
+    UserId(user_id): UserId,
+    tenant: Tenant,
+    quota: TenantQuota,
+    Database(database_pool): Database,
+    State(config): State<Arc<AppConfig>>,
+    State(outbox): State<Outbox>,
+    State(cache): State<Cache>,
+    State(encryption): State<EmailEncryption>,
+    State(clock): State<Arc<dyn Clock>>,
+    policy: EmailDomainPolicy,
     Json(payload): Json<UpdateUser>,
-) -> Result<Json<User>, AppError> {
-    let requested_changes = UserChanges::try_from(payload).map_err(AppError::validation)?;
+) -> Result<(StatusCode, Json<User>), AppError> {
+    let email_blocked = match payload.email.as_deref() {
+        Some(email) => policy.is_blocked(&email.trim().to_lowercase()).await?,
+        None => false,
+    };
 
     let mut transaction = database_pool.begin().await.map_err(AppError::from)?;
-    let current_user = sqlx::query_as::<_, User>(
-        "SELECT id, name, email, created_at FROM users WHERE id = ?",
+    let current_user = sqlx::query_as!(
+        User,
+        r#"SELECT id AS "id!: Uuid", name, email, created_at AS "created_at: DateTime<Utc>",
+                  status AS "status: UserStatus",
+                  suspended_until AS "suspended_until: DateTime<Utc>", suspension_reason,
+                  avatar_url, tenant_id
+           FROM users WHERE id = ? AND tenant_id = ?"#,
+        user_id,
+        tenant.0
     )
-    .bind(user_id)
-    .fetch_one(&mut *transaction)
+    .fetch_optional(&mut *transaction)
+    .traced("users.get", Some(user_id))
     .await
-    .map_err(|error| match error {
-        sqlx::Error::RowNotFound => AppError::not_found(),
-        other => AppError::from(other),
-    })?;
+    .map_err(AppError::from)?
+    .map(|user| encryption.open_user(user))
+    .transpose()
+    .map_err(AppError::internal)?;
 
-    let merged_name = requested_changes.name.unwrap_or(current_user.name);
-    let merged_email = requested_changes.email.unwrap_or(current_user.email);
+    let Some(current_user) = current_user else {
+        if !config.allow_put_upsert {
+            return Err(AppError::not_found());
+        }
 
-    sqlx::query("UPDATE users SET name = ?, email = ? WHERE id = ?")
-        .bind(&merged_name)
-        .bind(&merged_email)
-        .bind(user_id)
-        .execute(&mut *transaction)
+        let validated_user = NewUser::validate(CreateUser {
+            name: payload.name.unwrap_or_default(),
+            email: payload.email.unwrap_or_default(),
+        })
+        .map_err(AppError::validation)?;
+        if email_blocked {
+            return Err(AppError::validation(blocked_email_errors()));
+        }
+
+        let user = insert_user(
+            &mut transaction,
+            &encryption,
+            &tenant,
+            user_id,
+            clock.now(),
+            validated_user,
+        )
+        .await
+        .map_err(AppError::from)?
+        .ok_or_else(|| AppError::validation(duplicate_email_errors()))?;
+        quota
+            .ensure_user_capacity(&mut *transaction, &tenant, 1)
+            .await?;
+        outbox::record(
+            &mut *transaction,
+            &encryption,
+            DomainEvent::UserCreated { user: user.clone() }
+        )
         .await
         .map_err(AppError::from)?;
+        transaction.commit().await.map_err(AppError::from)?;
 
-    transaction.commit().await.map_err(AppError::from)?;
+        outbox.wake();
+        cache.invalidate_user(tenant.id(), user_id).await;
+
+        return Ok((StatusCode::CREATED, Json(user)));
+    };
+    if current_user.status == UserStatus::Merged {
+        return Err(AppError::bad_request(MERGED_USER_MESSAGE));
+    }
+
+    let requested_changes = UserChanges::validate(payload).map_err(AppError::validation)?;
+    let email_changed = requested_changes
+        .email
+        .as_ref()
+        .is_some_and(|email| *email != current_user.email);
+    // Quien ya usa un dominio bloqueado puede conservarlo, pero no se puede cambiar a uno.
+    if email_blocked && email_changed {
+        return Err(AppError::validation(blocked_email_errors()));
+    }
+
+    let name_changed = requested_changes
+        .name
+        .as_ref()
+        .is_some_and(|name| *name != current_user.name);
+    let merged_name = requested_changes.name.unwrap_or(current_user.name);
+    let merged_email = requested_changes.email.unwrap_or(current_user.email);
+    if email_changed
+        && email_in_use(&mut transaction, &encryption, &merged_email)
+            .await
+            .map_err(AppError::from)?
+    {
+        return Err(AppError::validation(duplicate_email_errors()));
+    }
+
+    let sealed_email = encryption.seal(&merged_email);
+    let email_index = encryption.blind_index(&merged_email);
+    sqlx::query!(
+        "UPDATE users SET name = ?, email = ?, email_index = ? WHERE id = ? AND tenant_id = ?",
+        merged_name,
+        sealed_email,
+        email_index,
+        user_id,
+        tenant.0
+    )
+    .execute(&mut *transaction)
+    .traced("users.update", Some(user_id))
+    .await
+    .map_err(AppError::from)?;
+    if name_changed {
+        fuzzy_search::index_user(&mut transaction, &tenant.0, user_id, &merged_name)
+            .await
+            .map_err(AppError::from)?;
+    }
 
     let updated_user = User {
-        id: user_id,
         name: merged_name,
         email: merged_email,
-        created_at: current_user.created_at,
+        ..current_user
     };
 
-    Ok(Json(updated_user))
+    outbox::record(
+        &mut *transaction,
+        &encryption,
+        DomainEvent::UserUpdated {
+            user: updated_user.clone(),
+        }
+    )
+    .await
+    .map_err(AppError::from)?;
+    transaction.commit().await.map_err(AppError::from)?;
+
+    outbox.wake();
+    cache.invalidate_user(tenant.id(), user_id).await;
+
+    Ok((StatusCode::OK, Json(updated_user)))
 }
 
 /// Elimina un usuario concreto si existe.
 pub async fn delete_user(
-    Path(user_id): Path<Uuid>,
-    State(database_pool): State<Pool<Sqlite>>,
+    UserId(user_id): UserId,
+    tenant: Tenant,
+    Database(database_pool): Database,
+    State(storage): State<Arc<dyn Storage>>,
+    State(encryption): State<EmailEncryption>,
+    State(outbox): State<Outbox>,
+    State(cache): State<Cache>,
 ) -> Result<StatusCode, AppError> {
-    let deletion_result = sqlx::query("DELETE FROM users WHERE id = ?")
-        .bind(user_id)
-        .execute(&database_pool)
+    let mut transaction = database_pool.begin().await.map_err(AppError::from)?;
+    let avatar_key = sqlx::query_scalar!(
+        "DELETE FROM users WHERE id = ? AND tenant_id = ? RETURNING avatar_key",
+        user_id,
+        tenant.0
+    )
+    .fetch_optional(&mut *transaction)
+    .traced("users.delete", Some(user_id))
+    .await
+    .map_err(AppError::from)?
+    .ok_or_else(AppError::not_found)?;
+
+    outbox::record(
+        &mut *transaction,
+        &encryption,
+        DomainEvent::UserDeleted {
+            user_id,
+            tenant_id: tenant.0.clone(),
+        }
+    )
+    .await
+    .map_err(AppError::from)?;
+    transaction.commit().await.map_err(AppError::from)?;
+
+    if let Some(avatar_key) = avatar_key {
+        remove_stored_avatar(storage.as_ref(), &avatar_key).await;
+    }
+
+    outbox.wake();
+    cache.invalidate_user(tenant.id(), user_id).await;
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Elimina en una única transacción todos los usuarios indicados, informando de los
+/// identificadores que no existían.
+pub async fn batch_delete_users(
+    tenant: Tenant,
+    Database(database_pool): Database,
+    State(storage): State<Arc<dyn Storage>>,
+    State(encryption): State<EmailEncryption>,
+    State(outbox): State<Outbox>,
+    State(cache): State<Cache>,
+    Json(payload): Json<BatchDeleteUsers>,
+) -> Result<Json<BatchDeleteResponse>, AppError> {
+    if payload.ids.len() > MAX_BATCH_SIZE {
+        return Err(AppError::bad_request(
+            "El lote supera el máximo de 500 elementos",
+        ));
+    }
+
+    let mut deleted = Vec::new();
+    let mut not_found = Vec::new();
+    let mut transaction = database_pool.begin().await.map_err(AppError::from)?;
+
+    let mut avatar_keys = Vec::new();
+    for user_id in payload.ids {
+        let deleted_row = sqlx::query_scalar!(
+            "DELETE FROM users WHERE id = ? AND tenant_id = ? RETURNING avatar_key",
+            user_id,
+            tenant.0
+        )
+        .fetch_optional(&mut *transaction)
+        .traced("users.delete", Some(user_id))
         .await
         .map_err(AppError::from)?;
 
-    if deletion_result.rows_affected() == 0 {
-        return Err(AppError::not_found());
+        match deleted_row {
+            None => not_found.push(user_id),
+            Some(avatar_key) => {
+                outbox::record(
+                    &mut *transaction,
+                    &encryption,
+                    DomainEvent::UserDeleted {
+                        user_id,
+                        tenant_id: tenant.0.clone(),
+                    }
+                )
+                .await
+                .map_err(AppError::from)?;
+                avatar_keys.extend(avatar_key);
+                deleted.push(user_id);
+            }
+        }
     }
 
-    Ok(StatusCode::NO_CONTENT)
+    transaction.commit().await.map_err(AppError::from)?;
+
+    for avatar_key in &avatar_keys {
+        remove_stored_avatar(storage.as_ref(), avatar_key).await;
+    }
+
+    outbox.wake();
+    for user_id in &deleted {
+        cache.invalidate_user(tenant.id(), *user_id).await;
+    }
+
+    Ok(Json(BatchDeleteResponse { deleted, not_found }))
 }
 
-/// Forma serializada del error que se devolverá en las respuestas HTTP.
-#[derive(Debug, Serialize)]
-struct ErrorResponse {
-    message: &'static str,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    errors: Option<Vec<FieldError>>,
+/// Suspende temporalmente a un usuario registrando el motivo y la fecha de expiración.
+pub async fn suspend_user(
+    UserId(user_id): UserId,
+    tenant: Tenant,
+    Database(database_pool): Database,
+    State(outbox): State<Outbox>,
+    State(cache): State<Cache>,
+    State(encryption): State<EmailEncryption>,
+    ValidatedJson(suspension): ValidatedJson<Suspension>,
+) -> Result<Json<User>, AppError> {
+    let mut transaction = database_pool.begin().await.map_err(AppError::from)?;
+    ensure_not_merged(&mut *transaction, &tenant, user_id).await?;
+    let user = sqlx::query_as!(
+        User,
+        r#"UPDATE users SET status = ?, suspended_until = ?, suspension_reason = ?
+           WHERE id = ? AND tenant_id = ?
+           RETURNING id AS "id!: Uuid", name, email, created_at AS "created_at: DateTime<Utc>",
+                     status AS "status: UserStatus",
+                     suspended_until AS "suspended_until: DateTime<Utc>", suspension_reason,
+                     avatar_url, tenant_id"#,
+        UserStatus::Suspended,
+        suspension.until,
+        suspension.reason,
+        user_id,
+        tenant.0
+    )
+    .fetch_optional(&mut *transaction)
+    .traced("users.suspend", Some(user_id))
+    .await
+    .map_err(AppError::from)?
+    .ok_or_else(AppError::not_found)?;
+    let user = encryption.open_user(user).map_err(AppError::internal)?;
+
+    outbox::record(
+        &mut *transaction,
+        &encryption,
+        DomainEvent::UserUpdated { user: user.clone() }
+    )
+    .await
+    .map_err(AppError::from)?;
+    transaction.commit().await.map_err(AppError::from)?;
+
+    outbox.wake();
+    cache.invalidate_user(tenant.id(), user_id).await;
+
+    Ok(Json(user))
 }
 
-/// Error por campo utilizado para describir el detalle de validaciones fallidas.
-#[derive(Debug, Serialize)]
-struct FieldError {
-    field: &'static str,
-    message: &'static str,
+/// Reactiva a un usuario suspendido o dado de baja, levantando la suspensión si la tuviera.
+pub async fn activate_user(
+    UserId(user_id): UserId,
+    tenant: Tenant,
+    Database(database_pool): Database,
+    State(outbox): State<Outbox>,
+    State(cache): State<Cache>,
+    State(encryption): State<EmailEncryption>,
+) -> Result<Json<User>, AppError> {
+    change_status(
+        &database_pool,
+        &outbox,
+        &cache,
+        &encryption,
+        &tenant,
+        user_id,
+        UserStatus::Active
+    )
+    .await
 }
 
-/// Error personalizado que agrupa distintas situaciones a nivel aplicación.
-#[derive(Debug)]
-pub struct AppError {
-    kind: AppErrorKind,
+/// Da de baja a un usuario; deja de aparecer en el listado por defecto hasta reactivarlo.
+pub async fn deactivate_user(
+    UserId(user_id): UserId,
+    tenant: Tenant,
+    Database(database_pool): Database,
+    State(outbox): State<Outbox>,
+    State(cache): State<Cache>,
+    State(encryption): State<EmailEncryption>,
+) -> Result<Json<User>, AppError> {
+    change_status(
+        &database_pool,
+        &outbox,
+        &cache,
+        &encryption,
+        &tenant,
+        user_id,
+        UserStatus::Deactivated
+    )
+    .await
 }
 
-/// Enumeración interna para clasificar los errores posibles.
-#[derive(Debug)]
-enum AppErrorKind {
-    Validation(ValidationErrors),
-    NotFound,
-    Sqlx(sqlx::Error),
+/// Fija el estado de un usuario sin fecha de expiración, descartando cualquier suspensión.
+async fn change_status(
+    database_pool: &Pool<Sqlite>,
+    outbox: &Outbox,
+    cache: &Cache,
+    encryption: &EmailEncryption,
+    tenant: &Tenant,
+    user_id: Uuid,
+    status: UserStatus,
+) -> Result<Json<User>, AppError> {
+    let mut transaction = database_pool.begin().await.map_err(AppError::from)?;
+    ensure_not_merged(&mut *transaction, tenant, user_id).await?;
+    let user = sqlx::query_as!(
+        User,
+        r#"UPDATE users SET status = ?, suspended_until = NULL, suspension_reason = NULL
+           WHERE id = ? AND tenant_id = ?
+           RETURNING id AS "id!: Uuid", name, email, created_at AS "created_at: DateTime<Utc>",
+                     status AS "status: UserStatus",
+                     suspended_until AS "suspended_until: DateTime<Utc>", suspension_reason,
+                     avatar_url, tenant_id"#,
+        status,
+        user_id,
+        tenant.0
+    )
+    .fetch_optional(&mut *transaction)
+    .traced("users.set_status", Some(user_id))
+    .await
+    .map_err(AppError::from)?
+    .ok_or_else(AppError::not_found)?;
+    let user = encryption.open_user(user).map_err(AppError::internal)?;
+
+    outbox::record(
+        &mut *transaction,
+        encryption,
+        DomainEvent::UserUpdated { user: user.clone() }
+    )
+    .await
+    .map_err(AppError::from)?;
+    transaction.commit().await.map_err(AppError::from)?;
+
+    outbox.wake();
+    cache.invalidate_user(tenant.id(), user_id).await;
+
+    Ok(Json(user))
 }
 
-impl AppError {
-    /// Construye un error de validación.
-    fn validation(errors: ValidationErrors) -> Self {
-        Self {
-            kind: AppErrorKind::Validation(errors),
-        }
+/// Fusiona `source_id` en el usuario de la ruta: sus publicaciones, comentarios, etiquetas,
+/// equipos y vistas pasan al destino, que conserva su nombre y su correo, y el origen queda
+/// en el estado terminal `merged` apuntando al destino. Todo ocurre en una única
+/// transacción, que registra `UserMerged` para el origen y `UserUpdated` para el destino.
+pub async fn merge_users(
+    UserId(target_id): UserId,
+    tenant: Tenant,
+    Database(database_pool): Database,
+    State(outbox): State<Outbox>,
+    State(cache): State<Cache>,
+    State(encryption): State<EmailEncryption>,
+    Json(payload): Json<MergeUsers>,
+) -> Result<Json<User>, AppError> {
+    let source_id = payload.source_id;
+    if source_id == target_id {
+        let mut errors = ValidationErrors::new();
+        errors.push("source_id", "Debe ser distinto del usuario de destino");
+        return Err(AppError::validation(errors));
     }
 
-    /// Construye un error de tipo "recurso no encontrado".
-    fn not_found() -> Self {
-        Self {
-            kind: AppErrorKind::NotFound,
-        }
+    let mut transaction = database_pool.begin().await.map_err(AppError::from)?;
+    for user_id in [target_id, source_id] {
+        ensure_not_merged(&mut *transaction, &tenant, user_id).await?;
+    }
+
+    let statements = [
+        sqlx::query!(
+            "UPDATE posts SET author_id = ?1 WHERE author_id = ?2",
+            target_id,
+            source_id
+        ),
+        sqlx::query!(
+            "UPDATE comments SET author_id = ?1 WHERE author_id = ?2",
+            target_id,
+            source_id
+        ),
+        sqlx::query!(
+            "INSERT OR IGNORE INTO user_tags (user_id, tag_id, created_at) \
+             SELECT ?1, tag_id, created_at FROM user_tags WHERE user_id = ?2",
+            target_id,
+            source_id
+        ),
+        sqlx::query!("DELETE FROM user_tags WHERE user_id = ?1", source_id),
+        // Si ambos pertenecían al mismo equipo se conserva el rol más alto.
+        sqlx::query!(
+            "INSERT INTO team_members (team_id, user_id, role, created_at) \
+             SELECT team_id, ?1, role, created_at FROM team_members WHERE user_id = ?2 \
+             ON CONFLICT (team_id, user_id) DO UPDATE SET role = 'owner' \
+             WHERE excluded.role = 'owner'",
+            target_id,
+            source_id
+        ),
+        sqlx::query!("DELETE FROM team_members WHERE user_id = ?1", source_id),
+        // Las vistas con un nombre que el destino ya usa se quedan en el origen.
+        sqlx::query!(
+            "UPDATE OR IGNORE saved_views SET owner_id = ?1 WHERE owner_id = ?2",
+            target_id,
+            source_id
+        ),
+    ];
+    for statement in statements {
+        statement
+            .execute(&mut *transaction)
+            .await
+            .map_err(AppError::from)?;
     }
+
+    let source = sqlx::query_as!(
+        User,
+        r#"UPDATE users SET status = ?, merged_into = ?, suspended_until = NULL,
+                            suspension_reason = NULL
+           WHERE id = ? AND tenant_id = ?
+           RETURNING id AS "id!: Uuid", name, email, created_at AS "created_at: DateTime<Utc>",
+                     status AS "status: UserStatus",
+                     suspended_until AS "suspended_until: DateTime<Utc>", suspension_reason,
+                     avatar_url, tenant_id"#,
+        UserStatus::Merged,
+        target_id,
+        source_id,
+        tenant.0
+    )
+    .fetch_one(&mut *transaction)
+    .traced("users.set_status", Some(source_id))
+    .await
+    .map_err(AppError::from)?;
+    let target = sqlx::query_as!(
+        User,
+        r#"SELECT id AS "id!: Uuid", name, email, created_at AS "created_at: DateTime<Utc>",
+                  status AS "status: UserStatus",
+                  suspended_until AS "suspended_until: DateTime<Utc>", suspension_reason,
+                  avatar_url, tenant_id
+           FROM users WHERE id = ? AND tenant_id = ?"#,
+        target_id,
+        tenant.0
+    )
+    .fetch_one(&mut *transaction)
+    .traced("users.get", Some(target_id))
+    .await
+    .map_err(AppError::from)?;
+
+    let source = encryption.open_user(source).map_err(AppError::internal)?;
+    let target = encryption.open_user(target).map_err(AppError::internal)?;
+
+    let events = [
+        DomainEvent::UserMerged {
+            user: source,
+            merged_into: target_id,
+        },
+        DomainEvent::UserUpdated {
+            user: target.clone(),
+        },
+    ];
+    for event in events {
+        outbox::record(&mut *transaction, &encryption, event)
+            .await
+            .map_err(AppError::from)?;
+    }
+    transaction.commit().await.map_err(AppError::from)?;
+
+    outbox.wake();
+    cache.invalidate_user(tenant.id(), source_id).await;
+    cache.invalidate_user(tenant.id(), target_id).await;
+
+    Ok(Json(target))
 }
 
-impl From<sqlx::Error> for AppError {
-    fn from(error: sqlx::Error) -> Self {
-        Self {
-            kind: AppErrorKind::Sqlx(error),
-        }
+/// Sustituye el avatar de un usuario por la imagen enviada en el campo multipart `avatar`.
+#[allow(clippy::too_many_arguments)]
+pub async fn upload_avatar(
+    UserId(user_id): UserId,
+    tenant: Tenant,
+    Database(database_pool): Database,
+    State(storage): State<Arc<dyn Storage>>,
+    State(outbox): State<Outbox>,
+    State(cache): State<Cache>,
+    State(encryption): State<EmailEncryption>,
+    mut multipart: Multipart,
+) -> Result<Json<User>, AppError> {
+    let avatar = read_avatar(&mut multipart).await?;
+
+    let mut transaction = database_pool.begin().await.map_err(AppError::from)?;
+    let previous_key = sqlx::query_scalar!(
+        "SELECT avatar_key FROM users WHERE id = ? AND tenant_id = ?",
+        user_id,
+        tenant.0
+    )
+    .fetch_optional(&mut *transaction)
+    .traced("users.get_avatar_key", Some(user_id))
+    .await
+    .map_err(AppError::from)?
+    .ok_or_else(AppError::not_found)?;
+
+    let key = avatar.storage_key(user_id);
+    storage
+        .put(&key, avatar.bytes, avatar.format.content_type())
+        .await
+        .map_err(AppError::internal)?;
+
+    let url = avatar_url(user_id);
+    let user = sqlx::query_as!(
+        User,
+        r#"UPDATE users SET avatar_key = ?, avatar_url = ? WHERE id = ?
+           RETURNING id AS "id!: Uuid", name, email, created_at AS "created_at: DateTime<Utc>",
+                     status AS "status: UserStatus",
+                     suspended_until AS "suspended_until: DateTime<Utc>", suspension_reason,
+                     avatar_url, tenant_id"#,
+        key,
+        url,
+        user_id
+    )
+    .fetch_one(&mut *transaction)
+    .traced("users.set_avatar", Some(user_id))
+    .await
+    .map_err(AppError::from)?;
+    let user = encryption.open_user(user).map_err(AppError::internal)?;
+
+    outbox::record(
+        &mut *transaction,
+        &encryption,
+        DomainEvent::UserUpdated { user: user.clone() }
+    )
+    .await
+    .map_err(AppError::from)?;
+    transaction.commit().await.map_err(AppError::from)?;
+
+    // Si cambió el formato, la clave anterior ya no la referencia nadie.
+    if let Some(previous_key) = previous_key.filter(|previous_key| *previous_key != key) {
+        remove_stored_avatar(storage.as_ref(), &previous_key).await;
     }
+
+    outbox.wake();
+    cache.invalidate_user(tenant.id(), user_id).await;
+
+    Ok(Json(user))
 }
 
-impl IntoResponse for AppError {
-    fn into_response(self) -> Response {
-        match self.kind {
-            AppErrorKind::Validation(errors) => {
-                let details = errors
-                    .errors
-                    .into_iter()
-                    .map(|ValidationError { field, message }| FieldError { field, message })
-                    .collect::<Vec<_>>();
+/// Sirve el avatar de un usuario desde su URL estable, con `ETag` para revalidar, o
+/// redirige a una URL prefirmada si el almacén la ofrece.
+pub async fn get_avatar(
+    UserId(user_id): UserId,
+    tenant: Tenant,
+    Database(database_pool): Database,
+    State(storage): State<Arc<dyn Storage>>,
+    headers: HeaderMap,
+) -> Result<Response, AppError> {
+    let key = sqlx::query_scalar!(
+        "SELECT avatar_key FROM users WHERE id = ? AND tenant_id = ?",
+        user_id,
+        tenant.0
+    )
+    .fetch_optional(&database_pool)
+    .traced("users.get_avatar_key", Some(user_id))
+    .await
+    .map_err(AppError::from)?
+    .flatten()
+    .ok_or_else(AppError::not_found)?;
 
-                let body = Json(ErrorResponse {
-                    message: "Datos de entrada inválidos",
-                    errors: Some(details),
-                });
+    // Con almacenes remotos el cliente descarga directamente del bucket.
+    if let Some(url) = storage.presigned_url(&key) {
+        return Ok((
+            StatusCode::TEMPORARY_REDIRECT,
+            [
+                (header::LOCATION, url),
+                (header::CACHE_CONTROL, "no-store".to_string()),
+            ]
+        )
+            .into_response());
+    }
+
+    let bytes = storage
+        .get(&key)
+        .await
+        .map_err(AppError::internal)?
+        .ok_or_else(AppError::not_found)?;
+
+    let content_type = AvatarFormat::from_key(&key)
+        .map(AvatarFormat::content_type)
+        .unwrap_or("application/octet-stream");
+    let etag = format!("\"{}\"", hex::encode(&Sha256::digest(&bytes)[..16]));
+    let cache_headers = [
+        (header::CACHE_CONTROL, REVALIDATE_CACHE_CONTROL.to_string()),
+        (header::ETAG, etag.clone()),
+    ];
+
+    let is_fresh = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.split(',').any(|tag| tag.trim() == etag));
+    if is_fresh {
+        return Ok((StatusCode::NOT_MODIFIED, cache_headers).into_response());
+    }
 
-                (StatusCode::UNPROCESSABLE_ENTITY, body).into_response()
+    Ok((
+        cache_headers,
+        [
+            (header::CONTENT_TYPE, content_type),
+            (header::X_CONTENT_TYPE_OPTIONS, "nosniff"),
+        ],
+        bytes
+    )
+        .into_response())
+}
+
+/// Lee el campo `avatar` del formulario, sin acumular más de lo que se admite.
+async fn read_avatar(multipart: &mut Multipart) -> Result<Avatar, AppError> {
+    let invalid_body = |_| AppError::bad_request("Cuerpo multipart inválido");
+
+    while let Some(mut field) = multipart.next_field().await.map_err(invalid_body)? {
+        if field.name() != Some(AVATAR_FIELD) {
+            continue;
+        }
+
+        let content_type = field.content_type().map(str::to_owned);
+        let mut bytes = Vec::new();
+        while let Some(chunk) = field.chunk().await.map_err(invalid_body)? {
+            bytes.extend_from_slice(&chunk);
+            if bytes.len() > MAX_AVATAR_BYTES {
+                break;
             }
-            AppErrorKind::NotFound => (
-                StatusCode::NOT_FOUND,
-                Json(ErrorResponse {
-                    message: "Recurso no encontrado",
-                    errors: None,
-                }),
+        }
+
+        return Avatar::try_new(content_type.as_deref(), bytes).map_err(AppError::validation);
+    }
+
+    Err(AppError::bad_request(
+        "Falta el campo `avatar` en el formulario",
+    ))
+}
+
+/// Borra un avatar que ya no está referenciado; un fallo solo deja un archivo huérfano.
+pub(crate) async fn remove_stored_avatar(storage: &dyn Storage, key: &str) {
+    if let Err(error) = storage.delete(key).await {
+        warn!(?error, key, "No se pudo borrar el avatar anterior");
+    }
+}
+
+/// Levanta las suspensiones que expiraron en `now` o antes, registra en el outbox el cambio
+/// de cada usuario liberado y devuelve su inquilino. Quien la llame debe avisar al relay.
+pub async fn lift_expired_suspensions(
+    database_pool: &Pool<Sqlite>,
+    encryption: &EmailEncryption,
+    now: DateTime<Utc>,
+) -> anyhow::Result<Vec<String>> {
+    let mut transaction = database_pool.begin().await?;
+    let users = sqlx::query_as!(
+        User,
+        r#"UPDATE users SET status = ?, suspended_until = NULL, suspension_reason = NULL
+           WHERE status = ? AND suspended_until <= ?
+           RETURNING id AS "id!: Uuid", name, email, created_at AS "created_at: DateTime<Utc>",
+                     status AS "status: UserStatus",
+                     suspended_until AS "suspended_until: DateTime<Utc>", suspension_reason,
+                     avatar_url, tenant_id"#,
+        UserStatus::Active,
+        UserStatus::Suspended,
+        now
+    )
+    .fetch_all(&mut *transaction)
+    .traced("users.lift_expired_suspensions", None)
+    .await?;
+
+    let mut tenants = Vec::with_capacity(users.len());
+    for user in encryption.open_users(users)? {
+        tenants.push(user.tenant_id.clone());
+        outbox::record(&mut *transaction, encryption, DomainEvent::UserUpdated { user }).await?;
+    }
+    transaction.commit().await?;
+
+    Ok(tenants)
+}
+
+/// Indica si algún usuario, de cualquier inquilino, usa ya `email`.
+///
+/// El correo es único entre todos los inquilinos. Con el cifrado activo se busca por el índice
+/// ciego y también por el correo en claro, que conservan las filas aún no cifradas con
+/// `rekey`: la restricción `UNIQUE` no los relaciona con el mismo correo cifrado.
+async fn email_in_use(
+    connection: &mut SqliteConnection,
+    encryption: &EmailEncryption,
+    email: &str,
+) -> Result<bool, sqlx::Error> {
+    let email_index = encryption.blind_index(email);
+    sqlx::query_scalar!(
+        r#"SELECT EXISTS(SELECT 1 FROM users WHERE email = ? OR email_index = ?)
+           AS "exists!: bool""#,
+        email,
+        email_index
+    )
+    .fetch_one(connection)
+    .traced("users.email_exists", None)
+    .await
+}
+
+/// Mensaje para el campo `email` cuando el correo ya lo usa otro usuario.
+const DUPLICATE_EMAIL_MESSAGE: &str = "Ya existe un usuario con este correo";
+
+/// Errores de validación de un correo que ya usa otro usuario.
+fn duplicate_email_errors() -> ValidationErrors {
+    let mut errors = ValidationErrors::new();
+    errors.push("email", DUPLICATE_EMAIL_MESSAGE);
+    errors
+}
+
+/// Inserta un usuario ya validado en el inquilino y con el identificador indicados, con
+/// `created_timestamp` como marca de creación, y lo añade al índice de búsqueda aproximada.
+/// El correo se guarda cifrado si el cifrado está activo. Devuelve `None`, sin insertar
+/// nada, si el correo ya está en uso (véase [`email_in_use`]).
+async fn insert_user(
+    connection: &mut SqliteConnection,
+    encryption: &EmailEncryption,
+    tenant: &Tenant,
+    user_id: Uuid,
+    created_timestamp: DateTime<Utc>,
+    validated_user: NewUser,
+) -> Result<Option<User>, sqlx::Error> {
+    if email_in_use(connection, encryption, &validated_user.email).await? {
+        return Ok(None);
+    }
+
+    let sealed_email = encryption.seal(&validated_user.email);
+    let email_index = encryption.blind_index(&validated_user.email);
+
+    sqlx::query!(
+        "INSERT INTO users (id, tenant_id, name, email, email_index, created_at) \
+         VALUES (?, ?, ?, ?, ?, ?)",
+        user_id,
+        tenant.0,
+        validated_user.name,
+        sealed_email,
+        email_index,
+        created_timestamp
+    )
+    .execute(&mut *connection)
+    .traced("users.insert", Some(user_id))
+    .await?;
+    fuzzy_search::index_user(connection, &tenant.0, user_id, &validated_user.name).await?;
+
+    Ok(Some(User {
+        id: user_id,
+        name: validated_user.name,
+        email: validated_user.email,
+        created_at: created_timestamp,
+        status: UserStatus::Active,
+        suspended_until: None,
+        suspension_reason: None,
+        avatar_url: None,
+        tenant_id: tenant.0.clone(),
+    }))
+}
+
+/// Responde `404` si el usuario no existe o pertenece a otro inquilino.
+pub(crate) async fn ensure_user_exists<'e, E>(
+    executor: E,
+    tenant: &Tenant,
+    user_id: Uuid,
+) -> Result<(), AppError>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    sqlx::query_scalar!(
+        "SELECT 1 FROM users WHERE id = ? AND tenant_id = ?",
+        user_id,
+        tenant.0
+    )
+    .fetch_optional(executor)
+    .traced("users.exists", Some(user_id))
+    .await
+    .map_err(AppError::from)?
+    .map(|_| ())
+    .ok_or_else(AppError::not_found)
+}
+
+/// Mensaje con el que se rechazan los cambios sobre una cuenta fusionada.
+const MERGED_USER_MESSAGE: &str = "El usuario se fusionó en otro y ya no admite cambios";
+
+/// Responde `404` si el usuario no existe o pertenece a otro inquilino, y `400` si está
+/// fusionado en otro: el estado `merged` es terminal.
+async fn ensure_not_merged<'e, E>(
+    executor: E,
+    tenant: &Tenant,
+    user_id: Uuid,
+) -> Result<(), AppError>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    let status = sqlx::query_scalar!(
+        r#"SELECT status AS "status: UserStatus" FROM users WHERE id = ? AND tenant_id = ?"#,
+        user_id,
+        tenant.0
+    )
+    .fetch_optional(executor)
+    .traced("users.get_status", Some(user_id))
+    .await
+    .map_err(AppError::from)?
+    .ok_or_else(AppError::not_found)?;
+
+    if status == UserStatus::Merged {
+        return Err(AppError::bad_request(MERGED_USER_MESSAGE));
+    }
+    Ok(())
+}
+
+/// Añade a `builder` el `WHERE` del listado de usuarios: inquilino, estado, etiqueta y
+/// `?filter=`. Con el cifrado de correos activo el correo guardado no se puede comparar, así
+/// que el filtro no admite el campo `email`.
+pub(crate) fn push_user_filters(
+    builder: &mut QueryBuilder<'_, Sqlite>,
+    tenant: &Tenant,
+    encryption: &EmailEncryption,
+    query: ListUsersQuery,
+) -> Result<(), AppError> {
+    let tag = query
+        .tag
+        .map(TagName::try_from)
+        .transpose()
+        .map_err(AppError::validation)?;
+    let filter = match query.filter {
+        Some(expression) => {
+            let fields = USER_FILTER_FIELDS
+                .iter()
+                .copied()
+                .filter(|field| !(encryption.is_enabled() && field.name == "email"))
+                .collect::<Vec<_>>();
+            Some(Filter::parse(&expression, &fields).map_err(AppError::validation)?)
+        }
+        None => None,
+    };
+
+    builder
+        .push(" WHERE tenant_id = ")
+        .push_bind(tenant.0.clone());
+    match query.status.status() {
+        Some(status) => builder.push(" AND status = ").push_bind(status),
+        // Las cuentas fusionadas no se listan nunca, ni siquiera con `status=all`.
+        None => builder.push(" AND status != ").push_bind(UserStatus::Merged),
+    };
+    if let Some(TagName(tag)) = tag {
+        builder
+            .push(
+                " AND id IN (SELECT user_tags.user_id FROM user_tags \
+                 JOIN tags ON tags.id = user_tags.tag_id WHERE tags.name = "
             )
-                .into_response(),
-            AppErrorKind::Sqlx(error) => {
-                error!(?error, "Error en la base de datos");
-                (
-                    StatusCode::INTERNAL_SERVER_ERROR,
-                    Json(ErrorResponse {
-                        message: "Ocurrió un error inesperado",
-                        errors: None,
-                    }),
-                )
-                    .into_response()
-            }
+            .push_bind(tag)
+            .push(")");
+    }
+    if let Some(filter) = filter {
+        builder.push(" AND ");
+        filter.push_sql(builder);
+    }
+
+    Ok(())
+}
+
+/// Cuenta en la base los usuarios que cumplen los filtros del listado.
+async fn count_matching_users(
+    database_pool: &Pool<Sqlite>,
+    tenant: &Tenant,
+    encryption: &EmailEncryption,
+    query: ListUsersQuery,
+) -> Result<i64, AppError> {
+    let mut builder = QueryBuilder::<Sqlite>::new("SELECT COUNT(*) FROM users");
+    push_user_filters(&mut builder, tenant, encryption, query)?;
+
+    builder
+        .build_query_scalar::<i64>()
+        .fetch_one(database_pool)
+        .traced("users.count", None)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Indica si el error proviene de una restricción `UNIQUE` de la base de datos.
+pub(crate) fn is_unique_violation(error: &sqlx::Error) -> bool {
+    error
+        .as_database_error()
+        .is_some_and(|database_error| database_error.is_unique_violation())
+}
+
+/// Valida un alta de un lote o importación, rechazando además los dominios bloqueados.
+async fn validate_new_user(
+    policy: &EmailDomainPolicy,
+    item: CreateUser,
+) -> Result<Result<NewUser, ValidationErrors>, AppError> {
+    Ok(match NewUser::validate(item) {
+        Ok(validated_user) if policy.is_blocked(&validated_user.email).await? => {
+            Err(blocked_email_errors())
         }
+        validated_user => validated_user,
+    })
+}
+
+/// Usuarios del inquilino, distintos de `user`, que podrían ser la misma persona.
+async fn find_duplicate_candidates<'e, E>(
+    executor: E,
+    encryption: &EmailEncryption,
+    tenant: &Tenant,
+    user: &User,
+) -> Result<Vec<DuplicateCandidate>, AppError>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    let others = sqlx::query_as!(
+        User,
+        r#"SELECT id AS "id!: Uuid", name, email, created_at AS "created_at: DateTime<Utc>",
+                  status AS "status: UserStatus",
+                  suspended_until AS "suspended_until: DateTime<Utc>", suspension_reason,
+                  avatar_url, tenant_id
+           FROM users WHERE tenant_id = ? AND id != ? AND erased_at IS NULL
+                 AND status != 'merged'
+           ORDER BY created_at, id"#,
+        tenant.0,
+        user.id
+    )
+    .fetch_all(executor)
+    .traced("users.list_comparable", Some(user.id))
+    .await
+    .map_err(AppError::from)?;
+    let others = encryption.open_users(others).map_err(AppError::internal)?;
+
+    let key = DuplicateKey::new(user);
+    let mut candidates = others
+        .into_iter()
+        .filter_map(|other| {
+            let (reasons, name_similarity) = key.compare(&DuplicateKey::new(&other))?;
+            Some(DuplicateCandidate {
+                user: other,
+                reasons,
+                name_similarity,
+            })
+        })
+        .collect::<Vec<_>>();
+    candidates.sort_by(|left, right| right.name_similarity.total_cmp(&left.name_similarity));
+
+    Ok(candidates)
+}
+
+/// Fila de importación ya decodificada o el error que impidió leerla.
+type ParsedRow = Result<CreateUser, ValidationError>;
+
+/// Error por fila cuando su estructura no coincide con la esperada.
+const MALFORMED_ROW: ValidationError = ValidationError {
+    field: "general",
+    message: "Fila con formato inválido",
+    code: None,
+};
+
+/// Decodifica un CSV con cabecera `name,email` en filas independientes.
+fn parse_csv_rows(body: &[u8]) -> Result<Vec<ParsedRow>, AppError> {
+    let mut reader = csv::ReaderBuilder::new().trim(csv::Trim::Headers).from_reader(body);
+
+    let headers = reader
+        .headers()
+        .map_err(|_| AppError::bad_request("CSV inválido"))?
+        .clone();
+    if !headers.iter().any(|column| column == "name")
+        || !headers.iter().any(|column| column == "email")
+    {
+        return Err(AppError::bad_request(
+            "El CSV debe incluir las columnas name y email",
+        ));
     }
-}
\ No newline at end of file
+
+    Ok(reader
+        .deserialize::<CreateUser>()
+        .map(|record| record.map_err(|_| MALFORMED_ROW))
+        .collect())
+}
+
+/// Decodifica un arreglo JSON, tolerando elementos individuales mal formados.
+fn parse_json_rows(body: &[u8]) -> Result<Vec<ParsedRow>, AppError> {
+    let values: Vec<serde_json::Value> = serde_json::from_slice(body)
+        .map_err(|_| AppError::bad_request("Se esperaba un arreglo JSON de usuarios"))?;
+
+    Ok(values
+        .into_iter()
+        .map(|value| serde_json::from_value::<CreateUser>(value).map_err(|_| MALFORMED_ROW))
+        .collect())
+}
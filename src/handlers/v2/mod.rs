@@ -0,0 +1,6 @@
+//! Handlers de la versión 2 de la API.
+//!
+//! Solo existen para los endpoints cuya respuesta cambia respecto a v1; delegan en los
+//! handlers de v1 y adaptan el resultado a la representación de v2.
+
+pub mod user;
@@ -0,0 +1,131 @@
+//! Handlers de `/v2/users`.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Query, State},
+    http::StatusCode,
+    Json,
+};
+
+use crate::cache::Cache;
+use crate::clock::Clock;
+use crate::config::AppConfig;
+use crate::email_domains::EmailDomainPolicy;
+use crate::encryption::EmailEncryption;
+use crate::handlers::error::AppError;
+use crate::handlers::fields::SparseFields;
+use crate::handlers::total_count::TotalCount;
+use crate::handlers::user;
+use crate::handlers::validated::ValidatedJson;
+use crate::ids::{IdGenerator, UserId};
+use crate::models::duplicate::CreateUserOptions;
+use crate::models::expand::UserExpansions;
+use crate::models::projection::Projected;
+use crate::models::user::{ListUsersQuery, NewUser, UpdateUser};
+use crate::models::v2::user::{UserList, UserV2};
+use crate::outbox::Outbox;
+use crate::tenant::{Database, Tenant, TenantQuota};
+
+/// Devuelve los usuarios del inquilino envueltos en `data`, con el total en `X-Total-Count`.
+pub async fn list_users(
+    tenant: Tenant,
+    database: Database,
+    cache: State<Cache>,
+    encryption: State<EmailEncryption>,
+    query: Query<ListUsersQuery>,
+) -> Result<(TotalCount, Json<UserList>), AppError> {
+    let (total, Json(users)) = user::list_users(
+        tenant,
+        database,
+        cache,
+        encryption,
+        query,
+        SparseFields::all(),
+    )
+    .await?;
+    let users = users
+        .into_iter()
+        .map(Projected::into_inner)
+        .collect::<Vec<_>>();
+
+    Ok((total, Json(UserList::from(users))))
+}
+
+/// Recupera un usuario concreto identificado por su UUID.
+pub async fn get_user(
+    user_id: UserId,
+    tenant: Tenant,
+    database: Database,
+    cache: State<Cache>,
+    encryption: State<EmailEncryption>,
+) -> Result<Json<UserV2>, AppError> {
+    let Json(expanded) = user::get_user(
+        user_id,
+        tenant,
+        database,
+        cache,
+        encryption,
+        SparseFields::all(),
+        UserExpansions::default(),
+    )
+    .await?;
+
+    Ok(Json(UserV2::from(expanded.user.into_inner())))
+}
+
+/// Crea un nuevo usuario; el cuerpo de la petición es el mismo que en v1. La comprobación
+/// de duplicados con `?check_duplicates=true` solo está disponible en v1.
+#[allow(clippy::too_many_arguments)]
+pub async fn create_user(
+    tenant: Tenant,
+    quota: TenantQuota,
+    database: Database,
+    outbox: State<Outbox>,
+    cache: State<Cache>,
+    ids: State<Arc<dyn IdGenerator>>,
+    clock: State<Arc<dyn Clock>>,
+    encryption: State<EmailEncryption>,
+    policy: EmailDomainPolicy,
+    payload: ValidatedJson<NewUser>,
+) -> Result<(StatusCode, Json<UserV2>), AppError> {
+    let (status, Json(created)) = user::create_user(
+        tenant,
+        quota,
+        database,
+        outbox,
+        cache,
+        ids,
+        clock,
+        encryption,
+        policy,
+        Query(CreateUserOptions::default()),
+        payload,
+    )
+    .await?;
+
+    Ok((status, Json(UserV2::from(created.user))))
+}
+
+/// Actualiza un usuario existente; el cuerpo de la petición es el mismo que en v1.
+#[allow(clippy::too_many_arguments)]
+pub async fn update_user(
+    user_id: UserId,
+    tenant: Tenant,
+    quota: TenantQuota,
+    database: Database,
+    config: State<Arc<AppConfig>>,
+    outbox: State<Outbox>,
+    cache: State<Cache>,
+    encryption: State<EmailEncryption>,
+    clock: State<Arc<dyn Clock>>,
+    policy: EmailDomainPolicy,
+    payload: Json<UpdateUser>,
+) -> Result<(StatusCode, Json<UserV2>), AppError> {
+    let (status, Json(user)) = user::update_user(
+        user_id, tenant, quota, database, config, outbox, cache, encryption, clock, policy, payload,
+    )
+    .await?;
+
+    Ok((status, Json(UserV2::from(user))))
+}
@@ -0,0 +1,38 @@
+//! Extractor de cuerpos JSON validados.
+//!
+//! [`ValidatedJson`] deserializa el payload declarado por [`Validate::Payload`] y lo valida
+//! antes de llegar al handler, de modo que cada tipo nuevo solo define sus reglas.
+
+use axum::{
+    async_trait,
+    extract::{FromRequest, Request},
+    response::{IntoResponse, Response},
+    Json,
+};
+
+use crate::handlers::error::AppError;
+use crate::models::validation::Validate;
+
+/// Cuerpo JSON ya validado y saneado. Si no supera la validación se responde `422` con el
+/// formato de error común; los cuerpos mal formados se rechazan igual que con [`Json`].
+#[derive(Debug, Clone)]
+pub struct ValidatedJson<T>(pub T);
+
+#[async_trait]
+impl<S, T> FromRequest<S> for ValidatedJson<T>
+where
+    S: Send + Sync,
+    T: Validate,
+{
+    type Rejection = Response;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        let Json(payload) = Json::<T::Payload>::from_request(request, state)
+            .await
+            .map_err(IntoResponse::into_response)?;
+
+        <T as Validate>::validate(payload)
+            .map(Self)
+            .map_err(|errors| AppError::validation(errors).into_response())
+    }
+}
@@ -0,0 +1,146 @@
+//! Handlers HTTP para las vistas guardadas del listado de usuarios.
+//!
+//! Cada vista pertenece a quien la crea, identificado mediante [`Actor`]: solo su dueño la
+//! lista, la ejecuta o la borra, y para cualquier otro responde `404` como si no existiera.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use sqlx::{QueryBuilder, Sqlite};
+use uuid::Uuid;
+
+use crate::clock::Clock;
+use crate::database::TracedQuery;
+use crate::encryption::EmailEncryption;
+use crate::handlers::actor::Actor;
+use crate::handlers::error::AppError;
+use crate::handlers::post::is_foreign_key_violation;
+use crate::handlers::total_count::TotalCount;
+use crate::handlers::user::{is_unique_violation, push_user_filters};
+use crate::handlers::validated::ValidatedJson;
+use crate::models::user::{User, ValidationErrors, USER_COLUMNS};
+use crate::models::view::{NewView, SavedView, SAVED_VIEW_COLUMNS};
+use crate::tenant::{Database, Tenant};
+
+/// Devuelve las vistas de quien realiza la petición, por nombre.
+pub async fn list_views(
+    actor: Actor,
+    tenant: Tenant,
+    Database(database_pool): Database,
+) -> Result<Json<Vec<SavedView>>, AppError> {
+    let views = sqlx::query_as::<_, SavedView>(&format!(
+        "SELECT {SAVED_VIEW_COLUMNS} FROM saved_views WHERE owner_id = ? AND tenant_id = ? \
+         ORDER BY name"
+    ))
+    .bind(actor.0)
+    .bind(tenant.id())
+    .fetch_all(&database_pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(Json(views))
+}
+
+/// Guarda una vista a nombre de quien realiza la petición. El nombre no se puede repetir
+/// entre sus vistas.
+pub async fn create_view(
+    actor: Actor,
+    tenant: Tenant,
+    Database(database_pool): Database,
+    State(clock): State<Arc<dyn Clock>>,
+    ValidatedJson(view): ValidatedJson<NewView>,
+) -> Result<(StatusCode, Json<SavedView>), AppError> {
+    let view = sqlx::query_as::<_, SavedView>(&format!(
+        "INSERT INTO saved_views \
+         (id, tenant_id, owner_id, name, status, tag, filter, sort, created_at) \
+         VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?) RETURNING {SAVED_VIEW_COLUMNS}"
+    ))
+    .bind(Uuid::new_v4())
+    .bind(tenant.id())
+    .bind(actor.0)
+    .bind(&view.name)
+    .bind(view.status)
+    .bind(&view.tag)
+    .bind(&view.filter)
+    .bind(view.sort)
+    .bind(clock.now())
+    .fetch_one(&database_pool)
+    .await
+    .map_err(|error| {
+        if is_unique_violation(&error) {
+            let mut errors = ValidationErrors::new();
+            errors.push("name", "Ya tiene una vista con ese nombre");
+            AppError::validation(errors)
+        } else if is_foreign_key_violation(&error) {
+            // La cabecera no corresponde a ningún usuario existente.
+            AppError::unauthorized()
+        } else {
+            AppError::from(error)
+        }
+    })?;
+
+    Ok((StatusCode::CREATED, Json(view)))
+}
+
+/// Borra una vista de quien realiza la petición.
+pub async fn delete_view(
+    actor: Actor,
+    tenant: Tenant,
+    Database(database_pool): Database,
+    Path(view_id): Path<Uuid>,
+) -> Result<StatusCode, AppError> {
+    let result =
+        sqlx::query("DELETE FROM saved_views WHERE id = ? AND owner_id = ? AND tenant_id = ?")
+            .bind(view_id)
+            .bind(actor.0)
+            .bind(tenant.id())
+            .execute(&database_pool)
+            .await
+            .map_err(AppError::from)?;
+
+    if result.rows_affected() == 0 {
+        return Err(AppError::not_found());
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Ejecuta una vista: devuelve los usuarios que obtendría el listado con sus parámetros, en
+/// el orden de la vista, con el total en `X-Total-Count`.
+pub async fn view_results(
+    actor: Actor,
+    tenant: Tenant,
+    Database(database_pool): Database,
+    State(encryption): State<EmailEncryption>,
+    Path(view_id): Path<Uuid>,
+) -> Result<(TotalCount, Json<Vec<User>>), AppError> {
+    let view = sqlx::query_as::<_, SavedView>(&format!(
+        "SELECT {SAVED_VIEW_COLUMNS} FROM saved_views \
+         WHERE id = ? AND owner_id = ? AND tenant_id = ?"
+    ))
+    .bind(view_id)
+    .bind(actor.0)
+    .bind(tenant.id())
+    .fetch_optional(&database_pool)
+    .await
+    .map_err(AppError::from)?
+    .ok_or_else(AppError::not_found)?;
+
+    let mut builder = QueryBuilder::<Sqlite>::new(format!("SELECT {USER_COLUMNS} FROM users"));
+    push_user_filters(&mut builder, &tenant, &encryption, view.list_query())?;
+    builder.push(" ORDER BY ").push(view.sort.order_by());
+
+    let users = builder
+        .build_query_as::<User>()
+        .fetch_all(&database_pool)
+        .traced("views.results", None)
+        .await
+        .map_err(AppError::from)?;
+    let users = encryption.open_users(users).map_err(AppError::internal)?;
+
+    Ok((TotalCount(users.len() as i64), Json(users)))
+}
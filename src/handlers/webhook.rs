@@ -0,0 +1,184 @@
+//! Handlers HTTP para gestionar suscripciones de webhooks.
+//!
+//! Permiten dar de alta, consultar, modificar y eliminar suscripciones, así como revisar
+//! el historial de entregas de cada una. Cada inquilino solo ve y gestiona las suyas.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::{Path, State},
+    http::StatusCode,
+    Json,
+};
+use sqlx::{types::Json as SqlJson, Pool, Sqlite};
+use uuid::Uuid;
+
+use crate::clock::Clock;
+use crate::handlers::error::AppError;
+use crate::handlers::validated::ValidatedJson;
+use crate::models::webhook::{
+    CreatedWebhook, NewWebhook, Webhook, WebhookChanges, WebhookDelivery, WEBHOOK_COLUMNS,
+};
+use crate::tenant::Tenant;
+use crate::webhooks::generate_secret;
+
+/// Número máximo de entregas devueltas en el historial.
+const DELIVERY_HISTORY_LIMIT: i64 = 100;
+
+/// Devuelve las suscripciones del inquilino.
+pub async fn list_webhooks(
+    tenant: Tenant,
+    State(database_pool): State<Pool<Sqlite>>,
+) -> Result<Json<Vec<Webhook>>, AppError> {
+    let webhooks = sqlx::query_as::<_, Webhook>(&format!(
+        "SELECT {WEBHOOK_COLUMNS} FROM webhooks WHERE tenant_id = ? ORDER BY created_at"
+    ))
+    .bind(tenant.id())
+    .fetch_all(&database_pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(Json(webhooks))
+}
+
+/// Recupera una suscripción concreta.
+pub async fn get_webhook(
+    Path(webhook_id): Path<Uuid>,
+    tenant: Tenant,
+    State(database_pool): State<Pool<Sqlite>>,
+) -> Result<Json<Webhook>, AppError> {
+    let webhook = fetch_webhook(&database_pool, &tenant, webhook_id).await?;
+
+    Ok(Json(webhook))
+}
+
+/// Crea una suscripción y devuelve el secreto de firma por única vez.
+pub async fn create_webhook(
+    tenant: Tenant,
+    State(database_pool): State<Pool<Sqlite>>,
+    State(clock): State<Arc<dyn Clock>>,
+    ValidatedJson(validated_webhook): ValidatedJson<NewWebhook>,
+) -> Result<(StatusCode, Json<CreatedWebhook>), AppError> {
+    let secret = validated_webhook.secret.unwrap_or_else(generate_secret);
+
+    let webhook = Webhook {
+        id: Uuid::new_v4(),
+        url: validated_webhook.url,
+        secret: secret.clone(),
+        events: SqlJson(validated_webhook.events),
+        active: true,
+        created_at: clock.now(),
+        tenant_id: tenant.id().to_string(),
+    };
+
+    sqlx::query(
+        "INSERT INTO webhooks (id, url, secret, events, active, created_at, tenant_id) \
+         VALUES (?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(webhook.id)
+    .bind(&webhook.url)
+    .bind(&webhook.secret)
+    .bind(&webhook.events)
+    .bind(webhook.active)
+    .bind(webhook.created_at)
+    .bind(&webhook.tenant_id)
+    .execute(&database_pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(CreatedWebhook { webhook, secret }),
+    ))
+}
+
+/// Actualiza la URL, los eventos o el estado activo de una suscripción.
+pub async fn update_webhook(
+    Path(webhook_id): Path<Uuid>,
+    tenant: Tenant,
+    State(database_pool): State<Pool<Sqlite>>,
+    ValidatedJson(requested_changes): ValidatedJson<WebhookChanges>,
+) -> Result<Json<Webhook>, AppError> {
+    let current_webhook = fetch_webhook(&database_pool, &tenant, webhook_id).await?;
+    let updated_webhook = Webhook {
+        url: requested_changes.url.unwrap_or(current_webhook.url),
+        events: requested_changes
+            .events
+            .map(SqlJson)
+            .unwrap_or(current_webhook.events),
+        active: requested_changes.active.unwrap_or(current_webhook.active),
+        ..current_webhook
+    };
+
+    sqlx::query(
+        "UPDATE webhooks SET url = ?, events = ?, active = ? WHERE id = ? AND tenant_id = ?",
+    )
+    .bind(&updated_webhook.url)
+    .bind(&updated_webhook.events)
+    .bind(updated_webhook.active)
+    .bind(webhook_id)
+    .bind(tenant.id())
+    .execute(&database_pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(Json(updated_webhook))
+}
+
+/// Elimina una suscripción junto con su historial de entregas.
+pub async fn delete_webhook(
+    Path(webhook_id): Path<Uuid>,
+    tenant: Tenant,
+    State(database_pool): State<Pool<Sqlite>>,
+) -> Result<StatusCode, AppError> {
+    let deletion_result = sqlx::query("DELETE FROM webhooks WHERE id = ? AND tenant_id = ?")
+        .bind(webhook_id)
+        .bind(tenant.id())
+        .execute(&database_pool)
+        .await
+        .map_err(AppError::from)?;
+
+    if deletion_result.rows_affected() == 0 {
+        return Err(AppError::not_found());
+    }
+
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Devuelve las entregas más recientes de una suscripción, de la más nueva a la más antigua.
+pub async fn list_webhook_deliveries(
+    Path(webhook_id): Path<Uuid>,
+    tenant: Tenant,
+    State(database_pool): State<Pool<Sqlite>>,
+) -> Result<Json<Vec<WebhookDelivery>>, AppError> {
+    fetch_webhook(&database_pool, &tenant, webhook_id).await?;
+
+    let deliveries = sqlx::query_as::<_, WebhookDelivery>(
+        "SELECT id, webhook_id, event_id, event_type, attempt, status_code, error, success, \
+         attempted_at FROM webhook_deliveries WHERE webhook_id = ? ORDER BY id DESC LIMIT ?",
+    )
+    .bind(webhook_id)
+    .bind(DELIVERY_HISTORY_LIMIT)
+    .fetch_all(&database_pool)
+    .await
+    .map_err(AppError::from)?;
+
+    Ok(Json(deliveries))
+}
+
+/// Busca una suscripción del inquilino devolviendo `404` si no existe o es de otro.
+async fn fetch_webhook(
+    database_pool: &Pool<Sqlite>,
+    tenant: &Tenant,
+    webhook_id: Uuid,
+) -> Result<Webhook, AppError> {
+    sqlx::query_as::<_, Webhook>(&format!(
+        "SELECT {WEBHOOK_COLUMNS} FROM webhooks WHERE id = ? AND tenant_id = ?"
+    ))
+    .bind(webhook_id)
+    .bind(tenant.id())
+    .fetch_optional(database_pool)
+    .await
+    .map_err(AppError::from)?
+    .ok_or_else(AppError::not_found)
+}
@@ -0,0 +1,315 @@
+//! Generación y representación de identificadores de usuario.
+//!
+//! Cada despliegue elige con `ID_FORMAT` cómo se generan y se muestran los identificadores:
+//! `uuid-v7` (por defecto), `uuid-v4` o `ulid`, y con `ID_PREFIX` un prefijo al estilo de
+//! Stripe (`ID_PREFIX=usr` produce `usr_01J9Z3...`). En la base de datos todos se guardan
+//! igual, como 128 bits en la columna `id`, así que cambiar de formato no exige migrar datos.
+//!
+//! UUIDv7 y ULID empiezan por la marca de tiempo en milisegundos: los identificadores se
+//! ordenan por fecha de creación, las inserciones caen al final del índice de la clave
+//! primaria y el desempate por `id` de la paginación sigue el orden de alta. Los usuarios
+//! existentes conservan sus UUIDv4; no se reescriben porque los identificadores son públicos,
+//! y los listados ordenan primero por `created_at`.
+//!
+//! El generador vive en el estado de la aplicación. Los extractores [`UserId`] y
+//! [`crate::handlers::actor::Actor`] interpretan el formato configurado y, por compatibilidad,
+//! también la forma canónica de UUID. Las respuestas HTTP muestran el formato configurado
+//! gracias a [`scope_public_ids`]; el outbox, los webhooks, el CDC y las exportaciones usan
+//! siempre la forma canónica.
+
+use std::{collections::HashMap, fmt, str::FromStr, sync::Arc};
+
+use axum::{
+    async_trait,
+    extract::{FromRef, FromRequestParts, Path, Request, State},
+    http::request::Parts,
+    middleware::Next,
+    response::Response,
+};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use ulid::Ulid;
+use uuid::Uuid;
+
+use crate::config::AppConfig;
+use crate::handlers::error::AppError;
+
+/// Estrategia de generación y representación de identificadores.
+pub trait IdGenerator: fmt::Debug + Send + Sync + 'static {
+    /// Genera un identificador nuevo.
+    fn generate(&self) -> Uuid;
+
+    /// Representación pública del identificador.
+    fn format(&self, id: Uuid) -> String {
+        id.to_string()
+    }
+
+    /// Interpreta la representación pública; `None` si no tiene el formato esperado.
+    fn parse(&self, value: &str) -> Option<Uuid> {
+        Uuid::parse_str(value).ok()
+    }
+}
+
+/// UUID aleatorio (versión 4).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UuidV4;
+
+impl IdGenerator for UuidV4 {
+    fn generate(&self) -> Uuid {
+        Uuid::new_v4()
+    }
+}
+
+/// UUID ordenado por tiempo (versión 7).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UuidV7;
+
+impl IdGenerator for UuidV7 {
+    fn generate(&self) -> Uuid {
+        Uuid::now_v7()
+    }
+}
+
+/// ULID: 26 caracteres en base32 de Crockford, ordenados por tiempo.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct UlidGenerator;
+
+impl IdGenerator for UlidGenerator {
+    fn generate(&self) -> Uuid {
+        Uuid::from_u128(Ulid::new().0)
+    }
+
+    fn format(&self, id: Uuid) -> String {
+        Ulid(id.as_u128()).to_string()
+    }
+
+    fn parse(&self, value: &str) -> Option<Uuid> {
+        parse_ulid(value)
+    }
+}
+
+/// Antepone `<prefijo>_` a la representación de otro generador.
+#[derive(Debug, Clone)]
+pub struct Prefixed {
+    prefix: String,
+    inner: Arc<dyn IdGenerator>,
+}
+
+impl Prefixed {
+    pub fn new(prefix: impl Into<String>, inner: Arc<dyn IdGenerator>) -> Self {
+        Self {
+            prefix: prefix.into(),
+            inner,
+        }
+    }
+}
+
+impl IdGenerator for Prefixed {
+    fn generate(&self) -> Uuid {
+        self.inner.generate()
+    }
+
+    fn format(&self, id: Uuid) -> String {
+        format!("{}_{}", self.prefix, self.inner.format(id))
+    }
+
+    fn parse(&self, value: &str) -> Option<Uuid> {
+        let id = value.strip_prefix(&self.prefix)?.strip_prefix('_')?;
+        self.inner.parse(id)
+    }
+}
+
+/// Formato elegido con `ID_FORMAT`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IdFormat {
+    UuidV4,
+    #[default]
+    UuidV7,
+    Ulid,
+}
+
+impl FromStr for IdFormat {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "uuid-v4" | "uuidv4" => Ok(Self::UuidV4),
+            "uuid-v7" | "uuidv7" | "uuid" => Ok(Self::UuidV7),
+            "ulid" => Ok(Self::Ulid),
+            other => Err(format!("Formato de identificador desconocido: {other}")),
+        }
+    }
+}
+
+/// Construye el generador indicado en la configuración.
+pub fn from_config(config: &AppConfig) -> Arc<dyn IdGenerator> {
+    let generator: Arc<dyn IdGenerator> = match config.id_format {
+        IdFormat::UuidV4 => Arc::new(UuidV4),
+        IdFormat::UuidV7 => Arc::new(UuidV7),
+        IdFormat::Ulid => Arc::new(UlidGenerator),
+    };
+
+    match &config.id_prefix {
+        Some(prefix) => Arc::new(Prefixed::new(prefix, generator)),
+        None => generator,
+    }
+}
+
+/// Indica si `value` sirve como prefijo: letras minúsculas ASCII, como `usr`.
+pub fn is_valid_prefix(value: &str) -> bool {
+    !value.is_empty() && value.len() <= 8 && value.bytes().all(|byte| byte.is_ascii_lowercase())
+}
+
+fn parse_ulid(value: &str) -> Option<Uuid> {
+    Ulid::from_string(value)
+        .ok()
+        .map(|ulid| Uuid::from_u128(ulid.0))
+}
+
+tokio::task_local! {
+    /// Generador con el que se muestran los identificadores durante la petición en curso.
+    static PUBLIC_IDS: Option<Arc<dyn IdGenerator>>;
+}
+
+/// Middleware que muestra en el formato configurado los identificadores que serializa la
+/// petición.
+pub async fn scope_public_ids(
+    State(generator): State<Arc<dyn IdGenerator>>,
+    request: Request,
+    next: Next,
+) -> Response {
+    PUBLIC_IDS.scope(Some(generator), next.run(request)).await
+}
+
+/// Ejecuta `f` serializando los identificadores en su forma canónica, para los datos que se
+/// guardan o salen de la API (outbox, caché).
+pub fn canonical<R>(f: impl FnOnce() -> R) -> R {
+    PUBLIC_IDS.sync_scope(None, f)
+}
+
+/// Representación de `id` en el formato de la petición en curso.
+pub fn to_public(id: Uuid) -> String {
+    PUBLIC_IDS
+        .try_with(|generator| generator.as_ref().map(|generator| generator.format(id)))
+        .ok()
+        .flatten()
+        .unwrap_or_else(|| id.to_string())
+}
+
+/// Interpreta un identificador en cualquiera de los formatos admitidos, con o sin prefijo.
+/// Los cuerpos de las peticiones y los datos guardados no dependen así de la configuración.
+pub fn parse_any(value: &str) -> Option<Uuid> {
+    let value = value.rsplit_once('_').map_or(value, |(_, id)| id);
+    Uuid::parse_str(value).ok().or_else(|| parse_ulid(value))
+}
+
+/// Identificador que se serializa en el formato público y se deserializa desde cualquiera.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PublicId(pub Uuid);
+
+impl Serialize for PublicId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&to_public(self.0))
+    }
+}
+
+impl<'de> Deserialize<'de> for PublicId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        parse_any(&value)
+            .map(PublicId)
+            .ok_or_else(|| serde::de::Error::custom(format!("Identificador inválido: {value}")))
+    }
+}
+
+/// Uso con `#[serde(with = "crate::ids::public")]` en campos `Uuid`.
+pub mod public {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+    use uuid::Uuid;
+
+    use super::PublicId;
+
+    pub fn serialize<S: Serializer>(id: &Uuid, serializer: S) -> Result<S::Ok, S::Error> {
+        PublicId(*id).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Uuid, D::Error> {
+        PublicId::deserialize(deserializer).map(|id| id.0)
+    }
+
+    /// Variante para campos `Option<Uuid>`.
+    pub mod option {
+        use serde::{Deserialize, Deserializer, Serialize, Serializer};
+        use uuid::Uuid;
+
+        use super::PublicId;
+
+        pub fn serialize<S: Serializer>(
+            id: &Option<Uuid>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            id.map(PublicId).serialize(serializer)
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<Uuid>, D::Error> {
+            Ok(Option::<PublicId>::deserialize(deserializer)?.map(|id| id.0))
+        }
+    }
+
+    /// Variante para campos `Vec<Uuid>`.
+    pub mod vec {
+        use serde::{Deserialize, Deserializer, Serializer};
+        use uuid::Uuid;
+
+        use super::PublicId;
+
+        pub fn serialize<S: Serializer>(ids: &[Uuid], serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq(ids.iter().copied().map(PublicId))
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Vec<Uuid>, D::Error> {
+            let ids = Vec::<PublicId>::deserialize(deserializer)?;
+            Ok(ids.into_iter().map(|id| id.0).collect())
+        }
+    }
+}
+
+/// Identificador de usuario tomado de la ruta (`:user_id`, o `:id` en `/users/:id`) e
+/// interpretado con el generador configurado. Responde `404` si no tiene un formato válido.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UserId(pub Uuid);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for UserId
+where
+    Arc<dyn IdGenerator>: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Path(params) = Path::<HashMap<String, String>>::from_request_parts(parts, state)
+            .await
+            .map_err(|_| AppError::not_found())?;
+        let value = params
+            .get("user_id")
+            .or_else(|| params.get("id"))
+            .ok_or_else(AppError::not_found)?;
+
+        parse_user_id(Arc::<dyn IdGenerator>::from_ref(state).as_ref(), value)
+            .map(UserId)
+            .ok_or_else(AppError::not_found)
+    }
+}
+
+/// Interpreta un identificador de usuario recibido en la ruta o en una cabecera: el formato
+/// configurado o la forma canónica de UUID.
+pub fn parse_user_id(generator: &dyn IdGenerator, value: &str) -> Option<Uuid> {
+    let value = value.trim();
+    generator
+        .parse(value)
+        .or_else(|| Uuid::parse_str(value).ok())
+}
@@ -0,0 +1,359 @@
+//! Cola de trabajos en segundo plano respaldada por SQLite.
+//!
+//! Los handlers encolan trabajos (envío de correos, exportaciones, etc.) en la tabla `jobs`
+//! y responden de inmediato. Un `Worker` lanzado desde `main.rs` reclama los trabajos
+//! pendientes, los ejecuta con el `Job` registrado para su tipo y, ante un fallo, los
+//! reprograma con backoff exponencial hasta agotar los intentos.
+
+use std::{collections::HashMap, future::Future, pin::Pin, sync::Arc, time::Duration};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sqlx::{Executor, FromRow, Sqlite, SqlitePool};
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::state::AppState;
+
+/// Número de intentos por defecto antes de marcar un trabajo como fallido.
+pub const DEFAULT_MAX_ATTEMPTS: u32 = 5;
+/// Espera inicial por defecto entre reintentos; se duplica en cada intento.
+const DEFAULT_BASE_DELAY: Duration = Duration::from_secs(5);
+/// Espera por defecto entre consultas cuando la cola está vacía.
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Espera máxima entre reintentos, sea cual sea el número de intentos.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(3600);
+
+/// Columnas de la tabla `jobs` en el orden esperado por [`JobRecord`].
+const JOB_COLUMNS: &str =
+    "id, kind, payload, status, attempts, max_attempts, run_at, last_error, created_at, updated_at";
+
+/// Trabajo ejecutable en segundo plano.
+///
+/// El propio valor se serializa como carga útil al encolarlo y se reconstruye en el worker.
+pub trait Job: Serialize + DeserializeOwned + Send + Sync + 'static {
+    /// Identificador estable del tipo de trabajo, guardado en la columna `kind`.
+    const KIND: &'static str;
+
+    /// Número de intentos antes de dar el trabajo por fallido.
+    const MAX_ATTEMPTS: u32 = DEFAULT_MAX_ATTEMPTS;
+
+    /// Ejecuta el trabajo; devolver un error provoca un reintento.
+    fn run(self, state: AppState) -> impl Future<Output = Result<()>> + Send;
+}
+
+/// Estado de un trabajo dentro de la cola.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum JobStatus {
+    Pending,
+    Running,
+    Completed,
+    Failed,
+}
+
+/// Fila de la tabla `jobs`.
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct JobRecord {
+    pub id: Uuid,
+    pub kind: String,
+    pub payload: String,
+    pub status: JobStatus,
+    pub attempts: i64,
+    pub max_attempts: i64,
+    pub run_at: DateTime<Utc>,
+    pub last_error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+type JobFuture = Pin<Box<dyn Future<Output = Result<()>> + Send>>;
+type JobHandler = Arc<dyn Fn(AppState, &str) -> Result<JobFuture> + Send + Sync>;
+
+/// Relación entre los tipos de trabajo y el código que los ejecuta.
+#[derive(Clone, Default)]
+pub struct JobRegistry {
+    handlers: HashMap<&'static str, JobHandler>,
+}
+
+impl JobRegistry {
+    /// Crea un registro sin trabajos.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registra un tipo de trabajo para que el worker sepa ejecutarlo.
+    pub fn register<J: Job>(mut self) -> Self {
+        let handler: JobHandler = Arc::new(|state, payload| {
+            let job: J = serde_json::from_str(payload)
+                .with_context(|| format!("Carga útil inválida para el trabajo {}", J::KIND))?;
+            Ok(Box::pin(job.run(state)))
+        });
+        self.handlers.insert(J::KIND, handler);
+        self
+    }
+}
+
+impl std::fmt::Debug for JobRegistry {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter
+            .debug_struct("JobRegistry")
+            .field("kinds", &self.handlers.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// Encola un trabajo para ejecutarlo lo antes posible.
+///
+/// Acepta cualquier ejecutor, de modo que puede encolarse dentro de la misma transacción
+/// que el cambio que lo origina.
+pub async fn enqueue<'e, E, J>(executor: E, job: &J) -> Result<Uuid>
+where
+    E: Executor<'e, Database = Sqlite>,
+    J: Job,
+{
+    enqueue_at(executor, job, Utc::now()).await
+}
+
+/// Encola un trabajo para ejecutarlo a partir de `run_at`.
+pub async fn enqueue_at<'e, E, J>(executor: E, job: &J, run_at: DateTime<Utc>) -> Result<Uuid>
+where
+    E: Executor<'e, Database = Sqlite>,
+    J: Job,
+{
+    let job_id = Uuid::new_v4();
+    let now = timestamp(Utc::now());
+
+    sqlx::query(
+        "INSERT INTO jobs (id, kind, payload, status, attempts, max_attempts, run_at, \
+         created_at, updated_at) VALUES (?, ?, ?, 'pending', 0, ?, ?, ?, ?)",
+    )
+    .bind(job_id)
+    .bind(J::KIND)
+    .bind(serde_json::to_string(job)?)
+    .bind(J::MAX_ATTEMPTS.max(1))
+    .bind(timestamp(run_at))
+    .bind(&now)
+    .bind(&now)
+    .execute(executor)
+    .await?;
+
+    debug!(%job_id, kind = J::KIND, "Trabajo encolado");
+
+    Ok(job_id)
+}
+
+/// Recupera un trabajo por identificador.
+pub async fn fetch_job(database_pool: &SqlitePool, job_id: Uuid) -> Result<Option<JobRecord>> {
+    let job =
+        sqlx::query_as::<_, JobRecord>(&format!("SELECT {JOB_COLUMNS} FROM jobs WHERE id = ?"))
+            .bind(job_id)
+            .fetch_optional(database_pool)
+            .await?;
+
+    Ok(job)
+}
+
+/// Elimina los trabajos completados o fallidos cuya última actualización es anterior a
+/// `older_than` y devuelve cuántos se borraron.
+pub async fn purge_finished(database_pool: &SqlitePool, older_than: DateTime<Utc>) -> Result<u64> {
+    let result =
+        sqlx::query("DELETE FROM jobs WHERE status IN ('completed', 'failed') AND updated_at < ?")
+            .bind(timestamp(older_than))
+            .execute(database_pool)
+            .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Situación de la cola en un instante dado.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct QueueStats {
+    /// Trabajos pendientes, incluidos los programados para más adelante.
+    pub pending: i64,
+    /// Momento en que venció el trabajo pendiente más antiguo que aún no se ha recogido.
+    pub oldest_due_at: Option<DateTime<Utc>>,
+}
+
+/// Cuenta los trabajos pendientes y busca el vencido más antiguo a `now`.
+pub async fn queue_stats(database_pool: &SqlitePool, now: DateTime<Utc>) -> Result<QueueStats> {
+    let (pending, oldest_due_at) = sqlx::query_as::<_, (i64, Option<DateTime<Utc>>)>(
+        "SELECT COUNT(*), MIN(CASE WHEN run_at <= ? THEN run_at END) \
+         FROM jobs WHERE status = 'pending'",
+    )
+    .bind(timestamp(now))
+    .fetch_one(database_pool)
+    .await?;
+
+    Ok(QueueStats {
+        pending,
+        oldest_due_at,
+    })
+}
+
+/// Proceso que consume la cola ejecutando los trabajos registrados.
+#[derive(Debug, Clone)]
+pub struct Worker {
+    state: AppState,
+    registry: JobRegistry,
+    poll_interval: Duration,
+    base_delay: Duration,
+}
+
+impl Worker {
+    /// Crea un worker con los intervalos por defecto.
+    pub fn new(state: AppState, registry: JobRegistry) -> Self {
+        Self {
+            state,
+            registry,
+            poll_interval: DEFAULT_POLL_INTERVAL,
+            base_delay: DEFAULT_BASE_DELAY,
+        }
+    }
+
+    /// Ajusta la espera entre consultas cuando no hay trabajos pendientes.
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Ajusta la espera inicial entre reintentos.
+    pub fn with_base_delay(mut self, base_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self
+    }
+
+    /// Reclama y ejecuta el siguiente trabajo vencido; devuelve `false` si no había ninguno.
+    pub async fn run_once(&self) -> Result<bool> {
+        let database_pool = &self.state.database_pool;
+        let now = timestamp(Utc::now());
+
+        let Some(job) = sqlx::query_as::<_, JobRecord>(&format!(
+            "UPDATE jobs SET status = 'running', attempts = attempts + 1, updated_at = ? \
+             WHERE id = (SELECT id FROM jobs WHERE status = 'pending' AND run_at <= ? \
+             ORDER BY run_at LIMIT 1) RETURNING {JOB_COLUMNS}"
+        ))
+        .bind(&now)
+        .bind(&now)
+        .fetch_optional(database_pool)
+        .await?
+        else {
+            return Ok(false);
+        };
+
+        let outcome = match self.registry.handlers.get(job.kind.as_str()) {
+            Some(handler) => match handler(self.state.clone(), &job.payload) {
+                Ok(future) => future.await,
+                Err(error) => Err(error),
+            },
+            None => Err(anyhow::anyhow!("Tipo de trabajo desconocido: {}", job.kind)),
+        };
+
+        match outcome {
+            Ok(()) => {
+                sqlx::query(
+                    "UPDATE jobs SET status = 'completed', last_error = NULL, updated_at = ? \
+                     WHERE id = ?",
+                )
+                .bind(timestamp(Utc::now()))
+                .bind(job.id)
+                .execute(database_pool)
+                .await?;
+
+                debug!(job_id = %job.id, kind = %job.kind, "Trabajo completado");
+            }
+            Err(error) if job.attempts >= job.max_attempts => {
+                sqlx::query(
+                    "UPDATE jobs SET status = 'failed', last_error = ?, updated_at = ? WHERE id = ?",
+                )
+                .bind(format!("{error:#}"))
+                .bind(timestamp(Utc::now()))
+                .bind(job.id)
+                .execute(database_pool)
+                .await?;
+
+                warn!(
+                    job_id = %job.id,
+                    kind = %job.kind,
+                    attempts = job.attempts,
+                    ?error,
+                    "Trabajo fallido tras agotar los reintentos"
+                );
+            }
+            Err(error) => {
+                let retry_in = self.retry_delay(job.attempts);
+                let retry_at = Utc::now()
+                    + chrono::Duration::from_std(retry_in).unwrap_or(chrono::Duration::zero());
+
+                sqlx::query(
+                    "UPDATE jobs SET status = 'pending', last_error = ?, run_at = ?, \
+                     updated_at = ? WHERE id = ?",
+                )
+                .bind(format!("{error:#}"))
+                .bind(timestamp(retry_at))
+                .bind(timestamp(Utc::now()))
+                .bind(job.id)
+                .execute(database_pool)
+                .await?;
+
+                warn!(
+                    job_id = %job.id,
+                    kind = %job.kind,
+                    attempt = job.attempts,
+                    ?retry_in,
+                    ?error,
+                    "Trabajo fallido, se reintentará"
+                );
+            }
+        }
+
+        Ok(true)
+    }
+
+    /// Bucle del worker: devuelve a la cola los trabajos interrumpidos y procesa sin pausa
+    /// mientras haya trabajos vencidos.
+    pub async fn run(self) {
+        match self.requeue_interrupted().await {
+            Ok(0) => {}
+            Ok(requeued) => info!(requeued, "Trabajos interrumpidos devueltos a la cola"),
+            Err(error) => warn!(
+                ?error,
+                "No se pudieron recuperar los trabajos interrumpidos"
+            ),
+        }
+
+        info!(kinds = ?self.registry, "Worker de trabajos iniciado");
+
+        loop {
+            match self.run_once().await {
+                Ok(true) => continue,
+                Ok(false) => {}
+                Err(error) => warn!(?error, "Fallo al procesar la cola de trabajos"),
+            }
+            tokio::time::sleep(self.poll_interval).await;
+        }
+    }
+
+    /// Devuelve a `pending` los trabajos que quedaron en `running` por un apagado abrupto.
+    async fn requeue_interrupted(&self) -> Result<u64> {
+        let result = sqlx::query("UPDATE jobs SET status = 'pending' WHERE status = 'running'")
+            .execute(&self.state.database_pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Espera antes del siguiente intento tras `attempts` intentos fallidos.
+    fn retry_delay(&self, attempts: i64) -> Duration {
+        let exponent = attempts.saturating_sub(1).clamp(0, 16) as u32;
+        (self.base_delay * 2u32.pow(exponent)).min(MAX_RETRY_DELAY)
+    }
+}
+
+/// Formato de fecha con precisión fija, de modo que las comparaciones de texto en SQLite
+/// respeten el orden cronológico. También lo usa el planificador.
+pub(crate) fn timestamp(at: DateTime<Utc>) -> String {
+    at.format("%Y-%m-%dT%H:%M:%S%.6fZ").to_string()
+}
@@ -1,3 +1,52 @@
+pub mod access_log;
+mod aws;
+pub mod backup;
+pub mod broker;
+#[cfg(feature = "bench")]
+pub mod bench;
+pub mod cache;
+pub mod cdc;
+pub mod chaos;
+#[cfg(feature = "client")]
+pub mod client;
+pub mod client_ip;
+pub mod clock;
+pub mod config;
+pub mod database;
+pub mod diagnostics;
+pub mod email_domains;
+pub mod encryption;
+pub mod env_schema;
+pub mod events;
+pub mod export;
+pub mod filter;
+pub mod fuzzy_search;
 pub mod handlers;
+pub mod ids;
+pub mod jobs;
+pub mod load_shed;
+pub mod maintenance;
+pub mod metrics;
+pub mod migrations;
 pub mod models;
+pub mod outbox;
+pub mod preflight;
+#[cfg(feature = "pprof")]
+pub mod profiling;
+pub mod reload;
+pub mod replication;
 pub mod routes;
+pub mod scheduler;
+pub mod secrets;
+pub mod shared_state;
+pub mod shutdown;
+pub mod signing;
+pub mod socket_activation;
+pub mod state;
+pub mod statsd;
+pub mod storage;
+pub mod tenant;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod trace_context;
+pub mod webhooks;
@@ -0,0 +1,139 @@
+//! Límites de concurrencia y descarte de carga por grupo de rutas.
+//!
+//! SQLite serializa las escrituras: si llegan más peticiones de las que la base puede atender,
+//! todas esperan y la latencia crece hasta agotar los timeouts de los clientes. Cada grupo de
+//! rutas tiene un máximo de peticiones en curso (`CONCURRENCY_LIMIT_API`,
+//! `CONCURRENCY_LIMIT_ADMIN`; `0` desactiva el límite). Al alcanzarlo, las peticiones nuevas
+//! no esperan turno: responden `503` con `Retry-After` (`LOAD_SHED_RETRY_AFTER_SECS`) y se
+//! contabilizan en `http_requests_shed_total`, de modo que el servicio se degrada rechazando
+//! el exceso en lugar de atender tarde a todos.
+//!
+//! El límite se comparte entre todas las rutas del grupo, así que conviene mantener fuera las
+//! sondas de salud y las métricas para que sigan respondiendo con el servicio saturado.
+
+use std::time::Duration;
+
+use axum::{
+    error_handling::HandleErrorLayer,
+    response::{IntoResponse, Response},
+    BoxError, Router,
+};
+use tower::{limit::GlobalConcurrencyLimitLayer, load_shed::error::Overloaded, ServiceBuilder};
+
+use crate::config::env_or;
+use crate::handlers::error::AppError;
+use crate::metrics::Metrics;
+
+/// Grupo de rutas con su propio límite de concurrencia.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RouteGroup {
+    /// La API versionada.
+    Api,
+    /// Las operaciones de administración (`/admin/backup`, `/admin/maintenance`,
+    /// `/admin/reload-config`).
+    Admin,
+}
+
+impl RouteGroup {
+    /// Etiqueta del grupo en las métricas.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Api => "api",
+            Self::Admin => "admin",
+        }
+    }
+}
+
+/// Límites de concurrencia de cada grupo de rutas.
+#[derive(Debug, Clone)]
+pub struct ConcurrencyLimits {
+    /// Peticiones simultáneas de la API (`CONCURRENCY_LIMIT_API`).
+    pub api: Option<usize>,
+    /// Peticiones simultáneas de administración (`CONCURRENCY_LIMIT_ADMIN`).
+    pub admin: Option<usize>,
+    /// Espera sugerida a los clientes rechazados (`LOAD_SHED_RETRY_AFTER_SECS`).
+    pub retry_after: Duration,
+}
+
+impl Default for ConcurrencyLimits {
+    fn default() -> Self {
+        Self {
+            api: Some(256),
+            admin: Some(4),
+            retry_after: Duration::from_secs(1),
+        }
+    }
+}
+
+impl ConcurrencyLimits {
+    /// Lee los límites de las variables de entorno; `0` desactiva el límite del grupo.
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+
+        Self {
+            api: limit_from_env("CONCURRENCY_LIMIT_API", defaults.api),
+            admin: limit_from_env("CONCURRENCY_LIMIT_ADMIN", defaults.admin),
+            retry_after: Duration::from_secs(env_or(
+                "LOAD_SHED_RETRY_AFTER_SECS",
+                defaults.retry_after.as_secs(),
+            )),
+        }
+    }
+
+    /// Límite configurado para `group`.
+    pub fn for_group(&self, group: RouteGroup) -> Option<usize> {
+        match group {
+            RouteGroup::Api => self.api,
+            RouteGroup::Admin => self.admin,
+        }
+    }
+
+    /// Aplica a `router` el límite de `group`, descartando las peticiones que lo superen.
+    pub fn apply<S>(&self, group: RouteGroup, router: Router<S>, metrics: &Metrics) -> Router<S>
+    where
+        S: Clone + Send + Sync + 'static,
+    {
+        let Some(limit) = self.for_group(group) else {
+            return router;
+        };
+
+        let metrics = metrics.clone();
+        let retry_after = self.retry_after;
+        router.route_layer(
+            ServiceBuilder::new()
+                .layer(HandleErrorLayer::new(move |error: BoxError| {
+                    let metrics = metrics.clone();
+                    async move { shed_response(error, group, retry_after, &metrics) }
+                }))
+                .load_shed()
+                .layer(GlobalConcurrencyLimitLayer::new(limit)),
+        )
+    }
+}
+
+/// Traduce el rechazo de la capa de descarte en la respuesta `503`.
+fn shed_response(
+    error: BoxError,
+    group: RouteGroup,
+    retry_after: Duration,
+    metrics: &Metrics,
+) -> Response {
+    if !error.is::<Overloaded>() {
+        return AppError::internal(anyhow::anyhow!(error)).into_response();
+    }
+
+    metrics.record_shed(group.as_str());
+    AppError::overloaded(retry_after).into_response()
+}
+
+/// Lee un límite en el que `0` significa "sin límite".
+fn limit_from_env(name: &str, default: Option<usize>) -> Option<usize> {
+    match std::env::var(name)
+        .ok()
+        .and_then(|value| value.parse::<usize>().ok())
+    {
+        Some(0) => None,
+        Some(limit) => Some(limit),
+        None => default,
+    }
+}
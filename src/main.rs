@@ -2,93 +2,756 @@
 //!
 //! Aquí se realiza la configuración inicial del entorno, la conexión a la base de datos,
 //! la ejecución de migraciones y el arranque del servidor HTTP basado en Axum.
+//!
+//! Con `migrate status`, `migrate undo` o `migrate to <versión>` como argumentos, el binario
+//! solo ejecuta ese subcomando sobre la base principal y termina. Con `--migrate-only` aplica
+//! las migraciones pendientes y termina, para que un despliegue las ejecute en un paso previo
+//! y arranque las réplicas con `RUN_MIGRATIONS=false`. Con `backup` genera una copia de la
+//! base principal, igual que `POST /admin/backup`, e imprime dónde quedó; con
+//! `restore <archivo>` sustituye la base principal por esa copia y la migra. Con
+//! `replica generations` o `replica restore [<fecha>]` consulta la réplica continua del WAL o
+//! restaura la base desde ella. Con `rekey-emails` vuelve a cifrar todos los correos con la
+//! clave actual de `EMAIL_ENCRYPTION_KEY`. Con la feature `bench`, `bench <url> [opciones]`
+//! genera carga CRUD contra otra instancia y resume su latencia y su tasa de errores.
 
 use anyhow::{Context, Result};
-use axum::Router;
+use axum::{extract::Request, middleware, Router, ServiceExt};
 use dotenvy::dotenv;
 use sqlx::sqlite::SqlitePool;
-use std::{env, net::SocketAddr};
-use tokio::net::TcpListener;
-use tower_http::services::ServeDir;
-use tracing::{error, info};
-use tracing_subscriber::EnvFilter;
-
-mod handlers;
-mod models;
-mod routes;
-
-/// Arranca el runtime principal, inicializando trazas, conexión a la base de datos
-/// y ejecutando las migraciones antes de levantar el servidor HTTP.
-#[tokio::main]
-async fn main() -> Result<()> {
-    dotenv().ok();
-    init_tracing();
+use std::{
+    env,
+    future::{Future, IntoFuture},
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use tokio::{net::TcpListener, sync::watch};
+use tower::ServiceBuilder;
+use tower_http::request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer};
+use tracing::{info, warn};
+use tracing_subscriber::{fmt, prelude::*, reload, EnvFilter, Registry};
+
+use rust_web_demo::{
+    access_log::{self, AccessLog, AccessLogConfig, LogFormat},
+    backup, broker, cache, cdc,
+    chaos::{self, ChaosConfig},
+    client_ip::{self, TrustedProxies},
+    config::{AppConfig, Environment}, database, diagnostics, email_domains, encryption,
+    encryption::EmailEncryption, env_schema, export::job::ExportJob, fuzzy_search,
+    handlers::error, ids, jobs,
+    load_shed::{ConcurrencyLimits, RouteGroup},
+    maintenance, metrics, migrations, outbox, preflight,
+    reload::{ConfigReloader, DEFAULT_LOG_FILTER},
+    replication, routes, scheduler, secrets,
+    shutdown,
+    shared_state::SharedStateBackend,
+    signing::{self, RequestSigning},
+    socket_activation::{InheritedListeners, SocketRole},
+    state::AppState, statsd, storage, trace_context, webhooks,
+};
+
+/// Cada cuánto se informa, durante el apagado, de las peticiones que siguen en curso.
+const SHUTDOWN_PROGRESS_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Inicializa las trazas y resuelve los secretos antes de arrancar el runtime principal: la
+/// resolución escribe en el entorno, lo que solo es seguro sin otros hilos en marcha.
+fn main() -> Result<()> {
+    let environment = load_dotenv()?;
+    let log_filter = init_tracing();
+    info!(%environment, "Perfil de ejecución");
+
+    let secrets_config = secrets::SecretsConfig::from_env()?;
+    let secrets = secrets::load(&secrets_config).context("No se pudieron resolver los secretos")?;
+    // Con los secretos ya en el entorno, cualquier valor mal escrito detiene el arranque en
+    // lugar de sustituirse en silencio por el valor por defecto.
+    env_schema::check_env()?;
+
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()
+        .context("No se pudo crear el runtime")?
+        .block_on(run(secrets, secrets_config.refresh_interval, log_filter))
+}
+
+/// Conecta con la base de datos y ejecuta las migraciones antes de levantar el servidor HTTP,
+/// salvo que los argumentos pidan un subcomando.
+async fn run(
+    secrets: secrets::Secrets,
+    secrets_refresh: Option<Duration>,
+    log_filter: LogFilterHandle,
+) -> Result<()> {
+    let config = AppConfig::from_env();
+    let run_mode = parse_args(env::args().skip(1).collect())?;
+
+    // La carga se genera contra otra instancia: no necesita la base local.
+    #[cfg(feature = "bench")]
+    if let RunMode::Bench(bench_config) = &run_mode {
+        let report = rust_web_demo::bench::run(bench_config).await?;
+        println!("{report}");
+        return Ok(());
+    }
 
-    let database_url =
-        env::var("DATABASE_URL").unwrap_or_else(|_| "sqlite://db.sqlite".to_string());
+    let database_pool = connect_with_retry(&config).await.with_context(|| {
+        format!(
+            "No se pudo conectar a la base de datos en {}",
+            config.database_url
+        )
+    })?;
 
-    let database_pool = SqlitePool::connect(&database_url)
+    match run_mode {
+        RunMode::Serve => {}
+        RunMode::MigrateOnly => return migrate_only(&database_pool, config).await,
+        RunMode::Migrate(command) => {
+            return migrations::run_command(&database_pool, command).await;
+        }
+        RunMode::Backup => return backup_once(&database_pool, &config).await,
+        RunMode::Restore(snapshot) => return restore(&database_pool, &config, &snapshot).await,
+        RunMode::Replica(command) => return replica(&database_pool, &config, command).await,
+        RunMode::RekeyEmails => return rekey_emails(&database_pool, config).await,
+        #[cfg(feature = "bench")]
+        RunMode::Bench(_) => unreachable!("bench se atiende antes de conectar con la base"),
+    }
+
+    if config.run_migrations {
+        migrations::MIGRATOR
+            .run(&database_pool)
+            .await
+            .context("Fallo al ejecutar migraciones")?;
+    } else {
+        // Otra réplica o el paso de migración del despliegue se encarga; solo se comprueba
+        // que el esquema esté al día para no atender peticiones contra uno antiguo.
+        migrations::ensure_up_to_date(&database_pool).await?;
+    }
+
+    let report = preflight::run(&config, &database_pool, Path::new(PUBLIC_DIR)).await;
+    report.log();
+    report.into_result()?;
+
+    if let Some(path) = &config.blocked_email_domains_file {
+        email_domains::import_file(&database_pool, path)
+            .await
+            .context("No se pudieron importar los dominios bloqueados")?;
+    }
+
+    // Los usuarios creados antes del índice de búsqueda aproximada se indexan una sola vez.
+    let indexed = fuzzy_search::index_missing(&database_pool)
         .await
-        .with_context(|| format!("No se pudo conectar a la base de datos en {}", database_url))?;
+        .context("No se pudo completar el índice de búsqueda aproximada")?;
+    if indexed > 0 {
+        info!(indexed, "Usuarios añadidos al índice de búsqueda aproximada");
+    }
 
-    sqlx::migrate!("./migrations")
-        .run(&database_pool)
+    let email_encryption = EmailEncryption::from_config(&config)?;
+    if let Some(key_id) = email_encryption.current_key_id() {
+        info!(key_id, "Cifrado de correos activo");
+    }
+
+    let listener_address = build_socket_addr(&config.host, config.port)?;
+    let admin_address = config
+        .admin_port
+        .map(|port| build_socket_addr(&config.admin_host, port))
+        .transpose()?;
+    let application_state = AppState::new(database_pool.clone(), config);
+    let cache = cache::Cache::from_config(
+        cache::CacheConfig::from_env(),
+        application_state.metrics.clone(),
+    )
+    .await?;
+    let storage_config = storage::StorageConfig::from_env()?;
+    info!(backend = ?storage_config.backend, "Almacenamiento configurado");
+    let application_state = application_state
+        .with_cache(cache.clone())
+        .with_storage(storage_config.build()?)
+        .with_email_encryption(email_encryption);
+    // Con varias réplicas, los nonces de firma y el modo de mantenimiento viven en Redis.
+    let shared_state = SharedStateBackend::from_env().connect().await?;
+    let application_state = match &shared_state {
+        Some(store) => {
+            let maintenance = application_state
+                .maintenance
+                .clone()
+                .with_shared_store(store.clone());
+            if application_state.config.maintenance_mode {
+                maintenance
+                    .publish()
+                    .await
+                    .context("No se pudo publicar el modo de mantenimiento")?;
+            }
+            diagnostics::spawn("maintenance-sync", maintenance::run_sync(maintenance.clone()));
+            application_state.with_maintenance(maintenance)
+        }
+        None => application_state,
+    };
+    application_state
+        .tenant_databases
+        .open_all(&database_pool)
         .await
-        .context("Fallo al ejecutar migraciones")?;
+        .context("No se pudieron abrir las bases de los inquilinos")?;
+    application_state
+        .events
+        .register(cache::CacheInvalidator::new(cache));
 
-    let application_router = Router::new()
-        .merge(routes::user_routes())
+    if let Some(statsd_config) = statsd::StatsdConfig::from_env() {
+        let (sink, exporter) = statsd::connect(&statsd_config).await?;
+        application_state.metrics.export_to(sink);
+        diagnostics::spawn(
+            "statsd-exporter",
+            exporter.run(application_state.metrics.clone()),
+        );
+        info!(?statsd_config, "Métricas enviadas también a StatsD");
+    }
+    diagnostics::spawn(
+        "pool-probe",
+        database::probe_pool(database_pool.clone(), application_state.metrics.clone()),
+    );
+    diagnostics::spawn("outbox-relay", outbox::run_relay(application_state.clone()));
+    diagnostics::spawn(
+        "webhook-dispatcher",
+        webhooks::run(
+            application_state.clone(),
+            webhooks::WebhookDispatcher::new(database_pool.clone()),
+        ),
+    );
+    if let Some(broker_config) = broker::BrokerConfig::from_env() {
+        diagnostics::spawn(
+            "broker-relay",
+            broker::run(application_state.clone(), broker_config),
+        );
+    }
+    if let Some(cdc_config) = cdc::CdcConfig::from_env() {
+        diagnostics::spawn("cdc", cdc::run(application_state.clone(), cdc_config));
+    }
+    if let Some(replication_config) = replication::ReplicationConfig::from_env() {
+        diagnostics::spawn(
+            "wal-replication",
+            replication::run(
+                application_state.config.clone(),
+                application_state.storage.clone(),
+                replication_config,
+            ),
+        );
+    }
+    diagnostics::spawn(
+        "job-worker",
+        jobs::Worker::new(
+            application_state.clone(),
+            jobs::JobRegistry::new().register::<ExportJob>(),
+        )
+        .run(),
+    );
+    diagnostics::spawn(
+        "scheduler",
+        scheduler::default_scheduler(application_state.clone())?.run(),
+    );
+    if let Some(interval) = secrets_refresh {
+        for name in secrets::MANAGED_SECRETS {
+            // Estos secretos solo se leen al arrancar.
+            secrets.on_rotate(name, |name, _| {
+                warn!(secret = name, "El secreto cambió; se aplicará al reiniciar el servicio")
+            });
+        }
+        diagnostics::spawn("secrets-refresh", secrets::run(secrets, interval));
+    }
+
+    // La API y la administración tienen su propio límite de peticiones en curso; salud,
+    // métricas y estáticos quedan fuera para seguir respondiendo con el servicio saturado.
+    let concurrency_limits = ConcurrencyLimits::from_env();
+    let access_log = AccessLog::new(AccessLogConfig::from_env());
+    let config_reloader =
+        ConfigReloader::from_env(access_log.clone()).with_log_filter(move |filter| {
+            log_filter
+                .reload(EnvFilter::try_new(filter)?)
+                .context("No se pudo aplicar el filtro de trazas")
+        });
+    let admin_routes = Router::new()
+        .merge(routes::backup_routes())
+        .merge(routes::maintenance_routes())
+        .merge(routes::email_domain_routes())
+        .merge(routes::reload_routes(config_reloader));
+    // Con clientes de firma configurados, la administración solo acepta peticiones firmadas.
+    let request_signing = RequestSigning::from_env().map(|request_signing| match &shared_state {
+        Some(store) => request_signing.with_nonce_store(store.clone()),
+        None => request_signing,
+    });
+    let admin_routes = match request_signing {
+        Some(request_signing) => {
+            info!(?request_signing, "Las rutas de administración exigen peticiones firmadas");
+            admin_routes.route_layer(middleware::from_fn_with_state(
+                request_signing,
+                signing::require_signature,
+            ))
+        }
+        None => admin_routes,
+    };
+    let public_routes = Router::new()
+        .merge(concurrency_limits.apply(
+            RouteGroup::Api,
+            routes::api_routes(&application_state),
+            &application_state.metrics,
+        ))
         .merge(routes::health_routes())
         .merge(routes::root_route())
-        .nest_service("/public", ServeDir::new("public"))
-        .with_state(database_pool.clone());
+        .merge(routes::public_routes(
+            PUBLIC_DIR,
+            application_state.config.public_spa_fallback,
+        ));
+    let operations_routes = Router::new()
+        .merge(concurrency_limits.apply(
+            RouteGroup::Admin,
+            admin_routes,
+            &application_state.metrics,
+        ))
+        .merge(routes::metrics_routes());
+
+    // El perfilado solo se monta si hay un token con el que protegerlo.
+    #[cfg(feature = "pprof")]
+    let operations_routes = match rust_web_demo::profiling::ProfilingConfig::from_env() {
+        Some(profiling) => {
+            info!("Perfilado de CPU disponible en /debug/pprof/profile");
+            operations_routes.merge(routes::debug_routes(profiling))
+        }
+        None => operations_routes,
+    };
+
+    // Los sockets que pase systemd (o un envoltorio de reinicios) sustituyen a los puertos
+    // configurados, para que las conexiones esperen en su cola durante un reinicio.
+    let mut inherited = InheritedListeners::from_env()?;
+    let public_listener = match inherited.take(SocketRole::Public) {
+        Some(socket) => adopt_listener(socket)?,
+        None => bind(listener_address).await?,
+    };
+    let admin_listener = match (inherited.take(SocketRole::Admin), admin_address) {
+        (Some(socket), _) => Some(adopt_listener(socket)?),
+        (None, Some(admin_address)) => Some(bind(admin_address).await?),
+        (None, None) => None,
+    };
+    for name in inherited.unused() {
+        warn!(?name, "Socket heredado sin uso; se cierra");
+    }
+
+    // Con `ADMIN_PORT`, la administración, las métricas y el perfilado se sirven solo en su
+    // propio puerto, normalmente limitado a la red interna; la salud, en los dos.
+    let listeners = match admin_listener {
+        Some(admin_listener) => vec![
+            ("server", public_listener, public_routes),
+            (
+                "admin-server",
+                admin_listener,
+                operations_routes.merge(routes::health_routes()),
+            ),
+        ],
+        None => vec![(
+            "server",
+            public_listener,
+            public_routes.merge(operations_routes),
+        )],
+    };
+
+    // Modo caos (solo desarrollo).
+    let chaos = match ChaosConfig::from_env() {
+        Some(_) if application_state.config.environment == Environment::Production => {
+            warn!("El modo caos no se puede activar en producción; se ignora CHAOS_ENABLED");
+            None
+        }
+        Some(chaos) => {
+            warn!(?chaos, "Modo caos activo: se inyectarán fallos en las peticiones");
+            Some(Arc::new(chaos))
+        }
+        None => None,
+    };
+    let stack = ServerStack {
+        state: application_state,
+        chaos,
+        access_log,
+        trusted_proxies: Arc::new(TrustedProxies::from_env()),
+    };
+
+    // Si un servidor termina, por la señal de apagado o por un error, se detienen los demás.
+    let shutdown = Arc::new(watch::channel(false).0);
+    // Los manejadores se instalan antes de aceptar conexiones, no cuando arranque la tarea.
+    let signal = shutdown::shutdown_signal();
+    let mut servers = Vec::new();
+    for (name, tcp_listener, routes) in listeners {
+        let address = tcp_listener
+            .local_addr()
+            .context("No se pudo leer la dirección de escucha")?;
+        info!(listener = name, "Servidor corriendo en http://{address}");
+
+        let server = stack.serve(tcp_listener, routes, shutdown.subscribe());
+        let shutdown = shutdown.clone();
+        // Cada servidor corre en su propia tarea para que aparezca con nombre en tokio-console.
+        servers.push(diagnostics::spawn(name, async move {
+            let result = server.await;
+            shutdown.send_replace(true);
+            result
+        }));
+    }
+    diagnostics::spawn("shutdown-signal", {
+        let shutdown = shutdown.clone();
+        async move {
+            signal.await;
+            shutdown.send_replace(true);
+        }
+    });
+    // Mientras el apagado espera a las peticiones en curso, el log explica por qué tarda.
+    diagnostics::spawn("shutdown-progress", {
+        let mut stopped = shutdown.subscribe();
+        let metrics = stack.state.metrics.clone();
+        async move {
+            if stopped.wait_for(|stopped| *stopped).await.is_ok() {
+                metrics::report_shutdown_progress(metrics, SHUTDOWN_PROGRESS_INTERVAL).await;
+            }
+        }
+    });
+
+    for server in servers {
+        server
+            .await
+            .context("La tarea del servidor terminó de forma inesperada")?
+            .context("Error al ejecutar el servidor")?;
+    }
+
+    Ok(())
+}
+
+/// Estado y capas comunes a todos los puertos en los que escucha el servidor.
+struct ServerStack {
+    state: AppState,
+    chaos: Option<Arc<ChaosConfig>>,
+    access_log: AccessLog,
+    trusted_proxies: Arc<TrustedProxies>,
+}
+
+impl ServerStack {
+    /// Sirve `routes` en `tcp_listener` con las capas comunes hasta que `shutdown` pase a
+    /// `true`.
+    fn serve(
+        &self,
+        tcp_listener: TcpListener,
+        routes: Router<AppState>,
+        mut shutdown: watch::Receiver<bool>,
+    ) -> impl Future<Output = std::io::Result<()>> + Send + 'static {
+        let routes = routes.fallback(error::not_found_fallback);
 
-    let listener_address = build_socket_addr()?;
-    let tcp_listener = TcpListener::bind(listener_address)
+        // El modo caos va por dentro del resto de capas para que las métricas y el log de
+        // accesos reflejen los fallos inyectados.
+        let routes = match &self.chaos {
+            Some(chaos) => routes.layer(middleware::from_fn_with_state(
+                chaos.clone(),
+                chaos::inject_faults,
+            )),
+            None => routes,
+        };
+
+        let router = routes
+            .layer(middleware::from_fn_with_state(
+                self.state.clone(),
+                database::retry_when_busy,
+            ))
+            .layer(middleware::from_fn_with_state(
+                self.state.clone(),
+                metrics::track_requests,
+            ))
+            .layer(middleware::from_fn_with_state(
+                self.state.clone(),
+                ids::scope_public_ids,
+            ))
+            .with_state(self.state.clone());
+
+        // Cada petición recibe un `x-request-id` (el del cliente o uno nuevo) que se devuelve
+        // en la respuesta y aparece en el log de accesos junto a la IP del cliente. La versión
+        // pedida por cabecera se resuelve antes del enrutado, y los `405` se reescriben después
+        // de que el router haya añadido la cabecera `Allow`.
+        let service = ServiceBuilder::new()
+            .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+            .layer(PropagateRequestIdLayer::x_request_id())
+            .layer(middleware::from_fn_with_state(
+                self.trusted_proxies.clone(),
+                client_ip::resolve_client_ip,
+            ))
+            .layer(middleware::from_fn(trace_context::propagate_trace_context))
+            .layer(middleware::from_fn_with_state(
+                self.access_log.clone(),
+                access_log::log_requests,
+            ))
+            .layer(middleware::from_fn(error::json_method_not_allowed))
+            .layer(middleware::map_request(routes::select_api_version))
+            .service(router);
+
+        axum::serve(
+            tcp_listener,
+            ServiceExt::<Request>::into_make_service_with_connect_info::<SocketAddr>(service),
+        )
+        .with_graceful_shutdown(async move {
+            let _ = shutdown.wait_for(|stopped| *stopped).await;
+        })
+        .into_future()
+    }
+}
+
+/// Carga `.env.<perfil>` y después `.env` sin sobrescribir lo ya definido, así que el entorno
+/// del proceso manda sobre el archivo del perfil y este sobre el común. El perfil sale de
+/// `APP_ENV`, en el entorno o en `.env`.
+fn load_dotenv() -> Result<Environment> {
+    let profile = match env::var("APP_ENV") {
+        Ok(profile) => Some(profile),
+        Err(_) => dotenvy::from_filename_iter(".env")
+            .ok()
+            .and_then(|variables| variables.flatten().find(|(name, _)| name == "APP_ENV"))
+            .map(|(_, profile)| profile),
+    };
+    let environment = match profile.filter(|profile| !profile.trim().is_empty()) {
+        Some(profile) => profile.parse().map_err(anyhow::Error::msg)?,
+        None => Environment::default(),
+    };
+
+    dotenvy::from_filename(format!(".env.{environment}")).ok();
+    dotenv().ok();
+    Ok(environment)
+}
+
+/// Configura la suscripción de trazas leyendo el filtro desde variables de entorno, con un
+/// formato compacto apto para consola o, con `LOG_FORMAT=json`, una línea JSON por evento.
+///
+/// Con la feature `tokio-console` se añade además la capa de `console-subscriber`, sin el
+/// filtro de `RUST_LOG`, que necesita los eventos internos de Tokio.
+///
+/// Devuelve el manejador con el que `POST /admin/reload-config` sustituye el filtro.
+fn init_tracing() -> LogFilterHandle {
+    let env_filter = EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(DEFAULT_LOG_FILTER));
+    let (env_filter, handle) = reload::Layer::new(env_filter);
+    let fmt_layer = match LogFormat::from_env() {
+        LogFormat::Human => fmt::layer().with_target(false).compact().boxed(),
+        LogFormat::Json => fmt::layer()
+            .with_target(false)
+            .json()
+            .flatten_event(true)
+            .boxed(),
+    };
+
+    let registry = tracing_subscriber::registry().with(fmt_layer.with_filter(env_filter));
+    #[cfg(feature = "tokio-console")]
+    let registry = registry.with(diagnostics::console_layer());
+    registry.init();
+    handle
+}
+
+/// Manejador para cambiar en caliente el filtro de `RUST_LOG`.
+type LogFilterHandle = reload::Handle<EnvFilter, Registry>;
+
+/// Directorio de archivos estáticos servido bajo `/public`.
+const PUBLIC_DIR: &str = "public";
+
+/// Qué hace el binario según sus argumentos.
+enum RunMode {
+    /// Sin argumentos: levanta el servidor.
+    Serve,
+    /// `--migrate-only`: aplica las migraciones pendientes y termina.
+    MigrateOnly,
+    /// `migrate <subcomando>`: lo ejecuta y termina.
+    Migrate(migrations::MigrateCommand),
+    /// `backup`: genera una copia de la base principal y termina.
+    Backup,
+    /// `restore <archivo>`: sustituye la base principal por esa copia y termina.
+    Restore(PathBuf),
+    /// `replica <subcomando>`: lo ejecuta y termina.
+    Replica(replication::ReplicaCommand),
+    /// `rekey-emails`: vuelve a cifrar los correos con la clave actual y termina.
+    RekeyEmails,
+    /// `bench <url> [opciones]`: genera carga contra esa instancia, imprime el resumen y
+    /// termina.
+    #[cfg(feature = "bench")]
+    Bench(rust_web_demo::bench::BenchConfig),
+}
+
+/// Interpreta los argumentos de la línea de comandos.
+fn parse_args(args: Vec<String>) -> Result<RunMode> {
+    match args.split_first() {
+        None => Ok(RunMode::Serve),
+        Some((flag, [])) if flag == "--migrate-only" => Ok(RunMode::MigrateOnly),
+        Some((command, [])) if command == "backup" => Ok(RunMode::Backup),
+        Some((command, [])) if command == "rekey-emails" => Ok(RunMode::RekeyEmails),
+        Some((command, [snapshot])) if command == "restore" => {
+            Ok(RunMode::Restore(PathBuf::from(snapshot)))
+        }
+        Some((command, rest)) if command == "replica" => {
+            replication::ReplicaCommand::parse(rest).map(RunMode::Replica)
+        }
+        Some((command, rest)) if command == "migrate" => {
+            migrations::MigrateCommand::parse(rest).map(RunMode::Migrate)
+        }
+        #[cfg(feature = "bench")]
+        Some((command, rest)) if command == "bench" => {
+            rust_web_demo::bench::BenchConfig::parse(rest).map(RunMode::Bench)
+        }
+        #[cfg(not(feature = "bench"))]
+        Some((command, _)) if command == "bench" => {
+            anyhow::bail!("El subcomando bench requiere compilar con --features bench")
+        }
+        Some((argument, _)) => anyhow::bail!("Argumento desconocido: {argument}"),
+    }
+}
+
+/// Aplica las migraciones de la base principal y, con `TENANT_DATABASE_DIR`, las de todas las
+/// bases de inquilinos registradas.
+async fn migrate_only(database_pool: &SqlitePool, config: AppConfig) -> Result<()> {
+    migrations::MIGRATOR
+        .run(database_pool)
+        .await
+        .context("Fallo al ejecutar migraciones")?;
+    AppState::new(database_pool.clone(), config)
+        .tenant_databases
+        .open_all(database_pool)
         .await
-        .with_context(|| format!("No se pudo abrir el puerto {}", listener_address))?;
+        .context("No se pudieron migrar las bases de los inquilinos")?;
 
-    info!("Servidor corriendo en http://{}", listener_address);
+    info!("Migraciones aplicadas");
+    Ok(())
+}
 
-    axum::serve(tcp_listener, application_router)
-        .with_graceful_shutdown(shutdown_signal())
+/// Vuelve a cifrar con la clave actual los correos de la base principal y, con
+/// `TENANT_DATABASE_DIR`, los de todas las bases de inquilinos.
+async fn rekey_emails(database_pool: &SqlitePool, config: AppConfig) -> Result<()> {
+    let encryption = EmailEncryption::from_config(&config)?;
+    migrations::ensure_up_to_date(database_pool).await?;
+    let state = AppState::new(database_pool.clone(), config);
+    state
+        .tenant_databases
+        .open_all(database_pool)
         .await
-        .context("Error al ejecutar el servidor")?;
+        .context("No se pudieron abrir las bases de los inquilinos")?;
+
+    let mut total = encryption::RekeyReport::default();
+    for pool in state.database_pools().await {
+        let report = encryption::rekey(&pool, &encryption).await?;
+        total.users += report.users;
+        total.rewritten += report.rewritten;
+    }
 
+    println!(
+        "Correos revisados: {}; cifrados de nuevo con la clave {}: {}",
+        total.users,
+        encryption.current_key_id().unwrap_or_default(),
+        total.rewritten
+    );
     Ok(())
 }
 
-/// Configura la suscripción de trazas leyendo el filtro desde variables de entorno
-/// y utilizando un formato compacto apto para consola.
-fn init_tracing() {
-    let env_filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+/// Genera una copia de la base principal en `BACKUP_DIR` o en el almacenamiento configurado.
+async fn backup_once(database_pool: &SqlitePool, config: &AppConfig) -> Result<()> {
+    let storage = storage::StorageConfig::from_env()?.build()?;
+    let artifact = backup::create(database_pool, config, storage.as_ref())
+        .await
+        .context("No se pudo generar la copia de seguridad")?;
+
+    println!("Copia guardada en {} ({} bytes)", artifact.location, artifact.size_bytes);
+    Ok(())
+}
+
+/// Restaura la base principal desde `snapshot`. El servidor debe estar parado o en modo de
+/// mantenimiento: las escrituras que lleguen mientras tanto se perderían.
+async fn restore(database_pool: &SqlitePool, config: &AppConfig, snapshot: &Path) -> Result<()> {
+    let report = backup::restore(database_pool, config, snapshot).await?;
+
+    match report.snapshot_version {
+        Some(version) => println!("Copia restaurada (versión {version})"),
+        None => println!("Copia restaurada (sin migraciones)"),
+    }
+    for version in &report.applied_migrations {
+        println!("Migración {version} aplicada");
+    }
+    println!("Base anterior guardada en {}", report.safety_copy.display());
+    Ok(())
+}
 
-    tracing_subscriber::fmt()
-        .with_env_filter(env_filter)
-        .with_target(false)
-        .compact()
-        .init();
+/// Lista las generaciones de la réplica del WAL o restaura la base principal desde ella.
+async fn replica(
+    database_pool: &SqlitePool,
+    config: &AppConfig,
+    command: replication::ReplicaCommand,
+) -> Result<()> {
+    let storage = storage::StorageConfig::from_env()?.build()?;
+    match command {
+        replication::ReplicaCommand::Generations => {
+            for generation in replication::generations(storage.as_ref()).await? {
+                println!("{}  {}", generation.id, generation.started_at.to_rfc3339());
+            }
+            Ok(())
+        }
+        replication::ReplicaCommand::Restore(target) => {
+            let path = config.backup_dir.join(format!(
+                "replica-{}.db",
+                chrono::Utc::now().format("%Y%m%dT%H%M%S%.3fZ")
+            ));
+            let point = replication::restore_to(storage.as_ref(), target, &path).await?;
+            println!(
+                "Réplica reconstruida hasta {} (generación {}, {} segmentos)",
+                point.restored_at.to_rfc3339(),
+                point.generation,
+                point.segments
+            );
+            restore(database_pool, config, &path).await
+        }
+    }
 }
 
-/// Construye la dirección en la que escuchará el servidor a partir de las variables
-/// de entorno `HOST` y `PORT`, aplicando valores por defecto cuando corresponda.
-fn build_socket_addr() -> Result<SocketAddr> {
-    let host = env::var("HOST").unwrap_or_else(|_| "0.0.0.0".to_string());
-    let port = env::var("PORT")
-        .ok()
-        .and_then(|value| value.parse::<u16>().ok())
-        .unwrap_or(3000);
+/// Espera máxima entre dos intentos de conexión a la base de datos.
+const MAX_CONNECT_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Conecta con la base de datos reintentando con backoff exponencial, para arrancar aunque
+/// la base aún no esté disponible (por ejemplo, al levantar todos los contenedores a la vez).
+async fn connect_with_retry(config: &AppConfig) -> Result<SqlitePool> {
+    let attempts = config.database_connect_attempts;
+    let connect_options = database::connect_options(config)?;
+    let mut backoff = config.database_connect_backoff;
+    let mut attempt = 1;
 
+    loop {
+        match database::pool_options(config)
+            .connect_with(connect_options.clone())
+            .await
+        {
+            Ok(pool) => {
+                if attempt > 1 {
+                    info!(attempt, "Conexión con la base de datos establecida");
+                }
+                return Ok(pool);
+            }
+            Err(error) if attempt < attempts => {
+                warn!(
+                    attempt,
+                    attempts,
+                    retry_in = ?backoff,
+                    %error,
+                    "La base de datos no está disponible, se reintentará"
+                );
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(MAX_CONNECT_BACKOFF);
+                attempt += 1;
+            }
+            Err(error) => return Err(error.into()),
+        }
+    }
+}
+
+/// Construye una dirección de escucha a partir de `HOST` y `PORT` (o de `ADMIN_HOST` y
+/// `ADMIN_PORT`).
+fn build_socket_addr(host: &str, port: u16) -> Result<SocketAddr> {
     format!("{host}:{port}")
         .parse::<SocketAddr>()
-        .with_context(|| format!("HOST o PORT inválidos: {host}:{port}"))
+        .with_context(|| format!("Dirección de escucha inválida: {host}:{port}"))
 }
 
-/// Espera la señal de `Ctrl+C` para realizar un apagado ordenado del servidor.
-async fn shutdown_signal() {
-    if let Err(error) = tokio::signal::ctrl_c().await {
-        error!(?error, "Error al esperar la señal Ctrl+C");
-    }
+/// Abre el puerto de escucha en `address`.
+async fn bind(address: SocketAddr) -> Result<TcpListener> {
+    TcpListener::bind(address)
+        .await
+        .with_context(|| format!("No se pudo abrir el puerto {address}"))
+}
 
-    info!("Señal de apagado recibida, cerrando servidor…");
+/// Atiende en un socket heredado, ya en escucha.
+fn adopt_listener(socket: std::net::TcpListener) -> Result<TcpListener> {
+    TcpListener::from_std(socket).context("No se pudo usar el socket heredado")
 }
\ No newline at end of file
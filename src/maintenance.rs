@@ -0,0 +1,195 @@
+//! Modo de mantenimiento.
+//!
+//! Mientras está activo, las rutas de la API que modifican datos (cualquier método distinto de
+//! `GET`, `HEAD`, `OPTIONS` o `TRACE`) responden `503` con el motivo del mantenimiento y las
+//! lecturas siguen funcionando. Sirve para aplicar migraciones o sacar copias de seguridad sin
+//! detener el servidor. Se activa al arrancar con `MAINTENANCE_MODE=true` o en caliente con
+//! `PUT /admin/maintenance`, que no se ve afectado por el propio modo.
+//!
+//! Solo se bloquean las peticiones HTTP: los trabajos en segundo plano, el relay del outbox y
+//! las tareas programadas siguen ejecutándose.
+//!
+//! Con un almacén compartido ([`Maintenance::with_shared_store`]), cada cambio se publica en él
+//! y [`run_sync`] lo aplica en el resto de réplicas cada [`SYNC_INTERVAL`].
+
+use std::{
+    fmt,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use anyhow::Result;
+
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::{cache::CacheStore, handlers::error::AppError};
+
+/// Cada cuánto se lee el estado publicado por otras réplicas.
+pub const SYNC_INTERVAL: Duration = Duration::from_secs(2);
+/// Clave del estado en el almacén compartido.
+const SHARED_KEY: &str = "maintenance:status";
+/// Vigencia del estado publicado. El almacén exige una, pero el estado no debe caducar:
+/// desactivar el modo también se publica.
+const SHARED_TTL: Duration = Duration::from_secs(365 * 24 * 60 * 60);
+
+/// Estado del modo de mantenimiento tal como lo devuelve `/admin/maintenance`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MaintenanceStatus {
+    pub enabled: bool,
+    /// Motivo que se muestra a los clientes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+    /// Momento en que se activó.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub since: Option<DateTime<Utc>>,
+}
+
+/// Interruptor compartido del modo de mantenimiento.
+#[derive(Clone, Default)]
+pub struct Maintenance {
+    status: Arc<RwLock<MaintenanceStatus>>,
+    shared: Option<Arc<dyn CacheStore>>,
+}
+
+impl fmt::Debug for Maintenance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Maintenance")
+            .field("status", &self.status())
+            .field("shared", &self.shared.is_some())
+            .finish()
+    }
+}
+
+impl Maintenance {
+    /// Crea el interruptor, activado o no según la configuración.
+    pub fn new(enabled: bool) -> Self {
+        let maintenance = Self::default();
+        if enabled {
+            maintenance.enable(None);
+        }
+        maintenance
+    }
+
+    /// Publica los cambios en `store` y adopta los que publiquen otras réplicas.
+    pub fn with_shared_store(mut self, store: Arc<dyn CacheStore>) -> Self {
+        self.shared = Some(store);
+        self
+    }
+
+    /// Estado actual.
+    pub fn status(&self) -> MaintenanceStatus {
+        self.status
+            .read()
+            .expect("mutex de mantenimiento envenenado")
+            .clone()
+    }
+
+    /// Indica si las escrituras están bloqueadas.
+    pub fn is_enabled(&self) -> bool {
+        self.status
+            .read()
+            .expect("mutex de mantenimiento envenenado")
+            .enabled
+    }
+
+    /// Activa el modo con el motivo indicado, conservando la fecha si ya estaba activo.
+    pub fn enable(&self, reason: Option<String>) {
+        let mut status = self
+            .status
+            .write()
+            .expect("mutex de mantenimiento envenenado");
+        let since = status
+            .since
+            .filter(|_| status.enabled)
+            .unwrap_or_else(Utc::now);
+        *status = MaintenanceStatus {
+            enabled: true,
+            reason,
+            since: Some(since),
+        };
+        info!(
+            reason = status.reason.as_deref(),
+            "Modo de mantenimiento activado"
+        );
+    }
+
+    /// Desactiva el modo.
+    pub fn disable(&self) {
+        let mut status = self
+            .status
+            .write()
+            .expect("mutex de mantenimiento envenenado");
+        if status.enabled {
+            info!("Modo de mantenimiento desactivado");
+        }
+        *status = MaintenanceStatus::default();
+    }
+
+    /// Publica el estado actual para el resto de réplicas; sin almacén compartido no hace nada.
+    pub async fn publish(&self) -> Result<()> {
+        let Some(shared) = &self.shared else {
+            return Ok(());
+        };
+        let status = serde_json::to_string(&self.status())?;
+        shared.set(SHARED_KEY, status, SHARED_TTL).await
+    }
+
+    /// Adopta el estado publicado en el almacén compartido, si hay alguno.
+    pub async fn sync(&self) -> Result<()> {
+        let Some(shared) = &self.shared else {
+            return Ok(());
+        };
+        let Some(published) = shared.get(SHARED_KEY).await? else {
+            return Ok(());
+        };
+        let published: MaintenanceStatus = serde_json::from_str(&published)?;
+
+        let mut status = self
+            .status
+            .write()
+            .expect("mutex de mantenimiento envenenado");
+        if published.enabled != status.enabled {
+            info!(
+                enabled = published.enabled,
+                reason = published.reason.as_deref(),
+                "Modo de mantenimiento cambiado desde otra réplica"
+            );
+        }
+        *status = published;
+        Ok(())
+    }
+}
+
+/// Bucle que adopta cada [`SYNC_INTERVAL`] el estado publicado por otras réplicas.
+pub async fn run_sync(maintenance: Maintenance) {
+    loop {
+        if let Err(error) = maintenance.sync().await {
+            warn!(
+                ?error,
+                "No se pudo leer el modo de mantenimiento compartido"
+            );
+        }
+        tokio::time::sleep(SYNC_INTERVAL).await;
+    }
+}
+
+/// Middleware que rechaza con `503` las peticiones que modifican datos mientras el modo de
+/// mantenimiento está activo.
+pub async fn reject_writes(
+    State(maintenance): State<Maintenance>,
+    request: Request,
+    next: Next,
+) -> Response {
+    if maintenance.is_enabled() && !request.method().is_safe() {
+        return AppError::maintenance(maintenance.status()).into_response();
+    }
+
+    next.run(request).await
+}
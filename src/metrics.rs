@@ -0,0 +1,716 @@
+//! Métricas en proceso del servicio HTTP.
+//!
+//! Registra el número de peticiones, los errores y la latencia de cada respuesta, tanto en
+//! totales acumulados como en ventanas de un minuto. Los datos alimentan el endpoint
+//! `/metrics` (formato Prometheus) y el panel HTML de `/admin/metrics`.
+//!
+//! Además, cada respuesta se suma a un histograma por ruta, método y clase de estado (`2xx`,
+//! `4xx`...). La ruta es la plantilla con la que casó el router (`/users/:id`), nunca la ruta
+//! real, para que el número de series no crezca con cada identificador; las peticiones que no
+//! casan con ninguna comparten la etiqueta `unmatched`.
+//!
+//! Con [`Metrics::export_to`], todo lo que se registra se reenvía también a StatsD
+//! ([`crate::statsd`]).
+//!
+//! También expone el estado del pool de la base principal: conexiones abiertas y ociosas en
+//! el momento de la consulta y la latencia de obtener una conexión, medida periódicamente
+//! por [`crate::database::probe_pool`].
+//!
+//! Para inspeccionar el servicio sin Prometheus, `/admin/status` resume en JSON el tiempo
+//! activo, las peticiones atendidas y en curso, el pool, la memoria del proceso y el último
+//! error.
+//!
+//! Durante un apagado ordenado, [`report_shutdown_progress`] registra cuántas peticiones
+//! siguen en curso hasta que terminan todas, y `/admin/status` indica desde cuándo se está
+//! apagando el servicio: si el apagado se alarga, se ve qué lo retiene.
+
+use std::{
+    collections::{BTreeMap, VecDeque},
+    fmt::Write,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use axum::{
+    extract::{MatchedPath, Request, State},
+    http::Method,
+    middleware::Next,
+    response::Response,
+};
+use chrono::{DateTime, Utc};
+use sqlx::SqlitePool;
+use tracing::info;
+
+use crate::statsd::StatsdSink;
+
+/// Límites superiores (en segundos) de los buckets del histograma de latencia.
+pub const LATENCY_BUCKETS: [f64; 10] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0];
+
+/// Número de minutos que se conservan para las series temporales.
+pub const WINDOW_MINUTES: usize = 60;
+
+/// Registro de métricas compartido entre todas las peticiones.
+#[derive(Debug, Clone)]
+pub struct Metrics {
+    inner: Arc<Mutex<MetricsInner>>,
+    started_at: Instant,
+}
+
+#[derive(Debug, Default)]
+struct MetricsInner {
+    totals: Histogram,
+    minutes: VecDeque<MinuteBucket>,
+    cache_hits: u64,
+    cache_misses: u64,
+    /// Peticiones descartadas por exceso de carga, por grupo de rutas.
+    shed: BTreeMap<&'static str, u64>,
+    database_pool: Option<SqlitePool>,
+    /// Latencias de obtención de conexión; los errores son esperas agotadas.
+    pool_acquire: Histogram,
+    /// Peticiones empezadas que aún no han terminado.
+    in_flight: u64,
+    /// Última respuesta `5xx`.
+    last_error_at: Option<DateTime<Utc>>,
+    /// Inicio del apagado ordenado, si ya empezó.
+    shutdown_started_at: Option<DateTime<Utc>>,
+    routes: BTreeMap<RouteKey, Histogram>,
+    statsd: Option<StatsdSink>,
+}
+
+/// Etiquetas de un histograma por ruta.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct RouteKey {
+    /// Plantilla de la ruta (`/users/:id`) o `unmatched`.
+    pub route: String,
+    /// Método HTTP, u `OTHER` si no es uno de los estándar.
+    pub method: &'static str,
+    /// Clase del estado: `1xx` a `5xx`.
+    pub status_class: &'static str,
+}
+
+impl RouteKey {
+    /// Etiquetas de una respuesta con estado `status` a `method` sobre `route`.
+    pub fn new(route: &str, method: &Method, status: u16) -> Self {
+        let method = match *method {
+            Method::GET => "GET",
+            Method::HEAD => "HEAD",
+            Method::POST => "POST",
+            Method::PUT => "PUT",
+            Method::PATCH => "PATCH",
+            Method::DELETE => "DELETE",
+            Method::OPTIONS => "OPTIONS",
+            _ => "OTHER",
+        };
+        let status_class = match status {
+            100..=199 => "1xx",
+            200..=299 => "2xx",
+            300..=399 => "3xx",
+            400..=499 => "4xx",
+            _ => "5xx",
+        };
+
+        Self {
+            route: route.to_string(),
+            method,
+            status_class,
+        }
+    }
+}
+
+/// Contadores agregados de un intervalo: peticiones, errores y distribución de latencias.
+#[derive(Debug, Clone, Default)]
+pub struct Histogram {
+    pub requests: u64,
+    pub errors: u64,
+    pub latency_sum: f64,
+    /// Conteo por bucket (no acumulado); la última posición corresponde a `+Inf`.
+    pub buckets: [u64; LATENCY_BUCKETS.len() + 1],
+}
+
+#[derive(Debug, Clone)]
+struct MinuteBucket {
+    minute: u64,
+    histogram: Histogram,
+}
+
+/// Punto de una serie temporal por minuto.
+#[derive(Debug, Clone)]
+pub struct MinutePoint {
+    /// Minuto desde la época Unix.
+    pub minute: u64,
+    pub requests: u64,
+    pub errors: u64,
+    /// Percentil 95 estimado de latencia en segundos, si hubo peticiones.
+    pub p95: Option<f64>,
+}
+
+/// Copia consistente del estado de las métricas en un instante.
+#[derive(Debug, Clone)]
+pub struct MetricsSnapshot {
+    pub uptime: Duration,
+    pub totals: Histogram,
+    /// Serie de los últimos [`WINDOW_MINUTES`] minutos, del más antiguo al más reciente.
+    pub per_minute: Vec<MinutePoint>,
+    pub cache_hits: u64,
+    pub cache_misses: u64,
+    /// Peticiones descartadas por grupo de rutas.
+    pub shed: BTreeMap<&'static str, u64>,
+    pub pool: Option<PoolStats>,
+    pub pool_acquire: Histogram,
+    /// Peticiones en curso.
+    pub in_flight: u64,
+    /// Instante de la última respuesta `5xx`, si hubo alguna.
+    pub last_error_at: Option<DateTime<Utc>>,
+    /// Inicio del apagado ordenado, si ya empezó.
+    pub shutdown_started_at: Option<DateTime<Utc>>,
+    /// Histogramas por ruta, método y clase de estado.
+    pub routes: BTreeMap<RouteKey, Histogram>,
+}
+
+/// Estado del pool de conexiones en un instante.
+#[derive(Debug, Clone, Copy)]
+pub struct PoolStats {
+    pub max_connections: u32,
+    pub open: u32,
+    pub idle: u32,
+}
+
+impl Histogram {
+    fn observe(&mut self, latency: Duration, is_error: bool) {
+        let seconds = latency.as_secs_f64();
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|upper_bound| seconds <= *upper_bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+
+        self.requests += 1;
+        if is_error {
+            self.errors += 1;
+        }
+        self.latency_sum += seconds;
+        self.buckets[bucket] += 1;
+    }
+
+    /// Estima un percentil usando el límite superior del bucket que lo contiene.
+    pub fn quantile(&self, quantile: f64) -> Option<f64> {
+        if self.requests == 0 {
+            return None;
+        }
+
+        let target = (self.requests as f64 * quantile).ceil() as u64;
+        let mut accumulated = 0;
+        for (index, count) in self.buckets.iter().enumerate() {
+            accumulated += count;
+            if accumulated >= target {
+                return Some(
+                    LATENCY_BUCKETS
+                        .get(index)
+                        .copied()
+                        .unwrap_or(LATENCY_BUCKETS[LATENCY_BUCKETS.len() - 1]),
+                );
+            }
+        }
+
+        None
+    }
+}
+
+impl Metrics {
+    /// Crea un registro vacío tomando el instante actual como inicio del servicio.
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(MetricsInner::default())),
+            started_at: Instant::now(),
+        }
+    }
+
+    /// Registra una respuesta; los códigos `5xx` se contabilizan como errores.
+    pub fn record(&self, status: u16, latency: Duration) {
+        let is_error = status >= 500;
+        let minute = current_minute();
+        let mut inner = self.inner.lock().expect("mutex de métricas envenenado");
+
+        inner.totals.observe(latency, is_error);
+        if is_error {
+            inner.last_error_at = Some(Utc::now());
+        }
+        if let Some(statsd) = &inner.statsd {
+            statsd.count("http.requests", 1, &[]);
+            if is_error {
+                statsd.count("http.request_errors", 1, &[]);
+            }
+            statsd.timing("http.request_duration", latency, &[]);
+        }
+
+        if inner.minutes.back().map(|bucket| bucket.minute) != Some(minute) {
+            inner.minutes.push_back(MinuteBucket {
+                minute,
+                histogram: Histogram::default(),
+            });
+        }
+        while inner
+            .minutes
+            .front()
+            .is_some_and(|bucket| bucket.minute + (WINDOW_MINUTES as u64) <= minute)
+        {
+            inner.minutes.pop_front();
+        }
+        if let Some(bucket) = inner.minutes.back_mut() {
+            bucket.histogram.observe(latency, is_error);
+        }
+    }
+
+    /// Registra una respuesta en el histograma de su ruta.
+    pub fn record_route(&self, key: RouteKey, latency: Duration) {
+        let is_error = key.status_class == "5xx";
+        let mut inner = self.inner.lock().expect("mutex de métricas envenenado");
+        if let Some(statsd) = inner
+            .statsd
+            .as_ref()
+            .filter(|statsd| statsd.supports_tags())
+        {
+            statsd.timing(
+                "http.route_request_duration",
+                latency,
+                &[
+                    ("route", &key.route),
+                    ("method", key.method),
+                    ("status", key.status_class),
+                ],
+            );
+        }
+        inner
+            .routes
+            .entry(key)
+            .or_default()
+            .observe(latency, is_error);
+    }
+
+    /// Cuenta una petición como en curso hasta que se suelta el guardián devuelto, aunque el
+    /// cliente cierre la conexión antes de recibir la respuesta.
+    pub fn track_in_flight(&self) -> InFlight {
+        self.inner
+            .lock()
+            .expect("mutex de métricas envenenado")
+            .in_flight += 1;
+        InFlight {
+            metrics: self.clone(),
+        }
+    }
+
+    /// Número de peticiones en curso.
+    pub fn in_flight(&self) -> u64 {
+        self.inner
+            .lock()
+            .expect("mutex de métricas envenenado")
+            .in_flight
+    }
+
+    /// Anota el inicio del apagado ordenado; si ya había empezado, conserva el primer instante.
+    pub fn begin_shutdown(&self) {
+        self.inner
+            .lock()
+            .expect("mutex de métricas envenenado")
+            .shutdown_started_at
+            .get_or_insert_with(Utc::now);
+    }
+
+    /// Registra una consulta a la caché indicando si encontró el valor.
+    pub fn record_cache_lookup(&self, hit: bool) {
+        let mut inner = self.inner.lock().expect("mutex de métricas envenenado");
+
+        if hit {
+            inner.cache_hits += 1;
+        } else {
+            inner.cache_misses += 1;
+        }
+        if let Some(statsd) = &inner.statsd {
+            statsd.count(if hit { "cache.hits" } else { "cache.misses" }, 1, &[]);
+        }
+    }
+
+    /// Registra una petición del grupo `group` descartada por exceso de carga.
+    pub fn record_shed(&self, group: &'static str) {
+        let mut inner = self.inner.lock().expect("mutex de métricas envenenado");
+        *inner.shed.entry(group).or_default() += 1;
+        if let Some(statsd) = &inner.statsd {
+            match statsd.supports_tags() {
+                true => statsd.count("http.requests_shed", 1, &[("group", group)]),
+                false => statsd.count(&format!("http.requests_shed.{group}"), 1, &[]),
+            }
+        }
+    }
+
+    /// Reenvía a StatsD todo lo que se registre a partir de ahora.
+    pub fn export_to(&self, sink: StatsdSink) {
+        let mut inner = self.inner.lock().expect("mutex de métricas envenenado");
+        inner.statsd = Some(sink);
+    }
+
+    /// Publica el estado de `pool` junto al resto de métricas.
+    pub fn watch_pool(&self, pool: SqlitePool) {
+        let mut inner = self.inner.lock().expect("mutex de métricas envenenado");
+        inner.database_pool = Some(pool);
+    }
+
+    /// Registra cuánto tardó en obtenerse una conexión del pool, o si se agotó la espera.
+    pub fn record_pool_acquire(&self, latency: Duration, timed_out: bool) {
+        let mut inner = self.inner.lock().expect("mutex de métricas envenenado");
+        inner.pool_acquire.observe(latency, timed_out);
+        if let Some(statsd) = &inner.statsd {
+            statsd.timing("db.pool.acquire_duration", latency, &[]);
+            if timed_out {
+                statsd.count("db.pool.acquire_timeouts", 1, &[]);
+            }
+        }
+    }
+
+    /// Devuelve una copia de las métricas rellenando con ceros los minutos sin tráfico.
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        let now_minute = current_minute();
+        let inner = self.inner.lock().expect("mutex de métricas envenenado");
+
+        let first_minute = now_minute + 1 - WINDOW_MINUTES as u64;
+        let per_minute = (first_minute..=now_minute)
+            .map(|minute| {
+                let histogram = inner
+                    .minutes
+                    .iter()
+                    .find(|bucket| bucket.minute == minute)
+                    .map(|bucket| bucket.histogram.clone())
+                    .unwrap_or_default();
+
+                MinutePoint {
+                    minute,
+                    requests: histogram.requests,
+                    errors: histogram.errors,
+                    p95: histogram.quantile(0.95),
+                }
+            })
+            .collect();
+
+        MetricsSnapshot {
+            uptime: self.started_at.elapsed(),
+            totals: inner.totals.clone(),
+            per_minute,
+            cache_hits: inner.cache_hits,
+            cache_misses: inner.cache_misses,
+            shed: inner.shed.clone(),
+            pool: inner.database_pool.as_ref().map(|pool| PoolStats {
+                max_connections: pool.options().get_max_connections(),
+                open: pool.size(),
+                idle: pool.num_idle() as u32,
+            }),
+            pool_acquire: inner.pool_acquire.clone(),
+            in_flight: inner.in_flight,
+            last_error_at: inner.last_error_at,
+            shutdown_started_at: inner.shutdown_started_at,
+            routes: inner.routes.clone(),
+        }
+    }
+
+    /// Serializa las métricas en el formato de texto de Prometheus.
+    pub fn render_prometheus(&self) -> String {
+        let snapshot = self.snapshot();
+        let totals = &snapshot.totals;
+        let mut output = String::new();
+
+        let _ = writeln!(
+            output,
+            "# HELP http_requests_total Peticiones HTTP atendidas."
+        );
+        let _ = writeln!(output, "# TYPE http_requests_total counter");
+        let _ = writeln!(output, "http_requests_total {}", totals.requests);
+
+        let _ = writeln!(
+            output,
+            "# HELP http_request_errors_total Respuestas con código 5xx."
+        );
+        let _ = writeln!(output, "# TYPE http_request_errors_total counter");
+        let _ = writeln!(output, "http_request_errors_total {}", totals.errors);
+
+        let _ = writeln!(
+            output,
+            "# HELP http_requests_in_flight Peticiones HTTP en curso."
+        );
+        let _ = writeln!(output, "# TYPE http_requests_in_flight gauge");
+        let _ = writeln!(output, "http_requests_in_flight {}", snapshot.in_flight);
+
+        let _ = writeln!(
+            output,
+            "# HELP http_request_duration_seconds Latencia de las peticiones HTTP."
+        );
+        let _ = writeln!(output, "# TYPE http_request_duration_seconds histogram");
+        let mut accumulated = 0;
+        for (index, upper_bound) in LATENCY_BUCKETS.iter().enumerate() {
+            accumulated += totals.buckets[index];
+            let _ = writeln!(
+                output,
+                "http_request_duration_seconds_bucket{{le=\"{upper_bound}\"}} {accumulated}"
+            );
+        }
+        let _ = writeln!(
+            output,
+            "http_request_duration_seconds_bucket{{le=\"+Inf\"}} {}",
+            totals.requests
+        );
+        let _ = writeln!(
+            output,
+            "http_request_duration_seconds_sum {}",
+            totals.latency_sum
+        );
+        let _ = writeln!(
+            output,
+            "http_request_duration_seconds_count {}",
+            totals.requests
+        );
+
+        let _ = writeln!(
+            output,
+            "# HELP http_route_request_duration_seconds Latencia por ruta, método y clase de \
+             estado."
+        );
+        let _ = writeln!(
+            output,
+            "# TYPE http_route_request_duration_seconds histogram"
+        );
+        for (key, histogram) in &snapshot.routes {
+            let labels = format!(
+                "route=\"{}\",method=\"{}\",status=\"{}\"",
+                escape_label(&key.route),
+                key.method,
+                key.status_class
+            );
+            let mut accumulated = 0;
+            for (index, upper_bound) in LATENCY_BUCKETS.iter().enumerate() {
+                accumulated += histogram.buckets[index];
+                let _ = writeln!(
+                    output,
+                    "http_route_request_duration_seconds_bucket{{{labels},le=\"{upper_bound}\"}} \
+                     {accumulated}"
+                );
+            }
+            let _ = writeln!(
+                output,
+                "http_route_request_duration_seconds_bucket{{{labels},le=\"+Inf\"}} {}",
+                histogram.requests
+            );
+            let _ = writeln!(
+                output,
+                "http_route_request_duration_seconds_sum{{{labels}}} {}",
+                histogram.latency_sum
+            );
+            let _ = writeln!(
+                output,
+                "http_route_request_duration_seconds_count{{{labels}}} {}",
+                histogram.requests
+            );
+        }
+
+        let _ = writeln!(
+            output,
+            "# HELP cache_hits_total Consultas a la caché que encontraron el valor."
+        );
+        let _ = writeln!(output, "# TYPE cache_hits_total counter");
+        let _ = writeln!(output, "cache_hits_total {}", snapshot.cache_hits);
+
+        let _ = writeln!(
+            output,
+            "# HELP cache_misses_total Consultas a la caché sin valor almacenado."
+        );
+        let _ = writeln!(output, "# TYPE cache_misses_total counter");
+        let _ = writeln!(output, "cache_misses_total {}", snapshot.cache_misses);
+
+        let _ = writeln!(
+            output,
+            "# HELP http_requests_shed_total Peticiones descartadas por exceso de carga."
+        );
+        let _ = writeln!(output, "# TYPE http_requests_shed_total counter");
+        for (group, shed) in &snapshot.shed {
+            let _ = writeln!(
+                output,
+                "http_requests_shed_total{{group=\"{group}\"}} {shed}"
+            );
+        }
+
+        if let Some(pool) = snapshot.pool {
+            let _ = writeln!(
+                output,
+                "# HELP db_pool_connections Conexiones abiertas del pool de la base principal."
+            );
+            let _ = writeln!(output, "# TYPE db_pool_connections gauge");
+            let _ = writeln!(
+                output,
+                "db_pool_connections{{state=\"idle\"}} {}",
+                pool.idle
+            );
+            let _ = writeln!(
+                output,
+                "db_pool_connections{{state=\"in_use\"}} {}",
+                pool.open.saturating_sub(pool.idle)
+            );
+
+            let _ = writeln!(
+                output,
+                "# HELP db_pool_max_connections Conexiones máximas del pool."
+            );
+            let _ = writeln!(output, "# TYPE db_pool_max_connections gauge");
+            let _ = writeln!(output, "db_pool_max_connections {}", pool.max_connections);
+        }
+
+        let acquire = &snapshot.pool_acquire;
+        let _ = writeln!(
+            output,
+            "# HELP db_pool_acquire_duration_seconds Tiempo para obtener una conexión del pool."
+        );
+        let _ = writeln!(output, "# TYPE db_pool_acquire_duration_seconds histogram");
+        let mut accumulated = 0;
+        for (index, upper_bound) in LATENCY_BUCKETS.iter().enumerate() {
+            accumulated += acquire.buckets[index];
+            let _ = writeln!(
+                output,
+                "db_pool_acquire_duration_seconds_bucket{{le=\"{upper_bound}\"}} {accumulated}"
+            );
+        }
+        let _ = writeln!(
+            output,
+            "db_pool_acquire_duration_seconds_bucket{{le=\"+Inf\"}} {}",
+            acquire.requests
+        );
+        let _ = writeln!(
+            output,
+            "db_pool_acquire_duration_seconds_sum {}",
+            acquire.latency_sum
+        );
+        let _ = writeln!(
+            output,
+            "db_pool_acquire_duration_seconds_count {}",
+            acquire.requests
+        );
+
+        let _ = writeln!(
+            output,
+            "# HELP db_pool_acquire_timeouts_total Esperas de conexión agotadas."
+        );
+        let _ = writeln!(output, "# TYPE db_pool_acquire_timeouts_total counter");
+        let _ = writeln!(output, "db_pool_acquire_timeouts_total {}", acquire.errors);
+
+        let _ = writeln!(
+            output,
+            "# HELP process_uptime_seconds Segundos desde el arranque."
+        );
+        let _ = writeln!(output, "# TYPE process_uptime_seconds gauge");
+        let _ = writeln!(
+            output,
+            "process_uptime_seconds {}",
+            snapshot.uptime.as_secs_f64()
+        );
+
+        output
+    }
+}
+
+/// Petición en curso; deja de contar al soltarse.
+#[derive(Debug)]
+pub struct InFlight {
+    metrics: Metrics,
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        let mut inner = self
+            .metrics
+            .inner
+            .lock()
+            .expect("mutex de métricas envenenado");
+        inner.in_flight = inner.in_flight.saturating_sub(1);
+    }
+}
+
+/// Marca el inicio del apagado y registra cada `interval` cuántas peticiones quedan en curso,
+/// hasta que no quede ninguna.
+pub async fn report_shutdown_progress(metrics: Metrics, interval: Duration) {
+    metrics.begin_shutdown();
+    loop {
+        let in_flight = metrics.in_flight();
+        if in_flight == 0 {
+            info!("No quedan peticiones en curso");
+            return;
+        }
+        info!(in_flight, "Esperando a {in_flight} peticiones en curso");
+        tokio::time::sleep(interval).await;
+    }
+}
+
+/// Memoria del proceso.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MemoryUsage {
+    /// Memoria residente actual, en bytes.
+    pub resident_bytes: u64,
+    /// Máximo de memoria residente desde el arranque, en bytes.
+    pub peak_resident_bytes: u64,
+}
+
+/// Lee la memoria del proceso de `/proc/self/status`; `None` fuera de Linux.
+pub fn memory_usage() -> Option<MemoryUsage> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let kibibytes = |field: &str| {
+        status
+            .lines()
+            .find_map(|line| line.strip_prefix(field))
+            .and_then(|value| value.trim().strip_suffix("kB"))
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .map(|value| value * 1024)
+    };
+
+    Some(MemoryUsage {
+        resident_bytes: kibibytes("VmRSS:")?,
+        peak_resident_bytes: kibibytes("VmHWM:")?,
+    })
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Middleware que mide la latencia de cada petición y registra su código de estado.
+pub async fn track_requests(
+    State(metrics): State<Metrics>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let started_at = Instant::now();
+    let _in_flight = metrics.track_in_flight();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map_or(UNMATCHED_ROUTE, MatchedPath::as_str)
+        .to_string();
+    let method = request.method().clone();
+    let response = next.run(request).await;
+
+    let status = response.status().as_u16();
+    let latency = started_at.elapsed();
+    metrics.record(status, latency);
+    metrics.record_route(RouteKey::new(&route, &method, status), latency);
+
+    response
+}
+
+/// Etiqueta de ruta de las peticiones que no casan con ninguna.
+pub const UNMATCHED_ROUTE: &str = "unmatched";
+
+/// Escapa un valor de etiqueta para el formato de texto de Prometheus.
+fn escape_label(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// Minuto actual expresado como minutos desde la época Unix.
+fn current_minute() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs() / 60)
+        .unwrap_or_default()
+}
@@ -0,0 +1,208 @@
+//! Migraciones del esquema y subcomandos `migrate` de la línea de comandos.
+//!
+//! Cada migración de `migrations/` es un par reversible: `<versión>_<nombre>.up.sql` aplica
+//! el cambio y `<versión>_<nombre>.down.sql` lo deshace. El servidor aplica las pendientes al
+//! arrancar; para inspeccionar o retroceder el esquema de la base principal sin editarla a
+//! mano están los subcomandos:
+//!
+//! - `migrate status`: lista las migraciones y si están aplicadas, pendientes o modificadas
+//!   después de aplicarse.
+//! - `migrate undo`: deshace la última migración aplicada.
+//! - `migrate to <versión>`: aplica o deshace las necesarias para dejar el esquema en esa
+//!   versión (`0` lo deja vacío).
+//!
+//! Las bases de los inquilinos se migran solas al abrirse; estos subcomandos no las tocan.
+
+use std::collections::HashMap;
+
+use anyhow::{bail, Context, Result};
+use sqlx::{
+    migrate::{Migrate, Migrator},
+    SqlitePool,
+};
+
+/// Migraciones de `migrations/`, embebidas en el binario.
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+/// Subcomando de `migrate` pedido en la línea de comandos.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrateCommand {
+    Status,
+    Undo,
+    To(i64),
+}
+
+impl MigrateCommand {
+    /// Interpreta los argumentos que siguen a `migrate`.
+    pub fn parse(args: &[String]) -> Result<Self> {
+        match args {
+            [command] if command == "status" => Ok(Self::Status),
+            [command] if command == "undo" => Ok(Self::Undo),
+            [command, version] if command == "to" => version
+                .parse()
+                .map(Self::To)
+                .with_context(|| format!("Versión de migración inválida: {version}")),
+            _ => bail!("Uso: migrate status | migrate undo | migrate to <versión>"),
+        }
+    }
+}
+
+/// Situación de una migración en la base.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MigrationState {
+    Applied,
+    Pending,
+    /// Aplicada, pero el archivo cambió desde entonces.
+    Modified,
+}
+
+impl MigrationState {
+    fn label(self) -> &'static str {
+        match self {
+            Self::Applied => "aplicada",
+            Self::Pending => "pendiente",
+            Self::Modified => "modificada",
+        }
+    }
+}
+
+/// Fila de `migrate status`.
+#[derive(Debug, Clone)]
+pub struct MigrationStatus {
+    pub version: i64,
+    pub description: String,
+    pub state: MigrationState,
+}
+
+/// Estado de cada migración conocida, de la más antigua a la más reciente.
+pub async fn status(pool: &SqlitePool) -> Result<Vec<MigrationStatus>> {
+    let applied = applied_checksums(pool).await?;
+
+    Ok(MIGRATOR
+        .iter()
+        .filter(|migration| !migration.migration_type.is_down_migration())
+        .map(|migration| MigrationStatus {
+            version: migration.version,
+            description: migration.description.to_string(),
+            state: match applied.get(&migration.version) {
+                None => MigrationState::Pending,
+                Some(checksum) if *checksum == *migration.checksum => MigrationState::Applied,
+                Some(_) => MigrationState::Modified,
+            },
+        })
+        .collect())
+}
+
+/// Deshace la última migración aplicada y devuelve su versión, o `None` si no había ninguna.
+pub async fn undo_last(pool: &SqlitePool) -> Result<Option<i64>> {
+    let mut versions = applied_versions(pool).await?;
+    let Some(latest) = versions.pop() else {
+        return Ok(None);
+    };
+
+    MIGRATOR
+        .undo(pool, versions.last().copied().unwrap_or(0))
+        .await
+        .with_context(|| format!("No se pudo deshacer la migración {latest}"))?;
+    Ok(Some(latest))
+}
+
+/// Deja el esquema en `target`: deshace las migraciones posteriores y aplica las anteriores
+/// que falten.
+pub async fn migrate_to(pool: &SqlitePool, target: i64) -> Result<()> {
+    if target != 0 && !MIGRATOR.iter().any(|migration| migration.version == target) {
+        bail!("No existe la migración {target}");
+    }
+
+    MIGRATOR
+        .undo(pool, target)
+        .await
+        .with_context(|| format!("No se pudo retroceder a la migración {target}"))?;
+
+    let applied = applied_checksums(pool).await?;
+    let mut connection = pool.acquire().await?;
+    for migration in MIGRATOR.iter().filter(|migration| {
+        !migration.migration_type.is_down_migration()
+            && migration.version <= target
+            && !applied.contains_key(&migration.version)
+    }) {
+        connection
+            .apply(migration)
+            .await
+            .with_context(|| format!("Fallo al aplicar la migración {}", migration.version))?;
+    }
+
+    Ok(())
+}
+
+/// Falla si quedan migraciones sin aplicar; la usa el servidor cuando arranca con
+/// `RUN_MIGRATIONS=false`.
+pub async fn ensure_up_to_date(pool: &SqlitePool) -> Result<()> {
+    let pending = status(pool)
+        .await?
+        .into_iter()
+        .filter(|migration| migration.state == MigrationState::Pending)
+        .map(|migration| migration.version.to_string())
+        .collect::<Vec<_>>();
+    if !pending.is_empty() {
+        bail!(
+            "Hay migraciones pendientes ({}); aplícalas con --migrate-only o arranca con \
+             RUN_MIGRATIONS=true",
+            pending.join(", ")
+        );
+    }
+
+    Ok(())
+}
+
+/// Ejecuta `command` sobre la base principal e imprime el resultado.
+pub async fn run_command(pool: &SqlitePool, command: MigrateCommand) -> Result<()> {
+    match command {
+        MigrateCommand::Status => {
+            for migration in status(pool).await? {
+                println!(
+                    "{}  {:<10}  {}",
+                    migration.version,
+                    migration.state.label(),
+                    migration.description
+                );
+            }
+        }
+        MigrateCommand::Undo => match undo_last(pool).await? {
+            Some(version) => println!("Migración {version} deshecha"),
+            None => println!("No hay migraciones aplicadas"),
+        },
+        MigrateCommand::To(version) => {
+            migrate_to(pool, version).await?;
+            println!("Esquema en la versión {version}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Versiones aplicadas, de la más antigua a la más reciente.
+pub async fn applied_versions(pool: &SqlitePool) -> Result<Vec<i64>> {
+    let mut versions = applied_checksums(pool)
+        .await?
+        .into_keys()
+        .collect::<Vec<_>>();
+    versions.sort_unstable();
+    Ok(versions)
+}
+
+/// Versiones aplicadas con su suma de comprobación. Falla si una migración quedó a medias.
+async fn applied_checksums(pool: &SqlitePool) -> Result<HashMap<i64, Vec<u8>>> {
+    let mut connection = pool.acquire().await?;
+    connection.ensure_migrations_table().await?;
+    if let Some(version) = connection.dirty_version().await? {
+        bail!("La migración {version} quedó a medias; corrígela a mano antes de continuar");
+    }
+
+    Ok(connection
+        .list_applied_migrations()
+        .await?
+        .into_iter()
+        .map(|migration| (migration.version, migration.checksum.into_owned()))
+        .collect())
+}
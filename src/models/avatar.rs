@@ -0,0 +1,125 @@
+//! Validación de los avatares subidos por los usuarios.
+//!
+//! Solo se aceptan imágenes PNG, JPEG y WebP de hasta [`MAX_AVATAR_BYTES`]. Además del
+//! `Content-Type` declarado se comprueba la firma del archivo, para no guardar contenido
+//! que un navegador pudiera interpretar como otra cosa.
+
+use uuid::Uuid;
+
+use crate::models::user::ValidationErrors;
+
+/// Nombre del campo multipart que contiene la imagen.
+pub const AVATAR_FIELD: &str = "avatar";
+/// Tamaño máximo admitido para un avatar (1 MiB).
+pub const MAX_AVATAR_BYTES: usize = 1024 * 1024;
+
+/// Formatos de imagen admitidos.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AvatarFormat {
+    Png,
+    Jpeg,
+    Webp,
+}
+
+impl AvatarFormat {
+    /// Interpreta un `Content-Type`, ignorando parámetros y mayúsculas.
+    pub fn from_content_type(content_type: &str) -> Option<Self> {
+        let essence = content_type
+            .split(';')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase();
+
+        match essence.as_str() {
+            "image/png" => Some(Self::Png),
+            "image/jpeg" | "image/jpg" => Some(Self::Jpeg),
+            "image/webp" => Some(Self::Webp),
+            _ => None,
+        }
+    }
+
+    /// Recupera el formato a partir de la extensión de una clave de almacenamiento.
+    pub fn from_key(key: &str) -> Option<Self> {
+        match key.rsplit_once('.')?.1 {
+            "png" => Some(Self::Png),
+            "jpg" => Some(Self::Jpeg),
+            "webp" => Some(Self::Webp),
+            _ => None,
+        }
+    }
+
+    /// `Content-Type` con el que se sirve la imagen.
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Png => "image/png",
+            Self::Jpeg => "image/jpeg",
+            Self::Webp => "image/webp",
+        }
+    }
+
+    /// Extensión usada en la clave de almacenamiento.
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Jpeg => "jpg",
+            Self::Webp => "webp",
+        }
+    }
+
+    /// Comprueba los bytes mágicos del formato.
+    fn matches_signature(self, bytes: &[u8]) -> bool {
+        match self {
+            Self::Png => bytes.starts_with(b"\x89PNG\r\n\x1a\n"),
+            Self::Jpeg => bytes.starts_with(&[0xFF, 0xD8, 0xFF]),
+            Self::Webp => bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP",
+        }
+    }
+}
+
+/// Avatar ya validado, listo para guardarse.
+#[derive(Debug, Clone)]
+pub struct Avatar {
+    pub format: AvatarFormat,
+    pub bytes: Vec<u8>,
+}
+
+impl Avatar {
+    /// Valida el tipo declarado, el tamaño y la firma del contenido.
+    pub fn try_new(content_type: Option<&str>, bytes: Vec<u8>) -> Result<Self, ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+
+        let format = content_type.and_then(AvatarFormat::from_content_type);
+        if format.is_none() {
+            errors.push(AVATAR_FIELD, "Debe ser una imagen PNG, JPEG o WebP");
+        }
+
+        if bytes.is_empty() {
+            errors.push(AVATAR_FIELD, "No puede estar vacío");
+        } else if bytes.len() > MAX_AVATAR_BYTES {
+            errors.push(AVATAR_FIELD, "Supera el tamaño máximo de 1 MiB");
+        } else if let Some(format) = format {
+            if !format.matches_signature(&bytes) {
+                errors.push(
+                    AVATAR_FIELD,
+                    "El contenido no coincide con el tipo declarado",
+                );
+            }
+        }
+
+        match format {
+            Some(format) if errors.is_empty() => Ok(Self { format, bytes }),
+            _ => Err(errors),
+        }
+    }
+
+    /// Clave de almacenamiento del avatar de un usuario.
+    pub fn storage_key(&self, user_id: Uuid) -> String {
+        format!("avatars/{user_id}.{}", self.format.extension())
+    }
+}
+
+/// URL estable desde la que se sirve el avatar de un usuario.
+pub fn avatar_url(user_id: Uuid) -> String {
+    format!("/users/{user_id}/avatar")
+}
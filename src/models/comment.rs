@@ -0,0 +1,87 @@
+//! Modelos y validaciones de los comentarios de publicaciones.
+//!
+//! Un comentario pertenece a una publicación y, opcionalmente, a un autor: si el autor se
+//! elimina, el comentario se conserva sin él. Los comentarios borrados desde la API solo se
+//! marcan con `deleted_at` y dejan de listarse.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use super::validation::{not_empty, Validate};
+
+/// Columnas de `comments` que se proyectan sobre el modelo [`Comment`].
+pub const COMMENT_COLUMNS: &str = "id, post_id, author_id, body, created_at, updated_at";
+
+/// Longitud máxima de un comentario, en caracteres.
+const MAX_BODY_LENGTH: u64 = 5_000;
+
+/// Comentario persistido.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Comment {
+    pub id: Uuid,
+    pub post_id: Uuid,
+    /// `None` si el autor ya no existe.
+    #[serde(with = "crate::ids::public::option")]
+    pub author_id: Option<Uuid>,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Payload esperado para crear un comentario.
+#[derive(Debug, Deserialize)]
+pub struct CreateComment {
+    #[serde(with = "crate::ids::public")]
+    pub author_id: Uuid,
+    pub body: String,
+}
+
+/// Payload esperado para editar un comentario.
+#[derive(Debug, Deserialize)]
+pub struct UpdateComment {
+    pub body: String,
+}
+
+/// Versión validada de un nuevo comentario.
+#[derive(Debug, Clone, validator::Validate)]
+pub struct NewComment {
+    pub author_id: Uuid,
+    #[validate(
+        custom(function = not_empty, message = "Debe contener al menos un carácter"),
+        length(max = MAX_BODY_LENGTH, message = "Debe tener 5000 caracteres o menos")
+    )]
+    pub body: String,
+}
+
+/// Cuerpo de comentario validado.
+#[derive(Debug, Clone, validator::Validate)]
+pub struct CommentBody {
+    #[validate(
+        custom(function = not_empty, message = "Debe contener al menos un carácter"),
+        length(max = MAX_BODY_LENGTH, message = "Debe tener 5000 caracteres o menos")
+    )]
+    pub body: String,
+}
+
+impl Validate for NewComment {
+    type Payload = CreateComment;
+
+    fn sanitize(value: CreateComment) -> Self {
+        Self {
+            author_id: value.author_id,
+            body: value.body.trim().to_string(),
+        }
+    }
+}
+
+impl Validate for CommentBody {
+    type Payload = UpdateComment;
+
+    fn sanitize(value: UpdateComment) -> Self {
+        Self {
+            body: value.body.trim().to_string(),
+        }
+    }
+}
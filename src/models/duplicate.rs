@@ -0,0 +1,121 @@
+//! Detección aproximada de usuarios duplicados.
+//!
+//! Dos usuarios se consideran posibles duplicados si la parte local de sus correos coincide
+//! una vez normalizada (sin subdirección `+etiqueta` ni puntos, así que
+//! `ana.garcia+tienda@example.com` y `anagarcia@example.org` coinciden) o si sus nombres son
+//! parecidos según la similitud de trigramas, la misma medida que `pg_trgm`. Solo se
+//! señalan candidatos: fusionarlos es decisión de un operador.
+
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+
+use super::user::User;
+
+/// Similitud de nombres a partir de la cual dos usuarios se señalan como duplicados.
+pub const NAME_SIMILARITY_THRESHOLD: f64 = 0.5;
+
+/// Motivo por el que dos usuarios se señalan como posibles duplicados.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicateReason {
+    SameEmailLocalPart,
+    SimilarName,
+}
+
+/// Usuario existente que podría ser el mismo que otro.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateCandidate {
+    pub user: User,
+    pub reasons: Vec<DuplicateReason>,
+    /// Similitud de trigramas entre los nombres, de 0 a 1.
+    pub name_similarity: f64,
+}
+
+/// Pareja de posibles duplicados devuelta por `GET /users/duplicates`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicatePair {
+    pub users: [User; 2],
+    pub reasons: Vec<DuplicateReason>,
+    pub name_similarity: f64,
+}
+
+/// Respuesta de un alta con `?check_duplicates=true`: el usuario creado y los existentes
+/// que se le parecen.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreatedUser {
+    #[serde(flatten)]
+    pub user: User,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub possible_duplicates: Option<Vec<DuplicateCandidate>>,
+}
+
+/// Parámetros de consulta aceptados al crear un usuario.
+#[derive(Debug, Default, Deserialize)]
+pub struct CreateUserOptions {
+    /// Si es `true`, la respuesta incluye los usuarios existentes que se le parecen.
+    #[serde(default)]
+    pub check_duplicates: bool,
+}
+
+/// Huella de un usuario precalculada para compararlo con muchos otros.
+#[derive(Debug)]
+pub struct DuplicateKey {
+    local_part: String,
+    trigrams: HashSet<[char; 3]>,
+}
+
+impl DuplicateKey {
+    pub fn new(user: &User) -> Self {
+        Self {
+            local_part: normalized_local_part(&user.email),
+            trigrams: trigrams(&user.name),
+        }
+    }
+
+    /// Motivos y similitud de nombres si los dos usuarios parecen el mismo.
+    pub fn compare(&self, other: &DuplicateKey) -> Option<(Vec<DuplicateReason>, f64)> {
+        let mut reasons = Vec::new();
+        if !self.local_part.is_empty() && self.local_part == other.local_part {
+            reasons.push(DuplicateReason::SameEmailLocalPart);
+        }
+
+        let name_similarity = similarity(&self.trigrams, &other.trigrams);
+        if name_similarity >= NAME_SIMILARITY_THRESHOLD {
+            reasons.push(DuplicateReason::SimilarName);
+        }
+
+        (!reasons.is_empty()).then_some((reasons, name_similarity))
+    }
+}
+
+/// Parte local sin subdirección ni puntos.
+fn normalized_local_part(email: &str) -> String {
+    let local_part = email.rsplit_once('@').map_or(email, |(local, _)| local);
+    let local_part = local_part.split('+').next().unwrap_or_default();
+    local_part.replace('.', "").to_lowercase()
+}
+
+/// Trigramas de cada palabra, rellenada con dos espacios delante y uno detrás.
+pub fn trigrams(name: &str) -> HashSet<[char; 3]> {
+    name.to_lowercase()
+        .split(|character: char| !character.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .flat_map(|word| {
+            let padded = ["  ", word, " "].concat().chars().collect::<Vec<_>>();
+            padded
+                .windows(3)
+                .map(|window| [window[0], window[1], window[2]])
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Trigramas compartidos sobre el total de trigramas distintos.
+fn similarity(left: &HashSet<[char; 3]>, right: &HashSet<[char; 3]>) -> f64 {
+    let union = left.union(right).count();
+    if union == 0 {
+        return 0.0;
+    }
+    left.intersection(right).count() as f64 / union as f64
+}
@@ -0,0 +1,125 @@
+//! Validación y normalización de direcciones de correo.
+//!
+//! Sigue la sintaxis de RFC 5321/5322 ampliada con RFC 6531 (caracteres UTF-8 en la parte
+//! local): la parte local puede ser un `dot-atom` o una cadena entre comillas y el dominio
+//! un nombre de host o un literal IP entre corchetes. Los dominios internacionalizados se
+//! guardan en Punycode (`usuario@xn--bcher-kva.example`), de modo que una misma dirección
+//! escrita en Unicode o en ASCII se considera la misma.
+//!
+//! Como política de la API se exige además que el dominio tenga al menos dos etiquetas:
+//! `usuario@localhost` es sintácticamente válido pero no es una dirección pública.
+
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+/// Longitud máxima de una dirección en un camino SMTP (RFC 5321, 4.5.3.1.3).
+const MAX_ADDRESS_LENGTH: usize = 254;
+/// Longitud máxima de la parte local, en octetos.
+const MAX_LOCAL_PART_LENGTH: usize = 64;
+/// Longitud máxima de un nombre de dominio, en octetos.
+const MAX_DOMAIN_LENGTH: usize = 253;
+/// Longitud máxima de cada etiqueta del dominio, en octetos.
+const MAX_LABEL_LENGTH: usize = 63;
+
+/// Devuelve la dirección normalizada (dominio en Punycode) si es válida.
+pub fn normalize_email(email: &str) -> Option<String> {
+    // La parte local entre comillas puede contener `@`, así que el separador es el último.
+    let (local_part, domain) = email.rsplit_once('@')?;
+    if !is_valid_local_part(local_part) {
+        return None;
+    }
+
+    let domain = normalize_domain(domain)?;
+    let normalized = format!("{local_part}@{domain}");
+    (normalized.len() <= MAX_ADDRESS_LENGTH).then_some(normalized)
+}
+
+/// Parte local: `dot-atom` o `quoted-string`.
+fn is_valid_local_part(local_part: &str) -> bool {
+    if local_part.is_empty() || local_part.len() > MAX_LOCAL_PART_LENGTH {
+        return false;
+    }
+
+    match local_part
+        .strip_prefix('"')
+        .and_then(|rest| rest.strip_suffix('"'))
+    {
+        Some(quoted) => is_valid_quoted_content(quoted),
+        None => local_part
+            .split('.')
+            .all(|atom| !atom.is_empty() && atom.chars().all(is_atom_char)),
+    }
+}
+
+/// Carácter admitido en un átomo: `atext` de RFC 5322 o cualquier carácter no ASCII.
+fn is_atom_char(character: char) -> bool {
+    character.is_ascii_alphanumeric()
+        || "!#$%&'*+-/=?^_`{|}~".contains(character)
+        || (!character.is_ascii() && !character.is_control())
+}
+
+/// Contenido de una parte local entrecomillada: `qtext` y pares escapados con `\`.
+fn is_valid_quoted_content(content: &str) -> bool {
+    let mut characters = content.chars();
+    while let Some(character) = characters.next() {
+        match character {
+            '\\' => match characters.next() {
+                Some(escaped) if escaped == ' ' || escaped == '\t' || is_visible(escaped) => {}
+                _ => return false,
+            },
+            '"' => return false,
+            ' ' | '\t' => {}
+            character if is_visible(character) => {}
+            _ => return false,
+        }
+    }
+    true
+}
+
+/// Carácter imprimible distinto del espacio (`VCHAR`, ampliado a UTF-8).
+fn is_visible(character: char) -> bool {
+    character.is_ascii_graphic() || (!character.is_ascii() && !character.is_control())
+}
+
+/// Valida el dominio de una dirección y lo convierte a su forma ASCII.
+pub fn normalize_domain(domain: &str) -> Option<String> {
+    if let Some(literal) = domain
+        .strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+    {
+        return is_valid_address_literal(literal).then(|| domain.to_string());
+    }
+
+    let ascii = idna::domain_to_ascii_strict(domain).ok()?;
+    let labels = ascii.split('.').collect::<Vec<_>>();
+    let is_valid = ascii.len() <= MAX_DOMAIN_LENGTH
+        && labels.len() >= 2
+        && labels.iter().all(|label| is_valid_label(label))
+        // Un TLD numérico haría pasar direcciones IP sin corchetes como `1.2.3.4`.
+        && !labels
+            .last()
+            .is_some_and(|tld| tld.bytes().all(|byte| byte.is_ascii_digit()));
+
+    is_valid.then_some(ascii)
+}
+
+/// Etiqueta de nombre de host: letras, dígitos y guiones, sin guion inicial ni final.
+fn is_valid_label(label: &str) -> bool {
+    !label.is_empty()
+        && label.len() <= MAX_LABEL_LENGTH
+        && !label.starts_with('-')
+        && !label.ends_with('-')
+        && label
+            .bytes()
+            .all(|byte| byte.is_ascii_alphanumeric() || byte == b'-')
+}
+
+/// Literal de dirección: `[192.0.2.1]` o `[IPv6:2001:db8::1]`.
+fn is_valid_address_literal(literal: &str) -> bool {
+    match literal
+        .get(..5)
+        .filter(|prefix| prefix.eq_ignore_ascii_case("ipv6:"))
+    {
+        Some(_) => literal[5..].parse::<Ipv6Addr>().is_ok(),
+        None => literal.parse::<Ipv4Addr>().is_ok(),
+    }
+}
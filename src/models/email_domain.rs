@@ -0,0 +1,73 @@
+//! Modelos y validaciones de la lista de dominios de correo bloqueados.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use super::email::normalize_domain;
+use super::validation::{invalid, not_empty, Validate};
+
+/// Columnas de `blocked_email_domains` que se proyectan sobre [`BlockedEmailDomain`].
+pub const BLOCKED_EMAIL_DOMAIN_COLUMNS: &str = "domain, reason, created_at";
+
+/// Longitud máxima del motivo del bloqueo.
+const MAX_REASON_LENGTH: u64 = 200;
+
+/// Dominio bloqueado.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct BlockedEmailDomain {
+    pub domain: String,
+    pub reason: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Cuerpo de `POST /admin/blocked-email-domains`.
+#[derive(Debug, Deserialize)]
+pub struct BlockEmailDomain {
+    pub domain: String,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+/// Bloqueo validado, con el dominio ya normalizado.
+#[derive(Debug, Clone, PartialEq, Eq, validator::Validate)]
+pub struct NewBlockedEmailDomain {
+    #[validate(
+        custom(function = not_empty, message = "Debe contener al menos un carácter"),
+        custom(function = blockable_domain, message = "Formato de dominio inválido")
+    )]
+    pub domain: String,
+    #[validate(length(max = MAX_REASON_LENGTH, message = "Debe tener 200 caracteres o menos"))]
+    pub reason: Option<String>,
+}
+
+impl Validate for NewBlockedEmailDomain {
+    type Payload = BlockEmailDomain;
+
+    fn sanitize(value: BlockEmailDomain) -> Self {
+        Self {
+            domain: parse_domain(&value.domain).unwrap_or_else(|| value.domain.trim().to_string()),
+            reason: value
+                .reason
+                .map(|reason| reason.trim().to_string())
+                .filter(|reason| !reason.is_empty()),
+        }
+    }
+}
+
+/// Comprueba que el dominio, ya normalizado, se pueda bloquear. El vacío lo rechaza
+/// [`not_empty`].
+fn blockable_domain(domain: &str) -> Result<(), validator::ValidationError> {
+    if domain.is_empty() || parse_domain(domain).is_some() {
+        Ok(())
+    } else {
+        Err(invalid())
+    }
+}
+
+/// Normaliza un dominio escrito por un operador (admite `@dominio` y mayúsculas). Los
+/// literales IP no se pueden bloquear.
+pub fn parse_domain(value: &str) -> Option<String> {
+    let domain = value.trim().trim_start_matches('@').to_lowercase();
+    normalize_domain(&domain).filter(|domain| !domain.starts_with('['))
+}
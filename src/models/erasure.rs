@@ -0,0 +1,28 @@
+//! Modelos del borrado de datos personales de un usuario.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Nombre que sustituye al de un usuario borrado.
+pub const ERASED_NAME: &str = "Usuario borrado";
+
+/// Correo que sustituye al de un usuario borrado. Es único por usuario, para respetar la
+/// restricción de `users.email`, y usa el dominio reservado `.invalid`, que nunca recibe correo.
+pub fn erased_email(user_id: Uuid) -> String {
+    format!("erased-{}@erased.invalid", user_id.simple())
+}
+
+/// Cuerpo de `POST /users/:id/erase`. Sin token se solicita el borrado; con él se confirma.
+#[derive(Debug, Default, Deserialize)]
+pub struct EraseUser {
+    #[serde(default)]
+    pub confirmation_token: Option<String>,
+}
+
+/// Respuesta a una solicitud de borrado: el token que hay que reenviar para confirmarlo.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ErasureConfirmation {
+    pub confirmation_token: String,
+    pub expires_at: DateTime<Utc>,
+}
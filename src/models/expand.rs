@@ -0,0 +1,110 @@
+//! Expansión de relaciones (`?expand=`) en la lectura de un usuario.
+//!
+//! `GET /users/:id?expand=posts,teams` incrusta los recursos relacionados en la misma
+//! respuesta. Las relaciones anidadas se indican con puntos (`posts.comments`) y están
+//! limitadas en profundidad y en número de elementos para acotar el coste de la consulta.
+
+use serde::Serialize;
+
+use super::comment::Comment;
+use super::post::Post;
+use super::projection::Projected;
+use super::tag::Tag;
+use super::team::Team;
+use super::user::{User, ValidationErrors};
+
+/// Niveles máximos de una relación: `posts.comments` tiene dos.
+pub const MAX_EXPAND_DEPTH: usize = 2;
+
+/// Elementos máximos que se incrustan por relación (y por publicación, en los comentarios).
+pub const MAX_EXPANDED_ITEMS: i64 = 50;
+
+/// Relaciones de un usuario que se pueden expandir.
+const USER_RELATIONS: &[&str] = &["posts", "posts.comments", "teams", "tags"];
+
+/// Relaciones pedidas para un usuario.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UserExpansions {
+    pub posts: bool,
+    pub post_comments: bool,
+    pub teams: bool,
+    pub tags: bool,
+}
+
+impl UserExpansions {
+    /// Indica si no se pidió ninguna relación.
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+impl TryFrom<&str> for UserExpansions {
+    type Error = ValidationErrors;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let mut errors = ValidationErrors::new();
+        let mut expansions = Self::default();
+
+        let relations = value
+            .split(',')
+            .map(str::trim)
+            .filter(|relation| !relation.is_empty())
+            .collect::<Vec<_>>();
+
+        if relations.is_empty() {
+            errors.push("expand", "Debe indicar al menos una relación");
+        } else if relations
+            .iter()
+            .any(|relation| relation.split('.').count() > MAX_EXPAND_DEPTH)
+        {
+            errors.push("expand", "Supera la profundidad máxima de expansión");
+        } else if relations
+            .iter()
+            .any(|relation| !USER_RELATIONS.contains(relation))
+        {
+            errors.push("expand", "Contiene una relación desconocida");
+        }
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        for relation in relations {
+            match relation {
+                "posts" => expansions.posts = true,
+                // Expandir los comentarios implica expandir sus publicaciones.
+                "posts.comments" => {
+                    expansions.posts = true;
+                    expansions.post_comments = true;
+                }
+                "teams" => expansions.teams = true,
+                "tags" => expansions.tags = true,
+                _ => {}
+            }
+        }
+
+        Ok(expansions)
+    }
+}
+
+/// Usuario con sus relaciones expandidas; las no pedidas se omiten.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExpandedUser {
+    #[serde(flatten)]
+    pub user: Projected<User>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub posts: Option<Vec<ExpandedPost>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub teams: Option<Vec<Team>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<Vec<Tag>>,
+}
+
+/// Publicación incrustada, con sus comentarios si se pidieron.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExpandedPost {
+    #[serde(flatten)]
+    pub post: Post,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub comments: Option<Vec<Comment>>,
+}
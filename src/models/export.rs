@@ -0,0 +1,62 @@
+//! Modelos de las exportaciones asíncronas del listado de usuarios.
+//!
+//! `POST /exports` registra una exportación en estado `pending` y encola
+//! [`ExportJob`](crate::export::job::ExportJob), que genera el archivo en el almacenamiento.
+//! `GET /exports/:id` devuelve el estado y, una vez completada, un enlace de descarga firmado
+//! y con caducidad.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::export::ExportFormat;
+
+use super::user::ListUsersQuery;
+
+/// Columnas de `exports` que se proyectan sobre el modelo [`Export`].
+pub const EXPORT_COLUMNS: &str =
+    "id, format, status, storage_key, row_count, error, created_at, completed_at";
+
+/// Estado de una exportación.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum ExportStatus {
+    /// Encolada, o a la espera de reintentar tras un fallo.
+    Pending,
+    Running,
+    Completed,
+    /// Agotó los reintentos; `error` indica el último motivo.
+    Failed,
+}
+
+/// Exportación de usuarios.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Export {
+    pub id: Uuid,
+    pub format: ExportFormat,
+    pub status: ExportStatus,
+    /// Clave del archivo en el almacenamiento; no se expone, se descarga con `download_url`.
+    #[serde(skip)]
+    pub storage_key: Option<String>,
+    /// Usuarios incluidos en el archivo, una vez completada.
+    pub row_count: Option<i64>,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    /// Enlace de descarga firmado, solo en las exportaciones completadas.
+    #[sqlx(skip)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub download_url: Option<String>,
+}
+
+/// Cuerpo de `POST /exports`: el formato del archivo y los mismos parámetros que el listado
+/// de usuarios (`status`, `tag` y `filter`).
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CreateExport {
+    #[serde(default)]
+    pub format: ExportFormat,
+    #[serde(flatten)]
+    pub query: ListUsersQuery,
+}
@@ -1 +1,21 @@
+pub mod avatar;
+pub mod comment;
+pub mod duplicate;
+pub mod email;
+pub mod email_domain;
+pub mod erasure;
+pub mod expand;
+pub mod export;
+pub mod pagination;
+pub mod post;
+pub mod preferences;
+pub mod projection;
+pub mod stats;
+pub mod tag;
+pub mod team;
+pub mod tenant;
 pub mod user;
+pub mod v2;
+pub mod validation;
+pub mod view;
+pub mod webhook;
@@ -0,0 +1,78 @@
+//! Parámetros y respuesta comunes de los listados paginados.
+
+use serde::{Deserialize, Serialize};
+
+use super::user::ValidationErrors;
+
+/// Tamaño de página por defecto.
+pub const DEFAULT_PER_PAGE: u32 = 20;
+/// Tamaño de página máximo admitido.
+pub const MAX_PER_PAGE: u32 = 100;
+
+/// Parámetros de consulta `?page=&per_page=`; las páginas empiezan en 1.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct PageParams {
+    pub page: u32,
+    pub per_page: u32,
+}
+
+impl Default for PageParams {
+    fn default() -> Self {
+        Self {
+            page: 1,
+            per_page: DEFAULT_PER_PAGE,
+        }
+    }
+}
+
+impl PageParams {
+    /// Comprueba que la página y su tamaño estén dentro de los límites.
+    pub fn validate(self) -> Result<Self, ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+
+        if self.page == 0 {
+            errors.push("page", "Debe ser mayor o igual que 1");
+        }
+        if self.per_page == 0 || self.per_page > MAX_PER_PAGE {
+            errors.push("per_page", "Debe estar entre 1 y 100");
+        }
+
+        if errors.is_empty() {
+            Ok(self)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Número de filas a devolver (`LIMIT`).
+    pub fn limit(self) -> i64 {
+        i64::from(self.per_page)
+    }
+
+    /// Número de filas a saltar (`OFFSET`).
+    pub fn offset(self) -> i64 {
+        i64::from(self.page.saturating_sub(1)) * i64::from(self.per_page)
+    }
+}
+
+/// Página de resultados junto con el total de elementos disponibles.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    pub page: u32,
+    pub per_page: u32,
+    pub total: i64,
+}
+
+impl<T> Page<T> {
+    /// Construye la respuesta a partir de los elementos y los parámetros usados.
+    pub fn new(items: Vec<T>, params: PageParams, total: i64) -> Self {
+        Self {
+            items,
+            page: params.page,
+            per_page: params.per_page,
+            total,
+        }
+    }
+}
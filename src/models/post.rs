@@ -0,0 +1,114 @@
+//! Modelos y validaciones de las publicaciones.
+//!
+//! Cada publicación pertenece a un usuario (`author_id`) y se elimina junto con él.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use super::validation::{invalid, not_empty, Validate};
+
+/// Columnas de `posts` que se proyectan sobre el modelo [`Post`].
+pub const POST_COLUMNS: &str = "id, author_id, title, body, created_at, updated_at";
+
+/// Longitud máxima del título, en caracteres.
+const MAX_TITLE_LENGTH: u64 = 200;
+/// Longitud máxima del cuerpo, en caracteres.
+const MAX_BODY_LENGTH: u64 = 20_000;
+
+/// Publicación persistida.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Post {
+    pub id: Uuid,
+    #[serde(with = "crate::ids::public")]
+    pub author_id: Uuid,
+    pub title: String,
+    pub body: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Payload esperado para crear una publicación.
+#[derive(Debug, Deserialize)]
+pub struct CreatePost {
+    #[serde(with = "crate::ids::public")]
+    pub author_id: Uuid,
+    pub title: String,
+    pub body: String,
+}
+
+/// Payload esperado para actualizar parcialmente una publicación.
+#[derive(Debug, Deserialize)]
+pub struct UpdatePost {
+    pub title: Option<String>,
+    pub body: Option<String>,
+}
+
+/// Versión validada de una nueva publicación.
+#[derive(Debug, Clone, validator::Validate)]
+pub struct NewPost {
+    pub author_id: Uuid,
+    #[validate(
+        custom(function = not_empty, message = "Debe contener al menos un carácter"),
+        length(max = MAX_TITLE_LENGTH, message = "Debe tener 200 caracteres o menos")
+    )]
+    pub title: String,
+    #[validate(
+        custom(function = not_empty, message = "Debe contener al menos un carácter"),
+        length(max = MAX_BODY_LENGTH, message = "Debe tener 20000 caracteres o menos")
+    )]
+    pub body: String,
+}
+
+/// Conjunto de cambios válidos sobre una publicación existente.
+#[derive(Debug, Clone, validator::Validate)]
+#[validate(schema(
+    function = PostChanges::has_changes,
+    message = "Debe proporcionar al menos un campo para actualizar"
+))]
+pub struct PostChanges {
+    #[validate(length(max = MAX_TITLE_LENGTH, message = "Debe tener 200 caracteres o menos"))]
+    pub title: Option<String>,
+    #[validate(length(max = MAX_BODY_LENGTH, message = "Debe tener 20000 caracteres o menos"))]
+    pub body: Option<String>,
+}
+
+impl Validate for NewPost {
+    type Payload = CreatePost;
+
+    fn sanitize(value: CreatePost) -> Self {
+        Self {
+            author_id: value.author_id,
+            title: value.title.trim().to_string(),
+            body: value.body.trim().to_string(),
+        }
+    }
+}
+
+impl Validate for PostChanges {
+    type Payload = UpdatePost;
+
+    /// Los campos vacíos cuentan como no enviados.
+    fn sanitize(value: UpdatePost) -> Self {
+        Self {
+            title: value
+                .title
+                .map(|title| title.trim().to_string())
+                .filter(|title| !title.is_empty()),
+            body: value
+                .body
+                .map(|body| body.trim().to_string())
+                .filter(|body| !body.is_empty()),
+        }
+    }
+}
+
+impl PostChanges {
+    fn has_changes(&self) -> Result<(), validator::ValidationError> {
+        if self.title.is_none() && self.body.is_none() {
+            return Err(invalid());
+        }
+        Ok(())
+    }
+}
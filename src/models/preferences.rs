@@ -0,0 +1,118 @@
+//! Preferencias de cada usuario.
+//!
+//! Se guardan como un documento JSON en `users.preferences`, pero siempre pasan por el
+//! tipo [`UserPreferences`]: las claves desconocidas se rechazan y las ausentes toman su
+//! valor por defecto, de modo que añadir una preferencia nueva no exige migrar datos.
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::user::ValidationErrors;
+
+/// Tema visual de la interfaz.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Theme {
+    Light,
+    Dark,
+    /// Sigue la configuración del sistema operativo.
+    #[default]
+    System,
+}
+
+/// Frecuencia del resumen de actividad por correo.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DigestFrequency {
+    Never,
+    Daily,
+    #[default]
+    Weekly,
+}
+
+/// Ajustes de notificación.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationSettings {
+    /// Avisos inmediatos por correo.
+    pub email: bool,
+    pub digest: DigestFrequency,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self {
+            email: true,
+            digest: DigestFrequency::default(),
+        }
+    }
+}
+
+/// Documento completo de preferencias de un usuario.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UserPreferences {
+    pub theme: Theme,
+    /// Código de idioma ISO 639-1, por ejemplo `es`.
+    pub language: String,
+    pub notifications: NotificationSettings,
+}
+
+impl Default for UserPreferences {
+    fn default() -> Self {
+        Self {
+            theme: Theme::default(),
+            language: "es".to_string(),
+            notifications: NotificationSettings::default(),
+        }
+    }
+}
+
+/// Payload de `PUT /users/:id/preferences`: solo se modifican los campos presentes.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UpdatePreferences {
+    pub theme: Option<Theme>,
+    pub language: Option<String>,
+    pub notifications: Option<UpdateNotificationSettings>,
+}
+
+/// Cambios parciales sobre [`NotificationSettings`].
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UpdateNotificationSettings {
+    pub email: Option<bool>,
+    pub digest: Option<DigestFrequency>,
+}
+
+impl UserPreferences {
+    /// Aplica los cambios sobre las preferencias actuales tras validarlos.
+    pub fn merge(self, changes: UpdatePreferences) -> Result<Self, ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+
+        let language = match changes.language {
+            Some(language) => {
+                let language = language.trim().to_ascii_lowercase();
+                if language.len() != 2 || !language.bytes().all(|byte| byte.is_ascii_lowercase()) {
+                    errors.push("language", "Debe ser un código ISO 639-1 de dos letras");
+                }
+                language
+            }
+            None => self.language,
+        };
+
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+
+        let notifications = changes.notifications.unwrap_or_default();
+
+        Ok(Self {
+            theme: changes.theme.unwrap_or(self.theme),
+            language,
+            notifications: NotificationSettings {
+                email: notifications.email.unwrap_or(self.notifications.email),
+                digest: notifications.digest.unwrap_or(self.notifications.digest),
+            },
+        })
+    }
+}
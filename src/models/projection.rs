@@ -0,0 +1,51 @@
+//! Serialización parcial de modelos (sparse fieldsets).
+//!
+//! Un modelo [`Projectable`] sabe serializar cada uno de sus campos por separado, de modo
+//! que [`Projected`] puede emitir solo los que pidió el cliente con `?fields=` sin construir
+//! antes el objeto completo. Sin selección, se serializa el modelo tal cual.
+
+use std::sync::Arc;
+
+use serde::{ser::SerializeMap, Serialize, Serializer};
+
+/// Modelo que admite serialización campo a campo.
+pub trait Projectable: Serialize {
+    /// Campos seleccionables, en el orden en que se emiten.
+    const FIELDS: &'static [&'static str];
+
+    /// Añade a `map` la entrada del campo indicado, que siempre es uno de [`Self::FIELDS`].
+    fn serialize_field<M: SerializeMap>(&self, field: &str, map: &mut M) -> Result<(), M::Error>;
+}
+
+/// Valor que se serializa limitado a los campos seleccionados.
+#[derive(Debug, Clone)]
+pub struct Projected<T> {
+    value: T,
+    fields: Option<Arc<[&'static str]>>,
+}
+
+impl<T> Projected<T> {
+    /// Proyecta `value` sobre `fields`; con `None` se serializa completo.
+    pub fn new(value: T, fields: Option<Arc<[&'static str]>>) -> Self {
+        Self { value, fields }
+    }
+
+    /// Recupera el valor sin proyectar.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
+
+impl<T: Projectable> Serialize for Projected<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let Some(fields) = &self.fields else {
+            return self.value.serialize(serializer);
+        };
+
+        let mut map = serializer.serialize_map(Some(fields.len()))?;
+        for field in fields.iter() {
+            self.value.serialize_field(field, &mut map)?;
+        }
+        map.end()
+    }
+}
@@ -0,0 +1,157 @@
+//! Parámetros y respuesta de las estadísticas agregadas de usuarios (`GET /stats/users`).
+
+use chrono::{Datelike, Days, NaiveDate};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use super::user::ValidationErrors;
+
+/// Días que abarca la ventana si no se indica `from`, contando el último.
+pub const DEFAULT_STATS_DAYS: u64 = 30;
+/// Días máximos de una ventana.
+pub const MAX_STATS_DAYS: u64 = 366;
+/// Número de dominios de correo por defecto.
+pub const DEFAULT_TOP_DOMAINS: u32 = 10;
+/// Número máximo de dominios de correo admitido.
+pub const MAX_TOP_DOMAINS: u32 = 50;
+
+/// Periodo en el que se agrupan las altas.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatsInterval {
+    #[default]
+    Day,
+    /// Semanas ISO, de lunes a domingo.
+    Week,
+}
+
+impl StatsInterval {
+    /// Expresión SQL que lleva la fecha de `column` al primer día de su periodo.
+    pub fn period_sql(self, column: &str) -> String {
+        match self {
+            Self::Day => format!("date({column})"),
+            Self::Week => format!("date({column}, 'weekday 0', '-6 days')"),
+        }
+    }
+
+    /// Primer día del periodo que contiene `date`.
+    pub fn period_start(self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Self::Day => date,
+            Self::Week => date - Days::new(u64::from(date.weekday().num_days_from_monday())),
+        }
+    }
+
+    /// Días entre el comienzo de un periodo y el del siguiente.
+    pub fn days(self) -> Days {
+        match self {
+            Self::Day => Days::new(1),
+            Self::Week => Days::new(7),
+        }
+    }
+}
+
+/// Parámetros de consulta `?from=&to=&interval=&top=` de `GET /stats/users`. Las fechas son
+/// días en UTC y ambas se incluyen; por defecto, los últimos 30 días hasta hoy.
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct UserStatsQuery {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub interval: StatsInterval,
+    pub top: u32,
+}
+
+impl Default for UserStatsQuery {
+    fn default() -> Self {
+        Self {
+            from: None,
+            to: None,
+            interval: StatsInterval::default(),
+            top: DEFAULT_TOP_DOMAINS,
+        }
+    }
+}
+
+/// Ventana validada de unas estadísticas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatsWindow {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub interval: StatsInterval,
+    pub top: u32,
+}
+
+impl UserStatsQuery {
+    /// Completa las fechas que falten tomando `today` como referencia y comprueba que la
+    /// ventana y el número de dominios estén dentro de los márgenes.
+    pub fn validate(self, today: NaiveDate) -> Result<StatsWindow, ValidationErrors> {
+        let mut errors = ValidationErrors::new();
+
+        let to = self.to.unwrap_or(today);
+        let from = self
+            .from
+            .unwrap_or_else(|| to - Days::new(DEFAULT_STATS_DAYS - 1));
+        if from > to {
+            errors.push("from", "Debe ser anterior o igual a `to`");
+        } else if (to - from).num_days() >= MAX_STATS_DAYS as i64 {
+            errors.push("from", "La ventana admite como mucho 366 días");
+        }
+        if self.top == 0 || self.top > MAX_TOP_DOMAINS {
+            errors.push("top", "Debe estar entre 1 y 50");
+        }
+
+        if errors.is_empty() {
+            Ok(StatsWindow {
+                from,
+                to,
+                interval: self.interval,
+                top: self.top,
+            })
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+/// Respuesta de `GET /stats/users`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserStats {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub interval: StatsInterval,
+    pub totals: UserTotals,
+    /// Altas de cada periodo de la ventana, incluidos los que no tienen ninguna.
+    pub signups: Vec<PeriodCount>,
+    /// Peticiones atendidas en cada periodo, según el resumen diario de `daily_user_stats`;
+    /// los días que aún no se han resumido (como hoy) cuentan cero.
+    pub requests: Vec<PeriodCount>,
+    /// Dominios de correo más frecuentes entre las altas de la ventana.
+    pub top_email_domains: Vec<DomainCount>,
+}
+
+/// Totales del inquilino; `signups` cuenta solo las altas de la ventana.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct UserTotals {
+    pub users: i64,
+    pub active: i64,
+    pub suspended: i64,
+    pub deactivated: i64,
+    /// Cuentas fusionadas en otras; siguen contando en `users`.
+    pub merged: i64,
+    pub signups: i64,
+}
+
+/// Recuento de un periodo, identificado por su primer día.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct PeriodCount {
+    pub period: NaiveDate,
+    pub count: i64,
+}
+
+/// Altas con correo de un dominio.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct DomainCount {
+    pub domain: String,
+    pub count: i64,
+}
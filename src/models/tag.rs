@@ -0,0 +1,55 @@
+//! Modelos y validaciones de las etiquetas de usuarios.
+//!
+//! Las etiquetas segmentan usuarios (por ejemplo `beta-tester`) y se identifican en la API
+//! por su nombre, que se normaliza a minúsculas.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use super::user::ValidationErrors;
+
+/// Columnas de `tags` que se proyectan sobre el modelo [`Tag`].
+pub const TAG_COLUMNS: &str = "id, name, created_at";
+
+/// Longitud máxima del nombre de una etiqueta.
+const MAX_TAG_LENGTH: usize = 50;
+
+/// Etiqueta persistida.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Tag {
+    pub id: Uuid,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Nombre de etiqueta validado: minúsculas, dígitos y guiones.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagName(pub String);
+
+impl TryFrom<String> for TagName {
+    type Error = ValidationErrors;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let mut errors = ValidationErrors::new();
+
+        let sanitized_name = value.trim().to_lowercase();
+        if sanitized_name.is_empty() {
+            errors.push("tag", "Debe contener al menos un carácter");
+        } else if sanitized_name.len() > MAX_TAG_LENGTH {
+            errors.push("tag", "Debe tener 50 caracteres o menos");
+        } else if !sanitized_name
+            .bytes()
+            .all(|byte| byte.is_ascii_lowercase() || byte.is_ascii_digit() || byte == b'-')
+        {
+            errors.push("tag", "Solo admite letras, dígitos y guiones");
+        }
+
+        if errors.is_empty() {
+            Ok(Self(sanitized_name))
+        } else {
+            Err(errors)
+        }
+    }
+}
@@ -0,0 +1,92 @@
+//! Modelos y validaciones de los equipos y sus miembros.
+//!
+//! Quien crea un equipo pasa a ser su primer `owner`. Un equipo debe conservar siempre al
+//! menos un `owner`, de modo que nunca quede sin nadie capaz de gestionarlo.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use super::validation::{not_empty, Validate};
+
+/// Columnas de `teams` que se proyectan sobre el modelo [`Team`].
+pub const TEAM_COLUMNS: &str = "id, name, created_at, updated_at";
+
+/// Columnas de `team_members` que se proyectan sobre el modelo [`TeamMember`].
+pub const TEAM_MEMBER_COLUMNS: &str = "team_id, user_id, role, created_at";
+
+/// Longitud máxima del nombre de un equipo, en caracteres.
+const MAX_TEAM_NAME_LENGTH: u64 = 100;
+
+/// Equipo persistido.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Team {
+    pub id: Uuid,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Rol de un usuario dentro de un equipo.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum TeamRole {
+    /// Gestiona el equipo: lo renombra, lo elimina y administra sus miembros.
+    Owner,
+    /// Puede consultar el equipo y abandonarlo.
+    #[default]
+    Member,
+}
+
+/// Pertenencia de un usuario a un equipo.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct TeamMember {
+    pub team_id: Uuid,
+    #[serde(with = "crate::ids::public")]
+    pub user_id: Uuid,
+    pub role: TeamRole,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Payload esperado para crear o renombrar un equipo.
+#[derive(Debug, Deserialize)]
+pub struct TeamPayload {
+    pub name: String,
+}
+
+/// Payload esperado para añadir un miembro a un equipo.
+#[derive(Debug, Deserialize)]
+pub struct AddTeamMember {
+    #[serde(with = "crate::ids::public")]
+    pub user_id: Uuid,
+    #[serde(default)]
+    pub role: TeamRole,
+}
+
+/// Payload esperado para cambiar el rol de un miembro.
+#[derive(Debug, Deserialize)]
+pub struct UpdateTeamMember {
+    pub role: TeamRole,
+}
+
+/// Nombre de equipo validado.
+#[derive(Debug, Clone, PartialEq, Eq, validator::Validate)]
+pub struct TeamName {
+    #[validate(
+        custom(function = not_empty, message = "Debe contener al menos un carácter"),
+        length(max = MAX_TEAM_NAME_LENGTH, message = "Debe tener 100 caracteres o menos")
+    )]
+    pub name: String,
+}
+
+impl Validate for TeamName {
+    type Payload = TeamPayload;
+
+    fn sanitize(value: TeamPayload) -> Self {
+        Self {
+            name: value.name.trim().to_string(),
+        }
+    }
+}
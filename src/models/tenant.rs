@@ -0,0 +1,110 @@
+//! Modelos y validaciones del registro de inquilinos.
+//!
+//! El registro vive siempre en la base principal, aunque los datos de cada inquilino se
+//! guarden en su propio archivo (ver [`crate::tenant::TenantDatabases`]).
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use super::validation::{not_empty, slug, Validate};
+
+/// Columnas de `tenants` que se proyectan sobre el modelo [`Tenant`].
+pub const TENANT_COLUMNS: &str = "id, name, created_at, max_users, max_requests_per_day";
+
+/// Longitud máxima del identificador de un inquilino.
+const MAX_TENANT_ID_LENGTH: u64 = 50;
+
+/// Longitud máxima del nombre visible de un inquilino.
+const MAX_TENANT_NAME_LENGTH: u64 = 100;
+
+/// Inquilino registrado.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Tenant {
+    pub id: String,
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    /// Máximo de usuarios; `null` si no hay límite.
+    pub max_users: Option<i64>,
+    /// Máximo de peticiones por día UTC; `null` si no hay límite.
+    pub max_requests_per_day: Option<i64>,
+}
+
+/// Cuerpo de `POST /tenants`.
+#[derive(Debug, Deserialize)]
+pub struct CreateTenant {
+    pub id: String,
+    pub name: String,
+    #[serde(flatten)]
+    pub quotas: TenantQuotas,
+}
+
+/// Cuerpo de `PUT /tenants/:id/quotas`; un campo ausente o `null` quita el límite.
+#[derive(Debug, Clone, Copy, Default, Deserialize, validator::Validate)]
+pub struct TenantQuotas {
+    #[serde(default)]
+    #[validate(range(min = 1, message = "Debe ser mayor que cero"))]
+    pub max_users: Option<i64>,
+    #[serde(default)]
+    #[validate(range(min = 1, message = "Debe ser mayor que cero"))]
+    pub max_requests_per_day: Option<i64>,
+}
+
+/// Inquilino validado y listo para insertarse.
+#[derive(Debug, Clone, PartialEq, Eq, validator::Validate)]
+pub struct NewTenant {
+    /// El identificador aparece en subdominios y nombres de archivo.
+    #[validate(
+        custom(function = not_empty, message = "Debe contener al menos un carácter"),
+        length(max = MAX_TENANT_ID_LENGTH, message = "Debe tener 50 caracteres o menos"),
+        custom(function = slug, message = "Solo admite letras, dígitos y guiones")
+    )]
+    pub id: String,
+    #[validate(
+        custom(function = not_empty, message = "Debe contener al menos un carácter"),
+        length(max = MAX_TENANT_NAME_LENGTH, message = "Debe tener 100 caracteres o menos")
+    )]
+    pub name: String,
+    #[validate(range(min = 1, message = "Debe ser mayor que cero"))]
+    pub max_users: Option<i64>,
+    #[validate(range(min = 1, message = "Debe ser mayor que cero"))]
+    pub max_requests_per_day: Option<i64>,
+}
+
+/// Consumo de un inquilino frente a sus cuotas, devuelto por `GET /tenants/:id/usage`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TenantUsage {
+    pub tenant_id: String,
+    pub users: QuotaUsage,
+    pub requests_today: QuotaUsage,
+    /// Momento en que se renueva la cuota diaria de peticiones.
+    pub resets_at: DateTime<Utc>,
+}
+
+/// Uso actual de una cuota y su límite, si lo tiene.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuotaUsage {
+    pub used: i64,
+    pub limit: Option<i64>,
+}
+
+impl Validate for TenantQuotas {
+    type Payload = TenantQuotas;
+
+    fn sanitize(value: TenantQuotas) -> Self {
+        value
+    }
+}
+
+impl Validate for NewTenant {
+    type Payload = CreateTenant;
+
+    fn sanitize(value: CreateTenant) -> Self {
+        Self {
+            id: value.id.trim().to_lowercase(),
+            name: value.name.trim().to_string(),
+            max_users: value.quotas.max_users,
+            max_requests_per_day: value.quotas.max_requests_per_day,
+        }
+    }
+}
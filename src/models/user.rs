@@ -4,213 +4,496 @@
 //! los modelos de dominio (`User`, `NewUser`, `UserChanges`) y la lógica de validación
 //! necesaria para asegurar datos consistentes.
 
-use std::fmt;
-
 use chrono::{DateTime, Utc};
-use serde::{Deserialize, Serialize};
+use serde::{ser::SerializeMap, Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
 
+use crate::filter::{FieldKind, FilterField};
+use crate::ids::PublicId;
+
+use super::email::normalize_email;
+use super::projection::Projectable;
+use super::validation::{email_address, in_the_future, invalid, not_empty, Validate};
+
+pub use super::validation::{ValidationError, ValidationErrors};
+
+/// Columnas de `users` que se proyectan sobre el modelo [`User`].
+pub const USER_COLUMNS: &str = "id, name, email, created_at, status, suspended_until, \
+    suspension_reason, avatar_url, tenant_id";
+
 /// Representa a un usuario registrado en la base de datos.
 #[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
 pub struct User {
+    #[serde(with = "crate::ids::public")]
     pub id: Uuid,
     pub name: String,
     pub email: String,
     pub created_at: DateTime<Utc>,
+    pub status: UserStatus,
+    /// Fecha hasta la que la cuenta permanece suspendida, si lo está.
+    pub suspended_until: Option<DateTime<Utc>>,
+    /// Motivo registrado al suspender la cuenta.
+    pub suspension_reason: Option<String>,
+    /// URL estable del avatar, si el usuario subió uno.
+    pub avatar_url: Option<String>,
+    /// Inquilino al que pertenece el usuario.
+    pub tenant_id: String,
 }
 
-/// Payload esperado para crear un usuario a través de la API.
-#[derive(Debug, Deserialize)]
-pub struct CreateUser {
-    pub name: String,
-    pub email: String,
+impl Projectable for User {
+    const FIELDS: &'static [&'static str] = &[
+        "id",
+        "name",
+        "email",
+        "created_at",
+        "status",
+        "suspended_until",
+        "suspension_reason",
+        "avatar_url",
+        "tenant_id",
+    ];
+
+    fn serialize_field<M: SerializeMap>(&self, field: &str, map: &mut M) -> Result<(), M::Error> {
+        match field {
+            "id" => map.serialize_entry(field, &PublicId(self.id)),
+            "name" => map.serialize_entry(field, &self.name),
+            "email" => map.serialize_entry(field, &self.email),
+            "created_at" => map.serialize_entry(field, &self.created_at),
+            "status" => map.serialize_entry(field, &self.status),
+            "suspended_until" => map.serialize_entry(field, &self.suspended_until),
+            "suspension_reason" => map.serialize_entry(field, &self.suspension_reason),
+            "avatar_url" => map.serialize_entry(field, &self.avatar_url),
+            "tenant_id" => map.serialize_entry(field, &self.tenant_id),
+            _ => Ok(()),
+        }
+    }
 }
 
-/// Payload esperado para actualizar parcialmente un usuario.
-#[derive(Debug, Deserialize)]
-pub struct UpdateUser {
-    pub name: Option<String>,
-    pub email: Option<String>,
+/// Estado del ciclo de vida de una cuenta.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum UserStatus {
+    #[default]
+    Active,
+    /// Suspendida hasta `suspended_until`; vuelve a `active` al vencer.
+    Suspended,
+    /// Dada de baja; solo se reactiva de forma explícita.
+    Deactivated,
+    /// Fusionada en otra cuenta (`users.merged_into`). Es terminal: no se reactiva, suspende
+    /// ni modifica, y no aparece en los listados.
+    Merged,
 }
 
-/// Versión validada de un nuevo usuario lista para persistirse.
-#[derive(Debug, Clone)]
-pub struct NewUser {
-    pub name: String,
-    pub email: String,
+impl UserStatus {
+    /// Nombre del estado tal como se guarda y se serializa.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Active => "active",
+            Self::Suspended => "suspended",
+            Self::Deactivated => "deactivated",
+            Self::Merged => "merged",
+        }
+    }
 }
 
-/// Conjunto de cambios válidos sobre un usuario existente.
-#[derive(Debug, Clone)]
-pub struct UserChanges {
-    pub name: Option<String>,
-    pub email: Option<String>,
+/// Filtro por estado aceptado por el listado de usuarios.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[serde(rename_all = "snake_case")]
+#[sqlx(type_name = "TEXT", rename_all = "snake_case")]
+pub enum StatusFilter {
+    #[default]
+    Active,
+    Suspended,
+    Deactivated,
+    All,
 }
 
-/// Error de validación asociado a un campo concreto.
-#[derive(Debug, Clone)]
-pub struct ValidationError {
-    pub field: &'static str,
-    pub message: &'static str,
+impl StatusFilter {
+    /// Estado concreto por el que filtrar, o `None` para no filtrar.
+    pub fn status(self) -> Option<UserStatus> {
+        match self {
+            Self::Active => Some(UserStatus::Active),
+            Self::Suspended => Some(UserStatus::Suspended),
+            Self::Deactivated => Some(UserStatus::Deactivated),
+            Self::All => None,
+        }
+    }
 }
 
-/// Colección de errores de validación para una solicitud.
-#[derive(Debug, Clone, Default)]
-pub struct ValidationErrors {
-    pub errors: Vec<ValidationError>,
+/// Parámetros de consulta del listado de usuarios.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ListUsersQuery {
+    /// Por defecto solo se listan las cuentas activas; `all` las incluye todas salvo las
+    /// fusionadas.
+    #[serde(default)]
+    pub status: StatusFilter,
+    /// Restringe el listado a los usuarios con esta etiqueta.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    /// Expresión de [`crate::filter`] sobre [`USER_FILTER_FIELDS`], combinada con el resto de
+    /// parámetros: para filtrar por un estado distinto de `active` hace falta `status=all`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub filter: Option<String>,
 }
 
-impl ValidationErrors {
-    /// Construye una instancia vacía.
-    pub fn new() -> Self {
-        Self::default()
-    }
-
-    /// Añade un error asociado a un campo determinado.
-    pub fn push(&mut self, field: &'static str, message: &'static str) {
-        self.errors.push(ValidationError { field, message });
-    }
-
-    /// Indica si no se registraron errores.
-    pub fn is_empty(&self) -> bool {
-        self.errors.is_empty()
-    }
+/// Campos que admite `?filter=` en el listado de usuarios.
+pub const USER_FILTER_FIELDS: &[FilterField] = &[
+    FilterField {
+        name: "name",
+        kind: FieldKind::Text,
+    },
+    FilterField {
+        name: "email",
+        kind: FieldKind::Text,
+    },
+    FilterField {
+        name: "status",
+        kind: FieldKind::Keyword(&["active", "suspended", "deactivated"]),
+    },
+    FilterField {
+        name: "created_at",
+        kind: FieldKind::Timestamp,
+    },
+    FilterField {
+        name: "suspended_until",
+        kind: FieldKind::Timestamp,
+    },
+];
+
+/// Número de sugerencias por defecto de `GET /users/suggest`.
+pub const DEFAULT_SUGGESTIONS: u32 = 10;
+/// Número máximo de sugerencias admitido.
+pub const MAX_SUGGESTIONS: u32 = 50;
+
+/// Parámetros de consulta `?q=&limit=` de `GET /users/suggest`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SuggestUsersQuery {
+    /// Prefijo del nombre o del correo.
+    pub q: String,
+    pub limit: u32,
 }
 
-impl fmt::Display for ValidationErrors {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        for (index, error) in self.errors.iter().enumerate() {
-            if index > 0 {
-                writeln!(f)?;
-            }
-            write!(f, "{}: {}", error.field, error.message)?;
+impl Default for SuggestUsersQuery {
+    fn default() -> Self {
+        Self {
+            q: String::new(),
+            limit: DEFAULT_SUGGESTIONS,
         }
-
-        Ok(())
     }
 }
 
-impl std::error::Error for ValidationErrors {}
-
-impl TryFrom<CreateUser> for NewUser {
-    type Error = ValidationErrors;
-
-    fn try_from(value: CreateUser) -> Result<Self, Self::Error> {
+impl SuggestUsersQuery {
+    /// Comprueba que haya un prefijo y que el límite esté dentro de los márgenes; devuelve el
+    /// prefijo sin espacios alrededor.
+    pub fn validate(mut self) -> Result<Self, ValidationErrors> {
         let mut errors = ValidationErrors::new();
 
-        let sanitized_name = value.name.trim().to_string();
-        if sanitized_name.is_empty() {
-            errors.push("name", "Debe contener al menos un carácter");
-        } else if sanitized_name.len() > 100 {
-            errors.push("name", "Debe tener 100 caracteres o menos");
+        self.q = self.q.trim().to_string();
+        if self.q.is_empty() {
+            errors.push("q", "El prefijo no puede estar vacío");
         }
-
-        let sanitized_email = value.email.trim().to_lowercase();
-        if sanitized_email.is_empty() {
-            errors.push("email", "Debe contener al menos un carácter");
-        } else if !is_valid_email(&sanitized_email) {
-            errors.push("email", "Formato de correo inválido");
+        if self.limit == 0 || self.limit > MAX_SUGGESTIONS {
+            errors.push("limit", "Debe estar entre 1 y 50");
         }
 
         if errors.is_empty() {
-            Ok(Self {
-                name: sanitized_name,
-                email: sanitized_email,
-            })
+            Ok(self)
         } else {
             Err(errors)
         }
     }
 }
 
-impl TryFrom<UpdateUser> for UserChanges {
-    type Error = ValidationErrors;
-
-    fn try_from(value: UpdateUser) -> Result<Self, Self::Error> {
-        let mut errors = ValidationErrors::new();
+/// Usuario reducido a lo que necesita un selector: identificador, nombre y correo.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct UserSuggestion {
+    #[serde(with = "crate::ids::public")]
+    pub id: Uuid,
+    pub name: String,
+    pub email: String,
+}
 
-        let sanitized_name = value
-            .name
-            .map(|name| name.trim().to_string())
-            .filter(|name| !name.is_empty());
+/// Número de resultados por defecto de `GET /users/search`.
+pub const DEFAULT_SEARCH_RESULTS: u32 = 20;
+/// Número máximo de resultados de `GET /users/search`.
+pub const MAX_SEARCH_RESULTS: u32 = 100;
+/// Puntuación mínima por defecto de `GET /users/search`.
+pub const DEFAULT_SEARCH_THRESHOLD: f64 = 0.5;
+
+/// Parámetros de consulta `?q=&limit=&threshold=` de `GET /users/search`.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SearchUsersQuery {
+    /// Nombre buscado, con erratas o incompleto.
+    pub q: String,
+    pub limit: u32,
+    /// Puntuación mínima, entre 0 (excluido) y 1.
+    pub threshold: f64,
+}
 
-        if let Some(ref candidate_name) = sanitized_name {
-            if candidate_name.len() > 100 {
-                errors.push("name", "Debe tener 100 caracteres o menos");
-            }
+impl Default for SearchUsersQuery {
+    fn default() -> Self {
+        Self {
+            q: String::new(),
+            limit: DEFAULT_SEARCH_RESULTS,
+            threshold: DEFAULT_SEARCH_THRESHOLD,
         }
+    }
+}
 
-        let sanitized_email = value
-            .email
-            .map(|email| email.trim().to_lowercase())
-            .filter(|email| !email.is_empty());
+impl SearchUsersQuery {
+    /// Comprueba que la consulta tenga letras o dígitos y que el límite y la puntuación
+    /// mínima estén dentro de los márgenes.
+    pub fn validate(self) -> Result<Self, ValidationErrors> {
+        let mut errors = ValidationErrors::new();
 
-        if let Some(ref candidate_email) = sanitized_email {
-            if !is_valid_email(candidate_email) {
-                errors.push("email", "Formato de correo inválido");
-            }
+        if !self.q.chars().any(char::is_alphanumeric) {
+            errors.push("q", "La consulta debe contener letras o dígitos");
         }
-
-        if sanitized_name.is_none() && sanitized_email.is_none() {
-            errors.push(
-                "general",
-                "Debe proporcionar al menos un campo para actualizar",
-            );
+        if self.limit == 0 || self.limit > MAX_SEARCH_RESULTS {
+            errors.push("limit", "Debe estar entre 1 y 100");
+        }
+        if !(self.threshold > 0.0 && self.threshold <= 1.0) {
+            errors.push("threshold", "Debe ser mayor que 0 y como máximo 1");
         }
 
         if errors.is_empty() {
-            Ok(Self {
-                name: sanitized_name,
-                email: sanitized_email,
-            })
+            Ok(self)
         } else {
             Err(errors)
         }
     }
 }
 
-/// Valida que el correo tenga un formato mínimo aceptable.
-fn is_valid_email(email: &str) -> bool {
-    // Verificar que no esté vacío
-    if email.is_empty() {
-        return false;
-    }
+/// Resultado de `GET /users/search`: el usuario y su puntuación.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserMatch {
+    #[serde(flatten)]
+    pub user: User,
+    /// Fracción de los trigramas de la consulta presentes en el nombre, de 0 a 1.
+    pub similarity: f64,
+}
 
-    // Verificar que haya exactamente un @
-    let at_count = email.matches('@').count();
-    if at_count != 1 {
-        return false;
-    }
+/// Respuesta de `GET /users/count`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserCount {
+    pub count: i64,
+}
 
-    let at_position = email.find('@').unwrap();
+/// Payload esperado para crear un usuario a través de la API.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateUser {
+    pub name: String,
+    pub email: String,
+}
 
-    // Verificar que el @ no esté al inicio o al final
-    if at_position == 0 || at_position == email.len() - 1 {
-        return false;
+/// Payload esperado para actualizar parcialmente un usuario.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UpdateUser {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+}
+
+/// Payload esperado para suspender temporalmente a un usuario.
+#[derive(Debug, Deserialize)]
+pub struct SuspendUser {
+    pub reason: String,
+    pub until: DateTime<Utc>,
+}
+
+/// Payload de `POST /users/:id/merge`: el usuario indicado se fusiona en el de la ruta.
+#[derive(Debug, Deserialize)]
+pub struct MergeUsers {
+    #[serde(with = "crate::ids::public")]
+    pub source_id: Uuid,
+}
+
+/// Suspensión validada lista para aplicarse.
+#[derive(Debug, Clone, validator::Validate)]
+pub struct Suspension {
+    #[validate(
+        custom(function = not_empty, message = "Debe contener al menos un carácter"),
+        length(max = 500, message = "Debe tener 500 caracteres o menos")
+    )]
+    pub reason: String,
+    #[validate(custom(function = in_the_future, message = "Debe ser una fecha futura"))]
+    pub until: DateTime<Utc>,
+}
+
+/// Versión validada de un nuevo usuario lista para persistirse.
+#[derive(Debug, Clone, validator::Validate)]
+pub struct NewUser {
+    #[validate(
+        custom(function = not_empty, message = "Debe contener al menos un carácter"),
+        length(max = 100, message = "Debe tener 100 caracteres o menos")
+    )]
+    pub name: String,
+    #[validate(
+        custom(function = not_empty, message = "Debe contener al menos un carácter"),
+        custom(function = email_address, message = "Formato de correo inválido")
+    )]
+    pub email: String,
+}
+
+/// Conjunto de cambios válidos sobre un usuario existente.
+#[derive(Debug, Clone, validator::Validate)]
+#[validate(schema(
+    function = UserChanges::has_changes,
+    message = "Debe proporcionar al menos un campo para actualizar"
+))]
+pub struct UserChanges {
+    #[validate(length(max = 100, message = "Debe tener 100 caracteres o menos"))]
+    pub name: Option<String>,
+    #[validate(custom(function = email_address, message = "Formato de correo inválido"))]
+    pub email: Option<String>,
+}
+
+/// Parámetros de consulta aceptados por la importación masiva de usuarios.
+#[derive(Debug, Default, Deserialize)]
+pub struct ImportOptions {
+    /// Si es `true`, se valida y se simula la importación sin persistir cambios.
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Resultado individual de una fila procesada durante una importación.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportRowStatus {
+    Created,
+    SkippedDuplicate,
+    Invalid,
+}
+
+/// Detalle del procesamiento de una fila concreta de la importación.
+#[derive(Debug, Serialize)]
+pub struct ImportRowReport {
+    /// Número de fila (empezando en 1, sin contar la cabecera CSV).
+    pub row: usize,
+    pub status: ImportRowStatus,
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        with = "crate::ids::public::option"
+    )]
+    pub id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<ValidationError>,
+}
+
+/// Informe completo devuelto tras una importación masiva.
+#[derive(Debug, Default, Serialize)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub created: usize,
+    pub skipped_duplicates: usize,
+    pub invalid: usize,
+    pub rows: Vec<ImportRowReport>,
+}
+
+impl ImportReport {
+    /// Registra el resultado de una fila y actualiza los contadores agregados.
+    pub fn record(&mut self, row: ImportRowReport) {
+        match row.status {
+            ImportRowStatus::Created => self.created += 1,
+            ImportRowStatus::SkippedDuplicate => self.skipped_duplicates += 1,
+            ImportRowStatus::Invalid => self.invalid += 1,
+        }
+        self.rows.push(row);
     }
+}
+
+/// Resultado de un elemento individual dentro de una creación en lote.
+///
+/// `status` replica el código HTTP que habría devuelto la operación aislada
+/// (`201`, `409` o `422`), siguiendo el estilo de las respuestas `207 Multi-Status`.
+#[derive(Debug, Serialize)]
+pub struct BatchItemResult {
+    pub index: usize,
+    pub status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<User>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<ValidationError>,
+}
+
+/// Respuesta agregada de una creación en lote.
+#[derive(Debug, Serialize)]
+pub struct BatchCreateResponse {
+    pub results: Vec<BatchItemResult>,
+}
+
+/// Payload esperado para eliminar varios usuarios a la vez.
+#[derive(Debug, Deserialize)]
+pub struct BatchDeleteUsers {
+    #[serde(with = "crate::ids::public::vec")]
+    pub ids: Vec<Uuid>,
+}
 
-    // Dividir en local y domain
-    let (local_part, domain_part) = email.split_at(at_position);
-    let domain_part = &domain_part[1..]; // Remover el @
+/// Resultado de una eliminación en lote.
+#[derive(Debug, Serialize)]
+pub struct BatchDeleteResponse {
+    #[serde(with = "crate::ids::public::vec")]
+    pub deleted: Vec<Uuid>,
+    #[serde(with = "crate::ids::public::vec")]
+    pub not_found: Vec<Uuid>,
+}
+
+impl Validate for NewUser {
+    type Payload = CreateUser;
 
-    // Verificar que la parte local no esté vacía
-    if local_part.is_empty() {
-        return false;
+    fn sanitize(value: CreateUser) -> Self {
+        Self {
+            name: value.name.trim().to_string(),
+            email: sanitize_email(&value.email),
+        }
     }
+}
 
-    // Verificar que el dominio no esté vacío
-    if domain_part.is_empty() {
-        return false;
+impl Validate for UserChanges {
+    type Payload = UpdateUser;
+
+    /// Los campos vacíos cuentan como no enviados.
+    fn sanitize(value: UpdateUser) -> Self {
+        Self {
+            name: value
+                .name
+                .map(|name| name.trim().to_string())
+                .filter(|name| !name.is_empty()),
+            email: value
+                .email
+                .map(|email| sanitize_email(&email))
+                .filter(|email| !email.is_empty()),
+        }
     }
+}
 
-    // Verificar que el dominio tenga al menos un punto
-    let dot_position = domain_part.rfind('.');
-    match dot_position {
-        Some(dot) => {
-            // El punto no puede estar al inicio o al final del dominio
-            dot > 0 && dot < domain_part.len() - 1
+impl UserChanges {
+    fn has_changes(&self) -> Result<(), validator::ValidationError> {
+        if self.name.is_none() && self.email.is_none() {
+            return Err(invalid());
         }
-        None => false,
+        Ok(())
     }
-}
\ No newline at end of file
+}
+
+impl Validate for Suspension {
+    type Payload = SuspendUser;
+
+    fn sanitize(value: SuspendUser) -> Self {
+        Self {
+            reason: value.reason.trim().to_string(),
+            until: value.until,
+        }
+    }
+}
+
+/// Correo sin espacios, en minúsculas y, si es válido, con el dominio en Punycode.
+fn sanitize_email(email: &str) -> String {
+    let email = email.trim().to_lowercase();
+    normalize_email(&email).unwrap_or(email)
+}
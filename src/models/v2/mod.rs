@@ -0,0 +1,6 @@
+//! Representaciones de la versión 2 de la API.
+//!
+//! Los modelos persistidos no cambian entre versiones; cada versión decide cómo los
+//! serializa. Las representaciones de aquí se construyen a partir de los modelos de v1.
+
+pub mod user;
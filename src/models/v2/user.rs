@@ -0,0 +1,67 @@
+//! Representación de los usuarios en `/v2`.
+//!
+//! Respecto a v1, las claves pasan a `camelCase`, el estado y los datos de la suspensión se
+//! agrupan en `status` y los listados se envuelven en un objeto con `data`.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::models::user::{User, UserStatus};
+
+/// Usuario tal como se devuelve en `/v2/users`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserV2 {
+    #[serde(with = "crate::ids::public")]
+    pub id: Uuid,
+    pub name: String,
+    pub email: String,
+    pub created_at: DateTime<Utc>,
+    pub status: UserStatusV2,
+    pub avatar_url: Option<String>,
+    pub tenant_id: String,
+}
+
+/// Estado de la cuenta junto con los detalles de la suspensión, si la hay.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UserStatusV2 {
+    pub state: UserStatus,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suspended_until: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// Listado de usuarios de `GET /v2/users`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserList {
+    pub data: Vec<UserV2>,
+}
+
+impl From<User> for UserV2 {
+    fn from(user: User) -> Self {
+        Self {
+            id: user.id,
+            name: user.name,
+            email: user.email,
+            created_at: user.created_at,
+            status: UserStatusV2 {
+                state: user.status,
+                suspended_until: user.suspended_until,
+                reason: user.suspension_reason,
+            },
+            avatar_url: user.avatar_url,
+            tenant_id: user.tenant_id,
+        }
+    }
+}
+
+impl From<Vec<User>> for UserList {
+    fn from(users: Vec<User>) -> Self {
+        Self {
+            data: users.into_iter().map(UserV2::from).collect(),
+        }
+    }
+}
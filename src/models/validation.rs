@@ -0,0 +1,202 @@
+//! Errores de validación y trait común para los payloads de entrada.
+//!
+//! Cada tipo validado (`NewUser`, `NewPost`, ...) declara sus reglas con
+//! `#[derive(validator::Validate)]` e implementa [`Validate`] indicando el payload del que se
+//! obtiene y cómo se sanea. El extractor `ValidatedJson` lo deserializa, lo sanea, comprueba
+//! las reglas y responde `422` con el formato común si no es válido. Las reglas propias del
+//! dominio que `validator` no trae están al final de este módulo.
+
+use std::{borrow::Cow, fmt};
+
+use chrono::{DateTime, Utc};
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::email::normalize_email;
+
+/// Tipo que se obtiene saneando un payload recibido por la API y comprobando las reglas que
+/// declara con `#[validate(...)]`.
+pub trait Validate: Sized + validator::Validate {
+    /// Forma en que llega el payload, tal cual se deserializa.
+    type Payload: DeserializeOwned;
+
+    /// Normaliza el payload (espacios, mayúsculas, campos vacíos) sin rechazar nada.
+    fn sanitize(payload: Self::Payload) -> Self;
+
+    /// Sanea el payload y devuelve el resultado si cumple sus reglas, o todos los errores
+    /// encontrados.
+    fn validate(payload: Self::Payload) -> Result<Self, ValidationErrors> {
+        let value = Self::sanitize(payload);
+        validator::Validate::validate(&value)?;
+        Ok(value)
+    }
+}
+
+/// Error de validación asociado a un campo concreto.
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationError {
+    pub field: &'static str,
+    pub message: &'static str,
+    /// Código estable para que los clientes distingan errores concretos sin depender del
+    /// texto del mensaje.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<&'static str>,
+}
+
+/// Colección de errores de validación para una solicitud.
+#[derive(Debug, Clone, Default)]
+pub struct ValidationErrors {
+    pub errors: Vec<ValidationError>,
+}
+
+impl ValidationErrors {
+    /// Construye una instancia vacía.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Añade un error asociado a un campo determinado.
+    pub fn push(&mut self, field: &'static str, message: &'static str) {
+        self.errors.push(ValidationError {
+            field,
+            message,
+            code: None,
+        });
+    }
+
+    /// Añade un error identificado además por un código estable.
+    pub fn push_with_code(
+        &mut self,
+        field: &'static str,
+        code: &'static str,
+        message: &'static str,
+    ) {
+        self.errors.push(ValidationError {
+            field,
+            message,
+            code: Some(code),
+        });
+    }
+
+    /// Indica si no se registraron errores.
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+impl fmt::Display for ValidationErrors {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (index, error) in self.errors.iter().enumerate() {
+            if index > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}: {}", error.field, error.message)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for ValidationErrors {}
+
+/// Campo con el que `validator` registra los errores de `#[validate(schema(...))]`.
+const SCHEMA_FIELD: &str = "__all__";
+
+/// Códigos de `validator` que no se publican: el mensaje ya describe la regla.
+const INTERNAL_CODES: [&str; 3] = ["length", "range", INVALID];
+
+/// Código de los errores de las reglas propias sin código público.
+const INVALID: &str = "invalid";
+
+impl From<validator::ValidationErrors> for ValidationErrors {
+    /// Traduce los errores de `validator` al formato común. Los de todo el payload se asignan
+    /// al campo `general` y, como `validator` no conserva el orden de los campos, se ordenan
+    /// por nombre para que la respuesta sea estable.
+    fn from(errors: validator::ValidationErrors) -> Self {
+        let mut fields = errors.field_errors().into_iter().collect::<Vec<_>>();
+        fields.sort_by(|(left, _), (right, _)| left.cmp(right));
+
+        let mut converted = Self::new();
+        for (field, field_errors) in fields {
+            let field = match field {
+                Cow::Borrowed(SCHEMA_FIELD) => "general",
+                Cow::Borrowed(field) => field,
+                Cow::Owned(_) => "general",
+            };
+            for error in field_errors {
+                let message = match &error.message {
+                    Some(Cow::Borrowed(message)) => message,
+                    _ => "Valor inválido",
+                };
+                let code = match &error.code {
+                    Cow::Borrowed(code) if !INTERNAL_CODES.contains(code) => Some(*code),
+                    _ => None,
+                };
+                converted.errors.push(ValidationError {
+                    field,
+                    message,
+                    code,
+                });
+            }
+        }
+
+        converted
+    }
+}
+
+/// Error de una regla propia; el mensaje lo fija el atributo `#[validate(...)]` que la usa.
+pub fn invalid() -> validator::ValidationError {
+    validator::ValidationError::new(INVALID)
+}
+
+/// Convierte el primer error de una validación propia, con su mensaje y su código.
+pub fn first_error(errors: ValidationErrors) -> validator::ValidationError {
+    let mut converted = invalid();
+    if let Some(error) = errors.errors.into_iter().next() {
+        if let Some(code) = error.code {
+            converted.code = Cow::Borrowed(code);
+        }
+        converted.message = Some(Cow::Borrowed(error.message));
+    }
+    converted
+}
+
+/// Rechaza los textos vacíos, que el saneado ya dejó sin espacios alrededor.
+pub fn not_empty(value: &str) -> Result<(), validator::ValidationError> {
+    if value.is_empty() {
+        Err(invalid())
+    } else {
+        Ok(())
+    }
+}
+
+/// Comprueba que el correo, ya normalizado, sea una dirección válida. El correo vacío lo
+/// rechaza [`not_empty`].
+pub fn email_address(email: &str) -> Result<(), validator::ValidationError> {
+    if email.is_empty() || normalize_email(email).is_some() {
+        Ok(())
+    } else {
+        Err(invalid())
+    }
+}
+
+/// Exige una fecha posterior al momento de la validación.
+pub fn in_the_future(moment: &DateTime<Utc>) -> Result<(), validator::ValidationError> {
+    if *moment > Utc::now() {
+        Ok(())
+    } else {
+        Err(invalid())
+    }
+}
+
+/// Solo admite letras minúsculas, dígitos y guiones, como en los identificadores que
+/// aparecen en URL. El texto vacío lo rechaza [`not_empty`].
+pub fn slug(value: &str) -> Result<(), validator::ValidationError> {
+    if value
+        .bytes()
+        .all(|byte| byte.is_ascii_lowercase() || byte.is_ascii_digit() || byte == b'-')
+    {
+        Ok(())
+    } else {
+        Err(invalid())
+    }
+}
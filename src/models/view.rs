@@ -0,0 +1,143 @@
+//! Modelos y validaciones de las vistas guardadas.
+//!
+//! Una vista guarda con un nombre los parámetros del listado de usuarios (`status`, `tag` y
+//! `filter`) junto con un orden, para repetir la misma consulta sin reconstruirla. Cada vista
+//! pertenece al usuario que la crea y solo él la ve.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+use uuid::Uuid;
+
+use crate::filter::Filter;
+
+use super::tag::TagName;
+use super::user::{ListUsersQuery, StatusFilter, USER_FILTER_FIELDS};
+use super::validation::{first_error, not_empty, Validate};
+
+/// Columnas de `saved_views` que se proyectan sobre el modelo [`SavedView`].
+pub const SAVED_VIEW_COLUMNS: &str = "id, name, status, tag, filter, sort, created_at";
+
+/// Longitud máxima del nombre de una vista, en caracteres.
+const MAX_VIEW_NAME_LENGTH: u64 = 100;
+
+/// Orden de los resultados de una vista. El identificador desempata siempre, como en el
+/// listado.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "TEXT")]
+pub enum ViewSort {
+    /// Por fecha de alta, el orden del listado.
+    #[default]
+    #[serde(rename = "created_at")]
+    #[sqlx(rename = "created_at")]
+    CreatedAt,
+    #[serde(rename = "-created_at")]
+    #[sqlx(rename = "-created_at")]
+    CreatedAtDesc,
+    /// Por nombre, sin distinguir mayúsculas.
+    #[serde(rename = "name")]
+    #[sqlx(rename = "name")]
+    Name,
+    #[serde(rename = "-name")]
+    #[sqlx(rename = "-name")]
+    NameDesc,
+}
+
+impl ViewSort {
+    /// Cláusula `ORDER BY` correspondiente, sin la palabra clave.
+    pub fn order_by(self) -> &'static str {
+        match self {
+            Self::CreatedAt => "created_at, id",
+            Self::CreatedAtDesc => "created_at DESC, id DESC",
+            Self::Name => "name COLLATE NOCASE, id",
+            Self::NameDesc => "name COLLATE NOCASE DESC, id DESC",
+        }
+    }
+}
+
+/// Vista guardada.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SavedView {
+    pub id: Uuid,
+    pub name: String,
+    pub status: StatusFilter,
+    pub tag: Option<String>,
+    pub filter: Option<String>,
+    pub sort: ViewSort,
+    pub created_at: DateTime<Utc>,
+}
+
+impl SavedView {
+    /// Parámetros del listado que reproduce la vista.
+    pub fn list_query(&self) -> ListUsersQuery {
+        ListUsersQuery {
+            status: self.status,
+            tag: self.tag.clone(),
+            filter: self.filter.clone(),
+        }
+    }
+}
+
+/// Payload esperado para guardar una vista.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct CreateView {
+    pub name: String,
+    #[serde(default)]
+    pub status: StatusFilter,
+    #[serde(default)]
+    pub tag: Option<String>,
+    #[serde(default)]
+    pub filter: Option<String>,
+    #[serde(default)]
+    pub sort: ViewSort,
+}
+
+/// Vista validada, lista para guardarse: el nombre sin espacios alrededor, la etiqueta
+/// normalizada y el filtro comprobado.
+#[derive(Debug, Clone, validator::Validate)]
+pub struct NewView {
+    #[validate(
+        custom(function = not_empty, message = "Debe contener al menos un carácter"),
+        length(max = MAX_VIEW_NAME_LENGTH, message = "Debe tener 100 caracteres o menos")
+    )]
+    pub name: String,
+    pub status: StatusFilter,
+    #[validate(custom(function = tag_name))]
+    pub tag: Option<String>,
+    /// Se comprueba con todos los campos; si al ejecutarla el correo no es filtrable porque
+    /// está cifrado, la ejecución responde `422`.
+    #[validate(custom(function = filter_expression))]
+    pub filter: Option<String>,
+    pub sort: ViewSort,
+}
+
+impl Validate for NewView {
+    type Payload = CreateView;
+
+    fn sanitize(payload: CreateView) -> Self {
+        Self {
+            name: payload.name.trim().to_string(),
+            status: payload.status,
+            tag: payload.tag.map(|tag| tag.trim().to_lowercase()),
+            filter: payload
+                .filter
+                .map(|filter| filter.trim().to_string())
+                .filter(|filter| !filter.is_empty()),
+            sort: payload.sort,
+        }
+    }
+}
+
+/// Aplica las reglas de [`TagName`], con su mensaje.
+fn tag_name(tag: &str) -> Result<(), validator::ValidationError> {
+    TagName::try_from(tag.to_string())
+        .map(|_| ())
+        .map_err(first_error)
+}
+
+/// Comprueba que el filtro sea una expresión válida, con el código `filter_*` del error.
+fn filter_expression(filter: &str) -> Result<(), validator::ValidationError> {
+    Filter::parse(filter, USER_FILTER_FIELDS)
+        .map(|_| ())
+        .map_err(first_error)
+}
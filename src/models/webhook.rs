@@ -0,0 +1,173 @@
+//! Modelos y validaciones de las suscripciones de webhooks.
+//!
+//! Una suscripción indica la URL a la que se envían los eventos del ciclo de vida de los
+//! usuarios, qué eventos le interesan y el secreto con el que se firma cada entrega.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{types::Json, FromRow};
+use uuid::Uuid;
+
+use super::validation::{invalid, Validate};
+
+/// Columnas de `webhooks` que se proyectan sobre el modelo [`Webhook`].
+pub const WEBHOOK_COLUMNS: &str = "id, url, secret, events, active, created_at, tenant_id";
+
+/// Eventos a los que se puede suscribir un webhook.
+pub const SUPPORTED_EVENTS: [&str; 4] = [
+    "user.created",
+    "user.updated",
+    "user.merged",
+    "user.deleted",
+];
+
+/// Suscripción de webhook persistida.
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Webhook {
+    pub id: Uuid,
+    pub url: String,
+    /// El secreto solo se muestra al crear la suscripción.
+    #[serde(skip_serializing)]
+    pub secret: String,
+    /// Eventos suscritos; una lista vacía equivale a todos.
+    pub events: Json<Vec<String>>,
+    pub active: bool,
+    pub created_at: DateTime<Utc>,
+    /// Inquilino cuyos eventos recibe la suscripción.
+    pub tenant_id: String,
+}
+
+impl Webhook {
+    /// Indica si la suscripción debe recibir el evento indicado.
+    pub fn accepts(&self, event_name: &str) -> bool {
+        self.active && (self.events.is_empty() || self.events.iter().any(|name| name == event_name))
+    }
+}
+
+/// Respuesta de creación, la única que incluye el secreto de firma.
+#[derive(Debug, Serialize)]
+pub struct CreatedWebhook {
+    #[serde(flatten)]
+    pub webhook: Webhook,
+    pub secret: String,
+}
+
+/// Registro de un intento de entrega de un evento a un webhook.
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct WebhookDelivery {
+    pub id: i64,
+    pub webhook_id: Uuid,
+    pub event_id: Uuid,
+    pub event_type: String,
+    pub attempt: i64,
+    pub status_code: Option<i64>,
+    pub error: Option<String>,
+    pub success: bool,
+    pub attempted_at: DateTime<Utc>,
+}
+
+/// Payload esperado para crear una suscripción.
+#[derive(Debug, Deserialize)]
+pub struct CreateWebhook {
+    pub url: String,
+    #[serde(default)]
+    pub events: Vec<String>,
+    /// Secreto opcional; si no se indica se genera uno aleatorio.
+    pub secret: Option<String>,
+}
+
+/// Payload esperado para actualizar parcialmente una suscripción.
+#[derive(Debug, Deserialize)]
+pub struct UpdateWebhook {
+    pub url: Option<String>,
+    pub events: Option<Vec<String>>,
+    pub active: Option<bool>,
+}
+
+/// Versión validada de una nueva suscripción.
+#[derive(Debug, Clone, validator::Validate)]
+pub struct NewWebhook {
+    #[validate(
+        custom(function = http_url, message = "Debe ser una URL http:// o https://"),
+        length(max = 2048, message = "Debe tener 2048 caracteres o menos")
+    )]
+    pub url: String,
+    #[validate(custom(function = supported_events, message = "Contiene eventos no soportados"))]
+    pub events: Vec<String>,
+    #[validate(length(min = 16, message = "Debe tener al menos 16 caracteres"))]
+    pub secret: Option<String>,
+}
+
+/// Conjunto de cambios válidos sobre una suscripción existente.
+#[derive(Debug, Clone, validator::Validate)]
+#[validate(schema(
+    function = WebhookChanges::has_changes,
+    message = "Debe proporcionar al menos un campo para actualizar"
+))]
+pub struct WebhookChanges {
+    #[validate(
+        custom(function = http_url, message = "Debe ser una URL http:// o https://"),
+        length(max = 2048, message = "Debe tener 2048 caracteres o menos")
+    )]
+    pub url: Option<String>,
+    #[validate(custom(function = supported_events, message = "Contiene eventos no soportados"))]
+    pub events: Option<Vec<String>>,
+    pub active: Option<bool>,
+}
+
+impl Validate for NewWebhook {
+    type Payload = CreateWebhook;
+
+    fn sanitize(value: CreateWebhook) -> Self {
+        Self {
+            url: value.url.trim().to_string(),
+            events: value.events,
+            secret: value
+                .secret
+                .map(|secret| secret.trim().to_string())
+                .filter(|secret| !secret.is_empty()),
+        }
+    }
+}
+
+impl Validate for WebhookChanges {
+    type Payload = UpdateWebhook;
+
+    fn sanitize(value: UpdateWebhook) -> Self {
+        Self {
+            url: value.url.map(|url| url.trim().to_string()),
+            events: value.events,
+            active: value.active,
+        }
+    }
+}
+
+impl WebhookChanges {
+    fn has_changes(&self) -> Result<(), validator::ValidationError> {
+        if self.url.is_none() && self.events.is_none() && self.active.is_none() {
+            return Err(invalid());
+        }
+        Ok(())
+    }
+}
+
+/// Comprueba que la URL sea HTTP(S).
+fn http_url(url: &str) -> Result<(), validator::ValidationError> {
+    if url.starts_with("http://") || url.starts_with("https://") {
+        Ok(())
+    } else {
+        Err(invalid())
+    }
+}
+
+/// Comprueba que todos los eventos indicados estén soportados.
+fn supported_events(events: &[String]) -> Result<(), validator::ValidationError> {
+    if events
+        .iter()
+        .all(|event| SUPPORTED_EVENTS.contains(&event.as_str()))
+    {
+        Ok(())
+    } else {
+        Err(invalid())
+    }
+}
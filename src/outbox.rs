@@ -0,0 +1,229 @@
+//! Outbox transaccional para publicar eventos de dominio de forma fiable.
+//!
+//! Los handlers escriben cada evento en la tabla `outbox` dentro de la misma transacción
+//! que la mutación que lo origina. Tras confirmar, avisan al relay, que publica los eventos
+//! pendientes en el [`EventBus`] en orden y los marca como enviados. Si el proceso cae entre
+//! el commit y la publicación, el relay los recupera al arrancar.
+//!
+//! El bus no garantiza la entrega: un suscriptor rezagado pierde eventos y una caída pierde
+//! los que estaba procesando. Sirve para reacciones que se pueden perder, como invalidar la
+//! caché. Los consumidores que necesitan la entrega al menos una vez (los webhooks, el broker
+//! y el CDC) leen el outbox por su cuenta, guardan su posición en `outbox_offsets` y
+//! deduplican por el identificador del evento.
+//!
+//! Con el cifrado de correos activo, el evento se guarda con el correo del usuario cifrado
+//! igual que en `users.email`, y cada lector del outbox lo descifra con [`decode`] antes de
+//! entregarlo.
+
+use std::{sync::Arc, time::Duration};
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use sqlx::{types::Json as SqlJson, Executor, FromRow, QueryBuilder, Sqlite, SqlitePool};
+use tokio::sync::Notify;
+use tracing::{info, warn};
+
+use crate::{
+    encryption::EmailEncryption,
+    events::{DomainEvent, EventBus, EventEnvelope},
+    ids,
+    jobs::timestamp,
+    models::user::User,
+    state::AppState,
+    trace_context::TraceContext,
+};
+
+/// Número máximo de eventos publicados por iteración del relay.
+const RELAY_BATCH_SIZE: i64 = 100;
+/// Espera máxima entre iteraciones si nadie avisa de eventos nuevos.
+const RELAY_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Señal compartida con la que los handlers despiertan al relay tras confirmar un cambio.
+#[derive(Debug, Clone, Default)]
+pub struct Outbox {
+    notify: Arc<Notify>,
+}
+
+impl Outbox {
+    /// Crea una señal sin avisos pendientes.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Avisa al relay de que hay eventos nuevos confirmados.
+    pub fn wake(&self) {
+        self.notify.notify_one();
+    }
+}
+
+#[derive(Debug, FromRow)]
+struct OutboxRow {
+    seq: i64,
+    payload: String,
+    traceparent: Option<String>,
+    tracestate: Option<String>,
+}
+
+/// Registra un evento en el outbox usando el ejecutor de la transacción en curso. Devuelve
+/// el evento con el correo en claro.
+pub async fn record<'e, E>(
+    executor: E,
+    encryption: &EmailEncryption,
+    event: DomainEvent,
+) -> Result<EventEnvelope, sqlx::Error>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    let envelope = EventEnvelope::new(event);
+    let mut stored = envelope.clone();
+    if let Some(user) = stored.event.user_mut() {
+        user.email = encryption.seal(&user.email);
+    }
+
+    // Los eventos guardados usan siempre la forma canónica de los identificadores, aunque
+    // se registren durante una petición que los muestra con otro formato.
+    let query = ids::canonical(|| {
+        sqlx::query(
+            "INSERT INTO outbox (event_id, event_type, payload, occurred_at, traceparent, \
+             tracestate) VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(envelope.id)
+        .bind(envelope.event.name())
+        .bind(SqlJson(&stored))
+        .bind(envelope.occurred_at)
+        .bind(
+            envelope
+                .trace_context
+                .as_ref()
+                .map(TraceContext::traceparent),
+        )
+        .bind(
+            envelope
+                .trace_context
+                .as_ref()
+                .and_then(|context| context.tracestate.clone()),
+        )
+    });
+    query.execute(executor).await?;
+
+    Ok(envelope)
+}
+
+/// Sustituye por `user` el usuario de los eventos ya registrados sobre él, enviados o no, y
+/// devuelve cuántos se reescribieron. Tras borrar sus datos personales, los eventos pendientes
+/// se entregan ya anonimizados y los enviados dejan de conservar los datos originales.
+pub async fn redact_user<'e, E>(
+    executor: E,
+    encryption: &EmailEncryption,
+    user: &User,
+) -> Result<u64, sqlx::Error>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    let mut redacted = user.clone();
+    redacted.email = encryption.seal(&redacted.email);
+
+    let query = ids::canonical(|| {
+        sqlx::query(
+            "UPDATE outbox SET payload = json_set(payload, '$.user', json(?)) \
+             WHERE json_extract(payload, '$.user.id') = ?",
+        )
+        .bind(SqlJson(&redacted))
+        .bind(ids::to_public(user.id))
+    });
+    let result = query.execute(executor).await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Interpreta el `payload` de una fila del outbox y descifra el correo del usuario. Los
+/// eventos guardados antes de activar el cifrado se leen tal cual.
+pub fn decode(payload: &str, encryption: &EmailEncryption) -> Result<EventEnvelope> {
+    let mut envelope = serde_json::from_str::<EventEnvelope>(payload)?;
+    if let Some(user) = envelope.event.user_mut() {
+        user.email = encryption.open(&user.email)?;
+    }
+
+    Ok(envelope)
+}
+
+/// Publica un lote de eventos pendientes en orden y devuelve cuántos se enviaron.
+pub async fn relay_once(
+    database_pool: &SqlitePool,
+    events: &EventBus,
+    encryption: &EmailEncryption,
+) -> Result<usize> {
+    let rows = sqlx::query_as::<_, OutboxRow>(
+        "SELECT seq, payload, traceparent, tracestate FROM outbox WHERE sent_at IS NULL \
+         ORDER BY seq LIMIT ?",
+    )
+    .bind(RELAY_BATCH_SIZE)
+    .fetch_all(database_pool)
+    .await?;
+
+    for row in &rows {
+        match decode(&row.payload, encryption) {
+            Ok(mut envelope) => {
+                envelope.trace_context = row.traceparent.as_deref().and_then(|traceparent| {
+                    TraceContext::parse(traceparent, row.tracestate.as_deref())
+                });
+                events.publish_envelope(envelope)
+            }
+            // Un evento ilegible no debe bloquear la cola: se marca y se registra.
+            Err(error) => warn!(seq = row.seq, ?error, "Evento del outbox ilegible"),
+        }
+
+        sqlx::query("UPDATE outbox SET sent_at = ? WHERE seq = ?")
+            .bind(timestamp(Utc::now()))
+            .bind(row.seq)
+            .execute(database_pool)
+            .await?;
+    }
+
+    Ok(rows.len())
+}
+
+/// Elimina los eventos enviados antes de `older_than` y devuelve cuántos se borraron.
+///
+/// `consumers` son los lectores que recorren el outbox por su cuenta y guardan su posición
+/// en `outbox_offsets` (los webhooks, el broker, el CDC): no se borra ningún evento que alguno de ellos no
+/// haya leído todavía, por antiguo que sea. Un consumidor sin posición aún lo conserva todo.
+pub async fn purge_sent(
+    database_pool: &SqlitePool,
+    older_than: DateTime<Utc>,
+    consumers: &[&str],
+) -> Result<u64> {
+    let mut query =
+        QueryBuilder::<Sqlite>::new("DELETE FROM outbox WHERE sent_at IS NOT NULL AND sent_at < ");
+    query.push_bind(timestamp(older_than));
+    for consumer in consumers {
+        query
+            .push(" AND seq <= COALESCE((SELECT last_seq FROM outbox_offsets WHERE consumer = ")
+            .push_bind(*consumer)
+            .push("), 0)");
+    }
+    let result = query.build().execute(database_pool).await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Bucle del relay: publica en cuanto un handler avisa o, como mínimo, cada pocos segundos.
+pub async fn run_relay(state: AppState) {
+    info!("Relay del outbox iniciado");
+
+    loop {
+        // Con una base por inquilino, cada una tiene su propio outbox.
+        let mut has_more = false;
+        for database_pool in state.database_pools().await {
+            match relay_once(&database_pool, &state.events, &state.email_encryption).await {
+                Ok(relayed) => has_more |= relayed as i64 == RELAY_BATCH_SIZE,
+                Err(error) => warn!(?error, "Fallo al publicar eventos del outbox"),
+            }
+        }
+        if has_more {
+            continue;
+        }
+
+        let _ = tokio::time::timeout(RELAY_POLL_INTERVAL, state.outbox.notify.notified()).await;
+    }
+}
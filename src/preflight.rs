@@ -0,0 +1,218 @@
+//! Comprobación previa al arranque.
+//!
+//! Antes de abrir el puerto, el servidor revisa la configuración, la conexión con la base de
+//! datos, la versión del esquema y el directorio de archivos estáticos, y escribe un resumen
+//! en el log. Si algo falla se detiene con un mensaje que indica qué corregir, en lugar de
+//! arrancar y fallar después con la primera petición que dependa de ello.
+
+use std::{net::SocketAddr, path::Path};
+
+use anyhow::{bail, Result};
+use sqlx::SqlitePool;
+use tracing::{error, info};
+
+use crate::config::AppConfig;
+use crate::migrations::{self, MigrationState, MIGRATOR};
+
+/// Resultado de una comprobación: el detalle si fue bien o el motivo del fallo.
+#[derive(Debug, Clone)]
+pub struct Check {
+    pub name: &'static str,
+    pub outcome: Result<String, String>,
+}
+
+/// Resultado de todas las comprobaciones previas.
+#[derive(Debug, Clone)]
+pub struct PreflightReport {
+    pub checks: Vec<Check>,
+}
+
+impl PreflightReport {
+    /// Indica si todas las comprobaciones fueron bien.
+    pub fn is_ok(&self) -> bool {
+        self.checks.iter().all(|check| check.outcome.is_ok())
+    }
+
+    /// Escribe en el log una línea por comprobación.
+    pub fn log(&self) {
+        for check in &self.checks {
+            match &check.outcome {
+                Ok(detail) => info!(check = check.name, "Comprobación previa correcta: {detail}"),
+                Err(problem) => {
+                    error!(check = check.name, "Comprobación previa fallida: {problem}")
+                }
+            }
+        }
+    }
+
+    /// Convierte el informe en un error que enumera todos los fallos.
+    pub fn into_result(self) -> Result<()> {
+        let problems = self
+            .checks
+            .iter()
+            .filter_map(|check| {
+                let problem = check.outcome.as_ref().err()?;
+                Some(format!("\n  - {}: {problem}", check.name))
+            })
+            .collect::<String>();
+        if !problems.is_empty() {
+            bail!("La comprobación previa al arranque falló:{problems}");
+        }
+
+        Ok(())
+    }
+}
+
+/// Ejecuta todas las comprobaciones sin detenerse en el primer fallo, para informar de todos
+/// los problemas de una vez.
+pub async fn run(config: &AppConfig, pool: &SqlitePool, public_dir: &Path) -> PreflightReport {
+    PreflightReport {
+        checks: vec![
+            Check {
+                name: "configuración",
+                outcome: check_config(config),
+            },
+            Check {
+                name: "base de datos",
+                outcome: check_database(pool).await,
+            },
+            Check {
+                name: "esquema",
+                outcome: check_schema(pool).await,
+            },
+            Check {
+                name: "archivos estáticos",
+                outcome: check_public_dir(public_dir),
+            },
+        ],
+    }
+}
+
+/// Valores de configuración que el servidor no puede usar.
+fn check_config(config: &AppConfig) -> Result<String, String> {
+    let mut problems = Vec::new();
+
+    let address = format!("{}:{}", config.host, config.port);
+    if address.parse::<SocketAddr>().is_err() {
+        problems.push(format!(
+            "HOST y PORT no forman una dirección válida ({address})"
+        ));
+    }
+    if let Some(admin_port) = config.admin_port {
+        let admin_address = format!("{}:{admin_port}", config.admin_host);
+        if admin_address.parse::<SocketAddr>().is_err() {
+            problems.push(format!(
+                "ADMIN_HOST y ADMIN_PORT no forman una dirección válida ({admin_address})"
+            ));
+        } else if admin_port == config.port {
+            problems.push(format!(
+                "ADMIN_PORT ({admin_port}) debe ser distinto de PORT"
+            ));
+        }
+    }
+    if config.database_max_connections == 0 {
+        problems.push("DATABASE_MAX_CONNECTIONS debe ser al menos 1".to_string());
+    }
+    if config.database_min_connections > config.database_max_connections {
+        problems.push(format!(
+            "DATABASE_MIN_CONNECTIONS ({}) no puede superar DATABASE_MAX_CONNECTIONS ({})",
+            config.database_min_connections, config.database_max_connections
+        ));
+    }
+    if let Some(path) = &config.blocked_email_domains_file {
+        if !path.is_file() {
+            problems.push(format!(
+                "BLOCKED_EMAIL_DOMAINS_FILE apunta a {}, que no existe",
+                path.display()
+            ));
+        }
+    }
+    if let Some(directory) = &config.tenant_database_dir {
+        if directory.exists() && !directory.is_dir() {
+            problems.push(format!(
+                "TENANT_DATABASE_DIR apunta a {}, que no es un directorio",
+                directory.display()
+            ));
+        }
+    }
+
+    if problems.is_empty() {
+        Ok(format!("escuchará en {address}"))
+    } else {
+        Err(problems.join("; "))
+    }
+}
+
+/// La base responde a una consulta.
+async fn check_database(pool: &SqlitePool) -> Result<String, String> {
+    sqlx::query_scalar::<_, String>("SELECT sqlite_version()")
+        .fetch_one(pool)
+        .await
+        .map(|version| format!("SQLite {version}"))
+        .map_err(|error| format!("la base no responde ({error}); revisa DATABASE_URL"))
+}
+
+/// El esquema coincide exactamente con las migraciones de este binario.
+async fn check_schema(pool: &SqlitePool) -> Result<String, String> {
+    let statuses = migrations::status(pool)
+        .await
+        .map_err(|error| format!("no se pudo leer el estado de las migraciones ({error:#})"))?;
+    let applied = migrations::applied_versions(pool)
+        .await
+        .map_err(|error| format!("no se pudo leer el estado de las migraciones ({error:#})"))?;
+
+    let versions_in = |state| {
+        statuses
+            .iter()
+            .filter(|migration| migration.state == state)
+            .map(|migration| migration.version.to_string())
+            .collect::<Vec<_>>()
+    };
+    let pending = versions_in(MigrationState::Pending);
+    if !pending.is_empty() {
+        return Err(format!(
+            "faltan migraciones ({}); aplícalas con --migrate-only",
+            pending.join(", ")
+        ));
+    }
+    let modified = versions_in(MigrationState::Modified);
+    if !modified.is_empty() {
+        return Err(format!(
+            "las migraciones {} cambiaron después de aplicarse; restaura sus archivos",
+            modified.join(", ")
+        ));
+    }
+    let unknown = applied
+        .iter()
+        .filter(|version| {
+            !MIGRATOR
+                .iter()
+                .any(|migration| migration.version == **version)
+        })
+        .map(ToString::to_string)
+        .collect::<Vec<_>>();
+    if !unknown.is_empty() {
+        return Err(format!(
+            "la base tiene migraciones que este binario no conoce ({}); despliega una versión \
+             más reciente o retrocede la base con `migrate to` desde la que las aplicó",
+            unknown.join(", ")
+        ));
+    }
+
+    Ok(match applied.last() {
+        Some(version) => format!("versión {version}"),
+        None => "sin migraciones".to_string(),
+    })
+}
+
+/// El directorio que se sirve bajo `/public` existe.
+fn check_public_dir(directory: &Path) -> Result<String, String> {
+    if directory.is_dir() {
+        Ok(format!("sirviendo {}", directory.display()))
+    } else {
+        Err(format!(
+            "no existe el directorio {}; créalo o arranca el servidor desde la raíz del proyecto",
+            directory.display()
+        ))
+    }
+}
@@ -0,0 +1,141 @@
+//! Perfilado de CPU bajo demanda (feature `pprof`).
+//!
+//! `GET /debug/pprof/profile?seconds=30` muestrea las pilas de todos los hilos durante ese
+//! tiempo con `pprof-rs` y devuelve el perfil en formato pprof (`go tool pprof`, Speedscope,
+//! Grafana Pyroscope) o, con `format=flamegraph`, como SVG. Sirve para investigar picos de
+//! latencia en producción sin desplegar una versión instrumentada.
+//!
+//! Perfilar cuesta CPU y el muestreo es global al proceso, así que solo se admite una captura
+//! a la vez y la ruta exige `Authorization: Bearer <PPROF_TOKEN>`; sin `PPROF_TOKEN` la ruta
+//! no se monta.
+
+use std::{
+    fmt,
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use pprof::protos::Message;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+/// Frecuencia de muestreo en Hz; un valor primo evita sincronizarse con tareas periódicas.
+const SAMPLE_FREQUENCY: i32 = 99;
+/// Bibliotecas cuyas pilas no se muestrean: desenrollarlas desde una señal no es seguro.
+const BLOCKLIST: [&str; 4] = ["libc", "libgcc", "pthread", "vdso"];
+/// Duración máxima de una captura.
+pub const MAX_PROFILE_SECONDS: u64 = 300;
+
+/// Indica si hay una captura en curso.
+static PROFILING: AtomicBool = AtomicBool::new(false);
+
+/// Configuración del perfilado leída desde variables de entorno.
+#[derive(Clone)]
+pub struct ProfilingConfig {
+    /// Token que deben presentar las peticiones (`PPROF_TOKEN`).
+    pub token: String,
+}
+
+impl fmt::Debug for ProfilingConfig {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter
+            .debug_struct("ProfilingConfig")
+            .field("token", &"***")
+            .finish()
+    }
+}
+
+impl ProfilingConfig {
+    /// Devuelve `None` si `PPROF_TOKEN` no está definido.
+    pub fn from_env() -> Option<Self> {
+        std::env::var("PPROF_TOKEN")
+            .ok()
+            .filter(|token| !token.trim().is_empty())
+            .map(|token| Self { token })
+    }
+
+    /// Comprueba el token presentado comparando sus resúmenes, para que el tiempo de la
+    /// comparación no revele cuántos caracteres coinciden.
+    pub fn accepts(&self, token: &str) -> bool {
+        Sha256::digest(token.as_bytes()) == Sha256::digest(self.token.as_bytes())
+    }
+}
+
+/// Formato del perfil devuelto.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProfileFormat {
+    /// Protobuf de pprof.
+    #[default]
+    Pprof,
+    /// Flame graph en SVG.
+    Flamegraph,
+}
+
+impl ProfileFormat {
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Self::Pprof => "application/octet-stream",
+            Self::Flamegraph => "image/svg+xml",
+        }
+    }
+}
+
+/// Marca la captura en curso y la libera al soltarse.
+struct ProfilingSlot;
+
+impl ProfilingSlot {
+    fn acquire() -> Option<Self> {
+        PROFILING
+            .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok()
+            .then_some(Self)
+    }
+}
+
+impl Drop for ProfilingSlot {
+    fn drop(&mut self) {
+        PROFILING.store(false, Ordering::Release);
+    }
+}
+
+/// Muestrea la CPU durante `duration` y devuelve el perfil en `format`, o `None` si ya hay
+/// otra captura en curso.
+pub async fn capture(duration: Duration, format: ProfileFormat) -> Result<Option<Vec<u8>>> {
+    let Some(slot) = ProfilingSlot::acquire() else {
+        return Ok(None);
+    };
+
+    // El perfilador no se puede mover entre hilos, así que toda la captura ocurre en un hilo
+    // bloqueante mientras el runtime sigue atendiendo peticiones.
+    tokio::task::spawn_blocking(move || {
+        let _slot = slot;
+        let guard = pprof::ProfilerGuardBuilder::default()
+            .frequency(SAMPLE_FREQUENCY)
+            .blocklist(&BLOCKLIST)
+            .build()
+            .context("No se pudo iniciar el perfilador")?;
+        std::thread::sleep(duration);
+        let report = guard
+            .report()
+            .build()
+            .context("No se pudo generar el informe del perfil")?;
+
+        let mut body = Vec::new();
+        match format {
+            ProfileFormat::Pprof => report
+                .pprof()
+                .context("No se pudo convertir el perfil a pprof")?
+                .encode(&mut body)
+                .context("No se pudo codificar el perfil")?,
+            ProfileFormat::Flamegraph => report
+                .flamegraph(&mut body)
+                .context("No se pudo generar el flame graph")?,
+        }
+
+        Ok(Some(body))
+    })
+    .await
+    .context("La captura del perfil terminó de forma inesperada")?
+}
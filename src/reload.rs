@@ -0,0 +1,218 @@
+//! Recarga de la configuración en caliente.
+//!
+//! `POST /admin/reload-config` vuelve a leer el archivo de configuración (`CONFIG_FILE`, `.env`
+//! por defecto) y aplica, sin reiniciar, los ajustes que se pueden cambiar con el servidor en
+//! marcha:
+//!
+//! - `RUST_LOG`: el filtro de las trazas.
+//! - `ACCESS_LOG` y `ACCESS_LOG_SKIP_PATHS`: el log de accesos.
+//!
+//! Al arrancar, las variables del entorno del proceso tienen prioridad sobre las del archivo;
+//! al recargar manda el archivo, porque es lo que se edita. Las variables que no aparecen en él
+//! se toman del entorno, que no se modifica: escribir en él con otros hilos en marcha no es
+//! seguro. Si algún valor no es válido no se aplica ninguno.
+//!
+//! Cada ajuste que cambia queda en el log con el target `audit` y su valor anterior y nuevo, y
+//! la respuesta enumera los cambios. El resto de la configuración sigue requiriendo un
+//! reinicio: la base de datos y el puerto se fijan al arrancar, los límites de concurrencia
+//! dimensionan semáforos al montar las rutas y los límites de validación son constantes.
+
+use std::{
+    collections::HashMap,
+    fmt,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use tracing::info;
+use tracing_subscriber::EnvFilter;
+
+use crate::access_log::{AccessLog, AccessLogConfig};
+
+/// Filtro de trazas cuando `RUST_LOG` no está definida.
+pub const DEFAULT_LOG_FILTER: &str = "info";
+
+/// Ajustes que se pueden recargar sin reiniciar.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReloadableSettings {
+    /// Filtro de trazas (`RUST_LOG`).
+    pub log_filter: String,
+    /// Log de accesos (`ACCESS_LOG`, `ACCESS_LOG_SKIP_PATHS`).
+    pub access_log: AccessLogConfig,
+}
+
+impl ReloadableSettings {
+    /// Lee los ajustes del entorno del proceso.
+    pub fn from_env() -> Self {
+        Self::from_lookup(|name| std::env::var(name).ok())
+    }
+
+    /// Lee los ajustes obteniendo cada variable de `lookup`.
+    pub fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        Self {
+            log_filter: lookup("RUST_LOG")
+                .map(|filter| filter.trim().to_string())
+                .filter(|filter| !filter.is_empty())
+                .unwrap_or_else(|| DEFAULT_LOG_FILTER.to_string()),
+            access_log: AccessLogConfig::from_lookup(&lookup),
+        }
+    }
+
+    /// Valor de cada variable, en el formato en que se configura.
+    fn values(&self) -> [(&'static str, String); 3] {
+        [
+            ("RUST_LOG", self.log_filter.clone()),
+            ("ACCESS_LOG", self.access_log.enabled.to_string()),
+            (
+                "ACCESS_LOG_SKIP_PATHS",
+                self.access_log.skip_paths.join(","),
+            ),
+        ]
+    }
+}
+
+/// Ajuste que cambió en una recarga.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SettingChange {
+    /// Variable de entorno del ajuste.
+    pub setting: String,
+    pub old: String,
+    pub new: String,
+}
+
+/// Resultado de una recarga.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ReloadReport {
+    /// Archivo leído, o `None` si no existe y solo se consultó el entorno.
+    pub source: Option<PathBuf>,
+    /// Ajustes que cambiaron; vacío si el archivo no trae novedades.
+    pub changes: Vec<SettingChange>,
+}
+
+/// Aplica un nuevo filtro de trazas al suscriptor.
+type ApplyLogFilter = dyn Fn(&str) -> Result<()> + Send + Sync;
+
+/// Recargador de la configuración; sus copias comparten los ajustes vigentes.
+#[derive(Clone)]
+pub struct ConfigReloader {
+    path: PathBuf,
+    current: Arc<Mutex<ReloadableSettings>>,
+    access_log: AccessLog,
+    apply_log_filter: Option<Arc<ApplyLogFilter>>,
+}
+
+impl fmt::Debug for ConfigReloader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConfigReloader")
+            .field("path", &self.path)
+            .field("current", &self.settings())
+            .finish_non_exhaustive()
+    }
+}
+
+impl ConfigReloader {
+    /// Recargador que lee `path` y parte de los ajustes `initial`, ya aplicados.
+    pub fn new(
+        path: impl Into<PathBuf>,
+        initial: ReloadableSettings,
+        access_log: AccessLog,
+    ) -> Self {
+        Self {
+            path: path.into(),
+            current: Arc::new(Mutex::new(initial)),
+            access_log,
+            apply_log_filter: None,
+        }
+    }
+
+    /// Lee `CONFIG_FILE` y los ajustes vigentes del entorno del proceso.
+    pub fn from_env(access_log: AccessLog) -> Self {
+        let path = std::env::var("CONFIG_FILE")
+            .ok()
+            .filter(|path| !path.trim().is_empty())
+            .unwrap_or_else(|| ".env".to_string());
+        Self::new(path, ReloadableSettings::from_env(), access_log)
+    }
+
+    /// Indica cómo aplicar un nuevo filtro de trazas; sin él, `RUST_LOG` no se recarga.
+    pub fn with_log_filter(
+        mut self,
+        apply: impl Fn(&str) -> Result<()> + Send + Sync + 'static,
+    ) -> Self {
+        self.apply_log_filter = Some(Arc::new(apply));
+        self
+    }
+
+    /// Ajustes vigentes.
+    pub fn settings(&self) -> ReloadableSettings {
+        self.current
+            .lock()
+            .expect("mutex de la configuración envenenado")
+            .clone()
+    }
+
+    /// Vuelve a leer el archivo y aplica los ajustes que cambiaron.
+    pub fn reload(&self) -> Result<ReloadReport> {
+        let (source, file) = read_file(&self.path)?;
+        let mut next = ReloadableSettings::from_lookup(|name| {
+            file.get(name).cloned().or_else(|| std::env::var(name).ok())
+        });
+        EnvFilter::try_new(&next.log_filter)
+            .with_context(|| format!("RUST_LOG no es un filtro válido: {}", next.log_filter))?;
+
+        let mut current = self
+            .current
+            .lock()
+            .expect("mutex de la configuración envenenado");
+        if self.apply_log_filter.is_none() {
+            next.log_filter = current.log_filter.clone();
+        }
+
+        let changes: Vec<SettingChange> = current
+            .values()
+            .into_iter()
+            .zip(next.values())
+            .filter(|((_, old), (_, new))| old != new)
+            .map(|((setting, old), (_, new))| SettingChange {
+                setting: setting.to_string(),
+                old,
+                new,
+            })
+            .collect();
+
+        if next.log_filter != current.log_filter {
+            if let Some(apply) = &self.apply_log_filter {
+                apply(&next.log_filter)?;
+            }
+        }
+        if next.access_log != current.access_log {
+            self.access_log.replace(next.access_log.clone());
+        }
+        for change in &changes {
+            info!(
+                target: "audit",
+                setting = change.setting,
+                old = change.old,
+                new = change.new,
+                "Ajuste de configuración recargado"
+            );
+        }
+        *current = next;
+
+        Ok(ReloadReport { source, changes })
+    }
+}
+
+/// Variables definidas en el archivo, o ninguna si no existe.
+fn read_file(path: &Path) -> Result<(Option<PathBuf>, HashMap<String, String>)> {
+    if !path.exists() {
+        return Ok((None, HashMap::new()));
+    }
+
+    let variables = dotenvy::from_path_iter(path)
+        .and_then(|variables| variables.collect::<Result<HashMap<_, _>, _>>())
+        .with_context(|| format!("No se pudo leer {}", path.display()))?;
+    Ok((Some(path.to_path_buf()), variables))
+}
@@ -0,0 +1,653 @@
+//! Replicación continua del WAL hacia el almacenamiento de objetos.
+//!
+//! Con `REPLICATE_WAL=true`, una tarea en segundo plano sube al almacenamiento configurado
+//! (S3 o MinIO con `STORAGE_BACKEND=s3`) los frames que SQLite añade al WAL de la base
+//! principal, de modo que si se pierde el disco se puede reconstruir la base hasta pocos
+//! segundos antes, o hasta cualquier instante anterior.
+//!
+//! La réplica se organiza en generaciones. Cada una empieza con una instantánea completa
+//! (`replica/<generación>/snapshot.db`) seguida de segmentos numerados
+//! (`replica/<generación>/wal/<n>`) con los frames confirmados desde el anterior; el índice de
+//! generaciones está en `replica/generations.json`. Se empieza una generación nueva al
+//! arrancar y cada vez que se pierde la continuidad del WAL, y solo se conservan las
+//! `REPLICATION_RETAIN_GENERATIONS` más recientes.
+//!
+//! Para no perder frames, el replicador mantiene abierta una transacción de lectura que
+//! impide a SQLite reiniciar el WAL, y es él quien hace los checkpoints: con las escrituras
+//! bloqueadas sube lo que quede, vuelca el WAL a la base y vuelve a fijar la lectura. Cada
+//! frame se valida con las sales y la suma de comprobación del WAL antes de subirlo, y solo se
+//! suben transacciones completas.
+//!
+//! [`restore_to`] reconstruye la base descargando la instantánea de la generación adecuada y
+//! aplicando en orden sus segmentos hasta el instante pedido; la precisión es la del
+//! intervalo de replicación, porque cada segmento lleva la hora en que se subió.
+
+use std::{
+    io::{ErrorKind, SeekFrom},
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{sqlite::SqliteJournalMode, Connection, SqliteConnection};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tracing::{debug, info, warn};
+
+use crate::backup;
+use crate::config::{env_or, parse_flag, AppConfig};
+use crate::database;
+use crate::storage::Storage;
+
+/// Clave del índice de generaciones.
+const GENERATIONS_KEY: &str = "replica/generations.json";
+/// Tamaño de la cabecera del archivo WAL.
+const WAL_HEADER_BYTES: usize = 32;
+/// Tamaño de la cabecera de cada frame del WAL.
+const FRAME_HEADER_BYTES: usize = 24;
+/// Número mágico del WAL; el bit menos significativo indica el orden de bytes de las sumas.
+const WAL_MAGIC: u32 = 0x377f_0682;
+/// Bytes al inicio de cada segmento con la hora en que se subió, en milisegundos.
+const SEGMENT_HEADER_BYTES: usize = 8;
+/// Fallos seguidos tras los que se descarta la generación actual y se empieza otra.
+const MAX_CONSECUTIVE_FAILURES: u32 = 10;
+
+/// Configuración de la replicación leída desde variables de entorno.
+#[derive(Debug, Clone)]
+pub struct ReplicationConfig {
+    /// Espera entre dos subidas de frames.
+    pub interval: Duration,
+    /// Tamaño del WAL a partir del cual el replicador hace un checkpoint.
+    pub checkpoint_bytes: u64,
+    /// Generaciones que se conservan en el almacenamiento.
+    pub retain_generations: usize,
+}
+
+impl ReplicationConfig {
+    /// Lee `REPLICATE_WAL` (obligatoria para activar la replicación),
+    /// `REPLICATION_INTERVAL_MS`, `REPLICATION_CHECKPOINT_BYTES` y
+    /// `REPLICATION_RETAIN_GENERATIONS`.
+    pub fn from_env() -> Option<Self> {
+        if !std::env::var("REPLICATE_WAL").is_ok_and(|value| parse_flag(&value)) {
+            return None;
+        }
+
+        let defaults = Self::default();
+        Some(Self {
+            interval: Duration::from_millis(env_or(
+                "REPLICATION_INTERVAL_MS",
+                defaults.interval.as_millis() as u64,
+            )),
+            checkpoint_bytes: env_or("REPLICATION_CHECKPOINT_BYTES", defaults.checkpoint_bytes),
+            retain_generations: env_or(
+                "REPLICATION_RETAIN_GENERATIONS",
+                defaults.retain_generations,
+            )
+            .max(1),
+        })
+    }
+}
+
+impl Default for ReplicationConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(1),
+            checkpoint_bytes: 4 * 1024 * 1024,
+            retain_generations: 2,
+        }
+    }
+}
+
+/// Generación registrada en el índice.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Generation {
+    pub id: String,
+    pub started_at: DateTime<Utc>,
+}
+
+/// Subcomando de `replica` pedido en la línea de comandos.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplicaCommand {
+    /// Lista las generaciones disponibles.
+    Generations,
+    /// Reconstruye la base hasta el instante indicado, o hasta el último segmento.
+    Restore(Option<DateTime<Utc>>),
+}
+
+impl ReplicaCommand {
+    /// Interpreta los argumentos que siguen a `replica`.
+    pub fn parse(args: &[String]) -> Result<Self> {
+        match args {
+            [command] if command == "generations" => Ok(Self::Generations),
+            [command] if command == "restore" => Ok(Self::Restore(None)),
+            [command, target] if command == "restore" => DateTime::parse_from_rfc3339(target)
+                .map(|target| Self::Restore(Some(target.with_timezone(&Utc))))
+                .with_context(|| format!("Fecha inválida (se espera RFC 3339): {target}")),
+            _ => bail!("Uso: replica generations | replica restore [<fecha RFC 3339>]"),
+        }
+    }
+}
+
+/// Error que indica que el WAL se reinició sin haberse replicado entero, de modo que la
+/// generación actual ya no puede continuar.
+#[derive(Debug)]
+pub struct ContinuityLost;
+
+impl std::fmt::Display for ContinuityLost {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter.write_str("El WAL se reinició sin haberse replicado por completo")
+    }
+}
+
+impl std::error::Error for ContinuityLost {}
+
+/// Punto del WAL hasta el que ya se ha replicado.
+#[derive(Debug, Clone, Copy)]
+struct WalPosition {
+    page_size: u32,
+    salt: [u32; 2],
+    big_endian: bool,
+    /// Desplazamiento en el archivo del siguiente frame por subir.
+    offset: u64,
+    /// Suma de comprobación acumulada hasta `offset`.
+    checksum: [u32; 2],
+}
+
+/// Replicador de una generación en curso.
+pub struct Replicator {
+    wal_path: PathBuf,
+    storage: Arc<dyn Storage>,
+    config: ReplicationConfig,
+    /// Conexión con la transacción de lectura que impide reiniciar el WAL.
+    reader: Box<SqliteConnection>,
+    /// Conexión con la que se bloquean las escrituras durante los checkpoints.
+    writer_lock: SqliteConnection,
+    generation: String,
+    next_segment: u64,
+    /// `None` mientras no existe WAL: el siguiente que aparezca se replica desde el inicio.
+    position: Option<WalPosition>,
+}
+
+impl Replicator {
+    /// Abre las conexiones del replicador sobre la base principal y empieza una generación
+    /// nueva subiendo una instantánea.
+    pub async fn start(
+        config: &AppConfig,
+        storage: Arc<dyn Storage>,
+        replication: ReplicationConfig,
+    ) -> Result<Self> {
+        if config.sqlite_journal_mode != SqliteJournalMode::Wal {
+            bail!("La replicación del WAL requiere SQLITE_JOURNAL_MODE=wal");
+        }
+        if config.database_url.contains(":memory:") {
+            bail!("La replicación del WAL requiere una base en disco");
+        }
+        let options = database::connect_options(config)?;
+        let mut wal_path = options.clone().get_filename().into_owned().into_os_string();
+        wal_path.push("-wal");
+        let wal_path = PathBuf::from(wal_path);
+
+        let mut reader = Box::new(SqliteConnection::connect_with(&options).await?);
+        let mut writer_lock = SqliteConnection::connect_with(&options).await?;
+
+        // Con las escrituras detenidas, la lectura fijada ve exactamente hasta el final del
+        // WAL: la instantánea incluye todo lo anterior y los segmentos siguen desde ahí.
+        execute(&mut writer_lock, "BEGIN IMMEDIATE").await?;
+        let pinned = async {
+            pin(&mut reader).await?;
+            committed_position(&wal_path).await
+        }
+        .await;
+        execute(&mut writer_lock, "COMMIT").await?;
+        let position = pinned?;
+
+        let generation = Generation {
+            id: ulid::Ulid::new().to_string().to_lowercase(),
+            started_at: Utc::now(),
+        };
+        let snapshot_path = std::env::temp_dir().join(format!("replica-{}.db", generation.id));
+        let (reader, _) = backup::snapshot_from(reader, &snapshot_path).await?;
+        let snapshot = tokio::fs::read(&snapshot_path).await;
+        let _ = tokio::fs::remove_file(&snapshot_path).await;
+        storage
+            .put(
+                &snapshot_key(&generation.id),
+                snapshot?,
+                "application/vnd.sqlite3",
+            )
+            .await
+            .context("No se pudo subir la instantánea de la generación")?;
+
+        let mut generations = generations(storage.as_ref()).await?;
+        generations.push(generation.clone());
+        let expired = generations
+            .len()
+            .saturating_sub(replication.retain_generations);
+        let expired = generations.drain(..expired).collect::<Vec<_>>();
+        storage
+            .put(
+                GENERATIONS_KEY,
+                serde_json::to_vec(&generations)?,
+                "application/json",
+            )
+            .await
+            .context("No se pudo actualizar el índice de generaciones")?;
+        for old in expired {
+            delete_generation(storage.as_ref(), &old.id).await?;
+        }
+
+        info!(generation = %generation.id, "Generación de réplica iniciada");
+        Ok(Self {
+            wal_path,
+            storage,
+            config: replication,
+            reader,
+            writer_lock,
+            generation: generation.id,
+            next_segment: 0,
+            position,
+        })
+    }
+
+    /// Identificador de la generación en curso.
+    pub fn generation(&self) -> &str {
+        &self.generation
+    }
+
+    /// Sube un segmento con las transacciones confirmadas desde la última llamada y devuelve
+    /// cuántos bytes de WAL contenía. Falla con [`ContinuityLost`] si el WAL se reinició sin
+    /// haberse replicado entero.
+    pub async fn sync(&mut self) -> Result<u64> {
+        let Some(header) = read_header(&self.wal_path).await? else {
+            return Ok(0);
+        };
+        let position = match self.position {
+            Some(position) if position.salt == header.salt => position,
+            // Tras un checkpoint completo SQLite reinicia el WAL incrementando la primera
+            // sal; la lectura fijada garantiza que lo anterior ya estaba replicado.
+            Some(position) if header.salt[0] == position.salt[0].wrapping_add(1) => header,
+            None => header,
+            Some(_) => return Err(ContinuityLost.into()),
+        };
+        self.position = Some(position);
+
+        let bytes = read_from(&self.wal_path, position.offset).await?;
+        let (length, checksum) = committed_frames(&position, &bytes);
+        if length == 0 {
+            return Ok(0);
+        }
+
+        let mut segment = Vec::with_capacity(SEGMENT_HEADER_BYTES + length);
+        segment.extend_from_slice(&Utc::now().timestamp_millis().to_be_bytes());
+        segment.extend_from_slice(&bytes[..length]);
+        self.storage
+            .put(
+                &segment_key(&self.generation, self.next_segment),
+                segment,
+                "application/octet-stream",
+            )
+            .await
+            .context("No se pudo subir el segmento del WAL")?;
+
+        self.next_segment += 1;
+        self.position = Some(WalPosition {
+            offset: position.offset + length as u64,
+            checksum,
+            ..position
+        });
+        Ok(length as u64)
+    }
+
+    /// Sube lo que quede del WAL y lo vuelca a la base para que SQLite pueda reiniciarlo.
+    /// Las escrituras quedan bloqueadas mientras dura.
+    pub async fn checkpoint(&mut self) -> Result<()> {
+        execute(&mut self.writer_lock, "BEGIN IMMEDIATE").await?;
+        let result = self.checkpoint_locked().await;
+        execute(&mut self.writer_lock, "COMMIT").await?;
+        result
+    }
+
+    async fn checkpoint_locked(&mut self) -> Result<()> {
+        self.sync().await?;
+
+        execute(&mut self.reader, "COMMIT").await?;
+        let checkpoint = sqlx::query_as::<_, (i64, i64, i64)>("PRAGMA wal_checkpoint(PASSIVE)")
+            .fetch_one(&mut *self.reader)
+            .await;
+        pin(&mut self.reader).await?;
+
+        let (_, frames, checkpointed) = checkpoint?;
+        debug!(frames, checkpointed, "Checkpoint de la réplica");
+        Ok(())
+    }
+
+    /// Una iteración de la tarea: sube los frames nuevos y, si el WAL ha crecido lo
+    /// suficiente, hace un checkpoint.
+    pub async fn tick(&mut self) -> Result<()> {
+        self.sync().await?;
+        if self
+            .position
+            .is_some_and(|position| position.offset >= self.config.checkpoint_bytes)
+        {
+            self.checkpoint().await?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Replica el WAL indefinidamente, empezando una generación nueva cuando se pierde la
+/// continuidad o los fallos se repiten.
+pub async fn run(
+    config: Arc<AppConfig>,
+    storage: Arc<dyn Storage>,
+    replication: ReplicationConfig,
+) {
+    loop {
+        let mut replicator =
+            match Replicator::start(&config, storage.clone(), replication.clone()).await {
+                Ok(replicator) => replicator,
+                Err(error) => {
+                    warn!(?error, "No se pudo iniciar la replicación del WAL");
+                    tokio::time::sleep(replication.interval.max(Duration::from_secs(5))).await;
+                    continue;
+                }
+            };
+
+        let mut failures = 0;
+        loop {
+            tokio::time::sleep(replication.interval).await;
+            let Err(error) = replicator.tick().await else {
+                failures = 0;
+                continue;
+            };
+
+            failures += 1;
+            warn!(
+                ?error,
+                generation = replicator.generation(),
+                failures,
+                "Fallo al replicar el WAL"
+            );
+            if error.is::<ContinuityLost>() || failures >= MAX_CONSECUTIVE_FAILURES {
+                info!("Se empezará una generación de réplica nueva");
+                break;
+            }
+        }
+    }
+}
+
+/// Generaciones del índice, de la más antigua a la más reciente.
+pub async fn generations(storage: &dyn Storage) -> Result<Vec<Generation>> {
+    match storage.get(GENERATIONS_KEY).await? {
+        Some(bytes) => {
+            serde_json::from_slice(&bytes).context("El índice de generaciones está dañado")
+        }
+        None => Ok(Vec::new()),
+    }
+}
+
+/// Punto hasta el que se reconstruyó la base.
+#[derive(Debug, Clone)]
+pub struct RestorePoint {
+    pub generation: String,
+    /// Hora del último segmento aplicado, o del inicio de la generación si no hay ninguno.
+    pub restored_at: DateTime<Utc>,
+    pub segments: u64,
+}
+
+/// Reconstruye en `path` la base tal como estaba en `target` (o en el último segmento
+/// subido) a partir de la réplica. El archivo resultante se restaura con
+/// [`backup::restore`].
+pub async fn restore_to(
+    storage: &dyn Storage,
+    target: Option<DateTime<Utc>>,
+    path: &Path,
+) -> Result<RestorePoint> {
+    if path.exists() {
+        bail!("Ya existe un archivo en {}", path.display());
+    }
+
+    let generations = generations(storage).await?;
+    let Some(generation) = generations
+        .iter()
+        .rev()
+        .find(|generation| target.is_none_or(|target| generation.started_at <= target))
+    else {
+        bail!("No hay ninguna generación de réplica anterior al instante pedido");
+    };
+
+    let mut database = storage
+        .get(&snapshot_key(&generation.id))
+        .await?
+        .with_context(|| format!("Falta la instantánea de la generación {}", generation.id))?;
+    let page_size = match database.get(16..18) {
+        Some([1, 0]) => 65_536,
+        Some(bytes) => usize::from(u16::from_be_bytes([bytes[0], bytes[1]])),
+        None => bail!(
+            "La instantánea de la generación {} está dañada",
+            generation.id
+        ),
+    };
+
+    let mut point = RestorePoint {
+        generation: generation.id.clone(),
+        restored_at: generation.started_at,
+        segments: 0,
+    };
+    while let Some(segment) = storage
+        .get(&segment_key(&generation.id, point.segments))
+        .await?
+    {
+        let Some((timestamp, frames)) = segment.split_first_chunk::<SEGMENT_HEADER_BYTES>() else {
+            bail!("El segmento {} está dañado", point.segments);
+        };
+        let Some(uploaded_at) = Utc
+            .timestamp_millis_opt(i64::from_be_bytes(*timestamp))
+            .single()
+        else {
+            bail!("El segmento {} está dañado", point.segments);
+        };
+        if target.is_some_and(|target| uploaded_at > target) {
+            break;
+        }
+
+        apply_frames(&mut database, page_size, frames)
+            .with_context(|| format!("El segmento {} está dañado", point.segments))?;
+        point.restored_at = uploaded_at;
+        point.segments += 1;
+    }
+
+    // Las páginas que vienen del WAL marcan la base en modo WAL; se deja con diario clásico
+    // para que el archivo sea autónomo, como las copias de `backup`.
+    database[18] = 1;
+    database[19] = 1;
+    if let Some(directory) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        tokio::fs::create_dir_all(directory).await?;
+    }
+    tokio::fs::write(path, database)
+        .await
+        .with_context(|| format!("No se pudo escribir {}", path.display()))?;
+
+    Ok(point)
+}
+
+/// Escribe las páginas de `frames` sobre `database` y ajusta su tamaño en cada commit.
+fn apply_frames(database: &mut Vec<u8>, page_size: usize, frames: &[u8]) -> Result<()> {
+    let frame_size = FRAME_HEADER_BYTES + page_size;
+    if !frames.len().is_multiple_of(frame_size) {
+        bail!("los frames no coinciden con el tamaño de página {page_size}");
+    }
+
+    for frame in frames.chunks_exact(frame_size) {
+        let page_number = be_u32(frame, 0) as usize;
+        if page_number == 0 {
+            bail!("frame sin número de página");
+        }
+        let start = (page_number - 1) * page_size;
+        if database.len() < start + page_size {
+            database.resize(start + page_size, 0);
+        }
+        database[start..start + page_size].copy_from_slice(&frame[FRAME_HEADER_BYTES..]);
+
+        let pages_after_commit = be_u32(frame, 4) as usize;
+        if pages_after_commit != 0 {
+            database.resize(pages_after_commit * page_size, 0);
+        }
+    }
+
+    Ok(())
+}
+
+/// Borra la instantánea y los segmentos de una generación.
+async fn delete_generation(storage: &dyn Storage, generation: &str) -> Result<()> {
+    storage.delete(&snapshot_key(generation)).await?;
+    let mut index = 0;
+    while storage
+        .get(&segment_key(generation, index))
+        .await?
+        .is_some()
+    {
+        storage.delete(&segment_key(generation, index)).await?;
+        index += 1;
+    }
+
+    info!(generation, "Generación de réplica eliminada");
+    Ok(())
+}
+
+fn snapshot_key(generation: &str) -> String {
+    format!("replica/{generation}/snapshot.db")
+}
+
+fn segment_key(generation: &str, index: u64) -> String {
+    format!("replica/{generation}/wal/{index:010}")
+}
+
+async fn execute(connection: &mut SqliteConnection, sql: &str) -> Result<()> {
+    sqlx::query(sql).execute(connection).await?;
+    Ok(())
+}
+
+/// Abre en `reader` una transacción de lectura que se mantiene hasta el siguiente `COMMIT`.
+async fn pin(reader: &mut SqliteConnection) -> Result<()> {
+    execute(reader, "BEGIN").await?;
+    sqlx::query("SELECT COUNT(*) FROM sqlite_master")
+        .fetch_one(&mut *reader)
+        .await?;
+    Ok(())
+}
+
+/// Posición tras la última transacción confirmada del WAL, o `None` si no hay WAL.
+async fn committed_position(wal_path: &Path) -> Result<Option<WalPosition>> {
+    let bytes = read_from(wal_path, 0).await?;
+    let Some(header) = parse_header(&bytes) else {
+        return Ok(None);
+    };
+    let (length, checksum) = committed_frames(&header, &bytes[WAL_HEADER_BYTES..]);
+
+    Ok(Some(WalPosition {
+        offset: header.offset + length as u64,
+        checksum,
+        ..header
+    }))
+}
+
+async fn read_header(wal_path: &Path) -> Result<Option<WalPosition>> {
+    let mut bytes = [0; WAL_HEADER_BYTES];
+    let mut file = match tokio::fs::File::open(wal_path).await {
+        Ok(file) => file,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(error) => return Err(error.into()),
+    };
+    match file.read_exact(&mut bytes).await {
+        Ok(_) => Ok(parse_header(&bytes)),
+        Err(error) if error.kind() == ErrorKind::UnexpectedEof => Ok(None),
+        Err(error) => Err(error.into()),
+    }
+}
+
+async fn read_from(wal_path: &Path, offset: u64) -> Result<Vec<u8>> {
+    let mut file = match tokio::fs::File::open(wal_path).await {
+        Ok(file) => file,
+        Err(error) if error.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(error) => return Err(error.into()),
+    };
+    file.seek(SeekFrom::Start(offset)).await?;
+    let mut bytes = Vec::new();
+    file.read_to_end(&mut bytes).await?;
+    Ok(bytes)
+}
+
+/// Interpreta la cabecera del WAL; `None` si no es válida.
+fn parse_header(bytes: &[u8]) -> Option<WalPosition> {
+    let header = bytes.get(..WAL_HEADER_BYTES)?;
+    let magic = be_u32(header, 0);
+    if magic & !1 != WAL_MAGIC {
+        return None;
+    }
+    let big_endian = magic & 1 == 1;
+    let checksum = wal_checksum(big_endian, [0, 0], &header[..24]);
+    if checksum != [be_u32(header, 24), be_u32(header, 28)] {
+        return None;
+    }
+
+    Some(WalPosition {
+        page_size: be_u32(header, 8),
+        salt: [be_u32(header, 16), be_u32(header, 20)],
+        big_endian,
+        offset: WAL_HEADER_BYTES as u64,
+        checksum,
+    })
+}
+
+/// Recorre los frames válidos de `bytes`, leídos desde `position.offset`, y devuelve cuántos
+/// bytes ocupan hasta el último commit junto con la suma de comprobación en ese punto.
+fn committed_frames(position: &WalPosition, bytes: &[u8]) -> (usize, [u32; 2]) {
+    let frame_size = FRAME_HEADER_BYTES + position.page_size as usize;
+    let mut checksum = position.checksum;
+    let mut committed = (0, checksum);
+
+    for (index, frame) in bytes.chunks_exact(frame_size).enumerate() {
+        if [be_u32(frame, 8), be_u32(frame, 12)] != position.salt {
+            break;
+        }
+        checksum = wal_checksum(position.big_endian, checksum, &frame[..8]);
+        checksum = wal_checksum(position.big_endian, checksum, &frame[FRAME_HEADER_BYTES..]);
+        if checksum != [be_u32(frame, 16), be_u32(frame, 20)] {
+            break;
+        }
+        if be_u32(frame, 4) != 0 {
+            committed = ((index + 1) * frame_size, checksum);
+        }
+    }
+
+    committed
+}
+
+/// Suma de comprobación acumulativa del WAL de SQLite.
+fn wal_checksum(big_endian: bool, [mut first, mut second]: [u32; 2], data: &[u8]) -> [u32; 2] {
+    let word = |bytes: &[u8]| {
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        if big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        }
+    };
+
+    for pair in data.chunks_exact(8) {
+        first = first.wrapping_add(word(&pair[..4])).wrapping_add(second);
+        second = second.wrapping_add(word(&pair[4..])).wrapping_add(first);
+    }
+    [first, second]
+}
+
+fn be_u32(bytes: &[u8], at: usize) -> u32 {
+    u32::from_be_bytes([bytes[at], bytes[at + 1], bytes[at + 2], bytes[at + 3]])
+}
@@ -0,0 +1,121 @@
+//! Composición de la API versionada.
+//!
+//! Cada versión se monta bajo su prefijo (`/v1`, `/v2`). Las rutas sin prefijo siguen
+//! disponibles para los clientes existentes y, por defecto, sirven v1; la cabecera
+//! `X-Api-Version` permite elegir otra versión sin cambiar la ruta. Un cambio
+//! incompatible se introduce en una versión nueva dejando intactas las anteriores.
+
+use axum::{
+    extract::Request,
+    http::{uri::PathAndQuery, Uri},
+    middleware, Router,
+};
+
+use super::{
+    export_download_routes, export_routes, post_routes, stats_routes, tag_routes, team_routes,
+    tenant_routes, user_routes, user_routes_v2, view_routes, webhook_routes,
+};
+use crate::handlers::error::AppError;
+use crate::maintenance;
+use crate::state::AppState;
+use crate::tenant;
+
+/// Cabecera con la versión de la API solicitada para las rutas sin prefijo.
+pub const API_VERSION_HEADER: &str = "x-api-version";
+
+/// Primer segmento de las rutas versionadas; el resto (salud, métricas, estáticos) no
+/// depende de la versión.
+const VERSIONED_RESOURCES: &[&str] = &[
+    "users",
+    "posts",
+    "tags",
+    "teams",
+    "tenants",
+    "views",
+    "stats",
+    "exports",
+    "webhooks",
+];
+
+/// Devuelve la API completa: `/v1`, `/v2` y las rutas sin prefijo, equivalentes a v1.
+///
+/// Durante el modo de mantenimiento, las rutas de la API solo admiten lecturas.
+pub fn api_routes(state: &AppState) -> Router<AppState> {
+    let v1 = versioned_routes(state, user_routes());
+
+    Router::new()
+        .nest("/v1", v1.clone())
+        .nest("/v2", versioned_routes(state, user_routes_v2()))
+        .merge(v1)
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            maintenance::reject_writes,
+        ))
+}
+
+/// Monta los recursos de una versión a partir de su router de usuarios.
+fn versioned_routes(state: &AppState, users: Router<AppState>) -> Router<AppState> {
+    // Las rutas que leen o escriben usuarios, o reciben sus eventos, operan siempre dentro
+    // de un inquilino.
+    let tenant_scoped_routes = Router::new()
+        .merge(users)
+        .merge(post_routes())
+        .merge(tag_routes())
+        .merge(team_routes())
+        .merge(view_routes())
+        .merge(stats_routes())
+        .merge(export_routes())
+        .merge(webhook_routes())
+        .route_layer(middleware::from_fn_with_state(
+            state.clone(),
+            tenant::resolve_tenant,
+        ));
+
+    Router::new()
+        .merge(tenant_scoped_routes)
+        .merge(tenant_routes())
+        .merge(export_download_routes())
+}
+
+/// Reescribe las rutas sin prefijo hacia la versión indicada en `X-Api-Version` (`1`,
+/// `2`, `v1` o `v2`).
+///
+/// Debe aplicarse alrededor del router, antes del enrutado. Las rutas que ya llevan
+/// prefijo se respetan aunque la cabecera indique otra versión.
+pub async fn select_api_version(mut request: Request) -> Result<Request, AppError> {
+    let Some(requested) = request.headers().get(API_VERSION_HEADER) else {
+        return Ok(request);
+    };
+    let version = match requested.to_str().map(str::trim) {
+        Ok("1" | "v1") => "v1",
+        Ok("2" | "v2") => "v2",
+        _ => return Err(AppError::bad_request("Versión de API no soportada")),
+    };
+
+    let resource = request
+        .uri()
+        .path()
+        .trim_start_matches('/')
+        .split('/')
+        .next()
+        .unwrap_or_default();
+    if !VERSIONED_RESOURCES.contains(&resource) {
+        return Ok(request);
+    }
+
+    let path_and_query = request
+        .uri()
+        .path_and_query()
+        .map(PathAndQuery::as_str)
+        .unwrap_or("/");
+    let mut parts = request.uri().clone().into_parts();
+    parts.path_and_query = Some(
+        format!("/{version}{path_and_query}")
+            .parse()
+            .map_err(|error: axum::http::uri::InvalidUri| AppError::internal(error.into()))?,
+    );
+    *request.uri_mut() =
+        Uri::from_parts(parts).map_err(|error| AppError::internal(error.into()))?;
+
+    Ok(request)
+}
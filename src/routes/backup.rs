@@ -0,0 +1,11 @@
+//! Ruta de administración de las copias de seguridad.
+
+use axum::{routing::post, Router};
+
+use crate::handlers::backup::create_backup;
+use crate::state::AppState;
+
+/// Devuelve el router con `POST /admin/backup`.
+pub fn backup_routes() -> Router<AppState> {
+    Router::new().route("/admin/backup", post(create_backup))
+}
@@ -0,0 +1,94 @@
+//! Rutas de depuración (feature `pprof`).
+//!
+//! Expone `GET /debug/pprof/profile`, protegida con `PPROF_TOKEN`; ver [`crate::profiling`].
+
+use std::{sync::Arc, time::Duration};
+
+use axum::{
+    extract::{Query, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use serde::Deserialize;
+
+use crate::handlers::error::AppError;
+use crate::profiling::{self, ProfileFormat, ProfilingConfig, MAX_PROFILE_SECONDS};
+use crate::state::AppState;
+
+/// Parámetros de `GET /debug/pprof/profile`.
+#[derive(Debug, Deserialize)]
+struct ProfileQuery {
+    /// Duración de la captura en segundos (30 por defecto).
+    #[serde(default = "default_seconds")]
+    seconds: u64,
+    #[serde(default)]
+    format: ProfileFormat,
+}
+
+fn default_seconds() -> u64 {
+    30
+}
+
+/// Muestrea la CPU durante `seconds` y devuelve el perfil como archivo descargable.
+async fn cpu_profile(
+    State(config): State<Arc<ProfilingConfig>>,
+    headers: HeaderMap,
+    Query(query): Query<ProfileQuery>,
+) -> Result<Response, AppError> {
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "));
+    if !token.is_some_and(|token| config.accepts(token)) {
+        return Ok(message(
+            StatusCode::UNAUTHORIZED,
+            "Se requiere el token de perfilado en la cabecera Authorization",
+        ));
+    }
+    if !(1..=MAX_PROFILE_SECONDS).contains(&query.seconds) {
+        return Err(AppError::bad_request("seconds debe estar entre 1 y 300"));
+    }
+
+    let Some(profile) = profiling::capture(Duration::from_secs(query.seconds), query.format)
+        .await
+        .map_err(AppError::internal)?
+    else {
+        return Ok(message(
+            StatusCode::CONFLICT,
+            "Ya hay una captura de perfil en curso",
+        ));
+    };
+
+    let filename = match query.format {
+        ProfileFormat::Pprof => "cpu.pb",
+        ProfileFormat::Flamegraph => "cpu.svg",
+    };
+    Ok((
+        [
+            (
+                header::CONTENT_TYPE,
+                query.format.content_type().to_string(),
+            ),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{filename}\""),
+            ),
+        ],
+        profile,
+    )
+        .into_response())
+}
+
+/// Respuesta de error con el formato común (`message`).
+fn message(status: StatusCode, message: &'static str) -> Response {
+    (status, Json(serde_json::json!({ "message": message }))).into_response()
+}
+
+/// Devuelve el router con `GET /debug/pprof/profile`.
+pub fn debug_routes(config: ProfilingConfig) -> Router<AppState> {
+    Router::new()
+        .route("/debug/pprof/profile", get(cpu_profile))
+        .with_state(Arc::new(config))
+}
@@ -0,0 +1,24 @@
+//! Rutas de administración de los dominios de correo bloqueados.
+
+use axum::{
+    routing::{delete, get},
+    Router,
+};
+
+use crate::handlers::email_domain::{
+    block_email_domain, list_blocked_email_domains, unblock_email_domain,
+};
+use crate::state::AppState;
+
+/// Devuelve el router con el listado, alta y baja de `/admin/blocked-email-domains`.
+pub fn email_domain_routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/admin/blocked-email-domains",
+            get(list_blocked_email_domains).post(block_email_domain),
+        )
+        .route(
+            "/admin/blocked-email-domains/:domain",
+            delete(unblock_email_domain),
+        )
+}
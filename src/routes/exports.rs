@@ -0,0 +1,25 @@
+//! Rutas HTTP de las exportaciones asíncronas de usuarios.
+//!
+//! `/exports` y `/exports/:id` operan dentro del inquilino; la descarga en
+//! `/exports/:id/download` se autoriza con la firma del enlace y no lo necesita.
+
+use axum::{
+    routing::{get, post},
+    Router,
+};
+
+use crate::handlers::export::{create_export, download_export, get_export};
+use crate::state::AppState;
+
+/// Devuelve un router con la creación y la consulta de exportaciones, que requieren
+/// inquilino.
+pub fn export_routes() -> Router<AppState> {
+    Router::new()
+        .route("/exports", post(create_export))
+        .route("/exports/:id", get(get_export))
+}
+
+/// Devuelve un router con la descarga de exportaciones mediante enlace firmado.
+pub fn export_download_routes() -> Router<AppState> {
+    Router::new().route("/exports/:id/download", get(download_export))
+}
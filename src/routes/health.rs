@@ -1,16 +1,215 @@
 //! Rutas de salud del servicio.
 //!
-//! Exponen un endpoint simple que permite verificar que la API está viva.
+//! `/health` solo indica que la API está viva. `/health/ready` comprueba además cada
+//! dependencia por separado, para que el orquestador decida si enviarle tráfico:
+//!
+//! - `migrations`: versión del esquema y migraciones pendientes o modificadas.
+//! - `database`: latencia de una consulta trivial a la base principal.
+//! - `job_queue`: trabajos pendientes y espera del vencido más antiguo; si supera
+//!   `JOB_QUEUE_MAX_AGE_SECS`, el worker no da abasto o está parado.
+//! - `cache`: latencia de un `PING` al almacén de la caché.
+//!
+//! Cada comprobación tiene su `status` (`up`, `degraded`, `down` o `disabled`) y dispone de
+//! [`CHECK_TIMEOUT`]. Si la base o las migraciones están `down` se responde `503`; los
+//! problemas de la cola o de la caché solo dejan el servicio `degraded` y se responde `200`,
+//! porque las peticiones se siguen atendiendo (sin caché, leyendo de la base).
+
+use std::{
+    future::Future,
+    time::{Duration, Instant},
+};
 
-use axum::{routing::get, Router};
+use anyhow::{anyhow, Result};
+use axum::{extract::State, http::StatusCode, routing::get, Json, Router};
+use serde::Serialize;
+use serde_json::{json, Value};
 use sqlx::SqlitePool;
 
+use crate::{
+    cache::Cache,
+    jobs,
+    migrations::{self, MigrationState},
+    state::AppState,
+};
+
+/// Tiempo máximo de cada comprobación de `/health/ready`.
+pub const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Resultado de una comprobación, de mejor a peor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+enum CheckStatus {
+    /// La dependencia no está configurada.
+    Disabled,
+    Up,
+    Degraded,
+    Down,
+}
+
+/// Comprobación con su estado y los datos que la justifican.
+#[derive(Debug, Serialize)]
+struct Check {
+    status: CheckStatus,
+    #[serde(flatten)]
+    details: Value,
+}
+
+impl Check {
+    fn new(status: CheckStatus, details: Value) -> Self {
+        Self { status, details }
+    }
+
+    fn down(error: anyhow::Error) -> Self {
+        Self::new(CheckStatus::Down, json!({ "error": format!("{error:#}") }))
+    }
+}
+
 /// Responde con `OK` indicando que la API está operativa.
 async fn health_check() -> &'static str {
     "OK"
 }
 
+/// Comprueba las dependencias y responde `503` si alguna imprescindible falla.
+async fn readiness(State(state): State<AppState>) -> (StatusCode, Json<Value>) {
+    let (migrations, database, job_queue, cache) = tokio::join!(
+        check_migrations(&state.database_pool),
+        check_database(&state.database_pool),
+        check_job_queue(&state),
+        check_cache(&state.cache),
+    );
+
+    let critical = [&migrations, &database]
+        .iter()
+        .any(|check| check.status == CheckStatus::Down);
+    let worst = [&migrations, &database, &job_queue, &cache]
+        .iter()
+        .map(|check| check.status)
+        .max()
+        .unwrap_or(CheckStatus::Up);
+    let (status_code, status) = match worst {
+        _ if critical => (StatusCode::SERVICE_UNAVAILABLE, CheckStatus::Down),
+        CheckStatus::Disabled | CheckStatus::Up => (StatusCode::OK, CheckStatus::Up),
+        CheckStatus::Degraded | CheckStatus::Down => (StatusCode::OK, CheckStatus::Degraded),
+    };
+
+    (
+        status_code,
+        Json(json!({
+            "status": status,
+            "checks": {
+                "migrations": migrations,
+                "database": database,
+                "job_queue": job_queue,
+                "cache": cache,
+            },
+        })),
+    )
+}
+
+/// Ejecuta `check` con el límite de [`CHECK_TIMEOUT`] y mide cuánto tarda.
+async fn timed<T>(check: impl Future<Output = Result<T>>) -> (Result<T>, f64) {
+    let started = Instant::now();
+    let result = match tokio::time::timeout(CHECK_TIMEOUT, check).await {
+        Ok(result) => result,
+        Err(_) => Err(anyhow!("Sin respuesta en {CHECK_TIMEOUT:?}")),
+    };
+    (result, started.elapsed().as_secs_f64() * 1000.0)
+}
+
+async fn check_migrations(database_pool: &SqlitePool) -> Check {
+    let (result, _) = timed(migrations::status(database_pool)).await;
+    let migrations = match result {
+        Ok(migrations) => migrations,
+        Err(error) => return Check::down(error),
+    };
+
+    let with_state = |state| {
+        migrations
+            .iter()
+            .filter(|migration| migration.state == state)
+            .map(|migration| migration.version)
+            .collect::<Vec<_>>()
+    };
+    let pending = with_state(MigrationState::Pending);
+    let modified = with_state(MigrationState::Modified);
+    let version = migrations
+        .iter()
+        .filter(|migration| migration.state != MigrationState::Pending)
+        .map(|migration| migration.version)
+        .max();
+    let status = if pending.is_empty() && modified.is_empty() {
+        CheckStatus::Up
+    } else {
+        CheckStatus::Down
+    };
+
+    Check::new(
+        status,
+        json!({
+            "version": version,
+            "expected_version": migrations.last().map(|migration| migration.version),
+            "pending": pending,
+            "modified": modified,
+        }),
+    )
+}
+
+async fn check_database(database_pool: &SqlitePool) -> Check {
+    let (result, latency_ms) = timed(async {
+        sqlx::query("SELECT 1").execute(database_pool).await?;
+        Ok(())
+    })
+    .await;
+
+    match result {
+        Ok(()) => Check::new(CheckStatus::Up, json!({ "latency_ms": latency_ms })),
+        Err(error) => Check::down(error),
+    }
+}
+
+async fn check_job_queue(state: &AppState) -> Check {
+    let now = state.clock.now();
+    let (result, _) = timed(jobs::queue_stats(&state.database_pool, now)).await;
+    let stats = match result {
+        Ok(stats) => stats,
+        Err(error) => return Check::down(error),
+    };
+
+    let oldest_wait = stats
+        .oldest_due_at
+        .map(|due_at| (now - due_at).num_seconds().max(0));
+    let max_age = state.config.job_queue_max_age.as_secs();
+    let status = match oldest_wait {
+        Some(wait) if wait as u64 > max_age => CheckStatus::Degraded,
+        _ => CheckStatus::Up,
+    };
+
+    Check::new(
+        status,
+        json!({
+            "pending": stats.pending,
+            "oldest_due_at": stats.oldest_due_at,
+            "oldest_wait_secs": oldest_wait,
+            "max_wait_secs": max_age,
+        }),
+    )
+}
+
+async fn check_cache(cache: &Cache) -> Check {
+    if !cache.is_enabled() {
+        return Check::new(CheckStatus::Disabled, json!({}));
+    }
+
+    let (result, latency_ms) = timed(cache.ping()).await;
+    match result {
+        Ok(()) => Check::new(CheckStatus::Up, json!({ "latency_ms": latency_ms })),
+        Err(error) => Check::down(error),
+    }
+}
+
 /// Devuelve el router con los endpoints de salud.
-pub fn health_routes() -> Router<SqlitePool> {
-    Router::new().route("/health", get(health_check))
-}
\ No newline at end of file
+pub fn health_routes() -> Router<AppState> {
+    Router::new()
+        .route("/health", get(health_check))
+        .route("/health/ready", get(readiness))
+}
@@ -0,0 +1,14 @@
+//! Ruta de administración del modo de mantenimiento.
+
+use axum::{routing::get, Router};
+
+use crate::handlers::maintenance::{get_maintenance, set_maintenance};
+use crate::state::AppState;
+
+/// Devuelve el router con `GET` y `PUT /admin/maintenance`.
+pub fn maintenance_routes() -> Router<AppState> {
+    Router::new().route(
+        "/admin/maintenance",
+        get(get_maintenance).put(set_maintenance),
+    )
+}
@@ -0,0 +1,164 @@
+//! Rutas de observabilidad.
+//!
+//! Exponen las métricas en formato Prometheus (`/metrics`) y un panel HTML ligero
+//! (`/admin/metrics`) con sparklines generadas en el servidor, útil para diagnósticos
+//! rápidos sin montar Grafana. `/admin/status` da un resumen en JSON para consultarlo a mano
+//! o desde un script.
+
+use std::fmt::Write;
+
+use axum::{
+    extract::State,
+    http::header,
+    response::{Html, IntoResponse},
+    routing::get,
+    Json, Router,
+};
+use serde_json::{json, Value};
+
+use crate::{
+    metrics::{self, Metrics, MinutePoint},
+    state::AppState,
+};
+
+/// Ancho en píxeles de cada sparkline.
+const SPARKLINE_WIDTH: f64 = 360.0;
+/// Alto en píxeles de cada sparkline.
+const SPARKLINE_HEIGHT: f64 = 48.0;
+
+/// Devuelve las métricas en formato de texto de Prometheus.
+async fn prometheus_metrics(State(metrics): State<Metrics>) -> impl IntoResponse {
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        metrics.render_prometheus(),
+    )
+}
+
+/// Renderiza el panel HTML con peticiones por minuto, errores y latencia p95.
+async fn metrics_dashboard(State(metrics): State<Metrics>) -> Html<String> {
+    let snapshot = metrics.snapshot();
+    let points = &snapshot.per_minute;
+
+    let requests = series(points, |point| point.requests as f64);
+    let errors = series(points, |point| point.errors as f64);
+    let latency = series(points, |point| point.p95.unwrap_or_default() * 1000.0);
+
+    let last_requests = points
+        .last()
+        .map(|point| point.requests)
+        .unwrap_or_default();
+    let last_errors = points.last().map(|point| point.errors).unwrap_or_default();
+    let last_p95 = points
+        .iter()
+        .rev()
+        .find_map(|point| point.p95)
+        .map(|p95| format!("{:.0} ms", p95 * 1000.0))
+        .unwrap_or_else(|| "—".to_string());
+
+    let mut cards = String::new();
+    let _ = write!(
+        cards,
+        "{}{}{}",
+        card("Peticiones / minuto", &last_requests.to_string(), &requests),
+        card("Errores 5xx / minuto", &last_errors.to_string(), &errors),
+        card("Latencia p95", &last_p95, &latency),
+    );
+
+    Html(format!(
+        r#"<!DOCTYPE html>
+<html lang="es">
+  <head>
+    <meta charset="utf-8" />
+    <meta http-equiv="refresh" content="30" />
+    <title>Métricas · Rust Web Demo</title>
+    <style>
+      body {{ margin: 0; font-family: system-ui, sans-serif; background: #f9fafb; }}
+      header {{ padding: 1.5rem; background: #111827; color: #fff; }}
+      main {{ padding: 1.5rem; max-width: 900px; margin: 0 auto; }}
+      .card {{ background: #fff; border: 1px solid #e5e7eb; border-radius: 0.6rem;
+               padding: 1rem; margin-bottom: 1rem; }}
+      .value {{ font-size: 1.6rem; font-weight: 600; }}
+      svg polyline {{ fill: none; stroke: #2563eb; stroke-width: 2; }}
+    </style>
+  </head>
+  <body>
+    <header>
+      <h1>Métricas</h1>
+      <p>Últimos 60 minutos · {total} peticiones desde el arranque · activo hace {uptime} s</p>
+    </header>
+    <main>{cards}</main>
+  </body>
+</html>"#,
+        total = snapshot.totals.requests,
+        uptime = snapshot.uptime.as_secs(),
+    ))
+}
+
+/// Resume el estado del proceso: tiempo activo, peticiones, pool, memoria y último error.
+async fn runtime_status(State(metrics): State<Metrics>) -> Json<Value> {
+    let snapshot = metrics.snapshot();
+
+    Json(json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "uptime_seconds": snapshot.uptime.as_secs(),
+        "requests": {
+            "total": snapshot.totals.requests,
+            "errors": snapshot.totals.errors,
+            "in_flight": snapshot.in_flight,
+        },
+        "last_error_at": snapshot.last_error_at,
+        "shutdown_started_at": snapshot.shutdown_started_at,
+        "database_pool": snapshot.pool.map(|pool| json!({
+            "max_connections": pool.max_connections,
+            "open": pool.open,
+            "idle": pool.idle,
+            "in_use": pool.open.saturating_sub(pool.idle),
+        })),
+        "memory": metrics::memory_usage().map(|memory| json!({
+            "resident_bytes": memory.resident_bytes,
+            "peak_resident_bytes": memory.peak_resident_bytes,
+        })),
+    }))
+}
+
+/// Construye una tarjeta del panel con su valor actual y la sparkline asociada.
+fn card(title: &str, value: &str, values: &[f64]) -> String {
+    format!(
+        r#"<section class="card"><h2>{title}</h2><p class="value">{value}</p>{}</section>"#,
+        sparkline(values)
+    )
+}
+
+/// Extrae una serie numérica de los puntos por minuto.
+fn series(points: &[MinutePoint], value: impl Fn(&MinutePoint) -> f64) -> Vec<f64> {
+    points.iter().map(value).collect()
+}
+
+/// Genera un SVG en línea con la evolución de la serie.
+fn sparkline(values: &[f64]) -> String {
+    let maximum = values.iter().copied().fold(0.0_f64, f64::max).max(1.0);
+    let step = SPARKLINE_WIDTH / (values.len().max(2) - 1) as f64;
+
+    let points = values
+        .iter()
+        .enumerate()
+        .map(|(index, value)| {
+            let x = index as f64 * step;
+            let y = SPARKLINE_HEIGHT - (value / maximum) * (SPARKLINE_HEIGHT - 2.0) - 1.0;
+            format!("{x:.1},{y:.1}")
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    format!(
+        r#"<svg width="{SPARKLINE_WIDTH}" height="{SPARKLINE_HEIGHT}" viewBox="0 0 {SPARKLINE_WIDTH} {SPARKLINE_HEIGHT}" role="img"><polyline points="{points}" /></svg>"#
+    )
+}
+
+/// Devuelve el router con los endpoints de métricas.
+pub fn metrics_routes() -> Router<AppState> {
+    Router::new()
+        .route("/metrics", get(prometheus_metrics))
+        .route("/admin/metrics", get(metrics_dashboard))
+        .route("/admin/status", get(runtime_status))
+}
@@ -1,7 +1,41 @@
+mod api;
+mod backup;
+#[cfg(feature = "pprof")]
+mod debug;
+mod email_domains;
+mod exports;
 mod health;
+mod maintenance;
+mod metrics;
+mod posts;
+mod public;
+mod reload;
 mod root;
+mod stats;
+mod tags;
+mod teams;
+mod tenants;
 mod users;
+mod views;
+mod webhooks;
 
+pub use api::{api_routes, select_api_version, API_VERSION_HEADER};
+pub use backup::backup_routes;
+#[cfg(feature = "pprof")]
+pub use debug::debug_routes;
+pub use email_domains::email_domain_routes;
+pub use exports::{export_download_routes, export_routes};
 pub use health::health_routes;
+pub use maintenance::maintenance_routes;
+pub use metrics::metrics_routes;
+pub use posts::post_routes;
+pub use public::{public_routes, IMMUTABLE_CACHE_CONTROL, REVALIDATE_CACHE_CONTROL};
+pub use reload::reload_routes;
 pub use root::root_route;
-pub use users::user_routes;
+pub use stats::stats_routes;
+pub use tags::tag_routes;
+pub use teams::team_routes;
+pub use tenants::tenant_routes;
+pub use users::{user_routes, user_routes_v2};
+pub use views::view_routes;
+pub use webhooks::webhook_routes;
@@ -0,0 +1,33 @@
+//! Rutas HTTP relacionadas con publicaciones.
+//!
+//! Define el recurso `/posts`, sus comentarios anidados en `/posts/:id/comments` y el
+//! listado `/users/:id/posts`.
+
+use axum::{routing::get, Router};
+
+use crate::handlers::comment::{
+    create_comment, delete_comment, get_comment, list_comments, update_comment,
+};
+use crate::handlers::post::{
+    create_post, delete_post, get_post, list_posts, list_user_posts, update_post,
+};
+use crate::state::AppState;
+
+/// Devuelve un router con todas las operaciones disponibles para publicaciones.
+pub fn post_routes() -> Router<AppState> {
+    Router::new()
+        .route("/posts", get(list_posts).post(create_post))
+        .route(
+            "/posts/:id",
+            get(get_post).put(update_post).delete(delete_post),
+        )
+        .route(
+            "/posts/:id/comments",
+            get(list_comments).post(create_comment),
+        )
+        .route(
+            "/posts/:id/comments/:comment_id",
+            get(get_comment).put(update_comment).delete(delete_comment),
+        )
+        .route("/users/:id/posts", get(list_user_posts))
+}
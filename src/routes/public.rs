@@ -0,0 +1,101 @@
+//! Archivos estáticos bajo `/public`.
+//!
+//! `ServeDir` ya envía `Last-Modified` y responde `304 Not Modified` ante
+//! `If-Modified-Since`; aquí se añade `Cache-Control`. Los archivos con hash en el nombre
+//! (por ejemplo `app.3f2a9c1b.js`) cambian de URL con cada versión y se marcan como
+//! inmutables durante un año; el resto se revalida en cada uso.
+//!
+//! Con `PUBLIC_SPA_FALLBACK=true` el directorio puede alojar una SPA con enrutado en el
+//! cliente: las rutas sin archivo y sin extensión (`/public/users/42`) reciben `index.html`
+//! para que recargar la página no dé `404`. Las que tienen extensión siguen respondiendo `404`,
+//! porque un recurso estático que falta no debe enmascararse con la página de entrada.
+
+use std::path::Path;
+
+use axum::{
+    body::Body,
+    extract::Request,
+    http::{header, HeaderValue, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    Router,
+};
+use tower::ServiceExt;
+use tower_http::services::{ServeDir, ServeFile};
+
+use crate::state::AppState;
+
+/// Política para archivos cuyo nombre incluye un hash de contenido.
+pub const IMMUTABLE_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+/// Política para el resto: el navegador guarda copia pero la revalida siempre.
+pub const REVALIDATE_CACHE_CONTROL: &str = "public, no-cache";
+
+/// Longitud mínima del segmento hexadecimal que se considera un hash.
+const MIN_HASH_LENGTH: usize = 8;
+
+/// Sirve el directorio indicado bajo `/public` con cabeceras de caché y, con
+/// `spa_fallback`, `index.html` en las rutas del cliente.
+pub fn public_routes(directory: impl AsRef<Path>, spa_fallback: bool) -> Router<AppState> {
+    let directory = directory.as_ref();
+    let router = if spa_fallback {
+        let index = ServeFile::new(directory.join("index.html"));
+        let fallback = tower::service_fn(move |request: Request| {
+            let index = index.clone();
+            async move {
+                if is_client_route(request.uri().path()) {
+                    Ok(index.oneshot(request).await?.map(Body::new))
+                } else {
+                    Ok(StatusCode::NOT_FOUND.into_response())
+                }
+            }
+        });
+        Router::new().nest_service("/public", ServeDir::new(directory).fallback(fallback))
+    } else {
+        Router::new().nest_service("/public", ServeDir::new(directory))
+    };
+
+    router.layer(middleware::from_fn(set_cache_control))
+}
+
+/// Indica si la ruta parece de la SPA y no un archivo: su último segmento no tiene extensión.
+fn is_client_route(path: &str) -> bool {
+    !path.rsplit('/').next().unwrap_or_default().contains('.')
+}
+
+/// Añade `Cache-Control` a las respuestas correctas o `304` de los archivos estáticos.
+async fn set_cache_control(request: Request, next: Next) -> Response {
+    let is_hashed = request
+        .uri()
+        .path()
+        .rsplit('/')
+        .next()
+        .is_some_and(has_content_hash);
+
+    let mut response = next.run(request).await;
+
+    if matches!(response.status(), StatusCode::OK | StatusCode::NOT_MODIFIED) {
+        let policy = if is_hashed {
+            IMMUTABLE_CACHE_CONTROL
+        } else {
+            REVALIDATE_CACHE_CONTROL
+        };
+        response
+            .headers_mut()
+            .insert(header::CACHE_CONTROL, HeaderValue::from_static(policy));
+    }
+
+    response
+}
+
+/// Indica si el nombre sigue el patrón `nombre.<hash>.ext` o `nombre-<hash>.ext`.
+fn has_content_hash(file_name: &str) -> bool {
+    let Some((stem, _extension)) = file_name.rsplit_once('.') else {
+        return false;
+    };
+
+    stem.rsplit(['.', '-']).next().is_some_and(|segment| {
+        segment.len() >= MIN_HASH_LENGTH
+            && segment != stem
+            && segment.bytes().all(|byte| byte.is_ascii_hexdigit())
+    })
+}
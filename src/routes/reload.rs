@@ -0,0 +1,31 @@
+//! Ruta de administración para recargar la configuración; ver [`crate::reload`].
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::post,
+    Json, Router,
+};
+
+use crate::reload::ConfigReloader;
+use crate::state::AppState;
+
+/// Recarga los ajustes y devuelve los que cambiaron; `422` si el archivo no es válido.
+async fn reload_config(State(reloader): State<ConfigReloader>) -> Response {
+    match reloader.reload() {
+        Ok(report) => Json(report).into_response(),
+        Err(error) => (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Json(serde_json::json!({ "message": format!("{error:#}") })),
+        )
+            .into_response(),
+    }
+}
+
+/// Devuelve el router con `POST /admin/reload-config`.
+pub fn reload_routes(reloader: ConfigReloader) -> Router<AppState> {
+    Router::new()
+        .route("/admin/reload-config", post(reload_config))
+        .with_state(reloader)
+}
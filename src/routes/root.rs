@@ -1,16 +1,245 @@
 //! Rutas raíz del servicio.
 //!
-//! Contienen un mensaje de bienvenida útil para pruebas rápidas o documentación.
+//! `GET /` presenta el servicio: su versión, si está en mantenimiento, cuánto lleva activo y
+//! enlaces a la documentación y a las rutas de diagnóstico. La representación se negocia con
+//! `Accept`: los navegadores reciben una página HTML, quien pida `application/json` un objeto
+//! JSON y el resto (por ejemplo `curl` con `*/*`) el saludo en texto plano de siempre.
+//!
+//! `GET /version` devuelve solo la versión y el perfil de ejecución (`APP_ENV`), para que los
+//! despliegues comprueben qué se está ejecutando.
+
+use std::sync::Arc;
+
+use axum::{
+    extract::State,
+    http::{header, HeaderMap, HeaderValue},
+    response::{Html, IntoResponse, Response},
+    routing::get,
+    Json, Router,
+};
+use serde::Serialize;
+
+use crate::{
+    config::{AppConfig, Environment},
+    maintenance::{Maintenance, MaintenanceStatus},
+    metrics::Metrics,
+    state::AppState,
+};
+
+/// Saludo que confirma el correcto despliegue.
+const WELCOME: &str = "Bienvenido a la API en Rust 🚀";
+
+/// Versión del servicio publicada en la portada.
+const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Enlaces que ofrece la portada, con su descripción.
+const LINKS: [(&str, &str, &str); 5] = [
+    ("docs", "/public/index.html", "Documentación de las rutas"),
+    ("version", "/version", "Versión y perfil de ejecución"),
+    ("health", "/health", "Comprobación de salud"),
+    ("metrics", "/metrics", "Métricas en formato Prometheus"),
+    ("dashboard", "/admin/metrics", "Panel de métricas"),
+];
+
+/// Representación de la portada elegida según `Accept`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Representation {
+    Text,
+    Json,
+    Html,
+}
+
+impl Representation {
+    /// En caso de empate gana la primera: así `*/*` mantiene el texto plano.
+    const PREFERENCE: [Self; 3] = [Self::Text, Self::Json, Self::Html];
+
+    fn media_type(self) -> (&'static str, &'static str) {
+        match self {
+            Self::Text => ("text", "plain"),
+            Self::Json => ("application", "json"),
+            Self::Html => ("text", "html"),
+        }
+    }
+
+    /// Elige la representación con mayor calidad en `Accept`, o texto si no hay cabecera.
+    fn negotiate(headers: &HeaderMap) -> Self {
+        let Some(accept) = headers
+            .get(header::ACCEPT)
+            .and_then(|value| value.to_str().ok())
+        else {
+            return Self::Text;
+        };
+
+        let mut best = (Self::Text, 0.0);
+        for representation in Self::PREFERENCE {
+            let quality = quality(accept, representation.media_type());
+            if quality > best.1 {
+                best = (representation, quality);
+            }
+        }
+        best.0
+    }
+}
 
-use axum::{routing::get, Router};
-use sqlx::SqlitePool;
+/// Calidad que `accept` asigna a `media_type`, tomada del rango más específico que lo cubre.
+fn quality(accept: &str, (kind, subtype): (&str, &str)) -> f32 {
+    let mut best: Option<(u8, f32)> = None;
+    for range in accept.split(',') {
+        let mut parts = range.split(';').map(str::trim);
+        let Some((range_kind, range_subtype)) =
+            parts.next().and_then(|range| range.split_once('/'))
+        else {
+            continue;
+        };
+        let specificity = match (range_kind, range_subtype) {
+            (k, s) if k.eq_ignore_ascii_case(kind) && s.eq_ignore_ascii_case(subtype) => 2,
+            (k, "*") if k.eq_ignore_ascii_case(kind) => 1,
+            ("*", "*") => 0,
+            _ => continue,
+        };
+        let q = parts
+            .find_map(|param| param.strip_prefix("q="))
+            .and_then(|q| q.parse().ok())
+            .unwrap_or(1.0);
+        if best.is_none_or(|(current, _)| specificity > current) {
+            best = Some((specificity, q));
+        }
+    }
+    best.map_or(0.0, |(_, q)| q)
+}
+
+/// Estado del servicio que muestra la portada.
+#[derive(Debug, Serialize)]
+struct ServiceInfo {
+    message: &'static str,
+    version: &'static str,
+    /// `ok` o `maintenance`.
+    status: &'static str,
+    uptime_seconds: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    maintenance: Option<MaintenanceStatus>,
+    links: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Portada del servicio en la representación que pida el cliente.
+async fn index(
+    State(metrics): State<Metrics>,
+    State(maintenance): State<Maintenance>,
+    headers: HeaderMap,
+) -> Response {
+    let representation = Representation::negotiate(&headers);
+    let status = maintenance.status();
+    let info = ServiceInfo {
+        message: WELCOME,
+        version: VERSION,
+        status: if status.enabled { "maintenance" } else { "ok" },
+        uptime_seconds: metrics.snapshot().uptime.as_secs(),
+        maintenance: status.enabled.then_some(status),
+        links: LINKS
+            .iter()
+            .map(|(name, href, _)| (name.to_string(), (*href).into()))
+            .collect(),
+    };
+
+    let mut response = match representation {
+        Representation::Text => WELCOME.into_response(),
+        Representation::Json => Json(info).into_response(),
+        Representation::Html => Html(landing_page(&info)).into_response(),
+    };
+    response
+        .headers_mut()
+        .insert(header::VARY, HeaderValue::from_static("accept"));
+    response
+}
+
+/// Renderiza la portada HTML.
+fn landing_page(info: &ServiceInfo) -> String {
+    let status = match &info.maintenance {
+        Some(maintenance) => format!(
+            r#"<p class="status maintenance">En mantenimiento: {}</p>"#,
+            escape_html(
+                maintenance
+                    .reason
+                    .as_deref()
+                    .unwrap_or("solo se admiten lecturas")
+            )
+        ),
+        None => r#"<p class="status ok">Operativo</p>"#.to_string(),
+    };
+    let links = LINKS
+        .iter()
+        .map(|(_, href, label)| {
+            format!(r#"<li><a href="{href}">{label}</a> <code>{href}</code></li>"#)
+        })
+        .collect::<String>();
 
-/// Devuelve un saludo sencillo que confirma el correcto despliegue.
-async fn index() -> &'static str {
-    "Bienvenido a la API en Rust 🚀"
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="es">
+  <head>
+    <meta charset="utf-8" />
+    <meta name="viewport" content="width=device-width, initial-scale=1" />
+    <title>Rust Web Demo</title>
+    <style>
+      body {{ margin: 0; font-family: system-ui, sans-serif; background: #f9fafb; }}
+      header {{ padding: 1.5rem; background: #111827; color: #fff; }}
+      main {{ padding: 1.5rem; max-width: 900px; margin: 0 auto; }}
+      .status {{ display: inline-block; padding: 0.3rem 0.8rem; border-radius: 0.6rem; }}
+      .ok {{ background: #dcfce7; color: #166534; }}
+      .maintenance {{ background: #fef3c7; color: #92400e; }}
+    </style>
+  </head>
+  <body>
+    <header>
+      <h1>{message}</h1>
+      <p>Versión {version} · activo hace {uptime} s</p>
+    </header>
+    <main>
+      {status}
+      <ul>{links}</ul>
+    </main>
+  </body>
+</html>"#,
+        message = info.message,
+        version = info.version,
+        uptime = info.uptime_seconds,
+    )
 }
 
-/// Construye el router asociado a la ruta base `/`.
-pub fn root_route() -> Router<SqlitePool> {
-    Router::new().route("/", get(index))
-}
\ No newline at end of file
+/// Versión y perfil del servicio.
+#[derive(Debug, Serialize)]
+struct VersionInfo {
+    version: &'static str,
+    environment: Environment,
+}
+
+/// Devuelve la versión y el perfil con que se ejecuta el servicio.
+async fn version(State(config): State<Arc<AppConfig>>) -> Json<VersionInfo> {
+    Json(VersionInfo {
+        version: VERSION,
+        environment: config.environment,
+    })
+}
+
+/// Escapa los caracteres con significado en HTML.
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for character in text.chars() {
+        match character {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(character),
+        }
+    }
+    escaped
+}
+
+/// Construye el router asociado a la ruta base `/` y a `/version`.
+pub fn root_route() -> Router<AppState> {
+    Router::new()
+        .route("/", get(index))
+        .route("/version", get(version))
+}
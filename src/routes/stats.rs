@@ -0,0 +1,14 @@
+//! Rutas HTTP de las estadísticas agregadas.
+//!
+//! Define `/stats/users`, con los totales, las altas por periodo y los dominios de correo
+//! más frecuentes de un inquilino.
+
+use axum::{routing::get, Router};
+
+use crate::handlers::stats::user_stats;
+use crate::state::AppState;
+
+/// Devuelve un router con las estadísticas disponibles.
+pub fn stats_routes() -> Router<AppState> {
+    Router::new().route("/stats/users", get(user_stats))
+}
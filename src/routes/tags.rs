@@ -0,0 +1,20 @@
+//! Rutas HTTP relacionadas con etiquetas.
+//!
+//! Define el catálogo `/tags` y la asignación de etiquetas en `/users/:id/tags`.
+
+use axum::{
+    routing::{delete, get, put},
+    Router,
+};
+
+use crate::handlers::tag::{attach_tag, delete_tag, detach_tag, list_tags, list_user_tags};
+use crate::state::AppState;
+
+/// Devuelve un router con todas las operaciones disponibles para etiquetas.
+pub fn tag_routes() -> Router<AppState> {
+    Router::new()
+        .route("/tags", get(list_tags))
+        .route("/tags/:tag", delete(delete_tag))
+        .route("/users/:id/tags", get(list_user_tags))
+        .route("/users/:id/tags/:tag", put(attach_tag).delete(detach_tag))
+}
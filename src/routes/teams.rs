@@ -0,0 +1,30 @@
+//! Rutas HTTP relacionadas con equipos.
+//!
+//! Define el recurso `/teams` y la gestión de miembros en `/teams/:id/members`. Todas las
+//! rutas exigen la cabecera `X-User-Id`.
+
+use axum::{
+    routing::{get, put},
+    Router,
+};
+
+use crate::handlers::team::{
+    add_member, create_team, delete_team, get_team, list_members, list_teams, remove_member,
+    update_member, update_team,
+};
+use crate::state::AppState;
+
+/// Devuelve un router con todas las operaciones disponibles para equipos.
+pub fn team_routes() -> Router<AppState> {
+    Router::new()
+        .route("/teams", get(list_teams).post(create_team))
+        .route(
+            "/teams/:id",
+            get(get_team).put(update_team).delete(delete_team),
+        )
+        .route("/teams/:id/members", get(list_members).post(add_member))
+        .route(
+            "/teams/:id/members/:user_id",
+            put(update_member).delete(remove_member),
+        )
+}
@@ -0,0 +1,22 @@
+//! Rutas HTTP para administrar inquilinos.
+//!
+//! Se montan fuera del middleware de resolución de inquilinos.
+
+use axum::{
+    routing::{delete, get, put},
+    Router,
+};
+
+use crate::handlers::tenant::{
+    create_tenant, delete_tenant, get_tenant_usage, list_tenants, update_tenant_quotas,
+};
+use crate::state::AppState;
+
+/// Devuelve un router con el alta, listado, cuotas y baja de inquilinos.
+pub fn tenant_routes() -> Router<AppState> {
+    Router::new()
+        .route("/tenants", get(list_tenants).post(create_tenant))
+        .route("/tenants/:id", delete(delete_tenant))
+        .route("/tenants/:id/quotas", put(update_tenant_quotas))
+        .route("/tenants/:id/usage", get(get_tenant_usage))
+}
@@ -1,21 +1,73 @@
 //! Rutas HTTP relacionadas con usuarios.
 //!
-//! Define las rutas y métodos soportados para operar sobre el recurso `/users`.
+//! Define las rutas y métodos soportados para operar sobre el recurso `/users`. Solo
+//! `/users` y `/users/:id` cambian de representación en v2; el resto de operaciones se
+//! comparten entre versiones.
 
 use axum::{
-    routing::get,
+    routing::{get, post},
     Router,
 };
-use sqlx::{Pool, Sqlite};
 
-use crate::handlers::user::{create_user, delete_user, get_user, list_users, update_user};
+use crate::handlers::erasure::erase_user;
+use crate::handlers::preferences::{get_preferences, update_preferences};
+use crate::handlers::user::{
+    activate_user, batch_create_users, batch_delete_users, count_users, create_user,
+    deactivate_user, delete_user, export_users, get_avatar, get_user, head_users, import_users,
+    list_duplicate_users, list_users, merge_users, search_users, suggest_users, suspend_user,
+    update_user, upload_avatar,
+};
+use crate::handlers::v2;
+use crate::state::AppState;
 
 /// Devuelve un router con todas las operaciones disponibles para usuarios.
-pub fn user_routes() -> Router<Pool<Sqlite>> {
+pub fn user_routes() -> Router<AppState> {
     Router::new()
-        .route("/users", get(list_users).post(create_user))
+        .route("/users", get(list_users).head(head_users).post(create_user))
         .route(
             "/users/:id",
             get(get_user).put(update_user).delete(delete_user),
         )
-}
\ No newline at end of file
+        .merge(shared_user_routes())
+}
+
+/// Devuelve el router de usuarios de v2, con la nueva representación de `User`.
+pub fn user_routes_v2() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/users",
+            get(v2::user::list_users)
+                .head(head_users)
+                .post(v2::user::create_user),
+        )
+        .route(
+            "/users/:id",
+            get(v2::user::get_user)
+                .put(v2::user::update_user)
+                .delete(delete_user),
+        )
+        .merge(shared_user_routes())
+}
+
+/// Operaciones de usuarios cuya respuesta es la misma en todas las versiones.
+fn shared_user_routes() -> Router<AppState> {
+    Router::new()
+        .route("/users/count", get(count_users))
+        .route("/users/duplicates", get(list_duplicate_users))
+        .route("/users/suggest", get(suggest_users))
+        .route("/users/search", get(search_users))
+        .route("/users/batch", post(batch_create_users))
+        .route("/users/batch-delete", post(batch_delete_users))
+        .route("/users/export", get(export_users))
+        .route("/users/import", post(import_users))
+        .route("/users/:id/suspend", post(suspend_user))
+        .route("/users/:id/activate", post(activate_user))
+        .route("/users/:id/deactivate", post(deactivate_user))
+        .route("/users/:id/merge", post(merge_users))
+        .route("/users/:id/erase", post(erase_user))
+        .route("/users/:id/avatar", get(get_avatar).put(upload_avatar))
+        .route(
+            "/users/:id/preferences",
+            get(get_preferences).put(update_preferences),
+        )
+}
@@ -0,0 +1,20 @@
+//! Rutas HTTP de las vistas guardadas.
+//!
+//! Define el recurso `/views` y su ejecución en `/views/:id/results`. Todas las rutas
+//! exigen la cabecera `X-User-Id`.
+
+use axum::{
+    routing::{delete, get},
+    Router,
+};
+
+use crate::handlers::view::{create_view, delete_view, list_views, view_results};
+use crate::state::AppState;
+
+/// Devuelve un router con todas las operaciones disponibles para vistas guardadas.
+pub fn view_routes() -> Router<AppState> {
+    Router::new()
+        .route("/views", get(list_views).post(create_view))
+        .route("/views/:id", delete(delete_view))
+        .route("/views/:id/results", get(view_results))
+}
@@ -0,0 +1,22 @@
+//! Rutas HTTP relacionadas con webhooks.
+//!
+//! Define las rutas para gestionar suscripciones (`/webhooks`) y consultar sus entregas.
+
+use axum::{routing::get, Router};
+
+use crate::handlers::webhook::{
+    create_webhook, delete_webhook, get_webhook, list_webhook_deliveries, list_webhooks,
+    update_webhook,
+};
+use crate::state::AppState;
+
+/// Devuelve un router con todas las operaciones disponibles para webhooks.
+pub fn webhook_routes() -> Router<AppState> {
+    Router::new()
+        .route("/webhooks", get(list_webhooks).post(create_webhook))
+        .route(
+            "/webhooks/:id",
+            get(get_webhook).put(update_webhook).delete(delete_webhook),
+        )
+        .route("/webhooks/:id/deliveries", get(list_webhook_deliveries))
+}
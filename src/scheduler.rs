@@ -0,0 +1,273 @@
+//! Planificador de tareas recurrentes.
+//!
+//! Cada tarea tiene un nombre estable y una expresión cron (con campo de segundos, por
+//! ejemplo `0 */5 * * * *`). La última ejecución de cada tarea se guarda en
+//! `scheduled_runs`, de modo que un reinicio no vuelve a lanzar una ejecución ya hecha y
+//! varias instancias compartiendo la base de datos no ejecutan la misma tarea dos veces.
+
+use std::{future::Future, pin::Pin, str::FromStr, sync::Arc, time::Duration};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use cron::Schedule;
+use tracing::{debug, info, warn};
+
+use crate::{
+    broker::{self, BrokerConfig},
+    cdc::{self, CdcConfig},
+    handlers, jobs,
+    jobs::timestamp,
+    outbox,
+    state::AppState,
+    webhooks,
+};
+
+/// Frecuencia con la que el planificador comprueba si hay tareas vencidas.
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+/// Antigüedad a partir de la cual se purgan los trabajos terminados.
+const FINISHED_JOBS_RETENTION_DAYS: i64 = 7;
+/// Antigüedad a partir de la cual se purgan los eventos ya publicados del outbox.
+const SENT_OUTBOX_RETENTION_DAYS: i64 = 7;
+/// Antigüedad a partir de la cual se purga el historial de entregas de webhooks.
+const WEBHOOK_DELIVERIES_RETENTION_DAYS: i64 = 30;
+
+type TaskFuture = Pin<Box<dyn Future<Output = Result<()>> + Send>>;
+type TaskAction = Arc<dyn Fn(AppState) -> TaskFuture + Send + Sync>;
+
+/// Tarea registrada junto con su calendario.
+struct ScheduledTask {
+    name: &'static str,
+    schedule: Schedule,
+    action: TaskAction,
+}
+
+/// Ejecuta tareas recurrentes según sus expresiones cron.
+pub struct Scheduler {
+    state: AppState,
+    tasks: Vec<ScheduledTask>,
+}
+
+impl Scheduler {
+    /// Crea un planificador sin tareas.
+    pub fn new(state: AppState) -> Self {
+        Self {
+            state,
+            tasks: Vec::new(),
+        }
+    }
+
+    /// Registra una tarea con la expresión cron indicada.
+    pub fn add<F, Fut>(mut self, name: &'static str, expression: &str, action: F) -> Result<Self>
+    where
+        F: Fn(AppState) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let schedule = Schedule::from_str(expression)
+            .with_context(|| format!("Expresión cron inválida para {name}: {expression}"))?;
+
+        self.tasks.push(ScheduledTask {
+            name,
+            schedule,
+            action: Arc::new(move |state| Box::pin(action(state))),
+        });
+
+        Ok(self)
+    }
+
+    /// Ejecuta las tareas vencidas en `now` y devuelve sus nombres.
+    ///
+    /// Una tarea sin ejecuciones previas solo registra `now` como punto de partida. Si el
+    /// servicio estuvo detenido y se perdieron varias ejecuciones, la tarea corre una sola vez.
+    pub async fn run_due(&self, now: DateTime<Utc>) -> Result<Vec<&'static str>> {
+        let database_pool = &self.state.database_pool;
+        let mut executed = Vec::new();
+
+        for task in &self.tasks {
+            let last_run_at: Option<String> =
+                sqlx::query_scalar("SELECT last_run_at FROM scheduled_runs WHERE task = ?")
+                    .bind(task.name)
+                    .fetch_optional(database_pool)
+                    .await?;
+
+            let Some(last_run_at) = last_run_at else {
+                sqlx::query(
+                    "INSERT INTO scheduled_runs (task, last_run_at) VALUES (?, ?) \
+                     ON CONFLICT (task) DO NOTHING",
+                )
+                .bind(task.name)
+                .bind(timestamp(now))
+                .execute(database_pool)
+                .await?;
+                continue;
+            };
+
+            let previous = DateTime::parse_from_rfc3339(&last_run_at)
+                .with_context(|| format!("Fecha inválida en scheduled_runs: {last_run_at}"))?
+                .with_timezone(&Utc);
+            let is_due = task
+                .schedule
+                .after(&previous)
+                .next()
+                .is_some_and(|next_run| next_run <= now);
+            if !is_due {
+                continue;
+            }
+
+            // Reclamar la ejecución solo si nadie la actualizó desde la lectura.
+            let claimed = sqlx::query(
+                "UPDATE scheduled_runs SET last_run_at = ? WHERE task = ? AND last_run_at = ?",
+            )
+            .bind(timestamp(now))
+            .bind(task.name)
+            .bind(&last_run_at)
+            .execute(database_pool)
+            .await?
+            .rows_affected()
+                == 1;
+            if !claimed {
+                continue;
+            }
+
+            debug!(task = task.name, "Ejecutando tarea programada");
+            if let Err(error) = (task.action)(self.state.clone()).await {
+                warn!(task = task.name, ?error, "La tarea programada falló");
+            }
+            executed.push(task.name);
+        }
+
+        Ok(executed)
+    }
+
+    /// Bucle del planificador.
+    pub async fn run(self) {
+        info!(
+            tasks = ?self.tasks.iter().map(|task| task.name).collect::<Vec<_>>(),
+            "Planificador iniciado"
+        );
+
+        let mut interval = tokio::time::interval(TICK_INTERVAL);
+        loop {
+            interval.tick().await;
+
+            if let Err(error) = self.run_due(Utc::now()).await {
+                warn!(?error, "Fallo al comprobar las tareas programadas");
+            }
+        }
+    }
+}
+
+/// Construye el planificador con las tareas de mantenimiento de la aplicación.
+///
+/// La expresión de cada tarea puede sobrescribirse con `CRON_<TAREA>` (por ejemplo
+/// `CRON_LIFT_EXPIRED_SUSPENSIONS`); el valor `off` la desactiva.
+pub fn default_scheduler(state: AppState) -> Result<Scheduler> {
+    let mut scheduler = Scheduler::new(state);
+
+    if let Some(expression) = cron_expression("lift_expired_suspensions", "0 * * * * *") {
+        scheduler = scheduler.add(
+            "lift_expired_suspensions",
+            &expression,
+            |state| async move {
+                let now = state.clock.now();
+                let mut tenants = Vec::new();
+                for database_pool in state.database_pools().await {
+                    tenants.extend(
+                        handlers::user::lift_expired_suspensions(
+                            &database_pool,
+                            &state.email_encryption,
+                            now,
+                        )
+                        .await?,
+                    );
+                }
+                if !tenants.is_empty() {
+                    info!(lifted = tenants.len(), "Suspensiones vencidas levantadas");
+                    state.outbox.wake();
+                    // Las cuentas reactivadas vuelven a entrar en el listado por defecto.
+                    tenants.sort_unstable();
+                    tenants.dedup();
+                    for tenant_id in &tenants {
+                        state.cache.invalidate_user_list(tenant_id).await;
+                    }
+                }
+                Ok(())
+            },
+        )?;
+    }
+
+    if let Some(expression) = cron_expression("purge_finished_jobs", "0 0 3 * * *") {
+        scheduler = scheduler.add("purge_finished_jobs", &expression, |state| async move {
+            let older_than = Utc::now() - chrono::Duration::days(FINISHED_JOBS_RETENTION_DAYS);
+            let purged = jobs::purge_finished(&state.database_pool, older_than).await?;
+            info!(purged, "Trabajos terminados purgados");
+            Ok(())
+        })?;
+    }
+
+    if let Some(expression) = cron_expression("purge_sent_outbox", "0 15 3 * * *") {
+        // Lo que los webhooks, el broker o el CDC aún no hayan leído se conserva aunque caduque.
+        let consumers = outbox_consumers();
+        scheduler = scheduler.add("purge_sent_outbox", &expression, move |state| {
+            let consumers = consumers.clone();
+            async move {
+                let older_than = Utc::now() - chrono::Duration::days(SENT_OUTBOX_RETENTION_DAYS);
+                let mut purged = 0;
+                for database_pool in state.database_pools().await {
+                    purged += outbox::purge_sent(&database_pool, older_than, &consumers).await?;
+                }
+                info!(purged, "Eventos publicados del outbox purgados");
+                Ok(())
+            }
+        })?;
+    }
+
+    if let Some(expression) = cron_expression("purge_webhook_deliveries", "0 30 3 * * *") {
+        scheduler = scheduler.add(
+            "purge_webhook_deliveries",
+            &expression,
+            |state| async move {
+                let older_than =
+                    Utc::now() - chrono::Duration::days(WEBHOOK_DELIVERIES_RETENTION_DAYS);
+                let purged = webhooks::purge_deliveries(&state.database_pool, older_than).await?;
+                info!(purged, "Historial de entregas de webhooks purgado");
+                Ok(())
+            },
+        )?;
+    }
+
+    if let Some(expression) = cron_expression("rollup_daily_stats", "0 5 0 * * *") {
+        scheduler = scheduler.add("rollup_daily_stats", &expression, |state| async move {
+            // Solo se resumen días cerrados, para que sus cifras no cambien después; si el
+            // servicio estuvo parado, se recuperan también los días que se saltó.
+            let yesterday = state.clock.now().date_naive() - chrono::Days::new(1);
+            let days = handlers::stats::rollup_pending_days(&state, yesterday).await?;
+            info!(%yesterday, days = days.len(), "Estadísticas diarias resumidas");
+            Ok(())
+        })?;
+    }
+
+    Ok(scheduler)
+}
+
+/// Consumidores configurados que leen el outbox con su propia posición en `outbox_offsets`.
+fn outbox_consumers() -> Vec<&'static str> {
+    let mut consumers = vec![webhooks::CONSUMER];
+    if BrokerConfig::from_env().is_some() {
+        consumers.push(broker::CONSUMER);
+    }
+    if CdcConfig::from_env().is_some() {
+        consumers.push(cdc::CONSUMER);
+    }
+    consumers
+}
+
+/// Lee la expresión cron de una tarea desde el entorno; `None` si está desactivada.
+fn cron_expression(task: &str, default: &str) -> Option<String> {
+    let variable = format!("CRON_{}", task.to_uppercase());
+
+    match std::env::var(variable) {
+        Ok(value) if value.trim().eq_ignore_ascii_case("off") => None,
+        Ok(value) if !value.trim().is_empty() => Some(value.trim().to_string()),
+        _ => Some(default.to_string()),
+    }
+}
@@ -0,0 +1,215 @@
+//! Secretos en AWS Secrets Manager.
+//!
+//! Se lee un único secreto cuyo valor es un objeto JSON con un campo por secreto, el formato
+//! que genera la consola para los pares clave/valor. Las peticiones se firman con SigV4.
+
+use std::{collections::HashMap, time::Duration};
+
+use anyhow::{bail, Context, Result};
+use chrono::Utc;
+use reqwest::Url;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use super::{SecretFuture, SecretSource};
+use crate::aws::{self, ALGORITHM};
+
+const SERVICE: &str = "secretsmanager";
+const CONTENT_TYPE: &str = "application/x-amz-json-1.1";
+const TARGET: &str = "secretsmanager.GetSecretValue";
+
+/// Secreto de AWS Secrets Manager y credenciales con las que se lee.
+#[derive(Clone)]
+pub struct AwsSecretsManagerConfig {
+    /// Nombre o ARN del secreto (`AWS_SECRETS_MANAGER_SECRET_ID`).
+    pub secret_id: String,
+    /// Región (`AWS_REGION` o `AWS_DEFAULT_REGION`).
+    pub region: String,
+    /// URL del servicio; por defecto, la de la región (`AWS_SECRETS_MANAGER_ENDPOINT`).
+    pub endpoint: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    /// Token de una credencial temporal (`AWS_SESSION_TOKEN`).
+    pub session_token: Option<String>,
+}
+
+impl std::fmt::Debug for AwsSecretsManagerConfig {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter
+            .debug_struct("AwsSecretsManagerConfig")
+            .field("secret_id", &self.secret_id)
+            .field("region", &self.region)
+            .field("endpoint", &self.endpoint)
+            .field("access_key_id", &self.access_key_id)
+            .field("secret_access_key", &"***")
+            .field("session_token", &self.session_token.as_ref().map(|_| "***"))
+            .finish()
+    }
+}
+
+impl AwsSecretsManagerConfig {
+    pub fn from_env() -> Result<Self> {
+        let optional = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .filter(|value| !value.trim().is_empty())
+        };
+        let required = |name: &str| {
+            optional(name).with_context(|| {
+                format!("Falta {name} para el proveedor de secretos aws-secrets-manager")
+            })
+        };
+
+        let region = optional("AWS_REGION")
+            .or_else(|| optional("AWS_DEFAULT_REGION"))
+            .unwrap_or_else(|| "us-east-1".to_string());
+        Ok(Self {
+            secret_id: required("AWS_SECRETS_MANAGER_SECRET_ID")?,
+            endpoint: optional("AWS_SECRETS_MANAGER_ENDPOINT")
+                .unwrap_or_else(|| format!("https://secretsmanager.{region}.amazonaws.com")),
+            region,
+            access_key_id: required("AWS_ACCESS_KEY_ID")?,
+            secret_access_key: required("AWS_SECRET_ACCESS_KEY")?,
+            session_token: optional("AWS_SESSION_TOKEN"),
+        })
+    }
+}
+
+/// Lee cada secreto como un campo del secreto JSON configurado.
+#[derive(Debug, Clone)]
+pub struct AwsSecretsManagerSource {
+    client: reqwest::Client,
+    config: AwsSecretsManagerConfig,
+    /// Host (con puerto, si no es el por defecto) al que se envían las peticiones.
+    host: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct GetSecretValueResponse {
+    secret_string: Option<String>,
+}
+
+impl AwsSecretsManagerSource {
+    pub fn new(config: AwsSecretsManagerConfig) -> Result<Self> {
+        let endpoint = Url::parse(&config.endpoint).with_context(|| {
+            format!(
+                "Endpoint de AWS Secrets Manager inválido: {}",
+                config.endpoint
+            )
+        })?;
+        let Some(host) = endpoint.host_str() else {
+            bail!(
+                "El endpoint de AWS Secrets Manager no tiene host: {}",
+                config.endpoint
+            );
+        };
+        let host = match endpoint.port() {
+            Some(port) => format!("{host}:{port}"),
+            None => host.to_string(),
+        };
+
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            // Los secretos se consultan primero en un runtime propio que se cierra después.
+            .pool_max_idle_per_host(0)
+            .build()
+            .context("No se pudo crear el cliente HTTP de AWS Secrets Manager")?;
+
+        Ok(Self {
+            client,
+            config,
+            host,
+        })
+    }
+
+    /// Valor completo del secreto configurado.
+    async fn secret_string(&self) -> Result<Option<String>> {
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let body = serde_json::to_vec(&serde_json::json!({ "SecretId": self.config.secret_id }))?;
+        let payload_hash = hex::encode(Sha256::digest(&body));
+
+        let mut headers = vec![
+            ("content-type", CONTENT_TYPE.to_string()),
+            ("host", self.host.clone()),
+            ("x-amz-date", amz_date.clone()),
+        ];
+        if let Some(token) = &self.config.session_token {
+            headers.push(("x-amz-security-token", token.clone()));
+        }
+        headers.push(("x-amz-target", TARGET.to_string()));
+
+        let signed_headers = headers
+            .iter()
+            .map(|(name, _)| *name)
+            .collect::<Vec<_>>()
+            .join(";");
+        let canonical_headers = headers
+            .iter()
+            .map(|(name, value)| format!("{name}:{value}\n"))
+            .collect::<String>();
+        let canonical_request =
+            format!("POST\n/\n\n{canonical_headers}\n{signed_headers}\n{payload_hash}");
+
+        let signer = aws::Signer {
+            secret_access_key: &self.config.secret_access_key,
+            region: &self.config.region,
+            service: SERVICE,
+        };
+        let authorization = format!(
+            "{ALGORITHM} Credential={}/{}, SignedHeaders={signed_headers}, Signature={}",
+            self.config.access_key_id,
+            signer.scope(&date),
+            signer.signature(&canonical_request, &amz_date, &date),
+        );
+
+        let mut request = self
+            .client
+            .post(format!("{}/", self.config.endpoint.trim_end_matches('/')))
+            .header(reqwest::header::AUTHORIZATION, authorization)
+            .body(body);
+        for (name, value) in headers.into_iter().filter(|(name, _)| *name != "host") {
+            request = request.header(name, value);
+        }
+
+        let response = request
+            .send()
+            .await
+            .context("Fallo al contactar con AWS Secrets Manager")?;
+        let status = response.status();
+        if !status.is_success() {
+            let message = response.text().await.unwrap_or_default();
+            // El secreto inexistente se trata como "sin valores", igual que en los demás
+            // proveedores.
+            if message.contains("ResourceNotFoundException") {
+                return Ok(None);
+            }
+            bail!("AWS Secrets Manager respondió {status}: {message}");
+        }
+
+        let response = response
+            .json::<GetSecretValueResponse>()
+            .await
+            .context("Respuesta de AWS Secrets Manager inválida")?;
+        Ok(response.secret_string)
+    }
+}
+
+impl SecretSource for AwsSecretsManagerSource {
+    fn fetch<'a>(&'a self, name: &'a str) -> SecretFuture<'a, Option<String>> {
+        Box::pin(async move {
+            let Some(secret_string) = self.secret_string().await? else {
+                return Ok(None);
+            };
+            let values = serde_json::from_str::<HashMap<String, serde_json::Value>>(&secret_string)
+                .context("El secreto de AWS Secrets Manager no es un objeto JSON")?;
+
+            Ok(values.get(name).map(|value| match value {
+                serde_json::Value::String(value) => value.clone(),
+                other => other.to_string(),
+            }))
+        })
+    }
+}
@@ -0,0 +1,41 @@
+//! Secretos en variables de entorno.
+
+use std::collections::HashMap;
+
+use super::{SecretFuture, SecretSource};
+
+/// Variables de entorno tal como estaban al crear el proveedor.
+///
+/// Se copian al arrancar porque [`super::load`] escribe en el entorno los secretos de los
+/// demás proveedores: leerlo después ocultaría sus rotaciones tras el valor ya exportado.
+#[derive(Clone, Default)]
+pub struct EnvSource {
+    variables: HashMap<String, String>,
+}
+
+impl std::fmt::Debug for EnvSource {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter.write_str("EnvSource")
+    }
+}
+
+impl EnvSource {
+    /// Copia las variables de entorno actuales.
+    pub fn snapshot() -> Self {
+        Self {
+            variables: std::env::vars().collect(),
+        }
+    }
+}
+
+impl SecretSource for EnvSource {
+    fn fetch<'a>(&'a self, name: &'a str) -> SecretFuture<'a, Option<String>> {
+        Box::pin(async move {
+            Ok(self
+                .variables
+                .get(name)
+                .filter(|value| !value.is_empty())
+                .cloned())
+        })
+    }
+}
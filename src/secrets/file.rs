@@ -0,0 +1,57 @@
+//! Secretos en archivos, como los que montan Docker y Kubernetes.
+
+use std::{io::ErrorKind, path::PathBuf};
+
+use anyhow::Context;
+
+use super::{SecretFuture, SecretSource};
+
+/// Lee el secreto `NOMBRE` del archivo indicado en `NOMBRE_FILE` o, si no, de
+/// `<directorio>/NOMBRE`. Se descarta el salto de línea final.
+#[derive(Debug, Clone, Default)]
+pub struct FileSource {
+    directory: Option<PathBuf>,
+}
+
+impl FileSource {
+    /// Busca los archivos sin `NOMBRE_FILE` en `directory`, si se indica.
+    pub fn new(directory: Option<PathBuf>) -> Self {
+        Self { directory }
+    }
+
+    /// Usa `SECRETS_DIR` como directorio, si está definido.
+    pub fn from_env() -> Self {
+        Self::new(
+            std::env::var("SECRETS_DIR")
+                .ok()
+                .filter(|value| !value.trim().is_empty())
+                .map(PathBuf::from),
+        )
+    }
+
+    fn path(&self, name: &str) -> Option<PathBuf> {
+        std::env::var(format!("{name}_FILE"))
+            .ok()
+            .filter(|value| !value.trim().is_empty())
+            .map(PathBuf::from)
+            .or_else(|| Some(self.directory.as_ref()?.join(name)))
+    }
+}
+
+impl SecretSource for FileSource {
+    fn fetch<'a>(&'a self, name: &'a str) -> SecretFuture<'a, Option<String>> {
+        Box::pin(async move {
+            let Some(path) = self.path(name) else {
+                return Ok(None);
+            };
+
+            match tokio::fs::read_to_string(&path).await {
+                Ok(contents) => Ok(Some(contents.trim_end_matches(['\r', '\n']).to_string())),
+                Err(error) if error.kind() == ErrorKind::NotFound => Ok(None),
+                Err(error) => {
+                    Err(error).with_context(|| format!("No se pudo leer {}", path.display()))
+                }
+            }
+        })
+    }
+}
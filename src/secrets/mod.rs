@@ -0,0 +1,331 @@
+//! Resolución de secretos.
+//!
+//! Las credenciales (URL de la base de datos, claves de S3 y Redis, claves de cifrado de los
+//! correos) pueden venir de varios proveedores, que se consultan en el orden de
+//! `SECRETS_PROVIDERS` hasta que uno tiene el secreto:
+//!
+//! - `env`: variables de entorno, incluidas las de `.env`.
+//! - `file`: archivos, como los secretos de Docker o Kubernetes; `<NOMBRE>_FILE` apunta al
+//!   archivo o, con `SECRETS_DIR`, se lee `<SECRETS_DIR>/<NOMBRE>`.
+//! - `vault`: un documento KV v2 de HashiCorp Vault (`VAULT_ADDR`, `VAULT_TOKEN`,
+//!   `VAULT_SECRET_PATH`).
+//! - `aws-secrets-manager`: un secreto JSON de AWS Secrets Manager
+//!   (`AWS_SECRETS_MANAGER_SECRET_ID`).
+//!
+//! Al arrancar, [`load`] resuelve los secretos de [`MANAGED_SECRETS`] y los deja en el entorno
+//! del proceso, así que `AppConfig::from_env` y el resto de configuraciones los leen igual que
+//! antes. [`Secrets`] guarda cada valor durante `SECRETS_CACHE_TTL_SECS` y, con
+//! `SECRETS_REFRESH_SECS`, [`run`] vuelve a consultarlos y avisa a los ganchos registrados con
+//! [`Secrets::on_rotate`] de los que cambiaron.
+
+mod aws_secrets_manager;
+mod env;
+mod file;
+mod vault;
+
+use std::{
+    collections::HashMap,
+    future::Future,
+    pin::Pin,
+    str::FromStr,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Context, Result};
+use tracing::{info, warn};
+
+use crate::config::env_or;
+
+pub use aws_secrets_manager::{AwsSecretsManagerConfig, AwsSecretsManagerSource};
+pub use env::EnvSource;
+pub use file::FileSource;
+pub use vault::{VaultConfig, VaultSource};
+
+/// Secretos que se resuelven al arrancar y se dejan en el entorno. Las credenciales nuevas
+/// (por ejemplo, claves JWT o de SMTP) se añaden aquí para que lleguen por el mismo camino.
+pub const MANAGED_SECRETS: &[&str] = &[
+    "DATABASE_URL",
+    "REDIS_URL",
+    "S3_ACCESS_KEY_ID",
+    "S3_SECRET_ACCESS_KEY",
+    "EMAIL_ENCRYPTION_KEY",
+    "EMAIL_ENCRYPTION_PREVIOUS_KEYS",
+    "EMAIL_BLIND_INDEX_KEY",
+];
+
+/// Futuro devuelto por las consultas de un [`SecretSource`].
+pub type SecretFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + Send + 'a>>;
+
+/// Proveedor de secretos.
+pub trait SecretSource: std::fmt::Debug + Send + Sync + 'static {
+    /// Valor del secreto `name`, o `None` si este proveedor no lo tiene.
+    fn fetch<'a>(&'a self, name: &'a str) -> SecretFuture<'a, Option<String>>;
+}
+
+/// Proveedor que se puede indicar en `SECRETS_PROVIDERS`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SecretProvider {
+    Env,
+    File,
+    Vault,
+    AwsSecretsManager,
+}
+
+impl FromStr for SecretProvider {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "env" => Ok(Self::Env),
+            "file" => Ok(Self::File),
+            "vault" => Ok(Self::Vault),
+            "aws-secrets-manager" => Ok(Self::AwsSecretsManager),
+            other => bail!("Proveedor de secretos desconocido: {other}"),
+        }
+    }
+}
+
+/// Configuración de los secretos leída desde variables de entorno.
+#[derive(Debug, Clone)]
+pub struct SecretsConfig {
+    /// Proveedores en orden de consulta (`SECRETS_PROVIDERS`, por defecto `env,file`).
+    pub providers: Vec<SecretProvider>,
+    /// Tiempo durante el que se reutiliza un valor ya resuelto (`SECRETS_CACHE_TTL_SECS`).
+    pub cache_ttl: Duration,
+    /// Cada cuánto se vuelven a consultar los secretos (`SECRETS_REFRESH_SECS`; `0`, el valor
+    /// por defecto, no los vuelve a consultar).
+    pub refresh_interval: Option<Duration>,
+}
+
+impl SecretsConfig {
+    pub fn from_env() -> Result<Self> {
+        let providers = std::env::var("SECRETS_PROVIDERS")
+            .unwrap_or_else(|_| "env,file".to_string())
+            .split(',')
+            .filter(|provider| !provider.trim().is_empty())
+            .map(SecretProvider::from_str)
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            providers,
+            cache_ttl: Duration::from_secs(env_or("SECRETS_CACHE_TTL_SECS", 300)),
+            refresh_interval: match env_or("SECRETS_REFRESH_SECS", 0) {
+                0 => None,
+                seconds => Some(Duration::from_secs(seconds)),
+            },
+        })
+    }
+
+    /// Crea los proveedores configurados, leyendo la configuración de cada uno.
+    pub fn build(&self) -> Result<Secrets> {
+        let sources = self
+            .providers
+            .iter()
+            .map(|provider| -> Result<Arc<dyn SecretSource>> {
+                Ok(match provider {
+                    SecretProvider::Env => Arc::new(EnvSource::snapshot()),
+                    SecretProvider::File => Arc::new(FileSource::from_env()),
+                    SecretProvider::Vault => Arc::new(VaultSource::new(VaultConfig::from_env()?)?),
+                    SecretProvider::AwsSecretsManager => Arc::new(AwsSecretsManagerSource::new(
+                        AwsSecretsManagerConfig::from_env()?,
+                    )?),
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Secrets::new(sources, self.cache_ttl))
+    }
+}
+
+/// Gancho al que se avisa cuando cambia un secreto, con su nombre y su nuevo valor.
+pub type RotationHook = Arc<dyn Fn(&str, Option<&str>) + Send + Sync>;
+
+#[derive(Debug, Clone)]
+struct CachedSecret {
+    value: Option<String>,
+    fetched_at: Instant,
+}
+
+struct Inner {
+    sources: Vec<Arc<dyn SecretSource>>,
+    cache_ttl: Duration,
+    cache: Mutex<HashMap<String, CachedSecret>>,
+    hooks: Mutex<Vec<(String, RotationHook)>>,
+}
+
+/// Cadena de proveedores con caché y ganchos de rotación.
+#[derive(Clone)]
+pub struct Secrets {
+    inner: Arc<Inner>,
+}
+
+impl std::fmt::Debug for Secrets {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter
+            .debug_struct("Secrets")
+            .field("sources", &self.inner.sources)
+            .field("cache_ttl", &self.inner.cache_ttl)
+            .finish()
+    }
+}
+
+impl Secrets {
+    /// Consulta `sources` en orden y guarda cada valor resuelto durante `cache_ttl`.
+    pub fn new(sources: Vec<Arc<dyn SecretSource>>, cache_ttl: Duration) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                sources,
+                cache_ttl,
+                cache: Mutex::new(HashMap::new()),
+                hooks: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+
+    /// Valor del secreto `name` según el primer proveedor que lo tenga.
+    pub async fn get(&self, name: &str) -> Result<Option<String>> {
+        let cached = self.inner.cache.lock().unwrap().get(name).cloned();
+        if let Some(cached) = cached {
+            if cached.fetched_at.elapsed() < self.inner.cache_ttl {
+                return Ok(cached.value);
+            }
+        }
+
+        let value = self.resolve(name).await?;
+        self.store(name, value.clone());
+        Ok(value)
+    }
+
+    /// Registra `hook` para que se ejecute cada vez que [`Secrets::refresh`] detecte un cambio
+    /// en el secreto `name`.
+    pub fn on_rotate(&self, name: &str, hook: impl Fn(&str, Option<&str>) + Send + Sync + 'static) {
+        self.inner
+            .hooks
+            .lock()
+            .unwrap()
+            .push((name.to_string(), Arc::new(hook)));
+    }
+
+    /// Vuelve a consultar todos los secretos ya resueltos, sin tener en cuenta la caché, y
+    /// devuelve los nombres de los que cambiaron. Un proveedor que falla no cambia el valor.
+    pub async fn refresh(&self) -> Vec<String> {
+        let names = self
+            .inner
+            .cache
+            .lock()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let mut changed = Vec::new();
+        for name in names {
+            let value = match self.resolve(&name).await {
+                Ok(value) => value,
+                Err(error) => {
+                    warn!(
+                        ?error,
+                        secret = name,
+                        "No se pudo volver a consultar el secreto"
+                    );
+                    continue;
+                }
+            };
+            if let Some(previous) = self.store(&name, value.clone()) {
+                if previous == value {
+                    continue;
+                }
+            }
+
+            let hooks = self
+                .inner
+                .hooks
+                .lock()
+                .unwrap()
+                .iter()
+                .filter(|(hook_name, _)| *hook_name == name)
+                .map(|(_, hook)| hook.clone())
+                .collect::<Vec<_>>();
+            for hook in hooks {
+                hook(&name, value.as_deref());
+            }
+            changed.push(name);
+        }
+
+        changed
+    }
+
+    async fn resolve(&self, name: &str) -> Result<Option<String>> {
+        for source in &self.inner.sources {
+            let value = source
+                .fetch(name)
+                .await
+                .with_context(|| format!("No se pudo consultar {name} en {source:?}"))?;
+            if value.is_some() {
+                return Ok(value);
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Guarda `value` en la caché y devuelve el valor anterior, si lo había.
+    fn store(&self, name: &str, value: Option<String>) -> Option<Option<String>> {
+        self.inner
+            .cache
+            .lock()
+            .unwrap()
+            .insert(
+                name.to_string(),
+                CachedSecret {
+                    value,
+                    fetched_at: Instant::now(),
+                },
+            )
+            .map(|previous| previous.value)
+    }
+}
+
+/// Resuelve los secretos de [`MANAGED_SECRETS`] y los deja en el entorno del proceso.
+///
+/// Debe llamarse antes de arrancar el runtime de Tokio: modificar el entorno mientras otros
+/// hilos lo leen no es seguro, así que las consultas se hacen en un runtime propio de un
+/// solo hilo que se cierra antes de escribir las variables.
+pub fn load(config: &SecretsConfig) -> Result<Secrets> {
+    let secrets = config.build()?;
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+        .context("No se pudo crear el runtime para resolver los secretos")?;
+    let resolved = runtime.block_on(async {
+        let mut resolved = Vec::new();
+        for name in MANAGED_SECRETS {
+            if let Some(value) = secrets.get(name).await? {
+                resolved.push((*name, value));
+            }
+        }
+        anyhow::Ok(resolved)
+    })?;
+    runtime.shutdown_timeout(Duration::from_secs(1));
+
+    for (name, value) in resolved {
+        if std::env::var(name).ok().as_deref() != Some(value.as_str()) {
+            std::env::set_var(name, value);
+        }
+    }
+
+    Ok(secrets)
+}
+
+/// Bucle que vuelve a consultar los secretos cada `interval`.
+pub async fn run(secrets: Secrets, interval: Duration) {
+    info!(?interval, "Rotación de secretos activa");
+    loop {
+        tokio::time::sleep(interval).await;
+        let changed = secrets.refresh().await;
+        if !changed.is_empty() {
+            info!(?changed, "Secretos rotados");
+        }
+    }
+}
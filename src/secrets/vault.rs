@@ -0,0 +1,128 @@
+//! Secretos en HashiCorp Vault.
+
+use std::{collections::HashMap, time::Duration};
+
+use anyhow::{bail, Context, Result};
+use reqwest::StatusCode;
+use serde::Deserialize;
+
+use super::{SecretFuture, SecretSource};
+
+/// Documento KV v2 de Vault del que se leen los secretos.
+#[derive(Clone)]
+pub struct VaultConfig {
+    /// URL de Vault, por ejemplo `https://vault.example.com:8200` (`VAULT_ADDR`).
+    pub address: String,
+    /// Token de acceso (`VAULT_TOKEN`).
+    pub token: String,
+    /// Punto de montaje del motor KV v2 (`VAULT_KV_MOUNT`, por defecto `secret`).
+    pub mount: String,
+    /// Ruta del documento dentro del motor (`VAULT_SECRET_PATH`).
+    pub path: String,
+    /// Espacio de nombres de Vault Enterprise (`VAULT_NAMESPACE`).
+    pub namespace: Option<String>,
+}
+
+impl std::fmt::Debug for VaultConfig {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter
+            .debug_struct("VaultConfig")
+            .field("address", &self.address)
+            .field("token", &"***")
+            .field("mount", &self.mount)
+            .field("path", &self.path)
+            .field("namespace", &self.namespace)
+            .finish()
+    }
+}
+
+impl VaultConfig {
+    pub fn from_env() -> Result<Self> {
+        let required = |name: &str| {
+            std::env::var(name)
+                .ok()
+                .filter(|value| !value.trim().is_empty())
+                .with_context(|| format!("Falta {name} para el proveedor de secretos vault"))
+        };
+
+        Ok(Self {
+            address: required("VAULT_ADDR")?,
+            token: required("VAULT_TOKEN")?,
+            mount: std::env::var("VAULT_KV_MOUNT").unwrap_or_else(|_| "secret".to_string()),
+            path: required("VAULT_SECRET_PATH")?,
+            namespace: std::env::var("VAULT_NAMESPACE").ok(),
+        })
+    }
+}
+
+/// Lee cada secreto como una clave del documento KV v2 configurado.
+#[derive(Debug, Clone)]
+pub struct VaultSource {
+    client: reqwest::Client,
+    config: VaultConfig,
+}
+
+#[derive(Deserialize)]
+struct KvResponse {
+    data: KvData,
+}
+
+#[derive(Deserialize)]
+struct KvData {
+    data: HashMap<String, serde_json::Value>,
+}
+
+impl VaultSource {
+    pub fn new(config: VaultConfig) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            // Los secretos se consultan primero en un runtime propio que se cierra después.
+            .pool_max_idle_per_host(0)
+            .build()
+            .context("No se pudo crear el cliente HTTP de Vault")?;
+
+        Ok(Self { client, config })
+    }
+
+    fn url(&self) -> String {
+        format!(
+            "{}/v1/{}/data/{}",
+            self.config.address.trim_end_matches('/'),
+            self.config.mount.trim_matches('/'),
+            self.config.path.trim_matches('/')
+        )
+    }
+}
+
+impl SecretSource for VaultSource {
+    fn fetch<'a>(&'a self, name: &'a str) -> SecretFuture<'a, Option<String>> {
+        Box::pin(async move {
+            let mut request = self
+                .client
+                .get(self.url())
+                .header("X-Vault-Token", &self.config.token);
+            if let Some(namespace) = &self.config.namespace {
+                request = request.header("X-Vault-Namespace", namespace);
+            }
+
+            let response = request
+                .send()
+                .await
+                .context("Fallo al contactar con Vault")?;
+            match response.status() {
+                StatusCode::NOT_FOUND => return Ok(None),
+                status if !status.is_success() => bail!("Vault respondió {status}"),
+                _ => {}
+            }
+
+            let document = response
+                .json::<KvResponse>()
+                .await
+                .context("Respuesta de Vault inválida")?;
+            Ok(document.data.data.get(name).map(|value| match value {
+                serde_json::Value::String(value) => value.clone(),
+                other => other.to_string(),
+            }))
+        })
+    }
+}
@@ -0,0 +1,58 @@
+//! Estado compartido entre réplicas.
+//!
+//! Los datos del servicio viven en la base, pero dos piezas se guardaban en la memoria de cada
+//! proceso y dejan de funcionar con varias réplicas detrás de un balanceador:
+//!
+//! - Los nonces de las peticiones firmadas ([`crate::signing`]): una petición capturada se
+//!   podría repetir contra otra réplica que no la ha visto.
+//! - El modo de mantenimiento ([`crate::maintenance`]): `PUT /admin/maintenance` solo lo
+//!   cambiaría en la réplica que atendió la petición.
+//!
+//! Con `SHARED_STATE_BACKEND=redis` ambas se guardan en el Redis de `REDIS_URL`, el mismo que
+//! puede usar la caché; con `memory` (por defecto) siguen en el proceso, lo que basta para una
+//! sola instancia. La caché de usuarios ya se comparte con `CACHE_BACKEND=redis`.
+
+use std::sync::Arc;
+
+use anyhow::Result;
+use tracing::info;
+
+use crate::cache::{CacheStore, RedisStore};
+
+/// Dónde se guarda el estado que deben ver todas las réplicas.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SharedStateBackend {
+    /// En la memoria de cada proceso.
+    Memory,
+    Redis {
+        url: String,
+    },
+}
+
+impl SharedStateBackend {
+    /// Lee `SHARED_STATE_BACKEND` (`memory` o `redis`) y `REDIS_URL`.
+    pub fn from_env() -> Self {
+        match std::env::var("SHARED_STATE_BACKEND")
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "redis" => Self::Redis {
+                url: std::env::var("REDIS_URL")
+                    .unwrap_or_else(|_| "redis://127.0.0.1:6379".to_string()),
+            },
+            _ => Self::Memory,
+        }
+    }
+
+    /// Abre el almacén compartido; devuelve `None` si el estado se queda en cada proceso.
+    pub async fn connect(&self) -> Result<Option<Arc<dyn CacheStore>>> {
+        let store: Option<Arc<dyn CacheStore>> = match self {
+            Self::Memory => None,
+            Self::Redis { url } => Some(Arc::new(RedisStore::connect(url).await?)),
+        };
+        info!(backend = ?self, "Estado compartido configurado");
+        Ok(store)
+    }
+}
@@ -0,0 +1,66 @@
+//! Señales de apagado del proceso.
+//!
+//! El servidor termina de forma ordenada con `Ctrl+C` (`SIGINT`) y, en Unix, con `SIGTERM`,
+//! que es la señal con la que systemd, Docker o Kubernetes detienen el servicio. Al recibir
+//! cualquiera de las dos deja de aceptar conexiones y espera a las peticiones en curso.
+
+use std::future::Future;
+
+use tracing::{error, info};
+
+/// Instala los manejadores de las señales de apagado y devuelve un futuro que se completa al
+/// recibir la primera.
+///
+/// Los manejadores se instalan al llamar a la función, no al esperar el futuro, para que una
+/// señal que llegue antes del primer `poll` no se pierda ni termine el proceso. Debe
+/// llamarse dentro de un runtime de Tokio.
+pub fn shutdown_signal() -> impl Future<Output = ()> {
+    // `tokio::signal::ctrl_c` no registra nada hasta el primer `poll`; en Unix se instala
+    // `SIGINT` aquí mismo, igual que `SIGTERM`.
+    #[cfg(unix)]
+    let interrupt = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::interrupt());
+    #[cfg(unix)]
+    let terminate = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate());
+
+    async move {
+        #[cfg(unix)]
+        let ctrl_c = async {
+            match interrupt {
+                Ok(mut interrupt) => {
+                    interrupt.recv().await;
+                }
+                Err(error) => {
+                    error!(?error, "Error al esperar la señal Ctrl+C");
+                    std::future::pending::<()>().await;
+                }
+            }
+        };
+        #[cfg(not(unix))]
+        let ctrl_c = async {
+            if let Err(error) = tokio::signal::ctrl_c().await {
+                error!(?error, "Error al esperar la señal Ctrl+C");
+                std::future::pending::<()>().await;
+            }
+        };
+
+        #[cfg(unix)]
+        let terminate = async {
+            match terminate {
+                Ok(mut terminate) => {
+                    terminate.recv().await;
+                }
+                Err(error) => {
+                    error!(?error, "Error al esperar la señal SIGTERM");
+                    std::future::pending::<()>().await;
+                }
+            }
+        };
+        #[cfg(not(unix))]
+        let terminate = std::future::pending::<()>();
+
+        tokio::select! {
+            _ = ctrl_c => info!("Señal Ctrl+C recibida, cerrando servidor…"),
+            _ = terminate => info!("Señal SIGTERM recibida, cerrando servidor…"),
+        }
+    }
+}
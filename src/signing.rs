@@ -0,0 +1,307 @@
+//! Firma HMAC de peticiones para clientes máquina.
+//!
+//! Los procesos automáticos (tareas programadas, scripts de despliegue) pueden autenticarse
+//! firmando cada petición con un secreto compartido en lugar de usar credenciales de persona.
+//! El cliente envía:
+//!
+//! - `X-Signature-Client`: su identificador, que selecciona el secreto.
+//! - `X-Signature-Timestamp`: segundos Unix en que firmó.
+//! - `X-Signature-Nonce`: un valor aleatorio distinto en cada petición.
+//! - `X-Signature`: `sha256=<hex>`, el HMAC-SHA256 de [`canonical_request`] (método, ruta con
+//!   query, marca de tiempo, nonce y SHA-256 del cuerpo, separados por saltos de línea).
+//!
+//! [`require_signature`] rechaza con `401` las firmas inválidas, las de más de
+//! `REQUEST_SIGNING_MAX_SKEW_SECS` (300 por defecto) de diferencia con el reloj del servidor y
+//! los nonces ya vistos en esa ventana, de modo que una petición capturada no se puede
+//! repetir. Los clientes se configuran en `REQUEST_SIGNING_CLIENTS` como pares
+//! `id:secreto` separados por comas; sin ella no se exige firma.
+//!
+//! Los nonces se recuerdan en memoria; con varias réplicas hay que guardarlos en el almacén
+//! compartido ([`RequestSigning::with_nonce_store`]) para que tampoco se puedan repetir contra
+//! otra réplica. Si ese almacén no responde, las peticiones firmadas se rechazan con `503`.
+
+use std::{collections::HashMap, fmt, sync::Arc, time::Duration};
+
+use axum::{
+    async_trait,
+    body::Body,
+    extract::{FromRequestParts, Request, State},
+    http::{request::Parts, HeaderMap, Method, StatusCode, Uri},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+use crate::cache::{CacheStore, MemoryStore};
+use crate::clock::{Clock, SystemClock};
+use crate::config::env_or;
+use crate::handlers::error::AppError;
+
+/// Cabecera con el identificador del cliente que firma.
+pub const CLIENT_HEADER: &str = "x-signature-client";
+/// Cabecera con el instante de la firma, en segundos Unix.
+pub const TIMESTAMP_HEADER: &str = "x-signature-timestamp";
+/// Cabecera con el valor de un solo uso de la petición.
+pub const NONCE_HEADER: &str = "x-signature-nonce";
+/// Cabecera con la firma `sha256=<hex>`.
+pub const SIGNATURE_HEADER: &str = "x-signature";
+
+/// Tamaño máximo del cuerpo que se lee para verificar la firma.
+const MAX_SIGNED_BODY_BYTES: usize = 10 * 1024 * 1024;
+/// Nonces recordados como máximo; los más antiguos caducan antes por tiempo.
+const MAX_REMEMBERED_NONCES: u64 = 100_000;
+
+/// Motivo por el que se rechaza una petición firmada.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignatureError {
+    /// Falta alguna de las cabeceras de firma.
+    Missing,
+    /// El cliente no está configurado.
+    UnknownClient,
+    /// La marca de tiempo falta de formato o se aleja demasiado del reloj del servidor.
+    Expired,
+    /// La firma no corresponde a la petición.
+    Invalid,
+    /// El nonce ya se usó.
+    Replayed,
+    /// No se pudo comprobar el nonce porque el almacén compartido no responde.
+    Unavailable,
+    /// El cuerpo supera lo que se lee para verificar la firma.
+    TooLarge,
+}
+
+impl SignatureError {
+    pub(crate) fn message(self) -> &'static str {
+        match self {
+            Self::Missing => "La petición debe ir firmada con las cabeceras X-Signature-*",
+            Self::UnknownClient => "Cliente de firma desconocido",
+            Self::Expired => "La marca de tiempo de la firma está fuera de la ventana admitida",
+            Self::Invalid => "La firma de la petición no es válida",
+            Self::Replayed => "La petición firmada ya se recibió antes",
+            Self::Unavailable => "No se pudo comprobar si la petición firmada es repetida",
+            Self::TooLarge => "El cuerpo es demasiado grande para verificar su firma",
+        }
+    }
+
+    pub(crate) fn status_code(self) -> StatusCode {
+        match self {
+            Self::Unavailable => StatusCode::SERVICE_UNAVAILABLE,
+            Self::TooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            _ => StatusCode::UNAUTHORIZED,
+        }
+    }
+}
+
+/// Se responde como cualquier otro error de la aplicación, con el mismo formato JSON.
+impl IntoResponse for SignatureError {
+    fn into_response(self) -> Response {
+        AppError::from(self).into_response()
+    }
+}
+
+/// Verificador de peticiones firmadas; sus copias comparten el almacén de nonces.
+#[derive(Clone)]
+pub struct RequestSigning {
+    clients: Arc<HashMap<String, String>>,
+    max_skew: Duration,
+    nonces: Arc<dyn CacheStore>,
+    clock: Arc<dyn Clock>,
+}
+
+impl fmt::Debug for RequestSigning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut clients: Vec<_> = self.clients.keys().collect();
+        clients.sort();
+        f.debug_struct("RequestSigning")
+            .field("clients", &clients)
+            .field("max_skew", &self.max_skew)
+            .finish_non_exhaustive()
+    }
+}
+
+impl RequestSigning {
+    /// Verificador para los clientes `id -> secreto` con la tolerancia de reloj indicada.
+    pub fn new(clients: HashMap<String, String>, max_skew: Duration) -> Self {
+        Self {
+            clients: Arc::new(clients),
+            max_skew,
+            nonces: Arc::new(MemoryStore::new(MAX_REMEMBERED_NONCES)),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Lee `REQUEST_SIGNING_CLIENTS` y `REQUEST_SIGNING_MAX_SKEW_SECS`; devuelve `None` si no
+    /// hay ningún cliente configurado.
+    pub fn from_env() -> Option<Self> {
+        let clients: HashMap<String, String> = std::env::var("REQUEST_SIGNING_CLIENTS")
+            .ok()?
+            .split(',')
+            .filter_map(|pair| pair.split_once(':'))
+            .map(|(id, secret)| (id.trim().to_string(), secret.trim().to_string()))
+            .filter(|(id, secret)| !id.is_empty() && !secret.is_empty())
+            .collect();
+        if clients.is_empty() {
+            return None;
+        }
+
+        let max_skew = Duration::from_secs(env_or("REQUEST_SIGNING_MAX_SKEW_SECS", 300));
+        Some(Self::new(clients, max_skew))
+    }
+
+    /// Recuerda los nonces en `store` en lugar de en la memoria del proceso.
+    pub fn with_nonce_store(mut self, store: Arc<dyn CacheStore>) -> Self {
+        self.nonces = store;
+        self
+    }
+
+    /// Sustituye el reloj con el que se comprueba la marca de tiempo.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Verifica la firma de una petición y devuelve el cliente que la firmó.
+    pub async fn verify(
+        &self,
+        method: &Method,
+        uri: &Uri,
+        headers: &HeaderMap,
+        body: &[u8],
+    ) -> Result<String, SignatureError> {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .ok_or(SignatureError::Missing)
+        };
+        let client = header(CLIENT_HEADER)?;
+        let timestamp = header(TIMESTAMP_HEADER)?;
+        let nonce = header(NONCE_HEADER)?;
+        let signature = header(SIGNATURE_HEADER)?;
+
+        let secret = self
+            .clients
+            .get(client)
+            .ok_or(SignatureError::UnknownClient)?;
+
+        let signed_at: i64 = timestamp.parse().map_err(|_| SignatureError::Expired)?;
+        let skew = self.clock.now().timestamp().abs_diff(signed_at);
+        if skew > self.max_skew.as_secs() {
+            return Err(SignatureError::Expired);
+        }
+
+        let expected = signature
+            .strip_prefix("sha256=")
+            .and_then(|hex| hex::decode(hex).ok())
+            .ok_or(SignatureError::Invalid)?;
+        let path = uri.path_and_query().map_or("/", |path| path.as_str());
+        let mut mac = mac(secret);
+        mac.update(canonical_request(method, path, signed_at, nonce, body).as_bytes());
+        mac.verify_slice(&expected)
+            .map_err(|_| SignatureError::Invalid)?;
+
+        // El nonce se registra solo con la firma ya comprobada, para que nadie pueda agotar
+        // los de un cliente sin conocer su secreto. Solo hace falta recordarlo mientras su
+        // marca de tiempo sea aceptable.
+        let first_use = self
+            .nonces
+            .insert_if_absent(
+                &format!("signing:nonce:{client}:{nonce}"),
+                String::new(),
+                self.max_skew * 2,
+            )
+            .await
+            .map_err(|error| {
+                warn!(
+                    ?error,
+                    "No se pudo registrar el nonce de la petición firmada"
+                );
+                SignatureError::Unavailable
+            })?;
+        if !first_use {
+            return Err(SignatureError::Replayed);
+        }
+
+        Ok(client.to_string())
+    }
+}
+
+/// Texto que se firma: método, ruta con query, marca de tiempo, nonce y SHA-256 del cuerpo.
+pub fn canonical_request(
+    method: &Method,
+    path_and_query: &str,
+    timestamp: i64,
+    nonce: &str,
+    body: &[u8],
+) -> String {
+    format!(
+        "{method}\n{path_and_query}\n{timestamp}\n{nonce}\n{}",
+        hex::encode(Sha256::digest(body))
+    )
+}
+
+/// Calcula el valor de `X-Signature` para una petición; lo usan los clientes que firman.
+pub fn sign(
+    secret: &str,
+    method: &Method,
+    path_and_query: &str,
+    timestamp: i64,
+    nonce: &str,
+    body: &[u8],
+) -> String {
+    let mut mac = mac(secret);
+    mac.update(canonical_request(method, path_and_query, timestamp, nonce, body).as_bytes());
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+fn mac(secret: &str) -> Hmac<Sha256> {
+    Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC acepta claves de cualquier longitud")
+}
+
+/// Cliente que firmó la petición, disponible en las rutas protegidas con
+/// [`require_signature`]. Responde `401` si la petición no pasó por la verificación.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedClient(pub String);
+
+#[async_trait]
+impl<S> FromRequestParts<S> for SignedClient
+where
+    S: Send + Sync,
+{
+    type Rejection = SignatureError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<SignedClient>()
+            .cloned()
+            .ok_or(SignatureError::Missing)
+    }
+}
+
+/// Middleware que exige una firma válida y deja el cliente en las extensiones como
+/// [`SignedClient`].
+pub async fn require_signature(
+    State(signing): State<RequestSigning>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let (mut parts, body) = request.into_parts();
+    let Ok(body) = axum::body::to_bytes(body, MAX_SIGNED_BODY_BYTES).await else {
+        return SignatureError::TooLarge.into_response();
+    };
+
+    match signing
+        .verify(&parts.method, &parts.uri, &parts.headers, &body)
+        .await
+    {
+        Ok(client) => {
+            parts.extensions.insert(SignedClient(client));
+            next.run(Request::from_parts(parts, Body::from(body))).await
+        }
+        Err(error) => error.into_response(),
+    }
+}
@@ -0,0 +1,195 @@
+//! Activación por socket.
+//!
+//! systemd (con una unidad `.socket`) o un envoltorio de reinicios sin cortes (`systemfd`,
+//! `catflap`...) puede abrir los puertos por su cuenta y pasárselos al proceso ya escuchando,
+//! según el protocolo de `sd_listen_fds`: `LISTEN_PID` lleva el PID al que van dirigidos,
+//! `LISTEN_FDS` cuántos descriptores recibe a partir del 3 y, opcionalmente, `LISTEN_FDNAMES`
+//! sus nombres separados por `:`. Como el socket sigue abierto entre un proceso y el
+//! siguiente, las conexiones que llegan durante un reinicio esperan en su cola en lugar de
+//! rechazarse.
+//!
+//! Si algún socket se llama `http` o `admin` se asignan por nombre: el primero sustituye a
+//! `HOST`/`PORT` y el segundo a `ADMIN_HOST`/`ADMIN_PORT`. Si no, se asignan por orden. Un
+//! socket de administración heredado separa la administración aunque no haya `ADMIN_PORT`.
+//! Los descriptores deben ser sockets TCP en escucha.
+
+use std::net::TcpListener;
+
+use anyhow::{bail, Context, Result};
+
+/// Primer descriptor heredado; los anteriores son la entrada y las salidas estándar.
+pub const LISTEN_FDS_START: i32 = 3;
+
+/// Puerto al que sustituye un socket heredado.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SocketRole {
+    /// La API y el resto de rutas públicas (`PORT`).
+    Public,
+    /// La administración, las métricas y el perfilado (`ADMIN_PORT`).
+    Admin,
+}
+
+impl SocketRole {
+    /// Nombre del socket en `LISTEN_FDNAMES` (`FileDescriptorName=` en la unidad de systemd).
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Public => "http",
+            Self::Admin => "admin",
+        }
+    }
+
+    /// Posición del socket cuando no tienen nombres conocidos.
+    fn position(self) -> usize {
+        match self {
+            Self::Public => 0,
+            Self::Admin => 1,
+        }
+    }
+}
+
+/// Descriptor recibido del proceso que arrancó el servidor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PassedFd {
+    pub fd: i32,
+    /// Nombre en `LISTEN_FDNAMES`, si lo trae.
+    pub name: Option<String>,
+}
+
+/// Descriptores dirigidos al proceso `pid` según las variables que devuelve `lookup`.
+///
+/// Si `LISTEN_PID` no coincide con `pid`, las variables son de otro proceso (por ejemplo, del
+/// padre que las heredó sin limpiarlas) y no hay ninguno.
+pub fn passed_fds(lookup: impl Fn(&str) -> Option<String>, pid: u32) -> Result<Vec<PassedFd>> {
+    let Some(listen_pid) = lookup("LISTEN_PID") else {
+        return Ok(Vec::new());
+    };
+    let listen_pid: u32 = listen_pid
+        .trim()
+        .parse()
+        .with_context(|| format!("LISTEN_PID no es un PID válido: {listen_pid}"))?;
+    if listen_pid != pid {
+        return Ok(Vec::new());
+    }
+
+    let count = lookup("LISTEN_FDS").unwrap_or_default();
+    let count: i32 = count
+        .trim()
+        .parse()
+        .ok()
+        .filter(|count| (0..=i32::MAX - LISTEN_FDS_START).contains(count))
+        .with_context(|| format!("LISTEN_FDS no es un número de descriptores válido: {count}"))?;
+
+    let names: Vec<Option<String>> = match lookup("LISTEN_FDNAMES") {
+        Some(names) => names
+            .split(':')
+            .map(|name| Some(name.to_string()).filter(|name| !name.is_empty()))
+            .collect(),
+        None => vec![None; count as usize],
+    };
+    if names.len() != count as usize {
+        bail!(
+            "LISTEN_FDNAMES trae {} nombres para {count} descriptores",
+            names.len()
+        );
+    }
+
+    Ok((LISTEN_FDS_START..)
+        .zip(names)
+        .map(|(fd, name)| PassedFd { fd, name })
+        .collect())
+}
+
+/// Sockets heredados pendientes de asignar.
+#[derive(Debug, Default)]
+pub struct InheritedListeners {
+    sockets: Vec<Option<(Option<String>, TcpListener)>>,
+}
+
+impl InheritedListeners {
+    /// Sockets con el nombre de cada uno, en el orden en que se recibieron.
+    pub fn new(sockets: Vec<(Option<String>, TcpListener)>) -> Self {
+        Self {
+            sockets: sockets.into_iter().map(Some).collect(),
+        }
+    }
+
+    /// Toma los sockets que el proceso recibió al arrancar. Fuera de Unix falla si se le
+    /// pasa alguno.
+    ///
+    /// Solo debe llamarse una vez: cada descriptor pasa a pertenecer al listener que lo
+    /// envuelve y se cierra con él.
+    pub fn from_env() -> Result<Self> {
+        let fds = passed_fds(|name| std::env::var(name).ok(), std::process::id())?;
+        let mut sockets = Vec::with_capacity(fds.len());
+        for PassedFd { fd, name } in fds {
+            let listener = adopt(fd)?;
+            listener
+                .set_nonblocking(true)
+                .with_context(|| format!("No se pudo preparar el socket heredado {fd}"))?;
+            sockets.push((name, listener));
+        }
+        Ok(Self::new(sockets))
+    }
+
+    /// Retira el socket que corresponde a `role`, si lo hay.
+    pub fn take(&mut self, role: SocketRole) -> Option<TcpListener> {
+        let index = self.index_of(role)?;
+        self.sockets[index].take().map(|(_, listener)| listener)
+    }
+
+    /// Indica si queda un socket para `role` sin retirarlo.
+    pub fn contains(&self, role: SocketRole) -> bool {
+        self.index_of(role).is_some()
+    }
+
+    /// Posición del socket pendiente para `role`: por nombre si alguno tiene uno conocido y,
+    /// si no, por orden.
+    fn index_of(&self, role: SocketRole) -> Option<usize> {
+        let is_known = |name: &Option<String>| {
+            [SocketRole::Public, SocketRole::Admin]
+                .iter()
+                .any(|role| name.as_deref() == Some(role.name()))
+        };
+        let index = if self
+            .sockets
+            .iter()
+            .flatten()
+            .any(|(name, _)| is_known(name))
+        {
+            self.sockets.iter().position(|slot| {
+                slot.as_ref()
+                    .is_some_and(|(name, _)| name.as_deref() == Some(role.name()))
+            })?
+        } else {
+            role.position()
+        };
+        self.sockets.get(index)?.as_ref().map(|_| index)
+    }
+
+    /// Nombres (o `None` si no lo tienen) de los sockets que nadie ha retirado.
+    pub fn unused(&self) -> Vec<Option<String>> {
+        self.sockets
+            .iter()
+            .flatten()
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+}
+
+#[cfg(unix)]
+fn adopt(fd: i32) -> Result<TcpListener> {
+    use std::os::fd::FromRawFd;
+
+    // SAFETY: según el protocolo de `sd_listen_fds`, los descriptores desde el 3 hasta
+    // `LISTEN_FDS` están abiertos y son para este proceso, que no los usa para nada más.
+    let listener = unsafe { TcpListener::from_raw_fd(fd) };
+    listener
+        .local_addr()
+        .with_context(|| format!("El descriptor heredado {fd} no es un socket TCP"))?;
+    Ok(listener)
+}
+
+#[cfg(not(unix))]
+fn adopt(fd: i32) -> Result<TcpListener> {
+    bail!("La activación por socket solo está disponible en Unix (descriptor {fd})")
+}
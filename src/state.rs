@@ -0,0 +1,189 @@
+//! Estado compartido de la aplicación.
+//!
+//! Agrupa los recursos que necesitan los handlers y middlewares (pool de base de datos,
+//! métricas, etc.). Gracias a `FromRef`, cada handler puede seguir extrayendo únicamente
+//! la pieza que necesita, por ejemplo `State<Pool<Sqlite>>`.
+
+use std::sync::Arc;
+
+use axum::extract::FromRef;
+use sqlx::{sqlite::SqliteConnectOptions, SqlitePool};
+
+use crate::{
+    cache::Cache,
+    clock::{Clock, SystemClock},
+    config::AppConfig,
+    database,
+    encryption::EmailEncryption,
+    events::EventBus,
+    ids::{self, IdGenerator},
+    maintenance::Maintenance,
+    metrics::Metrics,
+    outbox::Outbox,
+    storage::{LocalStorage, Storage},
+    tenant::TenantDatabases,
+};
+
+/// Estado global inyectado en el router de Axum.
+#[derive(Debug, Clone)]
+pub struct AppState {
+    pub database_pool: SqlitePool,
+    pub config: Arc<AppConfig>,
+    pub events: EventBus,
+    pub metrics: Metrics,
+    pub outbox: Outbox,
+    pub cache: Cache,
+    pub storage: Arc<dyn Storage>,
+    pub tenant_databases: TenantDatabases,
+    pub ids: Arc<dyn IdGenerator>,
+    pub maintenance: Maintenance,
+    pub email_encryption: EmailEncryption,
+    pub clock: Arc<dyn Clock>,
+}
+
+impl AppState {
+    /// Construye el estado a partir del pool de base de datos y la configuración, con un
+    /// bus de eventos sin suscriptores, métricas vacías, el outbox sin avisos pendientes,
+    /// la caché desactivada, el almacenamiento local por defecto, el generador de
+    /// identificadores configurado, los correos sin cifrar, el reloj del sistema y, si `config`
+    /// lo indica, una base de datos por inquilino.
+    pub fn new(database_pool: SqlitePool, config: AppConfig) -> Self {
+        let metrics = Metrics::new();
+        metrics.watch_pool(database_pool.clone());
+        let tenant_databases = match &config.tenant_database_dir {
+            Some(directory) => TenantDatabases::per_tenant(
+                directory,
+                database::with_pragmas(SqliteConnectOptions::new(), &config),
+                database::pool_options(&config),
+            ),
+            None => TenantDatabases::shared(),
+        };
+        let ids = ids::from_config(&config);
+        let maintenance = Maintenance::new(config.maintenance_mode);
+
+        Self {
+            database_pool,
+            config: Arc::new(config),
+            events: EventBus::new(),
+            cache: Cache::disabled(metrics.clone()),
+            metrics,
+            outbox: Outbox::new(),
+            storage: Arc::new(LocalStorage::new("storage")),
+            tenant_databases,
+            ids,
+            maintenance,
+            email_encryption: EmailEncryption::disabled(),
+            clock: Arc::new(SystemClock),
+        }
+    }
+
+    /// Sustituye la caché desactivada por la indicada.
+    pub fn with_cache(mut self, cache: Cache) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    /// Sustituye el almacenamiento por defecto por el indicado.
+    pub fn with_storage(mut self, storage: Arc<dyn Storage>) -> Self {
+        self.storage = storage;
+        self
+    }
+
+    /// Activa el cifrado de los correos con las claves indicadas.
+    pub fn with_email_encryption(mut self, email_encryption: EmailEncryption) -> Self {
+        self.email_encryption = email_encryption;
+        self
+    }
+
+    /// Sustituye el interruptor del modo de mantenimiento por el indicado.
+    pub fn with_maintenance(mut self, maintenance: Maintenance) -> Self {
+        self.maintenance = maintenance;
+        self
+    }
+
+    /// Sustituye el reloj del sistema por el indicado.
+    pub fn with_clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Base principal seguida de las bases de inquilinos abiertas, para las tareas que deben
+    /// recorrer todos los datos de usuarios.
+    pub async fn database_pools(&self) -> Vec<SqlitePool> {
+        let mut pools = vec![self.database_pool.clone()];
+        pools.extend(self.tenant_databases.open_pools().await);
+        pools
+    }
+}
+
+impl FromRef<AppState> for SqlitePool {
+    fn from_ref(state: &AppState) -> Self {
+        state.database_pool.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<AppConfig> {
+    fn from_ref(state: &AppState) -> Self {
+        state.config.clone()
+    }
+}
+
+impl FromRef<AppState> for EventBus {
+    fn from_ref(state: &AppState) -> Self {
+        state.events.clone()
+    }
+}
+
+impl FromRef<AppState> for Metrics {
+    fn from_ref(state: &AppState) -> Self {
+        state.metrics.clone()
+    }
+}
+
+impl FromRef<AppState> for Outbox {
+    fn from_ref(state: &AppState) -> Self {
+        state.outbox.clone()
+    }
+}
+
+impl FromRef<AppState> for Cache {
+    fn from_ref(state: &AppState) -> Self {
+        state.cache.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<dyn Storage> {
+    fn from_ref(state: &AppState) -> Self {
+        state.storage.clone()
+    }
+}
+
+impl FromRef<AppState> for TenantDatabases {
+    fn from_ref(state: &AppState) -> Self {
+        state.tenant_databases.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<dyn IdGenerator> {
+    fn from_ref(state: &AppState) -> Self {
+        state.ids.clone()
+    }
+}
+
+impl FromRef<AppState> for Maintenance {
+    fn from_ref(state: &AppState) -> Self {
+        state.maintenance.clone()
+    }
+}
+
+impl FromRef<AppState> for EmailEncryption {
+    fn from_ref(state: &AppState) -> Self {
+        state.email_encryption.clone()
+    }
+}
+
+impl FromRef<AppState> for Arc<dyn Clock> {
+    fn from_ref(state: &AppState) -> Self {
+        state.clock.clone()
+    }
+}
@@ -0,0 +1,227 @@
+//! Exportación de métricas a StatsD.
+//!
+//! Para quien no tenga Prometheus, `METRICS_SINK=statsd` (o `dogstatsd`) envía además las
+//! métricas por UDP a `STATSD_ADDR`. No hay una API aparte: [`Metrics`] reenvía a un
+//! [`StatsdSink`] cada contador y cada tiempo que registra, y [`StatsdExporter`] agrupa las
+//! líneas en paquetes y publica cada `STATSD_FLUSH_INTERVAL_MS` los valores instantáneos
+//! (peticiones en curso, conexiones del pool, tiempo activo) como gauges.
+//!
+//! Con `dogstatsd` las métricas llevan etiquetas (`|#route:/users/:id,status:2xx`), así que
+//! también se envía la latencia por ruta; StatsD clásico no admite etiquetas y solo recibe los
+//! totales. El envío nunca bloquea una petición: si el exportador no da abasto, las líneas se
+//! descartan.
+
+use std::{sync::Arc, time::Duration};
+
+use anyhow::{Context, Result};
+use tokio::{net::UdpSocket, sync::mpsc};
+use tracing::debug;
+
+use crate::{config::env_or, metrics::Metrics};
+
+/// Tamaño máximo de cada paquete; cabe en un datagrama sin fragmentar en casi cualquier red.
+const MAX_PACKET_BYTES: usize = 1432;
+/// Líneas pendientes de enviar como máximo.
+const QUEUE_CAPACITY: usize = 10_000;
+
+/// Dialecto del servidor de destino.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatsdFlavor {
+    /// StatsD clásico, sin etiquetas.
+    Plain,
+    /// DogStatsD (Datadog), con etiquetas.
+    Datadog,
+}
+
+/// Destino de las métricas.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatsdConfig {
+    /// `host:puerto` del servidor StatsD (`STATSD_ADDR`).
+    pub address: String,
+    /// Prefijo de todas las métricas (`STATSD_PREFIX`); vacío para no usar ninguno.
+    pub prefix: String,
+    pub flavor: StatsdFlavor,
+    /// Cada cuánto se envían los paquetes y los gauges (`STATSD_FLUSH_INTERVAL_MS`).
+    pub flush_interval: Duration,
+}
+
+impl StatsdConfig {
+    /// Lee `METRICS_SINK`, `STATSD_ADDR`, `STATSD_PREFIX` y `STATSD_FLUSH_INTERVAL_MS`;
+    /// devuelve `None` salvo con `METRICS_SINK=statsd` o `dogstatsd`.
+    pub fn from_env() -> Option<Self> {
+        let flavor = match std::env::var("METRICS_SINK")
+            .unwrap_or_default()
+            .trim()
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "statsd" => StatsdFlavor::Plain,
+            "dogstatsd" => StatsdFlavor::Datadog,
+            _ => return None,
+        };
+
+        Some(Self {
+            address: std::env::var("STATSD_ADDR")
+                .ok()
+                .filter(|address| !address.trim().is_empty())
+                .unwrap_or_else(|| "127.0.0.1:8125".to_string()),
+            prefix: std::env::var("STATSD_PREFIX").unwrap_or_else(|_| "proyecto_rust".to_string()),
+            flavor,
+            flush_interval: Duration::from_millis(env_or("STATSD_FLUSH_INTERVAL_MS", 1000).max(1)),
+        })
+    }
+}
+
+/// Extremo con el que [`Metrics`] envía las métricas al exportador.
+#[derive(Debug, Clone)]
+pub struct StatsdSink {
+    sender: mpsc::Sender<String>,
+    prefix: Arc<str>,
+    flavor: StatsdFlavor,
+}
+
+impl StatsdSink {
+    /// Suma `value` al contador `name`.
+    pub fn count(&self, name: &str, value: u64, tags: &[(&str, &str)]) {
+        self.send(name, &value.to_string(), "c", tags);
+    }
+
+    /// Registra una duración en el temporizador `name`, en milisegundos.
+    pub fn timing(&self, name: &str, duration: Duration, tags: &[(&str, &str)]) {
+        let milliseconds = format!("{:.3}", duration.as_secs_f64() * 1000.0);
+        self.send(name, &milliseconds, "ms", tags);
+    }
+
+    /// Fija el valor del gauge `name`.
+    pub fn gauge(&self, name: &str, value: f64, tags: &[(&str, &str)]) {
+        self.send(name, &value.to_string(), "g", tags);
+    }
+
+    /// Indica si el destino admite etiquetas.
+    pub fn supports_tags(&self) -> bool {
+        self.flavor == StatsdFlavor::Datadog
+    }
+
+    fn send(&self, name: &str, value: &str, kind: &str, tags: &[(&str, &str)]) {
+        let mut line = match self.prefix.as_ref() {
+            "" => format!("{name}:{value}|{kind}"),
+            prefix => format!("{prefix}.{name}:{value}|{kind}"),
+        };
+        if self.supports_tags() && !tags.is_empty() {
+            let tags = tags
+                .iter()
+                .map(|(key, value)| format!("{key}:{}", value.replace([',', '|', '#'], "_")))
+                .collect::<Vec<_>>()
+                .join(",");
+            line.push_str("|#");
+            line.push_str(&tags);
+        }
+
+        // Perder una línea es preferible a frenar la petición que la generó.
+        if self.sender.try_send(line).is_err() {
+            debug!(
+                metric = name,
+                "Cola de StatsD llena; se descarta la métrica"
+            );
+        }
+    }
+}
+
+/// Tarea que envía las líneas pendientes y los gauges periódicos.
+#[derive(Debug)]
+pub struct StatsdExporter {
+    socket: UdpSocket,
+    receiver: mpsc::Receiver<String>,
+    sink: StatsdSink,
+    flush_interval: Duration,
+}
+
+/// Abre el socket hacia `config.address` y devuelve el extremo para [`Metrics::export_to`] y
+/// el exportador que hay que lanzar.
+pub async fn connect(config: &StatsdConfig) -> Result<(StatsdSink, StatsdExporter)> {
+    let address = tokio::net::lookup_host(&config.address)
+        .await
+        .ok()
+        .and_then(|mut addresses| addresses.next())
+        .with_context(|| format!("Dirección de StatsD inválida: {}", config.address))?;
+    let local = if address.is_ipv4() {
+        "0.0.0.0:0"
+    } else {
+        "[::]:0"
+    };
+    let socket = UdpSocket::bind(local)
+        .await
+        .context("No se pudo abrir el socket UDP de StatsD")?;
+    socket
+        .connect(address)
+        .await
+        .with_context(|| format!("No se pudo preparar el envío a StatsD en {address}"))?;
+
+    let (sender, receiver) = mpsc::channel(QUEUE_CAPACITY);
+    let sink = StatsdSink {
+        sender,
+        prefix: config.prefix.trim_end_matches('.').into(),
+        flavor: config.flavor,
+    };
+    let exporter = StatsdExporter {
+        socket,
+        receiver,
+        sink: sink.clone(),
+        flush_interval: config.flush_interval,
+    };
+    Ok((sink, exporter))
+}
+
+impl StatsdExporter {
+    /// Envía las métricas de `metrics` hasta que se cierre el proceso.
+    pub async fn run(mut self, metrics: Metrics) {
+        let mut interval = tokio::time::interval(self.flush_interval);
+        let mut packet = String::new();
+
+        loop {
+            tokio::select! {
+                Some(line) = self.receiver.recv() => {
+                    if !packet.is_empty() && packet.len() + 1 + line.len() > MAX_PACKET_BYTES {
+                        self.flush(&mut packet).await;
+                    }
+                    if !packet.is_empty() {
+                        packet.push('\n');
+                    }
+                    packet.push_str(&line);
+                }
+                _ = interval.tick() => {
+                    publish_gauges(&self.sink, &metrics);
+                    // Los gauges recién encolados salen en la siguiente vuelta.
+                    self.flush(&mut packet).await;
+                }
+            }
+        }
+    }
+
+    async fn flush(&self, packet: &mut String) {
+        if packet.is_empty() {
+            return;
+        }
+        // Sin servidor escuchando el envío falla; las métricas se pierden sin más.
+        if let Err(error) = self.socket.send(packet.as_bytes()).await {
+            debug!(%error, "No se pudieron enviar las métricas a StatsD");
+        }
+        packet.clear();
+    }
+}
+
+/// Encola los valores instantáneos de `metrics`.
+fn publish_gauges(sink: &StatsdSink, metrics: &Metrics) {
+    let snapshot = metrics.snapshot();
+    sink.gauge("http.requests_in_flight", snapshot.in_flight as f64, &[]);
+    sink.gauge("process.uptime_seconds", snapshot.uptime.as_secs_f64(), &[]);
+    if let Some(pool) = snapshot.pool {
+        sink.gauge("db.pool.connections_idle", pool.idle as f64, &[]);
+        sink.gauge(
+            "db.pool.connections_in_use",
+            pool.open.saturating_sub(pool.idle) as f64,
+            &[],
+        );
+        sink.gauge("db.pool.max_connections", pool.max_connections as f64, &[]);
+    }
+}
@@ -0,0 +1,90 @@
+//! Almacén en el disco local.
+
+use std::path::{Component, Path, PathBuf};
+
+use anyhow::{bail, Context, Result};
+use uuid::Uuid;
+
+use super::{Storage, StorageFuture};
+
+/// Almacén en el disco local, pensado para una única instancia o un volumen compartido.
+#[derive(Debug, Clone)]
+pub struct LocalStorage {
+    root: PathBuf,
+}
+
+impl LocalStorage {
+    /// Crea un almacén bajo `root`; los directorios se crean al escribir.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Traduce una clave a una ruta dentro de la raíz, rechazando `..` y rutas absolutas.
+    fn resolve(&self, key: &str) -> Result<PathBuf> {
+        let relative = Path::new(key);
+        let is_safe = !key.is_empty()
+            && relative
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)));
+        if !is_safe {
+            bail!("Clave de almacenamiento inválida: {key}");
+        }
+
+        Ok(self.root.join(relative))
+    }
+}
+
+impl Storage for LocalStorage {
+    fn put<'a>(
+        &'a self,
+        key: &'a str,
+        bytes: Vec<u8>,
+        _content_type: &'a str,
+    ) -> StorageFuture<'a, ()> {
+        Box::pin(async move {
+            let path = self.resolve(key)?;
+            if let Some(parent) = path.parent() {
+                tokio::fs::create_dir_all(parent)
+                    .await
+                    .with_context(|| format!("No se pudo crear {}", parent.display()))?;
+            }
+
+            // Se escribe en un temporal y se renombra para no servir nunca un archivo a medias.
+            let temporary = path.with_extension(format!("tmp-{}", Uuid::new_v4()));
+            tokio::fs::write(&temporary, bytes)
+                .await
+                .with_context(|| format!("No se pudo escribir {}", temporary.display()))?;
+            tokio::fs::rename(&temporary, &path)
+                .await
+                .with_context(|| format!("No se pudo mover a {}", path.display()))?;
+
+            Ok(())
+        })
+    }
+
+    fn get<'a>(&'a self, key: &'a str) -> StorageFuture<'a, Option<Vec<u8>>> {
+        Box::pin(async move {
+            let path = self.resolve(key)?;
+            match tokio::fs::read(&path).await {
+                Ok(bytes) => Ok(Some(bytes)),
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(None),
+                Err(error) => {
+                    Err(error).with_context(|| format!("No se pudo leer {}", path.display()))
+                }
+            }
+        })
+    }
+
+    fn delete<'a>(&'a self, key: &'a str) -> StorageFuture<'a, ()> {
+        Box::pin(async move {
+            let path = self.resolve(key)?;
+            match tokio::fs::remove_file(&path).await {
+                Ok(()) => Ok(()),
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(()),
+                Err(error) => {
+                    Err(error).with_context(|| format!("No se pudo borrar {}", path.display()))
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,116 @@
+//! Almacenamiento de los archivos subidos por los usuarios.
+//!
+//! Los handlers trabajan con claves relativas (por ejemplo `avatars/<id>.png`) a través del
+//! trait [`Storage`], de modo que el backend concreto se elige al arrancar:
+//! [`LocalStorage`] guarda cada clave como un archivo bajo un directorio raíz y
+//! [`S3Storage`] la sube a un bucket S3 o MinIO, donde sobrevive a los reinicios del
+//! contenedor y se puede descargar directamente mediante URLs prefirmadas.
+
+mod local;
+mod s3;
+
+use std::{future::Future, path::PathBuf, pin::Pin, sync::Arc, time::Duration};
+
+use anyhow::{Context, Result};
+
+use crate::config::{env_or, parse_flag};
+
+pub use local::LocalStorage;
+pub use s3::{S3Config, S3Storage};
+
+/// Futuro devuelto por las operaciones de un [`Storage`].
+pub type StorageFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T>> + Send + 'a>>;
+
+/// Almacén de objetos binarios direccionados por clave.
+pub trait Storage: std::fmt::Debug + Send + Sync + 'static {
+    /// Guarda (o reemplaza) el contenido de `key`.
+    fn put<'a>(
+        &'a self,
+        key: &'a str,
+        bytes: Vec<u8>,
+        content_type: &'a str,
+    ) -> StorageFuture<'a, ()>;
+
+    /// Recupera el contenido de `key`, si existe.
+    fn get<'a>(&'a self, key: &'a str) -> StorageFuture<'a, Option<Vec<u8>>>;
+
+    /// Elimina `key`; no falla si ya no existía.
+    fn delete<'a>(&'a self, key: &'a str) -> StorageFuture<'a, ()>;
+
+    /// URL temporal desde la que el cliente puede descargar `key` sin pasar por la API,
+    /// si el backend lo admite.
+    fn presigned_url(&self, _key: &str) -> Option<String> {
+        None
+    }
+}
+
+/// Almacén elegido mediante `STORAGE_BACKEND`.
+#[derive(Debug, Clone)]
+pub enum StorageBackend {
+    Local { directory: PathBuf },
+    S3(S3Config),
+}
+
+/// Configuración del almacenamiento leída desde variables de entorno.
+#[derive(Debug, Clone)]
+pub struct StorageConfig {
+    pub backend: StorageBackend,
+}
+
+impl StorageConfig {
+    /// Lee `STORAGE_BACKEND` (`local` o `s3`) y las variables del backend elegido:
+    /// `STORAGE_DIR` para el local; `S3_BUCKET`, `S3_ACCESS_KEY_ID`, `S3_SECRET_ACCESS_KEY`,
+    /// `S3_REGION`, `S3_ENDPOINT`, `S3_PATH_STYLE` y `STORAGE_PRESIGN_TTL_SECS` para S3.
+    pub fn from_env() -> Result<Self> {
+        let backend = std::env::var("STORAGE_BACKEND").unwrap_or_default();
+        if !backend.trim().eq_ignore_ascii_case("s3") {
+            let directory = std::env::var("STORAGE_DIR").unwrap_or_else(|_| "storage".into());
+            return Ok(Self {
+                backend: StorageBackend::Local {
+                    directory: PathBuf::from(directory),
+                },
+            });
+        }
+
+        let required = |name: &str| {
+            std::env::var(name).with_context(|| format!("Falta {name} para STORAGE_BACKEND=s3"))
+        };
+        let region = std::env::var("S3_REGION").unwrap_or_else(|_| "us-east-1".into());
+        let custom_endpoint = std::env::var("S3_ENDPOINT").ok();
+        // Los endpoints propios (MinIO) suelen requerir direccionamiento por ruta.
+        let path_style = std::env::var("S3_PATH_STYLE")
+            .map(|value| parse_flag(&value))
+            .unwrap_or(custom_endpoint.is_some());
+
+        Ok(Self {
+            backend: StorageBackend::S3(S3Config {
+                endpoint: custom_endpoint
+                    .unwrap_or_else(|| format!("https://s3.{region}.amazonaws.com")),
+                region,
+                bucket: required("S3_BUCKET")?,
+                access_key_id: required("S3_ACCESS_KEY_ID")?,
+                secret_access_key: required("S3_SECRET_ACCESS_KEY")?,
+                path_style,
+                presign_ttl: Duration::from_secs(env_or("STORAGE_PRESIGN_TTL_SECS", 900)),
+            }),
+        })
+    }
+
+    /// Construye el almacén configurado.
+    pub fn build(&self) -> Result<Arc<dyn Storage>> {
+        Ok(match &self.backend {
+            StorageBackend::Local { directory } => Arc::new(LocalStorage::new(directory)),
+            StorageBackend::S3(config) => Arc::new(S3Storage::new(config.clone())?),
+        })
+    }
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            backend: StorageBackend::Local {
+                directory: PathBuf::from("storage"),
+            },
+        }
+    }
+}
@@ -0,0 +1,242 @@
+//! Almacén compatible con S3 (AWS S3, MinIO, etc.).
+//!
+//! Las peticiones se firman con AWS Signature Version 4 (véase [`crate::aws`]). Se admite el direccionamiento por ruta (`endpoint/bucket/key`,
+//! el habitual en MinIO) y el virtual-hosted (`bucket.endpoint/key`, el de AWS).
+
+use std::time::Duration;
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use reqwest::{Method, StatusCode, Url};
+use sha2::{Digest, Sha256};
+
+use super::{Storage, StorageFuture};
+use crate::aws::{self, uri_encode, ALGORITHM};
+use crate::trace_context;
+
+const SERVICE: &str = "s3";
+/// Hash que se firma en las URLs prefirmadas, cuyo cuerpo no se conoce de antemano.
+const UNSIGNED_PAYLOAD: &str = "UNSIGNED-PAYLOAD";
+
+/// Parámetros de conexión con el bucket.
+#[derive(Clone)]
+pub struct S3Config {
+    /// URL base del servicio, por ejemplo `https://s3.eu-west-1.amazonaws.com` o
+    /// `http://minio:9000`.
+    pub endpoint: String,
+    pub region: String,
+    pub bucket: String,
+    pub access_key_id: String,
+    pub secret_access_key: String,
+    /// Usa `endpoint/bucket/key` en lugar de `bucket.endpoint/key`.
+    pub path_style: bool,
+    /// Validez de las URLs prefirmadas de descarga.
+    pub presign_ttl: Duration,
+}
+
+impl std::fmt::Debug for S3Config {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        formatter
+            .debug_struct("S3Config")
+            .field("endpoint", &self.endpoint)
+            .field("region", &self.region)
+            .field("bucket", &self.bucket)
+            .field("access_key_id", &self.access_key_id)
+            .field("secret_access_key", &"***")
+            .field("path_style", &self.path_style)
+            .field("presign_ttl", &self.presign_ttl)
+            .finish()
+    }
+}
+
+/// Almacén sobre un bucket S3, compartido por todas las instancias.
+#[derive(Debug, Clone)]
+pub struct S3Storage {
+    client: reqwest::Client,
+    config: S3Config,
+    scheme: String,
+    /// Host (con puerto, si no es el por defecto) al que se envían las peticiones.
+    host: String,
+}
+
+impl S3Storage {
+    /// Valida el endpoint y prepara el cliente HTTP.
+    pub fn new(config: S3Config) -> Result<Self> {
+        let endpoint = Url::parse(&config.endpoint)
+            .with_context(|| format!("Endpoint de S3 inválido: {}", config.endpoint))?;
+        let Some(endpoint_host) = endpoint.host_str() else {
+            bail!("El endpoint de S3 no tiene host: {}", config.endpoint);
+        };
+
+        let mut host = if config.path_style {
+            endpoint_host.to_string()
+        } else {
+            format!("{}.{endpoint_host}", config.bucket)
+        };
+        if let Some(port) = endpoint.port() {
+            host = format!("{host}:{port}");
+        }
+
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .context("No se pudo crear el cliente HTTP de S3")?;
+
+        Ok(Self {
+            client,
+            scheme: endpoint.scheme().to_string(),
+            host,
+            config,
+        })
+    }
+
+    /// Genera una URL prefirmada para `method` sobre `key`, firmada en el instante `now`.
+    pub fn presigned_url_at(
+        &self,
+        method: &str,
+        key: &str,
+        expires_in: Duration,
+        now: DateTime<Utc>,
+    ) -> String {
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let credential = format!("{}/{}", self.config.access_key_id, self.scope(&date));
+        let path = self.object_path(key);
+
+        let query = format!(
+            "X-Amz-Algorithm={ALGORITHM}&X-Amz-Credential={}&X-Amz-Date={amz_date}\
+             &X-Amz-Expires={}&X-Amz-SignedHeaders=host",
+            uri_encode(&credential, true),
+            expires_in.as_secs(),
+        );
+        let canonical_request = format!(
+            "{method}\n{path}\n{query}\nhost:{}\n\nhost\n{UNSIGNED_PAYLOAD}",
+            self.host
+        );
+        let signature = self.signature(&canonical_request, &amz_date, &date);
+
+        format!(
+            "{}://{}{path}?{query}&X-Amz-Signature={signature}",
+            self.scheme, self.host
+        )
+    }
+
+    /// Envía una petición firmada con cabeceras sobre el objeto `key`.
+    async fn send(
+        &self,
+        method: Method,
+        key: &str,
+        body: Vec<u8>,
+        content_type: Option<&str>,
+    ) -> Result<reqwest::Response> {
+        let now = Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = hex::encode(Sha256::digest(&body));
+        let path = self.object_path(key);
+
+        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+        let canonical_request = format!(
+            "{method}\n{path}\n\nhost:{}\nx-amz-content-sha256:{payload_hash}\n\
+             x-amz-date:{amz_date}\n\n{signed_headers}\n{payload_hash}",
+            self.host
+        );
+        let signature = self.signature(&canonical_request, &amz_date, &date);
+        let authorization = format!(
+            "{ALGORITHM} Credential={}/{}, SignedHeaders={signed_headers}, Signature={signature}",
+            self.config.access_key_id,
+            self.scope(&date),
+        );
+
+        // Las cabeceras de traza no se firman: un proxy intermedio puede reescribirlas.
+        let mut request = trace_context::inject(
+            self.client
+                .request(method, format!("{}://{}{path}", self.scheme, self.host)),
+        )
+        .header("x-amz-date", amz_date)
+        .header("x-amz-content-sha256", payload_hash)
+        .header(reqwest::header::AUTHORIZATION, authorization)
+        .body(body);
+        if let Some(content_type) = content_type {
+            request = request.header(reqwest::header::CONTENT_TYPE, content_type);
+        }
+
+        request
+            .send()
+            .await
+            .with_context(|| format!("Fallo al contactar con S3 para {key}"))
+    }
+
+    /// Ruta canónica del objeto, ya codificada.
+    fn object_path(&self, key: &str) -> String {
+        let key = uri_encode(key, false);
+        if self.config.path_style {
+            format!("/{}/{key}", uri_encode(&self.config.bucket, true))
+        } else {
+            format!("/{key}")
+        }
+    }
+
+    /// Ámbito de la credencial: `<fecha>/<región>/s3/aws4_request`.
+    fn scope(&self, date: &str) -> String {
+        self.signer().scope(date)
+    }
+
+    /// Firma una petición canónica según SigV4.
+    fn signature(&self, canonical_request: &str, amz_date: &str, date: &str) -> String {
+        self.signer().signature(canonical_request, amz_date, date)
+    }
+
+    fn signer(&self) -> aws::Signer<'_> {
+        aws::Signer {
+            secret_access_key: &self.config.secret_access_key,
+            region: &self.config.region,
+            service: SERVICE,
+        }
+    }
+}
+
+impl Storage for S3Storage {
+    fn put<'a>(
+        &'a self,
+        key: &'a str,
+        bytes: Vec<u8>,
+        content_type: &'a str,
+    ) -> StorageFuture<'a, ()> {
+        Box::pin(async move {
+            let response = self
+                .send(Method::PUT, key, bytes, Some(content_type))
+                .await?;
+            if !response.status().is_success() {
+                bail!("S3 respondió {} al guardar {key}", response.status());
+            }
+            Ok(())
+        })
+    }
+
+    fn get<'a>(&'a self, key: &'a str) -> StorageFuture<'a, Option<Vec<u8>>> {
+        Box::pin(async move {
+            let response = self.send(Method::GET, key, Vec::new(), None).await?;
+            match response.status() {
+                StatusCode::NOT_FOUND => Ok(None),
+                status if status.is_success() => Ok(Some(response.bytes().await?.to_vec())),
+                status => bail!("S3 respondió {status} al leer {key}"),
+            }
+        })
+    }
+
+    fn delete<'a>(&'a self, key: &'a str) -> StorageFuture<'a, ()> {
+        Box::pin(async move {
+            let response = self.send(Method::DELETE, key, Vec::new(), None).await?;
+            let status = response.status();
+            if !status.is_success() && status != StatusCode::NOT_FOUND {
+                bail!("S3 respondió {status} al borrar {key}");
+            }
+            Ok(())
+        })
+    }
+
+    fn presigned_url(&self, key: &str) -> Option<String> {
+        Some(self.presigned_url_at("GET", key, self.config.presign_ttl, Utc::now()))
+    }
+}
@@ -0,0 +1,165 @@
+//! Registro de bases de datos SQLite por inquilino.
+//!
+//! Con `TENANT_DATABASE_DIR` definido, cada inquilino guarda sus usuarios en su propio
+//! archivo `<directorio>/<tenant>.sqlite` en lugar de compartir la base principal. Los pools
+//! se abren bajo demanda: la primera petición de un inquilino crea el archivo si no existe
+//! y le aplica las migraciones. La base principal sigue guardando el registro de
+//! inquilinos, los webhooks y la cola de trabajos.
+
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
+
+use anyhow::{bail, Context, Result};
+use sqlx::{
+    sqlite::{SqliteConnectOptions, SqlitePoolOptions},
+    SqlitePool,
+};
+use tokio::sync::Mutex;
+use tracing::info;
+
+use crate::fuzzy_search;
+use crate::migrations;
+
+/// Registro compartido de pools por inquilino; desactivado, todos usan la base principal.
+#[derive(Debug, Clone)]
+pub struct TenantDatabases {
+    directory: Option<PathBuf>,
+    /// Opciones, con los pragmas configurados, con las que se abre cada base.
+    connect_options: SqliteConnectOptions,
+    pool_options: SqlitePoolOptions,
+    pools: Arc<Mutex<HashMap<String, SqlitePool>>>,
+}
+
+impl TenantDatabases {
+    /// Registro desactivado: todos los inquilinos comparten la base principal.
+    pub fn shared() -> Self {
+        Self {
+            directory: None,
+            connect_options: SqliteConnectOptions::new(),
+            pool_options: SqlitePoolOptions::new(),
+            pools: Arc::default(),
+        }
+    }
+
+    /// Registro con un archivo por inquilino bajo `directory`, abierto con `connect_options`
+    /// en pools configurados con `pool_options`.
+    pub fn per_tenant(
+        directory: impl Into<PathBuf>,
+        connect_options: SqliteConnectOptions,
+        pool_options: SqlitePoolOptions,
+    ) -> Self {
+        Self {
+            directory: Some(directory.into()),
+            connect_options,
+            pool_options,
+            pools: Arc::default(),
+        }
+    }
+
+    /// Indica si cada inquilino tiene su propia base de datos.
+    pub fn is_enabled(&self) -> bool {
+        self.directory.is_some()
+    }
+
+    /// Pool del inquilino, creando y migrando su base si aún no existe, o `None` si el
+    /// registro está desactivado.
+    pub async fn pool(&self, tenant_id: &str) -> Result<Option<SqlitePool>> {
+        let Some(directory) = &self.directory else {
+            return Ok(None);
+        };
+
+        // El bloqueo se mantiene durante la creación para no migrar dos veces el mismo archivo.
+        let mut pools = self.pools.lock().await;
+        if let Some(pool) = pools.get(tenant_id) {
+            return Ok(Some(pool.clone()));
+        }
+
+        let path = database_path(directory, tenant_id)?;
+        tokio::fs::create_dir_all(directory)
+            .await
+            .with_context(|| format!("No se pudo crear {}", directory.display()))?;
+        let pool = self
+            .pool_options
+            .clone()
+            .connect_with(
+                self.connect_options
+                    .clone()
+                    .filename(&path)
+                    .create_if_missing(true),
+            )
+            .await
+            .with_context(|| format!("No se pudo abrir la base de {tenant_id}"))?;
+        migrations::MIGRATOR
+            .run(&pool)
+            .await
+            .with_context(|| format!("Fallo al migrar la base de {tenant_id}"))?;
+        fuzzy_search::index_missing(&pool).await.with_context(|| {
+            format!("No se pudo completar el índice de búsqueda de {tenant_id}")
+        })?;
+
+        info!(tenant = tenant_id, path = %path.display(), "Base de datos del inquilino abierta");
+        pools.insert(tenant_id.to_string(), pool.clone());
+
+        Ok(Some(pool))
+    }
+
+    /// Abre (y migra) las bases de todos los inquilinos registrados en `registry`, para que
+    /// las tareas de mantenimiento los recorran desde el arranque.
+    pub async fn open_all(&self, registry: &SqlitePool) -> Result<()> {
+        if !self.is_enabled() {
+            return Ok(());
+        }
+
+        let tenant_ids = sqlx::query_scalar::<_, String>("SELECT id FROM tenants")
+            .fetch_all(registry)
+            .await?;
+        for tenant_id in tenant_ids {
+            self.pool(&tenant_id).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Pools de los inquilinos abiertos hasta ahora, para las tareas de mantenimiento.
+    pub async fn open_pools(&self) -> Vec<SqlitePool> {
+        self.pools.lock().await.values().cloned().collect()
+    }
+
+    /// Cierra el pool del inquilino y borra su archivo junto con los auxiliares de SQLite.
+    pub async fn remove(&self, tenant_id: &str) -> Result<()> {
+        let Some(directory) = &self.directory else {
+            return Ok(());
+        };
+
+        if let Some(pool) = self.pools.lock().await.remove(tenant_id) {
+            pool.close().await;
+        }
+
+        let path = database_path(directory, tenant_id)?;
+        for suffix in ["", "-wal", "-shm"] {
+            let file = PathBuf::from(format!("{}{suffix}", path.display()));
+            match tokio::fs::remove_file(&file).await {
+                Ok(()) => {}
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
+                Err(error) => {
+                    return Err(error)
+                        .with_context(|| format!("No se pudo borrar {}", file.display()))
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Ruta del archivo de un inquilino; el identificador nunca puede salir del directorio.
+fn database_path(directory: &std::path::Path, tenant_id: &str) -> Result<PathBuf> {
+    let is_safe = !tenant_id.is_empty()
+        && tenant_id
+            .bytes()
+            .all(|byte| byte.is_ascii_lowercase() || byte.is_ascii_digit() || byte == b'-');
+    if !is_safe {
+        bail!("Identificador de inquilino inválido: {tenant_id}");
+    }
+
+    Ok(directory.join(format!("{tenant_id}.sqlite")))
+}
@@ -0,0 +1,152 @@
+//! Resolución del inquilino (tenant) de cada petición.
+//!
+//! El middleware [`resolve_tenant`] determina el inquilino a partir de la cabecera
+//! `X-Tenant-Id` o, si está configurado `TENANT_BASE_DOMAIN`, del subdominio del `Host`
+//! (`acme.example.com` → `acme`). Sin ninguno de los dos se usa el inquilino `default`.
+//! Un inquilino que no existe en la tabla `tenants` se rechaza antes de llegar al handler,
+//! y los handlers filtran cada consulta sobre `users` por el [`Tenant`] resuelto. Si los
+//! inquilinos tienen base propia ([`TenantDatabases`]), el middleware deja además su pool
+//! en la petición y los handlers lo obtienen mediante [`Database`]. El middleware también
+//! aplica la cuota diaria de peticiones y deja la [`TenantQuota`] del inquilino para los
+//! handlers que crean usuarios.
+
+mod databases;
+pub mod quota;
+
+use std::sync::Arc;
+
+use anyhow::anyhow;
+use axum::{
+    async_trait,
+    extract::{FromRef, FromRequestParts, Request, State},
+    http::{header, request::Parts, HeaderMap},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use sqlx::SqlitePool;
+
+use crate::clock::Clock;
+use crate::config::AppConfig;
+use crate::handlers::error::AppError;
+
+pub use databases::TenantDatabases;
+pub use quota::{QuotaExceeded, QuotaKind, TenantQuota};
+
+/// Cabecera con el identificador del inquilino.
+pub const TENANT_HEADER: &str = "x-tenant-id";
+
+/// Inquilino al que pertenecen los usuarios creados antes de la multi-tenencia y el que se
+/// usa cuando la petición no indica ninguno.
+pub const DEFAULT_TENANT: &str = "default";
+
+/// Inquilino resuelto para la petición en curso.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tenant(pub String);
+
+impl Tenant {
+    /// Identificador del inquilino tal como se guarda en `users.tenant_id`.
+    pub fn id(&self) -> &str {
+        &self.0
+    }
+}
+
+/// Base de datos con la que opera la petición: la del inquilino si tiene una propia o, si
+/// no, la principal.
+#[derive(Debug, Clone)]
+pub struct Database(pub SqlitePool);
+
+/// Middleware que resuelve el inquilino y lo deja en las extensiones de la petición.
+pub async fn resolve_tenant(
+    State(database_pool): State<SqlitePool>,
+    State(config): State<Arc<AppConfig>>,
+    State(tenant_databases): State<TenantDatabases>,
+    State(clock): State<Arc<dyn Clock>>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let tenant_id = requested_tenant(request.headers(), config.tenant_base_domain.as_deref())
+        .unwrap_or_else(|| DEFAULT_TENANT.to_string());
+
+    let limits = sqlx::query_as::<_, (Option<i64>, Option<i64>)>(
+        "SELECT max_users, max_requests_per_day FROM tenants WHERE id = ?",
+    )
+    .bind(&tenant_id)
+    .fetch_optional(&database_pool)
+    .await;
+    let quota = match limits {
+        Ok(Some((max_users, max_requests_per_day))) => TenantQuota {
+            max_users,
+            max_requests_per_day,
+        },
+        Ok(None) => return AppError::bad_request("Tenant desconocido").into_response(),
+        Err(error) => return AppError::from(error).into_response(),
+    };
+
+    if let Err(error) = quota::record_request(&database_pool, &tenant_id, quota, clock.now()).await
+    {
+        return error.into_response();
+    }
+
+    match tenant_databases.pool(&tenant_id).await {
+        Ok(Some(pool)) => {
+            request.extensions_mut().insert(Database(pool));
+        }
+        Ok(None) => {}
+        Err(error) => return AppError::internal(error).into_response(),
+    }
+
+    request.extensions_mut().insert(quota);
+    request.extensions_mut().insert(Tenant(tenant_id));
+    next.run(request).await
+}
+
+/// Inquilino indicado por la cabecera o, en su defecto, por el subdominio.
+fn requested_tenant(headers: &HeaderMap, base_domain: Option<&str>) -> Option<String> {
+    let from_header = headers
+        .get(TENANT_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.trim().to_ascii_lowercase())
+        .filter(|value| !value.is_empty());
+    if from_header.is_some() {
+        return from_header;
+    }
+
+    let base_domain = base_domain?;
+    let host = headers.get(header::HOST)?.to_str().ok()?;
+    let host = host.split(':').next().unwrap_or(host).to_ascii_lowercase();
+    let subdomain = host.strip_suffix(base_domain)?.strip_suffix('.')?;
+
+    // Solo un nivel: `a.b.example.com` no corresponde a ningún inquilino.
+    (!subdomain.is_empty() && !subdomain.contains('.')).then(|| subdomain.to_string())
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Tenant
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts.extensions.get::<Tenant>().cloned().ok_or_else(|| {
+            AppError::internal(anyhow!("La ruta no pasa por el middleware de tenants"))
+        })
+    }
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for Database
+where
+    SqlitePool: FromRef<S>,
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        Ok(parts
+            .extensions
+            .get::<Database>()
+            .cloned()
+            .unwrap_or_else(|| Database(SqlitePool::from_ref(state))))
+    }
+}
@@ -0,0 +1,179 @@
+//! Cuotas de uso por inquilino.
+//!
+//! Cada inquilino puede limitar su número de usuarios (`max_users`) y las peticiones que
+//! atiende por día UTC (`max_requests_per_day`). El middleware de resolución cuenta las
+//! peticiones y rechaza con `429` las que superan la cuota diaria; los handlers que crean
+//! usuarios comprueban [`TenantQuota::ensure_user_capacity`] antes de confirmar y responden
+//! `403` si el inquilino se quedaría por encima de su máximo.
+
+use anyhow::anyhow;
+use axum::{async_trait, extract::FromRequestParts, http::request::Parts};
+use chrono::{DateTime, Days, NaiveDate, Utc};
+use serde::Serialize;
+use sqlx::{Executor, Sqlite, SqlitePool};
+
+use super::Tenant;
+use crate::handlers::error::AppError;
+
+/// Límites del inquilino resuelto para la petición en curso.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TenantQuota {
+    pub max_users: Option<i64>,
+    pub max_requests_per_day: Option<i64>,
+}
+
+/// Cuota concreta que se ha superado.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotaKind {
+    MaxUsers,
+    MaxRequestsPerDay,
+}
+
+/// Detalle de una cuota superada, tal como se devuelve en el cuerpo del error.
+#[derive(Debug, Clone, Serialize)]
+pub struct QuotaExceeded {
+    pub quota: QuotaKind,
+    pub limit: i64,
+    pub used: i64,
+    /// Unidades que pedía la operación rechazada, cuando puede pedir varias.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requested: Option<i64>,
+    /// Momento en que la cuota vuelve a estar disponible, si se renueva sola.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resets_at: Option<DateTime<Utc>>,
+}
+
+impl TenantQuota {
+    /// Responde `403` si, con los `created` usuarios recién insertados, el inquilino supera
+    /// su máximo.
+    ///
+    /// Se llama dentro de la transacción que crea los usuarios, después de insertarlos, para
+    /// que el recuento incluya las filas nuevas y la transacción se descarte si sobran.
+    pub async fn ensure_user_capacity<'e, E>(
+        &self,
+        executor: E,
+        tenant: &Tenant,
+        created: i64,
+    ) -> Result<(), AppError>
+    where
+        E: Executor<'e, Database = Sqlite>,
+    {
+        let Some(limit) = self.max_users else {
+            return Ok(());
+        };
+
+        let total = count_users(executor, tenant.id()).await?;
+        if total > limit {
+            return Err(AppError::quota_exceeded(QuotaExceeded {
+                quota: QuotaKind::MaxUsers,
+                limit,
+                used: total - created,
+                requested: Some(created),
+                resets_at: None,
+            }));
+        }
+
+        Ok(())
+    }
+}
+
+/// Cuenta una petición del inquilino en el día en curso, salvo que ya haya agotado su
+/// cuota diaria, en cuyo caso devuelve el detalle para responder `429`.
+pub(super) async fn record_request(
+    database_pool: &SqlitePool,
+    tenant_id: &str,
+    quota: TenantQuota,
+    now: DateTime<Utc>,
+) -> Result<(), AppError> {
+    let today = now.date_naive();
+
+    // El `WHERE` del upsert solo se evalúa si ya hay fila del día: una cuota nula se
+    // rechaza antes para que tampoco deje pasar la primera petición.
+    if let Some(limit) = quota.max_requests_per_day.filter(|limit| *limit <= 0) {
+        return Err(daily_quota_exceeded(limit, today));
+    }
+
+    // El `WHERE` del upsert deja la fila intacta (y sin `RETURNING`) si la cuota está agotada.
+    let counted = sqlx::query_scalar::<_, i64>(
+        "INSERT INTO tenant_usage (tenant_id, day, requests) VALUES (?1, ?2, 1) \
+         ON CONFLICT (tenant_id, day) DO UPDATE SET requests = requests + 1 \
+         WHERE ?3 IS NULL OR requests < ?3 \
+         RETURNING requests",
+    )
+    .bind(tenant_id)
+    .bind(today.to_string())
+    .bind(quota.max_requests_per_day)
+    .fetch_optional(database_pool)
+    .await
+    .map_err(AppError::from)?;
+
+    match (counted, quota.max_requests_per_day) {
+        (Some(_), _) | (None, None) => Ok(()),
+        (None, Some(limit)) => Err(daily_quota_exceeded(limit, today)),
+    }
+}
+
+/// Error `429` de la cuota diaria de peticiones agotada en `today`.
+fn daily_quota_exceeded(limit: i64, today: NaiveDate) -> AppError {
+    AppError::quota_exceeded(QuotaExceeded {
+        quota: QuotaKind::MaxRequestsPerDay,
+        limit,
+        used: limit,
+        requested: None,
+        resets_at: Some(next_reset(today)),
+    })
+}
+
+/// Peticiones atendidas por el inquilino en el día indicado.
+pub async fn requests_on(
+    database_pool: &SqlitePool,
+    tenant_id: &str,
+    day: NaiveDate,
+) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar::<_, i64>(
+        "SELECT COALESCE(SUM(requests), 0) FROM tenant_usage WHERE tenant_id = ? AND day = ?",
+    )
+    .bind(tenant_id)
+    .bind(day.to_string())
+    .fetch_one(database_pool)
+    .await
+}
+
+/// Usuarios que pertenecen al inquilino.
+pub async fn count_users<'e, E>(executor: E, tenant_id: &str) -> Result<i64, AppError>
+where
+    E: Executor<'e, Database = Sqlite>,
+{
+    sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM users WHERE tenant_id = ?")
+        .bind(tenant_id)
+        .fetch_one(executor)
+        .await
+        .map_err(AppError::from)
+}
+
+/// Medianoche UTC siguiente a `day`, cuando se renueva la cuota diaria.
+pub fn next_reset(day: NaiveDate) -> DateTime<Utc> {
+    day.checked_add_days(Days::new(1))
+        .and_then(|next_day| next_day.and_hms_opt(0, 0, 0))
+        .map(|midnight| midnight.and_utc())
+        .unwrap_or_else(Utc::now)
+}
+
+#[async_trait]
+impl<S> FromRequestParts<S> for TenantQuota
+where
+    S: Send + Sync,
+{
+    type Rejection = AppError;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        parts
+            .extensions
+            .get::<TenantQuota>()
+            .copied()
+            .ok_or_else(|| {
+                AppError::internal(anyhow!("La ruta no pasa por el middleware de tenants"))
+            })
+    }
+}
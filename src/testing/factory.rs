@@ -0,0 +1,150 @@
+//! Fábricas de datos de prueba.
+//!
+//! Insertan filas directamente en la base, sin pasar por la API, con valores por defecto
+//! válidos y únicos que cada prueba sustituye solo donde le importan:
+//!
+//! ```ignore
+//! let ada = UserFactory::new()
+//!     .with_email("ada@example.com")
+//!     .create(&pool)
+//!     .await;
+//! ```
+//!
+//! Al no pasar por los handlers no se generan eventos del outbox ni webhooks; las pruebas que
+//! dependen de ellos deben crear los usuarios con [`super::TestContext::create_user`].
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use chrono::{DateTime, Utc};
+use sqlx::SqlitePool;
+use uuid::Uuid;
+
+use crate::{
+    encryption::EmailEncryption,
+    fuzzy_search,
+    models::user::{User, UserStatus},
+};
+
+/// Contador compartido para que los valores por defecto no se repitan dentro del proceso.
+static SEQUENCE: AtomicUsize = AtomicUsize::new(0);
+
+/// Constructor de usuarios de prueba.
+#[derive(Debug, Clone)]
+pub struct UserFactory {
+    id: Uuid,
+    name: String,
+    email: String,
+    tenant_id: String,
+    status: UserStatus,
+    created_at: DateTime<Utc>,
+    encryption: EmailEncryption,
+}
+
+impl UserFactory {
+    /// Usuario activo del inquilino `default`, creado ahora, con nombre y correo únicos
+    /// (`Usuario 7`, `usuario7@example.com`).
+    pub fn new() -> Self {
+        let sequence = SEQUENCE.fetch_add(1, Ordering::Relaxed);
+        Self {
+            id: Uuid::now_v7(),
+            name: format!("Usuario {sequence}"),
+            email: format!("usuario{sequence}@example.com"),
+            tenant_id: "default".to_string(),
+            status: UserStatus::Active,
+            created_at: Utc::now(),
+            encryption: EmailEncryption::disabled(),
+        }
+    }
+
+    pub fn with_id(mut self, id: Uuid) -> Self {
+        self.id = id;
+        self
+    }
+
+    pub fn with_name(mut self, name: impl Into<String>) -> Self {
+        self.name = name.into();
+        self
+    }
+
+    /// Correo tal como lo guardaría la API: ya normalizado.
+    pub fn with_email(mut self, email: impl Into<String>) -> Self {
+        self.email = email.into();
+        self
+    }
+
+    /// Inquilino del usuario; debe existir en `tenants`.
+    pub fn with_tenant(mut self, tenant_id: impl Into<String>) -> Self {
+        self.tenant_id = tenant_id.into();
+        self
+    }
+
+    pub fn with_status(mut self, status: UserStatus) -> Self {
+        self.status = status;
+        self
+    }
+
+    pub fn with_created_at(mut self, created_at: DateTime<Utc>) -> Self {
+        self.created_at = created_at;
+        self
+    }
+
+    /// Guarda el correo cifrado y con su índice ciego, como con `EMAIL_ENCRYPTION_KEY`.
+    pub fn with_encryption(mut self, encryption: &EmailEncryption) -> Self {
+        self.encryption = encryption.clone();
+        self
+    }
+
+    /// Inserta el usuario, con su entrada en el índice de búsqueda aproximada, y lo devuelve
+    /// con el correo en claro; falla si no se puede insertar.
+    pub async fn create(self, pool: &SqlitePool) -> User {
+        let mut transaction = pool.begin().await.expect("No se pudo abrir la transacción");
+        sqlx::query(
+            "INSERT INTO users (id, tenant_id, name, email, email_index, status, created_at) \
+             VALUES (?, ?, ?, ?, ?, ?, ?)",
+        )
+        .bind(self.id)
+        .bind(&self.tenant_id)
+        .bind(&self.name)
+        .bind(self.encryption.seal(&self.email))
+        .bind(self.encryption.blind_index(&self.email))
+        .bind(self.status)
+        .bind(self.created_at)
+        .execute(&mut *transaction)
+        .await
+        .expect("No se pudo insertar el usuario de prueba");
+        fuzzy_search::index_user(&mut transaction, &self.tenant_id, self.id, &self.name)
+            .await
+            .expect("No se pudo indexar el usuario de prueba");
+        transaction
+            .commit()
+            .await
+            .expect("No se pudo insertar el usuario de prueba");
+
+        User {
+            id: self.id,
+            name: self.name,
+            email: self.email,
+            created_at: self.created_at,
+            status: self.status,
+            suspended_until: None,
+            suspension_reason: None,
+            avatar_url: None,
+            tenant_id: self.tenant_id,
+        }
+    }
+
+    /// Inserta `count` usuarios con los valores por defecto.
+    pub async fn create_many(pool: &SqlitePool, count: usize) -> Vec<User> {
+        let mut users = Vec::with_capacity(count);
+        for _ in 0..count {
+            users.push(Self::new().create(pool).await);
+        }
+        users
+    }
+}
+
+impl Default for UserFactory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
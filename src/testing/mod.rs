@@ -0,0 +1,320 @@
+//! Utilidades para pruebas de integración (feature `testing`).
+//!
+//! [`TestContext`] monta la aplicación sobre una base SQLite en memoria con las migraciones
+//! aplicadas, el almacenamiento en un directorio temporal y el relay del outbox en marcha, y
+//! ofrece atajos para enviar peticiones y crear datos de prueba. Las pruebas de este crate lo
+//! usan a través de la dev-dependency sobre sí mismo; otros crates lo obtienen activando la
+//! feature:
+//!
+//! ```toml
+//! [dev-dependencies]
+//! rust_web_demo = { path = "...", features = ["testing"] }
+//! ```
+//!
+//! Los atajos están pensados para pruebas: ante un fallo inesperado hacen `panic!` en lugar de
+//! devolver un error.
+//!
+//! Cada prueba recibe su propia base en memoria, así que ninguna ve los datos de otra ni hace
+//! falta deshacer nada al terminar. Para no aplicar todas las migraciones en cada una,
+//! [`test_pool`] copia una plantilla ya migrada ([`migrated_template`]), que se crea una vez por
+//! versión de las migraciones en el directorio temporal y se reutiliza entre ejecuciones. Los
+//! datos que no necesitan pasar por la API se insertan con las fábricas de [`factory`].
+
+pub mod factory;
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use axum::{
+    body::Body,
+    http::{self, Request, StatusCode},
+    middleware, Router,
+};
+use http_body_util::BodyExt;
+use serde::de::DeserializeOwned;
+use sha2::{Digest, Sha256};
+use sqlx::{
+    sqlite::{SqliteConnectOptions, SqlitePoolOptions},
+    SqliteConnection, SqlitePool,
+};
+use tokio::sync::OnceCell;
+
+use crate::{
+    config::AppConfig, handlers::error, ids, metrics, migrations, models::user::User, outbox,
+    routes, state::AppState, storage::LocalStorage, trace_context,
+};
+
+static TEMPLATE: OnceCell<PathBuf> = OnceCell::const_new();
+
+/// Pool sobre una base SQLite en memoria con todas las migraciones aplicadas, copiada de
+/// [`migrated_template`].
+///
+/// Usa una única conexión: cada conexión a `sqlite::memory:` abriría una base distinta.
+pub async fn test_pool() -> SqlitePool {
+    let template = migrated_template().await;
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .expect("No se pudo abrir la base en memoria");
+    let mut connection = pool.acquire().await.expect("Pool sin conexión");
+    copy_database(&mut connection, template)
+        .await
+        .expect("No se pudo copiar la plantilla migrada");
+    drop(connection);
+    pool
+}
+
+/// Archivo SQLite con todas las migraciones aplicadas y sin más datos que los que ellas
+/// insertan.
+///
+/// Su nombre incluye un resumen de las migraciones, así que cambiar cualquiera crea una
+/// plantilla nueva. Se escribe con otro nombre y se renombra al terminar, de modo que varios
+/// binarios de prueba pueden crearla a la vez sin ver nunca una a medias.
+pub async fn migrated_template() -> &'static Path {
+    TEMPLATE.get_or_init(create_template).await
+}
+
+async fn create_template() -> PathBuf {
+    let mut fingerprint = Sha256::new();
+    for migration in migrations::MIGRATOR.iter() {
+        fingerprint.update(migration.version.to_le_bytes());
+        fingerprint.update(&migration.checksum);
+    }
+    let fingerprint = hex::encode(fingerprint.finalize());
+    let path =
+        std::env::temp_dir().join(format!("rust_web_demo-template-{}.db", &fingerprint[..16]));
+    if path.exists() {
+        return path;
+    }
+
+    let partial = path.with_extension(format!("{}.tmp", uuid::Uuid::new_v4()));
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(
+            SqliteConnectOptions::new()
+                .filename(&partial)
+                .create_if_missing(true),
+        )
+        .await
+        .expect("No se pudo crear la plantilla de la base de pruebas");
+    migrations::MIGRATOR
+        .run(&pool)
+        .await
+        .expect("No se pudieron aplicar las migraciones");
+    pool.close().await;
+    std::fs::rename(&partial, &path).expect("No se pudo guardar la plantilla migrada");
+    path
+}
+
+/// Copia en la base de `connection`, vacía, el esquema y los datos de la base de `source`.
+async fn copy_database(connection: &mut SqliteConnection, source: &Path) -> sqlx::Result<()> {
+    // La base en memoria se abre con `SQLITE_OPEN_MEMORY`, que `ATTACH` heredaría si no se
+    // indica el modo en una URI: la plantilla se vería vacía.
+    let source = source
+        .to_string_lossy()
+        .replace('%', "%25")
+        .replace('?', "%3f")
+        .replace('#', "%23");
+    sqlx::query("ATTACH DATABASE ? AS template")
+        .bind(format!("file:{source}?mode=ro"))
+        .execute(&mut *connection)
+        .await?;
+    let objects: Vec<(String, String, String)> = sqlx::query_as(
+        "SELECT type, name, sql FROM template.sqlite_master \
+         WHERE sql IS NOT NULL AND name NOT LIKE 'sqlite_%' ORDER BY rowid",
+    )
+    .fetch_all(&mut *connection)
+    .await?;
+
+    // Las tablas se llenan antes de crear los triggers, que si no se dispararían al copiar.
+    sqlx::query("PRAGMA foreign_keys = OFF")
+        .execute(&mut *connection)
+        .await?;
+    sqlx::query("BEGIN").execute(&mut *connection).await?;
+    for (_, name, sql) in objects.iter().filter(|(kind, ..)| kind == "table") {
+        sqlx::query(sql).execute(&mut *connection).await?;
+        sqlx::query(&format!(
+            "INSERT INTO main.\"{name}\" SELECT * FROM template.\"{name}\""
+        ))
+        .execute(&mut *connection)
+        .await?;
+    }
+    let has_sequences: bool = sqlx::query_scalar(
+        "SELECT EXISTS (SELECT 1 FROM template.sqlite_master WHERE name = 'sqlite_sequence')",
+    )
+    .fetch_one(&mut *connection)
+    .await?;
+    if has_sequences {
+        sqlx::query("INSERT INTO main.sqlite_sequence SELECT * FROM template.sqlite_sequence")
+            .execute(&mut *connection)
+            .await?;
+    }
+    for (_, _, sql) in objects.iter().filter(|(kind, ..)| kind != "table") {
+        sqlx::query(sql).execute(&mut *connection).await?;
+    }
+    sqlx::query("COMMIT").execute(&mut *connection).await?;
+    sqlx::query("PRAGMA foreign_keys = ON")
+        .execute(&mut *connection)
+        .await?;
+    sqlx::query("DETACH DATABASE template")
+        .execute(&mut *connection)
+        .await?;
+    Ok(())
+}
+
+/// Aplicación de prueba: el router completo y el estado con el que se construyó.
+pub struct TestContext {
+    pub app: Router,
+    pub state: AppState,
+}
+
+impl TestContext {
+    /// Contexto con la configuración por defecto.
+    pub async fn new() -> Self {
+        Self::with_config(AppConfig::default()).await
+    }
+
+    /// Contexto con la configuración indicada.
+    pub async fn with_config(config: AppConfig) -> Self {
+        Self::from_state(AppState::new(test_pool().await, config))
+    }
+
+    /// Contexto a partir de un estado ya preparado (caché, reloj, cifrado...). El
+    /// almacenamiento se sustituye por un directorio temporal propio de la prueba.
+    pub fn from_state(state: AppState) -> Self {
+        let storage_dir =
+            std::env::temp_dir().join(format!("storage-test-{}", uuid::Uuid::new_v4()));
+        let state = state.with_storage(Arc::new(LocalStorage::new(storage_dir)));
+        tokio::spawn(outbox::run_relay(state.clone()));
+
+        let app = routes::api_routes(&state)
+            .merge(routes::health_routes())
+            .merge(routes::metrics_routes())
+            .merge(routes::root_route())
+            .fallback(error::not_found_fallback)
+            .layer(middleware::from_fn_with_state(
+                state.clone(),
+                metrics::track_requests,
+            ))
+            .layer(middleware::from_fn_with_state(
+                state.clone(),
+                ids::scope_public_ids,
+            ))
+            .layer(middleware::from_fn(trace_context::propagate_trace_context))
+            .with_state(state.clone());
+
+        Self { app, state }
+    }
+
+    /// Envía `request` con las mismas capas externas que el servidor: la selección de versión
+    /// por cabecera y los `405` en JSON.
+    pub async fn request(&self, request: Request<Body>) -> http::Response<Body> {
+        let app = tower::Layer::layer(
+            &middleware::from_fn(error::json_method_not_allowed),
+            self.app.clone(),
+        );
+        let app = tower::Layer::layer(&middleware::map_request(routes::select_api_version), app);
+        tower::ServiceExt::oneshot(app, request)
+            .await
+            .expect("El router no puede fallar")
+    }
+
+    /// `GET uri`.
+    pub async fn get(&self, uri: &str) -> http::Response<Body> {
+        self.request(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+    }
+
+    /// `DELETE uri`.
+    pub async fn delete(&self, uri: &str) -> http::Response<Body> {
+        self.request(
+            Request::builder()
+                .method(http::Method::DELETE)
+                .uri(uri)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+    }
+
+    /// `POST uri` con `payload` como cuerpo JSON.
+    pub async fn post_json(&self, uri: &str, payload: serde_json::Value) -> http::Response<Body> {
+        self.send_json(http::Method::POST, uri, payload).await
+    }
+
+    /// `PUT uri` con `payload` como cuerpo JSON.
+    pub async fn put_json(&self, uri: &str, payload: serde_json::Value) -> http::Response<Body> {
+        self.send_json(http::Method::PUT, uri, payload).await
+    }
+
+    /// `PATCH uri` con `payload` como cuerpo JSON.
+    pub async fn patch_json(&self, uri: &str, payload: serde_json::Value) -> http::Response<Body> {
+        self.send_json(http::Method::PATCH, uri, payload).await
+    }
+
+    /// Petición con `payload` como cuerpo JSON.
+    pub async fn send_json(
+        &self,
+        method: http::Method,
+        uri: &str,
+        payload: serde_json::Value,
+    ) -> http::Response<Body> {
+        self.request(
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header(http::header::CONTENT_TYPE, "application/json")
+                .body(Body::from(serde_json::to_vec(&payload).unwrap()))
+                .unwrap(),
+        )
+        .await
+    }
+
+    /// Crea un usuario con `POST /users` y lo devuelve; falla si no se crea.
+    pub async fn create_user(&self, name: &str, email: &str) -> User {
+        let response = self
+            .post_json(
+                "/users",
+                serde_json::json!({ "name": name, "email": email }),
+            )
+            .await;
+
+        assert_eq!(response.status(), StatusCode::CREATED);
+        body_json(response).await
+    }
+
+    /// Crea `count` usuarios con nombres y correos distintos (`User 0`, `user0@example.com`...).
+    pub async fn create_users(&self, count: usize) -> Vec<User> {
+        let mut users = Vec::with_capacity(count);
+        for index in 0..count {
+            users.push(
+                self.create_user(
+                    &format!("User {index}"),
+                    &format!("user{index}@example.com"),
+                )
+                .await,
+            );
+        }
+        users
+    }
+}
+
+/// Cuerpo completo de la respuesta.
+pub async fn body_bytes(response: http::Response<Body>) -> Vec<u8> {
+    response
+        .into_body()
+        .collect()
+        .await
+        .expect("No se pudo leer el cuerpo de la respuesta")
+        .to_bytes()
+        .to_vec()
+}
+
+/// Cuerpo de la respuesta interpretado como JSON.
+pub async fn body_json<T: DeserializeOwned>(response: http::Response<Body>) -> T {
+    let bytes = body_bytes(response).await;
+    serde_json::from_slice(&bytes).expect("El cuerpo de la respuesta no es el JSON esperado")
+}
@@ -0,0 +1,180 @@
+//! Contexto de traza distribuida según W3C Trace Context.
+//!
+//! [`propagate_trace_context`] continúa la traza que llega en `traceparent` (y su
+//! `tracestate`) o, si no trae una válida, empieza una nueva. Cada petición recibe su propio
+//! identificador de span y se atiende dentro de un span `trace` con `trace_id` y `span_id`,
+//! así que todas sus líneas de log los llevan.
+//!
+//! Durante la petición, [`TraceContext::current`] devuelve el contexto e [`inject`] lo añade
+//! a las peticiones salientes de `reqwest` (S3, webhooks). Los eventos del outbox guardan el
+//! contexto de la petición que los originó, de modo que los webhooks que el relay entrega
+//! más tarde siguen perteneciendo a la misma traza.
+
+use std::future::Future;
+
+use axum::{extract::Request, http::HeaderMap, middleware::Next, response::Response};
+use rand::Rng;
+use tracing::{info_span, Instrument};
+
+/// Cabecera con la versión, la traza, el span padre y las opciones.
+pub const TRACEPARENT_HEADER: &str = "traceparent";
+/// Cabecera con el estado propio de cada proveedor de trazas, que se reenvía sin tocar.
+pub const TRACESTATE_HEADER: &str = "tracestate";
+
+/// Longitud máxima de `tracestate` que se reenvía; la especificación permite descartarlo.
+const MAX_TRACESTATE_LEN: usize = 512;
+/// Bit de `trace-flags` que indica que la traza se está muestreando.
+const SAMPLED_FLAG: u8 = 0x01;
+
+tokio::task_local! {
+    /// Contexto de la petición o de la entrega en curso.
+    static CURRENT: TraceContext;
+}
+
+/// Posición de una operación dentro de una traza distribuida.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceContext {
+    /// Identificador de la traza: 32 dígitos hexadecimales en minúscula.
+    pub trace_id: String,
+    /// Identificador del span: 16 dígitos hexadecimales en minúscula.
+    pub span_id: String,
+    /// `trace-flags`; solo se define el bit de muestreo.
+    pub flags: u8,
+    /// `tracestate` recibido, sin interpretar.
+    pub tracestate: Option<String>,
+}
+
+impl TraceContext {
+    /// Empieza una traza nueva, muestreada.
+    pub fn new_root() -> Self {
+        Self {
+            trace_id: random_id::<16>(),
+            span_id: random_id::<8>(),
+            flags: SAMPLED_FLAG,
+            tracestate: None,
+        }
+    }
+
+    /// Interpreta un `traceparent` y su `tracestate`; devuelve `None` si el primero no es
+    /// válido, en cuyo caso el segundo también se descarta.
+    ///
+    /// Las versiones posteriores a `00` se aceptan si empiezan con los mismos campos.
+    pub fn parse(traceparent: &str, tracestate: Option<&str>) -> Option<Self> {
+        let mut fields = traceparent.trim().split('-');
+        let version = fields.next()?;
+        let trace_id = fields.next()?;
+        let span_id = fields.next()?;
+        let flags = fields.next()?;
+
+        let version = parse_hex(version, 2)?;
+        let has_extra_fields = fields.next().is_some();
+        if version[0] == 0xff || (version[0] == 0 && has_extra_fields) {
+            return None;
+        }
+        let is_zero = |bytes: &[u8]| bytes.iter().all(|byte| *byte == 0);
+        if parse_hex(trace_id, 32).filter(|id| !is_zero(id)).is_none()
+            || parse_hex(span_id, 16).filter(|id| !is_zero(id)).is_none()
+        {
+            return None;
+        }
+        let flags = parse_hex(flags, 2)?[0];
+
+        Some(Self {
+            trace_id: trace_id.to_string(),
+            span_id: span_id.to_string(),
+            flags,
+            tracestate: tracestate
+                .map(str::trim)
+                .filter(|state| !state.is_empty() && state.len() <= MAX_TRACESTATE_LEN)
+                .map(str::to_string),
+        })
+    }
+
+    /// Contexto de una petición entrante: hijo del que trae en sus cabeceras o, si no trae
+    /// ninguno válido, el inicio de una traza nueva.
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let header = |name| headers.get(name).and_then(|value| value.to_str().ok());
+        header(TRACEPARENT_HEADER)
+            .and_then(|traceparent| Self::parse(traceparent, header(TRACESTATE_HEADER)))
+            .map(|parent| parent.child())
+            .unwrap_or_else(Self::new_root)
+    }
+
+    /// Span nuevo dentro de la misma traza.
+    pub fn child(&self) -> Self {
+        Self {
+            span_id: random_id::<8>(),
+            ..self.clone()
+        }
+    }
+
+    /// Indica si quien empezó la traza la está muestreando.
+    pub fn sampled(&self) -> bool {
+        self.flags & SAMPLED_FLAG != 0
+    }
+
+    /// Valor de `traceparent` que presenta este span como padre.
+    pub fn traceparent(&self) -> String {
+        format!("00-{}-{}-{:02x}", self.trace_id, self.span_id, self.flags)
+    }
+
+    /// Contexto de la petición o de la entrega en curso, si la hay.
+    pub fn current() -> Option<Self> {
+        CURRENT.try_with(Clone::clone).ok()
+    }
+
+    /// Ejecuta `future` con este contexto como el actual y dentro de un span `name` que lleva
+    /// sus identificadores.
+    pub async fn scope<F: Future>(self, name: &'static str, future: F) -> F::Output {
+        let span = info_span!(
+            "trace",
+            operation = name,
+            trace_id = %self.trace_id,
+            span_id = %self.span_id,
+        );
+        CURRENT.scope(self, future.instrument(span)).await
+    }
+}
+
+/// Añade a `request` las cabeceras del contexto en curso, si lo hay.
+pub fn inject(request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+    let Some(context) = TraceContext::current() else {
+        return request;
+    };
+    let request = request.header(TRACEPARENT_HEADER, context.traceparent());
+    match context.tracestate {
+        Some(tracestate) => request.header(TRACESTATE_HEADER, tracestate),
+        None => request,
+    }
+}
+
+/// Middleware que continúa o empieza la traza de cada petición y la atiende dentro de su
+/// span.
+pub async fn propagate_trace_context(mut request: Request, next: Next) -> Response {
+    let context = TraceContext::from_headers(request.headers());
+    request.extensions_mut().insert(context.clone());
+    context.scope("request", next.run(request)).await
+}
+
+fn parse_hex(value: &str, len: usize) -> Option<Vec<u8>> {
+    // Solo minúsculas: la especificación pide descartar las cabeceras en mayúsculas.
+    if value.len() != len
+        || !value
+            .bytes()
+            .all(|byte| matches!(byte, b'0'..=b'9' | b'a'..=b'f'))
+    {
+        return None;
+    }
+    hex::decode(value).ok()
+}
+
+fn random_id<const N: usize>() -> String {
+    loop {
+        let mut bytes = [0u8; N];
+        rand::thread_rng().fill(&mut bytes[..]);
+        // Un identificador a cero es inválido; la probabilidad es despreciable, pero se evita.
+        if bytes.iter().any(|byte| *byte != 0) {
+            return hex::encode(bytes);
+        }
+    }
+}
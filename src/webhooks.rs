@@ -0,0 +1,414 @@
+//! Entrega de webhooks salientes.
+//!
+//! `WebhookDispatcher` lee los eventos de dominio del outbox, como el broker y el CDC, y por
+//! cada uno envía un `POST` firmado a cada suscripción activa interesada del inquilino del
+//! usuario. Los fallos se reintentan con backoff exponencial y cada intento queda registrado
+//! en `webhook_deliveries`.
+//!
+//! La posición se guarda en `outbox_offsets` bajo [`CONSUMER`] y solo avanza cuando todas las
+//! entregas del evento terminan, con éxito o agotando los reintentos. Si el proceso cae a
+//! mitad, el evento se vuelve a entregar al arrancar: la entrega es al menos una vez y los
+//! receptores deduplican por `X-Webhook-Event-Id`. Los eventos se entregan en orden, así que
+//! un receptor que no responde retrasa a los demás mientras duran sus reintentos.
+//!
+//! Cada intento lleva `X-Webhook-Timestamp` (segundos Unix del envío), `X-Webhook-Event-Id` y
+//! `X-Webhook-Signature: sha256=<hex>`, el HMAC-SHA256 con el secreto de la suscripción de
+//! `<timestamp>.<event_id>.<cuerpo>`. Así el receptor puede descartar entregas antiguas y,
+//! guardando los identificadores ya procesados, las repetidas. [`SignedDelivery`] hace esas
+//! comprobaciones para receptores escritos en Rust.
+
+use std::{fmt, time::Duration};
+
+use axum::http::HeaderMap;
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha2::Sha256;
+use sqlx::SqlitePool;
+use tracing::{debug, info, warn};
+use uuid::Uuid;
+
+use crate::diagnostics;
+use crate::encryption::EmailEncryption;
+use crate::events::EventEnvelope;
+use crate::models::webhook::{Webhook, WEBHOOK_COLUMNS};
+use crate::outbox;
+use crate::state::AppState;
+use crate::trace_context::{self, TraceContext};
+
+/// Nombre con el que el despachador guarda su posición en `outbox_offsets`.
+pub const CONSUMER: &str = "webhooks";
+/// Cabecera con la firma HMAC-SHA256 de la marca de tiempo, el evento y el cuerpo.
+pub const SIGNATURE_HEADER: &str = "X-Webhook-Signature";
+/// Cabecera con el instante del envío, en segundos Unix.
+pub const TIMESTAMP_HEADER: &str = "X-Webhook-Timestamp";
+/// Cabecera con el nombre del evento entregado.
+pub const EVENT_HEADER: &str = "X-Webhook-Event";
+/// Cabecera con el identificador único del evento.
+pub const EVENT_ID_HEADER: &str = "X-Webhook-Event-Id";
+
+/// Número de intentos por defecto antes de abandonar una entrega.
+const DEFAULT_MAX_ATTEMPTS: u32 = 5;
+/// Espera inicial por defecto entre reintentos; se duplica en cada intento.
+const DEFAULT_BASE_DELAY: Duration = Duration::from_secs(2);
+/// Tiempo máximo de espera de cada petición saliente.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+/// Número máximo de eventos leídos del outbox por iteración.
+const DISPATCH_BATCH_SIZE: i64 = 100;
+/// Espera máxima entre iteraciones si el bus no avisa de eventos nuevos.
+const DISPATCH_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, sqlx::FromRow)]
+struct OutboxRow {
+    seq: i64,
+    payload: String,
+    traceparent: Option<String>,
+    tracestate: Option<String>,
+}
+
+/// Despachador que entrega los eventos del outbox a las URLs registradas.
+#[derive(Debug, Clone)]
+pub struct WebhookDispatcher {
+    database_pool: SqlitePool,
+    client: reqwest::Client,
+    max_attempts: u32,
+    base_delay: Duration,
+}
+
+impl WebhookDispatcher {
+    /// Crea un despachador con la política de reintentos por defecto. Las suscripciones y su
+    /// historial se leen de `database_pool`, la base principal.
+    pub fn new(database_pool: SqlitePool) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .unwrap_or_default();
+
+        Self {
+            database_pool,
+            client,
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+            base_delay: DEFAULT_BASE_DELAY,
+        }
+    }
+
+    /// Ajusta el número de intentos y la espera inicial entre reintentos.
+    pub fn with_retry_policy(mut self, max_attempts: u32, base_delay: Duration) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self.base_delay = base_delay;
+        self
+    }
+
+    /// Entrega en orden un lote de eventos del outbox de `outbox_pool` posteriores a la
+    /// posición de [`CONSUMER`] y devuelve cuántos se leyeron.
+    pub async fn dispatch_once(
+        &self,
+        outbox_pool: &SqlitePool,
+        encryption: &EmailEncryption,
+    ) -> anyhow::Result<usize> {
+        let last_seq: i64 =
+            sqlx::query_scalar("SELECT last_seq FROM outbox_offsets WHERE consumer = ?")
+                .bind(CONSUMER)
+                .fetch_optional(outbox_pool)
+                .await?
+                .unwrap_or(0);
+
+        let rows = sqlx::query_as::<_, OutboxRow>(
+            "SELECT seq, payload, traceparent, tracestate FROM outbox WHERE seq > ? \
+             ORDER BY seq LIMIT ?",
+        )
+        .bind(last_seq)
+        .bind(DISPATCH_BATCH_SIZE)
+        .fetch_all(outbox_pool)
+        .await?;
+
+        for row in &rows {
+            match outbox::decode(&row.payload, encryption) {
+                Ok(mut envelope) => {
+                    envelope.trace_context = row.traceparent.as_deref().and_then(|traceparent| {
+                        TraceContext::parse(traceparent, row.tracestate.as_deref())
+                    });
+                    self.dispatch(envelope).await?;
+                }
+                // Igual que en el relay del outbox, un evento ilegible no bloquea la cola.
+                Err(error) => warn!(seq = row.seq, ?error, "Evento del outbox ilegible"),
+            }
+
+            sqlx::query(
+                "INSERT INTO outbox_offsets (consumer, last_seq, updated_at) VALUES (?, ?, ?) \
+                 ON CONFLICT (consumer) DO UPDATE SET last_seq = excluded.last_seq, \
+                 updated_at = excluded.updated_at",
+            )
+            .bind(CONSUMER)
+            .bind(row.seq)
+            .bind(Utc::now())
+            .execute(outbox_pool)
+            .await?;
+        }
+
+        Ok(rows.len())
+    }
+
+    /// Entrega un evento a las suscripciones interesadas de su inquilino, en paralelo, y
+    /// espera a que terminen todas.
+    async fn dispatch(&self, envelope: EventEnvelope) -> Result<(), sqlx::Error> {
+        let webhooks = sqlx::query_as::<_, Webhook>(&format!(
+            "SELECT {WEBHOOK_COLUMNS} FROM webhooks WHERE active = 1 AND tenant_id = ?"
+        ))
+        .bind(envelope.event.tenant_id())
+        .fetch_all(&self.database_pool)
+        .await?;
+
+        let mut deliveries = Vec::new();
+        for webhook in webhooks {
+            if !webhook.accepts(envelope.event.name()) {
+                continue;
+            }
+
+            let dispatcher = self.clone();
+            let envelope = envelope.clone();
+            deliveries.push(diagnostics::spawn("webhook-delivery", async move {
+                // Cada entrega es un span hijo de la operación que originó el evento.
+                match envelope.trace_context.as_ref().map(TraceContext::child) {
+                    Some(context) => {
+                        context
+                            .scope("webhook", dispatcher.deliver(webhook, envelope))
+                            .await
+                    }
+                    None => dispatcher.deliver(webhook, envelope).await,
+                }
+            }));
+        }
+        for delivery in deliveries {
+            if let Err(error) = delivery.await {
+                warn!(?error, "La entrega del webhook terminó de forma inesperada");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Entrega un evento a una suscripción, reintentando hasta agotar los intentos.
+    async fn deliver(&self, webhook: Webhook, envelope: EventEnvelope) {
+        let body = match serde_json::to_vec(&envelope) {
+            Ok(body) => body,
+            Err(error) => {
+                warn!(?error, "No se pudo serializar el evento para el webhook");
+                return;
+            }
+        };
+        let event_type = envelope.event.name();
+
+        for attempt in 1..=self.max_attempts {
+            // Cada intento se firma con su propia marca de tiempo para que los reintentos no
+            // caduquen en el receptor.
+            let timestamp = Utc::now().timestamp();
+            let signature = sign_payload(&webhook.secret, timestamp, envelope.id, &body);
+            let outcome = trace_context::inject(self.client.post(&webhook.url))
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .header(SIGNATURE_HEADER, &signature)
+                .header(TIMESTAMP_HEADER, timestamp.to_string())
+                .header(EVENT_HEADER, event_type)
+                .header(EVENT_ID_HEADER, envelope.id.to_string())
+                .body(body.clone())
+                .send()
+                .await;
+
+            let (status_code, error) = match outcome {
+                Ok(response) => (Some(response.status().as_u16()), None),
+                Err(error) => (None, Some(error.to_string())),
+            };
+            let success = status_code.is_some_and(|status| (200..300).contains(&status));
+
+            if let Err(error) = sqlx::query(
+                "INSERT INTO webhook_deliveries \
+                 (webhook_id, event_id, event_type, attempt, status_code, error, success, \
+                 attempted_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+            )
+            .bind(webhook.id)
+            .bind(envelope.id)
+            .bind(event_type)
+            .bind(attempt)
+            .bind(status_code)
+            .bind(&error)
+            .bind(success)
+            .bind(Utc::now())
+            .execute(&self.database_pool)
+            .await
+            {
+                warn!(?error, "No se pudo registrar la entrega del webhook");
+            }
+
+            if success {
+                debug!(webhook_id = %webhook.id, attempt, "Webhook entregado");
+                return;
+            }
+
+            if attempt < self.max_attempts {
+                tokio::time::sleep(self.base_delay * 2u32.pow(attempt - 1)).await;
+            }
+        }
+
+        warn!(
+            webhook_id = %webhook.id,
+            event_id = %envelope.id,
+            "Entrega de webhook abandonada tras agotar los reintentos"
+        );
+    }
+}
+
+/// Bucle del despachador: entrega los eventos de todas las bases en cuanto el relay los
+/// publica en el bus o, como mínimo, cada pocos segundos.
+pub async fn run(state: AppState, dispatcher: WebhookDispatcher) {
+    info!("Despachador de webhooks iniciado");
+    // El bus solo sirve de aviso: lo que se entrega sale siempre del outbox, así que perder
+    // mensajes por ir rezagado no pierde entregas.
+    let mut published = state.events.subscribe();
+
+    loop {
+        let mut has_more = false;
+        for database_pool in state.database_pools().await {
+            match dispatcher
+                .dispatch_once(&database_pool, &state.email_encryption)
+                .await
+            {
+                Ok(dispatched) => has_more |= dispatched as i64 == DISPATCH_BATCH_SIZE,
+                Err(error) => warn!(
+                    ?error,
+                    "Fallo al entregar eventos del outbox a los webhooks"
+                ),
+            }
+        }
+        if has_more {
+            continue;
+        }
+
+        let _ = tokio::time::timeout(DISPATCH_POLL_INTERVAL, published.recv()).await;
+    }
+}
+
+/// Elimina los intentos de entrega registrados antes de `older_than` y devuelve cuántos
+/// se borraron.
+pub async fn purge_deliveries(
+    database_pool: &SqlitePool,
+    older_than: DateTime<Utc>,
+) -> Result<u64, sqlx::Error> {
+    let result =
+        sqlx::query("DELETE FROM webhook_deliveries WHERE julianday(attempted_at) < julianday(?)")
+            .bind(older_than)
+            .execute(database_pool)
+            .await?;
+
+    Ok(result.rows_affected())
+}
+
+/// Calcula la firma `sha256=<hex>` de una entrega con el secreto de la suscripción.
+pub fn sign_payload(secret: &str, timestamp: i64, event_id: Uuid, body: &[u8]) -> String {
+    format!(
+        "sha256={}",
+        hex::encode(
+            payload_mac(secret, timestamp, event_id, body)
+                .finalize()
+                .into_bytes()
+        )
+    )
+}
+
+fn payload_mac(secret: &str, timestamp: i64, event_id: Uuid, body: &[u8]) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC acepta claves de cualquier longitud");
+    mac.update(format!("{timestamp}.{event_id}.").as_bytes());
+    mac.update(body);
+    mac
+}
+
+/// Motivo por el que un receptor rechaza una entrega.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebhookSignatureError {
+    /// Falta alguna cabecera de firma o no tiene el formato esperado.
+    Missing,
+    /// La entrega se firmó fuera de la tolerancia admitida.
+    Expired,
+    /// La firma no corresponde al cuerpo.
+    Invalid,
+}
+
+impl fmt::Display for WebhookSignatureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Missing => "Faltan las cabeceras de firma del webhook",
+            Self::Expired => "La entrega del webhook está fuera de la ventana admitida",
+            Self::Invalid => "La firma del webhook no es válida",
+        })
+    }
+}
+
+impl std::error::Error for WebhookSignatureError {}
+
+/// Entrega recibida por un receptor, lista para verificar su firma.
+#[derive(Debug, Clone, Copy)]
+pub struct SignedDelivery<'a> {
+    pub signature: &'a str,
+    pub timestamp: i64,
+    pub event_id: Uuid,
+    pub body: &'a [u8],
+}
+
+impl<'a> SignedDelivery<'a> {
+    /// Toma la firma, la marca de tiempo y el evento de las cabeceras de la petición.
+    pub fn from_headers(
+        headers: &'a HeaderMap,
+        body: &'a [u8],
+    ) -> Result<Self, WebhookSignatureError> {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .ok_or(WebhookSignatureError::Missing)
+        };
+
+        Ok(Self {
+            signature: header(SIGNATURE_HEADER)?,
+            timestamp: header(TIMESTAMP_HEADER)?
+                .parse()
+                .map_err(|_| WebhookSignatureError::Missing)?,
+            event_id: header(EVENT_ID_HEADER)?
+                .parse()
+                .map_err(|_| WebhookSignatureError::Missing)?,
+            body,
+        })
+    }
+
+    /// Comprueba la firma con `secret` y que se haya firmado hace menos de `tolerance`.
+    pub fn verify(&self, secret: &str, tolerance: Duration) -> Result<(), WebhookSignatureError> {
+        self.verify_at(secret, tolerance, Utc::now())
+    }
+
+    /// Como [`SignedDelivery::verify`], tomando `now` como instante actual.
+    pub fn verify_at(
+        &self,
+        secret: &str,
+        tolerance: Duration,
+        now: DateTime<Utc>,
+    ) -> Result<(), WebhookSignatureError> {
+        if now.timestamp().abs_diff(self.timestamp) > tolerance.as_secs() {
+            return Err(WebhookSignatureError::Expired);
+        }
+
+        let expected = self
+            .signature
+            .strip_prefix("sha256=")
+            .and_then(|hex| hex::decode(hex).ok())
+            .ok_or(WebhookSignatureError::Invalid)?;
+        payload_mac(secret, self.timestamp, self.event_id, self.body)
+            .verify_slice(&expected)
+            .map_err(|_| WebhookSignatureError::Invalid)
+    }
+}
+
+/// Genera un secreto aleatorio de 32 bytes codificado en hexadecimal.
+pub fn generate_secret() -> String {
+    let mut bytes = [0u8; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
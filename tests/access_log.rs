@@ -0,0 +1,148 @@
+use std::{
+    io::Write,
+    sync::{Arc, Mutex},
+};
+
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+    middleware,
+    routing::get,
+    Router,
+};
+use tower::ServiceBuilder;
+use tower_http::request_id::{MakeRequestUuid, SetRequestIdLayer};
+
+use rust_web_demo::access_log::{self, AccessLog, AccessLogConfig, LogFormat};
+
+/// Destino de las trazas que guarda todo lo escrito.
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Captured {
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(bytes);
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Captured {
+    fn lines(&self) -> Vec<serde_json::Value> {
+        String::from_utf8(self.0.lock().unwrap().clone())
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+}
+
+#[tokio::test]
+async fn requests_are_logged_as_json_with_request_id() {
+    let captured = Captured::default();
+    let _guard = capture_json(&captured);
+    let app = app(AccessLogConfig::default());
+
+    let response = send(&app, "/users?email=ada@example.com", Some("req-123")).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    send(&app, "/missing", None).await;
+
+    let lines = captured.lines();
+    assert_eq!(lines.len(), 2, "{lines:?}");
+    assert_eq!(lines[0]["message"], "Petición atendida");
+    assert_eq!(lines[0]["method"], "GET");
+    assert_eq!(lines[0]["path"], "/users");
+    assert_eq!(lines[0]["status"], 200);
+    assert_eq!(lines[0]["bytes"], 9);
+    assert_eq!(lines[0]["request_id"], "req-123");
+    assert_eq!(lines[0]["client_ip"], "-");
+    assert!(lines[0]["latency_ms"].as_f64().unwrap() >= 0.0);
+
+    assert_eq!(lines[1]["path"], "/missing");
+    assert_eq!(lines[1]["status"], 404);
+    assert_ne!(lines[1]["request_id"], "-");
+}
+
+#[tokio::test]
+async fn successful_health_checks_are_not_logged() {
+    let captured = Captured::default();
+    let _guard = capture_json(&captured);
+    let router = app(AccessLogConfig::default());
+
+    send(&router, "/health", None).await;
+    send(&router, "/metrics", None).await;
+    assert!(captured.lines().is_empty());
+
+    // Los fallos de las rutas filtradas sí se registran.
+    let response = send(&router, "/health?fail=1", None).await;
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let lines = captured.lines();
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0]["status"], 503);
+
+    let disabled = app(AccessLogConfig {
+        enabled: false,
+        ..AccessLogConfig::default()
+    });
+    send(&disabled, "/users", None).await;
+    assert_eq!(captured.lines().len(), 1);
+}
+
+#[test]
+fn log_format_is_parsed_case_insensitively() {
+    assert_eq!("JSON".parse::<LogFormat>().unwrap(), LogFormat::Json);
+    assert_eq!("human".parse::<LogFormat>().unwrap(), LogFormat::Human);
+    assert!("xml".parse::<LogFormat>().is_err());
+}
+
+fn capture_json(captured: &Captured) -> tracing::subscriber::DefaultGuard {
+    let writer = captured.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer(move || writer.clone())
+        .with_target(false)
+        .json()
+        .flatten_event(true)
+        .finish();
+    tracing::subscriber::set_default(subscriber)
+}
+
+fn app(config: AccessLogConfig) -> Router {
+    Router::new()
+        .route("/users", get(|| async { "[\"ada\"]\n\n" }))
+        .route(
+            "/health",
+            get(|request: Request<Body>| async move {
+                match request.uri().query() {
+                    Some(_) => StatusCode::SERVICE_UNAVAILABLE,
+                    None => StatusCode::OK,
+                }
+            }),
+        )
+        .route("/metrics", get(|| async { "requests_total 1" }))
+        .layer(
+            ServiceBuilder::new()
+                .layer(SetRequestIdLayer::x_request_id(MakeRequestUuid))
+                .layer(middleware::from_fn_with_state(
+                    AccessLog::new(config),
+                    access_log::log_requests,
+                )),
+        )
+}
+
+async fn send(
+    app: &Router,
+    uri: &str,
+    request_id: Option<&str>,
+) -> axum::http::Response<axum::body::Body> {
+    let mut request = Request::builder().uri(uri);
+    if let Some(request_id) = request_id {
+        request = request.header("x-request-id", request_id);
+    }
+
+    tower::ServiceExt::oneshot(app.clone(), request.body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+}
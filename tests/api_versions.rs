@@ -0,0 +1,129 @@
+use axum::{
+    body::Body,
+    http::{self, Request, StatusCode},
+    middleware,
+};
+use http_body_util::BodyExt;
+use sqlx::sqlite::SqlitePoolOptions;
+use tower::{Layer, ServiceExt};
+
+use rust_web_demo::{config::AppConfig, models, routes, state::AppState};
+
+#[tokio::test]
+async fn v1_keeps_its_representation_under_prefix_and_without_it() {
+    let context = TestContext::new().await;
+    let created = context.create_user("/v1/users").await;
+    assert!(created["created_at"].is_string());
+
+    for uri in ["/v1/users", "/users"] {
+        let users: Vec<models::user::User> = body_json(context.request(uri, None).await).await;
+        assert_eq!(users.len(), 1);
+        assert_eq!(users[0].name, "Ada Lovelace");
+    }
+
+    let response = context.request("/users", Some("1")).await;
+    let users: Vec<models::user::User> = body_json(response).await;
+    assert_eq!(users.len(), 1);
+}
+
+#[tokio::test]
+async fn v2_users_use_the_new_representation_by_path_or_header() {
+    let context = TestContext::new().await;
+    let created = context.create_user("/v2/users").await;
+    assert!(created["createdAt"].is_string());
+    assert_eq!(created["status"]["state"], "active");
+    assert!(created.get("suspended_until").is_none());
+
+    for (uri, version) in [
+        ("/v2/users", None),
+        ("/users", Some("2")),
+        ("/users", Some("v2")),
+    ] {
+        let list: models::v2::user::UserList = body_json(context.request(uri, version).await).await;
+        assert_eq!(list.data.len(), 1);
+        assert_eq!(list.data[0].email, "ada@example.com");
+    }
+
+    let user_uri = format!("/v2/users/{}", created["id"].as_str().unwrap());
+    let user: serde_json::Value = body_json(context.request(&user_uri, None).await).await;
+    assert_eq!(user["tenantId"], "default");
+
+    // Los recursos sin cambios en v2 se sirven igual que en v1.
+    let response = context.request("/v2/tags", None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // La cabecera no afecta a las rutas que no dependen de la versión ni a las que ya
+    // llevan prefijo.
+    let response = context.request("/health", Some("2")).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = context.request("/v1/users", Some("2")).await;
+    let users: Vec<models::user::User> = body_json(response).await;
+    assert_eq!(users.len(), 1);
+
+    let response = context.request("/users", Some("3")).await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+async fn body_json<T: serde::de::DeserializeOwned>(response: http::Response<Body>) -> T {
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+struct TestContext {
+    app: axum::Router,
+}
+
+impl TestContext {
+    async fn new() -> Self {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+
+        let state = AppState::new(pool, AppConfig::default());
+        let app = routes::api_routes(&state)
+            .merge(routes::health_routes())
+            .with_state(state);
+
+        Self { app }
+    }
+
+    async fn send(&self, request: Request<Body>) -> http::Response<Body> {
+        middleware::map_request(routes::select_api_version)
+            .layer(self.app.clone())
+            .oneshot(request)
+            .await
+            .unwrap()
+    }
+
+    async fn request(&self, uri: &str, version: Option<&str>) -> http::Response<Body> {
+        let mut request = Request::builder().uri(uri);
+        if let Some(version) = version {
+            request = request.header(routes::API_VERSION_HEADER, version);
+        }
+        self.send(request.body(Body::empty()).unwrap()).await
+    }
+
+    async fn create_user(&self, uri: &str) -> serde_json::Value {
+        let response = self
+            .send(
+                Request::builder()
+                    .method(http::Method::POST)
+                    .uri(uri)
+                    .header(http::header::CONTENT_TYPE, "application/json")
+                    .body(Body::from(
+                        serde_json::to_vec(&serde_json::json!({
+                            "name": "Ada Lovelace",
+                            "email": "ada@example.com",
+                        }))
+                        .unwrap(),
+                    ))
+                    .unwrap(),
+            )
+            .await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+        body_json(response).await
+    }
+}
@@ -0,0 +1,230 @@
+use std::{path::PathBuf, sync::Arc};
+
+use axum::{
+    body::Body,
+    http::{self, Request, StatusCode},
+    routing::Router,
+};
+use http_body_util::BodyExt;
+use sqlx::sqlite::{SqliteConnectOptions, SqlitePoolOptions};
+
+use rust_web_demo::{
+    backup,
+    config::AppConfig,
+    migrations::{self, MigrationState, MIGRATOR},
+    routes,
+    state::AppState,
+    storage::{LocalStorage, Storage},
+    testing::factory::UserFactory,
+};
+
+#[tokio::test]
+async fn snapshot_copies_a_consistent_database() {
+    let pool = migrated_pool().await;
+    UserFactory::new()
+        .with_email("ada@example.com")
+        .create(&pool)
+        .await;
+    let directory = temp_dir();
+    let path = directory.join("snapshot.db");
+
+    let size = backup::snapshot(&pool, &path).await.unwrap();
+    assert_eq!(size, std::fs::metadata(&path).unwrap().len());
+    assert!(!directory.join("snapshot.db.partial").exists());
+    assert!(backup::snapshot(&pool, &path).await.is_err());
+
+    let copy = SqlitePoolOptions::new()
+        .connect_with(SqliteConnectOptions::new().filename(&path))
+        .await
+        .unwrap();
+    let (integrity,): (String,) = sqlx::query_as("PRAGMA integrity_check")
+        .fetch_one(&copy)
+        .await
+        .unwrap();
+    assert_eq!(integrity, "ok");
+    assert_eq!(count_users(&copy).await, 1);
+
+    copy.close().await;
+    std::fs::remove_dir_all(directory).unwrap();
+}
+
+#[tokio::test]
+async fn backup_endpoint_writes_to_the_configured_destination() {
+    let directory = temp_dir();
+    let config = AppConfig {
+        backup_dir: directory.join("backups"),
+        ..AppConfig::default()
+    };
+    let app = app(config, &directory).await;
+
+    let body = post_backup(&app).await;
+    assert_eq!(body["destination"], "file");
+    let location = PathBuf::from(body["location"].as_str().unwrap());
+    assert!(location.starts_with(directory.join("backups")));
+    assert_eq!(
+        body["size_bytes"].as_u64().unwrap(),
+        std::fs::metadata(&location).unwrap().len()
+    );
+
+    let config = AppConfig {
+        backup_dir: directory.join("backups"),
+        backup_to_storage: true,
+        ..AppConfig::default()
+    };
+    let app = self::app(config, &directory).await;
+
+    let body = post_backup(&app).await;
+    assert_eq!(body["destination"], "storage");
+    let key = body["location"].as_str().unwrap();
+    assert!(key.starts_with("backups/backup-"));
+    let storage = LocalStorage::new(directory.join("storage"));
+    let stored = storage.get(key).await.unwrap().unwrap();
+    assert_eq!(stored.len() as u64, body["size_bytes"].as_u64().unwrap());
+    assert!(stored.starts_with(b"SQLite format 3\0"));
+    assert_eq!(
+        std::fs::read_dir(directory.join("backups"))
+            .unwrap()
+            .count(),
+        1
+    );
+
+    std::fs::remove_dir_all(directory).unwrap();
+}
+
+#[tokio::test]
+async fn restore_replaces_the_database_and_migrates_it() {
+    let directory = temp_dir();
+    let config = AppConfig {
+        backup_dir: directory.join("backups"),
+        ..AppConfig::default()
+    };
+    let pool = migrated_pool().await;
+    let status = migrations::status(&pool).await.unwrap();
+    let previous = status[status.len() - 2].version;
+    migrations::migrate_to(&pool, previous).await.unwrap();
+    // La fábrica sigue el esquema actual; sobre el anterior se inserta a mano.
+    sqlx::query("INSERT INTO users (id, name, email, created_at) VALUES (?, ?, ?, ?)")
+        .bind(uuid::Uuid::new_v4())
+        .bind("Ada")
+        .bind("ada@example.com")
+        .bind(chrono::Utc::now())
+        .execute(&pool)
+        .await
+        .unwrap();
+    let snapshot = directory.join("snapshot.db");
+    backup::snapshot(&pool, &snapshot).await.unwrap();
+
+    MIGRATOR.run(&pool).await.unwrap();
+    UserFactory::new()
+        .with_email("grace@example.com")
+        .create(&pool)
+        .await;
+
+    let report = backup::restore(&pool, &config, &snapshot).await.unwrap();
+    assert_eq!(report.snapshot_version, Some(previous));
+    assert_eq!(report.applied_migrations, [status.last().unwrap().version]);
+    assert_eq!(count_users(&pool).await, 1);
+    assert!(migrations::status(&pool)
+        .await
+        .unwrap()
+        .iter()
+        .all(|migration| migration.state == MigrationState::Applied));
+
+    let safety_copy = SqlitePoolOptions::new()
+        .connect_with(SqliteConnectOptions::new().filename(&report.safety_copy))
+        .await
+        .unwrap();
+    assert_eq!(count_users(&safety_copy).await, 2);
+
+    safety_copy.close().await;
+    std::fs::remove_dir_all(directory).unwrap();
+}
+
+#[tokio::test]
+async fn restore_rejects_unusable_snapshots() {
+    let directory = temp_dir();
+    let config = AppConfig {
+        backup_dir: directory.join("backups"),
+        ..AppConfig::default()
+    };
+    let pool = migrated_pool().await;
+    UserFactory::new()
+        .with_email("ada@example.com")
+        .create(&pool)
+        .await;
+
+    let garbage = directory.join("garbage.db");
+    std::fs::write(&garbage, "no es una base de datos").unwrap();
+    let error = backup::restore(&pool, &config, &garbage).await.unwrap_err();
+    assert!(error.to_string().contains("dañada"));
+
+    let future = directory.join("future.db");
+    backup::snapshot(&pool, &future).await.unwrap();
+    let connection = SqlitePoolOptions::new()
+        .connect_with(SqliteConnectOptions::new().filename(&future))
+        .await
+        .unwrap();
+    sqlx::query(
+        "INSERT INTO _sqlx_migrations (version, description, success, checksum, execution_time) \
+         VALUES (99990101000000, 'futura', TRUE, x'00', 0)",
+    )
+    .execute(&connection)
+    .await
+    .unwrap();
+    connection.close().await;
+    let error = backup::restore(&pool, &config, &future).await.unwrap_err();
+    assert!(error.to_string().contains("99990101000000"));
+
+    assert!(
+        backup::restore(&pool, &config, &directory.join("missing.db"))
+            .await
+            .is_err()
+    );
+    assert_eq!(count_users(&pool).await, 1);
+    assert!(!directory.join("backups").exists());
+
+    std::fs::remove_dir_all(directory).unwrap();
+}
+
+async fn count_users(pool: &sqlx::SqlitePool) -> i64 {
+    sqlx::query_scalar("SELECT COUNT(*) FROM users")
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+async fn post_backup(app: &Router) -> serde_json::Value {
+    let request = Request::builder()
+        .method(http::Method::POST)
+        .uri("/admin/backup")
+        .body(Body::empty())
+        .unwrap();
+    let response = tower::ServiceExt::oneshot(app.clone(), request)
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+async fn app(config: AppConfig, directory: &std::path::Path) -> Router {
+    let storage: Arc<dyn Storage> = Arc::new(LocalStorage::new(directory.join("storage")));
+    let state = AppState::new(migrated_pool().await, config).with_storage(storage);
+    routes::backup_routes().with_state(state)
+}
+
+async fn migrated_pool() -> sqlx::SqlitePool {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+    pool
+}
+
+fn temp_dir() -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("backup-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&directory).unwrap();
+    directory
+}
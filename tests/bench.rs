@@ -0,0 +1,70 @@
+use std::future::IntoFuture;
+
+use rust_web_demo::{
+    bench::{self, BenchConfig, Mix, Operation},
+    testing::TestContext,
+};
+
+#[tokio::test]
+async fn bench_runs_the_requested_number_of_operations() {
+    let context = TestContext::new().await;
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(axum::serve(listener, context.app).into_future());
+
+    let config = BenchConfig {
+        concurrency: 4,
+        requests: Some(60),
+        ..BenchConfig::new(format!("http://{address}"))
+    };
+    let report = bench::run(&config).await.unwrap();
+
+    assert_eq!(report.total_requests(), 60);
+    assert_eq!(report.total_errors(), 0);
+    let creates = report
+        .operations
+        .iter()
+        .find(|stats| stats.operation == Operation::Create)
+        .unwrap();
+    assert!(creates.requests >= 4);
+    assert!(creates.p50 <= creates.p99 && creates.p99 <= creates.max);
+    assert!(report.to_string().contains("Total: 60 peticiones"));
+}
+
+#[test]
+fn arguments_are_parsed_with_defaults() {
+    let args: Vec<String> = [
+        "http://localhost:3000",
+        "--concurrency",
+        "16",
+        "--mix",
+        "get=3, list=1",
+    ]
+    .map(String::from)
+    .to_vec();
+
+    let config = BenchConfig::parse(&args).unwrap();
+    assert_eq!(config.target, "http://localhost:3000");
+    assert_eq!(config.concurrency, 16);
+    assert_eq!(config.requests, None);
+    assert_eq!(config.mix.weight(Operation::Get), 3);
+    assert_eq!(config.mix.weight(Operation::Create), 0);
+}
+
+#[test]
+fn invalid_arguments_are_rejected() {
+    for args in [
+        vec![],
+        vec!["--concurrency", "4"],
+        vec!["http://localhost:3000", "--concurrency", "0"],
+        vec!["http://localhost:3000", "--duration"],
+        vec!["http://localhost:3000", "--verbose", "1"],
+    ] {
+        let args: Vec<String> = args.into_iter().map(String::from).collect();
+        assert!(BenchConfig::parse(&args).is_err(), "{args:?}");
+    }
+
+    assert!(Mix::parse("create=0").is_err());
+    assert!(Mix::parse("create=uno").is_err());
+    assert!(Mix::parse("upsert=1").is_err());
+}
@@ -0,0 +1,163 @@
+use anyhow::{bail, Result};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::TcpListener,
+};
+
+use rust_web_demo::{
+    broker::{
+        self, BrokerConfig, BrokerKind, BrokerMessage, BrokerTransport, NatsPublisher,
+        SCHEMA_VERSION,
+    },
+    testing::TestContext,
+};
+
+/// Destino en memoria que falla mientras `failures` sea mayor que cero.
+#[derive(Default)]
+struct Recorder {
+    published: Vec<serde_json::Value>,
+    failures: usize,
+}
+
+impl BrokerTransport for Recorder {
+    async fn publish(&mut self, message: &BrokerMessage) -> Result<()> {
+        if self.failures > 0 {
+            self.failures -= 1;
+            bail!("broker caído");
+        }
+        self.published
+            .push(serde_json::from_slice(&message.to_json()?).unwrap());
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn outbox_events_are_published_in_order_and_resumed_after_a_failure() {
+    let context = TestContext::new().await;
+    let user = context.create_user("Ada Lovelace", "ada@example.com").await;
+    context.delete(&format!("/users/{}", user.id)).await;
+    let pool = &context.state.database_pool;
+
+    let mut recorder = Recorder {
+        failures: 1,
+        ..Recorder::default()
+    };
+    assert!(broker::publish_once(
+        pool,
+        &context.state.email_encryption,
+        &mut recorder,
+        broker::CONSUMER,
+        100
+    )
+    .await
+    .is_err());
+    assert!(recorder.published.is_empty());
+
+    assert_eq!(
+        broker::publish_once(
+            pool,
+            &context.state.email_encryption,
+            &mut recorder,
+            broker::CONSUMER,
+            100
+        )
+        .await
+        .unwrap(),
+        2
+    );
+    assert_eq!(
+        broker::publish_once(
+            pool,
+            &context.state.email_encryption,
+            &mut recorder,
+            broker::CONSUMER,
+            100
+        )
+        .await
+        .unwrap(),
+        0
+    );
+
+    let [created, deleted] = recorder.published.as_slice() else {
+        panic!("se esperaban dos eventos: {:?}", recorder.published);
+    };
+    assert_eq!(created["schema"], "user.created");
+    assert_eq!(created["schema_version"], SCHEMA_VERSION);
+    assert_eq!(created["user_id"], user.id.to_string());
+    assert_eq!(created["user"]["email"], "ada@example.com");
+    assert_eq!(created["tenant_id"], "default");
+    assert_eq!(deleted["schema"], "user.deleted");
+    assert!(deleted.get("user").is_none());
+    assert_ne!(created["event_id"], deleted["event_id"]);
+}
+
+#[tokio::test]
+async fn nats_publisher_sends_each_event_to_its_subject() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    // Servidor NATS mínimo: saluda, acepta la conexión y confirma cada `PING`.
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let (reader, mut writer) = stream.into_split();
+        let mut reader = BufReader::new(reader);
+        writer.write_all(b"INFO {}\r\n").await.unwrap();
+
+        let mut connect = String::new();
+        let mut published = Vec::new();
+        loop {
+            let mut line = String::new();
+            if reader.read_line(&mut line).await.unwrap() == 0 {
+                break;
+            }
+            let line = line.trim_end();
+            if line.starts_with("CONNECT") {
+                connect = line.to_string();
+            } else if line == "PING" {
+                writer.write_all(b"PONG\r\n").await.unwrap();
+                // El `PING` que sigue a una publicación es el último paso de la prueba.
+                if !published.is_empty() {
+                    break;
+                }
+            } else if let Some(arguments) = line.strip_prefix("PUB ") {
+                let (subject, length) = arguments.split_once(' ').unwrap();
+                let mut payload = vec![0; length.parse::<usize>().unwrap() + 2];
+                reader.read_exact(&mut payload).await.unwrap();
+                payload.truncate(payload.len() - 2);
+                published.push((subject.to_string(), payload));
+            }
+        }
+        (connect, published)
+    });
+
+    let context = TestContext::new().await;
+    let user = context.create_user("Ada Lovelace", "ada@example.com").await;
+    let mut publisher = NatsPublisher::new(&BrokerConfig {
+        kind: BrokerKind::Nats,
+        url: format!("nats://analitica:secreto@{address}"),
+        topic: "proyecto.users".to_string(),
+        batch_size: 100,
+        poll_interval: std::time::Duration::from_secs(1),
+    });
+    assert_eq!(
+        broker::publish_once(
+            &context.state.database_pool,
+            &context.state.email_encryption,
+            &mut publisher,
+            broker::CONSUMER,
+            100
+        )
+        .await
+        .unwrap(),
+        1
+    );
+
+    let (connect, published) = server.await.unwrap();
+    let options: serde_json::Value =
+        serde_json::from_str(connect.strip_prefix("CONNECT ").unwrap()).unwrap();
+    assert_eq!(options["user"], "analitica");
+    assert_eq!(options["pass"], "secreto");
+    let (subject, payload) = &published[0];
+    assert_eq!(subject, "proyecto.users.user.created");
+    let message: serde_json::Value = serde_json::from_slice(payload).unwrap();
+    assert_eq!(message["user_id"], user.id.to_string());
+}
@@ -0,0 +1,176 @@
+use std::sync::Arc;
+
+use axum::http::StatusCode;
+use uuid::Uuid;
+
+use rust_web_demo::{
+    cdc::{self, NdjsonFileSink, StorageSink},
+    storage::{LocalStorage, Storage},
+    testing::TestContext,
+};
+
+/// Da de alta, renombra y borra a un usuario a través de la API, que registra un evento del
+/// outbox por cada cambio.
+async fn create_update_and_delete(context: &TestContext) -> Uuid {
+    let user = context.create_user("Ada Lovelace", "ada@example.com").await;
+    let response = context
+        .put_json(
+            &format!("/users/{}", user.id),
+            serde_json::json!({ "name": "Ada King", "email": "ada@example.com" }),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = context.delete(&format!("/users/{}", user.id)).await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    user.id
+}
+
+fn parse_ndjson(contents: &str) -> Vec<serde_json::Value> {
+    contents
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn replicate_once_writes_outbox_events_and_advances_offset() {
+    let context = TestContext::new().await;
+    let pool = &context.state.database_pool;
+    let user = context.create_user("Ada Lovelace", "ada@example.com").await;
+
+    let output_dir = std::env::temp_dir().join(format!("cdc-test-{}", Uuid::new_v4()));
+    let mut sink = NdjsonFileSink::new(&output_dir, 1024 * 1024);
+
+    let replicated = cdc::replicate_once(
+        pool,
+        &context.state.email_encryption,
+        &mut sink,
+        "test",
+        100,
+    )
+    .await
+    .unwrap();
+    assert_eq!(replicated, 1);
+
+    let response = context
+        .put_json(
+            &format!("/users/{}", user.id),
+            serde_json::json!({ "name": "Ada King", "email": "ada@example.com" }),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = context.delete(&format!("/users/{}", user.id)).await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let replicated = cdc::replicate_once(
+        pool,
+        &context.state.email_encryption,
+        &mut sink,
+        "test",
+        100,
+    )
+    .await
+    .unwrap();
+    assert_eq!(replicated, 2);
+    assert_eq!(
+        cdc::replicate_once(
+            pool,
+            &context.state.email_encryption,
+            &mut sink,
+            "test",
+            100
+        )
+        .await
+        .unwrap(),
+        0
+    );
+
+    let mut files: Vec<_> = std::fs::read_dir(&output_dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    files.sort();
+    assert_eq!(files.len(), 1);
+
+    let records = parse_ndjson(&std::fs::read_to_string(&files[0]).unwrap());
+    let operations: Vec<&str> = records
+        .iter()
+        .map(|record| record["operation"].as_str().unwrap())
+        .collect();
+    assert_eq!(operations, vec!["insert", "update", "delete"]);
+    // Cada registro lleva el usuario tal como quedó tras su cambio.
+    assert_eq!(records[0]["user"]["name"], "Ada Lovelace");
+    assert_eq!(records[1]["user"]["name"], "Ada King");
+    assert!(records[2]["user"].is_null());
+    assert_eq!(records[2]["tenant_id"], "default");
+    assert_eq!(records[2]["user_id"], user.id.to_string());
+
+    let last_seq: i64 =
+        sqlx::query_scalar("SELECT last_seq FROM outbox_offsets WHERE consumer = ?")
+            .bind("test")
+            .fetch_one(pool)
+            .await
+            .unwrap();
+    assert_eq!(last_seq, records[2]["seq"].as_i64().unwrap());
+
+    std::fs::remove_dir_all(output_dir).unwrap();
+}
+
+#[tokio::test]
+async fn storage_sink_writes_one_object_per_batch() {
+    let context = TestContext::new().await;
+    let user_id = create_update_and_delete(&context).await;
+
+    let directory = std::env::temp_dir().join(format!("cdc-storage-test-{}", Uuid::new_v4()));
+    let storage: Arc<dyn Storage> = Arc::new(LocalStorage::new(&directory));
+    let mut sink = StorageSink::new(storage.clone(), "cdc/");
+
+    let pool = &context.state.database_pool;
+    assert_eq!(
+        cdc::replicate_once(
+            pool,
+            &context.state.email_encryption,
+            &mut sink,
+            cdc::CONSUMER,
+            2
+        )
+        .await
+        .unwrap(),
+        2
+    );
+    assert_eq!(
+        cdc::replicate_once(
+            pool,
+            &context.state.email_encryption,
+            &mut sink,
+            cdc::CONSUMER,
+            2
+        )
+        .await
+        .unwrap(),
+        1
+    );
+
+    let mut keys: Vec<_> = std::fs::read_dir(directory.join("cdc"))
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    keys.sort();
+    assert_eq!(keys.len(), 2);
+
+    let mut records = Vec::new();
+    for key in &keys {
+        let contents = storage.get(&format!("cdc/{key}")).await.unwrap().unwrap();
+        records.extend(parse_ndjson(&String::from_utf8(contents).unwrap()));
+    }
+    let operations: Vec<&str> = records
+        .iter()
+        .map(|record| record["operation"].as_str().unwrap())
+        .collect();
+    assert_eq!(operations, vec!["insert", "update", "delete"]);
+    assert!(records
+        .iter()
+        .all(|record| record["user_id"] == user_id.to_string()));
+
+    std::fs::remove_dir_all(directory).unwrap();
+}
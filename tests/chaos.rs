@@ -0,0 +1,89 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use axum::{
+    body::Body,
+    http::{self, Request, StatusCode},
+    middleware,
+    routing::{get, Router},
+};
+use http_body_util::BodyExt;
+
+use rust_web_demo::chaos::{self, ChaosConfig, CHAOS_FAULT_HEADER};
+
+#[tokio::test]
+async fn errors_are_injected_without_reaching_the_handler() {
+    let app = app(ChaosConfig {
+        error_percent: 100.0,
+        ..ChaosConfig::default()
+    });
+
+    let response = send(&app, "/users").await;
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert_eq!(response.headers()[CHAOS_FAULT_HEADER], "error");
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    assert!(!String::from_utf8_lossy(&body).contains("handler"));
+}
+
+#[tokio::test]
+async fn latency_is_added_before_handling() {
+    let app = app(ChaosConfig {
+        latency_percent: 100.0,
+        latency: Duration::from_millis(100),
+        ..ChaosConfig::default()
+    });
+
+    let started_at = Instant::now();
+    let response = send(&app, "/users").await;
+    assert!(started_at.elapsed() >= Duration::from_millis(100));
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[CHAOS_FAULT_HEADER], "latency");
+}
+
+#[tokio::test]
+async fn dropped_responses_fail_while_reading_the_body() {
+    let app = app(ChaosConfig {
+        drop_percent: 100.0,
+        ..ChaosConfig::default()
+    });
+
+    let response = send(&app, "/users").await;
+    assert_eq!(response.headers()[CHAOS_FAULT_HEADER], "drop");
+    assert!(response.into_body().collect().await.is_err());
+}
+
+#[tokio::test]
+async fn skipped_paths_and_zero_percentages_are_untouched() {
+    let failing = app(ChaosConfig {
+        error_percent: 100.0,
+        ..ChaosConfig::default()
+    });
+    let response = send(&failing, "/health").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers().get(CHAOS_FAULT_HEADER).is_none());
+
+    let calm = app(ChaosConfig::default());
+    for _ in 0..20 {
+        let response = send(&calm, "/users").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers().get(CHAOS_FAULT_HEADER).is_none());
+    }
+}
+
+fn app(config: ChaosConfig) -> Router {
+    Router::new()
+        .route("/users", get(|| async { "handler" }))
+        .route("/health", get(|| async { "ok" }))
+        .layer(middleware::from_fn_with_state(
+            Arc::new(config),
+            chaos::inject_faults,
+        ))
+}
+
+async fn send(app: &Router, uri: &str) -> http::Response<Body> {
+    tower::ServiceExt::oneshot(app.clone(), Request::get(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+}
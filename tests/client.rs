@@ -0,0 +1,89 @@
+use std::future::IntoFuture;
+
+use reqwest::StatusCode;
+
+use rust_web_demo::{
+    client::{ApiClient, ClientError},
+    models::user::{CreateUser, ListUsersQuery, StatusFilter, UpdateUser},
+    testing::TestContext,
+};
+
+#[tokio::test]
+async fn users_client_round_trips_the_crud_operations() {
+    let client = serve().await;
+    let users = client.users();
+
+    let created = users
+        .create(&CreateUser {
+            name: "Ada".to_string(),
+            email: "ada@example.com".to_string(),
+        })
+        .await
+        .unwrap();
+    assert_eq!(created.email, "ada@example.com");
+    assert_eq!(users.get(created.id).await.unwrap().name, "Ada");
+
+    let updated = users
+        .update(
+            created.id,
+            &UpdateUser {
+                name: Some("Ada Lovelace".to_string()),
+                ..UpdateUser::default()
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(updated.name, "Ada Lovelace");
+    assert_eq!(updated.email, "ada@example.com");
+
+    let listed = users
+        .list(&ListUsersQuery {
+            status: StatusFilter::All,
+            tag: None,
+            filter: None,
+        })
+        .await
+        .unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(users.count().await.unwrap(), 1);
+
+    users.delete(created.id).await.unwrap();
+    let error = users.get(created.id).await.unwrap_err();
+    assert_eq!(error.status(), Some(StatusCode::NOT_FOUND));
+}
+
+#[tokio::test]
+async fn validation_errors_carry_the_server_details() {
+    let client = serve().await;
+
+    let error = client
+        .users()
+        .create(&CreateUser {
+            name: "Ada".to_string(),
+            email: "no-es-un-correo".to_string(),
+        })
+        .await
+        .unwrap_err();
+
+    let ClientError::Api {
+        status,
+        message,
+        errors,
+    } = error
+    else {
+        panic!("se esperaba un error de la API: {error}");
+    };
+    assert_eq!(status, StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(message, "Datos de entrada inválidos");
+    assert!(errors.iter().any(|error| error.field == "email"));
+}
+
+/// Levanta la aplicación de prueba en un puerto libre y devuelve un cliente contra ella.
+async fn serve() -> ApiClient {
+    let context = TestContext::new().await;
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(axum::serve(listener, context.app).into_future());
+
+    ApiClient::new(&format!("http://{address}")).unwrap()
+}
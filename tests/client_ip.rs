@@ -0,0 +1,90 @@
+use std::{net::SocketAddr, sync::Arc};
+
+use axum::{
+    body::Body,
+    extract::connect_info::MockConnectInfo,
+    http::{Request, StatusCode},
+    middleware,
+    routing::get,
+    Router,
+};
+use http_body_util::BodyExt;
+
+use rust_web_demo::client_ip::{self, ClientIp, TrustedProxies};
+
+#[tokio::test]
+async fn forwarded_for_is_ignored_without_trusted_proxies() {
+    let app = app(TrustedProxies { hops: 0 });
+
+    let ip = client_ip_of(&app, Some("203.0.113.7")).await;
+    assert_eq!(ip, "10.0.0.2");
+}
+
+#[tokio::test]
+async fn trusted_hops_are_skipped_from_the_right() {
+    let one_hop = app(TrustedProxies { hops: 1 });
+    assert_eq!(
+        client_ip_of(&one_hop, Some("198.51.100.1, 203.0.113.7")).await,
+        "203.0.113.7"
+    );
+    assert_eq!(client_ip_of(&one_hop, None).await, "10.0.0.2");
+
+    let two_hops = app(TrustedProxies { hops: 2 });
+    assert_eq!(
+        client_ip_of(&two_hops, Some("198.51.100.1, 203.0.113.7:4711, 10.0.0.9")).await,
+        "203.0.113.7"
+    );
+    // Con menos saltos de los configurados se usa la entrada más lejana.
+    assert_eq!(
+        client_ip_of(&two_hops, Some("[2001:db8::1]:443")).await,
+        "2001:db8::1"
+    );
+}
+
+#[tokio::test]
+async fn unparsable_entries_leave_the_ip_unresolved() {
+    let app = app(TrustedProxies { hops: 1 });
+
+    assert_eq!(client_ip_of(&app, Some("unknown")).await, "-");
+
+    let request = Request::builder()
+        .uri("/required")
+        .header("x-forwarded-for", "unknown")
+        .body(Body::empty())
+        .unwrap();
+    let response = tower::ServiceExt::oneshot(app, request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+}
+
+fn app(proxies: TrustedProxies) -> Router {
+    Router::new()
+        .route(
+            "/",
+            get(|ip: Option<ClientIp>| async move {
+                ip.map_or_else(|| "-".to_string(), |ClientIp(ip)| ip.to_string())
+            }),
+        )
+        .route(
+            "/required",
+            get(|ClientIp(ip): ClientIp| async move { ip.to_string() }),
+        )
+        .layer(middleware::from_fn_with_state(
+            Arc::new(proxies),
+            client_ip::resolve_client_ip,
+        ))
+        .layer(MockConnectInfo(SocketAddr::from(([10, 0, 0, 2], 52000))))
+}
+
+async fn client_ip_of(app: &Router, forwarded_for: Option<&str>) -> String {
+    let mut request = Request::builder().uri("/");
+    if let Some(value) = forwarded_for {
+        request = request.header("x-forwarded-for", value);
+    }
+
+    let response = tower::ServiceExt::oneshot(app.clone(), request.body(Body::empty()).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    String::from_utf8(body.to_vec()).unwrap()
+}
@@ -0,0 +1,82 @@
+use std::sync::{Arc, Mutex};
+
+use rust_web_demo::{
+    access_log::{AccessLog, AccessLogConfig},
+    reload::{ConfigReloader, ReloadableSettings, SettingChange},
+};
+
+#[test]
+fn reload_applies_changed_settings_and_reports_them() {
+    let path = std::env::temp_dir().join(format!("reload-{}.env", uuid::Uuid::new_v4()));
+    let access_log = AccessLog::new(AccessLogConfig::default());
+    let applied_filters = Arc::new(Mutex::new(Vec::new()));
+    let reloader = ConfigReloader::new(&path, initial_settings(), access_log.clone())
+        .with_log_filter({
+            let applied_filters = applied_filters.clone();
+            move |filter| {
+                applied_filters.lock().unwrap().push(filter.to_string());
+                Ok(())
+            }
+        });
+
+    std::fs::write(
+        &path,
+        "RUST_LOG=debug\nACCESS_LOG=false\nACCESS_LOG_SKIP_PATHS=/health,/metrics\n",
+    )
+    .unwrap();
+    let report = reloader.reload().unwrap();
+
+    assert_eq!(report.source.as_deref(), Some(path.as_path()));
+    assert_eq!(
+        report.changes,
+        vec![
+            SettingChange {
+                setting: "RUST_LOG".to_string(),
+                old: "info".to_string(),
+                new: "debug".to_string(),
+            },
+            SettingChange {
+                setting: "ACCESS_LOG".to_string(),
+                old: "true".to_string(),
+                new: "false".to_string(),
+            },
+        ]
+    );
+    assert_eq!(*applied_filters.lock().unwrap(), ["debug"]);
+    assert!(!access_log.current().enabled);
+    assert_eq!(reloader.settings().log_filter, "debug");
+
+    // Sin cambios en el archivo no hay nada que aplicar.
+    assert!(reloader.reload().unwrap().changes.is_empty());
+    assert_eq!(applied_filters.lock().unwrap().len(), 1);
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn invalid_values_leave_every_setting_untouched() {
+    let path = std::env::temp_dir().join(format!("reload-{}.env", uuid::Uuid::new_v4()));
+    let access_log = AccessLog::new(AccessLogConfig::default());
+    let reloader = ConfigReloader::new(&path, initial_settings(), access_log.clone())
+        .with_log_filter(|_| panic!("no debe aplicarse un filtro inválido"));
+
+    std::fs::write(
+        &path,
+        "RUST_LOG=access_log=ruidoso\nACCESS_LOG=false\nACCESS_LOG_SKIP_PATHS=/health,/metrics\n",
+    )
+    .unwrap();
+    let error = reloader.reload().unwrap_err();
+
+    assert!(format!("{error:#}").contains("RUST_LOG"), "{error:#}");
+    assert!(access_log.current().enabled);
+    assert_eq!(reloader.settings(), initial_settings());
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+fn initial_settings() -> ReloadableSettings {
+    ReloadableSettings {
+        log_filter: "info".to_string(),
+        access_log: AccessLogConfig::default(),
+    }
+}
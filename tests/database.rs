@@ -0,0 +1,155 @@
+use std::time::Duration;
+
+use axum::{
+    body::Body,
+    extract::State,
+    http::{header, Request, StatusCode},
+    middleware,
+    routing::post,
+    Router,
+};
+use sqlx::{sqlite::SqlitePoolOptions, SqlitePool};
+use tower::ServiceExt;
+
+use rust_web_demo::{config::AppConfig, database, handlers::error::AppError, state::AppState};
+
+#[tokio::test]
+async fn connections_apply_configured_pragmas() {
+    let path = std::env::temp_dir().join(format!("pragmas-test-{}.sqlite", uuid::Uuid::new_v4()));
+    let config = AppConfig {
+        database_url: format!("sqlite://{}?mode=rwc", path.display()),
+        sqlite_busy_timeout: Duration::from_millis(2500),
+        ..AppConfig::default()
+    };
+
+    let pool = SqlitePoolOptions::new()
+        .max_connections(2)
+        .connect_with(database::connect_options(&config).unwrap())
+        .await
+        .unwrap();
+
+    let (journal_mode,): (String,) = sqlx::query_as("PRAGMA journal_mode")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    let (busy_timeout,): (i64,) = sqlx::query_as("PRAGMA busy_timeout")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    let (synchronous,): (i64,) = sqlx::query_as("PRAGMA synchronous")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    let (foreign_keys,): (i64,) = sqlx::query_as("PRAGMA foreign_keys")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+
+    assert_eq!(journal_mode, "wal");
+    assert_eq!(busy_timeout, 2500);
+    // 1 = NORMAL.
+    assert_eq!(synchronous, 1);
+    assert_eq!(foreign_keys, 1);
+
+    pool.close().await;
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{suffix}", path.display()));
+    }
+}
+
+#[tokio::test]
+async fn busy_database_requests_are_retried_then_rejected_with_503() {
+    let path = std::env::temp_dir().join(format!("busy-test-{}.sqlite", uuid::Uuid::new_v4()));
+    let config = AppConfig {
+        database_url: format!("sqlite://{}?mode=rwc", path.display()),
+        sqlite_busy_timeout: Duration::ZERO,
+        sqlite_busy_retries: 3,
+        ..AppConfig::default()
+    };
+    let pool = SqlitePoolOptions::new()
+        .max_connections(2)
+        .connect_with(database::connect_options(&config).unwrap())
+        .await
+        .unwrap();
+    sqlx::query("CREATE TABLE entries (id INTEGER PRIMARY KEY)")
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let state = AppState::new(pool.clone(), config);
+    let app = Router::new()
+        .route("/entries", post(insert_entry))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            database::retry_when_busy,
+        ))
+        .with_state(state);
+    let request = || {
+        Request::builder()
+            .method("POST")
+            .uri("/entries")
+            .body(Body::empty())
+            .unwrap()
+    };
+
+    // El bloqueo se libera mientras el middleware reintenta.
+    let mut locker = pool.acquire().await.unwrap();
+    sqlx::query("BEGIN IMMEDIATE")
+        .execute(&mut *locker)
+        .await
+        .unwrap();
+    let release = tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(80)).await;
+        sqlx::query("COMMIT").execute(&mut *locker).await.unwrap();
+        locker
+    });
+    let response = app.clone().oneshot(request()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    // Con el bloqueo retenido se agotan los reintentos.
+    let mut locker = release.await.unwrap();
+    sqlx::query("BEGIN IMMEDIATE")
+        .execute(&mut *locker)
+        .await
+        .unwrap();
+    let response = app.oneshot(request()).await.unwrap();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(response.headers()[header::RETRY_AFTER], "1");
+    sqlx::query("ROLLBACK").execute(&mut *locker).await.unwrap();
+
+    drop(locker);
+    pool.close().await;
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{suffix}", path.display()));
+    }
+}
+
+async fn insert_entry(State(pool): State<SqlitePool>) -> Result<StatusCode, AppError> {
+    sqlx::query("INSERT INTO entries DEFAULT VALUES")
+        .execute(&pool)
+        .await?;
+    Ok(StatusCode::CREATED)
+}
+
+#[tokio::test]
+async fn metrics_publish_pool_gauges_and_acquire_latency() {
+    let config = AppConfig::default();
+    let pool = database::pool_options(&config)
+        .max_connections(3)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    let state = AppState::new(pool.clone(), config);
+
+    let connection = pool.acquire().await.unwrap();
+    state
+        .metrics
+        .record_pool_acquire(Duration::from_millis(2), false);
+    let output = state.metrics.render_prometheus();
+    drop(connection);
+
+    assert!(output.contains("db_pool_connections{state=\"in_use\"} 1"));
+    assert!(output.contains("db_pool_max_connections 3"));
+    assert!(output.contains("db_pool_acquire_duration_seconds_count 1"));
+    assert!(output.contains("db_pool_acquire_timeouts_total 0"));
+}
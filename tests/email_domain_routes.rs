@@ -0,0 +1,196 @@
+use axum::{
+    body::Body,
+    http::{self, Request, StatusCode},
+    middleware,
+    routing::Router,
+};
+use http_body_util::BodyExt;
+use sqlx::sqlite::SqlitePoolOptions;
+
+use rust_web_demo::{config::AppConfig, email_domains, models, routes, state::AppState, tenant};
+
+#[tokio::test]
+async fn blocked_domains_reject_sign_ups_until_unblocked() {
+    let context = TestContext::new().await;
+
+    let response = context
+        .send(
+            http::Method::POST,
+            "/admin/blocked-email-domains",
+            Some(serde_json::json!({ "domain": "@Mailinator.COM", "reason": "Desechable" })),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let blocked: models::email_domain::BlockedEmailDomain = body_json(response).await;
+    assert_eq!(blocked.domain, "mailinator.com");
+    assert_eq!(blocked.reason.as_deref(), Some("Desechable"));
+
+    let response = context
+        .send(
+            http::Method::POST,
+            "/admin/blocked-email-domains",
+            Some(serde_json::json!({ "domain": "mailinator.com" })),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    for email in ["temp@mailinator.com", "temp@eu.mailinator.com"] {
+        let response = context
+            .send(
+                http::Method::POST,
+                "/users",
+                Some(serde_json::json!({ "name": "Temp", "email": email })),
+            )
+            .await;
+        assert_eq!(
+            response.status(),
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "{email}"
+        );
+        let body: serde_json::Value = body_json(response).await;
+        assert_eq!(body["errors"][0]["field"], "email");
+        assert_eq!(
+            body["errors"][0]["code"],
+            email_domains::BLOCKED_EMAIL_DOMAIN_CODE
+        );
+    }
+
+    let response = context
+        .send(
+            http::Method::POST,
+            "/users/batch",
+            Some(serde_json::json!([
+                { "name": "Temp", "email": "temp@mailinator.com" },
+                { "name": "Ada", "email": "ada@example.com" }
+            ])),
+        )
+        .await;
+    let body: serde_json::Value = body_json(response).await;
+    assert_eq!(body["results"][0]["status"], 422);
+    assert_eq!(
+        body["results"][0]["errors"][0]["code"],
+        email_domains::BLOCKED_EMAIL_DOMAIN_CODE
+    );
+    assert_eq!(body["results"][1]["status"], 201);
+
+    let listed: Vec<models::email_domain::BlockedEmailDomain> = body_json(
+        context
+            .send(http::Method::GET, "/admin/blocked-email-domains", None)
+            .await,
+    )
+    .await;
+    assert_eq!(listed.len(), 1);
+
+    let response = context
+        .send(
+            http::Method::DELETE,
+            "/admin/blocked-email-domains/mailinator.com",
+            None,
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = context
+        .send(
+            http::Method::DELETE,
+            "/admin/blocked-email-domains/mailinator.com",
+            None,
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let response = context
+        .send(
+            http::Method::POST,
+            "/users",
+            Some(serde_json::json!({ "name": "Temp", "email": "temp@mailinator.com" })),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn blocked_domains_are_imported_from_a_file() {
+    let context = TestContext::new().await;
+    let path = std::env::temp_dir().join(format!("blocked-{}.txt", uuid::Uuid::new_v4()));
+    std::fs::write(
+        &path,
+        "# Proveedores desechables\nyopmail.com\n\nGuerrillaMail.com # alias\nno es un dominio\n",
+    )
+    .unwrap();
+
+    let imported = email_domains::import_file(&context.pool, &path)
+        .await
+        .unwrap();
+    assert_eq!(imported, 2);
+    assert_eq!(
+        email_domains::import_file(&context.pool, &path)
+            .await
+            .unwrap(),
+        0
+    );
+    std::fs::remove_file(&path).unwrap();
+
+    let listed: Vec<models::email_domain::BlockedEmailDomain> = body_json(
+        context
+            .send(http::Method::GET, "/admin/blocked-email-domains", None)
+            .await,
+    )
+    .await;
+    let domains = listed
+        .iter()
+        .map(|blocked| blocked.domain.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(domains, ["guerrillamail.com", "yopmail.com"]);
+}
+
+async fn body_json<T: serde::de::DeserializeOwned>(response: http::Response<Body>) -> T {
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+struct TestContext {
+    app: Router,
+    pool: sqlx::SqlitePool,
+}
+
+impl TestContext {
+    async fn new() -> Self {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+
+        let state = AppState::new(pool.clone(), AppConfig::default());
+        let app = routes::user_routes()
+            .route_layer(middleware::from_fn_with_state(
+                state.clone(),
+                tenant::resolve_tenant,
+            ))
+            .merge(routes::email_domain_routes())
+            .with_state(state);
+
+        Self { app, pool }
+    }
+
+    async fn send(
+        &self,
+        method: http::Method,
+        uri: &str,
+        payload: Option<serde_json::Value>,
+    ) -> http::Response<Body> {
+        let mut request = Request::builder().method(method).uri(uri);
+        let body = match payload {
+            Some(payload) => {
+                request = request.header(http::header::CONTENT_TYPE, "application/json");
+                Body::from(serde_json::to_vec(&payload).unwrap())
+            }
+            None => Body::empty(),
+        };
+
+        tower::ServiceExt::oneshot(self.app.clone(), request.body(body).unwrap())
+            .await
+            .unwrap()
+    }
+}
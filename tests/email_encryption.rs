@@ -0,0 +1,368 @@
+use axum::{
+    body::Body,
+    http::{self, Request, StatusCode},
+    routing::Router,
+};
+use http_body_util::BodyExt;
+use sqlx::{sqlite::SqlitePoolOptions, SqlitePool};
+
+use rust_web_demo::{
+    config::AppConfig,
+    encryption::{self, EmailEncryption},
+    events::EventBus,
+    models::user::User,
+    outbox, routes,
+    state::AppState,
+    testing::factory::UserFactory,
+};
+
+const FIRST_KEY: [u8; 32] = [1; 32];
+const SECOND_KEY: [u8; 32] = [2; 32];
+const BLIND_INDEX_KEY: [u8; 32] = [9; 32];
+
+#[tokio::test]
+async fn emails_are_stored_encrypted_and_served_in_clear() {
+    let pool = connect().await;
+    let encryption = EmailEncryption::new(FIRST_KEY, &[], BLIND_INDEX_KEY);
+    let app = app(&pool, encryption.clone());
+
+    let response = send(
+        &app,
+        http::Method::POST,
+        "/users",
+        Some(serde_json::json!({ "name": "Ada", "email": "Ada@Example.com" })),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let created: User = body_json(response).await;
+    assert_eq!(created.email, "ada@example.com");
+
+    let (stored, index) = stored_email(&pool, &created).await;
+    assert!(stored.starts_with("enc:v1:"), "{stored}");
+    assert!(!stored.contains("ada@example.com"));
+    assert_eq!(index, encryption.blind_index("ada@example.com"));
+
+    let fetched: User = body_json(
+        send(
+            &app,
+            http::Method::GET,
+            &format!("/users/{}", created.id),
+            None,
+        )
+        .await,
+    )
+    .await;
+    assert_eq!(fetched.email, "ada@example.com");
+
+    // La unicidad del correo se mantiene a través del índice ciego.
+    let response = send(
+        &app,
+        http::Method::POST,
+        "/users/batch",
+        Some(serde_json::json!([{ "name": "Otra Ada", "email": "ada@example.com" }])),
+    )
+    .await;
+    let batch: serde_json::Value = body_json(response).await;
+    assert_eq!(batch["results"][0]["status"], 409);
+
+    let response = send(
+        &app,
+        http::Method::PUT,
+        &format!("/users/{}", created.id),
+        Some(serde_json::json!({ "email": "lovelace@example.com" })),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let (updated, updated_index) = stored_email(&pool, &created).await;
+    assert_ne!(updated, stored);
+    assert_eq!(encryption.open(&updated).unwrap(), "lovelace@example.com");
+    assert_eq!(
+        updated_index,
+        encryption.blind_index("lovelace@example.com")
+    );
+}
+
+#[tokio::test]
+async fn outbox_events_keep_the_email_encrypted_until_relayed() {
+    let pool = connect().await;
+    let encryption = EmailEncryption::new(FIRST_KEY, &[], BLIND_INDEX_KEY);
+    let app = app(&pool, encryption.clone());
+
+    let response = send(
+        &app,
+        http::Method::POST,
+        "/users",
+        Some(serde_json::json!({ "name": "Ada", "email": "ada@example.com" })),
+    )
+    .await;
+    let created: User = body_json(response).await;
+    send(
+        &app,
+        http::Method::PUT,
+        &format!("/users/{}", created.id),
+        Some(serde_json::json!({ "email": "lovelace@example.com" })),
+    )
+    .await;
+
+    let payloads: Vec<String> = sqlx::query_scalar("SELECT payload FROM outbox ORDER BY seq")
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+    assert_eq!(payloads.len(), 2);
+    for payload in &payloads {
+        assert!(!payload.contains("ada@example.com"), "{payload}");
+        assert!(!payload.contains("lovelace@example.com"), "{payload}");
+    }
+
+    // Los suscriptores reciben el correo en claro.
+    let events = EventBus::new();
+    let mut receiver = events.subscribe();
+    assert_eq!(
+        outbox::relay_once(&pool, &events, &encryption)
+            .await
+            .unwrap(),
+        2
+    );
+    let emails = [
+        receiver.recv().await.unwrap(),
+        receiver.recv().await.unwrap(),
+    ]
+    .map(|mut envelope| envelope.event.user_mut().unwrap().email.clone());
+    assert_eq!(emails, ["ada@example.com", "lovelace@example.com"]);
+}
+
+#[tokio::test]
+async fn factory_users_are_stored_like_the_api_stores_them() {
+    let pool = connect().await;
+    let encryption = EmailEncryption::new(FIRST_KEY, &[], BLIND_INDEX_KEY);
+    let app = app(&pool, encryption.clone());
+
+    let user = UserFactory::new()
+        .with_email("ada@example.com")
+        .with_encryption(&encryption)
+        .create(&pool)
+        .await;
+    let (stored, index) = stored_email(&pool, &user).await;
+    assert!(stored.starts_with("enc:v1:"), "{stored}");
+    assert_eq!(index, encryption.blind_index("ada@example.com"));
+
+    let fetched: User = body_json(
+        send(
+            &app,
+            http::Method::GET,
+            &format!("/users/{}", user.id),
+            None,
+        )
+        .await,
+    )
+    .await;
+    assert_eq!(fetched.email, "ada@example.com");
+    assert_eq!(fetched.name, user.name);
+
+    // El índice ciego de la fábrica también protege la unicidad del correo.
+    let response = send(
+        &app,
+        http::Method::POST,
+        "/users/batch",
+        Some(serde_json::json!([{ "name": "Otra Ada", "email": "ada@example.com" }])),
+    )
+    .await;
+    let batch: serde_json::Value = body_json(response).await;
+    assert_eq!(batch["results"][0]["status"], 409);
+}
+
+#[tokio::test]
+async fn plaintext_rows_not_yet_rekeyed_keep_their_email_unique() {
+    let pool = connect().await;
+    let legacy = UserFactory::new()
+        .with_email("grace@example.com")
+        .create(&pool)
+        .await;
+    assert_eq!(stored_email(&pool, &legacy).await.1, None);
+
+    let app = app(&pool, EmailEncryption::new(FIRST_KEY, &[], BLIND_INDEX_KEY));
+    let duplicate = serde_json::json!({ "name": "Otra Grace", "email": "Grace@Example.com" });
+
+    let response = send(&app, http::Method::POST, "/users", Some(duplicate.clone())).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body: serde_json::Value = body_json(response).await;
+    assert_eq!(body["errors"][0]["field"], "email");
+
+    let response = send(
+        &app,
+        http::Method::POST,
+        "/users/batch",
+        Some(serde_json::json!([duplicate])),
+    )
+    .await;
+    let batch: serde_json::Value = body_json(response).await;
+    assert_eq!(batch["results"][0]["status"], 409);
+
+    let response = send(
+        &app,
+        http::Method::POST,
+        "/users",
+        Some(serde_json::json!({ "name": "Ada", "email": "ada@example.com" })),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let ada: User = body_json(response).await;
+    let response = send(
+        &app,
+        http::Method::PUT,
+        &format!("/users/{}", ada.id),
+        Some(serde_json::json!({ "email": "grace@example.com" })),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    assert_eq!(
+        stored_email(&pool, &ada).await.1,
+        EmailEncryption::new(FIRST_KEY, &[], BLIND_INDEX_KEY).blind_index("ada@example.com")
+    );
+
+    let users: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(users, 2);
+}
+
+#[tokio::test]
+async fn rekey_encrypts_plaintext_rows_and_rotates_keys() {
+    let pool = connect().await;
+    let plaintext_app = app(&pool, EmailEncryption::disabled());
+    let grace: User = body_json(
+        send(
+            &plaintext_app,
+            http::Method::POST,
+            "/users",
+            Some(serde_json::json!({ "name": "Grace", "email": "grace@example.com" })),
+        )
+        .await,
+    )
+    .await;
+    assert_eq!(
+        stored_email(&pool, &grace).await,
+        ("grace@example.com".to_string(), None)
+    );
+
+    let first = EmailEncryption::new(FIRST_KEY, &[], BLIND_INDEX_KEY);
+    let ada: User = body_json(
+        send(
+            &app(&pool, first.clone()),
+            http::Method::POST,
+            "/users",
+            Some(serde_json::json!({ "name": "Ada", "email": "ada@example.com" })),
+        )
+        .await,
+    )
+    .await;
+
+    let report = encryption::rekey(&pool, &first).await.unwrap();
+    assert_eq!((report.users, report.rewritten), (2, 1));
+
+    let second = EmailEncryption::new(SECOND_KEY, &[FIRST_KEY], BLIND_INDEX_KEY);
+    let report = encryption::rekey(&pool, &second).await.unwrap();
+    assert_eq!((report.users, report.rewritten), (2, 2));
+    let report = encryption::rekey(&pool, &second).await.unwrap();
+    assert_eq!(report.rewritten, 0);
+
+    // Tras la rotación basta con la clave nueva.
+    let only_second = EmailEncryption::new(SECOND_KEY, &[], BLIND_INDEX_KEY);
+    let key_id = only_second.current_key_id().unwrap().to_string();
+    let app = app(&pool, only_second);
+    for (user, email) in [(&grace, "grace@example.com"), (&ada, "ada@example.com")] {
+        let (stored, _) = stored_email(&pool, user).await;
+        assert!(stored.starts_with(&format!("enc:v1:{key_id}:")), "{stored}");
+
+        let fetched: User = body_json(
+            send(
+                &app,
+                http::Method::GET,
+                &format!("/users/{}", user.id),
+                None,
+            )
+            .await,
+        )
+        .await;
+        assert_eq!(fetched.email, email);
+    }
+}
+
+#[tokio::test]
+async fn invalid_keys_are_rejected_at_startup() {
+    let config = AppConfig {
+        email_encryption_key: Some("no-es-hex".to_string()),
+        email_blind_index_key: Some(hex_key(BLIND_INDEX_KEY)),
+        ..AppConfig::default()
+    };
+    assert!(EmailEncryption::from_config(&config).is_err());
+
+    let config = AppConfig {
+        email_encryption_key: Some(hex_key(FIRST_KEY)),
+        ..AppConfig::default()
+    };
+    assert!(EmailEncryption::from_config(&config).is_err());
+
+    let config = AppConfig {
+        email_encryption_key: Some(hex_key(FIRST_KEY)),
+        email_blind_index_key: Some(hex_key(BLIND_INDEX_KEY)),
+        ..AppConfig::default()
+    };
+    assert!(EmailEncryption::from_config(&config).unwrap().is_enabled());
+    assert!(!EmailEncryption::from_config(&AppConfig::default())
+        .unwrap()
+        .is_enabled());
+}
+
+fn hex_key(key: [u8; 32]) -> String {
+    key.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+async fn stored_email(pool: &SqlitePool, user: &User) -> (String, Option<String>) {
+    sqlx::query_as("SELECT email, email_index FROM users WHERE id = ?")
+        .bind(user.id)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+async fn body_json<T: serde::de::DeserializeOwned>(response: http::Response<Body>) -> T {
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+async fn connect() -> SqlitePool {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+    pool
+}
+
+fn app(pool: &SqlitePool, encryption: EmailEncryption) -> Router {
+    let state = AppState::new(pool.clone(), AppConfig::default()).with_email_encryption(encryption);
+    routes::api_routes(&state).with_state(state)
+}
+
+async fn send(
+    app: &Router,
+    method: http::Method,
+    uri: &str,
+    payload: Option<serde_json::Value>,
+) -> http::Response<Body> {
+    let mut request = Request::builder().method(method).uri(uri);
+    let body = match payload {
+        Some(payload) => {
+            request = request.header(http::header::CONTENT_TYPE, "application/json");
+            Body::from(serde_json::to_vec(&payload).unwrap())
+        }
+        None => Body::empty(),
+    };
+
+    tower::ServiceExt::oneshot(app.clone(), request.body(body).unwrap())
+        .await
+        .unwrap()
+}
@@ -0,0 +1,63 @@
+use rust_web_demo::models::email::normalize_email;
+
+#[test]
+fn accepts_rfc_addresses_rejected_by_the_old_check() {
+    let valid_emails = [
+        "\"john doe\"@example.com",
+        "\"a@b\"@example.com",
+        "\"quoted\\\"escape\"@example.com",
+        "o'connor@example.ie",
+        "josé@example.com",
+        "user@[192.0.2.1]",
+        "user@[ipv6:2001:db8::1]",
+    ];
+
+    for email in valid_emails {
+        assert_eq!(
+            normalize_email(email).as_deref(),
+            Some(email),
+            "Should accept email: {email}"
+        );
+    }
+}
+
+#[test]
+fn rejects_malformed_addresses_accepted_by_the_old_check() {
+    let long_local_part = format!("{}@example.com", "a".repeat(65));
+    let long_label = format!("user@{}.com", "a".repeat(64));
+    let invalid_emails = [
+        "user..name@example.com",
+        ".user@example.com",
+        "user.@example.com",
+        "us er@example.com",
+        "user@-example.com",
+        "user@example-.com",
+        "user@exa_mple.com",
+        "user@example..com",
+        "user@1.2.3.4",
+        "user@[300.0.0.1]",
+        "\"unterminated@example.com",
+        long_local_part.as_str(),
+        long_label.as_str(),
+    ];
+
+    for email in invalid_emails {
+        assert_eq!(normalize_email(email), None, "Should reject email: {email}");
+    }
+}
+
+#[test]
+fn internationalized_domains_are_normalized_to_punycode() {
+    assert_eq!(
+        normalize_email("user@bücher.example").as_deref(),
+        Some("user@xn--bcher-kva.example")
+    );
+    assert_eq!(
+        normalize_email("user@xn--bcher-kva.example").as_deref(),
+        Some("user@xn--bcher-kva.example")
+    );
+    assert_eq!(
+        normalize_email("josé@例え.テスト").as_deref(),
+        Some("josé@xn--r8jz45g.xn--zckzah")
+    );
+}
@@ -0,0 +1,66 @@
+use std::collections::HashMap;
+
+use rust_web_demo::env_schema::{self, SCHEMA};
+
+#[test]
+fn every_problem_is_reported_at_once() {
+    let problems = validate(&[
+        ("PORT", "80a"),
+        ("ID_FORMAT", "uuid9"),
+        ("ACCESS_LOG", "quizá"),
+        ("STORAGE_BACKEND", "s3"),
+        ("S3_BUCKET", "copias"),
+    ]);
+
+    let names: Vec<&str> = problems.iter().map(|problem| problem.name).collect();
+    assert_eq!(
+        names,
+        [
+            "PORT",
+            "ACCESS_LOG",
+            "ID_FORMAT",
+            "S3_ACCESS_KEY_ID",
+            "S3_SECRET_ACCESS_KEY"
+        ]
+    );
+    assert_eq!(
+        problems[0].to_string(),
+        "PORT: «80a» no es un entero entre 0 y 65535"
+    );
+    assert_eq!(problems[3].message, "es obligatoria con STORAGE_BACKEND=s3");
+}
+
+#[test]
+fn conditional_requirements_look_inside_lists() {
+    let problems = validate(&[("SECRETS_PROVIDERS", "env, Vault")]);
+
+    let names: Vec<&str> = problems.iter().map(|problem| problem.name).collect();
+    assert_eq!(names, ["VAULT_ADDR", "VAULT_TOKEN", "VAULT_SECRET_PATH"]);
+    assert!(validate(&[("SECRETS_PROVIDERS", "env,ssm")])
+        .iter()
+        .any(|problem| problem.name == "SECRETS_PROVIDERS"));
+}
+
+#[test]
+fn empty_environment_and_documented_defaults_are_valid() {
+    assert!(validate(&[]).is_empty());
+    assert!(validate(&[("PORT", "  ")]).is_empty());
+
+    for variable in SCHEMA {
+        if let Some(default) = variable.default {
+            assert!(
+                variable.kind.check(default).is_ok(),
+                "el valor por defecto de {} no es válido",
+                variable.name
+            );
+        }
+    }
+}
+
+fn validate(variables: &[(&str, &str)]) -> Vec<env_schema::EnvProblem> {
+    let variables: HashMap<String, String> = variables
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+    env_schema::validate(|name| variables.get(name).cloned())
+}
@@ -0,0 +1,158 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    http::{self, Request, StatusCode},
+};
+use chrono::{Duration, TimeZone, Utc};
+
+use rust_web_demo::{
+    clock::MockClock,
+    config::AppConfig,
+    export::{job::ExportJob, ExportFormat},
+    jobs::{JobRegistry, Worker},
+    models::{
+        export::{Export, ExportStatus},
+        user::UserStatus,
+    },
+    state::AppState,
+    testing::{body_bytes, body_json, factory::UserFactory, test_pool, TestContext},
+};
+
+async fn create_export(context: &TestContext, payload: serde_json::Value) -> Export {
+    let response = context.post_json("/exports", payload).await;
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let export: Export = body_json(response).await;
+    assert_eq!(export.status, ExportStatus::Pending);
+    export
+}
+
+async fn fetch_export(context: &TestContext, export: &Export) -> Export {
+    let response = context.get(&format!("/exports/{}", export.id)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    body_json(response).await
+}
+
+fn worker(context: &TestContext) -> Worker {
+    Worker::new(
+        context.state.clone(),
+        JobRegistry::new().register::<ExportJob>(),
+    )
+}
+
+#[tokio::test]
+async fn exports_run_in_the_background_and_download_with_signed_links() {
+    let clock = MockClock::new(Utc.with_ymd_and_hms(2026, 10, 15, 12, 0, 0).unwrap());
+    let state =
+        AppState::new(test_pool().await, AppConfig::default()).with_clock(Arc::new(clock.clone()));
+    let context = TestContext::from_state(state);
+    let pool = &context.state.database_pool;
+    for (name, status) in [
+        ("Ada Lovelace", UserStatus::Active),
+        ("Ada Byron", UserStatus::Suspended),
+        ("Grace Hopper", UserStatus::Active),
+    ] {
+        UserFactory::new()
+            .with_name(name)
+            .with_status(status)
+            .create(pool)
+            .await;
+    }
+
+    let export = create_export(
+        &context,
+        serde_json::json!({ "format": "csv", "filter": r#"name sw "ada""# }),
+    )
+    .await;
+    assert_eq!(export.format, ExportFormat::Csv);
+    assert!(fetch_export(&context, &export).await.download_url.is_none());
+
+    assert!(worker(&context).run_once().await.unwrap());
+    let completed = fetch_export(&context, &export).await;
+    assert_eq!(completed.status, ExportStatus::Completed);
+    assert_eq!(completed.row_count, Some(1));
+    let url = completed.download_url.unwrap();
+
+    let response = context.get(&url).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[http::header::CONTENT_TYPE],
+        "text/csv; charset=utf-8"
+    );
+    let csv = String::from_utf8(body_bytes(response).await).unwrap();
+    assert!(csv.contains("Ada Lovelace"));
+    assert!(!csv.contains("Ada Byron") && !csv.contains("Grace Hopper"));
+
+    // La firma no vale para otra exportación ni admite retoques.
+    let other = create_export(&context, serde_json::json!({ "status": "all" })).await;
+    let tampered = if url.ends_with('0') {
+        format!("{}1", &url[..url.len() - 1])
+    } else {
+        format!("{}0", &url[..url.len() - 1])
+    };
+    for forged in [
+        tampered,
+        url.replace(&export.id.to_string(), &other.id.to_string()),
+    ] {
+        let response = context.get(&forged).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN, "{forged}");
+    }
+
+    // El enlace caduca, pero la exportación sigue ofreciendo uno nuevo.
+    clock.advance(Duration::seconds(900));
+    assert_eq!(context.get(&url).await.status(), StatusCode::FORBIDDEN);
+    let fresh = fetch_export(&context, &export).await.download_url.unwrap();
+    assert_eq!(context.get(&fresh).await.status(), StatusCode::OK);
+
+    assert!(worker(&context).run_once().await.unwrap());
+    let other = fetch_export(&context, &other).await;
+    assert_eq!(other.row_count, Some(3));
+    let users: Vec<serde_json::Value> =
+        body_json(context.get(&other.download_url.unwrap()).await).await;
+    assert_eq!(users.len(), 3);
+}
+
+#[tokio::test]
+async fn exports_belong_to_their_tenant_and_reject_invalid_parameters() {
+    let context = TestContext::new().await;
+    let pool = &context.state.database_pool;
+    sqlx::query("INSERT INTO tenants (id, name, created_at) VALUES ('acme', 'Acme', ?)")
+        .bind(Utc::now())
+        .execute(pool)
+        .await
+        .unwrap();
+
+    let export = create_export(&context, serde_json::json!({})).await;
+    assert_eq!(export.format, ExportFormat::Json);
+    let response = context
+        .request(
+            Request::builder()
+                .uri(format!("/exports/{}", export.id))
+                .header("x-tenant-id", "acme")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    for (payload, field) in [
+        (serde_json::json!({ "filter": "name eq" }), "filter"),
+        (serde_json::json!({ "tag": "No válida" }), "tag"),
+    ] {
+        let response = context.post_json("/exports", payload).await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body: serde_json::Value = body_json(response).await;
+        assert_eq!(body["errors"][0]["field"], field);
+    }
+    let response = context
+        .post_json("/exports", serde_json::json!({ "format": "xml" }))
+        .await;
+    assert!(response.status().is_client_error());
+
+    // Solo la exportación válida llegó a encolarse.
+    let jobs: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM jobs")
+        .fetch_one(pool)
+        .await
+        .unwrap();
+    assert_eq!(jobs, 1);
+}
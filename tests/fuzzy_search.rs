@@ -0,0 +1,110 @@
+use axum::http::StatusCode;
+
+use rust_web_demo::{
+    fuzzy_search,
+    models::{erasure::ErasureConfirmation, user::UserMatch},
+    testing::{body_json, TestContext},
+};
+
+async fn search(context: &TestContext, query: &str) -> Vec<(String, f64)> {
+    let response = context.get(&format!("/users/search?{query}")).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let matches: Vec<UserMatch> = body_json(response).await;
+    matches
+        .into_iter()
+        .map(|found| (found.user.name, found.similarity))
+        .collect()
+}
+
+fn names(matches: &[(String, f64)]) -> Vec<&str> {
+    matches.iter().map(|(name, _)| name.as_str()).collect()
+}
+
+#[tokio::test]
+async fn search_tolerates_typos_and_follows_writes() {
+    let context = TestContext::new().await;
+    let grace_hopper = context
+        .create_user("Grace Hopper", "hopper@example.com")
+        .await;
+    context.create_user("Grace", "grace@example.com").await;
+    let ada = context.create_user("Ada Lovelace", "ada@example.com").await;
+    let alan = context.create_user("Alan Turing", "alan@example.com").await;
+
+    // `grce` comparte 3 de sus 5 trigramas con «Grace»; el nombre más corto se parece más.
+    let matches = search(&context, "q=grce").await;
+    assert_eq!(names(&matches), ["Grace", "Grace Hopper"]);
+    assert!((matches[0].1 - 0.6).abs() < 1e-9);
+    assert_eq!(names(&search(&context, "q=grce&limit=1").await), ["Grace"]);
+    assert!(search(&context, "q=grce&threshold=0.9").await.is_empty());
+    assert_eq!(
+        names(&search(&context, "q=ada%20lovlace").await),
+        ["Ada Lovelace"]
+    );
+
+    let response = context
+        .put_json(
+            &format!("/users/{}", ada.id),
+            serde_json::json!({ "name": "Augusta King" }),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(search(&context, "q=lovlace").await.is_empty());
+    assert_eq!(names(&search(&context, "q=augsta").await), ["Augusta King"]);
+
+    context.delete(&format!("/users/{}", alan.id)).await;
+    assert!(search(&context, "q=alan").await.is_empty());
+
+    let erase_uri = format!("/users/{}/erase", grace_hopper.id);
+    let confirmation: ErasureConfirmation =
+        body_json(context.post_json(&erase_uri, serde_json::json!({})).await).await;
+    let response = context
+        .post_json(
+            &erase_uri,
+            serde_json::json!({ "confirmation_token": confirmation.confirmation_token }),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(search(&context, "q=hopper").await.is_empty());
+
+    let orphans: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM user_trigrams WHERE user_id IN (?, ?)")
+            .bind(alan.id)
+            .bind(grace_hopper.id)
+            .fetch_one(&context.state.database_pool)
+            .await
+            .unwrap();
+    assert_eq!(orphans, 0);
+}
+
+#[tokio::test]
+async fn users_created_before_the_index_are_backfilled() {
+    let context = TestContext::new().await;
+    let pool = &context.state.database_pool;
+    sqlx::query("INSERT INTO users (id, name, email, created_at) VALUES (?, ?, ?, ?)")
+        .bind(uuid::Uuid::now_v7())
+        .bind("Grace Hopper")
+        .bind("grace@example.com")
+        .bind(chrono::Utc::now())
+        .execute(pool)
+        .await
+        .unwrap();
+    assert!(search(&context, "q=grace").await.is_empty());
+
+    assert_eq!(fuzzy_search::index_missing(pool).await.unwrap(), 1);
+    assert_eq!(names(&search(&context, "q=grace").await), ["Grace Hopper"]);
+    assert_eq!(fuzzy_search::index_missing(pool).await.unwrap(), 0);
+
+    for query in [
+        "q=%20!",
+        "q=ada&limit=0",
+        "q=ada&threshold=0",
+        "q=ada&threshold=1.5",
+    ] {
+        let response = context.get(&format!("/users/search?{query}")).await;
+        assert_eq!(
+            response.status(),
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "{query}"
+        );
+    }
+}
@@ -0,0 +1,106 @@
+use axum::http::StatusCode;
+use chrono::{Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use rust_web_demo::{
+    cache::{Cache, CacheConfig, MemoryStore},
+    config::AppConfig,
+    jobs::{self, Job},
+    migrations,
+    state::AppState,
+    testing::{body_json, test_pool, TestContext},
+};
+
+/// Trabajo de prueba que no hace nada; solo ocupa la cola.
+#[derive(Serialize, Deserialize)]
+struct Noop;
+
+impl Job for Noop {
+    const KIND: &'static str = "test.noop";
+
+    async fn run(self, _state: AppState) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn readiness_reports_each_dependency() {
+    let context = TestContext::new().await;
+
+    let response = context.get("/health/ready").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: Value = body_json(response).await;
+    assert_eq!(body["status"], "up");
+
+    let checks = &body["checks"];
+    assert_eq!(checks["migrations"]["status"], "up");
+    assert_eq!(
+        checks["migrations"]["version"],
+        checks["migrations"]["expected_version"]
+    );
+    assert_eq!(checks["migrations"]["pending"], serde_json::json!([]));
+    assert_eq!(checks["database"]["status"], "up");
+    assert!(checks["database"]["latency_ms"].is_number());
+    assert_eq!(checks["job_queue"]["status"], "up");
+    assert_eq!(checks["job_queue"]["pending"], 0);
+    assert_eq!(checks["cache"]["status"], "disabled");
+}
+
+#[tokio::test]
+async fn a_stalled_job_queue_degrades_without_failing_readiness() {
+    let state = AppState::new(test_pool().await, AppConfig::default());
+    let cache = Cache::new(
+        MemoryStore::new(10),
+        CacheConfig::default(),
+        state.metrics.clone(),
+    );
+    let context = TestContext::from_state(state.with_cache(cache));
+    jobs::enqueue_at(
+        &context.state.database_pool,
+        &Noop,
+        Utc::now() - Duration::minutes(10),
+    )
+    .await
+    .unwrap();
+    jobs::enqueue_at(
+        &context.state.database_pool,
+        &Noop,
+        Utc::now() + Duration::hours(1),
+    )
+    .await
+    .unwrap();
+
+    let response = context.get("/health/ready").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body: Value = body_json(response).await;
+    assert_eq!(body["status"], "degraded");
+
+    let job_queue = &body["checks"]["job_queue"];
+    assert_eq!(job_queue["status"], "degraded");
+    assert_eq!(job_queue["pending"], 2);
+    assert!(job_queue["oldest_wait_secs"].as_i64().unwrap() >= 600);
+    assert_eq!(body["checks"]["cache"]["status"], "up");
+}
+
+#[tokio::test]
+async fn pending_migrations_make_the_service_unready() {
+    let context = TestContext::new().await;
+    let undone = migrations::undo_last(&context.state.database_pool)
+        .await
+        .unwrap()
+        .unwrap();
+
+    let response = context.get("/health/ready").await;
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    let body: Value = body_json(response).await;
+    assert_eq!(body["status"], "down");
+    assert_eq!(body["checks"]["migrations"]["status"], "down");
+    assert_eq!(
+        body["checks"]["migrations"]["pending"],
+        serde_json::json!([undone])
+    );
+
+    // La liveness no depende del esquema.
+    assert_eq!(context.get("/health").await.status(), StatusCode::OK);
+}
@@ -0,0 +1,167 @@
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqlitePoolOptions;
+use uuid::Uuid;
+
+use rust_web_demo::{
+    config::AppConfig,
+    jobs::{self, Job, JobRegistry, JobStatus, Worker},
+    state::AppState,
+};
+
+/// Trabajo de prueba que inserta un usuario.
+#[derive(Serialize, Deserialize)]
+struct InsertUser {
+    name: String,
+    email: String,
+}
+
+impl Job for InsertUser {
+    const KIND: &'static str = "test.insert_user";
+
+    async fn run(self, state: AppState) -> anyhow::Result<()> {
+        sqlx::query("INSERT INTO users (id, name, email, created_at) VALUES (?, ?, ?, ?)")
+            .bind(Uuid::new_v4())
+            .bind(self.name)
+            .bind(self.email)
+            .bind(chrono::Utc::now())
+            .execute(&state.database_pool)
+            .await?;
+        Ok(())
+    }
+}
+
+/// Trabajo de prueba que siempre falla.
+#[derive(Serialize, Deserialize)]
+struct AlwaysFails;
+
+impl Job for AlwaysFails {
+    const KIND: &'static str = "test.always_fails";
+    const MAX_ATTEMPTS: u32 = 2;
+
+    async fn run(self, _state: AppState) -> anyhow::Result<()> {
+        anyhow::bail!("fallo simulado")
+    }
+}
+
+#[tokio::test]
+async fn worker_runs_enqueued_job_and_marks_it_completed() {
+    let state = test_state().await;
+    let worker = test_worker(&state);
+
+    let job_id = jobs::enqueue(
+        &state.database_pool,
+        &InsertUser {
+            name: "Ada Lovelace".to_string(),
+            email: "ada@example.com".to_string(),
+        },
+    )
+    .await
+    .unwrap();
+
+    assert!(worker.run_once().await.unwrap());
+    assert!(!worker.run_once().await.unwrap());
+
+    let job = jobs::fetch_job(&state.database_pool, job_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(job.status, JobStatus::Completed);
+    assert_eq!(job.attempts, 1);
+
+    let users: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM users")
+        .fetch_one(&state.database_pool)
+        .await
+        .unwrap();
+    assert_eq!(users, 1);
+}
+
+#[tokio::test]
+async fn failing_job_is_retried_until_attempts_are_exhausted() {
+    let state = test_state().await;
+    let worker = test_worker(&state);
+
+    let job_id = jobs::enqueue(&state.database_pool, &AlwaysFails)
+        .await
+        .unwrap();
+
+    assert!(worker.run_once().await.unwrap());
+    let job = jobs::fetch_job(&state.database_pool, job_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(job.status, JobStatus::Pending);
+    assert_eq!(job.attempts, 1);
+    assert_eq!(job.last_error.as_deref(), Some("fallo simulado"));
+
+    assert!(worker.run_once().await.unwrap());
+    let job = jobs::fetch_job(&state.database_pool, job_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(job.status, JobStatus::Failed);
+    assert_eq!(job.attempts, 2);
+
+    assert!(!worker.run_once().await.unwrap());
+}
+
+#[tokio::test]
+async fn scheduled_and_unknown_jobs_are_handled() {
+    let state = test_state().await;
+    let worker = test_worker(&state);
+
+    let scheduled_id = jobs::enqueue_at(
+        &state.database_pool,
+        &InsertUser {
+            name: "Grace Hopper".to_string(),
+            email: "grace@example.com".to_string(),
+        },
+        chrono::Utc::now() + chrono::Duration::hours(1),
+    )
+    .await
+    .unwrap();
+    assert!(!worker.run_once().await.unwrap());
+
+    let unknown_worker = Worker::new(state.clone(), JobRegistry::new());
+    assert!(!unknown_worker.run_once().await.unwrap());
+
+    let job_id = jobs::enqueue(&state.database_pool, &AlwaysFails)
+        .await
+        .unwrap();
+    let unknown_worker = unknown_worker.with_base_delay(Duration::ZERO);
+    unknown_worker.run_once().await.unwrap();
+    unknown_worker.run_once().await.unwrap();
+
+    let job = jobs::fetch_job(&state.database_pool, job_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(job.status, JobStatus::Failed);
+    assert!(job.last_error.unwrap().contains("desconocido"));
+
+    let scheduled = jobs::fetch_job(&state.database_pool, scheduled_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(scheduled.status, JobStatus::Pending);
+}
+
+async fn test_state() -> AppState {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+
+    AppState::new(pool, AppConfig::default())
+}
+
+fn test_worker(state: &AppState) -> Worker {
+    let registry = JobRegistry::new()
+        .register::<InsertUser>()
+        .register::<AlwaysFails>();
+
+    Worker::new(state.clone(), registry).with_base_delay(Duration::ZERO)
+}
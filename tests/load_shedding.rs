@@ -0,0 +1,95 @@
+use std::{sync::Arc, time::Duration};
+
+use axum::{
+    body::Body,
+    http::{self, Request, StatusCode},
+    routing::{get, Router},
+};
+use http_body_util::BodyExt;
+use tokio::sync::Semaphore;
+
+use rust_web_demo::{
+    load_shed::{ConcurrencyLimits, RouteGroup},
+    metrics::Metrics,
+};
+
+#[tokio::test]
+async fn requests_over_the_limit_are_shed_with_retry_after() {
+    let metrics = Metrics::new();
+    let limits = ConcurrencyLimits {
+        api: Some(1),
+        admin: None,
+        retry_after: Duration::from_secs(3),
+    };
+    let release = Arc::new(Semaphore::new(0));
+    let app = limits.apply(RouteGroup::Api, slow_router(release.clone()), &metrics);
+
+    let first = tokio::spawn({
+        let app = app.clone();
+        async move { send(&app, "/slow").await }
+    });
+    // La primera petición ocupa el único hueco hasta que se libere.
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    let shed = send(&app, "/slow").await;
+    assert_eq!(shed.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(shed.headers()[http::header::RETRY_AFTER], "3");
+    let body: serde_json::Value = body_json(shed).await;
+    assert!(body["message"].as_str().unwrap().contains("saturado"));
+    assert_eq!(metrics.snapshot().shed.get("api"), Some(&1));
+    assert!(metrics
+        .render_prometheus()
+        .contains("http_requests_shed_total{group=\"api\"} 1"));
+
+    release.add_permits(1);
+    assert_eq!(first.await.unwrap().status(), StatusCode::OK);
+
+    // Con el hueco libre se vuelve a atender.
+    release.add_permits(1);
+    assert_eq!(send(&app, "/slow").await.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn groups_without_limit_are_not_wrapped() {
+    let metrics = Metrics::new();
+    let limits = ConcurrencyLimits {
+        api: None,
+        admin: None,
+        retry_after: Duration::from_secs(1),
+    };
+    let release = Arc::new(Semaphore::new(0));
+    let app = limits.apply(RouteGroup::Admin, slow_router(release.clone()), &metrics);
+
+    let pending: Vec<_> = (0..3)
+        .map(|_| {
+            let app = app.clone();
+            tokio::spawn(async move { send(&app, "/slow").await })
+        })
+        .collect();
+    release.add_permits(3);
+    for response in pending {
+        assert_eq!(response.await.unwrap().status(), StatusCode::OK);
+    }
+    assert!(metrics.snapshot().shed.is_empty());
+}
+
+/// Router con una ruta que no responde hasta que `release` tenga un permiso disponible.
+fn slow_router(release: Arc<Semaphore>) -> Router {
+    Router::new().route(
+        "/slow",
+        get(move || async move {
+            release.acquire().await.unwrap().forget();
+        }),
+    )
+}
+
+async fn body_json<T: serde::de::DeserializeOwned>(response: http::Response<Body>) -> T {
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+async fn send(app: &Router, uri: &str) -> http::Response<Body> {
+    tower::ServiceExt::oneshot(app.clone(), Request::get(uri).body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+}
@@ -0,0 +1,202 @@
+use axum::{
+    body::Body,
+    http::{self, Request, StatusCode},
+    routing::Router,
+};
+use http_body_util::BodyExt;
+use sqlx::sqlite::SqlitePoolOptions;
+use std::sync::Arc;
+
+use rust_web_demo::{
+    cache::{CacheStore, MemoryStore},
+    config::AppConfig,
+    maintenance::MaintenanceStatus,
+    routes,
+    state::AppState,
+};
+
+#[tokio::test]
+async fn maintenance_mode_rejects_writes_but_serves_reads() {
+    let context = TestContext::new(AppConfig::default()).await;
+
+    let response = context
+        .send(
+            http::Method::PUT,
+            "/admin/maintenance",
+            Some(serde_json::json!({ "enabled": true, "reason": "Copia de seguridad" })),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let status: MaintenanceStatus = body_json(response).await;
+    assert!(status.enabled);
+    assert_eq!(status.reason.as_deref(), Some("Copia de seguridad"));
+    assert!(status.since.is_some());
+
+    for uri in ["/users", "/v2/users"] {
+        let response = context
+            .send(
+                http::Method::POST,
+                uri,
+                Some(serde_json::json!({ "name": "Ada", "email": "ada@example.com" })),
+            )
+            .await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE, "{uri}");
+        assert!(response.headers().contains_key(http::header::RETRY_AFTER));
+        let body: serde_json::Value = body_json(response).await;
+        assert_eq!(body["maintenance"]["reason"], "Copia de seguridad");
+    }
+
+    let response = context.send(http::Method::GET, "/users", None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = context
+        .send(
+            http::Method::PUT,
+            "/admin/maintenance",
+            Some(serde_json::json!({ "enabled": false })),
+        )
+        .await;
+    let status: MaintenanceStatus = body_json(response).await;
+    assert_eq!(status, MaintenanceStatus::default());
+
+    let response = context
+        .send(
+            http::Method::POST,
+            "/users",
+            Some(serde_json::json!({ "name": "Ada", "email": "ada@example.com" })),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn maintenance_mode_can_be_enabled_at_startup() {
+    let config = AppConfig {
+        maintenance_mode: true,
+        ..AppConfig::default()
+    };
+    let context = TestContext::new(config).await;
+
+    let status: MaintenanceStatus = body_json(
+        context
+            .send(http::Method::GET, "/admin/maintenance", None)
+            .await,
+    )
+    .await;
+    assert!(status.enabled);
+    assert_eq!(status.reason, None);
+
+    let response = context
+        .send(
+            http::Method::POST,
+            "/users",
+            Some(serde_json::json!({ "name": "Ada", "email": "ada@example.com" })),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+}
+
+#[tokio::test]
+async fn maintenance_mode_reaches_every_replica_through_the_shared_store() {
+    let store: Arc<dyn CacheStore> = Arc::new(MemoryStore::new(100));
+    let first = TestContext::shared(store.clone()).await;
+    let second = TestContext::shared(store).await;
+
+    let response = first
+        .send(
+            http::Method::PUT,
+            "/admin/maintenance",
+            Some(serde_json::json!({ "enabled": true, "reason": "Migración" })),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Hasta que sincroniza, la otra réplica sigue aceptando escrituras.
+    assert!(!second.state.maintenance.is_enabled());
+    second.state.maintenance.sync().await.unwrap();
+    let status = second.state.maintenance.status();
+    assert!(status.enabled);
+    assert_eq!(status.reason.as_deref(), Some("Migración"));
+    let response = second
+        .send(
+            http::Method::POST,
+            "/users",
+            Some(serde_json::json!({ "name": "Ada", "email": "ada@example.com" })),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+    second
+        .send(
+            http::Method::PUT,
+            "/admin/maintenance",
+            Some(serde_json::json!({ "enabled": false })),
+        )
+        .await;
+    first.state.maintenance.sync().await.unwrap();
+    assert_eq!(
+        first.state.maintenance.status(),
+        MaintenanceStatus::default()
+    );
+}
+
+async fn body_json<T: serde::de::DeserializeOwned>(response: http::Response<Body>) -> T {
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+struct TestContext {
+    app: Router,
+    state: AppState,
+}
+
+impl TestContext {
+    async fn new(config: AppConfig) -> Self {
+        Self::from_state(AppState::new(pool().await, config))
+    }
+
+    /// Réplica que publica y lee el modo de mantenimiento en `store`.
+    async fn shared(store: Arc<dyn CacheStore>) -> Self {
+        let state = AppState::new(pool().await, AppConfig::default());
+        let maintenance = state.maintenance.clone().with_shared_store(store);
+        Self::from_state(state.with_maintenance(maintenance))
+    }
+
+    fn from_state(state: AppState) -> Self {
+        let app = routes::api_routes(&state)
+            .merge(routes::maintenance_routes())
+            .with_state(state.clone());
+
+        Self { app, state }
+    }
+
+    async fn send(
+        &self,
+        method: http::Method,
+        uri: &str,
+        payload: Option<serde_json::Value>,
+    ) -> http::Response<Body> {
+        let mut request = Request::builder().method(method).uri(uri);
+        let body = match payload {
+            Some(payload) => {
+                request = request.header(http::header::CONTENT_TYPE, "application/json");
+                Body::from(serde_json::to_vec(&payload).unwrap())
+            }
+            None => Body::empty(),
+        };
+
+        tower::ServiceExt::oneshot(self.app.clone(), request.body(body).unwrap())
+            .await
+            .unwrap()
+    }
+}
+
+async fn pool() -> sqlx::SqlitePool {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+    pool
+}
@@ -0,0 +1,128 @@
+use std::collections::BTreeSet;
+
+use sqlx::sqlite::SqlitePoolOptions;
+
+use rust_web_demo::{
+    migrations::{self, MigrateCommand, MigrationState, MIGRATOR},
+    testing::{test_pool, TestContext},
+};
+
+#[test]
+fn every_migration_has_a_down_script() {
+    let versions = |down: bool| {
+        MIGRATOR
+            .iter()
+            .filter(|migration| migration.migration_type.is_down_migration() == down)
+            .map(|migration| migration.version)
+            .collect::<BTreeSet<_>>()
+    };
+
+    assert!(!versions(false).is_empty());
+    assert_eq!(versions(false), versions(true));
+}
+
+#[test]
+fn migrate_subcommands_are_parsed() {
+    let args = |values: &[&str]| values.iter().map(ToString::to_string).collect::<Vec<_>>();
+
+    assert_eq!(
+        MigrateCommand::parse(&args(&["status"])).unwrap(),
+        MigrateCommand::Status
+    );
+    assert_eq!(
+        MigrateCommand::parse(&args(&["undo"])).unwrap(),
+        MigrateCommand::Undo
+    );
+    assert_eq!(
+        MigrateCommand::parse(&args(&["to", "202610150008"])).unwrap(),
+        MigrateCommand::To(202610150008)
+    );
+    assert!(MigrateCommand::parse(&args(&["to", "latest"])).is_err());
+    assert!(MigrateCommand::parse(&args(&[])).is_err());
+}
+
+#[tokio::test]
+async fn migrations_can_be_undone_and_reapplied() {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    MIGRATOR.run(&pool).await.unwrap();
+
+    let status = migrations::status(&pool).await.unwrap();
+    assert!(status
+        .iter()
+        .all(|migration| migration.state == MigrationState::Applied));
+    let latest = status.last().unwrap().version;
+
+    assert_eq!(migrations::undo_last(&pool).await.unwrap(), Some(latest));
+    let status = migrations::status(&pool).await.unwrap();
+    assert_eq!(status.last().unwrap().state, MigrationState::Pending);
+
+    migrations::migrate_to(&pool, 0).await.unwrap();
+    let status = migrations::status(&pool).await.unwrap();
+    assert!(status
+        .iter()
+        .all(|migration| migration.state == MigrationState::Pending));
+    let (tables,): (i64,) = sqlx::query_as(
+        "SELECT COUNT(*) FROM sqlite_master WHERE type = 'table' AND name = 'users'",
+    )
+    .fetch_one(&pool)
+    .await
+    .unwrap();
+    assert_eq!(tables, 0);
+    assert_eq!(migrations::undo_last(&pool).await.unwrap(), None);
+
+    migrations::migrate_to(&pool, status[1].version)
+        .await
+        .unwrap();
+    let status = migrations::status(&pool).await.unwrap();
+    assert_eq!(status[1].state, MigrationState::Applied);
+    assert_eq!(status[2].state, MigrationState::Pending);
+
+    MIGRATOR.run(&pool).await.unwrap();
+    assert!(migrations::migrate_to(&pool, 1).await.is_err());
+}
+
+#[tokio::test]
+async fn ensure_up_to_date_reports_pending_migrations() {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+
+    let error = migrations::ensure_up_to_date(&pool).await.unwrap_err();
+    assert!(error.to_string().contains("--migrate-only"));
+
+    MIGRATOR.run(&pool).await.unwrap();
+    migrations::ensure_up_to_date(&pool).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_pools_are_migrated_copies_isolated_from_each_other() {
+    let context = TestContext::new().await;
+    let pool = &context.state.database_pool;
+    migrations::ensure_up_to_date(pool).await.unwrap();
+    let (tenants,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM tenants WHERE id = 'default'")
+        .fetch_one(pool)
+        .await
+        .unwrap();
+    assert_eq!(tenants, 1);
+
+    // La copia lleva el esquema completo: el alta deja su evento en el outbox.
+    context.create_user("Ada Lovelace", "ada@example.com").await;
+    let (events,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM outbox")
+        .fetch_one(pool)
+        .await
+        .unwrap();
+    assert_eq!(events, 1);
+
+    let other = test_pool().await;
+    let (users,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM users")
+        .fetch_one(&other)
+        .await
+        .unwrap();
+    assert_eq!(users, 0);
+}
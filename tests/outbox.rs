@@ -0,0 +1,128 @@
+use chrono::{Duration, Utc};
+use sqlx::sqlite::SqlitePoolOptions;
+use uuid::Uuid;
+
+use rust_web_demo::{
+    broker,
+    encryption::EmailEncryption,
+    events::{DomainEvent, EventBus},
+    outbox,
+};
+
+#[tokio::test]
+async fn committed_events_are_relayed_once_and_rolled_back_ones_never() {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+
+    let events = EventBus::new();
+    let mut receiver = events.subscribe();
+
+    let committed_user = Uuid::new_v4();
+    let mut transaction = pool.begin().await.unwrap();
+    let envelope = outbox::record(
+        &mut *transaction,
+        &EmailEncryption::disabled(),
+        DomainEvent::UserDeleted {
+            user_id: committed_user,
+            tenant_id: "default".to_string(),
+        },
+    )
+    .await
+    .unwrap();
+    transaction.commit().await.unwrap();
+
+    let mut transaction = pool.begin().await.unwrap();
+    outbox::record(
+        &mut *transaction,
+        &EmailEncryption::disabled(),
+        DomainEvent::UserDeleted {
+            user_id: Uuid::new_v4(),
+            tenant_id: "default".to_string(),
+        },
+    )
+    .await
+    .unwrap();
+    transaction.rollback().await.unwrap();
+
+    // Sin relay en marcha el evento espera en la tabla, como tras una caída del proceso.
+    assert_eq!(
+        outbox::relay_once(&pool, &events, &EmailEncryption::disabled())
+            .await
+            .unwrap(),
+        1
+    );
+    assert_eq!(
+        outbox::relay_once(&pool, &events, &EmailEncryption::disabled())
+            .await
+            .unwrap(),
+        0
+    );
+
+    let relayed = receiver.recv().await.unwrap();
+    assert_eq!(relayed.id, envelope.id);
+    assert_eq!(relayed.event.user_id(), committed_user);
+    assert!(receiver.try_recv().is_err());
+}
+
+#[tokio::test]
+async fn purge_keeps_events_that_a_lagging_consumer_has_not_read() {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+
+    for _ in 0..3 {
+        outbox::record(
+            &pool,
+            &EmailEncryption::disabled(),
+            DomainEvent::UserDeleted {
+                user_id: Uuid::new_v4(),
+                tenant_id: "default".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+    }
+    assert_eq!(
+        outbox::relay_once(&pool, &EventBus::new(), &EmailEncryption::disabled())
+            .await
+            .unwrap(),
+        3
+    );
+
+    // El broker solo llegó a publicar el primero antes de caerse.
+    let first_seq: i64 = sqlx::query_scalar("SELECT MIN(seq) FROM outbox")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    sqlx::query("INSERT INTO outbox_offsets (consumer, last_seq, updated_at) VALUES (?, ?, ?)")
+        .bind(broker::CONSUMER)
+        .bind(first_seq)
+        .bind(Utc::now())
+        .execute(&pool)
+        .await
+        .unwrap();
+
+    let expired = Utc::now() + Duration::days(1);
+    let purged = outbox::purge_sent(&pool, expired, &[broker::CONSUMER, "cdc"])
+        .await
+        .unwrap();
+    assert_eq!(purged, 0, "un consumidor sin posición lo conserva todo");
+    let purged = outbox::purge_sent(&pool, expired, &[broker::CONSUMER])
+        .await
+        .unwrap();
+    assert_eq!(purged, 1);
+    let remaining: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM outbox")
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(remaining, 2);
+
+    assert_eq!(outbox::purge_sent(&pool, expired, &[]).await.unwrap(), 2);
+}
@@ -0,0 +1,582 @@
+use std::sync::Arc;
+
+use axum::{
+    body::Body,
+    http::{self, Request, StatusCode},
+    middleware,
+    routing::Router,
+};
+use http_body_util::BodyExt;
+use sqlx::sqlite::SqlitePoolOptions;
+
+use rust_web_demo::{
+    clock::{Clock, MockClock, SystemClock},
+    config::AppConfig,
+    models, routes,
+    state::AppState,
+    tenant,
+    testing::factory::UserFactory,
+};
+
+#[tokio::test]
+async fn post_lifecycle() {
+    let context = TestContext::new().await;
+    let author = UserFactory::new()
+        .with_name("Ada Lovelace")
+        .with_email("ada@example.com")
+        .create(&context.pool)
+        .await;
+
+    let response = context
+        .send_json(
+            http::Method::POST,
+            "/posts",
+            serde_json::json!({
+                "author_id": author.id,
+                "title": "  Notas sobre la máquina analítica  ",
+                "body": "Primer programa publicado."
+            }),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let created: models::post::Post = body_json(response).await;
+    assert_eq!(created.author_id, author.id);
+    assert_eq!(created.title, "Notas sobre la máquina analítica");
+
+    let uri = format!("/posts/{}", created.id);
+    let response = context
+        .send_json(
+            http::Method::PUT,
+            &uri,
+            serde_json::json!({ "body": "Versión revisada." }),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let updated: models::post::Post = body_json(response).await;
+    assert_eq!(updated.title, created.title);
+    assert_eq!(updated.body, "Versión revisada.");
+    assert!(updated.updated_at >= created.updated_at);
+
+    let fetched: models::post::Post = body_json(context.get(&uri).await).await;
+    assert_eq!(fetched.body, "Versión revisada.");
+
+    let response = context.delete(&uri).await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert_eq!(context.get(&uri).await.status(), StatusCode::NOT_FOUND);
+    assert_eq!(context.delete(&uri).await.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn create_post_validates_payload_and_author() {
+    let context = TestContext::new().await;
+    let author = UserFactory::new()
+        .with_name("Ada Lovelace")
+        .with_email("ada@example.com")
+        .create(&context.pool)
+        .await;
+
+    let response = context
+        .send_json(
+            http::Method::POST,
+            "/posts",
+            serde_json::json!({ "author_id": author.id, "title": "   ", "body": "Texto" }),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = context
+        .send_json(
+            http::Method::POST,
+            "/posts",
+            serde_json::json!({
+                "author_id": uuid::Uuid::new_v4(),
+                "title": "Huérfano",
+                "body": "Texto"
+            }),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body: serde_json::Value = body_json(response).await;
+    assert_eq!(body["errors"][0]["field"], "author_id");
+}
+
+#[tokio::test]
+async fn user_posts_are_listed_and_removed_with_their_author() {
+    let context = TestContext::new().await;
+    let ada = UserFactory::new()
+        .with_name("Ada Lovelace")
+        .with_email("ada@example.com")
+        .create(&context.pool)
+        .await;
+    let alan = UserFactory::new()
+        .with_name("Alan Turing")
+        .with_email("alan@example.com")
+        .create(&context.pool)
+        .await;
+
+    for (author, title) in [(&ada, "Uno"), (&ada, "Dos"), (&alan, "Tres")] {
+        context
+            .send_json(
+                http::Method::POST,
+                "/posts",
+                serde_json::json!({ "author_id": author.id, "title": title, "body": "Texto" }),
+            )
+            .await;
+    }
+
+    let response = context.get(&format!("/users/{}/posts", ada.id)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let posts: Vec<models::post::Post> = body_json(response).await;
+    assert_eq!(posts.len(), 2);
+    assert!(posts.iter().all(|post| post.author_id == ada.id));
+
+    let response = context.delete(&format!("/users/{}", ada.id)).await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let posts: Vec<models::post::Post> = body_json(context.get("/posts").await).await;
+    assert_eq!(posts.len(), 1);
+    assert_eq!(posts[0].author_id, alan.id);
+
+    let response = context.get(&format!("/users/{}/posts", ada.id)).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn posts_are_isolated_per_tenant() {
+    let context = TestContext::new().await;
+    sqlx::query("INSERT INTO tenants (id, name, created_at) VALUES ('acme', 'Acme', ?)")
+        .bind(chrono::Utc::now())
+        .execute(&context.pool)
+        .await
+        .unwrap();
+    let ada = UserFactory::new()
+        .with_name("Ada Lovelace")
+        .with_email("ada@example.com")
+        .create(&context.pool)
+        .await;
+    let alan = UserFactory::new()
+        .with_name("Alan Turing")
+        .with_email("alan@acme.com")
+        .with_tenant("acme")
+        .create(&context.pool)
+        .await;
+    let post = context.create_post(&ada, "Notas").await;
+
+    let as_acme = |method: http::Method, uri: &str, payload: Option<serde_json::Value>| {
+        let builder = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("x-tenant-id", "acme");
+        match payload {
+            Some(payload) => builder
+                .header(http::header::CONTENT_TYPE, "application/json")
+                .body(Body::from(serde_json::to_vec(&payload).unwrap()))
+                .unwrap(),
+            None => builder.body(Body::empty()).unwrap(),
+        }
+    };
+    let uri = format!("/posts/{}", post.id);
+
+    let response = context
+        .request(as_acme(http::Method::GET, "/posts", None))
+        .await;
+    let posts: Vec<models::post::Post> = body_json(response).await;
+    assert!(posts.is_empty());
+    let response = context
+        .request(as_acme(http::Method::GET, &uri, None))
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = context
+        .request(as_acme(
+            http::Method::PUT,
+            &uri,
+            Some(serde_json::json!({ "title": "Ajeno" })),
+        ))
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = context
+        .request(as_acme(http::Method::DELETE, &uri, None))
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = context
+        .request(as_acme(
+            http::Method::POST,
+            &format!("{uri}/comments"),
+            Some(serde_json::json!({ "author_id": alan.id, "body": "Hola" })),
+        ))
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // Tampoco puede publicar en nombre de un autor de otro inquilino.
+    let response = context
+        .request(as_acme(
+            http::Method::POST,
+            "/posts",
+            Some(
+                serde_json::json!({ "author_id": ada.id, "title": "Suplantado", "body": "Texto" }),
+            ),
+        ))
+        .await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body: serde_json::Value = body_json(response).await;
+    assert_eq!(body["errors"][0]["field"], "author_id");
+
+    let fetched: models::post::Post = body_json(context.get(&uri).await).await;
+    assert_eq!(fetched.title, "Notas");
+    let posts: Vec<models::post::Post> = body_json(context.get("/posts").await).await;
+    assert_eq!(posts.len(), 1);
+}
+
+#[tokio::test]
+async fn comments_are_paginated_and_soft_deleted() {
+    let context = TestContext::new().await;
+    let author = UserFactory::new()
+        .with_name("Ada Lovelace")
+        .with_email("ada@example.com")
+        .create(&context.pool)
+        .await;
+    let post = context.create_post(&author, "Hilo").await;
+    let comments_uri = format!("/posts/{}/comments", post.id);
+
+    let mut comments = Vec::new();
+    for index in 1..=5 {
+        let response = context
+            .send_json(
+                http::Method::POST,
+                &comments_uri,
+                serde_json::json!({ "author_id": author.id, "body": format!("Comentario {index}") }),
+            )
+            .await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+        comments.push(body_json::<models::comment::Comment>(response).await);
+    }
+
+    let page: models::pagination::Page<models::comment::Comment> =
+        body_json(context.get(&format!("{comments_uri}?per_page=2")).await).await;
+    assert_eq!(page.total, 5);
+    assert_eq!(page.items.len(), 2);
+    assert_eq!(page.items[0].body, "Comentario 1");
+
+    let page: models::pagination::Page<models::comment::Comment> = body_json(
+        context
+            .get(&format!("{comments_uri}?page=3&per_page=2"))
+            .await,
+    )
+    .await;
+    assert_eq!(page.items.len(), 1);
+    assert_eq!(page.items[0].body, "Comentario 5");
+
+    let comment_uri = format!("{comments_uri}/{}", comments[0].id);
+    let response = context
+        .send_json(
+            http::Method::PUT,
+            &comment_uri,
+            serde_json::json!({ "body": "Editado" }),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    assert_eq!(
+        context.delete(&comment_uri).await.status(),
+        StatusCode::NO_CONTENT
+    );
+    assert_eq!(
+        context.get(&comment_uri).await.status(),
+        StatusCode::NOT_FOUND
+    );
+    assert_eq!(
+        context.delete(&comment_uri).await.status(),
+        StatusCode::NOT_FOUND
+    );
+
+    let page: models::pagination::Page<models::comment::Comment> =
+        body_json(context.get(&comments_uri).await).await;
+    assert_eq!(page.total, 4);
+
+    let response = context.get(&format!("{comments_uri}?per_page=500")).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = context
+        .get(&format!("/posts/{}/comments", uuid::Uuid::new_v4()))
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn comments_outlive_their_author_but_not_their_post() {
+    let context = TestContext::new().await;
+    let ada = UserFactory::new()
+        .with_name("Ada Lovelace")
+        .with_email("ada@example.com")
+        .create(&context.pool)
+        .await;
+    let alan = UserFactory::new()
+        .with_name("Alan Turing")
+        .with_email("alan@example.com")
+        .create(&context.pool)
+        .await;
+    let post = context.create_post(&ada, "Hilo").await;
+    let comments_uri = format!("/posts/{}/comments", post.id);
+
+    let response = context
+        .send_json(
+            http::Method::POST,
+            &comments_uri,
+            serde_json::json!({ "author_id": alan.id, "body": "Interesante" }),
+        )
+        .await;
+    let comment: models::comment::Comment = body_json(response).await;
+
+    context.delete(&format!("/users/{}", alan.id)).await;
+    let orphan: models::comment::Comment =
+        body_json(context.get(&format!("{comments_uri}/{}", comment.id)).await).await;
+    assert_eq!(orphan.author_id, None);
+
+    context.delete(&format!("/posts/{}", post.id)).await;
+    let remaining = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM comments")
+        .fetch_one(&context.pool)
+        .await
+        .unwrap();
+    assert_eq!(remaining, 0);
+}
+
+#[tokio::test]
+async fn comment_payloads_are_validated_before_reaching_the_handler() {
+    let context = TestContext::new().await;
+    let ada = UserFactory::new()
+        .with_name("Ada Lovelace")
+        .with_email("ada@example.com")
+        .create(&context.pool)
+        .await;
+    let post = context.create_post(&ada, "Hilo").await;
+    let comments_uri = format!("/posts/{}/comments", post.id);
+
+    let response = context
+        .send_json(
+            http::Method::POST,
+            &comments_uri,
+            serde_json::json!({ "author_id": ada.id, "body": "   " }),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body: serde_json::Value = body_json(response).await;
+    assert_eq!(body["errors"][0]["field"], "body");
+
+    let response = context
+        .send_json(
+            http::Method::POST,
+            &comments_uri,
+            serde_json::json!({ "body": "Sin autor" }),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let listed: serde_json::Value = body_json(context.get(&comments_uri).await).await;
+    assert_eq!(listed["total"], 0);
+}
+
+#[tokio::test]
+async fn post_payloads_report_every_rule_they_break() {
+    let context = TestContext::new().await;
+    let ada = UserFactory::new()
+        .with_name("Ada Lovelace")
+        .create(&context.pool)
+        .await;
+
+    let response = context
+        .send_json(
+            http::Method::POST,
+            "/posts",
+            serde_json::json!({ "author_id": ada.id, "title": "t".repeat(201), "body": " " }),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body: serde_json::Value = body_json(response).await;
+    assert_eq!(
+        body["errors"],
+        serde_json::json!([
+            { "field": "body", "message": "Debe contener al menos un carácter" },
+            { "field": "title", "message": "Debe tener 200 caracteres o menos" },
+        ])
+    );
+
+    let post = context.create_post(&ada, "Hilo").await;
+    let response = context
+        .send_json(
+            http::Method::PUT,
+            &format!("/posts/{}", post.id),
+            serde_json::json!({ "title": "  " }),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body: serde_json::Value = body_json(response).await;
+    assert_eq!(
+        body["errors"],
+        serde_json::json!([{
+            "field": "general",
+            "message": "Debe proporcionar al menos un campo para actualizar",
+        }])
+    );
+}
+
+#[tokio::test]
+async fn user_can_be_fetched_with_expanded_posts_and_comments() {
+    let context = TestContext::new().await;
+    let ada = UserFactory::new()
+        .with_name("Ada Lovelace")
+        .with_email("ada@example.com")
+        .create(&context.pool)
+        .await;
+    let post = context.create_post(&ada, "Hilo").await;
+    context
+        .send_json(
+            http::Method::POST,
+            &format!("/posts/{}/comments", post.id),
+            serde_json::json!({ "author_id": ada.id, "body": "Primer comentario" }),
+        )
+        .await;
+    let user_uri = format!("/users/{}", ada.id);
+
+    let expanded: serde_json::Value =
+        body_json(context.get(&format!("{user_uri}?expand=posts")).await).await;
+    assert_eq!(expanded["name"], "Ada Lovelace");
+    assert_eq!(expanded["posts"][0]["title"], "Hilo");
+    assert!(expanded["posts"][0].get("comments").is_none());
+
+    let expanded: serde_json::Value = body_json(
+        context
+            .get(&format!("{user_uri}?expand=posts.comments&fields=id"))
+            .await,
+    )
+    .await;
+    assert_eq!(expanded.as_object().unwrap().len(), 2);
+    assert_eq!(
+        expanded["posts"][0]["comments"][0]["body"],
+        "Primer comentario"
+    );
+
+    // Sin `expand` la respuesta es el usuario tal cual.
+    let plain: serde_json::Value = body_json(context.get(&user_uri).await).await;
+    assert!(plain.get("posts").is_none());
+
+    let response = context
+        .get(&format!("{user_uri}?expand=posts.comments.author"))
+        .await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let response = context.get(&format!("{user_uri}?expand=friends")).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn post_timestamps_come_from_the_state_clock() {
+    let created_at = "2024-03-01T09:30:00Z".parse().unwrap();
+    let clock = MockClock::new(created_at);
+    let context = TestContext::with_clock(Arc::new(clock.clone())).await;
+    let author = UserFactory::new().create(&context.pool).await;
+
+    let post = context.create_post(&author, "Notas").await;
+    assert_eq!(post.created_at, created_at);
+    assert_eq!(post.updated_at, created_at);
+
+    clock.advance(chrono::Duration::minutes(5));
+    let response = context
+        .send_json(
+            http::Method::PUT,
+            &format!("/posts/{}", post.id),
+            serde_json::json!({ "body": "Versión revisada." }),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let updated: models::post::Post = body_json(response).await;
+    assert_eq!(updated.created_at, created_at);
+    assert_eq!(
+        updated.updated_at,
+        created_at + chrono::Duration::minutes(5)
+    );
+}
+
+struct TestContext {
+    app: Router,
+    pool: sqlx::SqlitePool,
+}
+
+impl TestContext {
+    async fn new() -> Self {
+        Self::with_clock(Arc::new(SystemClock)).await
+    }
+
+    async fn with_clock(clock: Arc<dyn Clock>) -> Self {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+
+        let state = AppState::new(pool.clone(), AppConfig::default()).with_clock(clock);
+        let app = routes::user_routes()
+            .merge(routes::post_routes())
+            .route_layer(middleware::from_fn_with_state(
+                state.clone(),
+                tenant::resolve_tenant,
+            ))
+            .with_state(state);
+
+        Self { app, pool }
+    }
+
+    async fn request(&self, request: Request<Body>) -> http::Response<Body> {
+        tower::ServiceExt::oneshot(self.app.clone(), request)
+            .await
+            .unwrap()
+    }
+
+    async fn create_post(&self, author: &models::user::User, title: &str) -> models::post::Post {
+        let response = self
+            .send_json(
+                http::Method::POST,
+                "/posts",
+                serde_json::json!({ "author_id": author.id, "title": title, "body": "Texto" }),
+            )
+            .await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+        body_json(response).await
+    }
+
+    async fn send_json(
+        &self,
+        method: http::Method,
+        uri: &str,
+        payload: serde_json::Value,
+    ) -> http::Response<Body> {
+        self.request(
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header(http::header::CONTENT_TYPE, "application/json")
+                .body(Body::from(serde_json::to_vec(&payload).unwrap()))
+                .unwrap(),
+        )
+        .await
+    }
+
+    async fn get(&self, uri: &str) -> http::Response<Body> {
+        self.request(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+    }
+
+    async fn delete(&self, uri: &str) -> http::Response<Body> {
+        self.request(
+            Request::builder()
+                .method(http::Method::DELETE)
+                .uri(uri)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+    }
+}
+
+async fn body_json<T: serde::de::DeserializeOwned>(response: http::Response<Body>) -> T {
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    serde_json::from_slice(&bytes).unwrap()
+}
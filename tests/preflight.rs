@@ -0,0 +1,45 @@
+use std::path::Path;
+
+use sqlx::sqlite::SqlitePoolOptions;
+
+use rust_web_demo::{config::AppConfig, migrations::MIGRATOR, preflight};
+
+async fn memory_pool() -> sqlx::SqlitePool {
+    SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn preflight_passes_with_a_migrated_database_and_public_dir() {
+    let pool = memory_pool().await;
+    MIGRATOR.run(&pool).await.unwrap();
+
+    let report = preflight::run(&AppConfig::default(), &pool, Path::new("public")).await;
+
+    assert!(report.is_ok(), "{report:?}");
+    report.into_result().unwrap();
+}
+
+#[tokio::test]
+async fn preflight_reports_every_problem_at_once() {
+    let pool = memory_pool().await;
+    let config = AppConfig {
+        database_max_connections: 0,
+        admin_port: Some(3000),
+        ..AppConfig::default()
+    };
+
+    let report = preflight::run(&config, &pool, Path::new("no-such-public-dir")).await;
+    assert!(!report.is_ok());
+
+    let message = report.into_result().unwrap_err().to_string();
+    assert!(message.contains("DATABASE_MAX_CONNECTIONS debe ser al menos 1"));
+    assert!(message.contains("ADMIN_PORT (3000) debe ser distinto de PORT"));
+    assert!(message.contains("faltan migraciones"));
+    assert!(message.contains("--migrate-only"));
+    assert!(message.contains("no-such-public-dir"));
+    assert!(!message.contains("base de datos:"));
+}
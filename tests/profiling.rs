@@ -0,0 +1,95 @@
+#![cfg(feature = "pprof")]
+
+use std::time::Duration;
+
+use axum::{
+    body::Body,
+    http::{self, Request, StatusCode},
+    routing::Router,
+};
+use sqlx::sqlite::SqlitePoolOptions;
+
+use rust_web_demo::{config::AppConfig, profiling::ProfilingConfig, routes, state::AppState};
+
+#[tokio::test]
+async fn profile_requires_the_token() {
+    let app = app().await;
+
+    let response = send(&app, "/debug/pprof/profile?seconds=1", None).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = send(&app, "/debug/pprof/profile?seconds=1", Some("otro")).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn profile_rejects_out_of_range_durations() {
+    let app = app().await;
+
+    for seconds in [0, 301] {
+        let uri = format!("/debug/pprof/profile?seconds={seconds}");
+        let response = send(&app, &uri, Some("secreto")).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
+
+#[tokio::test]
+async fn profile_allows_one_capture_at_a_time() {
+    let app = app().await;
+
+    let first = tokio::spawn({
+        let app = app.clone();
+        async move {
+            send(
+                &app,
+                "/debug/pprof/profile?seconds=1&format=flamegraph",
+                Some("secreto"),
+            )
+            .await
+        }
+    });
+    tokio::time::sleep(Duration::from_millis(200)).await;
+
+    let second = send(&app, "/debug/pprof/profile?seconds=1", Some("secreto")).await;
+    assert_eq!(second.status(), StatusCode::CONFLICT);
+
+    // Las muestras dependen de la carga de la máquina, así que solo se comprueba la respuesta.
+    let first = first.await.unwrap();
+    assert_eq!(first.status(), StatusCode::OK);
+    assert_eq!(first.headers()[http::header::CONTENT_TYPE], "image/svg+xml");
+    assert_eq!(
+        first.headers()[http::header::CONTENT_DISPOSITION],
+        "attachment; filename=\"cpu.svg\""
+    );
+
+    let again = send(&app, "/debug/pprof/profile?seconds=1", Some("secreto")).await;
+    assert_eq!(again.status(), StatusCode::OK);
+    assert_eq!(
+        again.headers()[http::header::CONTENT_TYPE],
+        "application/octet-stream"
+    );
+}
+
+async fn app() -> Router {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    let state = AppState::new(pool, AppConfig::default());
+    let config = ProfilingConfig {
+        token: "secreto".to_string(),
+    };
+    routes::debug_routes(config).with_state(state)
+}
+
+async fn send(app: &Router, uri: &str, token: Option<&str>) -> http::Response<Body> {
+    let mut request = Request::builder().method(http::Method::GET).uri(uri);
+    if let Some(token) = token {
+        request = request.header(http::header::AUTHORIZATION, format!("Bearer {token}"));
+    }
+
+    tower::ServiceExt::oneshot(app.clone(), request.body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+}
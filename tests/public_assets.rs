@@ -0,0 +1,99 @@
+use axum::{
+    body::Body,
+    http::{header, Request, Response, StatusCode},
+    routing::Router,
+};
+use http_body_util::BodyExt;
+use sqlx::sqlite::SqlitePoolOptions;
+use uuid::Uuid;
+
+use rust_web_demo::{config::AppConfig, routes, state::AppState};
+
+#[tokio::test]
+async fn plain_assets_are_revalidated_and_honor_if_modified_since() {
+    let app = test_app(false).await;
+
+    let response = get(&app, "/public/index.html", None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[header::CACHE_CONTROL],
+        routes::REVALIDATE_CACHE_CONTROL
+    );
+    let last_modified = response.headers()[header::LAST_MODIFIED]
+        .to_str()
+        .unwrap()
+        .to_string();
+
+    let response = get(&app, "/public/index.html", Some(&last_modified)).await;
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+    assert_eq!(
+        response.headers()[header::CACHE_CONTROL],
+        routes::REVALIDATE_CACHE_CONTROL
+    );
+}
+
+#[tokio::test]
+async fn hashed_assets_are_cached_as_immutable() {
+    let app = test_app(false).await;
+
+    let response = get(&app, "/public/app.3f2a9c1b.js", None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[header::CACHE_CONTROL],
+        routes::IMMUTABLE_CACHE_CONTROL
+    );
+
+    let response = get(&app, "/public/missing.3f2a9c1b.js", None).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    assert!(response.headers().get(header::CACHE_CONTROL).is_none());
+}
+
+#[tokio::test]
+async fn spa_fallback_serves_index_for_client_routes_only() {
+    let app = test_app(true).await;
+
+    let response = get(&app, "/public/users/42", None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[header::CACHE_CONTROL],
+        routes::REVALIDATE_CACHE_CONTROL
+    );
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    assert_eq!(&body[..], b"<h1>Hola</h1>");
+
+    let response = get(&app, "/public/app.3f2a9c1b.js", None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let response = get(&app, "/public/missing.3f2a9c1b.js", None).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let without_fallback = test_app(false).await;
+    let response = get(&without_fallback, "/public/users/42", None).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+async fn test_app(spa_fallback: bool) -> Router {
+    let directory = std::env::temp_dir().join(format!("public-test-{}", Uuid::new_v4()));
+    std::fs::create_dir_all(&directory).unwrap();
+    std::fs::write(directory.join("index.html"), "<h1>Hola</h1>").unwrap();
+    std::fs::write(directory.join("app.3f2a9c1b.js"), "console.log('hola');").unwrap();
+
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+
+    routes::public_routes(directory, spa_fallback).with_state(AppState::new(pool, AppConfig::default()))
+}
+
+async fn get(app: &Router, uri: &str, if_modified_since: Option<&str>) -> Response<Body> {
+    let mut request = Request::builder().uri(uri);
+    if let Some(value) = if_modified_since {
+        request = request.header(header::IF_MODIFIED_SINCE, value);
+    }
+
+    tower::ServiceExt::oneshot(app.clone(), request.body(Body::empty()).unwrap())
+        .await
+        .unwrap()
+}
@@ -0,0 +1,174 @@
+use std::{path::PathBuf, sync::Arc, time::Duration};
+
+use sqlx::{sqlite::SqliteConnectOptions, SqlitePool};
+
+use rust_web_demo::{
+    config::AppConfig,
+    database, migrations,
+    replication::{self, ReplicaCommand, ReplicationConfig, Replicator},
+    storage::{LocalStorage, Storage},
+    testing::factory::UserFactory,
+};
+
+#[tokio::test]
+async fn replicated_wal_restores_to_latest_and_earlier_points() {
+    let context = TestContext::new().await;
+    let mut replicator = context.start(ReplicationConfig::default()).await;
+
+    UserFactory::create_many(&context.pool, 10).await;
+    assert!(replicator.sync().await.unwrap() > 0);
+    assert_eq!(replicator.sync().await.unwrap(), 0);
+    tokio::time::sleep(Duration::from_millis(5)).await;
+    let after_first_batch = chrono::Utc::now();
+    tokio::time::sleep(Duration::from_millis(5)).await;
+
+    UserFactory::create_many(&context.pool, 5).await;
+    replicator.sync().await.unwrap();
+    // Tras el checkpoint SQLite reinicia el WAL; la generación continúa.
+    replicator.checkpoint().await.unwrap();
+    UserFactory::create_many(&context.pool, 5).await;
+    replicator.sync().await.unwrap();
+
+    let latest = context.restore(None).await;
+    assert_eq!(count_users(&latest).await, 20);
+    let earlier = context.restore(Some(after_first_batch)).await;
+    assert_eq!(count_users(&earlier).await, 10);
+
+    let generations = replication::generations(context.storage.as_ref())
+        .await
+        .unwrap();
+    assert_eq!(generations.len(), 1);
+    assert_eq!(generations[0].id, replicator.generation());
+}
+
+#[tokio::test]
+async fn new_generations_replace_the_oldest_ones() {
+    let context = TestContext::new().await;
+    let config = ReplicationConfig {
+        retain_generations: 1,
+        ..ReplicationConfig::default()
+    };
+
+    let mut first = context.start(config.clone()).await;
+    UserFactory::create_many(&context.pool, 3).await;
+    first.sync().await.unwrap();
+    let first = first.generation().to_string();
+    tokio::time::sleep(Duration::from_millis(5)).await;
+
+    let mut second = context.start(config).await;
+    UserFactory::create_many(&context.pool, 2).await;
+    second.sync().await.unwrap();
+
+    let generations = replication::generations(context.storage.as_ref())
+        .await
+        .unwrap();
+    assert_eq!(generations.len(), 1);
+    assert_eq!(generations[0].id, second.generation());
+    let old_snapshot = format!("replica/{first}/snapshot.db");
+    assert!(context.storage.get(&old_snapshot).await.unwrap().is_none());
+
+    let restored = context.restore(None).await;
+    assert_eq!(count_users(&restored).await, 5);
+
+    let before_any = generations[0].started_at - chrono::Duration::seconds(1);
+    let error = replication::restore_to(
+        context.storage.as_ref(),
+        Some(before_any),
+        &context.directory.join("too-early.db"),
+    )
+    .await
+    .unwrap_err();
+    assert!(error.to_string().contains("generación"));
+}
+
+#[test]
+fn replica_subcommands_are_parsed() {
+    let args = |values: &[&str]| values.iter().map(ToString::to_string).collect::<Vec<_>>();
+
+    assert_eq!(
+        ReplicaCommand::parse(&args(&["generations"])).unwrap(),
+        ReplicaCommand::Generations
+    );
+    assert_eq!(
+        ReplicaCommand::parse(&args(&["restore"])).unwrap(),
+        ReplicaCommand::Restore(None)
+    );
+    assert!(matches!(
+        ReplicaCommand::parse(&args(&["restore", "2026-10-15T12:00:00+02:00"])).unwrap(),
+        ReplicaCommand::Restore(Some(target)) if target.to_rfc3339() == "2026-10-15T10:00:00+00:00"
+    ));
+    assert!(ReplicaCommand::parse(&args(&["restore", "ayer"])).is_err());
+}
+
+async fn count_users(pool: &SqlitePool) -> i64 {
+    sqlx::query_scalar("SELECT COUNT(*) FROM users")
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+struct TestContext {
+    directory: PathBuf,
+    config: AppConfig,
+    pool: SqlitePool,
+    storage: Arc<dyn Storage>,
+}
+
+impl TestContext {
+    async fn new() -> Self {
+        let directory = std::env::temp_dir().join(format!("replica-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&directory).unwrap();
+        let config = AppConfig {
+            database_url: format!(
+                "sqlite://{}?mode=rwc",
+                directory.join("db.sqlite").display()
+            ),
+            ..AppConfig::default()
+        };
+        let pool = database::pool_options(&config)
+            .connect_with(database::connect_options(&config).unwrap())
+            .await
+            .unwrap();
+        migrations::MIGRATOR.run(&pool).await.unwrap();
+        let storage: Arc<dyn Storage> = Arc::new(LocalStorage::new(directory.join("storage")));
+
+        Self {
+            directory,
+            config,
+            pool,
+            storage,
+        }
+    }
+
+    async fn start(&self, replication: ReplicationConfig) -> Replicator {
+        Replicator::start(&self.config, self.storage.clone(), replication)
+            .await
+            .unwrap()
+    }
+
+    /// Reconstruye la base desde la réplica y la abre.
+    async fn restore(&self, target: Option<chrono::DateTime<chrono::Utc>>) -> SqlitePool {
+        let path = self
+            .directory
+            .join(format!("restored-{}.db", uuid::Uuid::new_v4()));
+        replication::restore_to(self.storage.as_ref(), target, &path)
+            .await
+            .unwrap();
+
+        let restored = SqlitePool::connect_with(SqliteConnectOptions::new().filename(&path))
+            .await
+            .unwrap();
+        let integrity: String = sqlx::query_scalar("PRAGMA integrity_check")
+            .fetch_one(&restored)
+            .await
+            .unwrap();
+        assert_eq!(integrity, "ok");
+        restored
+    }
+}
+
+impl Drop for TestContext {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.directory);
+    }
+}
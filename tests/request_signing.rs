@@ -0,0 +1,160 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use axum::{
+    body::{Body, Bytes},
+    http::{Method, Request, StatusCode},
+    middleware,
+    routing::post,
+    Router,
+};
+use chrono::{TimeZone, Utc};
+use http_body_util::BodyExt;
+
+use rust_web_demo::{
+    cache::{CacheStore, MemoryStore},
+    clock::{Clock, MockClock},
+    signing::{self, RequestSigning, SignedClient},
+};
+
+const SECRET: &str = "s3cr3t";
+
+#[tokio::test]
+async fn signed_requests_reach_the_handler_with_their_body() {
+    let (app, clock) = app();
+    let now = clock.now().timestamp();
+
+    let response = send(
+        &app,
+        signed("/admin/backup?full=1", b"{}", now, "n-1", SECRET),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = response.into_body().collect().await.unwrap().to_bytes();
+    assert_eq!(&body[..], b"cron:2");
+}
+
+#[tokio::test]
+async fn replayed_nonces_are_rejected() {
+    let (app, clock) = app();
+    let now = clock.now().timestamp();
+
+    let first = send(&app, signed("/admin/backup", b"", now, "n-1", SECRET)).await;
+    assert_eq!(first.status(), StatusCode::OK);
+
+    let replay = send(&app, signed("/admin/backup", b"", now, "n-1", SECRET)).await;
+    assert_eq!(replay.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn replicas_sharing_the_nonce_store_reject_each_others_replays() {
+    let store: Arc<dyn CacheStore> = Arc::new(MemoryStore::new(100));
+    let (first, clock) = app_with(Some(store.clone()));
+    let (second, _) = app_with(Some(store));
+    let now = clock.now().timestamp();
+
+    let response = send(&first, signed("/admin/backup", b"", now, "n-1", SECRET)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let replay = send(&second, signed("/admin/backup", b"", now, "n-1", SECRET)).await;
+    assert_eq!(replay.status(), StatusCode::UNAUTHORIZED);
+    let response = send(&second, signed("/admin/backup", b"", now, "n-2", SECRET)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn timestamps_outside_the_skew_window_are_rejected() {
+    let (app, clock) = app();
+    let signed_at = clock.now().timestamp();
+
+    clock.advance(chrono::Duration::seconds(299));
+    let response = send(&app, signed("/admin/backup", b"", signed_at, "n-1", SECRET)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    clock.advance(chrono::Duration::seconds(2));
+    let response = send(&app, signed("/admin/backup", b"", signed_at, "n-2", SECRET)).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn tampered_or_unsigned_requests_are_rejected() {
+    let (app, clock) = app();
+    let now = clock.now().timestamp();
+
+    let wrong_secret = signed("/admin/backup", b"{}", now, "n-1", "otro");
+    assert_eq!(
+        send(&app, wrong_secret).await.status(),
+        StatusCode::UNAUTHORIZED
+    );
+
+    // La firma cubre el cuerpo: cambiarlo la invalida.
+    let mut tampered = signed("/admin/backup", b"{}", now, "n-2", SECRET);
+    *tampered.body_mut() = Body::from("{\"drop\":true}");
+    assert_eq!(
+        send(&app, tampered).await.status(),
+        StatusCode::UNAUTHORIZED
+    );
+
+    let mut unknown = signed("/admin/backup", b"", now, "n-3", SECRET);
+    unknown
+        .headers_mut()
+        .insert(signing::CLIENT_HEADER, "intruso".parse().unwrap());
+    assert_eq!(send(&app, unknown).await.status(), StatusCode::UNAUTHORIZED);
+
+    let unsigned = Request::post("/admin/backup").body(Body::empty()).unwrap();
+    let response = send(&app, unsigned).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    let body: serde_json::Value =
+        serde_json::from_slice(&response.into_body().collect().await.unwrap().to_bytes()).unwrap();
+    assert!(body["message"].as_str().unwrap().contains("X-Signature"));
+    // El mismo formato que el resto de errores: solo `message`, sin campos.
+    assert!(body.get("errors").is_none());
+}
+
+fn app() -> (Router, MockClock) {
+    app_with(None)
+}
+
+/// Aplicación firmada que, con `nonces`, recuerda los nonces en ese almacén.
+fn app_with(nonces: Option<Arc<dyn CacheStore>>) -> (Router, MockClock) {
+    let clock = MockClock::new(Utc.with_ymd_and_hms(2024, 5, 1, 12, 0, 0).unwrap());
+    let mut signing = RequestSigning::new(
+        HashMap::from([("cron".to_string(), SECRET.to_string())]),
+        Duration::from_secs(300),
+    )
+    .with_clock(Arc::new(clock.clone()));
+    if let Some(nonces) = nonces {
+        signing = signing.with_nonce_store(nonces);
+    }
+
+    let app = Router::new()
+        .route(
+            "/admin/backup",
+            post(
+                |SignedClient(client): SignedClient, body: Bytes| async move {
+                    format!("{client}:{}", body.len())
+                },
+            ),
+        )
+        .layer(middleware::from_fn_with_state(
+            signing,
+            signing::require_signature,
+        ));
+    (app, clock)
+}
+
+fn signed(uri: &str, body: &[u8], timestamp: i64, nonce: &str, secret: &str) -> Request<Body> {
+    let signature = signing::sign(secret, &Method::POST, uri, timestamp, nonce, body);
+    Request::post(uri)
+        .header(signing::CLIENT_HEADER, "cron")
+        .header(signing::TIMESTAMP_HEADER, timestamp.to_string())
+        .header(signing::NONCE_HEADER, nonce)
+        .header(signing::SIGNATURE_HEADER, signature)
+        .body(Body::from(body.to_vec()))
+        .unwrap()
+}
+
+async fn send(app: &Router, request: Request<Body>) -> axum::http::Response<Body> {
+    tower::ServiceExt::oneshot(app.clone(), request)
+        .await
+        .unwrap()
+}
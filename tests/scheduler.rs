@@ -0,0 +1,72 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use chrono::{Duration, TimeZone, Utc};
+use sqlx::sqlite::SqlitePoolOptions;
+
+use rust_web_demo::{config::AppConfig, scheduler::Scheduler, state::AppState};
+
+#[tokio::test]
+async fn due_task_runs_once_and_survives_restart() {
+    let state = test_state().await;
+    let runs = Arc::new(AtomicUsize::new(0));
+    let start = Utc.with_ymd_and_hms(2026, 10, 15, 10, 15, 0).unwrap();
+
+    let scheduler = counting_scheduler(&state, &runs);
+    assert!(scheduler.run_due(start).await.unwrap().is_empty());
+    assert!(scheduler
+        .run_due(start + Duration::minutes(30))
+        .await
+        .unwrap()
+        .is_empty());
+
+    let executed = scheduler.run_due(start + Duration::hours(3)).await.unwrap();
+    assert_eq!(executed, vec!["hourly"]);
+    assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+    let restarted = counting_scheduler(&state, &runs);
+    assert!(restarted
+        .run_due(start + Duration::hours(3))
+        .await
+        .unwrap()
+        .is_empty());
+    assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+    restarted.run_due(start + Duration::hours(4)).await.unwrap();
+    assert_eq!(runs.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn invalid_cron_expression_is_rejected() {
+    let state = test_state().await;
+
+    let result = Scheduler::new(state).add("broken", "cada hora", |_| async { Ok(()) });
+    assert!(result.is_err());
+}
+
+async fn test_state() -> AppState {
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+
+    AppState::new(pool, AppConfig::default())
+}
+
+fn counting_scheduler(state: &AppState, runs: &Arc<AtomicUsize>) -> Scheduler {
+    let runs = runs.clone();
+
+    Scheduler::new(state.clone())
+        .add("hourly", "0 0 * * * *", move |_| {
+            let runs = runs.clone();
+            async move {
+                runs.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }
+        })
+        .unwrap()
+}
@@ -0,0 +1,284 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use axum::{
+    body::Bytes,
+    http::{HeaderMap, StatusCode},
+    routing::{get, post},
+    Json, Router,
+};
+
+use rust_web_demo::secrets::{
+    AwsSecretsManagerConfig, AwsSecretsManagerSource, FileSource, SecretFuture, SecretSource,
+    Secrets, VaultConfig, VaultSource,
+};
+
+/// Proveedor en memoria que cuenta las consultas.
+#[derive(Debug, Default)]
+struct MemorySource {
+    values: Mutex<HashMap<String, String>>,
+    fetches: AtomicUsize,
+}
+
+impl MemorySource {
+    fn with(values: &[(&str, &str)]) -> Arc<Self> {
+        let source = Self::default();
+        for (name, value) in values {
+            source.set(name, value);
+        }
+        Arc::new(source)
+    }
+
+    fn set(&self, name: &str, value: &str) {
+        self.values
+            .lock()
+            .unwrap()
+            .insert(name.to_string(), value.to_string());
+    }
+}
+
+impl SecretSource for MemorySource {
+    fn fetch<'a>(&'a self, name: &'a str) -> SecretFuture<'a, Option<String>> {
+        Box::pin(async move {
+            self.fetches.fetch_add(1, Ordering::SeqCst);
+            Ok(self.values.lock().unwrap().get(name).cloned())
+        })
+    }
+}
+
+#[tokio::test]
+async fn providers_are_consulted_in_order_and_cached() {
+    let first = MemorySource::with(&[("DATABASE_URL", "sqlite://primero.db")]);
+    let second = MemorySource::with(&[
+        ("DATABASE_URL", "sqlite://segundo.db"),
+        ("REDIS_URL", "redis://cache"),
+    ]);
+    let secrets = Secrets::new(
+        vec![first.clone(), second.clone()],
+        Duration::from_secs(300),
+    );
+
+    assert_eq!(
+        secrets.get("DATABASE_URL").await.unwrap().as_deref(),
+        Some("sqlite://primero.db")
+    );
+    assert_eq!(
+        secrets.get("REDIS_URL").await.unwrap().as_deref(),
+        Some("redis://cache")
+    );
+    assert_eq!(secrets.get("NO_EXISTE").await.unwrap(), None);
+    assert_eq!(second.fetches.load(Ordering::SeqCst), 2);
+
+    // Dentro del TTL no se vuelve a consultar a los proveedores.
+    first.set("DATABASE_URL", "sqlite://cambiado.db");
+    assert_eq!(
+        secrets.get("DATABASE_URL").await.unwrap().as_deref(),
+        Some("sqlite://primero.db")
+    );
+    assert_eq!(first.fetches.load(Ordering::SeqCst), 3);
+
+    let uncached = Secrets::new(vec![first.clone()], Duration::ZERO);
+    assert_eq!(
+        uncached.get("DATABASE_URL").await.unwrap().as_deref(),
+        Some("sqlite://cambiado.db")
+    );
+}
+
+#[tokio::test]
+async fn refresh_notifies_hooks_of_rotated_secrets() {
+    let source = MemorySource::with(&[("S3_SECRET_ACCESS_KEY", "v1"), ("REDIS_URL", "redis://a")]);
+    let secrets = Secrets::new(vec![source.clone()], Duration::from_secs(300));
+    secrets.get("S3_SECRET_ACCESS_KEY").await.unwrap();
+    secrets.get("REDIS_URL").await.unwrap();
+
+    let rotations = Arc::new(Mutex::new(Vec::new()));
+    let recorded = rotations.clone();
+    secrets.on_rotate("S3_SECRET_ACCESS_KEY", move |name, value| {
+        recorded
+            .lock()
+            .unwrap()
+            .push((name.to_string(), value.map(str::to_string)));
+    });
+
+    assert!(secrets.refresh().await.is_empty());
+
+    source.set("S3_SECRET_ACCESS_KEY", "v2");
+    assert_eq!(secrets.refresh().await, vec!["S3_SECRET_ACCESS_KEY"]);
+    assert_eq!(
+        *rotations.lock().unwrap(),
+        vec![("S3_SECRET_ACCESS_KEY".to_string(), Some("v2".to_string()))]
+    );
+    assert_eq!(
+        secrets
+            .get("S3_SECRET_ACCESS_KEY")
+            .await
+            .unwrap()
+            .as_deref(),
+        Some("v2")
+    );
+}
+
+#[tokio::test]
+async fn file_source_reads_secrets_from_a_directory() {
+    let directory = std::env::temp_dir().join(format!("secrets-{}", uuid::Uuid::new_v4()));
+    std::fs::create_dir_all(&directory).unwrap();
+    std::fs::write(directory.join("SECRETS_TEST_TOKEN"), "s3cr3t\n").unwrap();
+
+    let source = FileSource::new(Some(directory.clone()));
+    assert_eq!(
+        source.fetch("SECRETS_TEST_TOKEN").await.unwrap().as_deref(),
+        Some("s3cr3t")
+    );
+    assert_eq!(source.fetch("SECRETS_TEST_MISSING").await.unwrap(), None);
+    assert_eq!(
+        FileSource::default()
+            .fetch("SECRETS_TEST_TOKEN")
+            .await
+            .unwrap(),
+        None
+    );
+
+    std::fs::remove_dir_all(directory).unwrap();
+}
+
+#[tokio::test]
+async fn vault_source_reads_keys_from_a_kv_document() {
+    let app = Router::new().route("/v1/secret/data/rust-web-demo", get(fake_vault));
+    let address = serve(app).await;
+
+    let source = VaultSource::new(VaultConfig {
+        address: format!("http://{address}/"),
+        token: "vault-token".to_string(),
+        mount: "secret".to_string(),
+        path: "rust-web-demo".to_string(),
+        namespace: None,
+    })
+    .unwrap();
+    assert_eq!(
+        source.fetch("REDIS_URL").await.unwrap().as_deref(),
+        Some("redis://vault")
+    );
+    assert_eq!(source.fetch("DATABASE_URL").await.unwrap(), None);
+
+    let unauthorized = VaultSource::new(VaultConfig {
+        address: format!("http://{address}"),
+        token: "otro-token".to_string(),
+        mount: "secret".to_string(),
+        path: "rust-web-demo".to_string(),
+        namespace: None,
+    })
+    .unwrap();
+    assert!(unauthorized.fetch("REDIS_URL").await.is_err());
+
+    let missing = VaultSource::new(VaultConfig {
+        address: format!("http://{address}"),
+        token: "vault-token".to_string(),
+        mount: "secret".to_string(),
+        path: "otro-documento".to_string(),
+        namespace: None,
+    })
+    .unwrap();
+    assert_eq!(missing.fetch("REDIS_URL").await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn aws_secrets_manager_source_signs_requests_and_reads_json_fields() {
+    let app = Router::new().route("/", post(fake_secrets_manager));
+    let address = serve(app).await;
+
+    let source = AwsSecretsManagerSource::new(AwsSecretsManagerConfig {
+        secret_id: "rust-web-demo".to_string(),
+        region: "eu-west-1".to_string(),
+        endpoint: format!("http://{address}"),
+        access_key_id: "AKIDEXAMPLE".to_string(),
+        secret_access_key: "secret".to_string(),
+        session_token: Some("session".to_string()),
+    })
+    .unwrap();
+    assert_eq!(
+        source.fetch("S3_ACCESS_KEY_ID").await.unwrap().as_deref(),
+        Some("AKIAS3")
+    );
+    assert_eq!(
+        source
+            .fetch("EMAIL_ENCRYPTION_PREVIOUS_KEYS")
+            .await
+            .unwrap(),
+        None
+    );
+
+    let missing = AwsSecretsManagerSource::new(AwsSecretsManagerConfig {
+        secret_id: "no-existe".to_string(),
+        region: "eu-west-1".to_string(),
+        endpoint: format!("http://{address}"),
+        access_key_id: "AKIDEXAMPLE".to_string(),
+        secret_access_key: "secret".to_string(),
+        session_token: None,
+    })
+    .unwrap();
+    assert_eq!(missing.fetch("S3_ACCESS_KEY_ID").await.unwrap(), None);
+}
+
+async fn fake_vault(headers: HeaderMap) -> Result<Json<serde_json::Value>, StatusCode> {
+    if headers
+        .get("x-vault-token")
+        .and_then(|value| value.to_str().ok())
+        != Some("vault-token")
+    {
+        return Err(StatusCode::FORBIDDEN);
+    }
+
+    Ok(Json(serde_json::json!({
+        "data": {
+            "data": { "REDIS_URL": "redis://vault" },
+            "metadata": { "version": 3 }
+        }
+    })))
+}
+
+async fn fake_secrets_manager(headers: HeaderMap, body: Bytes) -> (StatusCode, String) {
+    let header = |name: &str| {
+        headers
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .unwrap_or_default()
+            .to_string()
+    };
+    let authorization = header("authorization");
+    assert!(
+        authorization.starts_with("AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/")
+            && authorization.contains("/eu-west-1/secretsmanager/aws4_request")
+            && authorization.contains("SignedHeaders=content-type;host;x-amz-date"),
+        "{authorization}"
+    );
+    assert_eq!(header("x-amz-target"), "secretsmanager.GetSecretValue");
+    assert_eq!(header("content-type"), "application/x-amz-json-1.1");
+
+    let request: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    if request["SecretId"] != "rust-web-demo" {
+        return (
+            StatusCode::BAD_REQUEST,
+            r#"{"__type":"ResourceNotFoundException"}"#.to_string(),
+        );
+    }
+    assert_eq!(header("x-amz-security-token"), "session");
+
+    let secret_string = serde_json::json!({ "S3_ACCESS_KEY_ID": "AKIAS3" }).to_string();
+    (
+        StatusCode::OK,
+        serde_json::json!({ "Name": "rust-web-demo", "SecretString": secret_string }).to_string(),
+    )
+}
+
+async fn serve(app: Router) -> std::net::SocketAddr {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+    address
+}
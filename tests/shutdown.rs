@@ -0,0 +1,38 @@
+#![cfg(unix)]
+
+use std::{process::Command, time::Duration};
+
+use rust_web_demo::shutdown::shutdown_signal;
+
+#[tokio::test]
+async fn sigterm_triggers_the_shutdown() {
+    let shutdown = tokio::spawn(shutdown_signal());
+
+    // El manejador ya está instalado: la señal no termina el proceso de pruebas.
+    let status = Command::new("kill")
+        .args(["-TERM", &std::process::id().to_string()])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    tokio::time::timeout(Duration::from_secs(5), shutdown)
+        .await
+        .expect("SIGTERM debería iniciar el apagado")
+        .unwrap();
+}
+
+#[tokio::test]
+async fn sigint_sent_before_the_first_poll_triggers_the_shutdown() {
+    let shutdown = shutdown_signal();
+
+    // Aún nadie ha esperado el futuro, pero el manejador ya está instalado.
+    let status = Command::new("kill")
+        .args(["-INT", &std::process::id().to_string()])
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    tokio::time::timeout(Duration::from_secs(5), shutdown)
+        .await
+        .expect("SIGINT debería iniciar el apagado");
+}
@@ -0,0 +1,156 @@
+use std::{
+    io::Write,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use axum::{
+    body::Body,
+    http::{self, Request, StatusCode},
+    routing::Router,
+};
+use http_body_util::BodyExt;
+use sqlx::{sqlite::SqlitePoolOptions, SqlitePool};
+
+use rust_web_demo::{config::AppConfig, database, models::user::User, routes, state::AppState};
+
+/// Destino de las trazas que guarda todo lo escrito.
+#[derive(Clone, Default)]
+struct Captured(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Captured {
+    fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(bytes);
+        Ok(bytes.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Captured {
+    /// Avisos de sentencia lenta registrados hasta ahora.
+    fn slow_statements(&self) -> Vec<serde_json::Value> {
+        String::from_utf8(self.0.lock().unwrap().clone())
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .filter(|line| line["level"] == "WARN" && line["target"] == "sqlx::query")
+            .collect()
+    }
+
+    fn clear(&self) {
+        self.0.lock().unwrap().clear();
+    }
+}
+
+#[tokio::test]
+async fn slow_statements_are_logged_with_statement_name_and_id() {
+    // SQLite ejecuta las sentencias en un hilo propio, así que el suscriptor debe ser global.
+    let captured = Captured::default();
+    let writer = captured.clone();
+    tracing::subscriber::set_global_default(
+        tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_max_level(tracing::Level::INFO)
+            .json()
+            .flatten_event(true)
+            .finish(),
+    )
+    .unwrap();
+
+    let slow_app = app(Duration::ZERO).await;
+    let created: User = body_json(
+        send(
+            &slow_app,
+            http::Method::POST,
+            "/users",
+            Some(serde_json::json!({ "name": "Ada", "email": "ada@example.com" })),
+        )
+        .await,
+    )
+    .await;
+    captured.clear();
+
+    let response = send(
+        &slow_app,
+        http::Method::GET,
+        &format!("/users/{}", created.id),
+        None,
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // El hilo de SQLite registra la sentencia al soltarla, que puede ocurrir justo después.
+    let mut get = None;
+    for _ in 0..50 {
+        get = captured
+            .slow_statements()
+            .into_iter()
+            .find(|line| line["span"]["statement"] == "users.get");
+        if get.is_some() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    let get = get.expect("No se registró la consulta lenta");
+    assert_eq!(get["span"]["name"], "db.query");
+    assert_eq!(get["span"]["id"], created.id.to_string());
+    assert!(get["summary"].as_str().unwrap().starts_with("SELECT"));
+    assert!(get["elapsed_secs"].as_f64().is_some());
+    // Los valores vinculados no aparecen en el log.
+    assert!(!get.to_string().contains("ada@example.com"));
+
+    // Por debajo del umbral no se registra nada.
+    let fast_app = app(Duration::from_secs(60)).await;
+    captured.clear();
+    send(&fast_app, http::Method::GET, "/users", None).await;
+    assert!(captured.slow_statements().is_empty());
+}
+
+async fn app(slow_query_threshold: Duration) -> Router {
+    let config = AppConfig {
+        slow_query_threshold,
+        ..AppConfig::default()
+    };
+    let pool = connect(&config).await;
+    let state = AppState::new(pool, config);
+    routes::api_routes(&state).with_state(state)
+}
+
+async fn connect(config: &AppConfig) -> SqlitePool {
+    let options = database::with_pragmas("sqlite::memory:".parse().unwrap(), config);
+    let pool = SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect_with(options)
+        .await
+        .unwrap();
+    sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+    pool
+}
+
+async fn body_json<T: serde::de::DeserializeOwned>(response: http::Response<Body>) -> T {
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+async fn send(
+    app: &Router,
+    method: http::Method,
+    uri: &str,
+    payload: Option<serde_json::Value>,
+) -> http::Response<Body> {
+    let mut request = Request::builder().method(method).uri(uri);
+    let body = match payload {
+        Some(payload) => {
+            request = request.header(http::header::CONTENT_TYPE, "application/json");
+            Body::from(serde_json::to_vec(&payload).unwrap())
+        }
+        None => Body::empty(),
+    };
+
+    tower::ServiceExt::oneshot(app.clone(), request.body(body).unwrap())
+        .await
+        .unwrap()
+}
@@ -0,0 +1,102 @@
+use std::{collections::HashMap, net::TcpListener};
+
+use rust_web_demo::socket_activation::{passed_fds, InheritedListeners, PassedFd, SocketRole};
+
+fn lookup(variables: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+    let variables: HashMap<String, String> = variables
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect();
+    move |name| variables.get(name).cloned()
+}
+
+fn listener() -> TcpListener {
+    TcpListener::bind("127.0.0.1:0").unwrap()
+}
+
+#[test]
+fn descriptors_are_only_taken_when_addressed_to_this_process() {
+    let variables = lookup(&[("LISTEN_PID", "42"), ("LISTEN_FDS", "2")]);
+
+    assert_eq!(passed_fds(&variables, 7).unwrap(), Vec::new());
+    assert_eq!(
+        passed_fds(&variables, 42).unwrap(),
+        vec![
+            PassedFd { fd: 3, name: None },
+            PassedFd { fd: 4, name: None }
+        ]
+    );
+    assert_eq!(passed_fds(lookup(&[]), 42).unwrap(), Vec::new());
+}
+
+#[test]
+fn descriptor_names_must_match_the_count() {
+    let named = lookup(&[
+        ("LISTEN_PID", "42"),
+        ("LISTEN_FDS", "2"),
+        ("LISTEN_FDNAMES", "admin:http"),
+    ]);
+    assert_eq!(
+        passed_fds(named, 42).unwrap(),
+        vec![
+            PassedFd {
+                fd: 3,
+                name: Some("admin".to_string())
+            },
+            PassedFd {
+                fd: 4,
+                name: Some("http".to_string())
+            },
+        ]
+    );
+
+    let mismatched = lookup(&[
+        ("LISTEN_PID", "42"),
+        ("LISTEN_FDS", "1"),
+        ("LISTEN_FDNAMES", "admin:http"),
+    ]);
+    assert!(passed_fds(mismatched, 42).is_err());
+    assert!(passed_fds(lookup(&[("LISTEN_PID", "42"), ("LISTEN_FDS", "dos")]), 42).is_err());
+}
+
+#[test]
+fn sockets_are_assigned_by_name_or_else_by_position() {
+    let public = listener();
+    let admin = listener();
+    let (public_address, admin_address) =
+        (public.local_addr().unwrap(), admin.local_addr().unwrap());
+
+    let mut named = InheritedListeners::new(vec![
+        (Some("admin".to_string()), admin),
+        (Some("http".to_string()), public),
+    ]);
+    assert_eq!(
+        named
+            .take(SocketRole::Public)
+            .unwrap()
+            .local_addr()
+            .unwrap(),
+        public_address
+    );
+    assert!(named.contains(SocketRole::Admin));
+    assert_eq!(
+        named.take(SocketRole::Admin).unwrap().local_addr().unwrap(),
+        admin_address
+    );
+    assert!(named.take(SocketRole::Admin).is_none());
+    assert!(named.unused().is_empty());
+
+    // Un único socket con nombre de administración no se usa como público.
+    let mut admin_only = InheritedListeners::new(vec![(Some("admin".to_string()), listener())]);
+    assert!(admin_only.take(SocketRole::Public).is_none());
+    assert!(admin_only.contains(SocketRole::Admin));
+
+    let mut unnamed = InheritedListeners::new(vec![
+        (Some("proyecto-rust.socket".to_string()), listener()),
+        (None, listener()),
+        (None, listener()),
+    ]);
+    assert!(unnamed.take(SocketRole::Public).is_some());
+    assert!(unnamed.take(SocketRole::Admin).is_some());
+    assert_eq!(unnamed.unused(), vec![None]);
+}
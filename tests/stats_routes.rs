@@ -0,0 +1,285 @@
+use std::sync::Arc;
+
+use axum::http::StatusCode;
+use chrono::{Duration, NaiveDate, TimeZone, Utc};
+
+use rust_web_demo::{
+    clock::{Clock, MockClock},
+    config::AppConfig,
+    encryption::EmailEncryption,
+    handlers::stats::rollup_daily_stats,
+    models::{
+        stats::{DomainCount, PeriodCount, UserStats, MAX_STATS_DAYS},
+        user::UserStatus,
+    },
+    scheduler::default_scheduler,
+    state::AppState,
+    testing::{body_json, factory::UserFactory, test_pool, TestContext},
+};
+
+/// Contexto con el reloj detenido el jueves 15 de octubre de 2026 y estos usuarios:
+/// tres altas en la semana del lunes 12, una el domingo anterior y otra en agosto.
+async fn context_with_signups(encryption: EmailEncryption) -> TestContext {
+    let clock = MockClock::new(Utc.with_ymd_and_hms(2026, 10, 15, 12, 0, 0).unwrap());
+    let state = AppState::new(test_pool().await, AppConfig::default())
+        .with_clock(Arc::new(clock))
+        .with_email_encryption(encryption.clone());
+    let context = TestContext::from_state(state);
+
+    for (email, (month, day, hour), status) in [
+        ("ada@example.com", (10, 15, 9), UserStatus::Active),
+        ("grace@example.com", (10, 14, 0), UserStatus::Active),
+        ("alan@example.org", (10, 12, 23), UserStatus::Suspended),
+        ("linus@kernel.org", (10, 11, 23), UserStatus::Deactivated),
+        ("old@example.com", (8, 1, 12), UserStatus::Active),
+    ] {
+        UserFactory::new()
+            .with_email(email)
+            .with_status(status)
+            .with_created_at(Utc.with_ymd_and_hms(2026, month, day, hour, 0, 0).unwrap())
+            .with_encryption(&encryption)
+            .create(&context.state.database_pool)
+            .await;
+    }
+    context
+}
+
+async fn stats(context: &TestContext, query: &str) -> UserStats {
+    let response = context.get(&format!("/stats/users?{query}")).await;
+    assert_eq!(response.status(), StatusCode::OK, "{query}");
+    body_json(response).await
+}
+
+fn date(month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2026, month, day).unwrap()
+}
+
+fn domains(stats: &UserStats) -> Vec<(&str, i64)> {
+    stats
+        .top_email_domains
+        .iter()
+        .map(|DomainCount { domain, count }| (domain.as_str(), *count))
+        .collect()
+}
+
+#[tokio::test]
+async fn stats_group_signups_by_period_within_the_window() {
+    let context = context_with_signups(EmailEncryption::disabled()).await;
+
+    let last_month = stats(&context, "").await;
+    assert_eq!(
+        (last_month.from, last_month.to),
+        (date(9, 16), date(10, 15))
+    );
+    assert_eq!(
+        (
+            last_month.totals.users,
+            last_month.totals.active,
+            last_month.totals.suspended,
+            last_month.totals.deactivated,
+            last_month.totals.signups,
+        ),
+        (5, 3, 1, 1, 4)
+    );
+    // La serie es continua: un día por fila, con ceros donde no hubo altas.
+    assert_eq!(last_month.signups.len(), 30);
+    let busy_days = last_month
+        .signups
+        .iter()
+        .filter(|bucket| bucket.count > 0)
+        .map(|bucket| bucket.period)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        busy_days,
+        [date(10, 11), date(10, 12), date(10, 14), date(10, 15)]
+    );
+    assert_eq!(
+        domains(&last_month),
+        [("example.com", 2), ("example.org", 1), ("kernel.org", 1)]
+    );
+
+    let weekly = stats(
+        &context,
+        "interval=week&from=2026-10-01&to=2026-10-15&top=1",
+    )
+    .await;
+    assert_eq!(
+        weekly.signups,
+        [
+            PeriodCount {
+                period: date(9, 28),
+                count: 0
+            },
+            PeriodCount {
+                period: date(10, 5),
+                count: 1
+            },
+            PeriodCount {
+                period: date(10, 12),
+                count: 3
+            },
+        ]
+    );
+    assert_eq!(domains(&weekly), [("example.com", 2)]);
+
+    for query in [
+        "from=2026-10-15&to=2026-10-01",
+        "from=2025-01-01&to=2026-10-15",
+        "top=0",
+        "top=51",
+    ] {
+        let response = context.get(&format!("/stats/users?{query}")).await;
+        assert_eq!(
+            response.status(),
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "{query}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn stats_count_domains_of_encrypted_emails() {
+    let encryption = EmailEncryption::new([1; 32], &[], [9; 32]);
+    let context = context_with_signups(encryption).await;
+
+    let last_month = stats(&context, "top=2").await;
+    assert_eq!(last_month.totals.signups, 4);
+    assert_eq!(
+        domains(&last_month),
+        [("example.com", 2), ("example.org", 1)]
+    );
+}
+
+#[tokio::test]
+async fn stats_read_requests_from_the_daily_rollup() {
+    let context = context_with_signups(EmailEncryption::disabled()).await;
+    let pool = &context.state.database_pool;
+    for (day, requests) in [(date(10, 12), 40), (date(10, 14), 7)] {
+        sqlx::query("INSERT INTO tenant_usage (tenant_id, day, requests) VALUES ('default', ?, ?)")
+            .bind(day.to_string())
+            .bind(requests)
+            .execute(pool)
+            .await
+            .unwrap();
+    }
+
+    // Hasta que se resume, el uso no aparece en las estadísticas.
+    let before = stats(&context, "from=2026-10-12&to=2026-10-15").await;
+    assert!(before.requests.iter().all(|bucket| bucket.count == 0));
+
+    for day in [date(10, 12), date(10, 14)] {
+        assert_eq!(rollup_daily_stats(&context.state, day).await.unwrap(), 1);
+    }
+    // Repetir un día ya resumido no duplica las cifras.
+    rollup_daily_stats(&context.state, date(10, 14))
+        .await
+        .unwrap();
+    // Y si el uso de un día desaparece, su fila también.
+    sqlx::query("INSERT INTO daily_user_stats (tenant_id, day, requests) VALUES ('gone', ?, 9)")
+        .bind(date(10, 12).to_string())
+        .execute(pool)
+        .await
+        .unwrap();
+    assert_eq!(
+        rollup_daily_stats(&context.state, date(10, 12))
+            .await
+            .unwrap(),
+        2
+    );
+
+    let rows = sqlx::query_as::<_, (String, i64)>(
+        "SELECT day, requests FROM daily_user_stats ORDER BY day",
+    )
+    .fetch_all(pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        rows,
+        [
+            ("2026-10-12".to_string(), 40),
+            ("2026-10-14".to_string(), 7)
+        ]
+    );
+
+    let daily = stats(&context, "from=2026-10-12&to=2026-10-15").await;
+    let requests = daily
+        .requests
+        .iter()
+        .map(|bucket| (bucket.period, bucket.count))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        requests,
+        [
+            (date(10, 12), 40),
+            (date(10, 13), 0),
+            (date(10, 14), 7),
+            (date(10, 15), 0)
+        ]
+    );
+
+    let weekly = stats(&context, "interval=week&from=2026-10-05&to=2026-10-15").await;
+    assert_eq!(
+        weekly.requests,
+        [
+            PeriodCount {
+                period: date(10, 5),
+                count: 0
+            },
+            PeriodCount {
+                period: date(10, 12),
+                count: 47
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn rollup_task_catches_up_on_every_missed_day() {
+    let clock = MockClock::new(Utc.with_ymd_and_hms(2026, 10, 15, 0, 30, 0).unwrap());
+    let state =
+        AppState::new(test_pool().await, AppConfig::default()).with_clock(Arc::new(clock.clone()));
+    let pool = state.database_pool.clone();
+    let scheduler = default_scheduler(state).unwrap();
+
+    // La primera pasada solo registra las tareas; la siguiente noche resume la ventana entera.
+    scheduler.run_due(clock.now()).await.unwrap();
+    clock.advance(Duration::days(1));
+    scheduler.run_due(clock.now()).await.unwrap();
+
+    for (day, requests) in [(16, 3), (17, 5), (18, 8), (19, 13)] {
+        sqlx::query("INSERT INTO tenant_usage (tenant_id, day, requests) VALUES ('default', ?, ?)")
+            .bind(date(10, day).to_string())
+            .bind(requests)
+            .execute(&pool)
+            .await
+            .unwrap();
+    }
+
+    // El servicio se detiene cuatro días: al volver, una sola ejecución recupera todos.
+    clock.advance(Duration::days(4));
+    scheduler.run_due(clock.now()).await.unwrap();
+
+    let rows = sqlx::query_as::<_, (String, i64)>(
+        "SELECT day, requests FROM daily_user_stats WHERE day >= '2026-10-16' ORDER BY day",
+    )
+    .fetch_all(&pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        rows,
+        [
+            ("2026-10-16".to_string(), 3),
+            ("2026-10-17".to_string(), 5),
+            ("2026-10-18".to_string(), 8),
+            ("2026-10-19".to_string(), 13),
+        ]
+    );
+
+    let rolled_up = sqlx::query_scalar::<_, String>("SELECT day FROM daily_rollups ORDER BY day")
+        .fetch_all(&pool)
+        .await
+        .unwrap();
+    assert_eq!(rolled_up.len() as u64, MAX_STATS_DAYS + 4);
+    assert_eq!(rolled_up.first().unwrap(), "2025-10-15");
+    assert_eq!(rolled_up.last().unwrap(), "2026-10-19");
+}
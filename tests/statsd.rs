@@ -0,0 +1,94 @@
+use std::time::Duration;
+
+use axum::http::Method;
+use tokio::net::UdpSocket;
+
+use rust_web_demo::{
+    metrics::{Metrics, RouteKey},
+    statsd::{self, StatsdConfig, StatsdFlavor},
+};
+
+/// Servidor StatsD de prueba y la configuración que apunta a él.
+async fn server(flavor: StatsdFlavor) -> (UdpSocket, StatsdConfig) {
+    let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+    let config = StatsdConfig {
+        address: socket.local_addr().unwrap().to_string(),
+        prefix: "app".to_string(),
+        flavor,
+        flush_interval: Duration::from_millis(20),
+    };
+    (socket, config)
+}
+
+/// Líneas recibidas hasta que aparece una que empieza por `prefix`.
+async fn receive_until(socket: &UdpSocket, prefix: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut buffer = [0; 2048];
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while !lines.iter().any(|line: &String| line.starts_with(prefix)) {
+            let size = socket.recv(&mut buffer).await.unwrap();
+            lines.extend(
+                String::from_utf8_lossy(&buffer[..size])
+                    .lines()
+                    .map(str::to_string),
+            );
+        }
+    })
+    .await
+    .expect("StatsD no recibió la métrica esperada");
+    lines
+}
+
+#[tokio::test]
+async fn recorded_metrics_are_pushed_with_tags_to_dogstatsd() {
+    let (socket, config) = server(StatsdFlavor::Datadog).await;
+    let (sink, exporter) = statsd::connect(&config).await.unwrap();
+    let metrics = Metrics::new();
+    metrics.export_to(sink);
+    tokio::spawn(exporter.run(metrics.clone()));
+
+    metrics.record(503, Duration::from_millis(12));
+    metrics.record_route(
+        RouteKey::new("/users/:id", &Method::GET, 503),
+        Duration::from_millis(12),
+    );
+    metrics.record_shed("api");
+
+    let lines = receive_until(&socket, "app.http.requests_shed").await;
+    assert!(lines.contains(&"app.http.requests:1|c".to_string()));
+    assert!(lines.contains(&"app.http.request_errors:1|c".to_string()));
+    assert!(lines.contains(&"app.http.request_duration:12.000|ms".to_string()));
+    assert!(lines.contains(
+        &"app.http.route_request_duration:12.000|ms|#route:/users/:id,method:GET,status:5xx"
+            .to_string()
+    ));
+    assert!(lines.contains(&"app.http.requests_shed:1|c|#group:api".to_string()));
+
+    let gauges = receive_until(&socket, "app.http.requests_in_flight:0|g").await;
+    assert!(gauges
+        .iter()
+        .any(|line| line.starts_with("app.process.uptime_seconds:")));
+}
+
+#[tokio::test]
+async fn plain_statsd_gets_untagged_metrics_only() {
+    let (socket, config) = server(StatsdFlavor::Plain).await;
+    let (sink, exporter) = statsd::connect(&config).await.unwrap();
+    let metrics = Metrics::new();
+    metrics.export_to(sink);
+    tokio::spawn(exporter.run(metrics.clone()));
+
+    metrics.record_route(
+        RouteKey::new("/users/:id", &Method::GET, 200),
+        Duration::from_millis(3),
+    );
+    metrics.record_shed("admin");
+    metrics.record_cache_lookup(true);
+
+    let lines = receive_until(&socket, "app.cache.hits").await;
+    assert!(lines.contains(&"app.http.requests_shed.admin:1|c".to_string()));
+    assert!(lines.iter().all(|line| !line.contains("|#")));
+    assert!(lines
+        .iter()
+        .all(|line| !line.contains("route_request_duration")));
+}
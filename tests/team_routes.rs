@@ -0,0 +1,359 @@
+use axum::{
+    body::Body,
+    http::{self, Request, StatusCode},
+    middleware,
+    routing::Router,
+};
+use http_body_util::BodyExt;
+use sqlx::sqlite::SqlitePoolOptions;
+use uuid::Uuid;
+
+use rust_web_demo::{
+    config::AppConfig, handlers::policy::Rule, models, models::team::TeamRole, routes,
+    state::AppState, tenant, testing::factory::UserFactory,
+};
+
+#[tokio::test]
+async fn team_owner_manages_members_and_members_have_read_access() {
+    let context = TestContext::new().await;
+    let owner = UserFactory::new()
+        .with_name("Ada Lovelace")
+        .with_email("ada@example.com")
+        .create(&context.pool)
+        .await;
+    let member = UserFactory::new()
+        .with_name("Alan Turing")
+        .with_email("alan@example.com")
+        .create(&context.pool)
+        .await;
+    let outsider = UserFactory::new()
+        .with_name("Grace Hopper")
+        .with_email("grace@example.com")
+        .create(&context.pool)
+        .await;
+
+    let response = context
+        .send_json(
+            owner.id,
+            http::Method::POST,
+            "/teams",
+            serde_json::json!({ "name": "  Motor analítico  " }),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let team: models::team::Team = body_json(response).await;
+    assert_eq!(team.name, "Motor analítico");
+    let team_uri = format!("/teams/{}", team.id);
+    let members_uri = format!("{team_uri}/members");
+
+    let response = context
+        .send_json(
+            owner.id,
+            http::Method::POST,
+            &members_uri,
+            serde_json::json!({ "user_id": member.id }),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let membership: models::team::TeamMember = body_json(response).await;
+    assert_eq!(membership.role, models::team::TeamRole::Member);
+
+    let response = context
+        .send_json(
+            owner.id,
+            http::Method::POST,
+            &members_uri,
+            serde_json::json!({ "user_id": member.id }),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = context
+        .send(member.id, http::Method::GET, &members_uri)
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let members: Vec<models::team::TeamMember> = body_json(response).await;
+    assert_eq!(members.len(), 2);
+
+    let teams: Vec<models::team::Team> =
+        body_json(context.send(member.id, http::Method::GET, "/teams").await).await;
+    assert_eq!(teams.len(), 1);
+
+    // Los miembros pueden consultar, pero no gestionar.
+    let response = context
+        .send_json(
+            member.id,
+            http::Method::PUT,
+            &team_uri,
+            serde_json::json!({ "name": "Otro nombre" }),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let response = context
+        .send_json(
+            member.id,
+            http::Method::POST,
+            &members_uri,
+            serde_json::json!({ "user_id": outsider.id }),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    // Quien no pertenece al equipo no puede ni verlo.
+    let response = context
+        .send(outsider.id, http::Method::GET, &team_uri)
+        .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+    let response = context
+        .request(
+            Request::builder()
+                .uri(&team_uri)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = context
+        .send(
+            owner.id,
+            http::Method::GET,
+            &format!("/teams/{}", Uuid::new_v4()),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // Un miembro puede abandonar el equipo por sí mismo.
+    let response = context
+        .send(
+            member.id,
+            http::Method::DELETE,
+            &format!("{members_uri}/{}", member.id),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let response = context
+        .send(owner.id, http::Method::DELETE, &team_uri)
+        .await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let remaining = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM team_members")
+        .fetch_one(&context.pool)
+        .await
+        .unwrap();
+    assert_eq!(remaining, 0);
+}
+
+#[tokio::test]
+async fn team_always_keeps_an_owner() {
+    let context = TestContext::new().await;
+    let owner = UserFactory::new()
+        .with_name("Ada Lovelace")
+        .with_email("ada@example.com")
+        .create(&context.pool)
+        .await;
+    let member = UserFactory::new()
+        .with_name("Alan Turing")
+        .with_email("alan@example.com")
+        .create(&context.pool)
+        .await;
+
+    let team: models::team::Team = body_json(
+        context
+            .send_json(
+                owner.id,
+                http::Method::POST,
+                "/teams",
+                serde_json::json!({ "name": "Núcleo" }),
+            )
+            .await,
+    )
+    .await;
+    let members_uri = format!("/teams/{}/members", team.id);
+    context
+        .send_json(
+            owner.id,
+            http::Method::POST,
+            &members_uri,
+            serde_json::json!({ "user_id": member.id }),
+        )
+        .await;
+
+    let owner_uri = format!("{members_uri}/{}", owner.id);
+    let response = context
+        .send_json(
+            owner.id,
+            http::Method::PUT,
+            &owner_uri,
+            serde_json::json!({ "role": "member" }),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let response = context
+        .send(owner.id, http::Method::DELETE, &owner_uri)
+        .await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    // Tras promover a otro miembro, el propietario original sí puede irse.
+    let response = context
+        .send_json(
+            owner.id,
+            http::Method::PUT,
+            &format!("{members_uri}/{}", member.id),
+            serde_json::json!({ "role": "owner" }),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = context
+        .send(owner.id, http::Method::DELETE, &owner_uri)
+        .await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let members: Vec<models::team::TeamMember> = body_json(
+        context
+            .send(member.id, http::Method::GET, &members_uri)
+            .await,
+    )
+    .await;
+    assert_eq!(members.len(), 1);
+    assert_eq!(members[0].role, models::team::TeamRole::Owner);
+}
+
+#[tokio::test]
+async fn teams_only_admit_members_of_their_tenant() {
+    let context = TestContext::new().await;
+    sqlx::query("INSERT INTO tenants (id, name, created_at) VALUES ('acme', 'Acme', ?)")
+        .bind(chrono::Utc::now())
+        .execute(&context.pool)
+        .await
+        .unwrap();
+    let owner = UserFactory::new()
+        .with_name("Ada Lovelace")
+        .with_email("ada@example.com")
+        .create(&context.pool)
+        .await;
+    let foreigner = UserFactory::new()
+        .with_name("Alan Turing")
+        .with_email("alan@acme.com")
+        .with_tenant("acme")
+        .create(&context.pool)
+        .await;
+
+    let response = context
+        .send_json(
+            owner.id,
+            http::Method::POST,
+            "/teams",
+            serde_json::json!({ "name": "Motor analítico" }),
+        )
+        .await;
+    let team: models::team::Team = body_json(response).await;
+    let members_uri = format!("/teams/{}/members", team.id);
+
+    let response = context
+        .send_json(
+            owner.id,
+            http::Method::POST,
+            &members_uri,
+            serde_json::json!({ "user_id": foreigner.id }),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body: serde_json::Value = body_json(response).await;
+    assert_eq!(body["errors"][0]["field"], "user_id");
+
+    // Desde otro inquilino el equipo no existe, aunque se declare ser su owner.
+    let response = context
+        .request(
+            Request::builder()
+                .uri(&members_uri)
+                .header("x-user-id", owner.id.to_string())
+                .header("x-tenant-id", "acme")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[test]
+fn policy_rules_grant_each_role_what_it_declares() {
+    for rule in [Rule::Member, Rule::Owner, Rule::OwnerOrSelf] {
+        assert!(rule.allows(Some(TeamRole::Owner), false));
+        assert!(!rule.allows(None, true));
+    }
+    assert!(Rule::Member.allows(Some(TeamRole::Member), false));
+    assert!(!Rule::Owner.allows(Some(TeamRole::Member), true));
+    assert!(Rule::OwnerOrSelf.allows(Some(TeamRole::Member), true));
+    assert!(!Rule::OwnerOrSelf.allows(Some(TeamRole::Member), false));
+}
+
+struct TestContext {
+    app: Router,
+    pool: sqlx::SqlitePool,
+}
+
+impl TestContext {
+    async fn new() -> Self {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+
+        let state = AppState::new(pool.clone(), AppConfig::default());
+        let app = routes::user_routes()
+            .merge(routes::team_routes())
+            .route_layer(middleware::from_fn_with_state(
+                state.clone(),
+                tenant::resolve_tenant,
+            ))
+            .with_state(state);
+
+        Self { app, pool }
+    }
+
+    async fn request(&self, request: Request<Body>) -> http::Response<Body> {
+        tower::ServiceExt::oneshot(self.app.clone(), request)
+            .await
+            .unwrap()
+    }
+
+    async fn send(&self, actor: Uuid, method: http::Method, uri: &str) -> http::Response<Body> {
+        self.request(
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("x-user-id", actor.to_string())
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+    }
+
+    async fn send_json(
+        &self,
+        actor: Uuid,
+        method: http::Method,
+        uri: &str,
+        payload: serde_json::Value,
+    ) -> http::Response<Body> {
+        self.request(
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("x-user-id", actor.to_string())
+                .header(http::header::CONTENT_TYPE, "application/json")
+                .body(Body::from(serde_json::to_vec(&payload).unwrap()))
+                .unwrap(),
+        )
+        .await
+    }
+}
+
+async fn body_json<T: serde::de::DeserializeOwned>(response: http::Response<Body>) -> T {
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    serde_json::from_slice(&bytes).unwrap()
+}
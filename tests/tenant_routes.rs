@@ -0,0 +1,330 @@
+use std::path::PathBuf;
+
+use axum::{
+    body::Body,
+    http::{self, Request, StatusCode},
+    middleware,
+    routing::Router,
+};
+use http_body_util::BodyExt;
+use sqlx::sqlite::SqlitePoolOptions;
+
+use rust_web_demo::{config::AppConfig, models, routes, state::AppState, tenant};
+
+#[tokio::test]
+async fn tenants_get_their_own_database_file_until_deleted() {
+    let directory = std::env::temp_dir().join(format!("tenants-test-{}", uuid::Uuid::new_v4()));
+    let context = TestContext::new(Some(directory.clone())).await;
+
+    let response = context
+        .send(
+            http::Method::POST,
+            "/tenants",
+            None,
+            Some(serde_json::json!({ "id": "Acme", "name": "Acme Corp" })),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let created: models::tenant::Tenant = body_json(response).await;
+    assert_eq!(created.id, "acme");
+
+    // La base se crea y migra con la primera petición del inquilino.
+    let database_file = directory.join("acme.sqlite");
+    assert!(!database_file.exists());
+    let response = context
+        .send(
+            http::Method::POST,
+            "/users",
+            Some("acme"),
+            Some(serde_json::json!({ "name": "Ada Lovelace", "email": "ada@acme.com" })),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    assert!(database_file.exists());
+
+    let users: Vec<models::user::User> = body_json(
+        context
+            .send(http::Method::GET, "/users", Some("acme"), None)
+            .await,
+    )
+    .await;
+    assert_eq!(users.len(), 1);
+    let main_users = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM users")
+        .fetch_one(&context.pool)
+        .await
+        .unwrap();
+    assert_eq!(main_users, 0);
+
+    let response = context
+        .send(http::Method::DELETE, "/tenants/acme", None, None)
+        .await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert!(!database_file.exists());
+
+    let response = context
+        .send(http::Method::GET, "/users", Some("acme"), None)
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    std::fs::remove_dir_all(&directory).ok();
+}
+
+#[tokio::test]
+async fn deleting_a_shared_tenant_removes_only_its_users() {
+    let context = TestContext::new(None).await;
+
+    let response = context
+        .send(
+            http::Method::POST,
+            "/tenants",
+            None,
+            Some(serde_json::json!({ "id": "acme", "name": "Acme Corp" })),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let response = context
+        .send(
+            http::Method::POST,
+            "/tenants",
+            None,
+            Some(serde_json::json!({ "id": "acme", "name": "Otra Acme" })),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    for (tenant_id, email) in [("acme", "ada@acme.com"), ("default", "ada@example.com")] {
+        let response = context
+            .send(
+                http::Method::POST,
+                "/users",
+                Some(tenant_id),
+                Some(serde_json::json!({ "name": "Ada Lovelace", "email": email })),
+            )
+            .await;
+        assert_eq!(response.status(), StatusCode::CREATED);
+    }
+
+    let response = context
+        .send(http::Method::DELETE, "/tenants/acme", None, None)
+        .await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let response = context
+        .send(http::Method::DELETE, "/tenants/acme", None, None)
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = context
+        .send(http::Method::DELETE, "/tenants/default", None, None)
+        .await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let tenants: Vec<models::tenant::Tenant> = body_json(
+        context
+            .send(http::Method::GET, "/tenants", None, None)
+            .await,
+    )
+    .await;
+    assert_eq!(tenants.len(), 1);
+    let remaining_tenants = sqlx::query_scalar::<_, String>("SELECT tenant_id FROM users")
+        .fetch_all(&context.pool)
+        .await
+        .unwrap();
+    assert_eq!(remaining_tenants, vec!["default".to_string()]);
+}
+
+#[tokio::test]
+async fn tenant_quotas_limit_users_and_daily_requests() {
+    let context = TestContext::new(None).await;
+
+    let response = context
+        .send(
+            http::Method::POST,
+            "/tenants",
+            None,
+            Some(serde_json::json!({
+                "id": "acme",
+                "name": "Acme Corp",
+                "max_users": 1,
+                "max_requests_per_day": 3,
+            })),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let response = context
+        .send(
+            http::Method::POST,
+            "/users",
+            Some("acme"),
+            Some(serde_json::json!({ "name": "Ada Lovelace", "email": "ada@acme.com" })),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let response = context
+        .send(
+            http::Method::POST,
+            "/users",
+            Some("acme"),
+            Some(serde_json::json!({ "name": "Alan Turing", "email": "alan@acme.com" })),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    let body: serde_json::Value = body_json(response).await;
+    assert_eq!(body["quota"]["quota"], "max_users");
+    assert_eq!(body["quota"]["limit"], 1);
+    assert_eq!(body["quota"]["used"], 1);
+
+    let response = context
+        .send(http::Method::GET, "/users", Some("acme"), None)
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let users: Vec<models::user::User> = body_json(response).await;
+    assert_eq!(users.len(), 1);
+
+    let response = context
+        .send(http::Method::GET, "/users", Some("acme"), None)
+        .await;
+    assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+    assert!(response.headers().contains_key(http::header::RETRY_AFTER));
+    let body: serde_json::Value = body_json(response).await;
+    assert_eq!(body["quota"]["quota"], "max_requests_per_day");
+
+    // El resto de inquilinos no se ve afectado.
+    let response = context.send(http::Method::GET, "/users", None, None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let usage: models::tenant::TenantUsage = body_json(
+        context
+            .send(http::Method::GET, "/tenants/acme/usage", None, None)
+            .await,
+    )
+    .await;
+    assert_eq!(
+        usage.users,
+        models::tenant::QuotaUsage {
+            used: 1,
+            limit: Some(1)
+        }
+    );
+    assert_eq!(
+        usage.requests_today,
+        models::tenant::QuotaUsage {
+            used: 3,
+            limit: Some(3)
+        }
+    );
+
+    let response = context
+        .send(
+            http::Method::PUT,
+            "/tenants/acme/quotas",
+            None,
+            Some(serde_json::json!({ "max_users": 0 })),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let response = context
+        .send(
+            http::Method::PUT,
+            "/tenants/acme/quotas",
+            None,
+            Some(serde_json::json!({})),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = context
+        .send(http::Method::GET, "/users", Some("acme"), None)
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn a_zero_daily_request_quota_rejects_the_first_request() {
+    let context = TestContext::new(None).await;
+    // La API exige cuotas positivas, pero la fila puede venir de fuera de ella.
+    sqlx::query(
+        "INSERT INTO tenants (id, name, created_at, max_requests_per_day) \
+         VALUES ('acme', 'Acme Corp', ?, 0)",
+    )
+    .bind(chrono::Utc::now())
+    .execute(&context.pool)
+    .await
+    .unwrap();
+
+    for _ in 0..2 {
+        let response = context
+            .send(http::Method::GET, "/users", Some("acme"), None)
+            .await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let body: serde_json::Value = body_json(response).await;
+        assert_eq!(body["quota"]["quota"], "max_requests_per_day");
+        assert_eq!(body["quota"]["limit"], 0);
+    }
+
+    let counted: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM tenant_usage WHERE tenant_id = 'acme'")
+            .fetch_one(&context.pool)
+            .await
+            .unwrap();
+    assert_eq!(counted, 0);
+}
+
+async fn body_json<T: serde::de::DeserializeOwned>(response: http::Response<Body>) -> T {
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+struct TestContext {
+    app: Router,
+    pool: sqlx::SqlitePool,
+}
+
+impl TestContext {
+    async fn new(tenant_database_dir: Option<PathBuf>) -> Self {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+
+        let config = AppConfig {
+            tenant_database_dir,
+            ..AppConfig::default()
+        };
+        let state = AppState::new(pool.clone(), config);
+        let app = routes::user_routes()
+            .route_layer(middleware::from_fn_with_state(
+                state.clone(),
+                tenant::resolve_tenant,
+            ))
+            .merge(routes::tenant_routes())
+            .with_state(state);
+
+        Self { app, pool }
+    }
+
+    async fn send(
+        &self,
+        method: http::Method,
+        uri: &str,
+        tenant_id: Option<&str>,
+        payload: Option<serde_json::Value>,
+    ) -> http::Response<Body> {
+        let mut request = Request::builder().method(method).uri(uri);
+        if let Some(tenant_id) = tenant_id {
+            request = request.header(tenant::TENANT_HEADER, tenant_id);
+        }
+        let body = match payload {
+            Some(payload) => {
+                request = request.header(http::header::CONTENT_TYPE, "application/json");
+                Body::from(serde_json::to_vec(&payload).unwrap())
+            }
+            None => Body::empty(),
+        };
+
+        tower::ServiceExt::oneshot(self.app.clone(), request.body(body).unwrap())
+            .await
+            .unwrap()
+    }
+}
@@ -0,0 +1,134 @@
+use std::time::Duration;
+
+use axum::{
+    body::{Body, Bytes},
+    extract::State,
+    http::{HeaderMap, Request, StatusCode},
+    routing::{post, Router},
+};
+use tokio::sync::mpsc;
+
+use rust_web_demo::{
+    config::AppConfig,
+    state::AppState,
+    testing::{test_pool, TestContext},
+    trace_context::{TraceContext, TRACEPARENT_HEADER, TRACESTATE_HEADER},
+    webhooks::{self, WebhookDispatcher},
+};
+
+const TRACE_ID: &str = "4bf92f3577b34da6a3ce929d0e0e4736";
+const PARENT_ID: &str = "00f067aa0ba902b7";
+
+#[test]
+fn traceparent_follows_the_w3c_format() {
+    let traceparent = format!("00-{TRACE_ID}-{PARENT_ID}-01");
+    let context = TraceContext::parse(&traceparent, Some("vendor=valor")).unwrap();
+    assert_eq!(context.trace_id, TRACE_ID);
+    assert_eq!(context.span_id, PARENT_ID);
+    assert!(context.sampled());
+    assert_eq!(context.tracestate.as_deref(), Some("vendor=valor"));
+    assert_eq!(context.traceparent(), traceparent);
+
+    let child = context.child();
+    assert_eq!(child.trace_id, TRACE_ID);
+    assert_ne!(child.span_id, PARENT_ID);
+
+    // Una versión futura se entiende por sus primeros campos.
+    let future = format!("01-{TRACE_ID}-{PARENT_ID}-00-extra");
+    assert!(!TraceContext::parse(&future, None).unwrap().sampled());
+
+    for invalid in [
+        format!("00-{}-{PARENT_ID}-01", TRACE_ID.to_uppercase()),
+        format!("00-{}-{PARENT_ID}-01", "0".repeat(32)),
+        format!("00-{TRACE_ID}-{}-01", "0".repeat(16)),
+        format!("ff-{TRACE_ID}-{PARENT_ID}-01"),
+        format!("00-{TRACE_ID}-{PARENT_ID}-01-extra"),
+        format!("00-{TRACE_ID}-{PARENT_ID}"),
+        "basura".to_string(),
+    ] {
+        assert_eq!(TraceContext::parse(&invalid, None), None, "{invalid}");
+    }
+}
+
+#[tokio::test]
+async fn webhooks_continue_the_trace_of_the_request_that_caused_them() {
+    let pool = test_pool().await;
+    let state = AppState::new(pool.clone(), AppConfig::default());
+    tokio::spawn(webhooks::run(state.clone(), WebhookDispatcher::new(pool)));
+    let context = TestContext::from_state(state);
+    let (receiver_address, mut deliveries) = spawn_receiver().await;
+
+    let response = context
+        .post_json(
+            "/webhooks",
+            serde_json::json!({
+                "url": format!("http://{receiver_address}/hook"),
+                "events": ["user.created"]
+            }),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    let response = context
+        .request(
+            Request::post("/users")
+                .header("content-type", "application/json")
+                .header(TRACEPARENT_HEADER, format!("00-{TRACE_ID}-{PARENT_ID}-01"))
+                .header(TRACESTATE_HEADER, "vendor=valor")
+                .body(Body::from(
+                    r#"{"name":"Ada Lovelace","email":"ada@example.com"}"#,
+                ))
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+
+    // El outbox conserva la traza de la petición, con el span de la petición como padre.
+    let (traceparent, tracestate): (String, String) =
+        sqlx::query_as("SELECT traceparent, tracestate FROM outbox WHERE event_type = ?")
+            .bind("user.created")
+            .fetch_one(&context.state.database_pool)
+            .await
+            .unwrap();
+    let recorded = TraceContext::parse(&traceparent, Some(&tracestate)).unwrap();
+    assert_eq!(recorded.trace_id, TRACE_ID);
+    assert_ne!(recorded.span_id, PARENT_ID);
+    assert_eq!(tracestate, "vendor=valor");
+
+    let headers = tokio::time::timeout(Duration::from_secs(5), deliveries.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    let delivered = TraceContext::parse(
+        headers[TRACEPARENT_HEADER].to_str().unwrap(),
+        headers
+            .get(TRACESTATE_HEADER)
+            .map(|value| value.to_str().unwrap()),
+    )
+    .unwrap();
+    assert_eq!(delivered.trace_id, TRACE_ID);
+    assert_ne!(delivered.span_id, recorded.span_id);
+    assert_eq!(delivered.tracestate.as_deref(), Some("vendor=valor"));
+}
+
+/// Levanta un receptor HTTP local que acepta todas las entregas y publica sus cabeceras.
+async fn spawn_receiver() -> (std::net::SocketAddr, mpsc::UnboundedReceiver<HeaderMap>) {
+    async fn receive(
+        State(sender): State<mpsc::UnboundedSender<HeaderMap>>,
+        headers: HeaderMap,
+        _body: Bytes,
+    ) -> StatusCode {
+        sender.send(headers).unwrap();
+        StatusCode::OK
+    }
+
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let app = Router::new()
+        .route("/hook", post(receive))
+        .with_state(sender);
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    (address, receiver)
+}
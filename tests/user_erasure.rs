@@ -0,0 +1,243 @@
+use axum::{
+    body::Body,
+    http::{self, Request, StatusCode},
+    routing::Router,
+};
+use http_body_util::BodyExt;
+use sqlx::{sqlite::SqlitePoolOptions, SqlitePool};
+
+use rust_web_demo::{
+    config::AppConfig,
+    models::{erasure::ErasureConfirmation, user::User},
+    routes,
+    state::AppState,
+    testing::factory::UserFactory,
+};
+
+#[tokio::test]
+async fn erasing_a_user_requires_confirmation_and_keeps_related_rows() {
+    let context = TestContext::new().await;
+    let user = UserFactory::new()
+        .with_name("Ada Lovelace")
+        .with_email("ada@example.com")
+        .create(&context.pool)
+        .await;
+    let now = chrono::Utc::now();
+    sqlx::query("INSERT INTO posts (id, author_id, title, body, created_at, updated_at) VALUES (?, ?, 'Notas', 'Texto', ?, ?)")
+        .bind(uuid::Uuid::new_v4())
+        .bind(user.id)
+        .bind(now)
+        .bind(now)
+        .execute(&context.pool)
+        .await
+        .unwrap();
+    let erase_uri = format!("/users/{}/erase", user.id);
+
+    let response = context.send(http::Method::POST, &erase_uri, None).await;
+    assert_eq!(response.status(), StatusCode::ACCEPTED);
+    let confirmation: ErasureConfirmation = body_json(response).await;
+    assert!(confirmation.expires_at > now);
+
+    let response = context
+        .send(
+            http::Method::POST,
+            &erase_uri,
+            Some(serde_json::json!({ "confirmation_token": "no-es-el-token" })),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let fetched: User = body_json(
+        context
+            .send(http::Method::GET, &format!("/users/{}", user.id), None)
+            .await,
+    )
+    .await;
+    assert_eq!(fetched.email, "ada@example.com");
+
+    let response = context
+        .send(
+            http::Method::POST,
+            &erase_uri,
+            Some(serde_json::json!({ "confirmation_token": confirmation.confirmation_token })),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let erased: User = body_json(response).await;
+    assert_eq!(erased.id, user.id);
+    assert_eq!(erased.name, "Usuario borrado");
+    assert_eq!(
+        erased.email,
+        format!("erased-{}@erased.invalid", user.id.simple())
+    );
+    assert_eq!(erased.status.as_str(), "deactivated");
+
+    let posts: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM posts WHERE author_id = ?")
+        .bind(user.id)
+        .fetch_one(&context.pool)
+        .await
+        .unwrap();
+    assert_eq!(posts, 1);
+
+    let response = context
+        .send(
+            http::Method::POST,
+            &erase_uri,
+            Some(serde_json::json!({ "confirmation_token": confirmation.confirmation_token })),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    // El correo original queda libre para un alta nueva.
+    let response = context
+        .send(
+            http::Method::POST,
+            "/users",
+            Some(serde_json::json!({ "name": "Ada Lovelace", "email": "ada@example.com" })),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+}
+
+#[tokio::test]
+async fn erasing_a_user_redacts_their_earlier_outbox_events() {
+    let context = TestContext::new().await;
+    let mut created_users = Vec::new();
+    for (name, email) in [
+        ("Ada Lovelace", "ada@example.com"),
+        ("Grace Hopper", "grace@example.com"),
+    ] {
+        let response = context
+            .send(
+                http::Method::POST,
+                "/users",
+                Some(serde_json::json!({ "name": name, "email": email })),
+            )
+            .await;
+        created_users.push(body_json::<User>(response).await);
+    }
+    let ada = &created_users[0];
+    context
+        .send(
+            http::Method::PUT,
+            &format!("/users/{}", ada.id),
+            Some(serde_json::json!({ "email": "lovelace@example.com" })),
+        )
+        .await;
+
+    let erase_uri = format!("/users/{}/erase", ada.id);
+    let response = context.send(http::Method::POST, &erase_uri, None).await;
+    let confirmation: ErasureConfirmation = body_json(response).await;
+    let response = context
+        .send(
+            http::Method::POST,
+            &erase_uri,
+            Some(serde_json::json!({ "confirmation_token": confirmation.confirmation_token })),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // Ningún evento, pendiente o no, conserva sus datos; los de otros usuarios no cambian.
+    let payloads: Vec<String> =
+        sqlx::query_scalar("SELECT payload FROM outbox WHERE sent_at IS NULL ORDER BY seq")
+            .fetch_all(&context.pool)
+            .await
+            .unwrap();
+    assert_eq!(payloads.len(), 4);
+    for payload in &payloads {
+        assert!(!payload.contains("Ada Lovelace"), "{payload}");
+        assert!(!payload.contains("ada@example.com"), "{payload}");
+        assert!(!payload.contains("lovelace@example.com"), "{payload}");
+    }
+    let grace_events = payloads
+        .iter()
+        .filter(|payload| payload.contains("grace@example.com"))
+        .count();
+    assert_eq!(grace_events, 1);
+    let first: serde_json::Value = serde_json::from_str(&payloads[0]).unwrap();
+    assert_eq!(first["type"], "user_created");
+    assert_eq!(first["user"]["name"], "Usuario borrado");
+    assert_eq!(first["user"]["id"], ada.id.to_string());
+}
+
+#[tokio::test]
+async fn erasure_tokens_expire_and_unknown_users_are_not_found() {
+    let context = TestContext::new().await;
+    let user = UserFactory::new()
+        .with_name("Grace Hopper")
+        .with_email("grace@example.com")
+        .create(&context.pool)
+        .await;
+    let erase_uri = format!("/users/{}/erase", user.id);
+
+    let response = context.send(http::Method::POST, &erase_uri, None).await;
+    let confirmation: ErasureConfirmation = body_json(response).await;
+    sqlx::query("UPDATE user_erasure_requests SET expires_at = ?")
+        .bind(chrono::Utc::now() - chrono::Duration::minutes(1))
+        .execute(&context.pool)
+        .await
+        .unwrap();
+
+    let response = context
+        .send(
+            http::Method::POST,
+            &erase_uri,
+            Some(serde_json::json!({ "confirmation_token": confirmation.confirmation_token })),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = context
+        .send(
+            http::Method::POST,
+            &format!("/users/{}/erase", uuid::Uuid::new_v4()),
+            None,
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+async fn body_json<T: serde::de::DeserializeOwned>(response: http::Response<Body>) -> T {
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+struct TestContext {
+    app: Router,
+    pool: SqlitePool,
+}
+
+impl TestContext {
+    async fn new() -> Self {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+
+        let state = AppState::new(pool.clone(), AppConfig::default());
+        let app = routes::api_routes(&state).with_state(state);
+
+        Self { app, pool }
+    }
+
+    async fn send(
+        &self,
+        method: http::Method,
+        uri: &str,
+        payload: Option<serde_json::Value>,
+    ) -> http::Response<Body> {
+        let mut request = Request::builder().method(method).uri(uri);
+        let body = match payload {
+            Some(payload) => {
+                request = request.header(http::header::CONTENT_TYPE, "application/json");
+                Body::from(serde_json::to_vec(&payload).unwrap())
+            }
+            None => Body::empty(),
+        };
+
+        tower::ServiceExt::oneshot(self.app.clone(), request.body(body).unwrap())
+            .await
+            .unwrap()
+    }
+}
@@ -0,0 +1,182 @@
+use axum::http::StatusCode;
+use chrono::{TimeZone, Utc};
+
+use rust_web_demo::{
+    encryption::EmailEncryption,
+    models::user::{User, UserCount, UserStatus},
+    state::AppState,
+    testing::{body_json, factory::UserFactory, test_pool, TestContext},
+};
+
+/// Codifica la expresión para la URL; basta con los caracteres que usan estas pruebas.
+fn encode(filter: &str) -> String {
+    filter
+        .replace('%', "%25")
+        .replace(' ', "%20")
+        .replace('"', "%22")
+        .replace('+', "%2B")
+        .replace('(', "%28")
+        .replace(')', "%29")
+}
+
+async fn listed(context: &TestContext, query: &str, filter: &str) -> Vec<String> {
+    let response = context
+        .get(&format!("/users?{query}&filter={}", encode(filter)))
+        .await;
+    assert_eq!(response.status(), StatusCode::OK, "{filter}");
+    let users: Vec<User> = body_json(response).await;
+    users.into_iter().map(|user| user.name).collect()
+}
+
+async fn rejection_code(context: &TestContext, filter: &str) -> String {
+    let response = context
+        .get(&format!("/users?filter={}", encode(filter)))
+        .await;
+    assert_eq!(
+        response.status(),
+        StatusCode::UNPROCESSABLE_ENTITY,
+        "{filter}"
+    );
+    let body: serde_json::Value = body_json(response).await;
+    assert_eq!(body["errors"][0]["field"], "filter");
+    body["errors"][0]["code"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn filters_combine_comparisons_with_the_other_parameters() {
+    let context = TestContext::new().await;
+    let pool = &context.state.database_pool;
+    for (name, email, created_at, status) in [
+        (
+            "Ada Lovelace",
+            "ada@example.com",
+            (2023, 6, 1),
+            UserStatus::Active,
+        ),
+        (
+            "Ada Byron",
+            "byron@example.org",
+            (2024, 3, 1),
+            UserStatus::Suspended,
+        ),
+        (
+            "Grace Hopper",
+            "grace@example.com",
+            (2024, 5, 1),
+            UserStatus::Active,
+        ),
+        (
+            "Alan Turing",
+            "alan@example.org",
+            (2024, 7, 1),
+            UserStatus::Deactivated,
+        ),
+    ] {
+        let (year, month, day) = created_at;
+        UserFactory::new()
+            .with_name(name)
+            .with_email(email)
+            .with_created_at(Utc.with_ymd_and_hms(year, month, day, 0, 0, 0).unwrap())
+            .with_status(status)
+            .create(pool)
+            .await;
+    }
+
+    assert_eq!(
+        listed(&context, "", r#"name co "ada""#).await,
+        ["Ada Lovelace"]
+    );
+    assert_eq!(
+        listed(
+            &context,
+            "status=all",
+            r#"name co "ada" and created_at gt 2024-01-01"#
+        )
+        .await,
+        ["Ada Byron"]
+    );
+    assert_eq!(
+        listed(
+            &context,
+            "status=all",
+            r#"not (status eq "active") and email ew ".org""#
+        )
+        .await,
+        ["Ada Byron", "Alan Turing"]
+    );
+    assert_eq!(
+        listed(
+            &context,
+            "",
+            r#"name sw "GRACE" or EMAIL EQ "Ada@Example.com""#
+        )
+        .await,
+        ["Ada Lovelace", "Grace Hopper"]
+    );
+    assert_eq!(
+        listed(
+            &context,
+            "status=all",
+            "created_at ge 2024-05-01T00:00:00+02:00 and created_at lt 2024-07-01"
+        )
+        .await,
+        ["Grace Hopper"]
+    );
+    assert_eq!(
+        listed(&context, "status=all", "not suspended_until pr")
+            .await
+            .len(),
+        4
+    );
+    // Los comodines de `LIKE` del valor se buscan tal cual.
+    assert!(listed(&context, "status=all", r#"name co "%""#)
+        .await
+        .is_empty());
+
+    let filter = encode(r#"email ew ".org""#);
+    let response = context
+        .get(&format!("/users/count?status=all&filter={filter}"))
+        .await;
+    assert_eq!(response.headers()["x-total-count"], "2");
+    let count: UserCount = body_json(response).await;
+    assert_eq!(count.count, 2);
+}
+
+#[tokio::test]
+async fn invalid_filters_are_rejected_before_reaching_the_database() {
+    let context = TestContext::new().await;
+
+    for (filter, code) in [
+        (r#"tenant_id eq "default""#, "filter_field"),
+        (r#"name gt "a""#, "filter_operator"),
+        (r#"status co "act""#, "filter_operator"),
+        (r#"status eq "banned""#, "filter_value"),
+        ("created_at gt yesterday", "filter_value"),
+        (r#"name eq "ada" or"#, "filter_syntax"),
+        (r#"(name eq "ada""#, "filter_syntax"),
+        (r#"name eq "ada"; DROP TABLE users"#, "filter_syntax"),
+        (r#"name eq "ada"#, "filter_syntax"),
+        (r#"name is "ada""#, "filter_syntax"),
+    ] {
+        assert_eq!(rejection_code(&context, filter).await, code, "{filter}");
+    }
+
+    let too_many = vec![r#"name eq "a""#; 21].join(" or ");
+    assert_eq!(
+        rejection_code(&context, &too_many).await,
+        "filter_too_complex"
+    );
+    let too_long = format!(r#"name eq "{}""#, "a".repeat(1000));
+    assert_eq!(rejection_code(&context, &too_long).await, "filter_too_long");
+
+    // Con el cifrado activo el correo guardado no se puede comparar.
+    let encrypted = TestContext::from_state(
+        AppState::new(test_pool().await, Default::default())
+            .with_email_encryption(EmailEncryption::new([1; 32], &[], [9; 32])),
+    );
+    assert_eq!(
+        rejection_code(&encrypted, r#"email eq "ada@example.com""#).await,
+        "filter_field"
+    );
+    assert!(listed(&encrypted, "", r#"name co "ada""#).await.is_empty());
+}
@@ -1,12 +1,19 @@
 use axum::{
     body::Body,
     http::{self, Request, StatusCode},
-    routing::Router,
 };
-use http_body_util::BodyExt;
-use sqlx::sqlite::SqlitePoolOptions;
-
-use rust_web_demo::{models, routes};
+use std::sync::Arc;
+
+use rust_web_demo::{
+    cache::{Cache, CacheConfig, CacheInvalidator, MemoryStore},
+    clock::{Clock, MockClock},
+    config::{AppConfig, Environment},
+    ids::{self, IdFormat},
+    metrics, models,
+    scheduler::default_scheduler,
+    state::AppState,
+    testing::{body_bytes, factory::UserFactory, test_pool, TestContext},
+};
 
 #[tokio::test]
 async fn list_users_returns_empty_array_initially() {
@@ -337,15 +344,76 @@ async fn update_user_with_invalid_email_returns_validation_error() {
 
 #[tokio::test]
 async fn create_user_sets_created_at_timestamp() {
-    let context = TestContext::new().await;
-    let before_creation = chrono::Utc::now();
+    let created_at = "2024-03-01T09:30:00.250Z".parse().unwrap();
+    let clock = MockClock::new(created_at);
+    let state = AppState::new(test_pool().await, AppConfig::default());
+    let context = TestContext::from_state(state.with_clock(Arc::new(clock.clone())));
 
     let user = context.create_user("Test User", "test@example.com").await;
+    assert_eq!(user.created_at, created_at);
+
+    clock.advance(chrono::Duration::minutes(5));
+    let later = context.create_user("Later User", "later@example.com").await;
+    assert_eq!(later.created_at, created_at + chrono::Duration::minutes(5));
+
+    let response = context
+        .request(
+            Request::builder()
+                .uri(format!("/users/{}", user.id))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    let bytes = body_bytes(response).await;
+    let fetched: models::user::User = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(fetched.created_at, created_at);
+}
+
+#[tokio::test]
+async fn create_user_assigns_time_ordered_ids() {
+    let context = TestContext::new().await;
+
+    let first = context.create_user("First User", "first@example.com").await;
+    let second = context
+        .create_user("Second User", "second@example.com")
+        .await;
+
+    assert_eq!(first.id.get_version_num(), 7);
+    assert!(first.id < second.id);
+}
+
+#[tokio::test]
+async fn prefixed_ulid_ids_are_rendered_and_parsed_in_paths() {
+    let context = TestContext::with_config(AppConfig {
+        id_format: IdFormat::Ulid,
+        id_prefix: Some("usr".to_string()),
+        ..AppConfig::default()
+    })
+    .await;
+
+    let response = context
+        .post_json(
+            "/users",
+            serde_json::json!({ "name": "Ada Lovelace", "email": "ada@example.com" }),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let created: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    let public_id = created["id"].as_str().unwrap().to_string();
+    assert!(public_id.starts_with("usr_"));
+    assert_eq!(public_id.len(), "usr_".len() + 26);
+
+    let response = context.get(&format!("/users/{public_id}")).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let fetched: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(fetched["id"], public_id.as_str());
 
-    let after_creation = chrono::Utc::now();
+    let canonical = ids::parse_any(&public_id).unwrap();
+    let response = context.get(&format!("/users/{canonical}")).await;
+    assert_eq!(response.status(), StatusCode::OK);
 
-    assert!(user.created_at >= before_creation);
-    assert!(user.created_at <= after_creation);
+    let response = context.get("/users/usr_not-an-id").await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
 }
 
 #[tokio::test]
@@ -381,6 +449,79 @@ async fn root_endpoint_returns_welcome_message() {
     assert_eq!(body, "Bienvenido a la API en Rust 🚀");
 }
 
+#[tokio::test]
+async fn root_endpoint_negotiates_html_and_json() {
+    let context = TestContext::new().await;
+    context
+        .state
+        .maintenance
+        .enable(Some("<migración>".to_string()));
+
+    let response = context
+        .request(
+            Request::builder()
+                .uri("/")
+                .header(
+                    http::header::ACCEPT,
+                    "text/html,application/xhtml+xml,application/xml;q=0.9,*/*;q=0.8",
+                )
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers()[http::header::CONTENT_TYPE]
+        .to_str()
+        .unwrap()
+        .starts_with("text/html"));
+    assert_eq!(response.headers()[http::header::VARY], "accept");
+    let page = String::from_utf8(body_bytes(response).await).unwrap();
+    assert!(page.contains(env!("CARGO_PKG_VERSION")));
+    assert!(page.contains("&lt;migración&gt;"));
+    assert!(page.contains(r#"href="/health""#));
+
+    let response = context
+        .request(
+            Request::builder()
+                .uri("/")
+                .header(http::header::ACCEPT, "application/json")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    let info: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(info["version"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(info["status"], "maintenance");
+    assert_eq!(info["maintenance"]["reason"], "<migración>");
+    assert_eq!(info["links"]["docs"], "/public/index.html");
+}
+
+#[tokio::test]
+async fn version_reports_the_running_profile() {
+    let config = AppConfig::defaults_for("prod".parse().unwrap());
+    assert_eq!(config.environment, Environment::Production);
+    let context = TestContext::with_config(config).await;
+
+    let response = context
+        .request(
+            Request::builder()
+                .uri("/version")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let info: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(info["version"], env!("CARGO_PKG_VERSION"));
+    assert_eq!(info["environment"], "production");
+
+    // El perfil de pruebas usa por defecto una base en memoria de una sola conexión.
+    let test_defaults = AppConfig::defaults_for(Environment::Test);
+    assert_eq!(test_defaults.database_url, "sqlite::memory:");
+    assert_eq!(test_defaults.database_max_connections, 1);
+    assert!("staging".parse::<Environment>().is_err());
+}
+
 #[tokio::test]
 async fn create_user_with_whitespace_only_name_returns_validation_error() {
     let context = TestContext::new().await;
@@ -476,6 +617,31 @@ async fn list_users_returns_users_in_creation_order() {
     assert_eq!(users[1].id, user2.id);
 }
 
+#[tokio::test]
+async fn list_users_orders_by_created_at_then_id_regardless_of_insertion_order() {
+    let context = TestContext::new().await;
+    let pool = &context.state.database_pool;
+    let earlier = "2026-01-01T00:00:00Z".parse().unwrap();
+    let later = "2026-02-01T00:00:00Z".parse().unwrap();
+
+    let newest = UserFactory::new().with_created_at(later).create(pool).await;
+    let tied_high = UserFactory::new()
+        .with_id(uuid::Uuid::from_u128(2))
+        .with_created_at(earlier)
+        .create(pool)
+        .await;
+    let tied_low = UserFactory::new()
+        .with_id(uuid::Uuid::from_u128(1))
+        .with_created_at(earlier)
+        .create(pool)
+        .await;
+
+    let users: Vec<models::user::User> =
+        serde_json::from_slice(&body_bytes(context.get("/users").await).await).unwrap();
+    let ids: Vec<_> = users.iter().map(|user| user.id).collect();
+    assert_eq!(ids, [tied_low.id, tied_high.id, newest.id]);
+}
+
 #[tokio::test]
 async fn create_user_with_maximum_valid_name_length_succeeds() {
     let context = TestContext::new().await;
@@ -515,6 +681,20 @@ async fn create_user_with_various_valid_email_formats_succeeds() {
     }
 }
 
+#[tokio::test]
+async fn create_user_stores_internationalized_domains_in_punycode() {
+    let context = TestContext::new().await;
+    let payload = serde_json::json!({
+        "name": "Test User",
+        "email": "  User@Bücher.Example "
+    });
+
+    let response = context.post_json("/users", payload).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let user: models::user::User = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(user.email, "user@xn--bcher-kva.example");
+}
+
 #[tokio::test]
 async fn create_user_with_invalid_email_formats_returns_validation_error() {
     let context = TestContext::new().await;
@@ -526,6 +706,9 @@ async fn create_user_with_invalid_email_formats_returns_validation_error() {
         "user.example.com",
         "user@example",
         "user@@example.com",
+        "user..name@example.com",
+        "us er@example.com",
+        "user@-example.com",
     ];
 
     for email in invalid_emails {
@@ -544,88 +727,1293 @@ async fn create_user_with_invalid_email_formats_returns_validation_error() {
     }
 }
 
-struct TestContext {
-    app: Router,
+#[tokio::test]
+async fn import_users_from_json_reports_each_row() {
+    let context = TestContext::new().await;
+    context.create_user("Existing User", "existing@example.com").await;
+
+    let payload = serde_json::json!([
+        { "name": "Ada Lovelace", "email": "ada@example.com" },
+        { "name": "Duplicate", "email": "EXISTING@example.com" },
+        { "name": "", "email": "invalid-email" },
+        { "name": "Missing Email" }
+    ]);
+
+    let response = context.post_json("/users/import", payload).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let bytes = body_bytes(response).await;
+    let report: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(report["dry_run"], false);
+    assert_eq!(report["created"], 1);
+    assert_eq!(report["skipped_duplicates"], 1);
+    assert_eq!(report["invalid"], 2);
+    assert_eq!(report["rows"][0]["status"], "created");
+    assert_eq!(report["rows"][1]["status"], "skipped_duplicate");
+    assert_eq!(report["rows"][2]["status"], "invalid");
+    assert_eq!(report["rows"][2]["errors"].as_array().unwrap().len(), 2);
+    assert_eq!(report["rows"][3]["row"], 4);
+
+    let bytes = body_bytes(context.get("/users").await).await;
+    let users: Vec<models::user::User> = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(users.len(), 2);
 }
 
-impl TestContext {
-    async fn new() -> Self {
-        let pool = SqlitePoolOptions::new()
-            .max_connections(1)
-            .connect("sqlite::memory:")
-            .await
-            .unwrap();
+#[tokio::test]
+async fn import_users_from_csv_in_dry_run_does_not_persist() {
+    let context = TestContext::new().await;
+    let csv = "name,email\nGrace Hopper,grace@example.com\nAlan Turing,alan@example.com\n";
 
-        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+    let response = context
+        .request(
+            Request::builder()
+                .method(http::Method::POST)
+                .uri("/users/import?dry_run=true")
+                .header(http::header::CONTENT_TYPE, "text/csv")
+                .body(Body::from(csv))
+                .unwrap(),
+        )
+        .await;
 
-        let app = routes::user_routes()
-            .merge(routes::health_routes())
-            .merge(routes::root_route())
-            .with_state(pool);
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = body_bytes(response).await;
+    let report: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(report["dry_run"], true);
+    assert_eq!(report["created"], 2);
 
-        Self { app }
-    }
+    let bytes = body_bytes(context.get("/users").await).await;
+    let users: Vec<models::user::User> = serde_json::from_slice(&bytes).unwrap();
+    assert!(users.is_empty());
+}
 
-    async fn request(&self, request: Request<Body>) -> http::Response<Body> {
-        let app = self.app.clone();
-        tower::ServiceExt::oneshot(app, request).await.unwrap()
-    }
+#[tokio::test]
+async fn batch_create_users_returns_multi_status_per_item() {
+    let context = TestContext::new().await;
+    context.create_user("Existing User", "existing@example.com").await;
 
-    async fn create_user(&self, name: &str, email: &str) -> models::user::User {
-        let payload = serde_json::json!({ "name": name, "email": email });
-
-        let response = self
-            .request(
-                Request::builder()
-                    .method(http::Method::POST)
-                    .uri("/users")
-                    .header(http::header::CONTENT_TYPE, "application/json")
-                    .body(Body::from(serde_json::to_vec(&payload).unwrap()))
-                    .unwrap(),
-            )
+    let payload = serde_json::json!([
+        { "name": "Ada Lovelace", "email": "ada@example.com" },
+        { "name": "Test User", "email": "invalid-email" },
+        { "name": "Duplicate", "email": "existing@example.com" },
+        { "name": "Ada Again", "email": "ADA@example.com" }
+    ]);
+
+    let response = context.post_json("/users/batch", payload).await;
+    assert_eq!(response.status(), StatusCode::MULTI_STATUS);
+
+    let bytes = body_bytes(response).await;
+    let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    let statuses: Vec<u64> = body["results"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|item| item["status"].as_u64().unwrap())
+        .collect();
+    assert_eq!(statuses, vec![201, 422, 409, 409]);
+    assert_eq!(body["results"][0]["user"]["email"], "ada@example.com");
+
+    let bytes = body_bytes(context.get("/users").await).await;
+    let users: Vec<models::user::User> = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(users.len(), 2);
+}
+
+#[tokio::test]
+async fn suspend_and_activate_user() {
+    let context = TestContext::new().await;
+    let user = context.create_user("Test User", "test@example.com").await;
+    let until = chrono::Utc::now() + chrono::Duration::days(7);
+
+    let payload = serde_json::json!({ "reason": "  Spam reiterado  ", "until": until });
+    let response = context
+        .post_json(&format!("/users/{}/suspend", user.id), payload)
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let bytes = body_bytes(response).await;
+    let suspended: models::user::User = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(suspended.status, models::user::UserStatus::Suspended);
+    assert_eq!(suspended.suspension_reason.as_deref(), Some("Spam reiterado"));
+    assert_eq!(suspended.suspended_until, Some(until));
+
+    let response = context
+        .post_json(&format!("/users/{}/activate", user.id), serde_json::json!({}))
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let bytes = body_bytes(response).await;
+    let activated: models::user::User = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(activated.status, models::user::UserStatus::Active);
+    assert!(activated.suspended_until.is_none());
+    assert!(activated.suspension_reason.is_none());
+}
+
+#[tokio::test]
+async fn scheduled_task_lifts_suspensions_when_the_state_clock_reaches_them() {
+    let clock = MockClock::new(chrono::Utc::now());
+    let state =
+        AppState::new(test_pool().await, AppConfig::default()).with_clock(Arc::new(clock.clone()));
+    let context = TestContext::from_state(state.clone());
+    let scheduler = default_scheduler(state).unwrap();
+    let user = context.create_user("Test User", "test@example.com").await;
+
+    let until = clock.now() + chrono::Duration::days(7);
+    let payload = serde_json::json!({ "reason": "Spam", "until": until });
+    let response = context
+        .post_json(&format!("/users/{}/suspend", user.id), payload)
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let status = || async {
+        let response = context.get(&format!("/users/{}", user.id)).await;
+        let user: models::user::User = serde_json::from_slice(&body_bytes(response).await).unwrap();
+        user.status
+    };
+
+    // La primera pasada solo registra las tareas.
+    scheduler.run_due(clock.now()).await.unwrap();
+    clock.advance(chrono::Duration::hours(1));
+    scheduler.run_due(clock.now()).await.unwrap();
+    assert_eq!(status().await, models::user::UserStatus::Suspended);
+
+    // Basta con que avance el reloj del estado, aunque el del sistema siga antes de `until`.
+    clock.advance(chrono::Duration::days(7));
+    scheduler.run_due(clock.now()).await.unwrap();
+    assert_eq!(status().await, models::user::UserStatus::Active);
+}
+
+#[tokio::test]
+async fn suggest_matches_name_or_email_prefixes_case_insensitively() {
+    let context = TestContext::new().await;
+    let pool = &context.state.database_pool;
+    let suggest = |query: &'static str| {
+        let context = &context;
+        async move {
+            let response = context.get(&format!("/users/suggest?{query}")).await;
+            assert_eq!(response.status(), StatusCode::OK);
+            let suggestions: Vec<serde_json::Value> =
+                serde_json::from_slice(&body_bytes(response).await).unwrap();
+            suggestions
+                .iter()
+                .map(|suggestion| suggestion["name"].as_str().unwrap().to_string())
+                .collect::<Vec<_>>()
+        }
+    };
+
+    for (name, email) in [
+        ("Ada Lovelace", "lovelace@example.com"),
+        ("adaline Byron", "byron@example.com"),
+        ("Grace Hopper", "ada.hopper@example.com"),
+        ("Alan Turing", "alan@example.com"),
+        ("100% Ada", "percent@example.com"),
+    ] {
+        UserFactory::new()
+            .with_name(name)
+            .with_email(email)
+            .create(pool)
             .await;
+    }
 
-        assert_eq!(response.status(), StatusCode::CREATED);
-        let bytes = body_bytes(response).await;
-        serde_json::from_slice(&bytes).unwrap()
+    assert_eq!(
+        suggest("q=ADA").await,
+        ["Ada Lovelace", "adaline Byron", "Grace Hopper"]
+    );
+    assert_eq!(
+        suggest("q=ada&limit=2").await,
+        ["Ada Lovelace", "adaline Byron"]
+    );
+    assert_eq!(suggest("q=love").await, ["Ada Lovelace"]);
+    assert_eq!(suggest("q=100%25").await, ["100% Ada"]);
+    assert_eq!(suggest("q=_").await, Vec::<String>::new());
+
+    let response = context.get("/users/suggest?q=ada").await;
+    let suggestions: serde_json::Value =
+        serde_json::from_slice(&body_bytes(response).await).unwrap();
+    let keys: Vec<_> = suggestions[0]
+        .as_object()
+        .unwrap()
+        .keys()
+        .cloned()
+        .collect();
+    assert_eq!(keys, ["email", "id", "name"]);
+
+    for query in ["q=%20", "limit=5", "q=ada&limit=0", "q=ada&limit=51"] {
+        let response = context.get(&format!("/users/suggest?{query}")).await;
+        assert_eq!(
+            response.status(),
+            StatusCode::UNPROCESSABLE_ENTITY,
+            "{query}"
+        );
     }
+}
 
-    async fn post_json(&self, uri: &str, payload: serde_json::Value) -> http::Response<Body> {
-        self.request(
-            Request::builder()
-                .method(http::Method::POST)
-                .uri(uri)
-                .header(http::header::CONTENT_TYPE, "application/json")
-                .body(Body::from(serde_json::to_vec(&payload).unwrap()))
-                .unwrap(),
+#[tokio::test]
+async fn listing_excludes_suspended_and_deactivated_users_by_default() {
+    let context = TestContext::new().await;
+    let active = context.create_user("Ada Lovelace", "ada@example.com").await;
+    let suspended = context.create_user("Alan Turing", "alan@example.com").await;
+    let deactivated = context.create_user("Grace Hopper", "grace@example.com").await;
+
+    let until = chrono::Utc::now() + chrono::Duration::days(7);
+    context
+        .post_json(
+            &format!("/users/{}/suspend", suspended.id),
+            serde_json::json!({ "reason": "Spam", "until": until }),
         )
-        .await
-    }
+        .await;
+    let response = context
+        .post_json(
+            &format!("/users/{}/deactivate", deactivated.id),
+            serde_json::json!({}),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
 
-    async fn put_json(&self, uri: &str, payload: serde_json::Value) -> http::Response<Body> {
-        self.request(
-            Request::builder()
-                .method(http::Method::PUT)
-                .uri(uri)
-                .header(http::header::CONTENT_TYPE, "application/json")
-                .body(Body::from(serde_json::to_vec(&payload).unwrap()))
-                .unwrap(),
+    let list_ids = |bytes: Vec<u8>| -> Vec<uuid::Uuid> {
+        let users: Vec<models::user::User> = serde_json::from_slice(&bytes).unwrap();
+        users.into_iter().map(|user| user.id).collect()
+    };
+
+    let bytes = body_bytes(context.get("/users").await).await;
+    assert_eq!(list_ids(bytes), vec![active.id]);
+
+    let bytes = body_bytes(context.get("/users?status=suspended").await).await;
+    assert_eq!(list_ids(bytes), vec![suspended.id]);
+
+    let bytes = body_bytes(context.get("/users?status=deactivated").await).await;
+    assert_eq!(list_ids(bytes), vec![deactivated.id]);
+
+    let bytes = body_bytes(context.get("/users?status=all").await).await;
+    assert_eq!(list_ids(bytes).len(), 3);
+
+    let response = context.get("/users?status=banned").await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+    context
+        .post_json(
+            &format!("/users/{}/activate", deactivated.id),
+            serde_json::json!({}),
         )
-        .await
-    }
+        .await;
+    let bytes = body_bytes(context.get("/users").await).await;
+    assert_eq!(list_ids(bytes).len(), 2);
+}
 
-    async fn get(&self, uri: &str) -> http::Response<Body> {
-        self.request(Request::builder().uri(uri).body(Body::empty()).unwrap())
-            .await
-    }
+#[tokio::test]
+async fn suspend_user_with_past_date_returns_validation_error() {
+    let context = TestContext::new().await;
+    let user = context.create_user("Test User", "test@example.com").await;
+    let payload = serde_json::json!({
+        "reason": "Motivo",
+        "until": chrono::Utc::now() - chrono::Duration::hours(1)
+    });
+
+    let response = context
+        .post_json(&format!("/users/{}/suspend", user.id), payload)
+        .await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn suspend_nonexistent_user_returns_not_found() {
+    let context = TestContext::new().await;
+    let payload = serde_json::json!({
+        "reason": "Motivo",
+        "until": chrono::Utc::now() + chrono::Duration::days(1)
+    });
+
+    let response = context
+        .post_json(&format!("/users/{}/suspend", uuid::Uuid::new_v4()), payload)
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn batch_delete_users_reports_missing_ids() {
+    let context = TestContext::new().await;
+    let first = context.create_user("First User", "first@example.com").await;
+    let second = context
+        .create_user("Second User", "second@example.com")
+        .await;
+    let missing = uuid::Uuid::new_v4();
+
+    let payload = serde_json::json!({ "ids": [first.id, missing, second.id] });
+    let response = context.post_json("/users/batch-delete", payload).await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let bytes = body_bytes(response).await;
+    let body: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(
+        body["deleted"],
+        serde_json::json!([first.id, second.id])
+    );
+    assert_eq!(body["not_found"], serde_json::json!([missing]));
+
+    let bytes = body_bytes(context.get("/users").await).await;
+    let users: Vec<models::user::User> = serde_json::from_slice(&bytes).unwrap();
+    assert!(users.is_empty());
+}
+
+#[tokio::test]
+async fn metrics_endpoint_counts_requests_and_errors() {
+    let context = TestContext::new().await;
+    context.get("/health").await;
+    context.get(&format!("/users/{}", uuid::Uuid::new_v4())).await;
+
+    let response = context.get("/metrics").await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    let bytes = body_bytes(response).await;
+    let body = String::from_utf8(bytes).unwrap();
+    assert!(body.contains("http_requests_total 2"));
+    assert!(body.contains("http_request_errors_total 0"));
+    assert!(body.contains("http_request_duration_seconds_bucket{le=\"+Inf\"} 2"));
+}
+
+#[tokio::test]
+async fn admin_metrics_dashboard_renders_sparklines() {
+    let context = TestContext::new().await;
+    context.get("/health").await;
+
+    let response = context.get("/admin/metrics").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers()[http::header::CONTENT_TYPE]
+        .to_str()
+        .unwrap()
+        .starts_with("text/html"));
+
+    let bytes = body_bytes(response).await;
+    let body = String::from_utf8(bytes).unwrap();
+    assert!(body.contains("Peticiones / minuto"));
+    assert_eq!(body.matches("<polyline").count(), 3);
+}
+
+#[tokio::test]
+async fn route_histograms_use_the_template_not_the_path() {
+    let context = TestContext::new().await;
+    let user = context.create_user("Ada Lovelace", "ada@example.com").await;
+    context.get(&format!("/users/{}", user.id)).await;
+    context.get(&format!("/users/{}", uuid::Uuid::new_v4())).await;
+    context.get("/no-existe").await;
+
+    let bytes = body_bytes(context.get("/metrics").await).await;
+    let body = String::from_utf8(bytes).unwrap();
+    assert!(body.contains(
+        "http_route_request_duration_seconds_count{route=\"/users/:id\",method=\"GET\",\
+         status=\"2xx\"} 1"
+    ));
+    assert!(body.contains(
+        "http_route_request_duration_seconds_count{route=\"/users/:id\",method=\"GET\",\
+         status=\"4xx\"} 1"
+    ));
+    assert!(body.contains(
+        "http_route_request_duration_seconds_count{route=\"unmatched\",method=\"GET\",\
+         status=\"4xx\"} 1"
+    ));
+    assert!(!body.contains(&user.id.to_string()));
+}
+
+#[tokio::test]
+async fn admin_status_summarizes_the_process() {
+    let context = TestContext::new().await;
+    context.get("/health").await;
+
+    let bytes = body_bytes(context.get("/admin/status").await).await;
+    let status: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(status["requests"]["total"], 1);
+    assert_eq!(status["requests"]["errors"], 0);
+    // La propia consulta está en curso mientras se genera la respuesta.
+    assert_eq!(status["requests"]["in_flight"], 1);
+    assert!(status["last_error_at"].is_null());
+    assert_eq!(status["database_pool"]["max_connections"], 1);
+
+    context
+        .state
+        .metrics
+        .record(500, std::time::Duration::from_millis(5));
+    let bytes = body_bytes(context.get("/admin/status").await).await;
+    let status: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(status["requests"]["errors"], 1);
+    assert!(status["last_error_at"].is_string());
+    assert_eq!(context.state.metrics.snapshot().in_flight, 0);
 }
 
-async fn body_bytes(response: http::Response<Body>) -> Vec<u8> {
-    response
-        .into_body()
-        .collect()
+#[tokio::test]
+async fn shutdown_progress_waits_for_in_flight_requests() {
+    let context = TestContext::new().await;
+    let metrics = context.state.metrics.clone();
+    let pending_request = metrics.track_in_flight();
+
+    let progress = tokio::spawn(metrics::report_shutdown_progress(
+        metrics.clone(),
+        std::time::Duration::from_millis(10),
+    ));
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    assert!(!progress.is_finished());
+
+    let bytes = body_bytes(context.get("/admin/status").await).await;
+    let status: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert!(status["shutdown_started_at"].is_string());
+    assert_eq!(status["requests"]["in_flight"], 2);
+
+    drop(pending_request);
+    tokio::time::timeout(std::time::Duration::from_secs(1), progress)
         .await
         .unwrap()
-        .to_bytes()
-        .to_vec()
+        .unwrap();
+}
+
+#[tokio::test]
+async fn export_users_as_csv_includes_header_and_rows() {
+    let context = TestContext::new().await;
+    context.create_user("Ada Lovelace", "ada@example.com").await;
+
+    let response = context.get("/users/export?format=csv").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[http::header::CONTENT_DISPOSITION],
+        "attachment; filename=\"users.csv\""
+    );
+
+    let bytes = body_bytes(response).await;
+    let body = String::from_utf8(bytes).unwrap();
+    let mut lines = body.lines();
+    assert!(lines.next().unwrap().starts_with("id,name,email,created_at"));
+    assert!(lines.next().unwrap().contains("ada@example.com"));
+}
+
+#[tokio::test]
+async fn export_users_as_parquet_produces_valid_file() {
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+
+    let context = TestContext::new().await;
+    context.create_user("Ada Lovelace", "ada@example.com").await;
+    context.create_user("Alan Turing", "alan@example.com").await;
+
+    let response = context.get("/users/export?format=parquet").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers()[http::header::CONTENT_TYPE],
+        "application/vnd.apache.parquet"
+    );
+
+    let bytes = body_bytes(response).await;
+    assert!(bytes.starts_with(b"PAR1"));
+    assert!(bytes.ends_with(b"PAR1"));
+
+    let reader = SerializedFileReader::new(axum::body::Bytes::from(bytes)).unwrap();
+    assert_eq!(reader.metadata().file_metadata().num_rows(), 2);
+    assert_eq!(reader.metadata().file_metadata().schema().get_fields().len(), 8);
+}
+
+#[tokio::test]
+async fn put_on_unknown_id_creates_user_when_upsert_enabled() {
+    let context = TestContext::with_config(AppConfig {
+        allow_put_upsert: true,
+        ..AppConfig::default()
+    })
+    .await;
+    let client_id = uuid::Uuid::new_v4();
+    let payload = serde_json::json!({ "name": "Sync Client", "email": "sync@example.com" });
+
+    let response = context
+        .put_json(&format!("/users/{}", client_id), payload)
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let bytes = body_bytes(response).await;
+    let created: models::user::User = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(created.id, client_id);
+
+    let payload = serde_json::json!({ "name": "Sync Client v2" });
+    let response = context
+        .put_json(&format!("/users/{}", client_id), payload)
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = body_bytes(response).await;
+    let updated: models::user::User = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(updated.name, "Sync Client v2");
+    assert_eq!(updated.email, "sync@example.com");
+}
+
+#[tokio::test]
+async fn put_upsert_requires_complete_payload() {
+    let context = TestContext::with_config(AppConfig {
+        allow_put_upsert: true,
+        ..AppConfig::default()
+    })
+    .await;
+    let payload = serde_json::json!({ "name": "Only Name" });
+
+    let response = context
+        .put_json(&format!("/users/{}", uuid::Uuid::new_v4()), payload)
+        .await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn user_lifecycle_publishes_domain_events() {
+    let context = TestContext::new().await;
+    let mut receiver = context.state.events.subscribe();
+
+    let user = context.create_user("Ada Lovelace", "ada@example.com").await;
+    context
+        .put_json(
+            &format!("/users/{}", user.id),
+            serde_json::json!({ "name": "Ada King" }),
+        )
+        .await;
+    context
+        .request(
+            Request::builder()
+                .method(http::Method::DELETE)
+                .uri(format!("/users/{}", user.id))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+
+    let mut names = Vec::new();
+    for _ in 0..3 {
+        let envelope = receiver.recv().await.unwrap();
+        assert_eq!(envelope.event.user_id(), user.id);
+        names.push(envelope.event.name());
+    }
+    assert_eq!(names, vec!["user.created", "user.updated", "user.deleted"]);
+}
+
+#[tokio::test]
+async fn cached_user_reads_hit_cache_and_are_invalidated_by_writes() {
+    let context = with_memory_cache().await;
+    let user = context.create_user("Ada Lovelace", "ada@example.com").await;
+    let uri = format!("/users/{}", user.id);
+
+    assert_eq!(context.get(&uri).await.status(), StatusCode::OK);
+    assert_eq!(context.get(&uri).await.status(), StatusCode::OK);
+    let snapshot = context.state.metrics.snapshot();
+    assert_eq!(snapshot.cache_hits, 1);
+    assert_eq!(snapshot.cache_misses, 1);
+
+    context
+        .put_json(&uri, serde_json::json!({ "name": "Ada King" }))
+        .await;
+
+    // La invalidación ocurre en el propio handler, sin esperar al evento.
+    let bytes = body_bytes(context.get(&uri).await).await;
+    let fetched: models::user::User = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(fetched.name, "Ada King");
+
+    let bytes = body_bytes(context.get("/metrics").await).await;
+    assert!(String::from_utf8_lossy(&bytes).contains("cache_hits_total"));
+}
+
+#[tokio::test]
+async fn uploaded_avatar_is_served_from_stable_url() {
+    let context = TestContext::new().await;
+    let user = context.create_user("Ada Lovelace", "ada@example.com").await;
+    let uri = format!("/users/{}/avatar", user.id);
+
+    let response = put_avatar(&context, &uri, "image/png", PNG_AVATAR).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let bytes = body_bytes(response).await;
+    let updated: models::user::User = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(updated.avatar_url.as_deref(), Some(uri.as_str()));
+
+    let bytes = body_bytes(context.get(&format!("/users/{}", user.id)).await).await;
+    let fetched: models::user::User = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(fetched.avatar_url.as_deref(), Some(uri.as_str()));
+
+    let response = context.get(&uri).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()[http::header::CONTENT_TYPE], "image/png");
+    let etag = response.headers()[http::header::ETAG].clone();
+    assert_eq!(body_bytes(response).await, PNG_AVATAR);
+
+    let response = context
+        .request(
+            Request::builder()
+                .uri(&uri)
+                .header(http::header::IF_NONE_MATCH, etag)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+}
+
+#[tokio::test]
+async fn avatar_upload_rejects_invalid_files() {
+    let context = TestContext::new().await;
+    let user = context.create_user("Ada Lovelace", "ada@example.com").await;
+    let uri = format!("/users/{}/avatar", user.id);
+
+    let response = put_avatar(&context, &uri, "text/plain", b"hola").await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = put_avatar(&context, &uri, "image/png", JPEG_AVATAR).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let mut oversized = PNG_AVATAR.to_vec();
+    oversized.resize(models::avatar::MAX_AVATAR_BYTES + 1, 0);
+    let response = put_avatar(&context, &uri, "image/png", &oversized).await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let missing = format!("/users/{}/avatar", uuid::Uuid::new_v4());
+    let response = put_avatar(&context, &missing, "image/png", PNG_AVATAR).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    assert_eq!(context.get(&uri).await.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn replaced_and_deleted_avatars_are_removed_from_storage() {
+    let context = TestContext::new().await;
+    let user = context.create_user("Ada Lovelace", "ada@example.com").await;
+    let uri = format!("/users/{}/avatar", user.id);
+    let png_key = format!("avatars/{}.png", user.id);
+    let jpeg_key = format!("avatars/{}.jpg", user.id);
+
+    put_avatar(&context, &uri, "image/png", PNG_AVATAR).await;
+    assert!(context.state.storage.get(&png_key).await.unwrap().is_some());
+
+    let response = put_avatar(&context, &uri, "image/jpeg", JPEG_AVATAR).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(context.state.storage.get(&png_key).await.unwrap().is_none());
+    assert_eq!(
+        context.get(&uri).await.headers()[http::header::CONTENT_TYPE],
+        "image/jpeg"
+    );
+
+    let response = context
+        .request(
+            Request::builder()
+                .method(http::Method::DELETE)
+                .uri(format!("/users/{}", user.id))
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert!(context.state.storage.get(&jpeg_key).await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn preferences_are_merged_on_update() {
+    let context = TestContext::new().await;
+    let user = context.create_user("Ada Lovelace", "ada@example.com").await;
+    let uri = format!("/users/{}/preferences", user.id);
+
+    let bytes = body_bytes(context.get(&uri).await).await;
+    let defaults: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(
+        defaults,
+        serde_json::json!({
+            "theme": "system",
+            "language": "es",
+            "notifications": { "email": true, "digest": "weekly" }
+        })
+    );
+
+    let response = context
+        .put_json(&uri, serde_json::json!({ "theme": "dark" }))
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    context
+        .put_json(
+            &uri,
+            serde_json::json!({ "language": "EN", "notifications": { "digest": "never" } }),
+        )
+        .await;
+
+    let bytes = body_bytes(context.get(&uri).await).await;
+    let merged: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(
+        merged,
+        serde_json::json!({
+            "theme": "dark",
+            "language": "en",
+            "notifications": { "email": true, "digest": "never" }
+        })
+    );
+
+    let response = context
+        .put_json(&uri, serde_json::json!({ "language": "español" }))
+        .await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = context
+        .put_json(&uri, serde_json::json!({ "font_size": 14 }))
+        .await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = context
+        .get(&format!("/users/{}/preferences", uuid::Uuid::new_v4()))
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn users_can_be_tagged_and_filtered_by_tag() {
+    let context = TestContext::new().await;
+    let tester = context.create_user("Ada Lovelace", "ada@example.com").await;
+    let other = context.create_user("Alan Turing", "alan@example.com").await;
+
+    let uri = format!("/users/{}/tags/Beta-Tester", tester.id);
+    let response = context.put_json(&uri, serde_json::json!({})).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let tags: Vec<models::tag::Tag> =
+        serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(tags.len(), 1);
+    assert_eq!(tags[0].name, "beta-tester");
+
+    // Asignarla de nuevo no la duplica.
+    let response = context.put_json(&uri, serde_json::json!({})).await;
+    let tags: Vec<models::tag::Tag> =
+        serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(tags.len(), 1);
+
+    let bytes = body_bytes(context.get("/users?tag=beta-tester").await).await;
+    let users: Vec<models::user::User> = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(
+        users.into_iter().map(|user| user.id).collect::<Vec<_>>(),
+        vec![tester.id]
+    );
+
+    let bytes = body_bytes(context.get("/users").await).await;
+    let users: Vec<models::user::User> = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(users.len(), 2);
+    assert!(users.iter().any(|user| user.id == other.id));
+
+    let response = context.get("/users?tag=beta%20tester").await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = context
+        .put_json(
+            &format!("/users/{}/tags/beta-tester", uuid::Uuid::new_v4()),
+            serde_json::json!({}),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let detach = || {
+        Request::builder()
+            .method(http::Method::DELETE)
+            .uri(format!("/users/{}/tags/beta-tester", tester.id))
+            .body(Body::empty())
+            .unwrap()
+    };
+    assert_eq!(
+        context.request(detach()).await.status(),
+        StatusCode::NO_CONTENT
+    );
+    assert_eq!(
+        context.request(detach()).await.status(),
+        StatusCode::NOT_FOUND
+    );
+
+    let bytes = body_bytes(context.get("/users?tag=beta-tester").await).await;
+    let users: Vec<models::user::User> = serde_json::from_slice(&bytes).unwrap();
+    assert!(users.is_empty());
+
+    // La etiqueta sigue en el catálogo aunque ya no la tenga nadie.
+    let bytes = body_bytes(context.get("/tags").await).await;
+    let tags: Vec<models::tag::Tag> = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(tags.len(), 1);
+}
+
+#[tokio::test]
+async fn tags_are_isolated_per_tenant() {
+    let context = TestContext::new().await;
+    sqlx::query("INSERT INTO tenants (id, name, created_at) VALUES ('acme', 'Acme', ?)")
+        .bind(chrono::Utc::now())
+        .execute(&context.state.database_pool)
+        .await
+        .unwrap();
+    let ada = context.create_user("Ada Lovelace", "ada@example.com").await;
+    let alan = UserFactory::new()
+        .with_name("Alan Turing")
+        .with_email("alan@acme.com")
+        .with_tenant("acme")
+        .create(&context.state.database_pool)
+        .await;
+
+    let as_acme = |method: http::Method, uri: &str| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("x-tenant-id", "acme")
+            .body(Body::empty())
+            .unwrap()
+    };
+    let response = context
+        .put_json(
+            &format!("/users/{}/tags/beta-tester", ada.id),
+            serde_json::json!({}),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+
+    // El catálogo de otro inquilino no incluye la etiqueta y no puede borrarla.
+    let response = context.request(as_acme(http::Method::GET, "/tags")).await;
+    let tags: Vec<models::tag::Tag> =
+        serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert!(tags.is_empty());
+    let response = context
+        .request(as_acme(http::Method::DELETE, "/tags/beta-tester"))
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // El mismo nombre en otro inquilino es otra etiqueta, y borrarla no toca la primera.
+    let uri = format!("/users/{}/tags/beta-tester", alan.id);
+    let response = context.request(as_acme(http::Method::PUT, &uri)).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let acme_tags: Vec<models::tag::Tag> =
+        serde_json::from_slice(&body_bytes(response).await).unwrap();
+    let response = context
+        .request(as_acme(http::Method::DELETE, "/tags/beta-tester"))
+        .await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+
+    let bytes = body_bytes(context.get("/tags").await).await;
+    let tags: Vec<models::tag::Tag> = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(tags.len(), 1);
+    assert_ne!(tags[0].id, acme_tags[0].id);
+    let bytes = body_bytes(context.get("/users?tag=beta-tester").await).await;
+    let users: Vec<models::user::User> = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(
+        users.into_iter().map(|user| user.id).collect::<Vec<_>>(),
+        vec![ada.id]
+    );
+}
+
+#[tokio::test]
+async fn users_are_isolated_per_tenant() {
+    let context = TestContext::with_config(AppConfig {
+        tenant_base_domain: Some("example.com".to_string()),
+        ..AppConfig::default()
+    })
+    .await;
+    sqlx::query("INSERT INTO tenants (id, name, created_at) VALUES ('acme', 'Acme', ?)")
+        .bind(chrono::Utc::now())
+        .execute(&context.state.database_pool)
+        .await
+        .unwrap();
+
+    let default_user = context.create_user("Ada Lovelace", "ada@example.com").await;
+    assert_eq!(default_user.tenant_id, "default");
+
+    let as_acme = |method: http::Method, uri: &str, body: Body| {
+        Request::builder()
+            .method(method)
+            .uri(uri)
+            .header("x-tenant-id", "acme")
+            .header(http::header::CONTENT_TYPE, "application/json")
+            .body(body)
+            .unwrap()
+    };
+    let payload = serde_json::json!({ "name": "Alan Turing", "email": "alan@example.com" });
+    let response = context
+        .request(as_acme(
+            http::Method::POST,
+            "/users",
+            Body::from(serde_json::to_vec(&payload).unwrap()),
+        ))
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let acme_user: models::user::User =
+        serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(acme_user.tenant_id, "acme");
+
+    // Cada inquilino solo ve a sus propios usuarios.
+    let bytes = body_bytes(context.get("/users").await).await;
+    let users: Vec<models::user::User> = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(
+        users.into_iter().map(|user| user.id).collect::<Vec<_>>(),
+        vec![default_user.id]
+    );
+    let response = context.get(&format!("/users/{}", acme_user.id)).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    let response = context
+        .request(as_acme(
+            http::Method::DELETE,
+            &format!("/users/{}", default_user.id),
+            Body::empty(),
+        ))
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    // El subdominio resuelve el inquilino igual que la cabecera.
+    let response = context
+        .request(
+            Request::builder()
+                .uri("/users")
+                .header(http::header::HOST, "acme.example.com:3000")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    let users: Vec<models::user::User> =
+        serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(
+        users.into_iter().map(|user| user.id).collect::<Vec<_>>(),
+        vec![acme_user.id]
+    );
+
+    let response = context
+        .request(
+            Request::builder()
+                .uri("/users")
+                .header("x-tenant-id", "globex")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn users_can_be_fetched_with_sparse_fieldsets() {
+    let context = TestContext::new().await;
+    let user = context.create_user("Ada Lovelace", "ada@example.com").await;
+
+    let bytes = body_bytes(context.get("/users?fields=name,id").await).await;
+    let users: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(
+        users,
+        serde_json::json!([{ "id": user.id, "name": "Ada Lovelace" }])
+    );
+
+    let bytes = body_bytes(
+        context
+            .get(&format!("/users/{}?fields=email", user.id))
+            .await,
+    )
+    .await;
+    let fetched: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(fetched, serde_json::json!({ "email": "ada@example.com" }));
+
+    // Sin `fields` la respuesta no cambia.
+    let bytes = body_bytes(context.get(&format!("/users/{}", user.id)).await).await;
+    let fetched: models::user::User = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(fetched.email, "ada@example.com");
+
+    let response = context.get("/users?fields=id,password").await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let response = context.get("/users?fields=").await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+}
+
+#[tokio::test]
+async fn user_collection_reports_total_count_without_a_body() {
+    let context = TestContext::new().await;
+    let ada = context.create_user("Ada Lovelace", "ada@example.com").await;
+    context.create_user("Alan Turing", "alan@example.com").await;
+    context
+        .post_json(
+            &format!("/users/{}/deactivate", ada.id),
+            serde_json::json!({}),
+        )
+        .await;
+
+    let response = context.get("/users").await;
+    assert_eq!(response.headers()["x-total-count"], "1");
+
+    let response = context
+        .request(
+            Request::builder()
+                .method(http::Method::HEAD)
+                .uri("/users?status=all")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["x-total-count"], "2");
+    assert!(body_bytes(response).await.is_empty());
+
+    let response = context.get("/users/count?status=deactivated").await;
+    assert_eq!(response.headers()["x-total-count"], "1");
+    let count: models::user::UserCount =
+        serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(count.count, 1);
+}
+
+#[tokio::test]
+async fn unsupported_method_returns_json_with_allowed_methods() {
+    let context = TestContext::new().await;
+
+    let response = context
+        .request(
+            Request::builder()
+                .method(http::Method::PATCH)
+                .uri("/users")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+    let allow = response.headers()[http::header::ALLOW]
+        .to_str()
+        .unwrap()
+        .to_string();
+    assert!(allow.contains("GET") && allow.contains("POST"));
+
+    let body: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    let allowed_methods = body["allowed_methods"].as_array().unwrap();
+    assert!(allowed_methods.contains(&serde_json::json!("GET")));
+    assert!(allowed_methods.contains(&serde_json::json!("POST")));
+    assert!(body["message"].is_string());
+}
+
+#[tokio::test]
+async fn unknown_route_returns_json_not_found() {
+    let context = TestContext::new().await;
+
+    let response = context
+        .request(
+            Request::builder()
+                .uri("/does-not-exist")
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+    let body: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(body["message"], "Recurso no encontrado");
+}
+
+#[tokio::test]
+async fn near_duplicate_users_are_flagged() {
+    let context = TestContext::new().await;
+    let ada = context
+        .create_user("Ada Lovelace", "ada.lovelace@example.com")
+        .await;
+    let alan = context.create_user("Alan Turing", "alan@example.com").await;
+    context
+        .create_user("Grace Hopper", "grace@example.com")
+        .await;
+
+    let response = context
+        .post_json(
+            "/users?check_duplicates=true",
+            serde_json::json!({ "name": "Ada Lovelace", "email": "adalovelace+shop@example.org" }),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let created: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(created["email"], "adalovelace+shop@example.org");
+    let candidates = created["possible_duplicates"].as_array().unwrap();
+    assert_eq!(candidates.len(), 1);
+    assert_eq!(candidates[0]["user"]["id"], ada.id.to_string());
+    assert_eq!(
+        candidates[0]["reasons"],
+        serde_json::json!(["same_email_local_part", "similar_name"])
+    );
+
+    let response = context
+        .post_json(
+            "/users",
+            serde_json::json!({ "name": "Alan M. Turing", "email": "turing@example.net" }),
+        )
+        .await;
+    let created: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert!(created.get("possible_duplicates").is_none());
+
+    let response = context.get("/users/duplicates").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let pairs: Vec<models::duplicate::DuplicatePair> =
+        serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(pairs.len(), 2);
+    assert_eq!(pairs[0].users[0].id, ada.id);
+    assert_eq!(pairs[0].name_similarity, 1.0);
+    assert_eq!(pairs[1].users[0].id, alan.id);
+    assert_eq!(
+        pairs[1].reasons,
+        [models::duplicate::DuplicateReason::SimilarName]
+    );
+}
+
+#[tokio::test]
+async fn merging_users_moves_related_rows_and_retires_the_source() {
+    let context = TestContext::new().await;
+    let target = context.create_user("Ada Lovelace", "ada@example.com").await;
+    let source = context
+        .create_user("Ada King", "ada.king@example.com")
+        .await;
+    let pool = &context.state.database_pool;
+    let now = chrono::Utc::now();
+    let team_id = uuid::Uuid::new_v4();
+    sqlx::query("INSERT INTO posts (id, author_id, title, body, created_at, updated_at) VALUES (?, ?, 'Notas', 'Texto', ?, ?)")
+        .bind(uuid::Uuid::new_v4())
+        .bind(source.id)
+        .bind(now)
+        .bind(now)
+        .execute(pool)
+        .await
+        .unwrap();
+    sqlx::query(
+        "INSERT INTO teams (id, name, created_at, updated_at) VALUES (?, 'Analítica', ?, ?)",
+    )
+    .bind(team_id)
+    .bind(now)
+    .bind(now)
+    .execute(pool)
+    .await
+    .unwrap();
+    for (user_id, role) in [(target.id, "member"), (source.id, "owner")] {
+        sqlx::query(
+            "INSERT INTO team_members (team_id, user_id, role, created_at) VALUES (?, ?, ?, ?)",
+        )
+        .bind(team_id)
+        .bind(user_id)
+        .bind(role)
+        .bind(now)
+        .execute(pool)
+        .await
+        .unwrap();
+    }
+
+    let response = context
+        .post_json(
+            &format!("/users/{}/merge", target.id),
+            serde_json::json!({ "source_id": target.id }),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+    let response = context
+        .post_json(
+            &format!("/users/{}/merge", target.id),
+            serde_json::json!({ "source_id": source.id }),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let merged: models::user::User = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(merged.email, "ada@example.com");
+
+    let (posts,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM posts WHERE author_id = ?")
+        .bind(target.id)
+        .fetch_one(pool)
+        .await
+        .unwrap();
+    assert_eq!(posts, 1);
+    let roles: Vec<(uuid::Uuid, String)> =
+        sqlx::query_as("SELECT user_id, role FROM team_members WHERE team_id = ?")
+            .bind(team_id)
+            .fetch_all(pool)
+            .await
+            .unwrap();
+    assert_eq!(roles, [(target.id, "owner".to_string())]);
+
+    let response = context.get(&format!("/users/{}", source.id)).await;
+    let source: models::user::User = serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(source.status, models::user::UserStatus::Merged);
+    let (merged_into,): (uuid::Uuid,) =
+        sqlx::query_as("SELECT merged_into FROM users WHERE id = ?")
+            .bind(source.id)
+            .fetch_one(pool)
+            .await
+            .unwrap();
+    assert_eq!(merged_into, target.id);
+    let (events,): (i64,) = sqlx::query_as(
+        "SELECT COUNT(*) FROM outbox WHERE json_extract(payload, '$.type') = 'user_merged' \
+         AND json_extract(payload, '$.merged_into') = ?",
+    )
+    .bind(target.id.to_string())
+    .fetch_one(pool)
+    .await
+    .unwrap();
+    assert_eq!(events, 1);
+
+    // El origen fusionado es terminal y no vuelve a aparecer en los listados.
+    for uri in ["activate", "deactivate"] {
+        let response = context
+            .post_json(
+                &format!("/users/{}/{uri}", source.id),
+                serde_json::json!({}),
+            )
+            .await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{uri}");
+    }
+    let response = context
+        .post_json(
+            &format!("/users/{}/suspend", source.id),
+            serde_json::json!({ "reason": "Spam", "until": now + chrono::Duration::days(1) }),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = context
+        .put_json(
+            &format!("/users/{}", source.id),
+            serde_json::json!({ "name": "Ada Byron" }),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = context
+        .post_json(
+            &format!("/users/{}/merge", source.id),
+            serde_json::json!({ "source_id": target.id }),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let response = context.get("/users?status=all").await;
+    let listed: Vec<models::user::User> =
+        serde_json::from_slice(&body_bytes(response).await).unwrap();
+    assert_eq!(
+        listed.iter().map(|user| user.id).collect::<Vec<_>>(),
+        [target.id]
+    );
+
+    let response = context
+        .post_json(
+            &format!("/users/{}/merge", target.id),
+            serde_json::json!({ "source_id": uuid::Uuid::new_v4() }),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+const PNG_AVATAR: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+const JPEG_AVATAR: &[u8] = &[0xFF, 0xD8, 0xFF, 0xE0, 0x00, 0x10, b'J', b'F', b'I', b'F'];
+
+async fn with_memory_cache() -> TestContext {
+    let state = AppState::new(test_pool().await, AppConfig::default());
+    let config = CacheConfig::default();
+    let cache = Cache::new(
+        MemoryStore::new(config.max_entries),
+        config,
+        state.metrics.clone(),
+    );
+    state.events.register(CacheInvalidator::new(cache.clone()));
+
+    TestContext::from_state(state.with_cache(cache))
+}
+
+async fn put_avatar(
+    context: &TestContext,
+    uri: &str,
+    content_type: &str,
+    contents: &[u8],
+) -> http::Response<Body> {
+    let boundary = "avatar-boundary";
+    let mut body = format!(
+        "--{boundary}\r\nContent-Disposition: form-data; name=\"avatar\"; \
+         filename=\"avatar\"\r\nContent-Type: {content_type}\r\n\r\n"
+    )
+    .into_bytes();
+    body.extend_from_slice(contents);
+    body.extend_from_slice(format!("\r\n--{boundary}--\r\n").as_bytes());
+
+    context
+        .request(
+            Request::builder()
+                .method(http::Method::PUT)
+                .uri(uri)
+                .header(
+                    http::header::CONTENT_TYPE,
+                    format!("multipart/form-data; boundary={boundary}"),
+                )
+                .body(Body::from(body))
+                .unwrap(),
+        )
+        .await
 }
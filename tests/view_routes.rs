@@ -0,0 +1,193 @@
+use axum::{
+    body::Body,
+    http::{self, Request, StatusCode},
+};
+use uuid::Uuid;
+
+use rust_web_demo::{
+    models::{
+        user::{User, UserStatus},
+        view::{SavedView, ViewSort},
+    },
+    testing::{body_json, factory::UserFactory, TestContext},
+};
+
+/// Envía una petición en nombre de `actor`, con `payload` como cuerpo JSON si lo hay.
+async fn send(
+    context: &TestContext,
+    actor: Uuid,
+    method: http::Method,
+    uri: &str,
+    payload: Option<serde_json::Value>,
+) -> http::Response<Body> {
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("x-user-id", actor.to_string())
+        .header(http::header::CONTENT_TYPE, "application/json");
+    let body = payload.map_or_else(Body::empty, |payload| {
+        Body::from(serde_json::to_vec(&payload).unwrap())
+    });
+    context.request(request.body(body).unwrap()).await
+}
+
+async fn create_view(context: &TestContext, actor: Uuid, payload: serde_json::Value) -> SavedView {
+    let response = send(context, actor, http::Method::POST, "/views", Some(payload)).await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    body_json(response).await
+}
+
+async fn results(context: &TestContext, actor: Uuid, view: &SavedView) -> Vec<String> {
+    let uri = format!("/views/{}/results", view.id);
+    let response = send(context, actor, http::Method::GET, &uri, None).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let total = response.headers()["x-total-count"]
+        .to_str()
+        .unwrap()
+        .to_string();
+    let users: Vec<User> = body_json(response).await;
+    assert_eq!(total, users.len().to_string());
+    users.into_iter().map(|user| user.name).collect()
+}
+
+#[tokio::test]
+async fn views_replay_their_query_and_belong_to_their_owner() {
+    let context = TestContext::new().await;
+    let pool = &context.state.database_pool;
+    let ada = UserFactory::new()
+        .with_name("Ada Lovelace")
+        .create(pool)
+        .await;
+    UserFactory::new()
+        .with_name("Ada Byron")
+        .with_status(UserStatus::Suspended)
+        .create(pool)
+        .await;
+    let grace = UserFactory::new()
+        .with_name("Grace Hopper")
+        .create(pool)
+        .await;
+
+    let by_name = create_view(
+        &context,
+        ada.id,
+        serde_json::json!({
+            "name": "  Todas las Ada  ",
+            "status": "all",
+            "filter": r#"name sw "ada""#,
+            "sort": "-name",
+        }),
+    )
+    .await;
+    assert_eq!(by_name.name, "Todas las Ada");
+    assert_eq!(by_name.sort, ViewSort::NameDesc);
+    assert_eq!(
+        results(&context, ada.id, &by_name).await,
+        ["Ada Lovelace", "Ada Byron"]
+    );
+
+    // La vista guarda la consulta, no sus resultados.
+    UserFactory::new().with_name("Ada King").create(pool).await;
+    assert_eq!(
+        results(&context, ada.id, &by_name).await,
+        ["Ada Lovelace", "Ada King", "Ada Byron"]
+    );
+
+    let active = create_view(&context, ada.id, serde_json::json!({ "name": "Activas" })).await;
+    assert_eq!(
+        results(&context, ada.id, &active).await,
+        ["Ada Lovelace", "Grace Hopper", "Ada King"]
+    );
+
+    let response = send(&context, ada.id, http::Method::GET, "/views", None).await;
+    let views: Vec<SavedView> = body_json(response).await;
+    let names = views
+        .iter()
+        .map(|view| view.name.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["Activas", "Todas las Ada"]);
+
+    // Para cualquier otro usuario las vistas de Ada no existen.
+    let response = send(&context, grace.id, http::Method::GET, "/views", None).await;
+    assert!(body_json::<Vec<SavedView>>(response).await.is_empty());
+    let view_uri = format!("/views/{}", by_name.id);
+    for (method, uri) in [
+        (http::Method::GET, format!("{view_uri}/results")),
+        (http::Method::DELETE, view_uri.clone()),
+    ] {
+        let response = send(&context, grace.id, method, &uri, None).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+    let response = context.get(&format!("{view_uri}/results")).await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let response = send(&context, ada.id, http::Method::DELETE, &view_uri, None).await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    let uri = format!("{view_uri}/results");
+    let response = send(&context, ada.id, http::Method::GET, &uri, None).await;
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn invalid_views_are_rejected_and_merges_keep_them() {
+    let context = TestContext::new().await;
+    let pool = &context.state.database_pool;
+    let ada = UserFactory::new().create(pool).await;
+    let duplicate = UserFactory::new().create(pool).await;
+    create_view(&context, ada.id, serde_json::json!({ "name": "Activas" })).await;
+
+    for (payload, field) in [
+        (serde_json::json!({ "name": "Activas" }), "name"),
+        (serde_json::json!({ "name": " " }), "name"),
+        (
+            serde_json::json!({ "name": "Rota", "filter": "name eq" }),
+            "filter",
+        ),
+        (
+            serde_json::json!({ "name": "Rota", "tag": "No válida" }),
+            "tag",
+        ),
+    ] {
+        let response = send(
+            &context,
+            ada.id,
+            http::Method::POST,
+            "/views",
+            Some(payload),
+        )
+        .await;
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body: serde_json::Value = body_json(response).await;
+        assert_eq!(body["errors"][0]["field"], field);
+    }
+
+    // Quien no existe no puede guardar vistas.
+    let payload = serde_json::json!({ "name": "Activas" });
+    let response = send(
+        &context,
+        Uuid::now_v7(),
+        http::Method::POST,
+        "/views",
+        Some(payload),
+    )
+    .await;
+    assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+    let view = create_view(
+        &context,
+        duplicate.id,
+        serde_json::json!({ "name": "Mías" }),
+    )
+    .await;
+    let response = context
+        .post_json(
+            &format!("/users/{}/merge", ada.id),
+            serde_json::json!({ "source_id": duplicate.id }),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = send(&context, ada.id, http::Method::GET, "/views", None).await;
+    let views: Vec<SavedView> = body_json(response).await;
+    assert_eq!(views.len(), 2);
+    assert!(views.iter().any(|merged| merged.id == view.id));
+}
@@ -0,0 +1,455 @@
+use std::{
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use axum::{
+    body::{Body, Bytes},
+    extract::State,
+    http::{self, HeaderMap, Request, StatusCode},
+    middleware,
+    routing::{post, Router},
+};
+use http_body_util::BodyExt;
+use sqlx::sqlite::SqlitePoolOptions;
+use tokio::sync::mpsc;
+
+use rust_web_demo::{
+    config::AppConfig,
+    outbox, routes,
+    state::AppState,
+    tenant,
+    webhooks::{self, SignedDelivery, WebhookSignatureError},
+};
+
+#[tokio::test]
+async fn create_webhook_returns_secret_once() {
+    let context = TestContext::new().await;
+
+    let response = context
+        .post_json(
+            "/webhooks",
+            serde_json::json!({ "url": "https://example.com/hook", "events": ["user.created"] }),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let created = json_body(response).await;
+    assert_eq!(created["secret"].as_str().unwrap().len(), 64);
+    assert_eq!(created["events"], serde_json::json!(["user.created"]));
+
+    let response = context
+        .get(&format!("/webhooks/{}", created["id"].as_str().unwrap()))
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let fetched = json_body(response).await;
+    assert!(fetched.get("secret").is_none());
+}
+
+#[tokio::test]
+async fn create_webhook_with_invalid_payload_returns_validation_error() {
+    let context = TestContext::new().await;
+
+    let response = context
+        .post_json(
+            "/webhooks",
+            serde_json::json!({ "url": "ftp://example.com", "events": ["user.exploded"] }),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    let body = json_body(response).await;
+    assert_eq!(body["errors"].as_array().unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn update_and_delete_webhook() {
+    let context = TestContext::new().await;
+    let created = json_body(
+        context
+            .post_json(
+                "/webhooks",
+                serde_json::json!({ "url": "https://example.com/a" }),
+            )
+            .await,
+    )
+    .await;
+    let uri = format!("/webhooks/{}", created["id"].as_str().unwrap());
+
+    let response = context
+        .put_json(&uri, serde_json::json!({ "active": false }))
+        .await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let updated = json_body(response).await;
+    assert_eq!(updated["active"], false);
+    assert_eq!(updated["url"], "https://example.com/a");
+
+    let response = context
+        .request(
+            Request::builder()
+                .method(http::Method::DELETE)
+                .uri(&uri)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await;
+    assert_eq!(response.status(), StatusCode::NO_CONTENT);
+    assert_eq!(context.get(&uri).await.status(), StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn user_events_are_delivered_signed_with_retries() {
+    let context = TestContext::new().await;
+    let (receiver_address, mut deliveries) = spawn_receiver(1).await;
+
+    let created = json_body(
+        context
+            .post_json(
+                "/webhooks",
+                serde_json::json!({
+                    "url": format!("http://{receiver_address}/hook"),
+                    "events": ["user.created"]
+                }),
+            )
+            .await,
+    )
+    .await;
+    let secret = created["secret"].as_str().unwrap().to_string();
+
+    context
+        .post_json(
+            "/users",
+            serde_json::json!({ "name": "Ada Lovelace", "email": "ada@example.com" }),
+        )
+        .await;
+
+    let mut last_delivery = None;
+    for _ in 0..2 {
+        last_delivery = Some(
+            tokio::time::timeout(Duration::from_secs(5), deliveries.recv())
+                .await
+                .unwrap()
+                .unwrap(),
+        );
+    }
+    let (headers, body) = last_delivery.unwrap();
+
+    assert_eq!(headers[webhooks::EVENT_HEADER], "user.created");
+    let delivery = SignedDelivery::from_headers(&headers, &body).unwrap();
+    delivery.verify(&secret, Duration::from_secs(300)).unwrap();
+    assert_eq!(
+        delivery.verify("otro-secreto", Duration::from_secs(300)),
+        Err(WebhookSignatureError::Invalid)
+    );
+    let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(
+        payload["id"].as_str().unwrap(),
+        delivery.event_id.to_string()
+    );
+    assert_eq!(payload["type"], "user_created");
+    assert_eq!(payload["user"]["email"], "ada@example.com");
+
+    let deliveries_uri = format!("/webhooks/{}/deliveries", created["id"].as_str().unwrap());
+    let mut history = serde_json::Value::Null;
+    for _ in 0..50 {
+        history = json_body(context.get(&deliveries_uri).await).await;
+        if history.as_array().unwrap().len() == 2 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(history[0]["attempt"], 2);
+    assert_eq!(history[0]["success"], true);
+    assert_eq!(history[1]["status_code"], 500);
+    assert_eq!(history[1]["success"], false);
+}
+
+#[test]
+fn stale_or_tampered_deliveries_are_rejected() {
+    let event_id = uuid::Uuid::new_v4();
+    let signed_at = chrono::Utc::now() - chrono::Duration::seconds(600);
+    let signature = webhooks::sign_payload("secreto", signed_at.timestamp(), event_id, b"{}");
+    let delivery = SignedDelivery {
+        signature: &signature,
+        timestamp: signed_at.timestamp(),
+        event_id,
+        body: b"{}",
+    };
+
+    let tolerance = Duration::from_secs(300);
+    assert_eq!(
+        delivery.verify("secreto", tolerance),
+        Err(WebhookSignatureError::Expired)
+    );
+    assert_eq!(delivery.verify_at("secreto", tolerance, signed_at), Ok(()));
+
+    let tampered = SignedDelivery {
+        body: b"{\"admin\":true}",
+        ..delivery
+    };
+    assert_eq!(
+        tampered.verify_at("secreto", tolerance, signed_at),
+        Err(WebhookSignatureError::Invalid)
+    );
+}
+
+#[tokio::test]
+async fn subscribers_only_receive_events_of_their_tenant() {
+    let context = TestContext::new().await;
+    sqlx::query("INSERT INTO tenants (id, name, created_at) VALUES ('acme', 'Acme', ?)")
+        .bind(chrono::Utc::now())
+        .execute(&context.pool)
+        .await
+        .unwrap();
+    let (default_address, mut default_deliveries) = spawn_receiver(0).await;
+    let (acme_address, mut acme_deliveries) = spawn_receiver(0).await;
+
+    let as_acme = |method: http::Method, uri: &str, payload: Option<serde_json::Value>| {
+        let builder = Request::builder()
+            .method(method)
+            .uri(uri)
+            .header(tenant::TENANT_HEADER, "acme");
+        match payload {
+            Some(payload) => builder
+                .header(http::header::CONTENT_TYPE, "application/json")
+                .body(Body::from(serde_json::to_vec(&payload).unwrap()))
+                .unwrap(),
+            None => builder.body(Body::empty()).unwrap(),
+        }
+    };
+    let response = context
+        .request(as_acme(
+            http::Method::POST,
+            "/webhooks",
+            Some(serde_json::json!({ "url": format!("http://{acme_address}/hook") })),
+        ))
+        .await;
+    assert_eq!(response.status(), StatusCode::CREATED);
+    let acme_webhook = json_body(response).await;
+    assert_eq!(acme_webhook["tenant_id"], "acme");
+    let default_webhook = json_body(
+        context
+            .post_json(
+                "/webhooks",
+                serde_json::json!({ "url": format!("http://{default_address}/hook") }),
+            )
+            .await,
+    )
+    .await;
+
+    // Cada inquilino solo ve y gestiona sus propias suscripciones.
+    let listed = json_body(
+        context
+            .request(as_acme(http::Method::GET, "/webhooks", None))
+            .await,
+    )
+    .await;
+    assert_eq!(listed.as_array().unwrap().len(), 1);
+    assert_eq!(listed[0]["id"], acme_webhook["id"]);
+    let default_uri = format!("/webhooks/{}", default_webhook["id"].as_str().unwrap());
+    for method in [http::Method::GET, http::Method::DELETE] {
+        let response = context.request(as_acme(method, &default_uri, None)).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    context
+        .post_json(
+            "/users",
+            serde_json::json!({ "name": "Ada Lovelace", "email": "ada@example.com" }),
+        )
+        .await;
+
+    let (_, body) = tokio::time::timeout(Duration::from_secs(5), default_deliveries.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    let payload: serde_json::Value = serde_json::from_slice(&body).unwrap();
+    assert_eq!(payload["user"]["email"], "ada@example.com");
+    // El suscriptor de `acme` no recibe nada del usuario de `default`.
+    assert!(
+        tokio::time::timeout(Duration::from_millis(200), acme_deliveries.recv())
+            .await
+            .is_err()
+    );
+}
+
+#[tokio::test]
+async fn events_relayed_while_the_dispatcher_was_down_are_still_delivered() {
+    let context = TestContext::without_dispatcher().await;
+    let (receiver_address, mut deliveries) = spawn_receiver(0).await;
+    context
+        .post_json(
+            "/webhooks",
+            serde_json::json!({ "url": format!("http://{receiver_address}/hook") }),
+        )
+        .await;
+    context
+        .post_json(
+            "/users",
+            serde_json::json!({ "name": "Ada Lovelace", "email": "ada@example.com" }),
+        )
+        .await;
+
+    // El relay publica el evento en el bus aunque nadie lo entregue todavía.
+    for _ in 0..50 {
+        let pending: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM outbox WHERE sent_at IS NULL")
+            .fetch_one(&context.pool)
+            .await
+            .unwrap();
+        if pending == 0 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+
+    context.start_dispatcher();
+    let (headers, _) = tokio::time::timeout(Duration::from_secs(5), deliveries.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(headers[webhooks::EVENT_HEADER], "user.created");
+
+    // La posición avanza una vez terminada la entrega.
+    let mut last_seq = None;
+    for _ in 0..50 {
+        last_seq =
+            sqlx::query_scalar::<_, i64>("SELECT last_seq FROM outbox_offsets WHERE consumer = ?")
+                .bind(webhooks::CONSUMER)
+                .fetch_optional(&context.pool)
+                .await
+                .unwrap();
+        if last_seq == Some(1) {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(last_seq, Some(1));
+}
+
+/// Levanta un receptor HTTP local que falla las primeras `failures` peticiones.
+async fn spawn_receiver(
+    failures: usize,
+) -> (SocketAddr, mpsc::UnboundedReceiver<(HeaderMap, Bytes)>) {
+    #[derive(Clone)]
+    struct ReceiverState {
+        sender: mpsc::UnboundedSender<(HeaderMap, Bytes)>,
+        calls: Arc<AtomicUsize>,
+        failures: usize,
+    }
+
+    async fn receive(
+        State(state): State<ReceiverState>,
+        headers: HeaderMap,
+        body: Bytes,
+    ) -> StatusCode {
+        state.sender.send((headers, body)).unwrap();
+        if state.calls.fetch_add(1, Ordering::SeqCst) < state.failures {
+            StatusCode::INTERNAL_SERVER_ERROR
+        } else {
+            StatusCode::OK
+        }
+    }
+
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let app = Router::new()
+        .route("/hook", post(receive))
+        .with_state(ReceiverState {
+            sender,
+            calls: Arc::new(AtomicUsize::new(0)),
+            failures,
+        });
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    (address, receiver)
+}
+
+struct TestContext {
+    app: Router,
+    pool: sqlx::SqlitePool,
+    state: AppState,
+}
+
+impl TestContext {
+    async fn new() -> Self {
+        let context = Self::without_dispatcher().await;
+        context.start_dispatcher();
+        context
+    }
+
+    /// Contexto con el relay del outbox en marcha pero sin despachador de webhooks.
+    async fn without_dispatcher() -> Self {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+
+        sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+
+        let state = AppState::new(pool.clone(), AppConfig::default());
+        tokio::spawn(outbox::run_relay(state.clone()));
+
+        let app = routes::user_routes()
+            .merge(routes::webhook_routes())
+            .route_layer(middleware::from_fn_with_state(
+                state.clone(),
+                tenant::resolve_tenant,
+            ))
+            .with_state(state.clone());
+
+        Self { app, pool, state }
+    }
+
+    fn start_dispatcher(&self) {
+        tokio::spawn(webhooks::run(
+            self.state.clone(),
+            webhooks::WebhookDispatcher::new(self.pool.clone())
+                .with_retry_policy(3, Duration::from_millis(10)),
+        ));
+    }
+
+    async fn request(&self, request: Request<Body>) -> http::Response<Body> {
+        let app = self.app.clone();
+        tower::ServiceExt::oneshot(app, request).await.unwrap()
+    }
+
+    async fn post_json(&self, uri: &str, payload: serde_json::Value) -> http::Response<Body> {
+        self.send_json(http::Method::POST, uri, payload).await
+    }
+
+    async fn put_json(&self, uri: &str, payload: serde_json::Value) -> http::Response<Body> {
+        self.send_json(http::Method::PUT, uri, payload).await
+    }
+
+    async fn send_json(
+        &self,
+        method: http::Method,
+        uri: &str,
+        payload: serde_json::Value,
+    ) -> http::Response<Body> {
+        self.request(
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header(http::header::CONTENT_TYPE, "application/json")
+                .body(Body::from(serde_json::to_vec(&payload).unwrap()))
+                .unwrap(),
+        )
+        .await
+    }
+
+    async fn get(&self, uri: &str) -> http::Response<Body> {
+        self.request(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+    }
+}
+
+async fn json_body(response: http::Response<Body>) -> serde_json::Value {
+    let bytes = response.into_body().collect().await.unwrap().to_bytes();
+    serde_json::from_slice(&bytes).unwrap()
+}